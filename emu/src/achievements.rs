@@ -0,0 +1,574 @@
+//! Achievements written in the trigger format of `RetroAchievements` (the one of `rcheevos`),
+//! checked once per frame against the memory of the game.
+//!
+//! Only the common part of the format is supported: memory sizes from a bit to a word,
+//! deltas and priors, hit counts, `ResetIf`, `PauseIf` and alternative groups.
+
+use crate::gba::Gba;
+
+/// Where the memory seen by achievements is in the GBA address space, as
+/// (start, end, GBA address of start): IWRAM first, EWRAM after, then the save RAM.
+const MEMORY_MAP: [(u32, u32, u32); 3] = [
+    (0x0_0000, 0x0_8000, 0x0300_0000),
+    (0x0_8000, 0x4_8000, 0x0200_0000),
+    (0x4_8000, 0x5_8000, 0x0E00_0000),
+];
+
+/// The GBA address of an address of achievements, `None` if it's not mapped.
+#[must_use]
+pub fn gba_address(address: u32) -> Option<u32> {
+    MEMORY_MAP
+        .iter()
+        .find(|(start, end, _)| (*start..*end).contains(&address))
+        .map(|(start, _, base)| base + address - start)
+}
+
+/// Reads a byte at an address of achievements, unmapped ones read as 0.
+#[must_use]
+pub fn peek(gba: &Gba, address: u32) -> u8 {
+    gba_address(address).map_or(0, |address| gba.peek(address))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Bit(u8),
+    LowNibble,
+    HighNibble,
+    Byte,
+    HalfWord,
+    TriByte,
+    Word,
+}
+
+impl Size {
+    /// The letter after `0x`, eg. `H` in `0xH1234`. A hex digit means a half word.
+    const fn from_prefix(prefix: u8) -> Option<Self> {
+        match prefix.to_ascii_uppercase() {
+            bit @ b'M'..=b'T' => Some(Self::Bit(bit - b'M')),
+            b'L' => Some(Self::LowNibble),
+            b'U' => Some(Self::HighNibble),
+            b'H' => Some(Self::Byte),
+            b' ' => Some(Self::HalfWord),
+            b'W' => Some(Self::TriByte),
+            b'X' => Some(Self::Word),
+            _ => None,
+        }
+    }
+
+    fn read(self, address: u32, peek: &dyn Fn(u32) -> u8) -> u32 {
+        let bytes = |count: u32| {
+            (0..count).fold(0, |value, offset| {
+                value | (u32::from(peek(address.wrapping_add(offset))) << (offset * 8))
+            })
+        };
+
+        match self {
+            Self::Bit(bit) => u32::from(peek(address) >> bit) & 1,
+            Self::LowNibble => u32::from(peek(address)) & 0xF,
+            Self::HighNibble => u32::from(peek(address)) >> 4,
+            Self::Byte => bytes(1),
+            Self::HalfWord => bytes(2),
+            Self::TriByte => bytes(3),
+            Self::Word => bytes(4),
+        }
+    }
+}
+
+/// Which value of a memory operand is compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum History {
+    /// The value in this frame.
+    Current,
+    /// The value in the previous frame.
+    Delta,
+    /// The value before the last change.
+    Prior,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Constant(u32),
+    Memory {
+        size: Size,
+        address: u32,
+        history: History,
+        current: u32,
+        previous: u32,
+        prior: u32,
+    },
+}
+
+impl Operand {
+    fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid operand \"{text}\"");
+
+        let (history, memory) = match text.as_bytes().first() {
+            Some(b'd' | b'D') => (History::Delta, &text[1..]),
+            Some(b'p' | b'P') => (History::Prior, &text[1..]),
+            _ => (History::Current, text),
+        };
+
+        let Some(memory) = memory
+            .strip_prefix("0x")
+            .or_else(|| memory.strip_prefix("0X"))
+        else {
+            if history != History::Current {
+                return Err(invalid());
+            }
+
+            return text
+                .strip_prefix(['h', 'H'])
+                .map_or_else(|| text.parse(), |hex| u32::from_str_radix(hex, 16))
+                .map(Self::Constant)
+                .map_err(|_| invalid());
+        };
+
+        let (size, address) = memory
+            .bytes()
+            .next()
+            .and_then(Size::from_prefix)
+            .map_or((Size::HalfWord, memory), |size| (size, &memory[1..]));
+        let address = u32::from_str_radix(address, 16).map_err(|_| invalid())?;
+
+        Ok(Self::Memory {
+            size,
+            address,
+            history,
+            current: 0,
+            previous: 0,
+            prior: 0,
+        })
+    }
+
+    /// Reads the memory of this frame, it must be called once per frame.
+    fn update(&mut self, peek: &dyn Fn(u32) -> u8) {
+        if let Self::Memory {
+            size,
+            address,
+            current,
+            previous,
+            prior,
+            ..
+        } = self
+        {
+            let value = size.read(*address, peek);
+            if value != *current {
+                *prior = *current;
+            }
+            *previous = *current;
+            *current = value;
+        }
+    }
+
+    const fn value(&self) -> u32 {
+        match *self {
+            Self::Constant(value) => value,
+            Self::Memory {
+                history,
+                current,
+                previous,
+                prior,
+                ..
+            } => match history {
+                History::Current => current,
+                History::Delta => previous,
+                History::Prior => prior,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Longer operators first, `<=` is not `<` followed by `=`.
+    const OPERATORS: [(&'static str, Self); 6] = [
+        ("!=", Self::NotEqual),
+        ("<=", Self::LessOrEqual),
+        (">=", Self::GreaterOrEqual),
+        ("=", Self::Equal),
+        ("<", Self::Less),
+        (">", Self::Greater),
+    ];
+
+    const fn compare(self, left: u32, right: u32) -> bool {
+        match self {
+            Self::Equal => left == right,
+            Self::NotEqual => left != right,
+            Self::Less => left < right,
+            Self::LessOrEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    None,
+    /// When true the hits of the whole achievement go back to 0.
+    ResetIf,
+    /// When true the group is false and its hits are kept.
+    PauseIf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    flag: Flag,
+    left: Operand,
+    comparison: Comparison,
+    right: Operand,
+    /// Frames the condition must be true for, 0 if once is enough.
+    required_hits: u32,
+    hits: u32,
+}
+
+impl Condition {
+    /// eg. `R:0xH1234!=d0xH1234.10.`
+    fn parse(text: &str) -> Result<Self, String> {
+        let (flag, text) = match text.split_once(':') {
+            Some(("R", text)) => (Flag::ResetIf, text),
+            Some(("P", text)) => (Flag::PauseIf, text),
+            Some(_) => return Err(format!("Unsupported condition \"{text}\"")),
+            None => (Flag::None, text),
+        };
+
+        let (index, operator, comparison) = Comparison::OPERATORS
+            .iter()
+            .filter_map(|(operator, comparison)| {
+                text.find(operator)
+                    .map(|index| (index, *operator, *comparison))
+            })
+            .min_by_key(|(index, operator, _)| (*index, std::cmp::Reverse(operator.len())))
+            .ok_or_else(|| format!("No comparison in \"{text}\""))?;
+
+        let left = &text[..index];
+        let right = &text[index + operator.len()..];
+        let (right, required_hits) = match right.strip_suffix('.').and_then(|r| r.split_once('.')) {
+            Some((operand, hits)) => (
+                operand,
+                hits.parse()
+                    .map_err(|_| format!("Invalid hit count \"{hits}\""))?,
+            ),
+            None => (right, 0),
+        };
+
+        Ok(Self {
+            flag,
+            left: Operand::parse(left)?,
+            comparison,
+            right: Operand::parse(right)?,
+            required_hits,
+            hits: 0,
+        })
+    }
+
+    const fn is_true(&self) -> bool {
+        self.comparison
+            .compare(self.left.value(), self.right.value())
+    }
+}
+
+/// A group of conditions that are true together.
+type Group = Vec<Condition>;
+
+/// When an achievement is earned: the core group and, if there are any, one of the
+/// alternative groups must be true in the same frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    core: Group,
+    alternatives: Vec<Group>,
+}
+
+impl Trigger {
+    /// Parses a trigger, eg. `0xH1234=5_d0xH1234=4S0xH10=1S0xH11=1`: groups are
+    /// separated by `S`, conditions by `_`.
+    ///
+    /// # Errors
+    /// It returns an error if a condition is not valid or not supported.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut groups = text.split('S').map(|group| {
+            group
+                .split('_')
+                .filter(|condition| !condition.is_empty())
+                .map(Condition::parse)
+                .collect::<Result<Group, String>>()
+        });
+
+        Ok(Self {
+            core: groups.next().transpose()?.unwrap_or_default(),
+            alternatives: groups.collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn conditions_mut(&mut self) -> impl Iterator<Item = &mut Condition> {
+        self.core
+            .iter_mut()
+            .chain(self.alternatives.iter_mut().flatten())
+    }
+
+    /// Checks the trigger in a new frame, `peek` reads the memory of achievements.
+    pub fn test(&mut self, peek: &dyn Fn(u32) -> u8) -> bool {
+        for condition in self.conditions_mut() {
+            condition.left.update(peek);
+            condition.right.update(peek);
+        }
+
+        let (core, mut reset) = test_group(&mut self.core);
+        let mut alternative = self.alternatives.is_empty();
+        for group in &mut self.alternatives {
+            let (is_true, group_reset) = test_group(group);
+            alternative |= is_true;
+            reset |= group_reset;
+        }
+
+        if reset {
+            for condition in self.conditions_mut() {
+                condition.hits = 0;
+            }
+            return false;
+        }
+
+        core && alternative
+    }
+}
+
+/// Whether the group is true and whether one of its `ResetIf` is.
+fn test_group(group: &mut Group) -> (bool, bool) {
+    let is_paused = group
+        .iter()
+        .any(|condition| condition.flag == Flag::PauseIf && condition.is_true());
+    if is_paused {
+        return (false, false);
+    }
+
+    let mut is_true = true;
+    let mut reset = false;
+
+    for condition in group {
+        let is_condition_true = condition.is_true();
+
+        match condition.flag {
+            Flag::PauseIf => {}
+            Flag::ResetIf => reset |= is_condition_true,
+            Flag::None if condition.required_hits == 0 => is_true &= is_condition_true,
+            Flag::None => {
+                if is_condition_true && condition.hits < condition.required_hits {
+                    condition.hits += 1;
+                }
+                is_true &= condition.hits >= condition.required_hits;
+            }
+        }
+    }
+
+    (is_true, reset)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The trigger must be false once before it can unlock the achievement, so
+    /// achievements true when the game starts (eg. from a loaded state) are not given.
+    Waiting,
+    Active,
+    Unlocked,
+}
+
+/// An achievement of the game being played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Achievement {
+    pub id: u32,
+    pub title: String,
+    pub description: String,
+    pub points: u32,
+    trigger: Trigger,
+    state: State,
+}
+
+impl Achievement {
+    /// # Errors
+    /// It returns an error if the trigger is not valid or not supported.
+    pub fn new(
+        id: u32,
+        title: String,
+        description: String,
+        points: u32,
+        trigger: &str,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            id,
+            title,
+            description,
+            points,
+            trigger: Trigger::parse(trigger)?,
+            state: State::Waiting,
+        })
+    }
+
+    #[must_use]
+    pub fn is_unlocked(&self) -> bool {
+        self.state == State::Unlocked
+    }
+
+    /// Marks an achievement earned in a previous session, it's not checked anymore.
+    pub const fn unlock(&mut self) {
+        self.state = State::Unlocked;
+    }
+
+    /// Checks the achievement in a new frame, it returns `true` when it's unlocked.
+    pub fn do_frame(&mut self, peek: &dyn Fn(u32) -> u8) -> bool {
+        match self.state {
+            State::Unlocked => false,
+            State::Waiting => {
+                if !self.trigger.test(peek) {
+                    self.state = State::Active;
+                }
+                false
+            }
+            State::Active => {
+                if self.trigger.test(peek) {
+                    self.state = State::Unlocked;
+                }
+                self.is_unlocked()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+
+    /// Memory of achievements made of `bytes` from address 0.
+    fn memory(bytes: &RefCell<Vec<u8>>) -> impl Fn(u32) -> u8 + '_ {
+        |address| bytes.borrow().get(address as usize).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn map_addresses() {
+        assert_eq!(gba_address(0x10), Some(0x0300_0010));
+        assert_eq!(gba_address(0x8010), Some(0x0200_0010));
+        assert_eq!(gba_address(0x4_8010), Some(0x0E00_0010));
+        assert_eq!(gba_address(0x5_8000), None);
+    }
+
+    #[test]
+    fn parse_operands() {
+        let memory = |size, address| Operand::Memory {
+            size,
+            address,
+            history: History::Current,
+            current: 0,
+            previous: 0,
+            prior: 0,
+        };
+
+        assert_eq!(Operand::parse("0xH1234"), Ok(memory(Size::Byte, 0x1234)));
+        assert_eq!(Operand::parse("0x1234"), Ok(memory(Size::HalfWord, 0x1234)));
+        assert_eq!(
+            Operand::parse("0x 1234"),
+            Ok(memory(Size::HalfWord, 0x1234))
+        );
+        assert_eq!(Operand::parse("0xX10"), Ok(memory(Size::Word, 0x10)));
+        assert_eq!(Operand::parse("0xO10"), Ok(memory(Size::Bit(2), 0x10)));
+        assert_eq!(Operand::parse("12"), Ok(Operand::Constant(12)));
+        assert_eq!(Operand::parse("h1F"), Ok(Operand::Constant(0x1F)));
+        assert!(Operand::parse("d12").is_err());
+        assert!(Operand::parse("0xZ12").is_err());
+    }
+
+    #[test]
+    fn read_sizes() {
+        let bytes = RefCell::new(vec![0x12, 0x34, 0x56, 0x78]);
+        let peek = memory(&bytes);
+
+        assert_eq!(Size::Byte.read(1, &peek), 0x34);
+        assert_eq!(Size::HalfWord.read(0, &peek), 0x3412);
+        assert_eq!(Size::TriByte.read(0, &peek), 0x56_3412);
+        assert_eq!(Size::Word.read(0, &peek), 0x7856_3412);
+        assert_eq!(Size::LowNibble.read(0, &peek), 0x2);
+        assert_eq!(Size::HighNibble.read(0, &peek), 0x1);
+        assert_eq!(Size::Bit(1).read(0, &peek), 1);
+        assert_eq!(Size::Bit(0).read(0, &peek), 0);
+    }
+
+    #[test]
+    fn hits_and_deltas() {
+        let bytes = RefCell::new(vec![0; 4]);
+        let peek = memory(&bytes);
+        // Byte 0 went from 1 to 2, three times.
+        let mut trigger = Trigger::parse("0xH0=2_d0xH0=1.3.").unwrap();
+
+        for frame in 0..3 {
+            bytes.borrow_mut()[0] = 1;
+            assert!(!trigger.test(&peek));
+            bytes.borrow_mut()[0] = 2;
+            assert_eq!(trigger.test(&peek), frame == 2);
+        }
+    }
+
+    #[test]
+    fn reset_and_pause() {
+        let bytes = RefCell::new(vec![0; 4]);
+        let peek = memory(&bytes);
+        let mut trigger = Trigger::parse("0xH0=1.2._R:0xH1=1_P:0xH2=1").unwrap();
+
+        bytes.borrow_mut()[0] = 1;
+        assert!(!trigger.test(&peek));
+
+        // Paused, the hit is kept.
+        bytes.borrow_mut()[2] = 1;
+        assert!(!trigger.test(&peek));
+        bytes.borrow_mut()[2] = 0;
+        assert!(trigger.test(&peek));
+
+        bytes.borrow_mut()[1] = 1;
+        assert!(!trigger.test(&peek));
+        bytes.borrow_mut()[1] = 0;
+        assert!(!trigger.test(&peek));
+        assert!(trigger.test(&peek));
+    }
+
+    #[test]
+    fn alternative_groups() {
+        let bytes = RefCell::new(vec![0; 4]);
+        let peek = memory(&bytes);
+        let mut trigger = Trigger::parse("0xH0=1S0xH1=1S0xH2=1").unwrap();
+
+        bytes.borrow_mut()[0] = 1;
+        assert!(!trigger.test(&peek));
+        bytes.borrow_mut()[2] = 1;
+        assert!(trigger.test(&peek));
+        bytes.borrow_mut()[0] = 0;
+        assert!(!trigger.test(&peek));
+    }
+
+    #[test]
+    fn achievements_wait_for_a_false_trigger() {
+        let bytes = RefCell::new(vec![1; 4]);
+        let peek = memory(&bytes);
+        let mut achievement =
+            Achievement::new(1, "Title".into(), String::new(), 5, "0xH0=1").unwrap();
+
+        assert!(!achievement.do_frame(&peek));
+        assert!(!achievement.do_frame(&peek));
+
+        bytes.borrow_mut()[0] = 0;
+        assert!(!achievement.do_frame(&peek));
+        bytes.borrow_mut()[0] = 1;
+        assert!(achievement.do_frame(&peek));
+        assert!(achievement.is_unlocked());
+        assert!(!achievement.do_frame(&peek));
+
+        assert!(Achievement::new(2, String::new(), String::new(), 0, "A:0xH0=1").is_err());
+    }
+}
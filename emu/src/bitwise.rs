@@ -52,23 +52,83 @@ where
     }
 
     fn get_bits(&self, bits_range: RangeInclusive<u8>) -> Self {
-        let start = bits_range.start();
-        let length = bits_range.len() as u32;
+        let (start, end) = (*bits_range.start(), *bits_range.end());
+        debug_assert!(start <= end && end < (size_of::<Self>() * 8) as u8);
 
-        // Gets a value with `length` number of ones.
-        // If bits_range is 1..=10 then length is 10 and we want
-        // 10 ones.
-        let mut mask = (2_u128.pow(length)) - 1;
-
-        // Moves the mask to the correct place.
-        // If `bits_range` is 1..=10 then we should move the mask
-        // 1 bit to the left in order to get from the first bit on.
-        mask <<= start;
+        // Gets a value with `end - start + 1` number of ones.
+        // If bits_range is 1..=10 then we want 10 ones, the shift is done
+        // from `u128::MAX` so a range as wide as the type doesn't overflow.
+        let mask = u128::MAX >> (127 - (end - start));
 
         let value: u128 = <Self as Into<u128>>::into(self.clone());
 
-        // We apply the mask and then move the value back to the 0 position.
-        <Self as TryFrom<u128>>::try_from((value & mask) >> start).unwrap()
+        // We move the value to the 0 position and then apply the mask,
+        // the result can't be wider than `Self` so the conversion never fails.
+        <Self as TryFrom<u128>>::try_from((value >> start) & mask).unwrap()
+    }
+
+    /// Same as [`Self::get_bits`] but the range is checked at compile time,
+    /// eg. `op_code.get_bits_const::<28, 31>()`.
+    fn get_bits_const<const START: u8, const END: u8>(&self) -> Self {
+        const {
+            assert!(START <= END && (END as usize) < size_of::<Self>() * 8);
+        }
+
+        self.get_bits(START..=END)
+    }
+
+    /// Gets a field at most 8 bits wide, eg. a condition or a color channel.
+    fn get_bits_u8(&self, bits_range: RangeInclusive<u8>) -> u8 {
+        debug_assert!(bits_range.len() <= 8);
+
+        let value: u128 = <Self as Into<u128>>::into(self.get_bits(bits_range));
+        value as u8
+    }
+
+    /// Gets a field at most 16 bits wide, eg. a register list.
+    fn get_bits_u16(&self, bits_range: RangeInclusive<u8>) -> u16 {
+        debug_assert!(bits_range.len() <= 16);
+
+        let value: u128 = <Self as Into<u128>>::into(self.get_bits(bits_range));
+        value as u16
+    }
+
+    /// Replaces the bits in `bits_range` with the lowest bits of `value`.
+    fn set_bits(&mut self, bits_range: RangeInclusive<u8>, value: Self) {
+        let (start, end) = (*bits_range.start(), *bits_range.end());
+        debug_assert!(start <= end && end < (size_of::<Self>() * 8) as u8);
+
+        // Gets a value with the right amount of ones (given by bits_range.len()),
+        // order goes from lsb to msb (right to left).
+        let mask = u128::MAX >> (127 - (end - start));
+        let value_bits: u128 = (<Self as Into<u128>>::into(value) & mask) << start;
+        let self_bits: u128 = <Self as Into<u128>>::into(self.clone());
+
+        // Now, shift the mask then flip it so we can choose where to insert
+        // our value bits.
+        let reverse_mask = !(mask << start);
+
+        // Say we have self being a u16 value with the following bits:
+        //     0b0000....10010011_01110011_u128
+        // and we want to set bits 7..=10, 4 bits starting from 7 to 0b1001.
+        //
+        // The constant `reverse_mask` will look something like:
+        //     0b1111....11111000_01111111_u128
+        // which is helpful for clearing the bits we are about to set.
+        //
+        //     0b0000....10010011_01110011_u128 &
+        //     0b1111....11111000_01111111_u128
+        //     --------------------------------
+        //     0b0000....10010000_01110011_u128
+        //
+        // The value above can be used in bit-or with our value bits
+        // to obtain the expected result:
+        //
+        //     0b0000....10010000_01110011_u128 |
+        //     0b0000....00000100_10000000_u128 =
+        //     --------------------------------
+        //     0b0000....10010100_11110011_u128
+        *self = <Self as TryFrom<u128>>::try_from((self_bits & reverse_mask) | value_bits).unwrap();
     }
 
     /// Checks if a certain sequence of bit is set to 1.
@@ -102,7 +162,7 @@ where
         let mask: u128 = !(0xFF << (8 * byte_nth));
 
         // We shift the new octet in place.
-        let shifted_value: u128 = (value as u128) << (8 * byte_nth);
+        let shifted_value: u128 = u128::from(value) << (8 * byte_nth);
 
         // We set the byte_nth octet to 0 using the mask and we
         // do the OR with the new octet.
@@ -114,6 +174,8 @@ where
     /// `bits_amount` is the numbers of bits of the value we want
     /// to sign-extend.
     fn sign_extended(&self, number_of_bits: u8) -> Self {
+        debug_assert!((1..=(size_of::<Self>() * 8) as u8).contains(&number_of_bits));
+
         let value: u128 = <Self as Into<u128>>::into(self.clone());
 
         // We generate a mask having a 1 in the last most significant bit of the value
@@ -144,6 +206,11 @@ where
 
         <Self as TryFrom<u128>>::try_from(value).unwrap()
     }
+
+    /// Sign-extends the value in place, see [`Self::sign_extended`].
+    fn sign_extend(&mut self, number_of_bits: u8) {
+        *self = self.sign_extended(number_of_bits);
+    }
 }
 
 impl Bits for u128 {}
@@ -153,6 +220,7 @@ impl Bits for u16 {}
 impl Bits for u8 {}
 
 #[cfg(test)]
+#[allow(clippy::should_panic_without_expect, clippy::unreadable_literal)]
 mod tests {
     use super::*;
 
@@ -205,7 +273,7 @@ mod tests {
         b.set_bit(1, true);
         b.set_bit(2, false);
         b.set_bit(3, false);
-        assert_eq!(b, 0b1100011)
+        assert_eq!(b, 0b1100011);
     }
 
     #[test]
@@ -236,6 +304,58 @@ mod tests {
         assert_eq!(b.get_bits(28..=31), 0b0);
     }
 
+    #[test]
+    fn get_bits_whole_value() {
+        assert_eq!(u128::MAX.get_bits(0..=127), u128::MAX);
+        assert_eq!(0xF000_0000_u32.get_bits_const::<28, 31>(), 0xF);
+        assert_eq!(0xABCD_u16.get_bits_const::<0, 15>(), 0xABCD);
+    }
+
+    #[test]
+    fn get_bits_narrow() {
+        let b = 0x1234_5678_u32;
+        assert_eq!(b.get_bits_u8(28..=31), 0x1);
+        assert_eq!(b.get_bits_u8(0..=7), 0x78);
+        assert_eq!(b.get_bits_u16(16..=31), 0x1234);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_bits_u8_too_wide() {
+        0u32.get_bits_u8(0..=8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_bits_out_of_range() {
+        0u16.get_bits(8..=16);
+    }
+
+    #[test]
+    fn set_bits() {
+        let mut b = 0b10001001_u32;
+        b.set_bits(4..=5, 0b11);
+        assert_eq!(b, 0b10111001_u32);
+        b.set_bits(1..=2, 0b11);
+        assert_eq!(b, 0b10111111_u32);
+
+        let mut b = 0b00000000_00000000_u32;
+        b.set_bits(0..=7, 0b11111111_u32);
+        assert_eq!(b, 0b00000000_11111111_u32);
+
+        let mut b = 0xFFFF_0000_u32;
+        b.set_bits(12..=19, 0xA5);
+        assert_eq!(b, 0xFFFA_5000);
+
+        // Bits of `value` outside the range are ignored.
+        b.set_bits(0..=3, 0xFF);
+        assert_eq!(b, 0xFFFA_500F);
+
+        let mut b = 0_u8;
+        b.set_bits(0..=7, 0xFF);
+        assert_eq!(b, 0xFF);
+    }
+
     #[test]
     fn are_bits_on() {
         let b = 0b1011001110_u32;
@@ -298,5 +418,13 @@ mod tests {
         let a: u32 = 0b1001; // -7 in i4
 
         assert_eq!(a.sign_extended(4) as i32, -7);
+
+        let mut b: u16 = 0b0111; // 7 in i4
+        b.sign_extend(4);
+        assert_eq!(b, 7);
+
+        let mut b: u16 = 0x80;
+        b.sign_extend(8);
+        assert_eq!(b, 0xFF80);
     }
 }
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Bytes written by a [`BootPatch`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatchWidth {
+    #[default]
+    Byte,
+    HalfWord,
+    Word,
+}
+
+impl PatchWidth {
+    pub const ALL: [Self; 3] = [Self::Byte, Self::HalfWord, Self::Word];
+
+    #[must_use]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::Byte => 1,
+            Self::HalfWord => 2,
+            Self::Word => 4,
+        }
+    }
+
+    /// The low bytes of `value` that fit the width, little endian.
+    #[must_use]
+    pub fn le_bytes(self, value: u32) -> Vec<u8> {
+        value.to_le_bytes()[..self.bytes()].to_vec()
+    }
+}
+
+/// A value written once in RAM or ROM when the BIOS jumps to the game, eg. the lives of a
+/// trainer or a flag to test a level. Lighter than a cheat file, the game can change the
+/// value afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootPatch {
+    pub address: u32,
+    /// Only the low bytes of the width are written, little endian.
+    pub value: u32,
+    #[serde(default)]
+    pub width: PatchWidth,
+}
+
+impl BootPatch {
+    /// Bytes to write from the address.
+    #[must_use]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.width.le_bytes(self.value)
+    }
+}
+
+/// Patches waiting for the end of the boot, applied again after every reset.
+#[derive(Debug, Default)]
+pub(crate) struct BootPatches {
+    pub(crate) patches: Vec<BootPatch>,
+    /// The BIOS hasn't jumped to the game yet since the start or the last reset.
+    pub(crate) pending: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bytes() {
+        let patch = |width| BootPatch {
+            address: 0x0300_0000,
+            value: 0x1234_5678,
+            width,
+        };
+
+        assert_eq!(patch(PatchWidth::Byte).bytes(), [0x78]);
+        assert_eq!(patch(PatchWidth::HalfWord).bytes(), [0x78, 0x56]);
+        assert_eq!(patch(PatchWidth::Word).bytes(), [0x78, 0x56, 0x34, 0x12]);
+    }
+}
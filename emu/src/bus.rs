@@ -1,32 +1,114 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use logger::log;
 use serde::{Deserialize, Serialize};
 
 use crate::bitwise::Bits;
-use crate::cpu::hardware::dma::{Dma, Registers};
-use crate::cpu::hardware::get_unmasked_address;
+use crate::core_profile::CoreProfile;
+use crate::cpu::hardware::debug::{DebugLevel, DebugOutput, DebugRequest, DebugSource};
+use crate::cpu::hardware::dma::{AddressControl, Dma, Registers, StartTiming};
+use crate::cpu::hardware::gpio::Gpio;
 use crate::cpu::hardware::internal_memory::InternalMemory;
 use crate::cpu::hardware::interrupt_control::InterruptControl;
+use crate::cpu::hardware::joybus::JoybusCommand;
 use crate::cpu::hardware::keypad::Keypad;
 use crate::cpu::hardware::lcd::Lcd;
 use crate::cpu::hardware::serial::Serial;
 use crate::cpu::hardware::sound::Sound;
 use crate::cpu::hardware::timers::Timers;
+use crate::debugger::coverage::Coverage;
+use crate::debugger::timeline::{Timeline, TimelineEvent};
+use crate::events::CoreEvent;
+use crate::memory_map::{Region, BG_PALETTE_SIZE, BIOS_SIZE, SRAM_SIZE};
+
+/// What the CPU sees of the machine: memory accesses, interrupts and time.
+/// The GBA memory map is [`GbaBus`], the CPU can run on anything else implementing it.
+pub trait Bus {
+    fn read8(&mut self, address: usize) -> u8;
+    fn read16(&mut self, address: usize) -> u16;
+    fn read32(&mut self, address: usize) -> u32;
+    fn write8(&mut self, address: usize, value: u8);
+    fn write16(&mut self, address: usize, value: u16);
+    fn write32(&mut self, address: usize, value: u32);
+
+    /// Cycles elapsed so far, memory accesses take time.
+    fn cycles(&self) -> u128;
+
+    /// Whether an enabled interrupt is waiting for the CPU.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    /// Reads an ARM instruction, it's a data read unless the bus tells them apart.
+    fn fetch32(&mut self, address: usize) -> u32 {
+        self.read32(address)
+    }
+
+    /// Reads a Thumb instruction, see [`Self::fetch32`].
+    fn fetch16(&mut self, address: usize) -> u16 {
+        self.read16(address)
+    }
+
+    /// Called before executing the instruction of `size` bytes at `address`.
+    fn instruction_executed(&mut self, _address: usize, _size: usize) {}
+
+    /// Lets `cycles` cycles go by without a memory access, for the work the emulator does
+    /// in one go in place of the CPU.
+    fn internal_cycles(&mut self, _cycles: u32) {}
+
+    /// Lets up to `cycles` cycles go by with the CPU halted, stopping early once an enabled
+    /// interrupt is requested, whatever the master enable says.
+    fn halt(&mut self, cycles: u32) {
+        self.internal_cycles(cycles);
+    }
+
+    /// Called on a SWI with its BIOS function number before the exception is taken,
+    /// it returns `true` if the bus handled the call and the exception must not happen.
+    fn software_interrupt(&mut self, _function: u8) -> bool {
+        false
+    }
+}
 
 #[derive(Default, Serialize, Deserialize)]
-pub struct Bus {
+pub struct GbaBus {
     pub internal_memory: InternalMemory,
     pub lcd: Lcd,
-    sound: Sound,
+    pub(crate) sound: Sound,
     dma: Dma,
     timers: Timers,
     serial: Serial,
     keypad: Keypad,
     interrupt_control: InterruptControl,
-    cycles_count: u128,
+    pub debug_output: DebugOutput,
+    pub(crate) cycles_count: u128,
     last_used_address: usize,
-    unused_region: HashMap<usize, u8>,
+    /// Last instruction fetched, reads of unmapped memory get it.
+    open_bus: u32,
+    unused_region: BTreeMap<usize, u8>,
+    /// Port of the cartridges with an RTC, `None` for the others.
+    pub(crate) gpio: Option<Gpio>,
+
+    /// EWRAM accesses take 1 wait state whatever the internal memory control says, the
+    /// "EWRAM overclock" some games use. It's a setting, it's kept when loading a state.
+    #[serde(skip)]
+    pub fast_ewram: bool,
+
+    /// Timing and open bus are simplified with the fast profile. It's a setting, it's kept
+    /// when loading a state.
+    #[serde(skip)]
+    pub profile: CoreProfile,
+
+    /// Events happened since the last dispatch, see `Gba::on_event`.
+    #[serde(skip)]
+    pub(crate) events: Vec<CoreEvent>,
+
+    /// ROM bytes executed and read, recorded only while it's `Some`.
+    #[serde(skip)]
+    pub coverage: Option<Coverage>,
+
+    /// DMA, timer and interrupt activity, recorded only while it's `Some`.
+    #[serde(skip)]
+    pub timeline: Option<Timeline>,
 }
 
 #[allow(dead_code)]
@@ -47,6 +129,22 @@ enum IrqType {
     Gamepak,
 }
 
+/// SRAM is on an 8 bits bus: wider reads get the byte repeated and wider writes
+/// store only the byte of the address.
+const SRAM: std::ops::RangeInclusive<usize> = 0x0E00_0000..=0x0E00_FFFF;
+
+/// The registers of the GPIO of the cartridge, in place of the ROM when it has one.
+const GPIO: std::ops::RangeInclusive<usize> = 0x0800_00C4..=0x0800_00C9;
+
+/// The offset in IWRAM of `address`, `None` outside of it.
+const fn iwram_offset(address: usize) -> Option<usize> {
+    if matches!(Region::of(address), Region::Iwram) {
+        Some(Region::Iwram.offset(address))
+    } else {
+        None
+    }
+}
+
 impl IrqType {
     /// Returns the index of the corresponding `IrqType` inside the Interrupt Request Flag register
     const fn get_idx_in_if(&self) -> u8 {
@@ -68,7 +166,7 @@ impl IrqType {
         }
     }
 }
-impl Bus {
+impl GbaBus {
     fn read_interrupt_control_raw(&self, address: usize) -> u8 {
         match address {
             0x0400_0200 => self.interrupt_control.interrupt_enable.get_byte(0),
@@ -100,7 +198,7 @@ impl Bus {
                 log("read on unused memory");
                 *self.unused_region.get(&address).unwrap_or(&0)
             }
-            _ => match address & 0b111 {
+            _ => match address & 0xFFFF {
                 0x800 => self.interrupt_control.internal_memory_control.get_byte(0),
                 0x801 => self.interrupt_control.internal_memory_control.get_byte(1),
                 0x802 => self.interrupt_control.internal_memory_control.get_byte(2),
@@ -148,7 +246,7 @@ impl Bus {
                 log("write on unused memory");
                 self.unused_region.insert(address, value);
             }
-            _ => match address & 0b111 {
+            _ => match address & 0xFFFF {
                 0x800 => self
                     .interrupt_control
                     .internal_memory_control
@@ -257,7 +355,14 @@ impl Bus {
             0x04000125 => self.serial.sio_multi_data_2.set_byte(1, value),
             0x04000126 => self.serial.sio_multi_data_3.set_byte(0, value),
             0x04000127 => self.serial.sio_multi_data_3.set_byte(1, value),
-            0x04000128 => self.serial.sio_control_register.set_byte(0, value),
+            0x04000128 => {
+                let was_started = self.serial.sio_control_register.get_bit(7);
+                self.serial.sio_control_register.set_byte(0, value);
+
+                if !was_started && self.serial.sio_control_register.get_bit(7) {
+                    self.events.push(CoreEvent::SerialTransferStarted);
+                }
+            }
             0x04000129 => self.serial.sio_control_register.set_byte(1, value),
             0x0400012A => self.serial.sio_multi_data_send_data_8.set_byte(0, value),
             0x0400012B => self.serial.sio_multi_data_send_data_8.set_byte(1, value),
@@ -265,18 +370,16 @@ impl Bus {
             0x04000135 => self.serial.sio_mode_select.set_byte(1, value),
             0x04000136 => self.serial.infrared_register.set_byte(0, value),
             0x04000137 => self.serial.infrared_register.set_byte(1, value),
-            0x04000140 => self.serial.sio_joy_bus_control.set_byte(0, value),
-            0x04000141 => self.serial.sio_joy_bus_control.set_byte(1, value),
-            0x04000150 => self.serial.sio_joy_bus_receive_data.set_byte(0, value),
-            0x04000151 => self.serial.sio_joy_bus_receive_data.set_byte(1, value),
-            0x04000152 => self.serial.sio_joy_bus_receive_data.set_byte(2, value),
-            0x04000153 => self.serial.sio_joy_bus_receive_data.set_byte(3, value),
-            0x04000154 => self.serial.sio_joy_bus_transmit_data.set_byte(0, value),
-            0x04000155 => self.serial.sio_joy_bus_transmit_data.set_byte(1, value),
-            0x04000156 => self.serial.sio_joy_bus_transmit_data.set_byte(2, value),
-            0x04000157 => self.serial.sio_joy_bus_transmit_data.set_byte(3, value),
-            0x04000158 => self.serial.sio_joy_bus_receive_status.set_byte(0, value),
-            0x04000159 => self.serial.sio_joy_bus_receive_status.set_byte(1, value),
+            0x04000140 => self.serial.write_joy_bus_control(0, value),
+            0x04000141 => self.serial.write_joy_bus_control(1, value),
+            // JOY_RECV is written by the JOY Bus master only
+            0x04000150..=0x04000153 => {}
+            0x04000154 => self.serial.write_joy_bus_transmit(0, value),
+            0x04000155 => self.serial.write_joy_bus_transmit(1, value),
+            0x04000156 => self.serial.write_joy_bus_transmit(2, value),
+            0x04000157 => self.serial.write_joy_bus_transmit(3, value),
+            0x04000158 => self.serial.write_joy_bus_status(0, value),
+            0x04000159 => self.serial.write_joy_bus_status(1, value),
             0x0400012C..=0x0400012F
             | 0x04000138..=0x04000139
             | 0x04000142..=0x0400014F
@@ -313,22 +416,16 @@ impl Bus {
 
     fn write_timers_raw(&mut self, address: usize, value: u8) {
         match address {
-            0x04000100 => self.timers.tm0cnt_l.set_byte(0, value),
-            0x04000101 => self.timers.tm0cnt_l.set_byte(1, value),
-            0x04000102 => self.timers.tm0cnt_h.set_byte(0, value),
-            0x04000103 => self.timers.tm0cnt_h.set_byte(1, value),
-            0x04000104 => self.timers.tm1cnt_l.set_byte(0, value),
-            0x04000105 => self.timers.tm1cnt_l.set_byte(1, value),
-            0x04000106 => self.timers.tm1cnt_h.set_byte(0, value),
-            0x04000107 => self.timers.tm1cnt_h.set_byte(1, value),
-            0x04000108 => self.timers.tm2cnt_l.set_byte(0, value),
-            0x04000109 => self.timers.tm2cnt_l.set_byte(1, value),
-            0x0400010A => self.timers.tm2cnt_h.set_byte(0, value),
-            0x0400010B => self.timers.tm2cnt_h.set_byte(1, value),
-            0x0400010C => self.timers.tm3cnt_l.set_byte(0, value),
-            0x0400010D => self.timers.tm3cnt_l.set_byte(1, value),
-            0x0400010E => self.timers.tm3cnt_h.set_byte(0, value),
-            0x0400010F => self.timers.tm3cnt_h.set_byte(1, value),
+            0x04000100..=0x0400010F => {
+                let index = (address - 0x04000100) / 4;
+                let offset = (address - 0x04000100) % 4;
+
+                if offset < 2 {
+                    self.timers.write_reload(index, offset as u8, value);
+                } else {
+                    self.timers.write_control(index, offset as u8 - 2, value);
+                }
+            }
             0x04000110..=0x0400011F => {
                 log(format!("write on unused memory {address:x}"));
                 self.unused_region.insert(address, value);
@@ -347,9 +444,9 @@ impl Bus {
 
         match address {
             0x040000B0..=0x040000BB => read_dma_bank(&self.dma.channels[0], address - 0x040000B0),
-            0x040000BC..=0x040000C7 => read_dma_bank(&self.dma.channels[0], address - 0x040000BC),
-            0x040000C8..=0x040000D3 => read_dma_bank(&self.dma.channels[0], address - 0x040000C8),
-            0x040000D4..=0x040000DF => read_dma_bank(&self.dma.channels[0], address - 0x040000D4),
+            0x040000BC..=0x040000C7 => read_dma_bank(&self.dma.channels[1], address - 0x040000BC),
+            0x040000C8..=0x040000D3 => read_dma_bank(&self.dma.channels[2], address - 0x040000C8),
+            0x040000D4..=0x040000DF => read_dma_bank(&self.dma.channels[3], address - 0x040000D4),
             0x040000E0..=0x040000FF => {
                 log("read on unused memory");
                 self.unused_region.get(&address).map_or(0, |v| *v)
@@ -375,27 +472,186 @@ impl Bus {
             _ => panic!("DMA channel write-address is out of bound"),
         };
 
-        match address {
-            0x040000B0..=0x040000BB => {
-                write_dma_bank(&mut self.dma.channels[0], address - 0x040000B0, value);
-            }
-            0x040000BC..=0x040000C7 => {
-                write_dma_bank(&mut self.dma.channels[1], address - 0x040000BC, value);
-            }
-            0x040000C8..=0x040000D3 => {
-                write_dma_bank(&mut self.dma.channels[2], address - 0x040000C8, value);
-            }
-            0x040000D4..=0x040000DF => {
-                write_dma_bank(&mut self.dma.channels[3], address - 0x040000D4, value);
-            }
+        let (index, offset) = match address {
+            0x040000B0..=0x040000DF => ((address - 0x040000B0) / 12, (address - 0x040000B0) % 12),
             0x040000E0..=0x040000FF => {
                 log("write on unused memory");
                 self.unused_region.insert(address, value);
+                return;
             }
             _ => panic!("Not implemented write memory address: {address:x}"),
+        };
+
+        let was_enabled = self.dma.channels[index].is_enabled();
+        write_dma_bank(&mut self.dma.channels[index], offset, value);
+
+        let channel = &mut self.dma.channels[index];
+        if !was_enabled && channel.is_enabled() {
+            channel.latch(index);
+
+            if channel.start_timing() == StartTiming::Immediately {
+                self.run_dma(index);
+            }
+        }
+    }
+
+    /// Runs the enabled channels waiting for `timing`, in priority order (DMA0 first).
+    fn trigger_dma(&mut self, timing: StartTiming) {
+        for index in 0..4 {
+            let channel = &self.dma.channels[index];
+            if channel.is_enabled() && channel.start_timing() == timing {
+                self.run_dma(index);
+            }
+        }
+    }
+
+    /// The Direct Sound FIFO `fifo` (0 for A, 1 for B) is half empty, it runs DMA1 and DMA2
+    /// if they are waiting to refill it.
+    fn request_fifo_dma(&mut self, fifo: usize) {
+        let fifo_address = 0x0400_00A0 + 4 * fifo as u32;
+
+        for index in [1, 2] {
+            let channel = &self.dma.channels[index];
+            if channel.is_enabled()
+                && channel.start_timing() == StartTiming::Special
+                && channel.internal_destination & !0b11 == fifo_address
+            {
+                self.run_dma(index);
+            }
+        }
+    }
+
+    /// DMA3 in video capture mode transfers once per line, in the `HBlank` of lines 2-161.
+    fn request_video_capture_dma(&mut self) {
+        let channel = &self.dma.channels[3];
+        if channel.is_enabled() && channel.start_timing() == StartTiming::Special {
+            self.run_dma(3);
+        }
+    }
+
+    /// Video capture is disabled at line 162, even with repeat set.
+    fn end_video_capture_dma(&mut self) {
+        let channel = &mut self.dma.channels[3];
+        if channel.is_enabled() && channel.start_timing() == StartTiming::Special {
+            channel.set_enabled(false);
+        }
+    }
+
+    /// Transfers all the units of the channel at once, the CPU is not stepped meanwhile.
+    fn run_dma(&mut self, index: usize) {
+        let channel = &self.dma.channels[index];
+
+        // Sound FIFO transfers are always 4 words to a fixed address, the count is ignored.
+        let is_fifo = channel.start_timing() == StartTiming::Special && matches!(index, 1 | 2);
+
+        let is_word = is_fifo || channel.is_word_transfer();
+        let unit_size: u32 = if is_word { 4 } else { 2 };
+        let count = if is_fifo { 4 } else { channel.internal_count };
+        let source_control = channel.source_control();
+        let destination_control = if is_fifo {
+            AddressControl::Fixed
+        } else {
+            channel.destination_control()
+        };
+        let mut source = channel.internal_source;
+        let mut destination = channel.internal_destination;
+
+        self.record_timeline(TimelineEvent::Dma {
+            channel: index as u8,
+            units: count,
+        });
+
+        for _ in 0..count {
+            let source_address = (source & !(unit_size - 1)) as usize;
+            let destination_address = (destination & !(unit_size - 1)) as usize;
+
+            // BIOS, unused regions and SRAM can't be read by DMA: the value of the
+            // last transfer is used instead.
+            let is_source_valid = (0x0200_0000..0x0E00_0000).contains(&source_address);
+
+            if is_word {
+                if is_source_valid {
+                    self.dma.latch = self.read_word_raw(source_address);
+                }
+                self.write_word_raw(destination_address, self.dma.latch);
+            } else {
+                if is_source_valid {
+                    let value: u32 = self.read_half_word_raw(source_address).into();
+                    self.dma.latch = value | (value << 16);
+                }
+                let value = self.dma.latch >> (8 * (destination_address & 2));
+                self.write_half_word_raw(destination_address, value as u16);
+            }
+
+            if let (true, Some(coverage)) = (is_source_valid, &mut self.coverage) {
+                coverage.mark_read(source_address, unit_size as usize);
+            }
+
+            // Game Pak ROM is always read incrementing the address.
+            let is_source_rom = (0x0800_0000..0x0E00_0000).contains(&source_address);
+            source = match source_control {
+                _ if is_source_rom => source.wrapping_add(unit_size),
+                AddressControl::Increment | AddressControl::IncrementReload => {
+                    source.wrapping_add(unit_size)
+                }
+                AddressControl::Decrement => source.wrapping_sub(unit_size),
+                AddressControl::Fixed => source,
+            };
+
+            destination = match destination_control {
+                AddressControl::Increment | AddressControl::IncrementReload => {
+                    destination.wrapping_add(unit_size)
+                }
+                AddressControl::Decrement => destination.wrapping_sub(unit_size),
+                AddressControl::Fixed => destination,
+            };
+        }
+
+        let channel = &mut self.dma.channels[index];
+        channel.internal_source = source;
+        channel.internal_destination = destination;
+
+        if channel.is_repeat() && channel.start_timing() != StartTiming::Immediately {
+            channel.reload_count(index);
+
+            if destination_control == AddressControl::IncrementReload {
+                channel.reload_destination(index);
+            }
+        } else {
+            channel.set_enabled(false);
+        }
+
+        if channel.is_irq_enabled() {
+            let irq_type = match index {
+                0 => IrqType::Dma0,
+                1 => IrqType::Dma1,
+                2 => IrqType::Dma2,
+                _ => IrqType::Dma3,
+            };
+
+            self.request_interrupt(&irq_type);
         }
     }
 
+    pub(crate) fn read_half_word_raw(&self, address: usize) -> u16 {
+        u16::from(self.read_raw(address)) | (u16::from(self.read_raw(address + 1)) << 8)
+    }
+
+    pub(crate) fn read_word_raw(&self, address: usize) -> u32 {
+        u32::from(self.read_half_word_raw(address))
+            | (u32::from(self.read_half_word_raw(address + 2)) << 16)
+    }
+
+    fn write_half_word_raw(&mut self, address: usize, value: u16) {
+        self.write_raw(address, value.get_byte(0));
+        self.write_raw(address + 1, value.get_byte(1));
+    }
+
+    fn write_word_raw(&mut self, address: usize, value: u32) {
+        self.write_half_word_raw(address, value.get_bits_u16(0..=15));
+        self.write_half_word_raw(address + 2, value.get_bits_u16(16..=31));
+    }
+
     fn read_sound_raw(&self, address: usize) -> u8 {
         match address {
             0x04000060 => self.sound.channel1_sweep.get_byte(0),
@@ -468,8 +724,8 @@ impl Bus {
             0x0400007D => self.sound.channel4_frequency_control.set_byte(1, value),
             0x04000080 => self.sound.control_stereo_volume_enable.set_byte(0, value),
             0x04000081 => self.sound.control_stereo_volume_enable.set_byte(1, value),
-            0x04000082 => self.sound.control_mixing_dma_control.set_byte(0, value),
-            0x04000083 => self.sound.control_mixing_dma_control.set_byte(1, value),
+            0x04000082 => self.sound.write_mixing_dma_control(0, value),
+            0x04000083 => self.sound.write_mixing_dma_control(1, value),
             0x04000084 => self.sound.control_sound_on_off.set_byte(0, value),
             0x04000085 => self.sound.control_sound_on_off.set_byte(1, value),
             0x04000088 => self.sound.sound_pwm_control.set_byte(0, value),
@@ -477,14 +733,8 @@ impl Bus {
             0x04000090..=0x0400009F => {
                 self.sound.channel3_wave_pattern_ram[address - 0x04000090] = value;
             }
-            0x040000A0 => self.sound.channel_a_fifo.set_byte(0, value),
-            0x040000A1 => self.sound.channel_a_fifo.set_byte(1, value),
-            0x040000A2 => self.sound.channel_a_fifo.set_byte(2, value),
-            0x040000A3 => self.sound.channel_a_fifo.set_byte(3, value),
-            0x040000A4 => self.sound.channel_b_fifo.set_byte(0, value),
-            0x040000A5 => self.sound.channel_b_fifo.set_byte(1, value),
-            0x040000A6 => self.sound.channel_b_fifo.set_byte(2, value),
-            0x040000A7 => self.sound.channel_b_fifo.set_byte(3, value),
+            0x040000A0..=0x040000A3 => self.sound.write_fifo(0, value),
+            0x040000A4..=0x040000A7 => self.sound.write_fifo(1, value),
             0x04000066..=0x04000067
             | 0x0400006A..=0x0400006B
             | 0x0400006E..=0x0400006F
@@ -635,109 +885,157 @@ impl Bus {
         }
     }
 
+    fn write_debug_output_raw(&mut self, address: usize, value: u8) {
+        match self.debug_output.write(address, value) {
+            Some(DebugRequest::NocashString { pointer, newline }) => {
+                let text = self.read_string(pointer as usize, 0x100);
+                self.debug_output.print_nocash(&text, newline);
+            }
+            None => {}
+        }
+    }
+
+    /// Reads a zero terminated string of at most `max_length` characters.
+    fn read_string(&self, address: usize, max_length: usize) -> String {
+        let bytes = (address..address + max_length)
+            .map(|address| self.read_raw(address))
+            .take_while(|&c| c != 0)
+            .collect::<Vec<u8>>();
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Prints the `AGBPrint` buffer (VBA debug output), called on SWI 0xFA.
+    /// The `AGBPrint` struct (request, bank, get, put) is at 0x09FE20F8 and
+    /// the ring buffer in the ROM bank selected by `bank`.
+    pub fn flush_agb_print(&mut self) {
+        const AGB_PRINT_STRUCT: usize = 0x09FE_20F8;
+
+        let read_half_word = |bus: &Self, address: usize| {
+            u16::from(bus.read_raw(address)) | (u16::from(bus.read_raw(address + 1)) << 8)
+        };
+
+        let bank = usize::from(read_half_word(self, AGB_PRINT_STRUCT + 2));
+        let mut get = read_half_word(self, AGB_PRINT_STRUCT + 4);
+        let put = read_half_word(self, AGB_PRINT_STRUCT + 6);
+        let buffer = 0x0800_0000 + (bank << 16);
+
+        let mut bytes = Vec::new();
+        while get != put {
+            bytes.push(self.read_raw(buffer + usize::from(get)));
+            get = get.wrapping_add(1);
+        }
+
+        self.write_raw(AGB_PRINT_STRUCT + 4, put.get_byte(0));
+        self.write_raw(AGB_PRINT_STRUCT + 5, put.get_byte(1));
+
+        let text = String::from_utf8_lossy(&bytes);
+        for line in text.lines() {
+            self.debug_output
+                .push(DebugLevel::Info, DebugSource::AgbPrint, line.to_string());
+        }
+    }
+
     #[must_use]
     pub fn read_raw(&self, address: usize) -> u8 {
+        let region = Region::of(address);
+        let offset = region.offset(address);
+
+        match region {
+            Region::Iwram => self.internal_memory.working_iram()[offset],
+            Region::Io => self.read_io_raw(address),
+            Region::Ewram => self.internal_memory.working_ram()[offset],
+            Region::Palette if offset < BG_PALETTE_SIZE => self.lcd.memory.bg_palette_ram[offset],
+            Region::Palette => self.lcd.memory.obj_palette_ram[offset - BG_PALETTE_SIZE],
+            Region::Vram => self.lcd.memory.video_ram[offset],
+            Region::Oam => self.lcd.memory.obj_attributes[offset],
+            Region::Rom if GPIO.contains(&address) => self
+                .gpio
+                .as_ref()
+                .and_then(|gpio| gpio.read(address))
+                .unwrap_or_else(|| self.internal_memory.read_rom(offset)),
+            Region::Rom => self.internal_memory.read_rom(offset),
+            Region::Bios if offset < BIOS_SIZE => self.internal_memory.bios()[offset],
+            Region::Sram if offset < SRAM_SIZE => self.internal_memory.sram[offset],
+            Region::Bios | Region::Sram | Region::Unused => self.read_unused(address),
+        }
+    }
+
+    fn read_io_raw(&self, address: usize) -> u8 {
         match address {
-            (0x0000000..=0x0003FFF) | (0x2000000..=0x03FFFFFF) | (0x08000000..=0x0E00FFFF) => {
-                self.internal_memory.read_at(address)
-            }
             0x4000000..=0x400005F => self.read_lcd_raw(address),
             0x4000060..=0x40000AF => self.read_sound_raw(address),
             0x40000B0..=0x40000FF => self.read_dma_raw(address),
             0x4000100..=0x400011F => self.read_timers_raw(address),
             0x4000120..=0x400012F | 0x4000134..=0x40001FF => self.read_serial_raw(address),
             0x4000130..=0x4000133 => self.read_keypad_raw(address),
+            0x4FF_F600..=0x4FF_F781 | 0x4FF_FA00..=0x4FF_FA1F => self.debug_output.read(address),
             0x4000200..=0x4FFFFFF => self.read_interrupt_control_raw(address),
-            0x5000000..=0x5FFFFFF => {
-                let unmasked_address = get_unmasked_address(address, 0x00FFFF00, 0xFF0000FF, 8, 4);
+            _ => unreachable!(),
+        }
+    }
 
-                match unmasked_address {
-                    0x05000000..=0x050001FF => {
-                        self.lcd.memory.bg_palette_ram[unmasked_address - 0x05000000]
-                    }
-                    0x05000200..=0x050003FF => {
-                        self.lcd.memory.obj_palette_ram[unmasked_address - 0x05000200]
-                    }
-                    _ => unreachable!(),
+    fn read_unused(&self, address: usize) -> u8 {
+        log(format!("read on unused memory {address:x}"));
+        // Bytes never written read as open bus, or as zero with the fast profile.
+        self.unused_region
+            .get(&address)
+            .copied()
+            .unwrap_or_else(|| {
+                if self.profile.is_fast() {
+                    0
+                } else {
+                    self.open_bus.get_byte((address & 3) as u8)
                 }
-            }
-            0x6000000..=0x6FFFFFF => {
-                let unmasked_address = get_unmasked_address(address, 0x00FF0000, 0xFF00FFFF, 16, 2);
+            })
+    }
 
-                // VRAM is 64k+32k+32k with the last two 32k being one mirrors of each other
-                match unmasked_address {
-                    0x06000000..=0x06017FFF => {
-                        self.lcd.memory.video_ram[unmasked_address - 0x06000000]
-                    }
-                    0x06018000..=0x0601FFFF => {
-                        self.lcd.memory.video_ram[unmasked_address - 0x06000000 - 0x8000]
-                    }
-                    _ => unreachable!(),
-                }
+    pub fn write_raw(&mut self, address: usize, value: u8) {
+        let region = Region::of(address);
+        let offset = region.offset(address);
+
+        match region {
+            Region::Iwram => self.internal_memory.working_iram_mut()[offset] = value,
+            Region::Io => self.write_io_raw(address, value),
+            Region::Ewram => self.internal_memory.working_ram_mut()[offset] = value,
+            Region::Palette if offset < BG_PALETTE_SIZE => {
+                self.lcd.memory.write_bg_palette_ram(offset, value);
             }
-            0x7000000..=0x7FFFFFF => {
-                let unmasked_address = get_unmasked_address(address, 0x00FFFF00, 0xFF0000FF, 8, 4);
-
-                self.lcd.memory.obj_attributes[unmasked_address - 0x07000000]
+            Region::Palette => {
+                self.lcd
+                    .memory
+                    .write_obj_palette_ram(offset - BG_PALETTE_SIZE, value);
             }
-            0x000_4000..=0x1FF_FFFF | 0xE01_0000..=0xFFF_FFFF | 0x1000_0000..=0xFFFF_FFFF => {
-                log(format!("read on unused memory {address:x}"));
-                *self.unused_region.get(&address).unwrap_or(&0)
+            Region::Vram => self.lcd.memory.write_video_ram(offset, value),
+            Region::Oam => self.lcd.memory.write_obj_attributes(offset, value),
+            Region::Rom if GPIO.contains(&address) && self.gpio.is_some() => {
+                if let Some(gpio) = &mut self.gpio {
+                    gpio.write(address, value, self.cycles_count);
+                }
+            }
+            Region::Sram if offset < SRAM_SIZE => self.internal_memory.sram[offset] = value,
+            Region::Rom => self.internal_memory.write_at(address, value),
+            Region::Bios if offset < BIOS_SIZE => self.internal_memory.write_at(address, value),
+            Region::Bios | Region::Sram | Region::Unused => {
+                log(format!("write on unused memory {address:x}"));
+                self.unused_region.insert(address, value);
             }
-            _ => unimplemented!(),
         }
     }
 
-    pub fn write_raw(&mut self, address: usize, value: u8) {
+    fn write_io_raw(&mut self, address: usize, value: u8) {
         match address {
-            0x0000000..=0x0003FFF | 0x2000000..=0x03FFFFFF | 0x08000000..=0x0E00FFFF => {
-                self.internal_memory.write_at(address, value);
-            }
             0x4000000..=0x400005F => self.write_lcd_raw(address, value),
             0x4000060..=0x40000AF => self.write_sound_raw(address, value),
             0x40000B0..=0x40000FF => self.write_dma_raw(address, value),
             0x4000100..=0x400011F => self.write_timers_raw(address, value),
             0x4000120..=0x400012F | 0x4000134..=0x40001FF => self.write_serial_raw(address, value),
             0x4000130..=0x4000133 => self.write_keypad_raw(address, value),
-            0x4000200..=0x4FFFFFF => self.write_interrupt_control_raw(address, value),
-            0x5000000..=0x5FFFFFF => {
-                let unmasked_address = get_unmasked_address(address, 0x00FFFF00, 0xFF0000FF, 8, 4);
-
-                match unmasked_address {
-                    0x05000000..=0x050001FF => {
-                        self.lcd.memory.bg_palette_ram[unmasked_address - 0x05000000] = value;
-                    }
-                    0x05000200..=0x050003FF => {
-                        self.lcd.memory.obj_palette_ram[unmasked_address - 0x05000200] = value;
-                    }
-                    _ => unreachable!(),
-                };
+            0x4FF_F600..=0x4FF_F781 | 0x4FF_FA00..=0x4FF_FA1F => {
+                self.write_debug_output_raw(address, value);
             }
-            0x6000000..=0x6FFFFFF => {
-                let unmasked_address = get_unmasked_address(address, 0x00FF0000, 0xFF00FFFF, 16, 2);
-
-                // VRAM is 64k+32k+32k with the last two 32k being one mirrors of each other
-                match unmasked_address {
-                    0x06000000..=0x06017FFF => {
-                        self.lcd.memory.video_ram[unmasked_address - 0x06000000] = value;
-                    }
-                    0x06018000..=0x0601FFFF => {
-                        self.lcd.memory.video_ram[unmasked_address - 0x06000000 - 0x8000] = value;
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            0x700_0000..=0x7FF_FFFF => {
-                let unmasked_address =
-                    get_unmasked_address(address, 0x00FF_FF00, 0xFF00_00FF, 8, 4);
-
-                self.lcd.memory.obj_attributes[unmasked_address - 0x0700_0000] = value;
-            }
-            0x000_4000..=0x1FF_FFFF | 0xE01_0000..=0xFFF_FFFF | 0x1000_0000..=0xFFFF_FFFF => {
-                log(format!("write on unused memory {address:x}"));
-                self.unused_region.insert(address, value);
-            }
-            _ => unimplemented!(),
+            0x4000200..=0x4FFFFFF => self.write_interrupt_control_raw(address, value),
+            _ => unreachable!(),
         }
     }
 
@@ -747,6 +1045,7 @@ impl Bus {
         }
 
         self.last_used_address = address;
+        self.read_side_effects(address, 1);
 
         self.read_raw(address)
     }
@@ -761,6 +1060,32 @@ impl Bus {
         self.write_raw(address, value);
     }
 
+    /// Registers changed by CPU reads, and the coverage.
+    /// `read_raw` has none so the UI can inspect memory.
+    fn read_side_effects(&mut self, address: usize, size: usize) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark_read(address, size);
+        }
+
+        // Reading the upper halfword of JOY_RECV
+        if address <= 0x04000153 && address + size > 0x04000152 {
+            self.serial.acknowledge_joy_bus_receive();
+        }
+    }
+
+    /// Runs up to `cycles` cycles while the CPU waits in a loop, stopping early at an
+    /// interrupt or an event. It returns the cycles run.
+    pub(crate) fn idle(&mut self, cycles: u32) -> u32 {
+        for run in 0..cycles {
+            if self.is_irq_pending() || !self.events.is_empty() {
+                return run;
+            }
+            self.step();
+        }
+
+        cycles
+    }
+
     fn step(&mut self) {
         // Step cycles at beginning or end?
         // It may have an impact when we will introduce timers.
@@ -774,10 +1099,40 @@ impl Bus {
         let val = *self.interrupt_control.interrupt_request.back().unwrap();
         self.interrupt_control.interrupt_request.push(val);
 
+        let overflows = self.timers.step();
+        if overflows != 0 {
+            self.handle_timer_overflows(overflows);
+        }
+
+        if self.sound.step() {
+            self.events.push(CoreEvent::AudioBufferFull);
+        }
+
         // A pixel takes 4 cycles to get drawn
-        if self.cycles_count % 4 == 0 {
+        if self.cycles_count.is_multiple_of(4) {
             let lcd_output = self.lcd.step();
 
+            if lcd_output.entered_hblank {
+                self.trigger_dma(StartTiming::HBlank);
+            }
+
+            if lcd_output.video_capture {
+                self.request_video_capture_dma();
+            }
+
+            if lcd_output.video_capture_ended {
+                self.end_video_capture_dma();
+            }
+
+            if lcd_output.entered_vblank {
+                self.trigger_dma(StartTiming::VBlank);
+                self.events.push(CoreEvent::VBlank);
+            }
+
+            if lcd_output.frame_completed {
+                self.events.push(CoreEvent::FrameComplete);
+            }
+
             if lcd_output.request_hblank_irq {
                 self.request_interrupt(&IrqType::HBlank);
             }
@@ -792,7 +1147,45 @@ impl Bus {
         }
     }
 
+    /// `overflows` has a bit for each timer that overflowed in this cycle.
+    fn handle_timer_overflows(&mut self, overflows: u8) {
+        for (index, irq_type) in [
+            IrqType::Timer0,
+            IrqType::Timer1,
+            IrqType::Timer2,
+            IrqType::Timer3,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if !overflows.get_bit(index as u8) {
+                continue;
+            }
+
+            self.record_timeline(TimelineEvent::TimerOverflow(index as u8));
+
+            if self.timers.is_irq_enabled(index) {
+                self.request_interrupt(irq_type);
+            }
+
+            // Timers 0 and 1 clock the Direct Sound channels.
+            if index < 2 {
+                let refill = self.sound.timer_overflow(index);
+
+                for (fifo, refill) in refill.into_iter().enumerate() {
+                    if refill {
+                        self.request_fifo_dma(fifo);
+                    }
+                }
+            }
+        }
+    }
+
     fn request_interrupt(&mut self, irq_type: &IrqType) {
+        self.record_timeline(TimelineEvent::Irq {
+            bit: irq_type.get_idx_in_if(),
+        });
+
         self.interrupt_control
             .interrupt_request
             .back_mut()
@@ -800,29 +1193,88 @@ impl Bus {
             .set_bit(irq_type.get_idx_in_if(), true);
     }
 
+    fn record_timeline(&mut self, event: TimelineEvent) {
+        if let Some(timeline) = &mut self.timeline {
+            let (line, dot) = self.lcd.position();
+            timeline.record(self.cycles_count, line, dot, event);
+        }
+    }
+
+    /// Runs a command of the JOY Bus master, it returns the response of the GBA.
+    pub fn joy_bus_command(&mut self, command: JoybusCommand) -> Vec<u8> {
+        let (response, request_irq) = self.serial.joy_bus_command(command);
+
+        if request_irq {
+            self.request_interrupt(&IrqType::Serial);
+        }
+
+        response
+    }
+
+    /// Sets the state of the keys, a bit is cleared while its key is pressed.
+    pub fn set_key_input(&mut self, keys: u16) {
+        self.keypad.key_input = keys;
+
+        if self.keypad.is_interrupt_requested() {
+            self.request_interrupt(&IrqType::Keypad);
+        }
+    }
+
+    #[must_use]
+    pub const fn key_input(&self) -> u16 {
+        self.keypad.key_input
+    }
+
+    /// The saved state of every component serialized on its own, to find out which
+    /// one differs between two states.
+    pub(crate) fn state_parts(&self) -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+        fn part(
+            name: &'static str,
+            value: &impl Serialize,
+        ) -> Result<(&'static str, Vec<u8>), String> {
+            bincode::serialize(value)
+                .map(|data| (name, data))
+                .map_err(|err| err.to_string())
+        }
+
+        Ok(vec![
+            part("internal_memory", &self.internal_memory)?,
+            part("lcd", &self.lcd)?,
+            part("sound", &self.sound)?,
+            part("dma", &self.dma)?,
+            part("timers", &self.timers)?,
+            part("serial", &self.serial)?,
+            part("keypad", &self.keypad)?,
+            part("interrupt_control", &self.interrupt_control)?,
+            part("debug_output", &self.debug_output)?,
+            part("cycles_count", &self.cycles_count)?,
+            part("last_used_address", &self.last_used_address)?,
+            part("open_bus", &self.open_bus)?,
+            part("unused_region", &self.unused_region)?,
+            part("gpio", &self.gpio)?,
+        ])
+    }
+
     #[must_use]
     pub fn with_memory(memory: InternalMemory) -> Self {
         Self {
+            gpio: Gpio::detect(&memory.rom),
             internal_memory: memory,
             ..Default::default()
         }
     }
 
     const fn get_wait_cycles(&self, address: usize) -> u128 {
-        let _ = self;
-        let _ = address;
-
         // let _is_sequential =
         // address == self.last_used_address || address + 4 == self.last_used_address;
 
-        // TODO: Restore this when we have a proper memory map
-        // match address {
-        // Bios
-        // 0x0..=0x3FFF => 1,
-        // _ => 1,
-        // }
-
-        1
+        // TODO: Restore the other regions when we have a proper memory map
+        match address {
+            _ if self.profile.is_fast() => 1,
+            0x0200_0000..=0x02FF_FFFF if self.fast_ewram => 2,
+            0x0200_0000..=0x02FF_FFFF => 1 + self.interrupt_control.ewram_wait_states(),
+            _ => 1,
+        }
     }
 
     pub fn read_word(&mut self, mut address: usize) -> u32 {
@@ -839,11 +1291,22 @@ impl Bus {
 
         self.last_used_address = address;
 
+        if SRAM.contains(&address) {
+            return u32::from(self.read_raw(address)) * 0x0101_0101;
+        }
+
         if address & 3 != 0 {
             log("warning, read_word has address not word aligned");
             address &= !3;
         }
 
+        self.read_side_effects(address, 4);
+
+        if let Some(offset) = iwram_offset(address) {
+            let bytes = &self.internal_memory.working_iram()[offset..offset + 4];
+            return u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
         let part_0: u32 = self.read_raw(address).into();
         let part_1: u32 = self.read_raw(address + 1).into();
         let part_2: u32 = self.read_raw(address + 2).into();
@@ -852,6 +1315,27 @@ impl Bus {
         part_3 << 24_u32 | part_2 << 16_u32 | part_1 << 8_u32 | part_0
     }
 
+    /// Reads an instruction, unlike [`Self::read_word`] it isn't a data read for the coverage.
+    pub fn fetch_word(&mut self, address: usize) -> u32 {
+        let coverage = self.coverage.take();
+        let value = self.read_word(address);
+        self.coverage = coverage;
+        self.open_bus = value;
+
+        value
+    }
+
+    /// Like [`Self::fetch_word`] for Thumb instructions.
+    pub fn fetch_half_word(&mut self, address: usize) -> u16 {
+        let coverage = self.coverage.take();
+        let value = self.read_half_word(address);
+        self.coverage = coverage;
+        // Simplified: in Thumb it depends on the region and on the alignment.
+        self.open_bus = u32::from(value) * 0x0001_0001;
+
+        value
+    }
+
     pub fn write_word(&mut self, mut address: usize, value: u32) {
         // TODO: Look at read_word
         for _ in 0..self.get_wait_cycles(address) {
@@ -860,15 +1344,26 @@ impl Bus {
 
         self.last_used_address = address;
 
+        if SRAM.contains(&address) {
+            self.write_raw(address, value.get_byte((address & 3) as u8));
+            return;
+        }
+
         if address & 3 != 0 {
             log("warning, write_word has address not word aligned");
             address &= !3;
         }
 
-        let part_0: u8 = value.get_bits(0..=7).try_into().unwrap();
-        let part_1: u8 = value.get_bits(8..=15).try_into().unwrap();
-        let part_2: u8 = value.get_bits(16..=23).try_into().unwrap();
-        let part_3: u8 = value.get_bits(24..=31).try_into().unwrap();
+        if let Some(offset) = iwram_offset(address) {
+            self.internal_memory.working_iram_mut()[offset..offset + 4]
+                .copy_from_slice(&value.to_le_bytes());
+            return;
+        }
+
+        let part_0: u8 = value.get_byte(0);
+        let part_1: u8 = value.get_byte(1);
+        let part_2: u8 = value.get_byte(2);
+        let part_3: u8 = value.get_byte(3);
 
         self.write_raw(address, part_0);
         self.write_raw(address + 1, part_1);
@@ -884,11 +1379,22 @@ impl Bus {
 
         self.last_used_address = address;
 
+        if SRAM.contains(&address) {
+            return u16::from(self.read_raw(address)) * 0x0101;
+        }
+
         if address & 1 != 0 {
             log("warning, read_half_word has address not half-word aligned");
             address &= !1;
         }
 
+        self.read_side_effects(address, 2);
+
+        if let Some(offset) = iwram_offset(address) {
+            let bytes = &self.internal_memory.working_iram()[offset..offset + 2];
+            return u16::from_le_bytes(bytes.try_into().unwrap());
+        }
+
         let part_0: u16 = self.read_raw(address).into();
         let part_1: u16 = self.read_raw(address + 1).into();
 
@@ -903,13 +1409,24 @@ impl Bus {
 
         self.last_used_address = address;
 
+        if SRAM.contains(&address) {
+            self.write_raw(address, value.get_byte((address & 1) as u8));
+            return;
+        }
+
         if address & 1 != 0 {
             log("warning, write_half_word has address not half-word aligned");
             address &= !1;
         }
 
-        let part_0: u8 = value.get_bits(0..=7).try_into().unwrap();
-        let part_1: u8 = value.get_bits(8..=15).try_into().unwrap();
+        if let Some(offset) = iwram_offset(address) {
+            self.internal_memory.working_iram_mut()[offset..offset + 2]
+                .copy_from_slice(&value.to_le_bytes());
+            return;
+        }
+
+        let part_0: u8 = value.get_byte(0);
+        let part_1: u8 = value.get_byte(1);
 
         self.write_raw(address, part_0);
         self.write_raw(address + 1, part_1);
@@ -922,20 +1439,103 @@ impl Bus {
     pub fn is_irq_pending(&self) -> bool {
         // Interrupt Master Enable has to be 1
         // && there needs to be an interrupt requested which is also enabled in the interrupt enable reg
-        (self.interrupt_control.interrupt_master_enable == 1)
-            && (self.interrupt_control.interrupt_enable
-                & *self.interrupt_control.interrupt_request.front().unwrap()
-                != 0)
+        (self.interrupt_control.interrupt_master_enable == 1) && self.is_irq_requested()
+    }
+
+    /// Whether an interrupt enabled in IE is requested, it wakes up a halted CPU even
+    /// when the master enable is off.
+    fn is_irq_requested(&self) -> bool {
+        self.interrupt_control.interrupt_enable
+            & *self.interrupt_control.interrupt_request.front().unwrap()
+            != 0
+    }
+}
+
+impl Bus for GbaBus {
+    fn read8(&mut self, address: usize) -> u8 {
+        self.read_byte(address)
+    }
+
+    fn read16(&mut self, address: usize) -> u16 {
+        self.read_half_word(address)
+    }
+
+    fn read32(&mut self, address: usize) -> u32 {
+        self.read_word(address)
+    }
+
+    fn write8(&mut self, address: usize, value: u8) {
+        self.write_byte(address, value);
+    }
+
+    fn write16(&mut self, address: usize, value: u16) {
+        self.write_half_word(address, value);
+    }
+
+    fn write32(&mut self, address: usize, value: u32) {
+        self.write_word(address, value);
+    }
+
+    fn cycles(&self) -> u128 {
+        self.cycles_count
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.is_irq_pending()
+    }
+
+    fn fetch32(&mut self, address: usize) -> u32 {
+        self.fetch_word(address)
+    }
+
+    fn fetch16(&mut self, address: usize) -> u16 {
+        self.fetch_half_word(address)
+    }
+
+    fn instruction_executed(&mut self, address: usize, size: usize) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark_executed(address, size);
+        }
+    }
+
+    fn internal_cycles(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.step();
+        }
+    }
+
+    fn halt(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            // The events stop it too, the frontend handles them between the steps.
+            if self.is_irq_requested() || !self.events.is_empty() {
+                return;
+            }
+            self.step();
+        }
+    }
+
+    fn software_interrupt(&mut self, function: u8) -> bool {
+        // SWI 0xFA is not a BIOS function, debuggers use it to flush `AGBPrint`.
+        if function == 0xFA {
+            self.flush_agb_print();
+        }
+
+        function == 0xFA
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::decimal_bitwise_operands)]
 mod tests {
-    use crate::bus::Bus;
+    use crate::bus::GbaBus;
+    use crate::cpu::hardware::joybus::JoybusCommand;
+    use crate::debugger::coverage::{Coverage, EXECUTED, READ};
+    use crate::debugger::timeline::{Timeline, TimelineEvent};
+    use crate::events::CoreEvent;
 
     #[test]
     fn test_write_lcd_reg() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x04000048; // WININ lower byte
 
         bus.write_raw(address, 10);
@@ -950,7 +1550,7 @@ mod tests {
 
     #[test]
     fn test_read_lcd_reg() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x04000048; // WININ lower byte
 
         bus.lcd.registers.winin = (5 << 8) | 10;
@@ -964,7 +1564,7 @@ mod tests {
 
     #[test]
     fn test_write_timer_register() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x04000100;
 
         bus.write_raw(address, 10);
@@ -973,7 +1573,7 @@ mod tests {
 
     #[test]
     fn test_read_timer_register() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x04000100;
 
         bus.timers.tm0cnt_l = (5 << 8) | 10;
@@ -981,9 +1581,26 @@ mod tests {
         assert_eq!(bus.read_raw(address), 10);
     }
 
+    #[test]
+    fn iwram_fast_path() {
+        let mut bus = GbaBus::default();
+
+        bus.write_word(0x0300_7FFC, 0x1234_5678);
+        assert_eq!(bus.internal_memory.read_at(0x0300_7FFC), 0x78);
+        assert_eq!(bus.internal_memory.read_at(0x0300_7FFF), 0x12);
+        // Mirrored every 32 KBytes.
+        assert_eq!(bus.read_word(0x03FF_FFFC), 0x1234_5678);
+        assert_eq!(bus.read_half_word(0x0301_7FFE), 0x1234);
+        assert_eq!(bus.read_byte(0x0300_FFFD), 0x56);
+
+        bus.write_half_word(0x0300_8010, 0xBEEF);
+        bus.write_byte(0x0300_0012, 0xAD);
+        assert_eq!(bus.read_word(0x0300_0010), 0x00AD_BEEF);
+    }
+
     #[test]
     fn write_bg_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x05000008;
 
         bus.write_raw(address, 10);
@@ -992,7 +1609,7 @@ mod tests {
 
     #[test]
     fn read_bg_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.bg_palette_ram[8] = 15;
 
         let address = 0x05000008;
@@ -1003,7 +1620,7 @@ mod tests {
 
     #[test]
     fn test_last_byte_bg_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
 
         let address = 0x050001FF;
         bus.write_raw(address, 5);
@@ -1013,7 +1630,7 @@ mod tests {
 
     #[test]
     fn write_obj_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x05000208;
 
         bus.write_raw(address, 10);
@@ -1022,7 +1639,7 @@ mod tests {
 
     #[test]
     fn read_obj_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.obj_palette_ram[8] = 15;
 
         let address = 0x05000208;
@@ -1034,7 +1651,7 @@ mod tests {
 
     #[test]
     fn test_last_byte_obj_palette_ram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
 
         let address = 0x050003FF;
         bus.write_raw(address, 5);
@@ -1044,7 +1661,7 @@ mod tests {
 
     #[test]
     fn write_vram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         let address = 0x06000004;
 
         bus.write_raw(address, 23);
@@ -1053,7 +1670,7 @@ mod tests {
 
     #[test]
     fn read_vram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.video_ram[4] = 15;
 
         let address = 0x06000004;
@@ -1064,7 +1681,7 @@ mod tests {
 
     #[test]
     fn test_last_byte_vram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
 
         let address = 0x06017FFF;
         bus.write_raw(address, 5);
@@ -1074,7 +1691,7 @@ mod tests {
 
     #[test]
     fn test_mirror_bg_palette() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.bg_palette_ram[0x134] = 5;
 
         assert_eq!(bus.read_raw(0x05000134), 5);
@@ -1097,7 +1714,7 @@ mod tests {
 
     #[test]
     fn test_mirror_obj_palette() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.obj_palette_ram[0x134] = 5;
 
         assert_eq!(bus.read_raw(0x05000334), 5);
@@ -1120,7 +1737,7 @@ mod tests {
 
     #[test]
     fn test_mirror_vram() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.video_ram[0x09345] = 5;
 
         assert_eq!(bus.read_raw(0x06009345), 5);
@@ -1145,9 +1762,31 @@ mod tests {
         assert_eq!(bus.read_raw(0x06131345), 10);
     }
 
+    #[test]
+    fn test_mirror_vram_edges() {
+        let mut bus = GbaBus::default();
+
+        // The last 32k mirror the OBJ tiles before them, then the 96k repeat every 128k.
+        bus.write_word(0x0601_8000, 0x1122_3344);
+        assert_eq!(bus.read_word(0x0601_0000), 0x1122_3344);
+        bus.write_half_word(0x0601_7FFE, 0xABCD);
+        assert_eq!(bus.read_half_word(0x0601_FFFE), 0xABCD);
+        assert_eq!(bus.read_word(0x0603_8000), 0x1122_3344);
+        assert_eq!(bus.read_word(0x06FF_8000), 0x1122_3344);
+
+        bus.write_word(0x0600_0000, 0x5566_7788);
+        assert_eq!(bus.read_word(0x0602_0000), 0x5566_7788);
+
+        // Palettes and OAM repeat every 1k.
+        bus.write_half_word(0x0500_0402, 0x7FFF);
+        assert_eq!(bus.lcd.memory.bg_palette_ram[2..4], [0xFF, 0x7F]);
+        bus.write_word(0x07FF_FC00, 0x0102_0304);
+        assert_eq!(bus.read_word(0x0700_0000), 0x0102_0304);
+    }
+
     #[test]
     fn test_mirror_oam() {
-        let mut bus = Bus::default();
+        let mut bus = GbaBus::default();
         bus.lcd.memory.obj_attributes[0x134] = 5;
 
         assert_eq!(bus.read_raw(0x07000134), 5);
@@ -1167,4 +1806,340 @@ mod tests {
         bus.write_raw(0x07FFFD34, 13);
         assert_eq!(bus.lcd.memory.obj_attributes[0x134], 13);
     }
+
+    #[test]
+    fn test_nocash_string_out() {
+        let mut bus = GbaBus::default();
+        for (offset, c) in b"hello\0".iter().enumerate() {
+            bus.write_byte(0x0200_0000 + offset, *c);
+        }
+
+        bus.write_word(0x04FF_FA18, 0x0200_0000);
+
+        assert_eq!(bus.debug_output.messages.back().unwrap().text, "hello");
+    }
+
+    #[test]
+    fn test_agb_print() {
+        let mut bus = GbaBus::default();
+        // AGBPrint buffer in bank 0xFD (0x08FD0000), 3 characters to print
+        bus.write_half_word(0x09FE_20FA, 0xFD);
+        bus.write_half_word(0x09FE_20FC, 0);
+        bus.write_half_word(0x09FE_20FE, 3);
+        for (offset, c) in b"hi\n".iter().enumerate() {
+            bus.write_byte(0x08FD_0000 + offset, *c);
+        }
+
+        bus.flush_agb_print();
+
+        assert_eq!(bus.debug_output.messages.back().unwrap().text, "hi");
+        assert_eq!(bus.read_half_word(0x09FE_20FC), 3);
+    }
+
+    /// Sets up DMA3 and enables it with `control` (enable bit included).
+    fn start_dma3(bus: &mut GbaBus, source: u32, destination: u32, count: u16, control: u16) {
+        bus.write_word(0x040000D4, source);
+        bus.write_word(0x040000D8, destination);
+        bus.write_half_word(0x040000DC, count);
+        bus.write_half_word(0x040000DE, control);
+    }
+
+    #[test]
+    fn test_dma_immediate_word_copy() {
+        let mut bus = GbaBus::default();
+        bus.write_word(0x0200_0000, 0x1234_5678);
+        bus.write_word(0x0200_0004, 0x9ABC_DEF0);
+
+        // Word transfer, enabled
+        start_dma3(&mut bus, 0x0200_0000, 0x0300_0000, 2, 0x8400);
+
+        assert_eq!(bus.read_word(0x0300_0000), 0x1234_5678);
+        assert_eq!(bus.read_word(0x0300_0004), 0x9ABC_DEF0);
+        assert!(!bus.dma.channels[3].is_enabled());
+    }
+
+    #[test]
+    fn test_dma_invalid_source_uses_latch() {
+        let mut bus = GbaBus::default();
+        bus.write_word(0x0200_0000, 0xCAFE_BABE);
+        start_dma3(&mut bus, 0x0200_0000, 0x0300_0000, 1, 0x8400);
+
+        // BIOS can't be read by DMA
+        bus.internal_memory.write_at(0x0000_0100, 0xFF);
+        start_dma3(&mut bus, 0x0000_0100, 0x0300_0010, 2, 0x8400);
+        assert_eq!(bus.read_word(0x0300_0010), 0xCAFE_BABE);
+        assert_eq!(bus.read_word(0x0300_0014), 0xCAFE_BABE);
+
+        // Neither can the region past the Game Pak ROM
+        start_dma3(&mut bus, 0x0E00_0000, 0x0300_0020, 1, 0x8400);
+        assert_eq!(bus.read_word(0x0300_0020), 0xCAFE_BABE);
+    }
+
+    #[test]
+    fn test_dma_half_word_latch() {
+        let mut bus = GbaBus::default();
+        bus.write_half_word(0x0200_0000, 0xBEEF);
+        start_dma3(&mut bus, 0x0200_0000, 0x0300_0000, 1, 0x8000);
+
+        assert_eq!(bus.dma.latch, 0xBEEF_BEEF);
+
+        // Word transfer from an invalid source writes the duplicated halfword
+        start_dma3(&mut bus, 0x0000_0000, 0x0300_0010, 1, 0x8400);
+        assert_eq!(bus.read_word(0x0300_0010), 0xBEEF_BEEF);
+    }
+
+    #[test]
+    fn test_dma_hblank_repeat() {
+        let mut bus = GbaBus::default();
+        bus.write_half_word(0x0200_0000, 1);
+
+        // Halfword, fixed source, repeat, HBlank timing
+        start_dma3(
+            &mut bus,
+            0x0200_0000,
+            0x0300_0000,
+            1,
+            0x8000 | 0x2000 | 0x0200 | 0x0100,
+        );
+        assert_eq!(bus.read_half_word(0x0300_0000), 0);
+
+        // Until the first HBlank
+        while bus.lcd.registers.dispstat & 0b10 == 0 {
+            bus.step();
+        }
+        assert_eq!(bus.read_half_word(0x0300_0000), 1);
+        assert!(bus.dma.channels[3].is_enabled());
+        assert_eq!(bus.dma.channels[3].internal_destination, 0x0300_0002);
+    }
+
+    #[test]
+    fn timeline() {
+        let mut bus = GbaBus {
+            timeline: Some(Timeline::new(16)),
+            ..Default::default()
+        };
+
+        // HBlank IRQ, and DMA3 of 2 halfwords at HBlank
+        bus.write_half_word(0x0400_0004, 1 << 4);
+        start_dma3(&mut bus, 0x0200_0000, 0x0300_0000, 2, 0x8000 | 0x2000);
+
+        while bus.lcd.registers.dispstat & 0b10 == 0 {
+            bus.step();
+        }
+
+        let entries = bus.timeline.as_ref().unwrap().entries();
+        assert_eq!(
+            entries.iter().map(|entry| entry.event).collect::<Vec<_>>(),
+            vec![
+                TimelineEvent::Dma {
+                    channel: 3,
+                    units: 2
+                },
+                TimelineEvent::Irq { bit: 1 },
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|entry| (entry.line, entry.dot) == (0, 241)));
+    }
+
+    #[test]
+    fn test_dma_video_capture() {
+        let mut bus = GbaBus::default();
+        bus.write_half_word(0x0200_0000, 1);
+
+        // Halfword, fixed source, repeat, special timing
+        start_dma3(
+            &mut bus,
+            0x0200_0000,
+            0x0300_0000,
+            1,
+            0x8000 | 0x3000 | 0x0200 | 0x0100,
+        );
+
+        let step_until_line = |bus: &mut GbaBus, line: u16| {
+            while bus.lcd.registers.vcount != line {
+                bus.step();
+            }
+        };
+
+        // Nothing is transferred in the first two lines
+        step_until_line(&mut bus, 2);
+        assert_eq!(bus.dma.channels[3].internal_destination, 0x0300_0000);
+
+        // One unit per line from line 2 to 161, VBlank lines included
+        step_until_line(&mut bus, 163);
+        assert_eq!(
+            bus.dma.channels[3].internal_destination,
+            0x0300_0000 + 160 * 2
+        );
+        assert_eq!(bus.read_half_word(0x0300_0000 + 159 * 2), 1);
+        assert!(!bus.dma.channels[3].is_enabled());
+    }
+
+    #[test]
+    fn test_sound_fifo_dma() {
+        let mut bus = GbaBus::default();
+
+        // Sound on, FIFO A at 100% on both sides with timer 0
+        bus.write_half_word(0x0400_0084, 0x80);
+        bus.write_half_word(0x0400_0082, 0x0304);
+
+        // DMA1 to FIFO A: word, repeat, special timing
+        bus.write_word(0x0400_00BC, 0x0200_0000);
+        bus.write_word(0x0400_00C0, 0x0400_00A0);
+        bus.write_half_word(0x0400_00C6, 0x8000 | 0x3000 | 0x0400 | 0x0200);
+
+        // Timer 0 overflowing every 2 cycles
+        bus.write_half_word(0x0400_0100, 0xFFFE);
+        bus.write_half_word(0x0400_0102, 0x0080);
+
+        let mut step = |cycles: usize| {
+            for _ in 0..cycles {
+                bus.step();
+            }
+            bus.dma.channels[1].internal_source
+        };
+
+        // The FIFO is refilled with 4 words as long as it's half empty
+        assert_eq!(step(2), 0x0200_0010);
+        assert_eq!(step(2), 0x0200_0020);
+        assert_eq!(step(28), 0x0200_0020);
+        assert_eq!(step(2), 0x0200_0030);
+        assert!(bus.dma.channels[1].is_enabled());
+    }
+
+    #[test]
+    fn test_timer_irq() {
+        let mut bus = GbaBus::default();
+
+        // Timer 2 counts the overflows of timer 1, with the IRQ enabled
+        bus.write_half_word(0x0400_0104, 0xFFFF);
+        bus.write_half_word(0x0400_0108, 0xFFFF);
+        bus.write_half_word(0x0400_010A, 0x00C4);
+        bus.write_half_word(0x0400_0106, 0x0080);
+
+        bus.step();
+        assert_eq!(bus.read_half_word(0x0400_0108), 0xFFFF);
+        // Timer 2 IRQ
+        assert_eq!(
+            *bus.interrupt_control.interrupt_request.back().unwrap() & (1 << 5),
+            1 << 5
+        );
+    }
+
+    #[test]
+    fn test_serial_start_event() {
+        let mut bus = GbaBus::default();
+        bus.write_raw(0x04000128, 0x80);
+        bus.write_raw(0x04000128, 0x81);
+
+        assert_eq!(bus.events, vec![CoreEvent::SerialTransferStarted]);
+    }
+
+    #[test]
+    fn test_joy_bus() {
+        let mut bus = GbaBus::default();
+
+        // Not in JOY Bus mode: the master gets no answer.
+        assert!(bus.joy_bus_command(JoybusCommand::Reset).is_empty());
+
+        bus.write_half_word(0x04000134, 0xC000);
+        bus.write_half_word(0x04000140, 0x40);
+
+        assert_eq!(
+            bus.joy_bus_command(JoybusCommand::Reset),
+            vec![0x00, 0x04, 0x00]
+        );
+        assert_eq!(bus.read_half_word(0x04000140), 0x41);
+
+        // Flags are cleared writing 1, polling games don't see a reset that never happened.
+        bus.write_half_word(0x04000140, 0x47);
+        assert_eq!(bus.read_half_word(0x04000140), 0x40);
+
+        assert_eq!(
+            bus.joy_bus_command(JoybusCommand::Write(0x1234_5678)),
+            vec![0x02]
+        );
+        assert_eq!(bus.read_half_word(0x04000158), 0x02);
+        assert_eq!(bus.read_word(0x04000150), 0x1234_5678);
+        assert_eq!(bus.read_half_word(0x04000158), 0x00);
+
+        bus.write_word(0x04000154, 0xCAFE_BABE);
+        assert_eq!(bus.read_half_word(0x04000158), 0x08);
+        assert_eq!(
+            bus.joy_bus_command(JoybusCommand::Read),
+            vec![0xBE, 0xBA, 0xFE, 0xCA, 0x00]
+        );
+        assert_eq!(bus.read_half_word(0x04000140), 0x46);
+    }
+
+    #[test]
+    fn coverage_reads() {
+        let mut bus = GbaBus::default();
+        bus.internal_memory.rom = vec![0; 0x20];
+        bus.coverage = Some(Coverage::new(0x20));
+
+        bus.read_half_word(0x0800_0004);
+        // Instruction fetches are not data reads.
+        bus.fetch_word(0x0800_0008);
+        bus.fetch_half_word(0x0800_000C);
+        let _ = bus.read_raw(0x0800_0010);
+
+        let coverage = bus.coverage.as_ref().unwrap();
+        assert_eq!(&coverage.flags()[4..6], &[READ, READ]);
+        assert!(coverage.flags()[6..].iter().all(|&flags| flags == 0));
+        assert_eq!(coverage.flags()[0] & EXECUTED, 0);
+    }
+
+    #[test]
+    fn internal_memory_control_mirror() {
+        let mut bus = GbaBus::default();
+
+        assert_eq!(bus.read_word(0x0400_0800), 0x0D00_0020);
+
+        bus.write_word(0x0401_0800, 0x0E00_0020);
+        assert_eq!(bus.read_word(0x0400_0800), 0x0E00_0020);
+    }
+
+    #[test]
+    fn ewram_wait_states() {
+        let mut bus = GbaBus::default();
+        let cycles = |bus: &mut GbaBus, address| {
+            let start = bus.cycles_count;
+            bus.read_word(address);
+            bus.cycles_count - start
+        };
+
+        // 2 wait states set by the BIOS.
+        assert_eq!(cycles(&mut bus, 0x0200_0000), 3);
+        assert_eq!(cycles(&mut bus, 0x0300_0000), 1);
+
+        bus.write_word(0x0400_0800, 0x0E00_0020);
+        assert_eq!(cycles(&mut bus, 0x0200_0000), 2);
+
+        bus.write_word(0x0400_0800, 0x0000_0020);
+        assert_eq!(cycles(&mut bus, 0x0200_0000), 16);
+
+        bus.fast_ewram = true;
+        assert_eq!(cycles(&mut bus, 0x0200_0000), 2);
+    }
+
+    #[test]
+    fn sram_8_bit_bus() {
+        let mut bus = GbaBus::default();
+
+        bus.write_byte(0x0E00_0001, 0x12);
+        assert_eq!(bus.read_half_word(0x0E00_0001), 0x1212);
+        assert_eq!(bus.read_word(0x0E00_0001), 0x1212_1212);
+        assert_eq!(bus.read_word(0x0E00_0000), 0xFFFF_FFFF);
+
+        // Only the byte of the address is stored.
+        bus.write_word(0x0E00_0006, 0xAABB_CCDD);
+        bus.write_half_word(0x0E00_0009, 0x1122);
+        let bytes: Vec<u8> = (0x0E00_0004..0x0E00_000C)
+            .map(|address| bus.read_byte(address))
+            .collect();
+        assert_eq!(bytes, [0xFF, 0xFF, 0xBB, 0xFF, 0xFF, 0x11, 0xFF, 0xFF]);
+    }
 }
@@ -0,0 +1,6 @@
+pub mod backup;
+pub mod dat;
+pub mod hash;
+#[allow(clippy::similar_names)]
+pub mod header;
+pub mod patch;
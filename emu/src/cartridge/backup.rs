@@ -0,0 +1,81 @@
+/// Memory a cartridge keeps its saves in, told by the ID string the Nintendo library
+/// for it leaves in the ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupKind {
+    Eeprom,
+    Sram,
+    Flash64K,
+    Flash128K,
+    /// No ID string, the game doesn't save or uses its own library.
+    Unknown,
+}
+
+/// ID strings, the longest first as `FLASH_V` is also the start of the others.
+const ID_STRINGS: [(&[u8], BackupKind); 6] = [
+    (b"FLASH1M_V", BackupKind::Flash128K),
+    (b"FLASH512_V", BackupKind::Flash64K),
+    (b"FLASH_V", BackupKind::Flash64K),
+    (b"EEPROM_V", BackupKind::Eeprom),
+    (b"SRAM_F_V", BackupKind::Sram),
+    (b"SRAM_V", BackupKind::Sram),
+];
+
+impl BackupKind {
+    /// Looks for the ID strings, the whole ROM is scanned.
+    #[must_use]
+    pub fn detect(rom: &[u8]) -> Self {
+        // The strings are word aligned.
+        for offset in (0..rom.len()).step_by(4) {
+            let rest = &rom[offset..];
+            if let Some((_, kind)) = ID_STRINGS.iter().find(|(id, _)| rest.starts_with(id)) {
+                return *kind;
+            }
+        }
+
+        Self::Unknown
+    }
+
+    /// Bytes of save data, `None` when it's not known (EEPROMs are 512 bytes or 8 `KBytes`).
+    #[must_use]
+    pub const fn size(self) -> Option<usize> {
+        match self {
+            Self::Sram => Some(0x8000),
+            Self::Flash64K => Some(0x1_0000),
+            Self::Flash128K => Some(0x2_0000),
+            Self::Eeprom | Self::Unknown => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rom_with(id: &[u8]) -> Vec<u8> {
+        let mut rom = vec![0; 0x400];
+        rom[0x200..0x200 + id.len()].copy_from_slice(id);
+        rom
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            BackupKind::detect(&rom_with(b"EEPROM_V124")),
+            BackupKind::Eeprom
+        );
+        assert_eq!(
+            BackupKind::detect(&rom_with(b"SRAM_F_V102")),
+            BackupKind::Sram
+        );
+        assert_eq!(
+            BackupKind::detect(&rom_with(b"FLASH_V126")),
+            BackupKind::Flash64K
+        );
+        assert_eq!(
+            BackupKind::detect(&rom_with(b"FLASH1M_V103")),
+            BackupKind::Flash128K
+        );
+        assert_eq!(BackupKind::detect(&rom_with(b"")), BackupKind::Unknown);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use super::hash::RomHash;
+
+/// A ROM entry of a DAT file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatEntry {
+    pub game: String,
+    pub size: usize,
+    pub crc32: u32,
+    /// Lowercase hex digest, not every DAT has it.
+    pub sha1: Option<String>,
+    /// Game serial (eg. "AGB-AXVE"), not every DAT has it.
+    pub serial: Option<String>,
+}
+
+impl DatEntry {
+    fn matches(&self, hash: &RomHash) -> bool {
+        self.size == hash.size
+            && self.crc32 == hash.crc32
+            && self.sha1.as_ref().is_none_or(|sha1| *sha1 == hash.sha1)
+    }
+}
+
+/// Result of checking a ROM against a DAT file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// Size and checksums match an entry of the DAT.
+    Verified { game: String },
+    /// The ROM starts with a good dump but it has extra bytes at the end.
+    Overdump { game: String, expected_size: usize },
+    /// The DAT knows this game (same serial) but checksums don't match.
+    BadDump { game: String },
+    /// The ROM is not in the DAT.
+    Unknown,
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Verified { game } => write!(f, "Verified good dump of \"{game}\""),
+            Self::Overdump {
+                game,
+                expected_size,
+            } => write!(
+                f,
+                "Overdump of \"{game}\", good dump is {expected_size} bytes"
+            ),
+            Self::BadDump { game } => write!(f, "Bad dump of \"{game}\""),
+            Self::Unknown => f.write_str("Not found in DAT"),
+        }
+    }
+}
+
+/// No-Intro (Logiqx XML) DAT file.
+#[derive(Debug, Default)]
+pub struct Dat {
+    pub entries: Vec<DatEntry>,
+}
+
+impl Dat {
+    /// Parses a Logiqx XML DAT, only `game` and `rom` elements are read.
+    ///
+    /// # Errors
+    /// It returns an error if a `rom` element has a missing or invalid `size`/`crc`
+    /// or if there are no entries at all.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+
+        for game_block in text.split("<game").skip(1) {
+            let game_block = game_block.split("</game>").next().unwrap_or_default();
+            let game_attributes = parse_attributes(tag_content(game_block));
+            let game = game_attributes.get("name").cloned().unwrap_or_default();
+
+            for rom_block in game_block.split("<rom").skip(1) {
+                let attributes = parse_attributes(tag_content(rom_block));
+
+                let size = attributes
+                    .get("size")
+                    .and_then(|size| size.parse().ok())
+                    .ok_or_else(|| format!("Invalid rom size in \"{game}\""))?;
+                let crc32 = attributes
+                    .get("crc")
+                    .and_then(|crc| u32::from_str_radix(crc, 16).ok())
+                    .ok_or_else(|| format!("Invalid rom crc in \"{game}\""))?;
+
+                entries.push(DatEntry {
+                    game: game.clone(),
+                    size,
+                    crc32,
+                    sha1: attributes.get("sha1").map(|sha1| sha1.to_lowercase()),
+                    serial: attributes.get("serial").cloned(),
+                });
+            }
+        }
+
+        if entries.is_empty() {
+            return Err("No rom found in DAT".to_string());
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Looks for `rom` in the DAT, `game_code` is the one found in the cartridge header.
+    #[must_use]
+    pub fn verify(&self, rom: &[u8], hash: &RomHash, game_code: &str) -> Verification {
+        if let Some(entry) = self.entries.iter().find(|entry| entry.matches(hash)) {
+            return Verification::Verified {
+                game: entry.game.clone(),
+            };
+        }
+
+        // Dumpers sometimes read past the end of the chip, the good dump is then a prefix of the ROM.
+        let mut prefix_crc32 = HashMap::new();
+        for entry in self.entries.iter().filter(|entry| entry.size < rom.len()) {
+            let crc32 = *prefix_crc32
+                .entry(entry.size)
+                .or_insert_with(|| crc32fast::hash(&rom[..entry.size]));
+
+            if crc32 == entry.crc32 {
+                return Verification::Overdump {
+                    game: entry.game.clone(),
+                    expected_size: entry.size,
+                };
+            }
+        }
+
+        let same_game = self.entries.iter().find(|entry| {
+            entry
+                .serial
+                .as_ref()
+                .is_some_and(|serial| !game_code.is_empty() && serial.ends_with(game_code))
+        });
+
+        same_game.map_or(Verification::Unknown, |entry| Verification::BadDump {
+            game: entry.game.clone(),
+        })
+    }
+}
+
+/// Content of the tag starting at the beginning of `block`, up to `>`.
+fn tag_content(block: &str) -> &str {
+    block.split('>').next().unwrap_or_default()
+}
+
+/// Parses `key="value"` pairs, XML entities in values are decoded.
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag;
+
+    while let Some((key, value_start)) = rest.split_once("=\"") {
+        let Some((value, next)) = value_start.split_once('"') else {
+            break;
+        };
+
+        let key = key.split_whitespace().last().unwrap_or_default();
+        attributes.insert(key.to_string(), decode_entities(value));
+        rest = next;
+    }
+
+    attributes
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn make_dat(rom: &[u8]) -> String {
+        let hash = RomHash::new(rom);
+        format!(
+            r#"<?xml version="1.0"?>
+<datafile>
+    <header><name>Nintendo - Game Boy Advance</name></header>
+    <game name="Clementine &amp; Friends (Europe)">
+        <description>Clementine &amp; Friends (Europe)</description>
+        <rom name="Clementine.gba" size="{}" crc="{:08X}" sha1="{}" serial="AGB-ACLE"/>
+    </game>
+</datafile>"#,
+            hash.size,
+            hash.crc32,
+            hash.sha1.to_uppercase()
+        )
+    }
+
+    #[test]
+    fn parse_dat() {
+        let dat = Dat::parse(&make_dat(b"rom!")).unwrap();
+
+        assert_eq!(dat.entries.len(), 1);
+        assert_eq!(dat.entries[0].game, "Clementine & Friends (Europe)");
+        assert_eq!(dat.entries[0].size, 4);
+        assert_eq!(dat.entries[0].serial.as_deref(), Some("AGB-ACLE"));
+        assert!(Dat::parse("<datafile></datafile>").is_err());
+    }
+
+    #[test]
+    fn verify_rom() {
+        let rom = b"rom!";
+        let dat = Dat::parse(&make_dat(rom)).unwrap();
+        let game = "Clementine & Friends (Europe)".to_string();
+
+        assert_eq!(
+            dat.verify(rom, &RomHash::new(rom), "ACLE"),
+            Verification::Verified { game: game.clone() }
+        );
+
+        let overdump = b"rom!\xFF\xFF\xFF\xFF";
+        assert_eq!(
+            dat.verify(overdump, &RomHash::new(overdump), "ACLE"),
+            Verification::Overdump {
+                game: game.clone(),
+                expected_size: 4
+            }
+        );
+
+        let bad_dump = b"rom?";
+        assert_eq!(
+            dat.verify(bad_dump, &RomHash::new(bad_dump), "ACLE"),
+            Verification::BadDump { game }
+        );
+        assert_eq!(
+            dat.verify(bad_dump, &RomHash::new(bad_dump), "AXVE"),
+            Verification::Unknown
+        );
+    }
+}
@@ -0,0 +1,46 @@
+/// Checksums of a ROM, the same used by No-Intro DAT files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomHash {
+    pub size: usize,
+    pub crc32: u32,
+    /// Lowercase hex digest, it also identifies the game on `RetroAchievements`.
+    pub md5: String,
+    /// Lowercase hex digest.
+    pub sha1: String,
+}
+
+impl RomHash {
+    #[must_use]
+    pub fn new(rom: &[u8]) -> Self {
+        Self {
+            size: rom.len(),
+            crc32: crc32fast::hash(rom),
+            md5: format!("{:x}", md5::compute(rom)),
+            sha1: sha1_smol::Sha1::from(rom).digest().to_string(),
+        }
+    }
+}
+
+/// Key identifying a game, used to name per-game files (configs, save states).
+/// It's the CRC32 of the ROM, so patched ROMs get their own key.
+#[must_use]
+pub fn game_key(rom: &[u8]) -> String {
+    format!("{:08x}", crc32fast::hash(rom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hash_known_values() {
+        let hash = RomHash::new(b"abc");
+
+        assert_eq!(hash.size, 3);
+        assert_eq!(hash.crc32, 0x3524_41C2);
+        assert_eq!(hash.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hash.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(game_key(b"abc"), "352441c2");
+    }
+}
@@ -1,16 +1,38 @@
+/// Size in bytes of the cartridge header.
+pub const HEADER_SIZE: usize = 0xE4;
+
+/// Fixed value at offset 0xB2, required by the BIOS.
+const FIXED_VALUE: u8 = 0x96;
+
+/// Compressed bitmap the BIOS compares against before booting the cartridge.
+const NINTENDO_LOGO: [u8; 156] = [
+    0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
+    0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21, 0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20,
+    0x10, 0x46, 0x4A, 0x4A, 0xF8, 0x27, 0x31, 0xEC, 0x58, 0xC7, 0xE8, 0x33, 0x82, 0xE3, 0xCE, 0xBF,
+    0x85, 0xF4, 0xDF, 0x94, 0xCE, 0x4B, 0x09, 0xC1, 0x94, 0x56, 0x8A, 0xC0, 0x13, 0x72, 0xA7, 0xFC,
+    0x9F, 0x84, 0x4D, 0x73, 0xA3, 0xCA, 0x9A, 0x61, 0x58, 0x97, 0xA3, 0x27, 0xFC, 0x03, 0x98, 0x76,
+    0x23, 0x1D, 0xC7, 0x61, 0x03, 0x04, 0xAE, 0x56, 0xBF, 0x38, 0x84, 0x00, 0x40, 0xA7, 0x0E, 0xFD,
+    0xFF, 0x52, 0xFE, 0x03, 0x6F, 0x95, 0x30, 0xF1, 0x97, 0xFB, 0xC0, 0x85, 0x60, 0xD6, 0x80, 0x25,
+    0xA9, 0x63, 0xBE, 0x03, 0x01, 0x4E, 0x38, 0xE2, 0xF9, 0xA2, 0x34, 0xFF, 0xBB, 0x3E, 0x03, 0x44,
+    0x78, 0x00, 0x90, 0xCB, 0x88, 0x11, 0x3A, 0x94, 0x65, 0xC0, 0x7C, 0x63, 0x87, 0xF0, 0x3C, 0xAF,
+    0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
+];
+
 #[allow(dead_code)] // FIXME: remove this `allow` when all member are used.
-pub struct CartridgeHeader {
+pub struct Header {
     pub rom_entry_point: [u8; 4],
     pub nintendo_logo: [u8; 156],
     pub game_title: String,
     pub game_code: String,
-    pub marker_code: String,
+    pub maker_code: String,
     pub fixed_value: [u8; 1],
     pub main_unit_code: [u8; 1],
     pub device_type: [u8; 1],
     pub reserved_area_1: [u8; 7],
     pub software_version: [u8; 1],
     pub complement_check: u8,
+    /// Checksum computed over 0xA0..=0xBC, it differs from `complement_check` on bad dumps.
+    pub computed_complement_check: u8,
     pub reserved_area_2: [u8; 2],
     pub ram_entry_point: [u8; 4],
     pub boot_mode: [u8; 1],
@@ -19,22 +41,34 @@ pub struct CartridgeHeader {
     pub joybus_mode_entry_point: [u8; 4],
 }
 
-impl CartridgeHeader {
-    /// Create a new `CartridgeHeader` from a slice of bytes.
+impl Header {
+    /// Create a new `Header` from a slice of bytes.
+    ///
+    /// An invalid logo or complement check doesn't make the parsing fail,
+    /// use [`Header::warnings`] to know if the cartridge looks like a bad dump.
     ///
     /// # Errors
+    /// It returns an error if `data` is too small to contain a header.
     pub fn new(data: &[u8]) -> Result<Self, String> {
+        if data.len() < HEADER_SIZE {
+            return Err(format!(
+                "Cartridge is {} bytes long but header needs {HEADER_SIZE} bytes",
+                data.len()
+            ));
+        }
+
         let rom_entry_point = Self::extract_rom_entry_point(data);
         let nintendo_logo = Self::extract_nintendo_logo(data);
         let game_title = Self::extract_game_title(data);
         let game_code = Self::extract_game_code(data);
-        let marker_code = Self::extract_marker_code(data);
+        let maker_code = Self::extract_maker_code(data);
         let fixed_value = Self::extract_fixed_value(data);
         let main_unit_code = Self::extract_main_unit_code(data);
         let device_type = Self::extract_device_type(data);
         let reserved_area_1 = Self::extract_reserved_area_1(data);
         let software_version = Self::extract_software_version(data);
-        let complement_check = Self::extract_complement_check(data)?;
+        let complement_check = Self::extract_complement_check(data);
+        let computed_complement_check = Self::compute_complement_check(data);
         let reserved_area_2 = Self::extract_reserved_area_2(data);
         let ram_entry_point = Self::extract_ram_entry_point(data);
         let boot_mode = Self::extract_boot_mode(data);
@@ -47,13 +81,14 @@ impl CartridgeHeader {
             nintendo_logo,
             game_title,
             game_code,
-            marker_code,
+            maker_code,
             fixed_value,
             main_unit_code,
             device_type,
             reserved_area_1,
             software_version,
             complement_check,
+            computed_complement_check,
             reserved_area_2,
             ram_entry_point,
             boot_mode,
@@ -83,7 +118,7 @@ impl CartridgeHeader {
             .try_into()
             .expect("extracting game title");
 
-        String::from_utf8(game_title_bytes.into()).expect("parsing game title")
+        String::from_utf8_lossy(&game_title_bytes).into_owned()
     }
 
     /// Uppercase ascii, 4 characters
@@ -92,16 +127,16 @@ impl CartridgeHeader {
             .try_into()
             .expect("extracting game code");
 
-        String::from_utf8(game_code_bytes.into()).expect("parsing game code")
+        String::from_utf8_lossy(&game_code_bytes).into_owned()
     }
 
     /// Uppercase ascii, 2 characters
-    fn extract_marker_code(data: &[u8]) -> String {
-        let marker_code_bytes: [u8; 2] = data[0x0B0..=0x0B1]
+    fn extract_maker_code(data: &[u8]) -> String {
+        let maker_code_bytes: [u8; 2] = data[0x0B0..=0x0B1]
             .try_into()
-            .expect("extracting marker code");
+            .expect("extracting maker code");
 
-        String::from_utf8(marker_code_bytes.into()).expect("parsing marker code")
+        String::from_utf8_lossy(&maker_code_bytes).into_owned()
     }
 
     /// Must be 0x96, required
@@ -140,18 +175,16 @@ impl CartridgeHeader {
     }
 
     /// Header checksum, required
-    fn extract_complement_check(data: &[u8]) -> Result<u8, String> {
-        let checksum_expected = data[0xBD];
-        let checksum = data[0xA0..0xBD]
+    const fn extract_complement_check(data: &[u8]) -> u8 {
+        data[0x0BD]
+    }
+
+    /// Checksum the BIOS computes over 0xA0..=0xBC
+    fn compute_complement_check(data: &[u8]) -> u8 {
+        data[0x0A0..0x0BD]
             .iter()
             .fold(0u8, |acc, &item| acc.wrapping_sub(item))
-            .wrapping_sub(0x19);
-
-        if checksum != checksum_expected {
-            return Err(format!("Expected {checksum_expected} but got {checksum}"));
-        }
-
-        Ok(checksum)
+            .wrapping_sub(0x19)
     }
 
     /// Should be zero filled
@@ -217,4 +250,108 @@ impl CartridgeHeader {
             .try_into()
             .expect("extracting joybus mode entry point")
     }
+
+    /// Software version of the game (0x00 for the first release).
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        self.software_version[0]
+    }
+
+    /// Game title without the zero padding.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        self.game_title.trim_end_matches('\0')
+    }
+
+    #[must_use]
+    pub fn is_logo_valid(&self) -> bool {
+        self.nintendo_logo == NINTENDO_LOGO
+    }
+
+    #[must_use]
+    pub const fn is_fixed_value_valid(&self) -> bool {
+        self.fixed_value[0] == FIXED_VALUE
+    }
+
+    #[must_use]
+    pub const fn is_complement_check_valid(&self) -> bool {
+        self.complement_check == self.computed_complement_check
+    }
+
+    /// Problems found in the header, a real BIOS would refuse to boot a cartridge
+    /// with any of these. An empty list means the header looks like a good dump.
+    #[must_use]
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if !self.is_logo_valid() {
+            warnings.push("Nintendo logo doesn't match".to_string());
+        }
+
+        if !self.is_fixed_value_valid() {
+            warnings.push(format!(
+                "Fixed value should be 0x{FIXED_VALUE:02X} but is 0x{:02X}",
+                self.fixed_value[0]
+            ));
+        }
+
+        if !self.is_complement_check_valid() {
+            warnings.push(format!(
+                "Complement check should be 0x{:02X} but is 0x{:02X}",
+                self.computed_complement_check, self.complement_check
+            ));
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn make_header_data() -> Vec<u8> {
+        let mut data = vec![0; HEADER_SIZE];
+        data[0x004..=0x09F].copy_from_slice(&NINTENDO_LOGO);
+        data[0x0A0..=0x0AB].copy_from_slice(b"CLEMENTINE\0\0");
+        data[0x0AC..=0x0AF].copy_from_slice(b"ACLE");
+        data[0x0B0..=0x0B1].copy_from_slice(b"01");
+        data[0x0B2] = FIXED_VALUE;
+        data[0x0BC] = 1;
+        data[0x0BD] = Header::compute_complement_check(&data);
+
+        data
+    }
+
+    #[test]
+    fn parse_valid_header() {
+        let header = Header::new(&make_header_data()).unwrap();
+
+        assert_eq!(header.title(), "CLEMENTINE");
+        assert_eq!(header.game_code, "ACLE");
+        assert_eq!(header.maker_code, "01");
+        assert_eq!(header.version(), 1);
+        assert!(header.is_logo_valid());
+        assert!(header.is_complement_check_valid());
+        assert!(header.warnings().is_empty());
+    }
+
+    #[test]
+    fn bad_dump_is_not_an_error() {
+        let mut data = make_header_data();
+        data[0x0BD] = data[0x0BD].wrapping_add(1);
+        data[0x010] = 0;
+
+        let header = Header::new(&data).unwrap();
+
+        assert!(!header.is_logo_valid());
+        assert!(!header.is_complement_check_valid());
+        assert_eq!(header.warnings().len(), 2);
+    }
+
+    #[test]
+    fn header_too_small() {
+        assert!(Header::new(&[0; HEADER_SIZE - 1]).is_err());
+    }
 }
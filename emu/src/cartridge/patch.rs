@@ -0,0 +1,495 @@
+/// Kind of soft-patch, detected from the magic at the beginning of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchKind {
+    Ips,
+    Ups,
+    Bps,
+}
+
+impl PatchKind {
+    /// File extensions looked up next to the ROM, in order of preference.
+    pub const EXTENSIONS: [&'static str; 3] = ["bps", "ups", "ips"];
+
+    #[must_use]
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(b"PATCH") {
+            Some(Self::Ips)
+        } else if patch.starts_with(b"UPS1") {
+            Some(Self::Ups)
+        } else if patch.starts_with(b"BPS1") {
+            Some(Self::Bps)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for PatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ips => f.write_str("IPS"),
+            Self::Ups => f.write_str("UPS"),
+            Self::Bps => f.write_str("BPS"),
+        }
+    }
+}
+
+/// Applies `patch` to a copy of `rom` and returns the patched copy.
+///
+/// # Errors
+/// It returns an error if the patch format is unknown, if the patch is truncated
+/// or if UPS/BPS checksums don't match (usually the patch is meant for another ROM).
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    match PatchKind::detect(patch) {
+        Some(PatchKind::Ips) => apply_ips(rom, patch),
+        Some(PatchKind::Ups) => apply_ups(rom, patch),
+        Some(PatchKind::Bps) => apply_bps(rom, patch),
+        None => Err("Unknown patch format".to_string()),
+    }
+}
+
+/// UPS and BPS patches can't make a ROM larger than the biggest cartridge, their header
+/// is checked against it before anything is allocated.
+const MAX_TARGET_SIZE: usize = 32 * 1024 * 1024;
+
+fn check_target_size(target_size: usize) -> Result<(), String> {
+    if target_size > MAX_TARGET_SIZE {
+        return Err(format!(
+            "Patch makes a ROM of {target_size} bytes, more than the {MAX_TARGET_SIZE} bytes of a cartridge"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Cursor over the patch bytes, every read is bounds checked.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8], position: usize) -> Self {
+        Self { data, position }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let value = *self
+            .data
+            .get(self.position)
+            .ok_or_else(|| format!("Patch truncated at offset {:#X}", self.position))?;
+        self.position += 1;
+
+        Ok(value)
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let value = self
+            .data
+            .get(self.position..self.position.saturating_add(count))
+            .ok_or_else(|| format!("Patch truncated at offset {:#X}", self.position))?;
+        self.position += count;
+
+        Ok(value)
+    }
+
+    fn big_endian(&mut self, count: usize) -> Result<usize, String> {
+        Ok(self
+            .bytes(count)?
+            .iter()
+            .fold(0, |acc, &byte| (acc << 8) | usize::from(byte)))
+    }
+
+    /// Variable length number used by UPS and BPS.
+    fn number(&mut self) -> Result<usize, String> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+
+        loop {
+            let byte = self.byte()?;
+            value = usize::from(byte & 0x7F)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or("Patch number overflow")?;
+
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+
+            shift = shift.checked_shl(7).ok_or("Patch number overflow")?;
+            value = value.checked_add(shift).ok_or("Patch number overflow")?;
+        }
+    }
+
+    fn u32_le(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    const EOF: usize = 0x45_4F_46;
+
+    let mut output = rom.to_vec();
+    let mut reader = Reader::new(patch, 5);
+
+    loop {
+        let offset = reader.big_endian(3)?;
+        if offset == EOF {
+            break;
+        }
+
+        let size = reader.big_endian(2)?;
+        // Size 0 means the record is RLE encoded.
+        let (size, data) = if size == 0 {
+            let size = reader.big_endian(2)?;
+            (size, vec![reader.byte()?; size])
+        } else {
+            (size, reader.bytes(size)?.to_vec())
+        };
+
+        if output.len() < offset + size {
+            output.resize(offset + size, 0);
+        }
+
+        output[offset..offset + size].copy_from_slice(&data);
+    }
+
+    // Some patches have a 3 bytes truncation size after the EOF marker.
+    if patch.len() - reader.position == 3 {
+        output.truncate(reader.big_endian(3)?);
+    }
+
+    Ok(output)
+}
+
+/// Checks the patch and ROM checksums, it returns the expected checksum of the patched ROM.
+fn check_footer(rom: &[u8], patch: &[u8]) -> Result<u32, String> {
+    if patch.len() < 12 {
+        return Err("Patch truncated".to_string());
+    }
+
+    let mut footer = Reader::new(patch, patch.len() - 12);
+    let source_crc = footer.u32_le()?;
+    let target_crc = footer.u32_le()?;
+    let patch_crc = footer.u32_le()?;
+
+    if crc32fast::hash(&patch[..patch.len() - 4]) != patch_crc {
+        return Err("Patch is corrupted".to_string());
+    }
+
+    if crc32fast::hash(rom) != source_crc {
+        return Err("Patch is not meant for this ROM".to_string());
+    }
+
+    Ok(target_crc)
+}
+
+fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let target_crc = check_footer(rom, patch)?;
+    let end = patch.len() - 12;
+
+    let mut reader = Reader::new(patch, 4);
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    check_target_size(target_size)?;
+
+    if source_size != rom.len() {
+        return Err(format!(
+            "Patch expects a ROM of {source_size} bytes but it is {} bytes",
+            rom.len()
+        ));
+    }
+
+    let mut output = rom.to_vec();
+    output.resize(target_size, 0);
+
+    let mut position: usize = 0;
+    while reader.position < end {
+        position = position
+            .checked_add(reader.number()?)
+            .ok_or("Patch offset out of bounds")?;
+
+        // XOR block terminated by a zero byte, the terminator is applied too.
+        loop {
+            let value = reader.byte()?;
+            if position < target_size {
+                output[position] ^= value;
+            }
+            position = position
+                .checked_add(1)
+                .ok_or("Patch offset out of bounds")?;
+
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    if crc32fast::hash(&output) != target_crc {
+        return Err("Patched ROM checksum doesn't match".to_string());
+    }
+
+    Ok(output)
+}
+
+fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let target_crc = check_footer(rom, patch)?;
+    let end = patch.len() - 12;
+
+    let mut reader = Reader::new(patch, 4);
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+    check_target_size(target_size)?;
+
+    if source_size != rom.len() {
+        return Err(format!(
+            "Patch expects a ROM of {source_size} bytes but it is {} bytes",
+            rom.len()
+        ));
+    }
+
+    let mut output = Vec::with_capacity(target_size);
+    let mut source_relative: usize = 0;
+    let mut target_relative: usize = 0;
+
+    let relative_offset = |reader: &mut Reader, base: usize| -> Result<usize, String> {
+        let data = reader.number()?;
+        let delta = data >> 1;
+        let moved = if data & 1 == 0 {
+            base.checked_add(delta)
+        } else {
+            base.checked_sub(delta)
+        };
+
+        moved.ok_or_else(|| "Patch offset out of bounds".to_string())
+    };
+
+    while reader.position < end {
+        let data = reader.number()?;
+        let length = (data >> 2) + 1;
+        let out_of_bounds = || "Patch offset out of bounds".to_string();
+
+        // Every action appends `length` bytes, the output can't grow past the target.
+        if length > target_size - output.len() {
+            return Err("Patch writes past the end of the patched ROM".to_string());
+        }
+
+        match data & 0b11 {
+            // SourceRead: copy from the ROM at the same offset of the output
+            0 => {
+                let start = output.len();
+                output.extend_from_slice(rom.get(start..start + length).ok_or_else(out_of_bounds)?);
+            }
+            // TargetRead: copy from the patch
+            1 => output.extend_from_slice(reader.bytes(length)?),
+            // SourceCopy: copy from anywhere in the ROM
+            2 => {
+                source_relative = relative_offset(&mut reader, source_relative)?;
+                output.extend_from_slice(
+                    rom.get(source_relative..source_relative.saturating_add(length))
+                        .ok_or_else(out_of_bounds)?,
+                );
+                source_relative += length;
+            }
+            // TargetCopy: copy from the output itself, byte by byte since ranges can overlap
+            _ => {
+                target_relative = relative_offset(&mut reader, target_relative)?;
+                for _ in 0..length {
+                    let value = *output.get(target_relative).ok_or_else(out_of_bounds)?;
+                    output.push(value);
+                    target_relative += 1;
+                }
+            }
+        }
+    }
+
+    if output.len() != target_size || crc32fast::hash(&output) != target_crc {
+        return Err("Patched ROM checksum doesn't match".to_string());
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn with_footer(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+        patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(&patch).to_le_bytes());
+
+        patch
+    }
+
+    #[test]
+    fn detect_kind() {
+        assert_eq!(PatchKind::detect(b"PATCHEOF"), Some(PatchKind::Ips));
+        assert_eq!(PatchKind::detect(b"UPS1"), Some(PatchKind::Ups));
+        assert_eq!(PatchKind::detect(b"BPS1"), Some(PatchKind::Bps));
+        assert_eq!(PatchKind::detect(b"ROM"), None);
+        assert!(apply(&[0; 4], b"ROM").is_err());
+    }
+
+    #[test]
+    fn apply_ips_records() {
+        let rom = [0_u8; 4];
+        let mut patch = b"PATCH".to_vec();
+        // Normal record: 2 bytes at offset 1
+        patch.extend_from_slice(&[0, 0, 1, 0, 2, 0xAA, 0xBB]);
+        // RLE record: 3 times 0xCC at offset 4, it grows the ROM
+        patch.extend_from_slice(&[0, 0, 4, 0, 0, 0, 3, 0xCC]);
+        patch.extend_from_slice(b"EOF");
+
+        assert_eq!(
+            apply(&rom, &patch).unwrap(),
+            vec![0, 0xAA, 0xBB, 0, 0xCC, 0xCC, 0xCC]
+        );
+
+        // Truncation after EOF
+        patch.extend_from_slice(&[0, 0, 2]);
+        assert_eq!(apply(&rom, &patch).unwrap(), vec![0, 0xAA]);
+    }
+
+    #[test]
+    fn apply_ips_truncated() {
+        assert!(apply(&[0; 4], b"PATCH\x00\x00\x01\x00\x02\xAA").is_err());
+    }
+
+    #[test]
+    fn apply_ups_xor() {
+        let rom = [1_u8, 2, 3, 4];
+        let target = [1_u8, 7, 3, 4, 9];
+
+        // source size 4, target size 5
+        let mut patch = b"UPS1".to_vec();
+        patch.extend_from_slice(&[0x84, 0x85]);
+        // skip 1 byte, xor 2 with 5, terminator
+        patch.extend_from_slice(&[0x81, 0x05, 0x00]);
+        // skip 1 byte (position is 3 after the terminator), xor 0 with 9
+        patch.extend_from_slice(&[0x81, 0x09, 0x00]);
+        let patch = with_footer(patch, &rom, &target);
+
+        assert_eq!(apply(&rom, &patch).unwrap(), target.to_vec());
+        assert!(apply(&[1, 2, 3, 5], &patch).is_err());
+    }
+
+    #[test]
+    fn apply_bps_actions() {
+        let rom = [1_u8, 2, 3, 4];
+        let target = [1_u8, 2, 9, 3, 4, 4, 4];
+
+        // source size 4, target size 7, no metadata
+        let mut patch = b"BPS1".to_vec();
+        patch.extend_from_slice(&[0x84, 0x87, 0x80]);
+        // SourceRead 2 bytes
+        patch.push(0x80 | (1 << 2));
+        // TargetRead 1 byte
+        patch.extend_from_slice(&[0x80 | 1, 9]);
+        // SourceCopy 2 bytes from offset +2
+        patch.extend_from_slice(&[0x80 | (1 << 2) | 2, 0x80 | (2 << 1)]);
+        // TargetCopy 2 bytes from offset +4, it overlaps the bytes it's writing
+        patch.extend_from_slice(&[0x80 | (1 << 2) | 3, 0x80 | (4 << 1)]);
+        let patch = with_footer(patch, &rom, &target);
+
+        assert_eq!(apply(&rom, &patch).unwrap(), target.to_vec());
+    }
+
+    /// A variable length number of UPS and BPS.
+    fn number(mut value: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = u8::try_from(value & 0x7F).unwrap();
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte | 0x80);
+                return bytes;
+            }
+            bytes.push(byte);
+            value -= 1;
+        }
+    }
+
+    #[test]
+    fn reject_huge_targets() {
+        let rom = [0_u8; 4];
+        for magic in [b"UPS1", b"BPS1"] {
+            let mut patch = magic.to_vec();
+            patch.extend(number(4));
+            patch.extend(number(MAX_TARGET_SIZE + 1));
+            patch.push(0x80);
+            let patch = with_footer(patch, &rom, &rom);
+
+            assert!(apply(&rom, &patch).unwrap_err().contains("more than"));
+        }
+    }
+
+    #[test]
+    fn bps_stops_at_the_target_size() {
+        let rom = [1_u8, 2, 3, 4];
+
+        // Target size 2, a TargetRead of 1 byte and a TargetCopy of 2^40 bytes.
+        let mut patch = b"BPS1".to_vec();
+        patch.extend_from_slice(&[0x84, 0x82, 0x80]);
+        patch.extend_from_slice(&[0x80 | 1, 9]);
+        patch.extend(number(((1 << 40) - 1) << 2 | 3));
+        patch.push(0x80);
+        let patch = with_footer(patch, &rom, &rom);
+
+        assert_eq!(
+            apply(&rom, &patch).unwrap_err(),
+            "Patch writes past the end of the patched ROM"
+        );
+    }
+
+    #[test]
+    fn bps_source_copy_out_of_bounds() {
+        let rom = [1_u8, 2, 3, 4];
+
+        // SourceCopy of 1 byte from the largest offset.
+        let mut patch = b"BPS1".to_vec();
+        patch.extend_from_slice(&[0x84, 0x81, 0x80]);
+        patch.push(0x80 | 2);
+        patch.extend(number((usize::MAX >> 1) << 1));
+        let patch = with_footer(patch, &rom, &rom);
+
+        assert_eq!(
+            apply(&rom, &patch).unwrap_err(),
+            "Patch offset out of bounds"
+        );
+    }
+
+    #[test]
+    fn ups_offset_overflow() {
+        let rom = [1_u8, 2, 3, 4];
+
+        // Two skips adding up past usize::MAX.
+        let mut patch = b"UPS1".to_vec();
+        patch.extend_from_slice(&[0x84, 0x84]);
+        for _ in 0..2 {
+            patch.extend(number(usize::MAX / 2 + 1));
+            patch.extend_from_slice(&[0x05, 0x00]);
+        }
+        let patch = with_footer(patch, &rom, &rom);
+
+        assert_eq!(
+            apply(&rom, &patch).unwrap_err(),
+            "Patch offset out of bounds"
+        );
+    }
+
+    #[test]
+    fn reader_number() {
+        // 0x80 terminates immediately with value 0, 0x00 0x80 encodes 128.
+        assert_eq!(Reader::new(&[0x80], 0).number().unwrap(), 0);
+        assert_eq!(Reader::new(&[0x00, 0x80], 0).number().unwrap(), 128);
+
+        for value in [0, 127, 128, 0x4000, MAX_TARGET_SIZE, usize::MAX] {
+            assert_eq!(Reader::new(&number(value), 0).number().unwrap(), value);
+        }
+    }
+}
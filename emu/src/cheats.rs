@@ -0,0 +1,159 @@
+use crate::boot_patch::PatchWidth;
+
+/// Seeds of the encryption of `GameShark` Advance (v1 and v2) codes.
+const GAMESHARK_SEEDS: [u32; 4] = [0x09F4_FBBD, 0x9681_884A, 0x3520_27E9, 0xF3DE_E5A7];
+const TEA_DELTA: u32 = 0x9E37_79B9;
+const TEA_ROUNDS: u32 = 32;
+
+/// When a cheat writes its value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CheatMode {
+    /// Written again at the end of every frame, the game can't change it.
+    #[default]
+    Freeze,
+    /// Written at the end of the next frame, then the cheat turns itself off.
+    Once,
+}
+
+impl CheatMode {
+    pub const ALL: [Self; 2] = [Self::Freeze, Self::Once];
+}
+
+/// A value written in memory while the game runs, see [`crate::gba::Gba::cheats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    pub name: String,
+    pub address: u32,
+    /// Only the low bytes of the width are written, little endian.
+    pub value: u32,
+    pub width: PatchWidth,
+    pub mode: CheatMode,
+    pub enabled: bool,
+}
+
+impl Cheat {
+    /// Bytes to write from the address.
+    #[must_use]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.width.le_bytes(self.value)
+    }
+
+    /// The decrypted `GameShark` Advance code writing the value, the first digit is the width.
+    #[must_use]
+    pub const fn raw_gameshark_code(&self) -> (u32, u32) {
+        let (kind, mask) = match self.width {
+            PatchWidth::Byte => (0, 0xFF),
+            PatchWidth::HalfWord => (1, 0xFFFF),
+            PatchWidth::Word => (2, u32::MAX),
+        };
+
+        (kind << 28 | self.address & 0x0FFF_FFFF, self.value & mask)
+    }
+
+    /// The code to type in a `GameShark` Advance (v1 or v2) or in another emulator. The
+    /// device writes it continuously, like [`CheatMode::Freeze`].
+    #[must_use]
+    pub fn gameshark_code(&self) -> String {
+        let (address, value) = self.raw_gameshark_code();
+        let (address, value) = encrypt_gameshark(address, value);
+
+        format!("{address:08X} {value:08X}")
+    }
+}
+
+/// TEA encryption of the two words of a `GameShark` Advance code.
+#[must_use]
+pub fn encrypt_gameshark(mut address: u32, mut value: u32) -> (u32, u32) {
+    let [seed_0, seed_1, seed_2, seed_3] = GAMESHARK_SEEDS;
+    let mut sum = 0_u32;
+
+    for _ in 0..TEA_ROUNDS {
+        sum = sum.wrapping_add(TEA_DELTA);
+        address = address.wrapping_add(
+            (value << 4).wrapping_add(seed_0)
+                ^ value.wrapping_add(sum)
+                ^ (value >> 5).wrapping_add(seed_1),
+        );
+        value = value.wrapping_add(
+            (address << 4).wrapping_add(seed_2)
+                ^ address.wrapping_add(sum)
+                ^ (address >> 5).wrapping_add(seed_3),
+        );
+    }
+
+    (address, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// The decryption of the `GameShark` Advance, the inverse of [`encrypt_gameshark`].
+    fn decrypt_gameshark(mut address: u32, mut value: u32) -> (u32, u32) {
+        let [seed_0, seed_1, seed_2, seed_3] = GAMESHARK_SEEDS;
+        let mut sum = TEA_DELTA.wrapping_mul(TEA_ROUNDS);
+
+        for _ in 0..TEA_ROUNDS {
+            value = value.wrapping_sub(
+                (address << 4).wrapping_add(seed_2)
+                    ^ address.wrapping_add(sum)
+                    ^ (address >> 5).wrapping_add(seed_3),
+            );
+            address = address.wrapping_sub(
+                (value << 4).wrapping_add(seed_0)
+                    ^ value.wrapping_add(sum)
+                    ^ (value >> 5).wrapping_add(seed_1),
+            );
+            sum = sum.wrapping_sub(TEA_DELTA);
+        }
+
+        (address, value)
+    }
+
+    fn cheat(width: PatchWidth) -> Cheat {
+        Cheat {
+            name: "Lives".to_string(),
+            address: 0x0300_0010,
+            value: 0x1234_5609,
+            width,
+            mode: CheatMode::Freeze,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn raw_gameshark_code() {
+        assert_eq!(
+            cheat(PatchWidth::Byte).raw_gameshark_code(),
+            (0x0300_0010, 0x09)
+        );
+        assert_eq!(
+            cheat(PatchWidth::HalfWord).raw_gameshark_code(),
+            (0x1300_0010, 0x5609)
+        );
+        assert_eq!(
+            cheat(PatchWidth::Word).raw_gameshark_code(),
+            (0x2300_0010, 0x1234_5609)
+        );
+    }
+
+    #[test]
+    fn gameshark_code_decrypts_to_the_raw_one() {
+        for width in PatchWidth::ALL {
+            let cheat = cheat(width);
+            let code = cheat.gameshark_code();
+            let (address, value) = code.split_once(' ').unwrap();
+            let encrypted = (
+                u32::from_str_radix(address, 16).unwrap(),
+                u32::from_str_radix(value, 16).unwrap(),
+            );
+
+            assert_ne!(encrypted, cheat.raw_gameshark_code());
+            assert_eq!(
+                decrypt_gameshark(encrypted.0, encrypted.1),
+                cheat.raw_gameshark_code()
+            );
+        }
+    }
+}
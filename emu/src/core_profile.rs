@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// How closely the core follows the hardware, chosen per game: the accuracy profile is
+/// for testing and for the games that need it, the fast one for low-end devices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CoreProfile {
+    /// Wait states of the memory, open bus reads of unused memory and every instruction
+    /// executed.
+    #[default]
+    Accuracy,
+    /// One cycle for every access, zero from unused memory, the loops waiting for an
+    /// interrupt skipped and the instructions decoded once.
+    Fast,
+}
+
+impl CoreProfile {
+    pub const ALL: [Self; 2] = [Self::Accuracy, Self::Fast];
+
+    #[must_use]
+    pub const fn is_fast(self) -> bool {
+        matches!(self, Self::Fast)
+    }
+}
+
+/// ARM `b .` with the always condition, the loop games use to wait for an interrupt.
+const ARM_IDLE_LOOP: u32 = 0xEAFF_FFFE;
+/// Thumb `b .`.
+const THUMB_IDLE_LOOP: u32 = 0xE7FE;
+
+/// Whether `op_code` is a branch to itself, nothing changes until an interrupt comes.
+#[must_use]
+pub const fn is_idle_loop(op_code: u32, is_thumb: bool) -> bool {
+    if is_thumb {
+        op_code == THUMB_IDLE_LOOP
+    } else {
+        op_code == ARM_IDLE_LOOP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_loops() {
+        assert!(is_idle_loop(0xEAFF_FFFE, false));
+        assert!(is_idle_loop(0xE7FE, true));
+        // Conditional and to another address
+        assert!(!is_idle_loop(0x0AFF_FFFE, false));
+        assert!(!is_idle_loop(0xEAFF_FFFD, false));
+        assert!(!is_idle_loop(0xE7FD, true));
+    }
+}
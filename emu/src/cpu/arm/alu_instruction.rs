@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use crate::bitwise::Bits;
 use crate::cpu::flags::ShiftKind;
 
+use super::encodings::ArmFormat;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ArmModeAluInstr {
     And = 0x0,
@@ -23,29 +25,35 @@ pub enum ArmModeAluInstr {
     Mvn = 0xF,
 }
 
-impl std::fmt::Display for ArmModeAluInstr {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ArmModeAluInstr {
+    pub(crate) const fn name(self) -> &'static str {
         match self {
-            Self::And => f.write_str("AND"),
-            Self::Eor => f.write_str("EOR"),
-            Self::Sub => f.write_str("SUB"),
-            Self::Rsb => f.write_str("RSB"),
-            Self::Add => f.write_str("ADD"),
-            Self::Adc => f.write_str("ADC"),
-            Self::Sbc => f.write_str("SBC"),
-            Self::Rsc => f.write_str("RSC"),
-            Self::Tst => f.write_str("TST"),
-            Self::Teq => f.write_str("TEQ"),
-            Self::Cmp => f.write_str("CMP"),
-            Self::Cmn => f.write_str("CMN"),
-            Self::Orr => f.write_str("ORR"),
-            Self::Mov => f.write_str("MOV"),
-            Self::Bic => f.write_str("BIC"),
-            Self::Mvn => f.write_str("MVN"),
+            Self::And => "AND",
+            Self::Eor => "EOR",
+            Self::Sub => "SUB",
+            Self::Rsb => "RSB",
+            Self::Add => "ADD",
+            Self::Adc => "ADC",
+            Self::Sbc => "SBC",
+            Self::Rsc => "RSC",
+            Self::Tst => "TST",
+            Self::Teq => "TEQ",
+            Self::Cmp => "CMP",
+            Self::Cmn => "CMN",
+            Self::Orr => "ORR",
+            Self::Mov => "MOV",
+            Self::Bic => "BIC",
+            Self::Mvn => "MVN",
         }
     }
 }
 
+impl std::fmt::Display for ArmModeAluInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum AIKind {
     Logical,
@@ -113,6 +121,33 @@ pub struct ArithmeticOpResult {
     pub zero: bool,
 }
 
+/// `first + second + carry` in 33 bits like the ALU of the ARM7TDMI: the carry out is
+/// bit 32 and the overflow is computed on the whole sum, not on its partial sums.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn add_with_carry(first: u32, second: u32, carry: bool) -> ArithmeticOpResult {
+    let wide = u64::from(first) + u64::from(second) + u64::from(carry);
+    let result = wide as u32;
+
+    ArithmeticOpResult {
+        result,
+        carry: wide.get_bit(32),
+        // The operands have the same sign and the result has the other one.
+        overflow: ((first ^ result) & (second ^ result)).get_bit(31),
+        sign: result.get_bit(31),
+        zero: result == 0,
+    }
+}
+
+/// `first - second - !carry`, done as `first + !second + carry` like the ARM7TDMI:
+/// the carry out is set when there is no borrow.
+#[must_use]
+pub fn sub_with_carry(first: u32, second: u32, carry: bool) -> ArithmeticOpResult {
+    add_with_carry(first, !second, carry)
+}
+
+/// The carry is the last bit shifted out, its index is only taken where the amount is
+/// between 1 and 32 so the conversion to `u8` can't truncate.
 pub fn shift(kind: ShiftKind, shift_amount: u32, rm: u32, carry: bool) -> ArithmeticOpResult {
     match kind {
         ShiftKind::Lsl => {
@@ -134,7 +169,7 @@ pub fn shift(kind: ShiftKind, shift_amount: u32, rm: u32, carry: bool) -> Arithm
                     let result = (rm << shift_amount) as u32;
                     ArithmeticOpResult {
                         result,
-                        carry: rm.get_bit((32 - shift_amount).try_into().unwrap()),
+                        carry: rm.get_bit(32 - shift_amount as u8),
                         ..Default::default()
                     }
                 }
@@ -161,7 +196,7 @@ pub fn shift(kind: ShiftKind, shift_amount: u32, rm: u32, carry: bool) -> Arithm
 
                     ArithmeticOpResult {
                         result,
-                        carry: rm.get_bit((shift_amount - 1).try_into().unwrap()),
+                        carry: rm.get_bit(shift_amount as u8 - 1),
                         ..Default::default()
                     }
                 }
@@ -175,7 +210,7 @@ pub fn shift(kind: ShiftKind, shift_amount: u32, rm: u32, carry: bool) -> Arithm
         ShiftKind::Asr => match shift_amount {
             1..=31 => ArithmeticOpResult {
                 result: ((rm as i32) >> shift_amount) as u32,
-                carry: rm.get_bit((shift_amount - 1).try_into().unwrap()),
+                carry: rm.get_bit(shift_amount as u8 - 1),
                 ..Default::default()
             },
             _ => ArithmeticOpResult {
@@ -215,7 +250,7 @@ pub fn shift(kind: ShiftKind, shift_amount: u32, rm: u32, carry: bool) -> Arithm
                 // ROR#1..31: normal rotate right
                 1..=31 => ArithmeticOpResult {
                     result: rm.rotate_right(new_shift_amount),
-                    carry: rm.get_bit((new_shift_amount - 1).try_into().unwrap()),
+                    carry: rm.get_bit(new_shift_amount as u8 - 1),
                     ..Default::default()
                 },
 
@@ -246,25 +281,14 @@ pub enum PsrOpKind {
 
 impl From<u32> for PsrOpKind {
     fn from(op_code: u32) -> Self {
-        if op_code.get_bits(23..=27) == 0b0_0010
-            && op_code.get_bits(16..=21) == 0b00_1111
-            && op_code.get_bits(0..=11) == 0b0000_0000_0000
-        {
-            Self::Mrs {
+        match ArmFormat::decode(op_code) {
+            Some(ArmFormat::Mrs) => Self::Mrs {
                 destination_register: op_code.get_bits(12..=15),
-            }
-        } else if op_code.get_bits(23..=27) == 0b00010
-            && op_code.get_bits(12..=21) == 0b10_1001_1111
-            && op_code.get_bits(4..=11) == 0b0000_0000
-        {
-            Self::Msr {
+            },
+            Some(ArmFormat::Msr) => Self::Msr {
                 source_register: op_code.get_bits(0..=3),
-            }
-        } else if op_code.get_bits(26..=27) == 0b00
-            && op_code.get_bits(23..=24) == 0b10
-            && op_code.get_bits(12..=21) == 0b10_1000_1111
-        {
-            Self::MsrFlg {
+            },
+            Some(ArmFormat::MsrFlg) => Self::MsrFlg {
                 operand: if op_code.get_bit(25) {
                     AluSecondOperandInfo::Immediate {
                         base: op_code.get_bits(0..=7),
@@ -277,9 +301,8 @@ impl From<u32> for PsrOpKind {
                         register: op_code.get_bits(0..=3),
                     }
                 },
-            }
-        } else {
-            unreachable!()
+            },
+            _ => unreachable!(),
         }
     }
 }
@@ -380,6 +403,209 @@ mod tests {
         assert_eq!(instruction_kind, AIKind::Logical);
     }
 
+    /// Flags of a result as a `NZCV` string, like the ones of `CpuTest`.
+    fn flags(result: &ArithmeticOpResult) -> String {
+        [
+            (result.sign, 'N'),
+            (result.zero, 'Z'),
+            (result.carry, 'C'),
+            (result.overflow, 'V'),
+        ]
+        .iter()
+        .map(|(is_set, flag)| {
+            if *is_set {
+                *flag
+            } else {
+                flag.to_ascii_lowercase()
+            }
+        })
+        .collect()
+    }
+
+    #[test]
+    fn add_with_carry_flags() {
+        let cases = [
+            (0, 0, false, 0, "nZcv"),
+            (0, 0, true, 1, "nzcv"),
+            (0xFFFF_FFFF, 0, true, 0, "nZCv"),
+            (0xFFFF_FFFF, 0xFFFF_FFFF, true, 0xFFFF_FFFF, "NzCv"),
+            (0x7FFF_FFFF, 0, true, 0x8000_0000, "NzcV"),
+            (0x7FFF_FFFF, 1, false, 0x8000_0000, "NzcV"),
+            (0x8000_0000, 0x8000_0000, false, 0, "nZCV"),
+            // The partial sum overflows, the whole one doesn't: -1 + MIN + 1 = MIN.
+            (0xFFFF_FFFF, 0x8000_0000, true, 0x8000_0000, "NzCv"),
+            (0x8000_0000, 0xFFFF_FFFF, false, 0x7FFF_FFFF, "nzCV"),
+        ];
+
+        for (first, second, carry, expected, expected_flags) in cases {
+            let result = add_with_carry(first, second, carry);
+            assert_eq!(
+                (result.result, flags(&result).as_str()),
+                (expected, expected_flags),
+                "0x{first:08X} + 0x{second:08X} + {carry}"
+            );
+        }
+    }
+
+    #[test]
+    fn sub_with_carry_flags() {
+        let cases = [
+            // With the carry set there's no borrow, it's a plain subtraction.
+            (0, 0, true, 0, "nZCv"),
+            // The borrow of a clear carry makes it -1, the first subtraction doesn't borrow.
+            (0, 0, false, 0xFFFF_FFFF, "Nzcv"),
+            (1, 0, false, 0, "nZCv"),
+            (5, 3, true, 2, "nzCv"),
+            (3, 5, true, 0xFFFF_FFFE, "Nzcv"),
+            (0x8000_0000, 1, true, 0x7FFF_FFFF, "nzCV"),
+            (0x8000_0000, 0, false, 0x7FFF_FFFF, "nzCV"),
+            (0x7FFF_FFFF, 0xFFFF_FFFF, true, 0x8000_0000, "NzcV"),
+            (0xFFFF_FFFF, 0xFFFF_FFFF, false, 0xFFFF_FFFF, "Nzcv"),
+        ];
+
+        for (first, second, carry, expected, expected_flags) in cases {
+            let result = sub_with_carry(first, second, carry);
+            assert_eq!(
+                (result.result, flags(&result).as_str()),
+                (expected, expected_flags),
+                "0x{first:08X} - 0x{second:08X} - !{carry}"
+            );
+        }
+    }
+
+    /// Values where the shifts and the flags change behavior, then random ones. The seed
+    /// is fixed so a failure can be reproduced.
+    fn operands(random: usize) -> Vec<u32> {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0x00C1_E3E7);
+        let edges = [0, 1, 2, 0x7FFF_FFFF, 0x8000_0000, 0x8000_0001, 0xFFFF_FFFF];
+
+        edges
+            .into_iter()
+            .chain((0..random).map(|_| rng.gen()))
+            .collect()
+    }
+
+    /// `shift` done the long way, in 64 bits: the value is shifted as a whole and the
+    /// carry is the bit next to the 32 kept. An amount of 0 is LSR#32, ASR#32 and RRX.
+    fn reference_shift(kind: ShiftKind, amount: u32, rm: u32, carry: bool) -> (u32, bool) {
+        match kind {
+            ShiftKind::Lsl if amount == 0 => (rm, carry),
+            ShiftKind::Lsl => {
+                let wide = u64::from(rm).checked_shl(amount).unwrap_or(0);
+                (wide as u32, wide.get_bit(32))
+            }
+            ShiftKind::Lsr => {
+                let amount = if amount == 0 { 32 } else { amount };
+                let wide = (u64::from(rm) << 32).checked_shr(amount).unwrap_or(0);
+                ((wide >> 32) as u32, wide.get_bit(31))
+            }
+            ShiftKind::Asr => {
+                let amount = if amount == 0 { 32 } else { amount.min(63) };
+                let wide = ((i64::from(rm as i32) << 32) >> amount) as u64;
+                ((wide >> 32) as u32, wide.get_bit(31))
+            }
+            ShiftKind::Ror if amount == 0 => ((rm >> 1) | (u32::from(carry) << 31), rm.get_bit(0)),
+            ShiftKind::Ror => {
+                // One bit at a time, the carry is the last bit moved around.
+                let (mut result, mut carry) = (rm, carry);
+                for _ in 0..amount {
+                    carry = result.get_bit(0);
+                    result = result.rotate_right(1);
+                }
+                (result, carry)
+            }
+        }
+    }
+
+    #[test]
+    fn shift_matches_reference() {
+        for rm in operands(24) {
+            for kind in [
+                ShiftKind::Lsl,
+                ShiftKind::Lsr,
+                ShiftKind::Asr,
+                ShiftKind::Ror,
+            ] {
+                for amount in 0..=255 {
+                    for carry in [false, true] {
+                        let result = shift(kind, amount, rm, carry);
+                        assert_eq!(
+                            (result.result, result.carry),
+                            reference_shift(kind, amount, rm, carry),
+                            "{kind:?}#{amount} of 0x{rm:08X} with carry {carry}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shift_special_amounts() {
+        for rm in operands(64) {
+            for carry in [false, true] {
+                // LSR#0 encodes LSR#32
+                let lsr_0 = shift(ShiftKind::Lsr, 0, rm, carry);
+                let lsr_32 = shift(ShiftKind::Lsr, 32, rm, carry);
+                assert_eq!((lsr_0.result, lsr_0.carry), (0, rm.get_bit(31)));
+                assert_eq!((lsr_0.result, lsr_0.carry), (lsr_32.result, lsr_32.carry));
+
+                // ROR by more than 32 is ROR by the amount minus 32, a multiple of 32 is ROR#32
+                for amount in 33..=255 {
+                    let normalized = match amount % 32 {
+                        0 => 32,
+                        amount => amount,
+                    };
+                    let ror = shift(ShiftKind::Ror, amount, rm, carry);
+                    let expected = shift(ShiftKind::Ror, normalized, rm, carry);
+                    assert_eq!(
+                        (ror.result, ror.carry),
+                        (expected.result, expected.carry),
+                        "ROR#{amount} of 0x{rm:08X}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn add_and_sub_match_reference() {
+        let operands = operands(256);
+
+        for (&first, &second) in operands.iter().zip(operands.iter().rev()) {
+            for carry in [false, true] {
+                let sum = i64::from(first as i32) + i64::from(second as i32) + i64::from(carry);
+                let wide = u64::from(first) + u64::from(second) + u64::from(carry);
+                let result = add_with_carry(first, second, carry);
+                assert_eq!(
+                    (result.result, result.carry, result.overflow),
+                    (wide as u32, wide > 0xFFFF_FFFF, i32::try_from(sum).is_err()),
+                    "0x{first:08X} + 0x{second:08X} + {carry}"
+                );
+                assert_eq!(
+                    (result.sign, result.zero),
+                    (result.result.get_bit(31), result.result == 0)
+                );
+
+                let borrow = u64::from(!carry);
+                let difference =
+                    i64::from(first as i32) - i64::from(second as i32) - i64::from(!carry);
+                let result = sub_with_carry(first, second, carry);
+                assert_eq!(
+                    (result.result, result.carry, result.overflow),
+                    (
+                        first.wrapping_sub(second).wrapping_sub(u32::from(!carry)),
+                        u64::from(first) >= u64::from(second) + borrow,
+                        i32::try_from(difference).is_err()
+                    ),
+                    "0x{first:08X} - 0x{second:08X} - !{carry}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_arithmetic_instruction() {
         let alu_op_code = 2;
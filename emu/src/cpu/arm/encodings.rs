@@ -0,0 +1,139 @@
+use crate::cpu::encoding::Encoding;
+
+/// Formats of the ARM instructions, as they are told apart by the decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmFormat {
+    BranchAndExchange,
+    SingleDataSwap,
+    MultiplyLong,
+    Multiply,
+    HalfwordDataTransferRegister,
+    HalfwordDataTransferImmediate,
+    Undefined,
+    SoftwareInterrupt,
+    CoprocessorRegisterTransfer,
+    CoprocessorDataOperation,
+    CoprocessorDataTransfer,
+    BlockDataTransfer,
+    Branch,
+    SingleDataTransfer,
+    Mrs,
+    Msr,
+    MsrFlg,
+    DataProcessing,
+}
+
+/// Formats without the layout of their fields.
+const NO_LAYOUT: &str = "FMT: |_Cond__|";
+
+/// Every ARM format, in order of priority: the encodings of the first ones overlap the
+/// ones after (eg. a multiply is a data processing with `1001` in bits 4-7).
+pub const ARM_ENCODINGS: [Encoding<ArmFormat>; 18] = [
+    Encoding::new(
+        ArmFormat::BranchAndExchange,
+        "cccc_0001_0010_1111_1111_1111_0001_nnnn",
+        "FMT: |_Cond__|0_0_0_1|0_0_1_0|1_1_1_1|1_1_1_1|1_1_1_1|0_0_0_1|__Rn___|",
+    ),
+    Encoding::new(
+        ArmFormat::SingleDataSwap,
+        "cccc_0001_0b00_nnnn_dddd_0000_1001_mmmm",
+        NO_LAYOUT,
+    ),
+    Encoding::new(
+        ArmFormat::MultiplyLong,
+        "cccc_0000_1uas_hhhh_llll_ssss_1001_mmmm",
+        "FMT: |_Cond__|0_0_0|__code__|S|_RdHi_|_RdLo_|_Rs__|1_0_0_1|_Rm__|",
+    ),
+    Encoding::new(
+        ArmFormat::Multiply,
+        "cccc_0000_00as_dddd_nnnn_ssss_1001_mmmm",
+        "FMT: |_Cond__|0_0_0|__code__|S|_Rd__|_Rn__|_Rs__|1_0_0_1|__Rm___|",
+    ),
+    Encoding::new(
+        ArmFormat::HalfwordDataTransferRegister,
+        "cccc_000p_u0wl_nnnn_dddd_xxxx_1sh1_mmmm",
+        "FMT: |_Cond__|0_0_0|P|U|0|W|L|__Rn___|__Rd___|0_0_0_0|1|S|H|1|__Rm___|",
+    ),
+    Encoding::new(
+        ArmFormat::HalfwordDataTransferImmediate,
+        "cccc_000p_u1wl_nnnn_dddd_oooo_1sh1_oooo",
+        "FMT: |_Cond__|0_0_0|P|U|1|W|L|__Rn___|__Rd___|_Offset|1|S|H|1|_Offset|",
+    ),
+    Encoding::new(
+        ArmFormat::Undefined,
+        "cccc_011x_xxxx_xxxx_xxxx_xxxx_xxx1_xxxx",
+        NO_LAYOUT,
+    ),
+    Encoding::new(
+        ArmFormat::SoftwareInterrupt,
+        "cccc_1111_xxxx_xxxx_xxxx_xxxx_xxxx_xxxx",
+        NO_LAYOUT,
+    ),
+    Encoding::new(
+        ArmFormat::CoprocessorRegisterTransfer,
+        "cccc_1110_oool_nnnn_dddd_pppp_iii1_mmmm",
+        NO_LAYOUT,
+    ),
+    Encoding::new(
+        ArmFormat::CoprocessorDataOperation,
+        "cccc_1110_oooo_nnnn_dddd_pppp_iii0_mmmm",
+        NO_LAYOUT,
+    ),
+    Encoding::new(
+        ArmFormat::CoprocessorDataTransfer,
+        "cccc_110p_unwl_nnnn_dddd_pppp_oooo_oooo",
+        "FMT: |_Cond__|1_1_0|P|U|N|W|L|__Rn___|__CRd__|__Cp#__|____Offset_____|",
+    ),
+    Encoding::new(
+        ArmFormat::BlockDataTransfer,
+        "cccc_100p_uswl_nnnn_rrrr_rrrr_rrrr_rrrr",
+        "FMT: |_Cond__|1_0_0|P|U|S|W|L|__Rn___|_____________Reg_List__________|",
+    ),
+    Encoding::new(
+        ArmFormat::Branch,
+        "cccc_101l_oooo_oooo_oooo_oooo_oooo_oooo",
+        "FMT: |_Cond__|1_0_1|L|______________________Offset___________________|",
+    ),
+    Encoding::new(
+        ArmFormat::SingleDataTransfer,
+        "cccc_01ip_ubwl_nnnn_dddd_oooo_oooo_oooo",
+        "FMT: |_Cond__|0_1|I|P|U|B|W|L|__Rn___|__Rd___|________Offset_________|",
+    ),
+    Encoding::new(
+        ArmFormat::Mrs,
+        "cccc_0001_0p00_1111_dddd_0000_0000_0000",
+        "FMT: |_Cond__|0_0_0_1_0|P|0_0_1_1_1_1|_Rd__|0_0_0_0_0_0_0_0_0_0_0_0_0|",
+    ),
+    Encoding::new(
+        ArmFormat::Msr,
+        "cccc_0001_0p10_1001_1111_0000_0000_mmmm",
+        "FMT: |_Cond__|0_0_0_1_0|P|1_0_1_0_0_1_1_1_1_1_0_0_0_0_0_0_0_0|__Rm___|",
+    ),
+    Encoding::new(
+        ArmFormat::MsrFlg,
+        "cccc_00i1_0p10_1000_1111_oooo_oooo_oooo",
+        "FMT: |_Cond__|0_0|I|1_0|P|1_0_1_0_0_0_1_1_1_1|_Operand____|",
+    ),
+    Encoding::new(
+        ArmFormat::DataProcessing,
+        "cccc_00io_ooos_nnnn_dddd_oooo_oooo_oooo",
+        "FMT: |_Cond__|0_0|I|_code__|S|__Rn___|__Rd___|_______operand2________|",
+    ),
+];
+
+impl ArmFormat {
+    /// The format of `op_code`, `None` if no encoding matches it.
+    #[must_use]
+    pub fn decode(op_code: u32) -> Option<Self> {
+        crate::cpu::encoding::find(&ARM_ENCODINGS, op_code).map(|encoding| encoding.format)
+    }
+
+    /// Names of the fields of the format.
+    #[must_use]
+    pub fn layout(self) -> &'static str {
+        ARM_ENCODINGS
+            .iter()
+            .find(|encoding| encoding.format == self)
+            .map_or(NO_LAYOUT, |encoding| encoding.layout)
+    }
+}
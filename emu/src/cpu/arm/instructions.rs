@@ -2,6 +2,7 @@ use crate::bitwise::Bits;
 use crate::cpu::arm::alu_instruction::{AluSecondOperandInfo, ArmModeAluInstr, ShiftOperator};
 use crate::cpu::arm7tdmi::HalfwordTransferKind;
 use crate::cpu::condition::Condition;
+use crate::cpu::dispatch;
 use crate::cpu::flags::{
     HalfwordDataTransferOffsetKind, Indexing, LoadStoreKind, Offsetting, OperandKind,
     ReadWriteKind, ShiftKind,
@@ -10,6 +11,7 @@ use logger::log;
 use serde::{Deserialize, Serialize};
 
 use super::alu_instruction::{PsrKind, PsrOpKind};
+use super::encodings::ArmFormat;
 
 /// Possible operation on transfer data.
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
@@ -64,7 +66,7 @@ impl std::fmt::Display for SingleDataTransferOffsetInfo {
             } => {
                 write!(f, "{reg_offset}, {shift_kind} #{shift_amount}")?;
             }
-        };
+        }
 
         Ok(())
     }
@@ -103,7 +105,13 @@ pub enum ArmModeInstruction {
         psr_kind: PsrKind,
         kind: PsrOpKind,
     },
-    SingleDataSwap,
+    SingleDataSwap {
+        condition: Condition,
+        quantity: ReadWriteKind,
+        rn: u32,
+        rd: u32,
+        rm: u32,
+    },
     BranchAndExchange {
         condition: Condition,
         register: usize,
@@ -160,7 +168,11 @@ pub enum ArmModeInstruction {
     },
     CoprocessorDataOperation,
     CoprocessorRegisterTransfer,
-    SoftwareInterrupt,
+    SoftwareInterrupt {
+        condition: Condition,
+        /// Ignored by the CPU, the BIOS uses bits 16-23 as function number.
+        comment: u32,
+    },
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -190,7 +202,7 @@ impl std::fmt::Display for ArmModeMultiplyLongVariant {
 
 impl From<u32> for ArmModeMultiplyVariant {
     fn from(op_code: u32) -> Self {
-        let mul_op_code: u32 = op_code.get_bits(21..=24);
+        let mul_op_code: u32 = op_code.get_bits_const::<21, 24>();
         match mul_op_code {
             0b0000 => Self::Mul,
             0b0001 => Self::Mla,
@@ -201,7 +213,7 @@ impl From<u32> for ArmModeMultiplyVariant {
 
 impl From<u32> for ArmModeMultiplyLongVariant {
     fn from(op_code: u32) -> Self {
-        let mul_op_code: u32 = op_code.get_bits(21..=24);
+        let mul_op_code: u32 = op_code.get_bits_const::<21, 24>();
         match mul_op_code {
             0b0100 => Self::Umull,
             0b0101 => Self::Umlal,
@@ -296,7 +308,19 @@ impl ArmModeInstruction {
                     format!("MSR{condition} {psr_kind}_flg, {operand}")
                 }
             },
-            Self::SingleDataSwap => panic!("SingleDataSwap not implemented"),
+            Self::SingleDataSwap {
+                condition,
+                quantity,
+                rn,
+                rd,
+                rm,
+            } => {
+                let b = match quantity {
+                    ReadWriteKind::Word => "",
+                    ReadWriteKind::Byte => "B",
+                };
+                format!("SWP{condition}{b} R{rd}, R{rm}, [R{rn}]")
+            }
             Self::BranchAndExchange {
                 condition,
                 register,
@@ -374,7 +398,7 @@ impl ArmModeInstruction {
 
                 format!("{op}{condition}{b} R{rd}, {offset_info}")
             }
-            Self::Undefined => panic!("Undefined not implemented"),
+            Self::Undefined => "UNDEFINED".to_owned(),
             Self::BlockDataTransfer {
                 condition,
                 indexing,
@@ -445,9 +469,12 @@ impl ArmModeInstruction {
                 // FIXME: Finish address
                 format!("{op}{condition}{long_transfer} p{cp_number},{crd},{address:08X}")
             }
-            Self::CoprocessorDataOperation => panic!("CoprocessorDataOperation not implemented"),
-            Self::CoprocessorRegisterTransfer => panic!("CoprocessorRegisterTransfer not implemented"),
-            Self::SoftwareInterrupt => panic!("SoftwareInterrupt not implemented"),
+            Self::CoprocessorDataOperation | Self::CoprocessorRegisterTransfer => {
+                self.kind().to_owned()
+            }
+            Self::SoftwareInterrupt { condition, comment } => {
+                format!("SWI{condition} #0x{comment:06X}")
+            }
         }
     }
 }
@@ -455,251 +482,405 @@ impl ArmModeInstruction {
 impl From<u32> for ArmModeInstruction {
     #[allow(clippy::too_many_lines)]
     fn from(op_code: u32) -> Self {
-        let condition = Condition::from(op_code.get_bits(28..=31) as u8);
-        // NOTE: The order is based on how many bits are already know at decoding time.
-        // It can happen `op_code` coalesced into one/two or more than two possible solution, that's because
-        // we tried to order with this priority.
-        if op_code.get_bits(4..=27) == 0b0001_0010_1111_1111_1111_0001 {
-            let register = op_code.get_bits(0..=3) as usize;
-            Self::BranchAndExchange {
-                condition,
-                register,
+        let condition = Condition::from(op_code.get_bits_u8(28..=31));
+
+        let Some(format) = dispatch::arm_format(op_code) else {
+            log("not identified instruction");
+            unimplemented!()
+        };
+
+        match format {
+            ArmFormat::BranchAndExchange => {
+                let register = op_code.get_bits_const::<0, 3>() as usize;
+                Self::BranchAndExchange {
+                    condition,
+                    register,
+                }
             }
-        } else if op_code.get_bits(23..=27) == 0b00010
-            && op_code.get_bits(20..=21) == 0b00
-            && op_code.get_bits(4..=11) == 0b0000_1001
-        {
-            Self::SingleDataSwap
-        } else if op_code.get_bits(23..=27) == 0b00001 && op_code.get_bits(4..=7) == 0b1001 {
-            let variant = ArmModeMultiplyLongVariant::from(op_code);
+            ArmFormat::SingleDataSwap => Self::SingleDataSwap {
+                condition,
+                quantity: op_code.get_bit(22).into(),
+                rn: op_code.get_bits(16..=19),
+                rd: op_code.get_bits(12..=15),
+                rm: op_code.get_bits(0..=3),
+            },
+            ArmFormat::MultiplyLong => {
+                let variant = ArmModeMultiplyLongVariant::from(op_code);
 
-            let should_set_codes = op_code.get_bit(20);
+                let should_set_codes = op_code.get_bit(20);
 
-            let rm_operand_register = op_code.get_bits(0..=3);
-            let rs_operand_register = op_code.get_bits(8..=11);
-            let rdlo_destination_register = op_code.get_bits(12..=15);
-            let rdhi_destination_register = op_code.get_bits(16..=19);
+                let rm_operand_register = op_code.get_bits_const::<0, 3>();
+                let rs_operand_register = op_code.get_bits_const::<8, 11>();
+                let rdlo_destination_register = op_code.get_bits_const::<12, 15>();
+                let rdhi_destination_register = op_code.get_bits_const::<16, 19>();
 
-            Self::MultiplyLong {
-                variant,
-                condition,
-                should_set_codes,
-                rdhi_destination_register,
-                rdlo_destination_register,
-                rm_operand_register,
-                rs_operand_register,
+                Self::MultiplyLong {
+                    variant,
+                    condition,
+                    should_set_codes,
+                    rdhi_destination_register,
+                    rdlo_destination_register,
+                    rm_operand_register,
+                    rs_operand_register,
+                }
             }
-        } else if op_code.get_bits(22..=27) == 0b00_0000 && op_code.get_bits(4..=7) == 0b1001 {
-            let variant = ArmModeMultiplyVariant::from(op_code);
+            ArmFormat::Multiply => {
+                let variant = ArmModeMultiplyVariant::from(op_code);
 
-            let should_set_codes = op_code.get_bit(20);
+                let should_set_codes = op_code.get_bit(20);
 
-            let rm_operand_register = op_code.get_bits(0..=3);
-            let rs_operand_register = op_code.get_bits(8..=11);
-            let rn_accumulate_register = op_code.get_bits(12..=15);
-            let rd_destination_register = op_code.get_bits(16..=19);
+                let rm_operand_register = op_code.get_bits_const::<0, 3>();
+                let rs_operand_register = op_code.get_bits_const::<8, 11>();
+                let rn_accumulate_register = op_code.get_bits_const::<12, 15>();
+                let rd_destination_register = op_code.get_bits_const::<16, 19>();
 
-            Self::Multiply {
-                variant,
-                condition,
-                should_set_codes,
-                rd_destination_register,
-                rn_accumulate_register,
-                rm_operand_register,
-                rs_operand_register,
+                Self::Multiply {
+                    variant,
+                    condition,
+                    should_set_codes,
+                    rd_destination_register,
+                    rn_accumulate_register,
+                    rm_operand_register,
+                    rs_operand_register,
+                }
             }
-        } else if op_code.get_bits(25..=27) == 0b000 && op_code.get_bit(7) && op_code.get_bit(4) {
-            let indexing: Indexing = op_code.get_bit(24).into();
-            let offsetting: Offsetting = op_code.get_bit(23).into();
-            let write_back = op_code.get_bit(21);
-            let load_store_kind: LoadStoreKind = op_code.get_bit(20).into();
-            let base_register = op_code.get_bits(16..=19);
-            let source_destination_register = op_code.get_bits(12..=15);
-            let transfer_kind: HalfwordTransferKind = (op_code.get_bits(5..=6) as u8).into();
-            let operand_kind: OperandKind = op_code.get_bit(22).into();
+            ArmFormat::HalfwordDataTransferRegister | ArmFormat::HalfwordDataTransferImmediate => {
+                let indexing: Indexing = op_code.get_bit(24).into();
+                let offsetting: Offsetting = op_code.get_bit(23).into();
+                let write_back = op_code.get_bit(21);
+                let load_store_kind: LoadStoreKind = op_code.get_bit(20).into();
+                let base_register = op_code.get_bits_const::<16, 19>();
+                let source_destination_register = op_code.get_bits_const::<12, 15>();
+                let transfer_kind: HalfwordTransferKind = op_code.get_bits_u8(5..=6).into();
 
-            Self::HalfwordDataTransfer {
-                condition,
-                indexing,
-                offsetting,
-                write_back,
-                load_store_kind,
-                offset_kind: if operand_kind == OperandKind::Register {
-                    HalfwordDataTransferOffsetKind::Register {
-                        register: op_code.get_bits(0..=3),
-                    }
-                } else {
-                    let immediate_offset_high = op_code.get_bits(8..=11);
-                    let immediate_offset_low = op_code.get_bits(0..=3);
+                Self::HalfwordDataTransfer {
+                    condition,
+                    indexing,
+                    offsetting,
+                    write_back,
+                    load_store_kind,
+                    offset_kind: if format == ArmFormat::HalfwordDataTransferRegister {
+                        HalfwordDataTransferOffsetKind::Register {
+                            register: op_code.get_bits_const::<0, 3>(),
+                        }
+                    } else {
+                        let immediate_offset_high = op_code.get_bits_const::<8, 11>();
+                        let immediate_offset_low = op_code.get_bits_const::<0, 3>();
 
-                    HalfwordDataTransferOffsetKind::Immediate {
-                        offset: (immediate_offset_high << 4) | immediate_offset_low,
-                    }
-                },
-                base_register,
-                source_destination_register,
-                transfer_kind,
+                        HalfwordDataTransferOffsetKind::Immediate {
+                            offset: (immediate_offset_high << 4) | immediate_offset_low,
+                        }
+                    },
+                    base_register,
+                    source_destination_register,
+                    transfer_kind,
+                }
             }
-        } else if op_code.get_bits(25..=27) == 0b011 && op_code.get_bit(4) {
-            log("undefined instruction decode...");
-            Self::Undefined
-        } else if op_code.get_bits(24..=27) == 0b1111 {
-            Self::SoftwareInterrupt
-        } else if op_code.get_bits(24..=27) == 0b1110 && op_code.get_bit(4) {
-            Self::CoprocessorRegisterTransfer
-        } else if op_code.get_bits(24..=27) == 0b1110 && !op_code.get_bit(4) {
-            Self::CoprocessorDataOperation
-        } else if op_code.get_bits(25..=27) == 0b110 {
-            let indexing: Indexing = op_code.get_bit(24).into();
-            let offsetting: Offsetting = op_code.get_bit(23).into();
-            let transfer_length = op_code.get_bit(22);
-            let write_back = op_code.get_bit(21);
-            let load_store: LoadStoreKind = op_code.get_bit(20).into();
-
-            let rn = op_code.get_bits(16..=19);
-            let crd = op_code.get_bits(12..=15);
-            let cp_number = op_code.get_bits(8..=11);
-            let offset = op_code.get_bits(0..=7);
-
-            Self::CoprocessorDataTransfer {
-                condition,
-                indexing,
-                offsetting,
-                transfer_length,
-                write_back,
-                load_store,
-                rn,
-                crd,
-                cp_number,
-                offset,
+            ArmFormat::Undefined => {
+                log("undefined instruction decode...");
+                Self::Undefined
             }
-        } else if op_code.get_bits(25..=27) == 0b100 {
-            let indexing = op_code.get_bit(24).into();
-            let offsetting = op_code.get_bit(23).into();
-            let load_psr = op_code.get_bit(22);
-            let write_back = op_code.get_bit(21);
-            let load_store = op_code.get_bit(20).into();
-            let rn = op_code.get_bits(16..=19);
-            let reg_list = op_code.get_bits(0..=15);
-
-            Self::BlockDataTransfer {
+            ArmFormat::SoftwareInterrupt => Self::SoftwareInterrupt {
                 condition,
-                indexing,
-                offsetting,
-                load_psr,
-                write_back,
-                load_store,
-                rn,
-                register_list: reg_list,
+                comment: op_code.get_bits_const::<0, 23>(),
+            },
+            ArmFormat::CoprocessorRegisterTransfer => Self::CoprocessorRegisterTransfer,
+            ArmFormat::CoprocessorDataOperation => Self::CoprocessorDataOperation,
+            ArmFormat::CoprocessorDataTransfer => {
+                let indexing: Indexing = op_code.get_bit(24).into();
+                let offsetting: Offsetting = op_code.get_bit(23).into();
+                let transfer_length = op_code.get_bit(22);
+                let write_back = op_code.get_bit(21);
+                let load_store: LoadStoreKind = op_code.get_bit(20).into();
+
+                let rn = op_code.get_bits_const::<16, 19>();
+                let crd = op_code.get_bits_const::<12, 15>();
+                let cp_number = op_code.get_bits_const::<8, 11>();
+                let offset = op_code.get_bits_const::<0, 7>();
+
+                Self::CoprocessorDataTransfer {
+                    condition,
+                    indexing,
+                    offsetting,
+                    transfer_length,
+                    write_back,
+                    load_store,
+                    rn,
+                    crd,
+                    cp_number,
+                    offset,
+                }
             }
-        } else if op_code.get_bits(25..=27) == 0b101 {
-            let link = op_code.get_bit(24);
-            let offset = op_code.get_bits(0..=23) << 2;
-            Self::Branch {
-                condition,
-                link,
-                offset,
+            ArmFormat::BlockDataTransfer => {
+                let indexing = op_code.get_bit(24).into();
+                let offsetting = op_code.get_bit(23).into();
+                let load_psr = op_code.get_bit(22);
+                let write_back = op_code.get_bit(21);
+                let load_store = op_code.get_bit(20).into();
+                let rn = op_code.get_bits_const::<16, 19>();
+                let reg_list = op_code.get_bits_const::<0, 15>();
+
+                Self::BlockDataTransfer {
+                    condition,
+                    indexing,
+                    offsetting,
+                    load_psr,
+                    write_back,
+                    load_store,
+                    rn,
+                    register_list: reg_list,
+                }
             }
-        } else if op_code.get_bits(26..=27) == 0b01 {
-            // NOTE: This bit is negated because the meaning is inverted in SingleDataTransfer then other istructions.
-            let op_kind: OperandKind = (!op_code.get_bit(25)).into();
-            let indexing: Indexing = op_code.get_bit(24).into(); // FIXME: should we use this?
-            let offsetting: Offsetting = op_code.get_bit(23).into();
-            let byte_or_word: ReadWriteKind = op_code.into(); // TODO: is this the same for all instruction?
-            let load_store: SingleDataTransferKind = op_code.into(); // TODO: is this the same bit for all instruction?
-            let write_back = op_code.get_bit(21);
-            let rn = op_code.get_bits(16..=19);
-            let rd = op_code.get_bits(12..=15);
-
-            let offset_info = match op_kind {
-                OperandKind::Immediate => {
-                    let offset = op_code.get_bits(0..=11);
-                    SingleDataTransferOffsetInfo::Immediate { offset }
+            ArmFormat::Branch => {
+                let link = op_code.get_bit(24);
+                let offset = op_code.get_bits_const::<0, 23>() << 2;
+                Self::Branch {
+                    condition,
+                    link,
+                    offset,
                 }
-                OperandKind::Register => {
-                    let shift_amount = op_code.get_bits(7..=11);
-                    let shift_kind: ShiftKind = op_code.get_bits(5..=6).into();
-                    let reg_offset = op_code.get_bits(0..=3);
-                    SingleDataTransferOffsetInfo::RegisterImmediate {
-                        shift_amount,
-                        shift_kind,
-                        reg_offset,
+            }
+            ArmFormat::SingleDataTransfer => {
+                // NOTE: This bit is negated because the meaning is inverted in SingleDataTransfer then other istructions.
+                let op_kind: OperandKind = (!op_code.get_bit(25)).into();
+                let indexing: Indexing = op_code.get_bit(24).into(); // FIXME: should we use this?
+                let offsetting: Offsetting = op_code.get_bit(23).into();
+                let byte_or_word: ReadWriteKind = op_code.into(); // TODO: is this the same for all instruction?
+                let load_store: SingleDataTransferKind = op_code.into(); // TODO: is this the same bit for all instruction?
+                let write_back = op_code.get_bit(21);
+                let rn = op_code.get_bits_const::<16, 19>();
+                let rd = op_code.get_bits_const::<12, 15>();
+
+                let offset_info = match op_kind {
+                    OperandKind::Immediate => {
+                        let offset = op_code.get_bits_const::<0, 11>();
+                        SingleDataTransferOffsetInfo::Immediate { offset }
                     }
-                }
-            };
+                    OperandKind::Register => {
+                        let shift_amount = op_code.get_bits_const::<7, 11>();
+                        let shift_kind: ShiftKind = op_code.get_bits_const::<5, 6>().into();
+                        let reg_offset = op_code.get_bits_const::<0, 3>();
+                        SingleDataTransferOffsetInfo::RegisterImmediate {
+                            shift_amount,
+                            shift_kind,
+                            reg_offset,
+                        }
+                    }
+                };
 
-            Self::SingleDataTransfer {
-                condition,
-                kind: load_store,
-                quantity: byte_or_word,
-                write_back,
-                indexing,
-                rd,
-                base_register: rn,
-                offset_info,
-                offsetting,
-            }
-        } else if op_code.get_bits(26..=27) == 0b00 {
-            let alu_instruction = op_code.get_bits(21..=24).into();
-            let set_conditions = op_code.get_bit(20);
-            let rn = op_code.get_bits(16..=19);
-            let op_kind: OperandKind = op_code.get_bit(25).into();
-            let rd = op_code.get_bits(12..=15);
-
-            if matches!(
-                alu_instruction,
-                ArmModeAluInstr::Tst
-                    | ArmModeAluInstr::Teq
-                    | ArmModeAluInstr::Cmp
-                    | ArmModeAluInstr::Cmn
-            ) && !set_conditions
-            {
-                // PSR instruction
-                return Self::PSRTransfer {
+                Self::SingleDataTransfer {
                     condition,
-                    psr_kind: PsrKind::from(op_code.get_bit(22)),
-                    kind: PsrOpKind::from(op_code),
-                };
+                    kind: load_store,
+                    quantity: byte_or_word,
+                    write_back,
+                    indexing,
+                    rd,
+                    base_register: rn,
+                    offset_info,
+                    offsetting,
+                }
             }
+            ArmFormat::Mrs | ArmFormat::Msr | ArmFormat::MsrFlg => Self::PSRTransfer {
+                condition,
+                psr_kind: PsrKind::from(op_code.get_bit(22)),
+                kind: PsrOpKind::from(op_code),
+            },
+            ArmFormat::DataProcessing => {
+                let alu_instruction = op_code.get_bits_const::<21, 24>().into();
+                let set_conditions = op_code.get_bit(20);
+                let rn = op_code.get_bits_const::<16, 19>();
+                let op_kind: OperandKind = op_code.get_bit(25).into();
+                let rd = op_code.get_bits_const::<12, 15>();
 
-            let op2 = match op_kind {
-                OperandKind::Immediate => {
-                    let shift = op_code.get_bits(8..=11) * 2;
-                    let base = op_code.get_bits(0..=7);
-                    AluSecondOperandInfo::Immediate { base, shift }
+                // The tests without S are the PSR transfers, the ones left have no meaning.
+                if matches!(
+                    alu_instruction,
+                    ArmModeAluInstr::Tst
+                        | ArmModeAluInstr::Teq
+                        | ArmModeAluInstr::Cmp
+                        | ArmModeAluInstr::Cmn
+                ) && !set_conditions
+                {
+                    unreachable!()
                 }
-                OperandKind::Register => {
-                    let shift_kind: ShiftKind = op_code.get_bits(5..=6).into();
-                    let shift_by_register_bit = op_code.get_bit(4);
-                    let register = op_code.get_bits(0..=3);
-                    let shift_op = if shift_by_register_bit {
-                        if op_code.get_bit(7) {
-                            todo!("should be zero or need different work")
+
+                let op2 = match op_kind {
+                    OperandKind::Immediate => {
+                        let shift = op_code.get_bits_const::<8, 11>() * 2;
+                        let base = op_code.get_bits_const::<0, 7>();
+                        AluSecondOperandInfo::Immediate { base, shift }
+                    }
+                    OperandKind::Register => {
+                        let shift_kind: ShiftKind = op_code.get_bits_const::<5, 6>().into();
+                        let shift_by_register_bit = op_code.get_bit(4);
+                        let register = op_code.get_bits_const::<0, 3>();
+                        let shift_op = if shift_by_register_bit {
+                            if op_code.get_bit(7) {
+                                todo!("should be zero or need different work")
+                            }
+                            ShiftOperator::Register(op_code.get_bits_const::<8, 11>())
+                        } else {
+                            ShiftOperator::Immediate(op_code.get_bits_const::<7, 11>())
+                        };
+                        AluSecondOperandInfo::Register {
+                            shift_op,
+                            shift_kind,
+                            register,
                         }
-                        ShiftOperator::Register(op_code.get_bits(8..=11))
-                    } else {
-                        ShiftOperator::Immediate(op_code.get_bits(7..=11))
-                    };
-                    AluSecondOperandInfo::Register {
-                        shift_op,
-                        shift_kind,
-                        register,
                     }
+                };
+
+                Self::DataProcessing {
+                    condition,
+                    alu_instruction,
+                    set_conditions,
+                    op_kind,
+                    rn,
+                    destination: rd,
+                    op2,
+                }
+            }
+        }
+    }
+}
+
+/// Every name returned by [`ArmModeInstruction::kind`], in the order of the formats.
+pub const ARM_KINDS: [&str; 45] = [
+    "AND",
+    "EOR",
+    "SUB",
+    "RSB",
+    "ADD",
+    "ADC",
+    "SBC",
+    "RSC",
+    "TST",
+    "TEQ",
+    "CMP",
+    "CMN",
+    "ORR",
+    "MOV",
+    "BIC",
+    "MVN",
+    "MUL",
+    "MLA",
+    "UMULL",
+    "UMLAL",
+    "SMULL",
+    "SMLAL",
+    "MRS",
+    "MSR",
+    "SWP",
+    "BX",
+    "LDRH",
+    "STRH",
+    "LDRSB",
+    "LDRSH",
+    "LDR",
+    "LDRB",
+    "STR",
+    "STRB",
+    "PLD",
+    "Undefined",
+    "LDM",
+    "STM",
+    "B",
+    "BL",
+    "LDC",
+    "STC",
+    "CDP",
+    "MRC/MCR",
+    "SWI",
+];
+
+impl ArmModeInstruction {
+    /// The format of the encoding the instruction was decoded from.
+    pub(crate) const fn format(&self) -> ArmFormat {
+        match self {
+            Self::DataProcessing { .. } => ArmFormat::DataProcessing,
+            Self::Multiply { .. } => ArmFormat::Multiply,
+            Self::MultiplyLong { .. } => ArmFormat::MultiplyLong,
+            Self::PSRTransfer { kind, .. } => match kind {
+                PsrOpKind::Mrs { .. } => ArmFormat::Mrs,
+                PsrOpKind::Msr { .. } => ArmFormat::Msr,
+                PsrOpKind::MsrFlg { .. } => ArmFormat::MsrFlg,
+            },
+            Self::SingleDataSwap { .. } => ArmFormat::SingleDataSwap,
+            Self::BranchAndExchange { .. } => ArmFormat::BranchAndExchange,
+            Self::HalfwordDataTransfer { offset_kind, .. } => match offset_kind {
+                HalfwordDataTransferOffsetKind::Register { .. } => {
+                    ArmFormat::HalfwordDataTransferRegister
                 }
-            };
+                HalfwordDataTransferOffsetKind::Immediate { .. } => {
+                    ArmFormat::HalfwordDataTransferImmediate
+                }
+            },
+            Self::SingleDataTransfer { .. } => ArmFormat::SingleDataTransfer,
+            Self::Undefined => ArmFormat::Undefined,
+            Self::BlockDataTransfer { .. } => ArmFormat::BlockDataTransfer,
+            Self::Branch { .. } => ArmFormat::Branch,
+            Self::CoprocessorDataTransfer { .. } => ArmFormat::CoprocessorDataTransfer,
+            Self::CoprocessorDataOperation => ArmFormat::CoprocessorDataOperation,
+            Self::CoprocessorRegisterTransfer => ArmFormat::CoprocessorRegisterTransfer,
+            Self::SoftwareInterrupt { .. } => ArmFormat::SoftwareInterrupt,
+        }
+    }
 
+    /// Name of the operation, the data processing ones by ALU operation.
+    pub(crate) const fn kind(&self) -> &'static str {
+        match self {
             Self::DataProcessing {
-                condition,
-                alu_instruction,
-                set_conditions,
-                op_kind,
-                rn,
-                destination: rd,
-                op2,
-            }
-        } else {
-            log("not identified instruction");
-            unimplemented!()
+                alu_instruction, ..
+            } => alu_instruction.name(),
+            Self::Multiply { variant, .. } => match variant {
+                ArmModeMultiplyVariant::Mul => "MUL",
+                ArmModeMultiplyVariant::Mla => "MLA",
+            },
+            Self::MultiplyLong { variant, .. } => match variant {
+                ArmModeMultiplyLongVariant::Umull => "UMULL",
+                ArmModeMultiplyLongVariant::Umlal => "UMLAL",
+                ArmModeMultiplyLongVariant::Smull => "SMULL",
+                ArmModeMultiplyLongVariant::Smlal => "SMLAL",
+            },
+            Self::PSRTransfer { kind, .. } => match kind {
+                PsrOpKind::Mrs { .. } => "MRS",
+                PsrOpKind::Msr { .. } | PsrOpKind::MsrFlg { .. } => "MSR",
+            },
+            Self::SingleDataSwap { quantity, .. } => match quantity {
+                ReadWriteKind::Word => "SWP",
+                ReadWriteKind::Byte => "SWPB",
+            },
+            Self::BranchAndExchange { .. } => "BX",
+            Self::HalfwordDataTransfer {
+                load_store_kind: LoadStoreKind::Store,
+                ..
+            } => "STRH",
+            Self::HalfwordDataTransfer { transfer_kind, .. } => match transfer_kind {
+                HalfwordTransferKind::UnsignedHalfwords => "LDRH",
+                HalfwordTransferKind::SignedByte => "LDRSB",
+                HalfwordTransferKind::SignedHalfwords => "LDRSH",
+            },
+            Self::SingleDataTransfer { kind, quantity, .. } => match (kind, quantity) {
+                (SingleDataTransferKind::Ldr, ReadWriteKind::Word) => "LDR",
+                (SingleDataTransferKind::Ldr, ReadWriteKind::Byte) => "LDRB",
+                (SingleDataTransferKind::Str, ReadWriteKind::Word) => "STR",
+                (SingleDataTransferKind::Str, ReadWriteKind::Byte) => "STRB",
+                (SingleDataTransferKind::Pld, _) => "PLD",
+            },
+            Self::Undefined => "Undefined",
+            Self::BlockDataTransfer { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "LDM",
+                LoadStoreKind::Store => "STM",
+            },
+            Self::Branch { link: false, .. } => "B",
+            Self::Branch { link: true, .. } => "BL",
+            Self::CoprocessorDataTransfer { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "LDC",
+                LoadStoreKind::Store => "STC",
+            },
+            Self::CoprocessorDataOperation => "CDP",
+            Self::CoprocessorRegisterTransfer => "MRC/MCR",
+            Self::SoftwareInterrupt { .. } => "SWI",
         }
     }
 }
@@ -864,4 +1045,36 @@ mod tests {
 
         assert_eq!("LDRB R5, 12, LSL #0", output.disassembler());
     }
+
+    #[test]
+    fn decode_software_interrupt() {
+        let output = ArmModeInstruction::from(0xEF06_0000);
+        assert_eq!(
+            ArmModeInstruction::SoftwareInterrupt {
+                condition: Condition::AL,
+                comment: 0x06_0000,
+            },
+            output
+        );
+        assert_eq!("SWI #0x060000", output.disassembler());
+
+        let output = ArmModeInstruction::from(0x1F00_00FA);
+        assert_eq!("SWINE #0x0000FA", output.disassembler());
+    }
+
+    #[test]
+    fn decode_single_data_swap() {
+        let output = ArmModeInstruction::from(0xE142_3091);
+        assert_eq!(
+            ArmModeInstruction::SingleDataSwap {
+                condition: Condition::AL,
+                quantity: ReadWriteKind::Byte,
+                rn: 2,
+                rd: 3,
+                rm: 1,
+            },
+            output
+        );
+        assert_eq!("SWPB R3, R1, [R2]", output.disassembler());
+    }
 }
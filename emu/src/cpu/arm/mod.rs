@@ -5,6 +5,8 @@
 #[allow(clippy::missing_panics_doc)]
 pub mod alu_instruction;
 
+pub mod encodings;
+
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::similar_names)]
 pub mod instructions;
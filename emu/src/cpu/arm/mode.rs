@@ -17,7 +17,7 @@ impl TryFrom<u32> for ArmModeOpcode {
     fn try_from(op_code: u32) -> Result<Self, Self::Error> {
         Ok(Self {
             instruction: ArmModeInstruction::from(op_code),
-            condition: Condition::from(op_code.get_bits(28..=31) as u8),
+            condition: Condition::from(op_code.get_bits_u8(28..=31)),
             raw: op_code,
         })
     }
@@ -39,60 +39,7 @@ impl std::fmt::Display for ArmModeOpcode {
         let bytes_pos1 = "POS: |..3 ..................2 ..................1 ..................0|\n";
         let bytes_pos2 = "     |1_0_9_8_7_6_5_4_3_2_1_0_9_8_7_6_5_4_3_2_1_0_9_8_7_6_5_4_3_2_1_0|\n";
 
-        let op_code_format: &str = match &self.instruction {
-            ArmModeInstruction::DataProcessing { .. } => {
-                "FMT: |_Cond__|0_0|I|_code__|S|__Rn___|__Rd___|_______operand2________|"
-            }
-            ArmModeInstruction::Multiply { .. } => {
-                "FMT: |_Cond__|0_0_0|__code__|S|_Rd__|_Rn__|_Rs__|1_0_0_1|__Rm___|"
-            }
-            ArmModeInstruction::MultiplyLong { .. } => {
-                "FMT: |_Cond__|0_0_0|__code__|S|_RdHi_|_RdLo_|_Rs__|1_0_0_1|_Rm__|"
-            }
-            ArmModeInstruction::PSRTransfer {
-                condition: _,
-                psr_kind: _,
-                kind,
-            } => match kind {
-                crate::cpu::arm::alu_instruction::PsrOpKind::Mrs { .. } => {
-                    "FMT: |_Cond__|0_0_0_1_0|P|0_0_1_1_1_1|_Rd__|0_0_0_0_0_0_0_0_0_0_0_0_0|"
-                }
-                crate::cpu::arm::alu_instruction::PsrOpKind::Msr { .. } => {
-                    "FMT: |_Cond__|0_0_0_1_0|P|1_0_1_0_0_1_1_1_1_1_0_0_0_0_0_0_0_0|__Rm___|"
-                }
-                crate::cpu::arm::alu_instruction::PsrOpKind::MsrFlg { .. } => {
-                    "FMT: |_Cond__|0_0|I|1_0|P|1_0_1_0_0_0_1_1_1_1|_Operand____|"
-                }
-            },
-            ArmModeInstruction::BranchAndExchange { .. } => {
-                "FMT: |_Cond__|0_0_0_1|0_0_1_0|1_1_1_1|1_1_1_1|1_1_1_1|0_0_0_1|__Rn___|"
-            }
-            ArmModeInstruction::HalfwordDataTransfer { offset_kind, .. } => match offset_kind {
-                crate::cpu::flags::HalfwordDataTransferOffsetKind::Register { .. } => {
-                    "FMT: |_Cond__|0_0_0|P|U|0|W|L|__Rn___|__Rd___|0_0_0_0|1|S|H|1|__Rm___|"
-                }
-                crate::cpu::flags::HalfwordDataTransferOffsetKind::Immediate { .. } => {
-                    "FMT: |_Cond__|0_0_0|P|U|1|W|L|__Rn___|__Rd___|_Offset|1|S|H|1|_Offset|"
-                }
-            },
-            ArmModeInstruction::SingleDataTransfer { .. } => {
-                "FMT: |_Cond__|0_1|I|P|U|B|W|L|__Rn___|__Rd___|________Offset_________|"
-            }
-            ArmModeInstruction::BlockDataTransfer { .. } => {
-                "FMT: |_Cond__|1_0_0|P|U|S|W|L|__Rn___|_____________Reg_List__________|"
-            }
-            ArmModeInstruction::Branch { .. } => {
-                "FMT: |_Cond__|1_0_1|L|______________________Offset___________________|"
-            }
-            ArmModeInstruction::CoprocessorDataTransfer { .. } => {
-                "FMT: |_Cond__|1_1_0|P|U|N|W|L|__Rn___|__CRd__|__Cp#__|____Offset_____|"
-            }
-            ArmModeInstruction::Undefined
-            | ArmModeInstruction::SingleDataSwap
-            | ArmModeInstruction::CoprocessorDataOperation
-            | ArmModeInstruction::CoprocessorRegisterTransfer
-            | ArmModeInstruction::SoftwareInterrupt => "FMT: |_Cond__|",
-        };
+        let op_code_format = self.instruction.format().layout();
 
         let mut raw_bits = String::new();
         for i in format!("{:#034b}", self.raw).chars().skip(2) {
@@ -1,6 +1,8 @@
 use crate::bitwise::Bits;
+use crate::bus::Bus;
 use crate::cpu::arm::alu_instruction::{
-    shift, AIKind, ArithmeticOpResult, ArmModeAluInstr, Kind, PsrOpKind,
+    add_with_carry, shift, sub_with_carry, AIKind, ArithmeticOpResult, ArmModeAluInstr, Kind,
+    PsrOpKind,
 };
 use crate::cpu::arm::instructions::{
     ArmModeMultiplyLongVariant, ArmModeMultiplyVariant, SingleDataTransferKind,
@@ -8,20 +10,21 @@ use crate::cpu::arm::instructions::{
 };
 use crate::cpu::arm::mode::ArmModeOpcode;
 use crate::cpu::arm7tdmi::{Arm7tdmi, HalfwordTransferKind};
+use crate::cpu::call_stack::FrameKind;
 use crate::cpu::cpu_modes::Mode;
 use crate::cpu::flags::{
     HalfwordDataTransferOffsetKind, Indexing, LoadStoreKind, Offsetting, OperandKind,
     ReadWriteKind, ShiftKind,
 };
-use crate::cpu::psr::CpuState;
-use crate::cpu::registers::REG_PROGRAM_COUNTER;
+use crate::cpu::psr::{CpuState, Psr};
+use crate::cpu::registers::{REG_LR, REG_PROGRAM_COUNTER};
 use logger::log;
 
 use super::alu_instruction::{AluSecondOperandInfo, PsrKind};
 
 pub const SIZE_OF_INSTRUCTION: u32 = 4;
 
-impl Arm7tdmi {
+impl<B: Bus> Arm7tdmi<B> {
     pub fn data_processing(
         &mut self,
         op_code: ArmModeOpcode, // FIXME: This parameter will be remove after change `psr_transfer`.
@@ -87,7 +90,24 @@ impl Arm7tdmi {
             ArmModeAluInstr::Mvn => {
                 self.mvn(destination.try_into().unwrap(), op2, set_conditions);
             }
-        };
+        }
+
+        let is_test = matches!(
+            alu_instruction,
+            ArmModeAluInstr::Teq
+                | ArmModeAluInstr::Cmn
+                | ArmModeAluInstr::Cmp
+                | ArmModeAluInstr::Tst
+        );
+
+        // TSTP/TEQP/CMPP/CMNP: a leftover of the 26-bit cores where R15 held the PSR.
+        // In User and System mode there's no SPSR, only the flags they just set are kept.
+        if is_test
+            && destination == REG_PROGRAM_COUNTER
+            && matches!(self.cpsr.mode(), Mode::User | Mode::System)
+        {
+            return;
+        }
 
         if set_conditions && destination == REG_PROGRAM_COUNTER {
             // We move current SPSR into the CPSR.
@@ -106,14 +126,7 @@ impl Arm7tdmi {
 
         // Test instructions do not modify destination so we don't flush pipeline even if
         // destination == R15
-        if !matches!(
-            alu_instruction,
-            ArmModeAluInstr::Teq
-                | ArmModeAluInstr::Cmn
-                | ArmModeAluInstr::Cmp
-                | ArmModeAluInstr::Tst
-        ) && destination == REG_PROGRAM_COUNTER
-        {
+        if !is_test && destination == REG_PROGRAM_COUNTER {
             self.flush_pipeline();
         }
     }
@@ -147,9 +160,10 @@ impl Arm7tdmi {
                     "PSR transfer should not use R15 as source/destination"
                 );
 
-                let rm = self
-                    .registers
-                    .register_at(source_register.try_into().unwrap());
+                let rm = Psr::from(
+                    self.registers
+                        .register_at(source_register.try_into().unwrap()),
+                );
 
                 let current_mode = self.cpsr.mode();
 
@@ -159,35 +173,28 @@ impl Arm7tdmi {
                         PsrKind::Spsr => &mut self.spsr,
                     };
 
-                    // Setting flags
-                    psr.set_sign_flag(rm.get_bit(31));
-                    psr.set_zero_flag(rm.get_bit(30));
-                    psr.set_carry_flag(rm.get_bit(29));
-                    psr.set_overflow_flag(rm.get_bit(28));
+                    psr.set_flags_from(rm);
 
                     // In User mode we can only set the flags so we don't touch the other bits
                     if current_mode != Mode::User {
-                        psr.set_irq_disable(rm.get_bit(7));
-                        psr.set_fiq_disable(rm.get_bit(6));
-
                         // Documentation says that software should never touch T (state) bit
                         // Should we set it? I guess software are written in order to not switch this bit
                         // but who knows?
-                        if psr.state_bit() != rm.get_bit(5) {
+                        if psr.state_bit() != rm.state_bit() {
                             log("WARNING: Changing state bit (arm/thumb) in MSR instruction. This should not happen.");
                         }
-                        psr.set_state_bit(rm.get_bit(5));
+                        psr.set_control_from(rm);
                     }
                 }
 
                 // If we're modifying CPSR we need to be sure we're not in User mode.
                 // Since in User mode we can only modify flags.
                 if psr_kind == PsrKind::Cpsr && self.cpsr.mode() != Mode::User {
-                    self.swap_mode(&rm.get_bits(0..=4).try_into().unwrap());
+                    self.swap_mode(&rm.mode());
                 } else if psr_kind == PsrKind::Spsr {
                     // If we're modifying SPSR we're sure we're not in System|User (checked before)
                     // We use `set_mode_raw` since the BIOS sometimes writes 0 in the SPSR.
-                    self.spsr.set_mode_raw(rm.get_bits(0..=4));
+                    self.spsr.set_mode_raw(rm.mode_raw());
                 }
             }
             PsrOpKind::MsrFlg { operand } => {
@@ -205,11 +212,7 @@ impl Arm7tdmi {
                     PsrKind::Spsr => &mut self.spsr,
                 };
 
-                // Setting flags
-                psr.set_sign_flag(op.get_bit(31));
-                psr.set_zero_flag(op.get_bit(30));
-                psr.set_carry_flag(op.get_bit(29));
-                psr.set_overflow_flag(op.get_bit(28));
+                psr.set_flags_from(op.into());
             }
         }
     }
@@ -335,42 +338,17 @@ impl Arm7tdmi {
     }
 
     pub fn adc(&mut self, rd: usize, rn: u32, op2: u32, s: bool) {
-        let carry: u32 = self.cpsr.carry_flag().into();
-
-        let first_op_result = Self::add_inner_op(rn, op2);
-        let second_op_result = Self::add_inner_op(first_op_result.result, carry);
-
-        let result_op = ArithmeticOpResult {
-            result: second_op_result.result,
-            carry: first_op_result.carry || second_op_result.carry,
-            overflow: first_op_result.overflow || second_op_result.overflow,
-            sign: second_op_result.sign,
-            zero: second_op_result.zero,
-        };
+        let result = add_with_carry(rn, op2, self.cpsr.carry_flag());
 
-        self.registers.set_register_at(rd, result_op.result);
+        self.registers.set_register_at(rd, result.result);
 
         if s {
-            self.cpsr.set_flags(&result_op);
+            self.cpsr.set_flags(&result);
         }
     }
 
     pub fn sbc(&mut self, rd: usize, rn: u32, op2: u32, s: bool) {
-        let carry: u32 = self.cpsr.carry_flag().into();
-
-        let first_op_result = Self::sub_inner_op(rn, op2);
-        let second_op_result = Self::add_inner_op(first_op_result.result, carry);
-        let third_op_result = Self::sub_inner_op(second_op_result.result, 1);
-
-        let result = ArithmeticOpResult {
-            result: third_op_result.result,
-            carry: first_op_result.carry || second_op_result.carry || third_op_result.carry,
-            overflow: first_op_result.overflow
-                || second_op_result.overflow
-                || third_op_result.overflow,
-            sign: third_op_result.sign,
-            zero: third_op_result.zero,
-        };
+        let result = sub_with_carry(rn, op2, self.cpsr.carry_flag());
 
         self.registers.set_register_at(rd, result.result);
 
@@ -410,45 +388,12 @@ impl Arm7tdmi {
 
     #[must_use]
     pub fn add_inner_op(first_op: u32, second_op: u32) -> ArithmeticOpResult {
-        // we do the sum in 64bits so that the 32nd bit is the carry
-        let result_and_carry = (first_op as u64).wrapping_add(second_op as u64);
-        let result = result_and_carry as u32;
-
-        let sign_op1 = first_op.get_bit(31);
-        let sign_op2 = second_op.get_bit(31);
-        let sign_r = result.get_bit(31);
-
-        let carry = (result_and_carry & 0x0001_0000_0000) >> 32 == 1;
-
-        // overflow only occurs when operands have the same sign and result has the opposite one
-        let same_sign = sign_op1 == sign_op2;
-
-        ArithmeticOpResult {
-            result,
-            carry,
-            overflow: same_sign && (sign_op1 != sign_r),
-            sign: result.get_bit(31),
-            zero: result == 0,
-        }
+        add_with_carry(first_op, second_op, false)
     }
 
     #[must_use]
     pub fn sub_inner_op(first_op: u32, second_op: u32) -> ArithmeticOpResult {
-        let result = first_op.wrapping_sub(second_op);
-
-        let sign_op1 = first_op.get_bit(31);
-        let sign_op2 = second_op.get_bit(31);
-        let sign_r = result.get_bit(31);
-
-        let different_sign = sign_op1 != sign_op2;
-
-        ArithmeticOpResult {
-            result,
-            carry: first_op >= second_op,
-            overflow: different_sign && sign_op2 == sign_r,
-            sign: result.get_bit(31),
-            zero: result == 0,
-        }
+        sub_with_carry(first_op, second_op, true)
     }
 
     fn add(&mut self, rd: usize, rn: u32, op2: u32, s: bool) {
@@ -535,6 +480,11 @@ impl Arm7tdmi {
 
     pub fn branch_and_exchange(&mut self, register: usize) {
         let mut rn = self.registers.register_at(register);
+        let next_instruction =
+            (self.registers.program_counter() as u32).wrapping_sub(SIZE_OF_INSTRUCTION);
+        // `MOV LR, PC` followed by `BX` is how functions are called through a register.
+        let is_call = register != REG_LR && self.registers.register_at(REG_LR) == next_instruction;
+
         let state: CpuState = rn.get_bit(0).into();
         self.cpsr.set_cpu_state(state);
 
@@ -549,6 +499,10 @@ impl Arm7tdmi {
         self.registers.set_program_counter(rn);
 
         self.flush_pipeline();
+
+        if is_call {
+            self.call_stack.push(rn, next_instruction, FrameKind::Call);
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -606,24 +560,24 @@ impl Arm7tdmi {
 
                 match transfer_kind {
                     HalfwordTransferKind::UnsignedHalfwords => {
-                        self.bus.write_half_word(address, value as u16);
+                        self.bus.write16(address, value as u16);
                     }
                     _ => unreachable!("HS flags can't be != from 01 for STORE (L=0)"),
-                };
+                }
             }
             LoadStoreKind::Load => match transfer_kind {
                 HalfwordTransferKind::UnsignedHalfwords => {
-                    let v = self.bus.read_half_word(address);
+                    let v = self.bus.read16(address);
                     self.registers
                         .set_register_at(source_destination_register as usize, v.into());
                 }
                 HalfwordTransferKind::SignedByte => {
-                    let v = self.bus.read_byte(address) as u32;
+                    let v = self.bus.read8(address) as u32;
                     self.registers
                         .set_register_at(source_destination_register as usize, v.sign_extended(8));
                 }
                 HalfwordTransferKind::SignedHalfwords => {
-                    let v = self.bus.read_half_word(address) as u32;
+                    let v = self.bus.read16(address) as u32;
                     self.registers
                         .set_register_at(source_destination_register as usize, v.sign_extended(16));
                 }
@@ -695,12 +649,12 @@ impl Arm7tdmi {
         match kind {
             SingleDataTransferKind::Ldr => match quantity {
                 ReadWriteKind::Byte => {
-                    let value = self.bus.read_byte(address) as u32;
+                    let value = self.bus.read8(address) as u32;
                     self.registers
                         .set_register_at(rd.try_into().unwrap(), value);
                 }
                 ReadWriteKind::Word => {
-                    let v = self.bus.read_word(address);
+                    let v = self.bus.read32(address);
                     self.registers.set_register_at(rd.try_into().unwrap(), v);
                 }
             },
@@ -713,7 +667,7 @@ impl Arm7tdmi {
                         v += 4;
                     }
 
-                    self.bus.write_byte(address, v as u8);
+                    self.bus.write8(address, v as u8);
                 }
                 ReadWriteKind::Word => {
                     let mut v = self.registers.register_at(rd.try_into().unwrap());
@@ -723,7 +677,7 @@ impl Arm7tdmi {
                         v += 4;
                     }
 
-                    self.bus.write_word(address, v);
+                    self.bus.write32(address, v);
                 }
             },
             SingleDataTransferKind::Pld => todo!("implement single data transfer operation"),
@@ -735,6 +689,28 @@ impl Arm7tdmi {
         }
     }
 
+    /// Loads `rd` from the address in `rn` and stores `rm` there, `rm` is read before
+    /// the load so it can be the same register as `rd`.
+    pub(crate) fn single_data_swap(&mut self, quantity: ReadWriteKind, rn: u32, rd: u32, rm: u32) {
+        let address = self.registers.register_at(rn as usize) as usize;
+        let source = self.registers.register_at(rm as usize);
+
+        let value = match quantity {
+            ReadWriteKind::Byte => {
+                let value = self.bus.read8(address).into();
+                self.bus.write8(address, source as u8);
+                value
+            }
+            ReadWriteKind::Word => {
+                let value = self.bus.read32(address);
+                self.bus.write32(address, source);
+                value
+            }
+        };
+
+        self.registers.set_register_at(rd as usize, value);
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn block_data_transfer(
         &mut self,
@@ -748,37 +724,67 @@ impl Arm7tdmi {
     ) {
         let base_register = rn.try_into().unwrap();
         let memory_base = self.registers.register_at(base_register);
-        let mut address = memory_base.try_into().unwrap();
 
         if load_psr {
             unimplemented!();
         }
 
-        let transfer = match load_store {
-            LoadStoreKind::Store => {
-                |arm: &mut Self, address: usize, reg_source: usize| {
-                    let mut value = arm.registers.register_at(reg_source);
+        // An empty list transfers R15 but moves the base as if it had 16 registers.
+        let (reg_list, register_count) = if reg_list == 0 {
+            (1 << 15, 16)
+        } else {
+            (reg_list, reg_list.count_ones())
+        };
 
-                    // If R15 we get the value of the current instruction + 4 (it is +8 already)
-                    if reg_source == REG_PROGRAM_COUNTER.try_into().unwrap() {
-                        value += 4;
-                    }
+        // The registers go from the lowest address up, whatever the direction.
+        let size = register_count * 4;
+        let (mut address, new_base) = match (offsetting, indexing) {
+            (Offsetting::Up, Indexing::Post) => (memory_base, memory_base.wrapping_add(size)),
+            (Offsetting::Up, Indexing::Pre) => {
+                (memory_base.wrapping_add(4), memory_base.wrapping_add(size))
+            }
+            (Offsetting::Down, Indexing::Post) => (
+                memory_base.wrapping_sub(size).wrapping_add(4),
+                memory_base.wrapping_sub(size),
+            ),
+            (Offsetting::Down, Indexing::Pre) => (
+                memory_base.wrapping_sub(size),
+                memory_base.wrapping_sub(size),
+            ),
+        };
+
+        let is_base_first = reg_list.trailing_zeros() == rn;
+        for register in (0..=15_u8).filter(|register| reg_list.is_bit_on(*register)) {
+            let register = usize::from(register);
+
+            match load_store {
+                LoadStoreKind::Store => {
+                    let value = if register == base_register && write_back && !is_base_first {
+                        // The base is written back after the first transfer.
+                        new_base
+                    } else if register == REG_PROGRAM_COUNTER as usize {
+                        // If R15 we get the value of the current instruction + 4 (it is +8 already)
+                        self.registers.register_at(register) + 4
+                    } else {
+                        self.registers.register_at(register)
+                    };
 
-                    arm.bus.write_word(address, value);
+                    self.bus.write32(address as usize, value);
+                }
+                LoadStoreKind::Load => {
+                    let value = self.bus.read32(address as usize);
+                    self.registers.set_register_at(register, value);
                 }
             }
-            LoadStoreKind::Load => |arm: &mut Self, address: usize, reg_destination: usize| {
-                let v = arm.bus.read_word(address);
-                arm.registers.set_register_at(reg_destination, v);
-            },
-        };
 
-        self.exec_data_transfer(reg_list, indexing, &mut address, offsetting, transfer);
+            address = address.wrapping_add(4);
+        }
 
-        if write_back {
-            self.registers
-                .set_register_at(base_register, address.try_into().unwrap());
-        };
+        // A loaded base keeps the loaded value.
+        let is_base_loaded = load_store == LoadStoreKind::Load && reg_list.is_bit_on(rn as u8);
+        if write_back && !is_base_loaded {
+            self.registers.set_register_at(base_register, new_base);
+        }
 
         // If LDM and R15 is in register list we flush the pipeline
         if load_store == LoadStoreKind::Load && reg_list.is_bit_on(15) {
@@ -786,45 +792,6 @@ impl Arm7tdmi {
         }
     }
 
-    fn exec_data_transfer<F>(
-        &mut self,
-        reg_list: u32,
-        indexing: Indexing,
-        address: &mut usize,
-        offsetting: Offsetting,
-        transfer: F,
-    ) where
-        F: Fn(&mut Self, usize, usize),
-    {
-        let alignment = 4; // Since are word, the alignment is 4.
-
-        let change_address = |address: usize| match offsetting {
-            Offsetting::Down => address.wrapping_sub(alignment),
-            Offsetting::Up => address.wrapping_add(alignment),
-        };
-
-        // If we are decreasing we still want to store the lowest reg to the lowest
-        // memory address. For this reason we reverse the loop order.
-        let range_registers: Box<dyn Iterator<Item = u8>> = match offsetting {
-            Offsetting::Down => Box::new((0..=15).rev()),
-            Offsetting::Up => Box::new(0..=15),
-        };
-
-        for reg_source in range_registers {
-            if reg_list.is_bit_on(reg_source) {
-                if indexing == Indexing::Pre {
-                    *address = change_address(*address);
-                }
-
-                transfer(self, *address, reg_source.into());
-
-                if indexing == Indexing::Post {
-                    *address = change_address(*address);
-                }
-            }
-        }
-    }
-
     pub fn branch(&mut self, is_link: bool, offset: u32) {
         let offset = offset.sign_extended(26) as i32;
         let old_pc: u32 = self.registers.program_counter().try_into().unwrap();
@@ -837,6 +804,14 @@ impl Arm7tdmi {
         self.registers.set_program_counter(new_pc as u32);
 
         self.flush_pipeline();
+
+        if is_link {
+            self.call_stack.push(
+                new_pc as u32,
+                old_pc.wrapping_sub(SIZE_OF_INSTRUCTION),
+                FrameKind::Call,
+            );
+        }
     }
 
     pub fn multiply(
@@ -986,6 +961,11 @@ impl Arm7tdmi {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::too_many_lines,
+    clippy::unreadable_literal,
+    clippy::unusual_byte_groupings
+)]
 mod tests {
     use super::*;
     use crate::cpu::arm::alu_instruction::{AluSecondOperandInfo, ShiftOperator};
@@ -993,86 +973,18 @@ mod tests {
     use crate::cpu::arm::instructions::{ArmModeInstruction, SingleDataTransferOffsetInfo};
     use crate::cpu::condition::Condition;
     use crate::cpu::flags::ShiftKind;
+    use crate::cpu::test_dsl::{assemble, CpuTest};
 
     use pretty_assertions::assert_eq;
 
-    pub trait BitsUtilsTest
-    where
-        Self: Clone + Sized + Into<u128> + TryFrom<u128> + From<bool> + TryInto<u8> + From<u8>,
-        <Self as TryFrom<u128>>::Error: std::fmt::Debug,
-    {
-        fn set_bits(&mut self, bits_range: std::ops::RangeInclusive<u8>, value: Self) {
-            let start = bits_range.start();
-            let length = bits_range.len() as u32;
-            let self_bits: u128 = self.clone().into();
-
-            // Set all of the desider bits to 1 then & it with the value provided
-            // considering the right amount of bits (given by bits_range.len()).
-            // Order goes from lsb to msb (right to left).
-            let mask = (2_u128.pow(length)) - 1;
-            let value_bits: u128 = value.into();
-            let value_bits: u128 = (value_bits & mask) << start;
-
-            // Now, shift the mask then flip it so we can choose where to insert
-            // our value bits
-            let reverse_mask = !(mask << start);
-
-            // Say we have self being a u16 value with the following bits:
-            //     0b0000....10010011_01110011_u128
-            // and we want to set bits 7..=10, 4 bits starting from 7 to 0b1001.
-            //
-            // The constant `reverse_mask` will look something like:
-            //     0b1111....11111000_01111111_u128
-            // which is helpful for clearing the bits we are about to set.
-            //
-            //     0b0000....10010011_01110011_u128 &
-            //     0b1111....11111000_01111111_u128
-            //     --------------------------------
-            //     0b0000....10010000_01110011_u128
-            //
-            // The value above can be used in bit-or with our value bits
-            // to obtain the expected result:
-            //
-            //     0b0000....10010000_01110011_u128 |
-            //     0b0000....00000100_10000000_u128 =
-            //     --------------------------------
-            //     0b0000....10010100_11110011_u128
-
-            let new_self =
-                <Self as TryFrom<u128>>::try_from((self_bits & reverse_mask) | value_bits).unwrap();
-            self.clone_from(&new_self);
-        }
-    }
-
-    impl BitsUtilsTest for u32 {}
-
-    #[test]
-    fn set_bits() {
-        let mut b = 0b10001001_u32;
-        b.set_bits(4..=5, 0b11);
-        assert_eq!(b, 0b10111001_u32);
-        b.set_bits(1..=2, 0b11);
-        assert_eq!(b, 0b10111111_u32);
-
-        let mut b = 0b00000000_00000000_u32;
-        b.set_bits(0..=7, 0b11111111_u32);
-        assert_eq!(b, 0b00000000_11111111_u32);
-    }
-
     #[test]
     fn check_cmn() {
         {
             let op_code = 0b1110_00_0_1011_0_1001_1111_000000001110;
-            let mut cpu = Arm7tdmi::default();
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
-            cpu.execute_arm(Arm7tdmi::decode(op_code));
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .flags("nzcv")
+                .run()
+                .assert_flags("nzcv");
         }
     }
 
@@ -1080,10 +992,9 @@ mod tests {
     fn check_teq() {
         {
             let op_code = 0b1110_00_1_1001_1_1100_0000_000000000001;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::AL,
                     alu_instruction: ArmModeAluInstr::Teq,
@@ -1097,27 +1008,21 @@ mod tests {
 
             #[cfg(feature = "disassembler")]
             {
-                let asm = op_code.instruction.disassembler();
+                let asm = decoded.instruction.disassembler();
                 assert_eq!(asm, "TEQ R12, #1");
             }
 
-            cpu.registers.set_register_at(12, 0xFFFF_FFFF);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
-            cpu.execute_arm(op_code);
-            assert!(cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .register(12, 0xFFFF_FFFF)
+                .flags("nzcv")
+                .run()
+                .assert_flags("Nzcv");
         }
         {
             let op_code: u32 = 0b0000_00_0_1001_0_1001_1111_000000001100;
-            let cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::EQ,
                     psr_kind: PsrKind::Cpsr,
@@ -1127,19 +1032,24 @@ mod tests {
                 }
             );
 
-            assert!(!cpu.cpsr.can_execute(op_code.condition));
             #[cfg(feature = "disassembler")]
             {
-                let asm = op_code.instruction.disassembler();
+                let asm = decoded.instruction.disassembler();
                 assert_eq!(asm, "MSREQ CPSR, R12");
             }
+
+            // Z is clear, the CPSR is left alone.
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .register(12, 0xF000_0000 | Mode::User as u32)
+                .run()
+                .assert_flags("nzcv");
+            assert_eq!(test.cpu.cpsr.mode(), Mode::Supervisor);
         }
         {
             let op_code = 0b1110_00_0_1001_1_1001_0011_000000000000;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::AL,
                     alu_instruction: ArmModeAluInstr::Teq,
@@ -1155,22 +1065,20 @@ mod tests {
                 }
             );
 
-            let rn = 9_usize;
-            cpu.registers.set_register_at(rn, 100);
-            cpu.cpsr.set_sign_flag(true); // set for later verify.
-            cpu.execute_arm(op_code);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .register(9, 100)
+                .flags("Nzcv")
+                .run()
+                .assert_flags("nzcv");
         }
     }
 
     #[test]
     fn check_cmp() {
         let op_code: u32 = 0b1110_00_1_1010_1_1110_0000_000000000000;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Cmp,
@@ -1184,59 +1092,57 @@ mod tests {
 
         #[cfg(feature = "disassembler")]
         {
-            let asm = op_code.instruction.disassembler();
+            let asm = decoded.instruction.disassembler();
             assert_eq!(asm, "CMP R14, #0");
         }
-        assert!(!cpu.cpsr.sign_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        cpu.execute_arm(op_code);
-        assert!(!cpu.cpsr.sign_flag());
-        assert!(cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.overflow_flag());
+
+        CpuTest::arm_opcodes(&[op_code])
+            .register(14, 0)
+            .flags("nzcv")
+            .run()
+            .assert_flags("nZCv");
     }
 
     #[test]
     fn check_orr() {
-        {
-            let op_code: u32 = 0b0000_00_1_1100_0_1100_1100_000011000000;
-            let cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-            assert_eq!(
-                op_code.instruction,
-                ArmModeInstruction::DataProcessing {
-                    condition: Condition::EQ,
-                    alu_instruction: ArmModeAluInstr::Orr,
-                    set_conditions: false,
-                    op_kind: OperandKind::Immediate,
-                    rn: 12,
-                    destination: 12,
-                    op2: AluSecondOperandInfo::Immediate {
-                        base: 192,
-                        shift: 0
-                    }
+        let op_code: u32 = 0b0000_00_1_1100_0_1100_1100_000011000000;
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        assert_eq!(
+            decoded.instruction,
+            ArmModeInstruction::DataProcessing {
+                condition: Condition::EQ,
+                alu_instruction: ArmModeAluInstr::Orr,
+                set_conditions: false,
+                op_kind: OperandKind::Immediate,
+                rn: 12,
+                destination: 12,
+                op2: AluSecondOperandInfo::Immediate {
+                    base: 192,
+                    shift: 0
                 }
-            );
-
-            assert!(!cpu.cpsr.can_execute(op_code.condition));
-            #[cfg(feature = "disassembler")]
-            {
-                let asm = op_code.instruction.disassembler();
-                assert_eq!(asm, "ORREQ R12, R12, #192");
             }
+        );
+
+        #[cfg(feature = "disassembler")]
+        {
+            let asm = decoded.instruction.disassembler();
+            assert_eq!(asm, "ORREQ R12, R12, #192");
         }
+
+        // Z is clear, nothing is written.
+        CpuTest::arm_opcodes(&[op_code])
+            .register(12, 5)
+            .run()
+            .assert_register(12, 5);
     }
 
     #[test]
     fn check_mov() {
         {
             let op_code: u32 = 0b0000_00_1_1101_0_0000_1110_000000000100;
-            let cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::EQ,
                     alu_instruction: ArmModeAluInstr::Mov,
@@ -1248,19 +1154,22 @@ mod tests {
                 }
             );
 
-            assert!(!cpu.cpsr.can_execute(op_code.condition));
             #[cfg(feature = "disassembler")]
             {
-                let asm = op_code.instruction.disassembler();
+                let asm = decoded.instruction.disassembler();
                 assert_eq!(asm, "MOVEQ R14, #4");
             }
+
+            CpuTest::arm_opcodes(&[op_code])
+                .register(14, 0x1234)
+                .run()
+                .assert_register(14, 0x1234);
         }
         {
             let op_code: u32 = 0b1110_00_1_1101_0_0000_0000_000011011111;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::AL,
                     alu_instruction: ArmModeAluInstr::Mov,
@@ -1277,28 +1186,22 @@ mod tests {
 
             #[cfg(feature = "disassembler")]
             {
-                let asm = op_code.instruction.disassembler();
+                let asm = decoded.instruction.disassembler();
                 assert_eq!(asm, "MOV R0, #223");
             }
 
-            cpu.registers.set_register_at(0, 1);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
-            cpu.execute_arm(op_code);
-            assert_eq!(cpu.registers.register_at(0), 0xDF);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .register(0, 1)
+                .flags("nzcv")
+                .run()
+                .assert_register(0, 0xDF)
+                .assert_flags("nzcv");
         }
         {
             let op_code: u32 = 0b1110_00_1_1101_0_0000_1100_001100000001;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::AL,
                     alu_instruction: ArmModeAluInstr::Mov,
@@ -1312,67 +1215,53 @@ mod tests {
 
             #[cfg(feature = "disassembler")]
             {
-                let asm = op_code.instruction.disassembler();
+                let asm = decoded.instruction.disassembler();
                 assert_eq!(asm, "MOV R12, #67108864");
             }
 
-            cpu.registers.set_register_at(12, 1);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
-            cpu.execute_arm(op_code);
-            assert_eq!(cpu.registers.register_at(12), 0x4000000);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .register(12, 1)
+                .flags("nzcv")
+                .run()
+                .assert_register(12, 0x0400_0000)
+                .assert_flags("nzcv");
         }
     }
 
     #[test]
     fn check_add() {
-        {
-            let op_code: u32 = 0b1110_00_1_0100_0_1111_0000_000000000001;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-            assert_eq!(
-                op_code.instruction,
-                ArmModeInstruction::DataProcessing {
-                    condition: Condition::AL,
-                    alu_instruction: ArmModeAluInstr::Add,
-                    set_conditions: false,
-                    op_kind: OperandKind::Immediate,
-                    rn: 15,
-                    destination: 0,
-                    op2: AluSecondOperandInfo::Immediate { base: 1, shift: 0 }
-                }
-            );
-
-            #[cfg(feature = "disassembler")]
-            {
-                let asm = op_code.instruction.disassembler();
-                assert_eq!(asm, "ADD R0, R15, #1");
+        let op_code: u32 = 0b1110_00_1_0100_0_1111_0000_000000000001;
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        assert_eq!(
+            decoded.instruction,
+            ArmModeInstruction::DataProcessing {
+                condition: Condition::AL,
+                alu_instruction: ArmModeAluInstr::Add,
+                set_conditions: false,
+                op_kind: OperandKind::Immediate,
+                rn: 15,
+                destination: 0,
+                op2: AluSecondOperandInfo::Immediate { base: 1, shift: 0 }
             }
+        );
 
-            cpu.registers.set_register_at(15, 15);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
-            cpu.execute_arm(op_code);
-            assert_eq!(cpu.registers.register_at(0), 16);
-            assert!(!cpu.cpsr.sign_flag());
-            assert!(!cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.carry_flag());
-            assert!(!cpu.cpsr.overflow_flag());
+        #[cfg(feature = "disassembler")]
+        {
+            let asm = decoded.instruction.disassembler();
+            assert_eq!(asm, "ADD R0, R15, #1");
         }
 
+        // R15 reads as the address of the instruction + 8.
+        CpuTest::arm_opcodes(&[op_code])
+            .flags("nzcv")
+            .run()
+            .assert_register(0, 8 + 1)
+            .assert_flags("nzcv");
+
         let op_code = 0b1110_00_1_0100_0_1111_0000_000000100000;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Add,
@@ -1383,9 +1272,10 @@ mod tests {
                 op2: AluSecondOperandInfo::Immediate { base: 32, shift: 0 }
             }
         );
-        cpu.registers.set_register_at(15, 15);
-        cpu.execute_arm(op_code);
-        assert_eq!(cpu.registers.register_at(0), 15 + 32);
+
+        CpuTest::arm_opcodes(&[op_code])
+            .run()
+            .assert_register(0, 8 + 32);
     }
 
     #[test]
@@ -1393,10 +1283,9 @@ mod tests {
         // Case when R15 is used as operand and shift amount is taken from register:
         // R2 = R1 + (R15 << R3)
         let op_code = 0b1110_00_0_0100_0_0001_0010_0011_0001_1111;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Add,
@@ -1412,23 +1301,21 @@ mod tests {
             },
         );
 
-        cpu.registers.set_register_at(2, 5);
-        cpu.registers.set_register_at(1, 10);
-        cpu.registers.set_register_at(15, 500);
-        cpu.registers.set_register_at(3, 0);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(2), 500 + 4 + 10);
+        // The register shift takes an extra cycle, R15 reads as the address + 12.
+        CpuTest::arm_opcodes(&[op_code])
+            .register(1, 10)
+            .register(2, 5)
+            .register(3, 0)
+            .run()
+            .assert_register(2, 12 + 10);
     }
 
     #[test]
     fn check_add_carry_bit() {
         let op_code: u32 = 0b1110_00_0_0100_1_1111_0000_0000_0000_1110;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Add,
@@ -1444,38 +1331,27 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(15, (1 << 31) + 1);
-        cpu.registers.set_register_at(14, 1 << 31);
-        cpu.execute_arm(op_code);
-        assert_eq!(cpu.registers.register_at(0), 1);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
-        assert!(!cpu.cpsr.zero_flag());
+        // R15 reads as 8.
+        CpuTest::arm_opcodes(&[op_code])
+            .register(14, 0xFFFF_FFF9)
+            .run()
+            .assert_register(0, 1)
+            .assert_flags("nzCv");
+
+        CpuTest::arm_opcodes(&[op_code])
+            .register(14, 0x7FFF_FFF9)
+            .run()
+            .assert_register(0, 0x8000_0001)
+            .assert_flags("NzcV");
     }
 
     #[test]
     fn check_mov_rx_immediate() {
-        // MOV R0, 0
-        let mut op_code: u32 = 0b1110_00_1_1101_0_0000_0000_0000_0000_0000;
-
-        // bits [11-8] are ROR-Shift applied to nn
-        let is = op_code & 0b0000_0000_0000_0000_0000_1111_0000_0000;
-
-        // MOV Rx,x
-        let mut cpu = Arm7tdmi::default();
-        let rx = 0;
-        let register_for_op = rx << 12;
-        let immediate_value = rx;
-
-        // Rd parameter
-        op_code = (op_code & 0b1111_1111_1111_1111_0000_1111_1111_1111) + register_for_op;
-        // Immediate parameter
-        op_code = (op_code & 0b1111_1111_1111_1111_1111_1111_0000_0000) + immediate_value;
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        // MOV R0, #0
+        let op_code: u32 = 0b1110_00_1_1101_0_0000_0000_0000_0000_0000;
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Mov,
@@ -1487,19 +1363,18 @@ mod tests {
             }
         );
 
-        cpu.execute_arm(op_code);
-        let rotated = rx.rotate_right(is * 2);
-        assert_eq!(cpu.registers.register_at(rx.try_into().unwrap()), rotated);
+        CpuTest::arm_opcodes(&[op_code])
+            .register(0, 5)
+            .run()
+            .assert_register(0, 0);
     }
 
     #[test]
     fn check_mov_cpsr() {
-        // Checks for Z flag
         let op_code = 0b1110_00_0_1101_1_0000_0001_00000_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Mov,
@@ -1515,25 +1390,22 @@ mod tests {
             }
         );
 
-        cpu.execute_arm(op_code);
-
-        assert!(cpu.cpsr.zero_flag());
-
         // Checks for Z flag
-        let op_code = 0b1110_00_0_1101_1_0000_0001_00000_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.registers.set_register_at(2, -5_i32 as u32);
-        cpu.execute_arm(op_code);
+        CpuTest::arm_opcodes(&[op_code])
+            .register(2, 0)
+            .run()
+            .assert_flags("nZcv");
 
-        assert!(cpu.cpsr.sign_flag());
+        // Checks for N flag
+        CpuTest::arm_opcodes(&[op_code])
+            .register(2, -5_i32 as u32)
+            .run()
+            .assert_flags("Nzcv");
     }
 
     #[test]
     fn shift_from_register_is_0() {
         let op_code = 0b1110_00_0_0100_0_0000_0001_0011_0111_0010;
-        let mut cpu = Arm7tdmi::default();
         let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
             op_code.instruction,
@@ -1552,19 +1424,191 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(0, 5);
-        cpu.registers.set_register_at(2, 11);
-        cpu.registers.set_register_at(3, 8 << 8);
+        CpuTest::arm(&["ADD R1, R0, R2, ROR R3"])
+            .register(0, 5)
+            .register(2, 11)
+            .register(3, 8 << 8)
+            .run()
+            .assert_register(1, 16);
+    }
+
+    #[test]
+    fn carry_chains() {
+        // 64 bits arithmetic, R1:R0 op R3:R2 into R5:R4, as in the ARMWrestler ALU tests.
+        let cases = [
+            (
+                ["ADDS R4, R0, R2", "ADCS R5, R1, R3"],
+                [0xFFFF_FFFF, 0, 1, 0],
+                [0, 1],
+                "nzcv",
+            ),
+            (
+                ["ADDS R4, R0, R2", "ADCS R5, R1, R3"],
+                [0xFFFF_FFFF, 0xFFFF_FFFF, 1, 0x8000_0000],
+                [0, 0x8000_0000],
+                "NzCv",
+            ),
+            (
+                ["ADDS R4, R0, R2", "ADCS R5, R1, R3"],
+                [0, 0x7FFF_FFFF, 0, 1],
+                [0, 0x8000_0000],
+                "NzcV",
+            ),
+            (
+                ["SUBS R4, R0, R2", "SBCS R5, R1, R3"],
+                [0, 1, 1, 0],
+                [0xFFFF_FFFF, 0],
+                "nZCv",
+            ),
+            (
+                ["SUBS R4, R0, R2", "SBCS R5, R1, R3"],
+                [0, 0, 0, 0],
+                [0, 0],
+                "nZCv",
+            ),
+            (
+                ["SUBS R4, R0, R2", "SBCS R5, R1, R3"],
+                [0, 0, 1, 0],
+                [0xFFFF_FFFF, 0xFFFF_FFFF],
+                "Nzcv",
+            ),
+            (
+                ["SUBS R4, R0, R2", "SBCS R5, R1, R3"],
+                [0, 0x8000_0000, 1, 0],
+                [0xFFFF_FFFF, 0x7FFF_FFFF],
+                "nzCV",
+            ),
+            // Reversed: R3:R2 - R1:R0.
+            (
+                ["RSBS R4, R0, R2", "RSCS R5, R1, R3"],
+                [1, 0, 0, 1],
+                [0xFFFF_FFFF, 0],
+                "nZCv",
+            ),
+            (
+                ["RSBS R4, R0, R2", "RSCS R5, R1, R3"],
+                [1, 0, 0, 0],
+                [0xFFFF_FFFF, 0xFFFF_FFFF],
+                "Nzcv",
+            ),
+        ];
+
+        for (program, [r0, r1, r2, r3], [low, high], expected_flags) in cases {
+            let test = CpuTest::arm(&program)
+                .register(0, r0)
+                .register(1, r1)
+                .register(2, r2)
+                .register(3, r3)
+                .run();
+
+            let context = format!("{program:?} on 0x{r1:08X}{r0:08X}, 0x{r3:08X}{r2:08X}");
+            let result = [4, 5].map(|index| test.cpu.registers.register_at(index));
+            assert_eq!(result, [low, high], "R4 and R5 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
+    }
 
-        cpu.execute_arm(op_code);
+    #[test]
+    fn shift_by_immediate() {
+        let cases = [
+            // LSL #0 leaves the value and the carry alone.
+            (
+                "MOVS R0, R1, LSL #0",
+                0x8000_0001,
+                "nzCv",
+                0x8000_0001,
+                "NzCv",
+            ),
+            ("MOVS R0, R1, LSL #1", 0x8000_0001, "nzcv", 2, "nzCv"),
+            (
+                "MOVS R0, R1, LSR #1",
+                0x8000_0001,
+                "nzcv",
+                0x4000_0000,
+                "nzCv",
+            ),
+            ("MOVS R0, R1, LSR #32", 0x8000_0000, "nzcv", 0, "nZCv"),
+            (
+                "MOVS R0, R1, ASR #4",
+                0x8000_0000,
+                "nzcv",
+                0xF800_0000,
+                "Nzcv",
+            ),
+            (
+                "MOVS R0, R1, ASR #32",
+                0x8000_0000,
+                "nzcv",
+                0xFFFF_FFFF,
+                "NzCv",
+            ),
+            ("MOVS R0, R1, ROR #4", 0xF, "nzcv", 0xF000_0000, "NzCv"),
+            // RRX shifts the carry in and bit 0 out.
+            ("MOVS R0, R1, RRX", 1, "nzCv", 0x8000_0000, "NzCv"),
+            ("MOVS R0, R1, RRX", 2, "nzcv", 1, "nzcv"),
+        ];
+
+        for (instruction, value, flags, expected, expected_flags) in cases {
+            let test = CpuTest::arm(&[instruction])
+                .register(1, value)
+                .flags(flags)
+                .run();
+
+            let context = format!("{instruction} on 0x{value:08X}");
+            let result = test.cpu.registers.register_at(0);
+            assert_eq!(result, expected, "R0 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
+    }
 
-        assert_eq!(cpu.registers.register_at(1), 16);
+    #[test]
+    fn shift_by_register() {
+        let cases = [
+            // An amount of 0 leaves the value and the carry alone.
+            ("MOVS R0, R1, LSL R2", 5, 0, "nzCv", 5, "nzCv"),
+            ("MOVS R0, R1, LSR R2", 5, 0, "nzCv", 5, "nzCv"),
+            ("MOVS R0, R1, LSL R2", 1, 32, "nzcv", 0, "nZCv"),
+            ("MOVS R0, R1, LSL R2", 1, 33, "nzCv", 0, "nZcv"),
+            ("MOVS R0, R1, LSR R2", 0x8000_0000, 32, "nzcv", 0, "nZCv"),
+            ("MOVS R0, R1, LSR R2", 0x8000_0000, 33, "nzCv", 0, "nZcv"),
+            (
+                "MOVS R0, R1, ASR R2",
+                0x8000_0000,
+                40,
+                "nzcv",
+                0xFFFF_FFFF,
+                "NzCv",
+            ),
+            (
+                "MOVS R0, R1, ROR R2",
+                0x8000_0000,
+                32,
+                "nzcv",
+                0x8000_0000,
+                "NzCv",
+            ),
+            ("MOVS R0, R1, ROR R2", 0x10, 36, "nzcv", 1, "nzcv"),
+            // Only the bottom byte of the register is used.
+            ("MOVS R0, R1, LSL R2", 1, 0x101, "nzcv", 2, "nzcv"),
+        ];
+
+        for (instruction, value, amount, flags, expected, expected_flags) in cases {
+            let test = CpuTest::arm(&[instruction])
+                .register(1, value)
+                .register(2, amount)
+                .flags(flags)
+                .run();
+
+            let context = format!("{instruction} on 0x{value:08X} by {amount}");
+            let result = test.cpu.registers.register_at(0);
+            assert_eq!(result, expected, "R0 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
     }
 
     #[test]
     fn check_and() {
         let op_code = 0b1110_00_1_0000_0_0000_0001_0000_10101010;
-        let mut cpu = Arm7tdmi::default();
         let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
             op_code.instruction,
@@ -1583,17 +1627,15 @@ mod tests {
         );
 
         // All 1 except msb
-        cpu.registers.set_register_at(0, 2_u32.pow(31) - 1);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 0b10101010);
+        CpuTest::arm(&["AND R1, R0, #0b10101010"])
+            .register(0, 2_u32.pow(31) - 1)
+            .run()
+            .assert_register(1, 0b10101010);
     }
 
     #[test]
     fn check_eor() {
         let op_code = 0b1110_00_1_0001_0_0000_0001_0000_10101010;
-        let mut cpu = Arm7tdmi::default();
         let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
             op_code.instruction,
@@ -1611,21 +1653,19 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(0, 0b11111111);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 0b01010101);
+        CpuTest::arm(&["EOR R1, R0, #0b10101010"])
+            .register(0, 0b11111111)
+            .run()
+            .assert_register(1, 0b01010101);
     }
 
     #[test]
     fn check_tst() {
         {
             let op_code = 0b0000_00_0_1000_0_1111_1100_0000_00000000;
-            let cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::EQ,
                     psr_kind: PsrKind::Cpsr,
@@ -1635,15 +1675,17 @@ mod tests {
                 }
             );
 
-            assert!(!cpu.cpsr.can_execute(op_code.condition));
+            // Z is clear, R12 is left alone.
+            CpuTest::arm_opcodes(&[op_code])
+                .register(12, 0x1234)
+                .run()
+                .assert_register(12, 0x1234);
         }
         {
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00_1_1000_1_0000_0001_0000_00000000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::DataProcessing {
                     condition: Condition::AL,
                     alu_instruction: ArmModeAluInstr::Tst,
@@ -1654,18 +1696,17 @@ mod tests {
                     op2: AluSecondOperandInfo::Immediate { base: 0, shift: 0 }
                 }
             );
-            cpu.cpsr.set_sign_flag(true);
 
-            cpu.execute_arm(op_code);
-            assert!(cpu.cpsr.zero_flag());
-            assert!(!cpu.cpsr.sign_flag());
+            CpuTest::arm_opcodes(&[op_code])
+                .flags("Nzcv")
+                .run()
+                .assert_flags("nZcv");
         }
     }
 
     #[test]
     fn check_bic() {
         let op_code = 0b1110_00_1_1110_0_0000_0001_0000_10101010;
-        let mut cpu = Arm7tdmi::default();
         let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
             op_code.instruction,
@@ -1683,17 +1724,15 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(0, 0b11111111);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 0b01010101);
+        CpuTest::arm(&["BIC R1, R0, #0b10101010"])
+            .register(0, 0b11111111)
+            .run()
+            .assert_register(1, 0b01010101);
     }
 
     #[test]
     fn check_mvn() {
         let op_code = 0b1110_00_1_1111_1_0000_0001_0000_11111111;
-        let mut cpu = Arm7tdmi::default();
         let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
             op_code.instruction,
@@ -1711,61 +1750,18 @@ mod tests {
             }
         );
 
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), (2_u32.pow(24) - 1) << 8);
-        assert!(cpu.cpsr.sign_flag());
+        CpuTest::arm(&["MVNS R1, #0xFF"])
+            .run()
+            .assert_register(1, (2_u32.pow(24) - 1) << 8)
+            .assert_flags("Nzcv");
     }
 
     #[test]
     fn check_sub() {
         let op_code = 0b1110_00_0_0010_1_0000_0001_00000_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sub,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-
-        cpu.registers.set_register_at(0, 10);
-        cpu.registers.set_register_at(2, 5);
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 5);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.sign_flag());
-
-        //Covers carry logic
-        let op_code = 0b1110_00_0_0010_1_0000_0001_00000_00_0_0010;
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        cpu.registers.set_register_at(2, 15);
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1) as i32, -5);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-        assert!(!cpu.cpsr.zero_flag());
-
-        // Covers overflow logic
-        let op_code = 0b1110_00_0_0010_1_0000_0001_00000_00_0_0010;
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Sub,
@@ -1781,26 +1777,33 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(0, 1);
-        cpu.registers.set_register_at(2, i32::MIN as u32);
+        let cases = [
+            (10, 5, 5, "nzCv"),
+            // Covers carry logic
+            (10, 15, -5_i32 as u32, "Nzcv"),
+            // Covers overflow logic
+            (1, i32::MIN as u32, (i32::MIN + 1) as u32, "NzcV"),
+        ];
 
-        cpu.execute_arm(op_code);
+        for (rn, rm, expected, expected_flags) in cases {
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .register(0, rn)
+                .register(2, rm)
+                .run();
 
-        assert_eq!(cpu.registers.register_at(1), (i32::MIN + 1) as u32);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-        assert!(!cpu.cpsr.zero_flag());
+            let context = format!("SUBS R1, R0, R2 on 0x{rn:08X}, 0x{rm:08X}");
+            let result = test.cpu.registers.register_at(1);
+            assert_eq!(result, expected, "R1 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
     }
 
     #[test]
     fn check_adc() {
-        // Covers all flags=0
         let op_code = 0b1110_00_0_0101_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
                 alu_instruction: ArmModeAluInstr::Adc,
@@ -1816,27 +1819,43 @@ mod tests {
             }
         );
 
-        cpu.registers.set_register_at(0, 1);
-        cpu.registers.set_register_at(2, 1);
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 3);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+        // All of them with the carry set.
+        let cases = [
+            // Covers all flags=0
+            (1, 1, 3, "nzcv"),
+            // Covers carry during first sum
+            (u32::MAX, 1, 1, "nzCv"),
+            // Covers carry during second sum
+            (u32::MAX - 1, 1, 0, "nZCv"),
+            // Covers overflow during first sum
+            (i32::MAX as u32, 1, (1 << 31) + 1, "NzcV"),
+            // Covers overflow during second sum
+            (i32::MAX as u32 - 1, 1, 1 << 31, "NzcV"),
+        ];
+
+        for (rn, rm, expected, expected_flags) in cases {
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .register(0, rn)
+                .register(2, rm)
+                .flags("nzCv")
+                .run();
+
+            let context = format!("ADCS R1, R0, R2 on 0x{rn:08X}, 0x{rm:08X}");
+            let result = test.cpu.registers.register_at(1);
+            assert_eq!(result, expected, "R1 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
+    }
 
-        // Covers carry during first sum
-        let op_code = 0b1110_00_0_0101_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+    #[test]
+    fn check_sbc() {
+        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             ArmModeInstruction::DataProcessing {
                 condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Adc,
+                alu_instruction: ArmModeAluInstr::Sbc,
                 set_conditions: true,
                 op_kind: OperandKind::Register,
                 rn: 0,
@@ -1849,377 +1868,199 @@ mod tests {
             }
         );
 
-        cpu.cpsr.set_carry_flag(true);
-        cpu.registers.set_register_at(0, u32::MAX);
-        cpu.registers.set_register_at(2, 1);
+        let cases = [
+            // Covers all flag=0
+            (10, 5, "nzCv", 5, "nzCv"),
+            // Covers borrow, the carry is cleared
+            (0, 1, "nzCv", -1_i32 as u32, "Nzcv"),
+            // Covers no borrow
+            (u32::MAX, 0, "nzCv", -1_i32 as u32, "NzCv"),
+            // Covers borrow caused by a clear carry
+            (0, 0, "nzcv", -1_i32 as u32, "Nzcv"),
+            // Covers overflow with borrow
+            (i32::MAX as u32, -1_i32 as u32, "nzCv", 1 << 31, "NzcV"),
+            // Covers no overflow when nothing is subtracted
+            (i32::MAX as u32, 0, "nzCv", i32::MAX as u32, "nzCv"),
+            // Covers overflow caused by a clear carry
+            (i32::MIN as u32, 0, "nzcv", i32::MAX as u32, "nzCV"),
+        ];
+
+        for (rn, rm, flags, expected, expected_flags) in cases {
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .register(0, rn)
+                .register(2, rm)
+                .flags(flags)
+                .run();
+
+            let context = format!("SBCS R1, R0, R2 on 0x{rn:08X}, 0x{rm:08X} with {flags}");
+            let result = test.cpu.registers.register_at(1);
+            assert_eq!(result, expected, "R1 of {context}");
+            assert_eq!(test.current_flags(), expected_flags, "flags of {context}");
+        }
+    }
 
-        cpu.execute_arm(op_code);
+    #[test]
+    fn check_ror() {
+        // ROR R5, R6
+        CpuTest::thumb_opcodes(&[0x41F5])
+            .register(5, 1)
+            .register(6, 10)
+            .flags("nzcv")
+            .run()
+            .assert_register(5, 0x0040_0000)
+            .assert_flags("nzcv");
+    }
 
-        assert_eq!(cpu.registers.register_at(1), 1);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+    #[test]
+    fn psr_transfer_programs() {
+        // The CPU starts in Supervisor mode with IRQ and FIQ disabled.
+        let control = 0xC0 | Mode::Supervisor as u32;
+        CpuTest::arm(&["MRS R0, CPSR"])
+            .flags("NzCv")
+            .run()
+            .assert_register(0, 0xA000_0000 | control);
+
+        CpuTest::arm(&["MSR CPSR_flg, #0xF0000000", "MRS R0, CPSR"])
+            .run()
+            .assert_register(0, 0xF000_0000 | control)
+            .assert_flags("NZCV");
+
+        // The flags are set from a register, the control bits are left alone.
+        CpuTest::arm(&["MSR CPSR_flg, R0"])
+            .register(0, 0x5000_001F)
+            .run()
+            .assert_flags("nZcV");
+
+        let test = CpuTest::arm(&["MOV R13, #0x100", "MSR CPSR, R0", "MOV R13, #0x200"])
+            .register(0, Mode::Irq as u32)
+            .run()
+            .assert_register(13, 0x200);
+        assert_eq!(test.cpu.cpsr.mode(), Mode::Irq);
+
+        // The SPSR of the mode, not the CPSR.
+        CpuTest::arm(&["MSR SPSR, R0", "MRS R1, SPSR", "MRS R2, CPSR"])
+            .mode(&Mode::Irq)
+            .register(0, 0xF000_0010)
+            .run()
+            .assert_register(1, 0xF000_0010)
+            .assert_register(2, 0xC0 | Mode::Irq as u32)
+            .assert_flags("nzcv");
+    }
 
-        // Covers carry during second sum
-        let op_code = 0b1110_00_0_0101_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Adc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-
-        cpu.cpsr.set_carry_flag(true);
-        cpu.registers.set_register_at(0, u32::MAX - 1);
-        cpu.registers.set_register_at(2, 1);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 0);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
-
-        // Covers overflow during first sum
-        let op_code = 0b1110_00_0_0101_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Adc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        // All 1 except MSB
-        cpu.registers.set_register_at(0, i32::MAX as u32);
-        cpu.registers.set_register_at(2, 1);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), (1 << 31) + 1);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-
-        // Covers overflow during second sum
-        let op_code = 0b1110_00_0_0101_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Adc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        // All 1 except MSB
-        cpu.registers.set_register_at(0, i32::MAX as u32 - 1);
-        cpu.registers.set_register_at(2, 1);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 1 << 31);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
+    #[test]
+    fn test_instructions_with_rd_r15() {
+        // TEQP R0, R1 copies the SPSR into the CPSR, the result is discarded.
+        let teqp = 0xE130_F001;
+        let test = CpuTest::arm_opcodes(&[assemble("MSR SPSR, R2", 0), teqp])
+            .mode(&Mode::Irq)
+            .register(0, 1)
+            .register(1, 1)
+            .register(2, 0x9000_0000 | Mode::System as u32)
+            .run()
+            .assert_flags("NzcV");
+        assert_eq!(test.cpu.cpsr.mode(), Mode::System);
+
+        // CMPP R0, #0 without an SPSR only sets the flags.
+        let cmpp = 0xE350_F000;
+        for mode in [Mode::User, Mode::System] {
+            let test = CpuTest::arm_opcodes(&[cmpp])
+                .mode(&mode)
+                .register(0, 0)
+                .flags("Nzcv")
+                .run()
+                .assert_flags("nZCv");
+            assert_eq!(test.cpu.cpsr.mode(), mode);
+        }
     }
 
     #[test]
-    fn check_sbc() {
-        // Covers all flag=0
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.registers.set_register_at(0, 10);
-        cpu.registers.set_register_at(2, 5);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 5);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
-
-        // Covers carry during first diff
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.registers.set_register_at(0, 0);
-        cpu.registers.set_register_at(2, 1);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), -1_i32 as u32);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-
-        // Covers carry during sum
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.registers.set_register_at(0, u32::MAX);
-        cpu.registers.set_register_at(2, 0);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), -1_i32 as u32);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-
-        // Covers carry during second diff
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(false);
+    fn msr_in_user_mode_sets_only_flags() {
+        let test = CpuTest::arm(&["MSR CPSR, R0"])
+            .mode(&Mode::User)
+            .register(0, 0xF000_0000 | Mode::Supervisor as u32)
+            .run()
+            .assert_flags("NZCV");
 
-        cpu.registers.set_register_at(0, 0);
-        cpu.registers.set_register_at(2, 0);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), -1_i32 as u32);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-
-        // Covers overflow during first diff
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.registers.set_register_at(0, i32::MAX as u32);
-        cpu.registers.set_register_at(2, -1_i32 as u32);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), 1 << 31);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
-
-        // Covers overflow during sum
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(true);
-
-        cpu.registers.set_register_at(0, i32::MAX as u32);
-        cpu.registers.set_register_at(2, 0);
-
-        cpu.execute_arm(op_code);
-
-        assert_eq!(cpu.registers.register_at(1), i32::MAX as u32);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
-
-        // Covers overflow during second diff
-        let op_code = 0b1110_00_0_0110_1_0000_0001_0000_0_00_0_0010;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-        assert_eq!(
-            op_code.instruction,
-            ArmModeInstruction::DataProcessing {
-                condition: Condition::AL,
-                alu_instruction: ArmModeAluInstr::Sbc,
-                set_conditions: true,
-                op_kind: OperandKind::Register,
-                rn: 0,
-                destination: 1,
-                op2: AluSecondOperandInfo::Register {
-                    shift_op: ShiftOperator::Immediate(0),
-                    shift_kind: ShiftKind::Lsl,
-                    register: 2,
-                }
-            }
-        );
-        cpu.cpsr.set_carry_flag(false);
-
-        cpu.registers.set_register_at(0, i32::MIN as u32);
-        cpu.registers.set_register_at(2, 0);
-
-        cpu.execute_arm(op_code);
+        assert_eq!(test.cpu.cpsr.mode(), Mode::User);
+        assert!(test.cpu.cpsr.irq_disable());
+    }
 
-        assert_eq!(cpu.registers.register_at(1), i32::MAX as u32);
-        assert!(cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+    #[test]
+    fn block_transfer_base_in_list() {
+        // STMIA R0!, {R0, R1}: the base is the first register, its old value is stored.
+        CpuTest::arm_opcodes(&[0xE8A0_0003])
+            .register(0, 0x100)
+            .register(1, 1)
+            .run()
+            .assert_memory(0x100, 0x100)
+            .assert_memory(0x104, 1)
+            .assert_register(0, 0x108);
+
+        // STMIA R1!, {R0, R1}: the base isn't the first one, the written back value is stored.
+        CpuTest::arm_opcodes(&[0xE8A1_0003])
+            .register(0, 7)
+            .register(1, 0x100)
+            .run()
+            .assert_memory(0x100, 7)
+            .assert_memory(0x104, 0x108)
+            .assert_register(1, 0x108);
+
+        // LDMIA R0!, {R0, R1}: the loaded base wins over the write back.
+        CpuTest::arm_opcodes(&[0xE8B0_0003])
+            .register(0, 0x100)
+            .memory(0x100, 0x55)
+            .memory(0x104, 0x66)
+            .run()
+            .assert_register(0, 0x55)
+            .assert_register(1, 0x66);
     }
 
     #[test]
-    fn check_ror() {
-        let mut cpu = Arm7tdmi::default();
-        cpu.registers.set_register_at(5, 1);
-        // rd = 5
-        // value in rs = 10
-        cpu.ror(5, 10);
+    fn block_transfer_empty_list() {
+        // STMIA R2!, {}: R15 is stored and the base moves as if there were 16 registers.
+        CpuTest::arm_opcodes(&[0xE8A2_0000])
+            .register(2, 0x100)
+            .run()
+            .assert_memory(0x100, 0xC)
+            .assert_register(2, 0x140);
+
+        // STMDB R2!, {}
+        CpuTest::arm_opcodes(&[0xE922_0000])
+            .register(2, 0x100)
+            .run()
+            .assert_memory(0xC0, 0xC)
+            .assert_register(2, 0xC0);
+    }
 
-        assert_eq!(4194304, cpu.registers.register_at(5));
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.sign_flag());
-        assert!(!cpu.cpsr.carry_flag());
+    #[test]
+    fn single_data_swap() {
+        // SWP R0, R1, [R2]
+        CpuTest::arm_opcodes(&[0xE102_0091])
+            .register(1, 0x1234_5678)
+            .register(2, 0x100)
+            .memory(0x100, 0xCAFE_BABE)
+            .run()
+            .assert_register(0, 0xCAFE_BABE)
+            .assert_memory(0x100, 0x1234_5678);
+
+        // SWPB R3, R3, [R2]: the source is read before the load into the same register.
+        CpuTest::arm_opcodes(&[0xE142_3093])
+            .register(2, 0x100)
+            .register(3, 0xFFFF_FF12)
+            .memory(0x100, 0xCAFE_BABE)
+            .run()
+            .assert_register(3, 0xBE)
+            .assert_memory(0x100, 0xCAFE_BA12);
     }
 
     #[test]
     fn check_psr_transfer() {
         {
             // Covers MRS with CPSR and User mode
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00010_0_001111_0000_000000000000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Cpsr,
@@ -2228,27 +2069,19 @@ mod tests {
                     }
                 }
             );
-            cpu.cpsr.set_mode(&Mode::User);
-
-            cpu.cpsr.set_carry_flag(true);
-            cpu.cpsr.set_overflow_flag(true);
-            cpu.cpsr.set_zero_flag(true);
-            cpu.cpsr.set_sign_flag(true);
-
-            cpu.execute_arm(op_code);
 
-            assert_eq!(
-                0b1111_00000000000000000000_110_10000,
-                cpu.registers.register_at(0),
-            );
+            CpuTest::arm_opcodes(&[op_code])
+                .mode(&Mode::User)
+                .flags("NZCV")
+                .run()
+                .assert_register(0, 0b1111_00000000000000000000_110_10000);
         }
         {
             // Covers MRS with SPSR_fiq
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00010_1_001111_0000_000000000000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Spsr,
@@ -2258,73 +2091,70 @@ mod tests {
                 }
             );
 
-            cpu.register_bank.spsr_fiq.set_state_bit(true);
-            cpu.register_bank.spsr_fiq.set_mode(&Mode::Fiq);
-            cpu.register_bank.spsr_fiq.set_carry_flag(true);
-            cpu.register_bank.spsr_fiq.set_overflow_flag(true);
-            cpu.register_bank.spsr_fiq.set_zero_flag(true);
-            cpu.register_bank.spsr_fiq.set_sign_flag(true);
-
-            cpu.swap_mode(&Mode::Fiq);
+            let mut test = CpuTest::arm_opcodes(&[op_code]);
+            let spsr_fiq = &mut test.cpu.register_bank.spsr_fiq;
+            spsr_fiq.set_state_bit(true);
+            spsr_fiq.set_mode(&Mode::Fiq);
+            spsr_fiq.set_carry_flag(true);
+            spsr_fiq.set_overflow_flag(true);
+            spsr_fiq.set_zero_flag(true);
+            spsr_fiq.set_sign_flag(true);
 
-            cpu.execute_arm(op_code);
-
-            assert_eq!(
-                cpu.registers.register_at(0),
-                0b1111_00000000000000000000_001_10001
-            );
+            test.mode(&Mode::Fiq)
+                .run()
+                .assert_register(0, 0b1111_00000000000000000000_001_10001);
         }
         {
             // Covers MSR with CPSR and User Mode
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00010_0_1010011111_00000000_0000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Cpsr,
                     kind: PsrOpKind::Msr { source_register: 0 }
                 }
             );
-            cpu.cpsr.set_mode(&Mode::User);
 
-            cpu.registers.set_register_at(0, 0b1111 << 28);
-
-            cpu.execute_arm(op_code);
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .mode(&Mode::User)
+                .register(0, 0b1111 << 28)
+                .run();
 
             // All flags set and User mode
-            assert_eq!(0b1111_00000000000000000000_110_10000, u32::from(cpu.cpsr));
+            assert_eq!(
+                u32::from(test.cpu.cpsr),
+                0b1111_00000000000000000000_110_10000
+            );
         }
         {
             // Covers MSR with SPSR_fiq
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00010_1_1010011111_00000000_0000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Spsr,
                     kind: PsrOpKind::Msr { source_register: 0 }
                 }
             );
-            cpu.cpsr.set_mode(&Mode::Fiq);
 
-            cpu.registers.set_register_at(0, 0b1111 << 28 | (0b10001));
-
-            cpu.execute_arm(op_code);
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .mode(&Mode::Fiq)
+                .register(0, 0b1111 << 28 | 0b10001)
+                .run();
 
             // All flags set and Fiq mode
-            assert_eq!(u32::from(cpu.spsr), 0b1111 << 28 | (0b10001));
+            assert_eq!(u32::from(test.cpu.spsr), 0b1111 << 28 | 0b10001);
         }
         {
             // Covers MSR-flags with CPSR and User mode
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00_0_10_0_1010001111_00000000_0000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Cpsr,
@@ -2337,22 +2167,24 @@ mod tests {
                     }
                 }
             );
-            cpu.cpsr.set_mode(&Mode::User);
-
-            cpu.registers.set_register_at(0, 0b1111 << 28);
 
-            cpu.execute_arm(op_code);
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .mode(&Mode::User)
+                .register(0, 0b1111 << 28)
+                .run();
 
             // All flags set and User mode
-            assert_eq!(0b1111_00000000000000000000_110_10000, u32::from(cpu.cpsr));
+            assert_eq!(
+                u32::from(test.cpu.cpsr),
+                0b1111_00000000000000000000_110_10000
+            );
         }
         {
             // Covers MSR-flags with SPSR_fiq
-            let mut cpu = Arm7tdmi::default();
             let op_code = 0b1110_00_0_10_1_1010001111_00000000_0000;
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 ArmModeInstruction::PSRTransfer {
                     condition: Condition::AL,
                     psr_kind: PsrKind::Spsr,
@@ -2365,15 +2197,15 @@ mod tests {
                     }
                 }
             );
-            cpu.cpsr.set_mode(&Mode::Fiq);
 
             // Trying to change MODE bits to a User mode
-            cpu.registers.set_register_at(0, 0b1111 << 28 | (0b10000));
-
-            cpu.execute_arm(op_code);
+            let test = CpuTest::arm_opcodes(&[op_code])
+                .mode(&Mode::Fiq)
+                .register(0, 0b1111 << 28 | 0b10000)
+                .run();
 
             // All flags set
-            assert_eq!(u32::from(cpu.spsr), 0b1111 << 28);
+            assert_eq!(u32::from(test.cpu.spsr), 0b1111 << 28);
         }
     }
 
@@ -2473,10 +2305,9 @@ mod tests {
         }
         {
             let op_code = 0b1110_0101_1101_1111_1101_0000_0001_1000;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 SingleDataTransfer {
                     condition: Condition::AL,
                     kind: SingleDataTransferKind::Ldr,
@@ -2491,20 +2322,15 @@ mod tests {
             );
             #[cfg(feature = "disassembler")]
             {
-                let f = op_code.instruction.disassembler();
+                let f = decoded.instruction.disassembler();
                 assert_eq!(f, "LDRB R13, #24");
             }
 
-            // because in this specific case address will be
-            // then will be 0x03000050 (.wrapping_add(offset))
-            cpu.registers.set_program_counter(0x03000050);
-
-            // simulate mem already contains something.
-            cpu.bus.write_byte(0x03000068, 99);
-
-            cpu.execute_arm(op_code);
-            assert_eq!(cpu.registers.register_at(13), 99);
-            assert_eq!(cpu.registers.program_counter(), 0x03000050);
+            // R15 reads as 8, the byte is at 8 + 24.
+            CpuTest::arm_opcodes(&[op_code])
+                .memory(0x20, 99)
+                .run()
+                .assert_register(13, 99);
         }
     }
 
@@ -2535,10 +2361,9 @@ mod tests {
         }
         {
             let op_code: u32 = 0b1110_0101_1000_0001_0001_0000_0000_0000;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 SingleDataTransfer {
                     condition: Condition::AL,
                     kind: SingleDataTransferKind::Str,
@@ -2553,31 +2378,21 @@ mod tests {
             );
             #[cfg(feature = "disassembler")]
             {
-                let f = op_code.instruction.disassembler();
+                let f = decoded.instruction.disassembler();
                 assert_eq!(f, "STR R1, #0");
             }
-            cpu.registers.set_register_at(1, 16843008);
-
-            // because in this specific case address will be
-            // then will be 0x03000050 + 8 (.wrapping_sub(offset))
-            cpu.registers.set_program_counter(0x03000050);
-
-            cpu.execute_arm(op_code);
 
-            let bus = cpu.bus;
-
-            assert_eq!(bus.read_raw(0x01010100), 0);
-            assert_eq!(bus.read_raw(0x01010100 + 1), 1);
-            assert_eq!(bus.read_raw(0x01010100 + 2), 1);
-            assert_eq!(bus.read_raw(0x01010100 + 3), 1);
-            assert_eq!(cpu.registers.program_counter(), 0x03000050);
+            // The register is both the base and the stored value.
+            CpuTest::arm_opcodes(&[op_code])
+                .register(1, 0x0101_0100)
+                .run()
+                .assert_memory(0x0101_0100, 0x0101_0100);
         }
         {
             let op_code = 0b1110_0101_1100_1111_1101_0000_0001_1000;
-            let mut cpu = Arm7tdmi::default();
-            let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+            let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
             assert_eq!(
-                op_code.instruction,
+                decoded.instruction,
                 SingleDataTransfer {
                     condition: Condition::AL,
                     kind: SingleDataTransferKind::Str,
@@ -2592,31 +2407,24 @@ mod tests {
             );
             #[cfg(feature = "disassembler")]
             {
-                let f = op_code.instruction.disassembler();
+                let f = decoded.instruction.disassembler();
                 assert_eq!(f, "STRB R13, #24");
             }
 
-            // because in this specific case address will be
-            // then will be 0x03000050 (.wrapping_add(offset))
-            cpu.registers.set_program_counter(0x03000050);
-            cpu.registers.set_register_at(13, 50);
-
-            cpu.execute_arm(op_code);
-
-            let memory = cpu.bus.internal_memory;
-
-            assert_eq!(memory.read_at(0x03000068), 50);
-            assert_eq!(cpu.registers.program_counter(), 0x03000050);
+            // R15 reads as 8, the byte goes to 8 + 24.
+            CpuTest::arm_opcodes(&[op_code])
+                .register(13, 50)
+                .run()
+                .assert_memory(0x20, 50);
         }
     }
 
     #[test]
     fn check_ldr_word() {
         let op_code = 0b1110_0101_1001_1111_1101_0000_0010_1000;
-        let mut cpu = Arm7tdmi::default();
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
+        let decoded: ArmModeOpcode = Arm7tdmi::decode(op_code);
         assert_eq!(
-            op_code.instruction,
+            decoded.instruction,
             SingleDataTransfer {
                 condition: Condition::AL,
                 kind: SingleDataTransferKind::Ldr,
@@ -2630,310 +2438,91 @@ mod tests {
             }
         );
 
-        // simulate mem already contains something.
-        // in u32 this is 16843009 00000001_00000001_00000001_00000001.
-        cpu.bus.write_word(0x28, 0x01010101);
-        cpu.execute_arm(op_code);
-        assert_eq!(cpu.registers.register_at(13), 16843009);
-        assert_eq!(cpu.registers.program_counter(), 0);
+        // R15 reads as 8, the word is at 8 + 40.
+        CpuTest::arm_opcodes(&[op_code])
+            .memory(0x30, 0x0101_0101)
+            .run()
+            .assert_register(13, 0x0101_0101);
     }
 
     #[test]
     fn check_multiply_non_halfword_mul() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rd_destination_register: u32 = 7;
-
-        cpu.registers
-            .set_register_at(rm_operand_register as usize, 100);
-        cpu.registers
-            .set_register_at(rs_operand_register as usize, 101);
-        cpu.registers
-            .set_register_at(rd_destination_register as usize, 0);
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(16..=19, rd_destination_register);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0000); // 0000b: MUL{cond}{S}   Rd,Rm,Rs        ;multiply   Rd = Rm*Rs
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        assert_eq!(
-            cpu.registers.register_at(rd_destination_register as usize),
-            10100_u32
-        );
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+        // MULS R7, R5, R6
+        CpuTest::arm_opcodes(&[0xE017_0695])
+            .register(5, 100)
+            .register(6, 101)
+            .register(7, 0)
+            .run()
+            .assert_register(7, 10100)
+            .assert_flags("nzcv");
     }
 
     #[test]
     fn check_multiply_non_halfword_mla() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rd_destination_register: u32 = 7;
-        let rn_acc_register: u32 = 8;
-
-        cpu.registers
-            .set_register_at(rm_operand_register as usize, 100);
-        cpu.registers
-            .set_register_at(rs_operand_register as usize, 101);
-        cpu.registers
-            .set_register_at(rd_destination_register as usize, 0);
-        cpu.registers.set_register_at(rn_acc_register as usize, 32);
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(12..=15, rn_acc_register);
-        op_code.set_bits(16..=19, rd_destination_register);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0001); // 0001b: MLA{cond}{S}   Rd,Rm,Rs,Rn     ;mul.& accumulate Rd = Rm*Rs+Rn
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        assert_eq!(
-            cpu.registers.register_at(rd_destination_register as usize),
-            10132_u32
-        );
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+        // MLAS R7, R5, R6, R8
+        CpuTest::arm_opcodes(&[0xE037_8695])
+            .register(5, 100)
+            .register(6, 101)
+            .register(7, 0)
+            .register(8, 32)
+            .run()
+            .assert_register(7, 10132)
+            .assert_flags("nzcv");
     }
 
     #[test]
     fn check_multiply_long_non_halfword_umull() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rdhi_destination_register: u32 = 7;
-        let rdlo_destination_register: u32 = 8;
-
-        cpu.registers
-            .set_register_at(rm_operand_register as usize, 123456);
-        cpu.registers
-            .set_register_at(rs_operand_register as usize, 654321);
-        cpu.registers
-            .set_register_at(rdhi_destination_register as usize, 0);
-        cpu.registers
-            .set_register_at(rdlo_destination_register as usize, 0);
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(12..=15, rdlo_destination_register);
-        op_code.set_bits(16..=19, rdhi_destination_register);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0100); // 0100b: UMULL{cond}{S} RdLo,RdHi,Rm,Rs ;multiply   RdHiLo=Rm*Rs
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        let rdhi_register_value: u64 =
-            cpu.registers
-                .register_at(rdhi_destination_register as usize) as u64;
-        let rdlo_register_value: u64 =
-            cpu.registers
-                .register_at(rdlo_destination_register as usize) as u64;
-        let rdhilo_register_value: u64 = rdhi_register_value << 32 | rdlo_register_value;
-        assert_eq!(rdhilo_register_value, 123456 * 654321);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+        // UMULLS R8, R7, R5, R6
+        // 123456 * 654321 = 0x12_CEDA_BE40
+        CpuTest::arm_opcodes(&[0xE097_8695])
+            .register(5, 123_456)
+            .register(6, 654_321)
+            .run()
+            .assert_register(7, 0x12)
+            .assert_register(8, 0xCEDA_BE40)
+            .assert_flags("nzcv");
     }
 
     #[test]
     fn check_multiply_long_non_halfword_umlal() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rdhi_destination_register: u32 = 7;
-        let rdlo_destination_register: u32 = 8;
-
-        let operand_1 = 123456_u32;
-        let operand_2 = 654321_u32;
-        let accumulate = 123456789_u32;
-        cpu.registers
-            .set_register_at(rm_operand_register as usize, operand_1);
-        cpu.registers
-            .set_register_at(rs_operand_register as usize, operand_2);
-        cpu.registers
-            .set_register_at(rdhi_destination_register as usize, 0_u32);
-        cpu.registers
-            .set_register_at(rdlo_destination_register as usize, accumulate);
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(12..=15, rdlo_destination_register);
-        op_code.set_bits(16..=19, rdhi_destination_register);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0101); // 0101b: UMLAL{cond}{S} RdLo,RdHi,Rm,Rs ;mul.& acc. RdHiLo=Rm*Rs+RdHiLo
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        let rdhi_register_value: u64 =
-            cpu.registers
-                .register_at(rdhi_destination_register as usize) as u64;
-        let rdlo_register_value: u64 =
-            cpu.registers
-                .register_at(rdlo_destination_register as usize) as u64;
-        let rdhilo_register_value: u64 = rdhi_register_value << 32 | rdlo_register_value;
-
-        let expected = operand_1 as u64 * operand_2 as u64 + accumulate as u64;
-        assert_eq!(rdhilo_register_value, expected);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(!cpu.cpsr.sign_flag());
+        // UMLALS R8, R7, R5, R6
+        // 123456 * 654321 + 123456789 = 0x12_D636_8B55
+        CpuTest::arm_opcodes(&[0xE0B7_8695])
+            .register(5, 123_456)
+            .register(6, 654_321)
+            .register(7, 0)
+            .register(8, 123_456_789)
+            .run()
+            .assert_register(7, 0x12)
+            .assert_register(8, 0xD636_8B55)
+            .assert_flags("nzcv");
     }
 
     #[test]
     fn check_multiply_long_non_halfword_smull() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rdhi_destination_register: u32 = 7;
-        let rdlo_destination_register: u32 = 8;
-
-        let operand_1 = -123456_i32;
-        let operand_2 = 654321_i32;
-        cpu.registers.set_register_at(
-            rm_operand_register as usize,
-            u32::from_be_bytes(operand_1.to_be_bytes()),
-        );
-        cpu.registers.set_register_at(
-            rs_operand_register as usize,
-            u32::from_be_bytes(operand_2.to_be_bytes()),
-        );
-        cpu.registers
-            .set_register_at(rdhi_destination_register as usize, 0_u32);
-        cpu.registers
-            .set_register_at(rdlo_destination_register as usize, 0_u32);
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(12..=15, rdlo_destination_register);
-        op_code.set_bits(16..=19, rdhi_destination_register);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0110); // 0110b: SMULL{cond}{S} RdLo,RdHi,Rm,Rs ;sign.mul.  RdHiLo=Rm*Rs
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        let rdhi_register_value: u64 =
-            cpu.registers
-                .register_at(rdhi_destination_register as usize) as u64;
-        let rdlo_register_value: u64 =
-            cpu.registers
-                .register_at(rdlo_destination_register as usize) as u64;
-        let rdhilo_register_value: u64 = rdhi_register_value << 32 | rdlo_register_value;
-        let rdhilo_register_value: i64 = i64::from_be_bytes(rdhilo_register_value.to_be_bytes());
-
-        let expected = operand_1 as i64 * operand_2 as i64;
-        assert_eq!(rdhilo_register_value, expected);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
+        // SMULLS R8, R7, R5, R6
+        // -123456 * 654321 = 0xFFFF_FFED_3125_41C0
+        CpuTest::arm_opcodes(&[0xE0D7_8695])
+            .register(5, -123_456_i32 as u32)
+            .register(6, 654_321)
+            .run()
+            .assert_register(7, 0xFFFF_FFED)
+            .assert_register(8, 0x3125_41C0)
+            .assert_flags("Nzcv");
     }
 
     #[test]
     fn check_multiply_long_non_halfword_smlal() {
-        let mut cpu = Arm7tdmi::default();
-
-        let rm_operand_register: u32 = 5;
-        let rs_operand_register: u32 = 6;
-        let rdlo_destination_register: u32 = 7;
-        let rdhi_destination_register: u32 = 8;
-
-        let operand_1 = 453_i32;
-        let operand_2 = -754_i32;
-        let accumulate = 98764_i64;
-        let accumulate_u64 = u64::from_be_bytes(accumulate.to_be_bytes());
-        cpu.registers.set_register_at(
-            rm_operand_register as usize,
-            u32::from_be_bytes(operand_1.to_be_bytes()),
-        );
-        cpu.registers.set_register_at(
-            rs_operand_register as usize,
-            u32::from_be_bytes(operand_2.to_be_bytes()),
-        );
-
-        cpu.registers.set_register_at(
-            rdlo_destination_register as usize,
-            accumulate_u64.get_bits(0..=31) as u32,
-        );
-        cpu.registers.set_register_at(
-            rdhi_destination_register as usize,
-            accumulate_u64.get_bits(32..=63) as u32,
-        );
-
-        let mut op_code = 0u32;
-        op_code.set_bits(4..=7, 0b1001);
-        op_code.set_bits(0..=3, rm_operand_register);
-        op_code.set_bits(8..=11, rs_operand_register);
-        op_code.set_bits(12..=15, rdlo_destination_register as u32);
-        op_code.set_bits(16..=19, rdhi_destination_register as u32);
-        op_code.set_bits(20..=20, 0b1);
-        op_code.set_bits(21..=24, 0b0111); // 0111b: SMLAL{cond}{S} RdLo,RdHi,Rm,Rs ;sign.m&a.  RdHiLo=Rm*Rs+RdHiLo
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(25..=27, 0b000);
-        op_code.set_bits(28..=31, Condition::AL as u32);
-
-        let op_code: ArmModeOpcode = Arm7tdmi::decode(op_code);
-
-        cpu.execute_arm(op_code);
-        let rdhi_register_value: u64 =
-            cpu.registers
-                .register_at(rdhi_destination_register as usize) as u64;
-        let rdlo_register_value: u64 =
-            cpu.registers
-                .register_at(rdlo_destination_register as usize) as u64;
-        let rdhilo_register_value: u64 = rdhi_register_value << 32 | rdlo_register_value;
-        let rdhilo_register_value: i64 = i64::from_be_bytes(rdhilo_register_value.to_be_bytes());
-
-        let expected = operand_1 as i64 * operand_2 as i64 + accumulate;
-        assert_eq!(rdhilo_register_value, expected);
-        assert!(!cpu.cpsr.carry_flag());
-        assert!(!cpu.cpsr.zero_flag());
-        assert!(!cpu.cpsr.overflow_flag());
-        assert!(cpu.cpsr.sign_flag());
+        // SMLALS R7, R8, R5, R6
+        // 453 * -754 + 98764 = 0xFFFF_FFFF_FFFC_4B92
+        CpuTest::arm_opcodes(&[0xE0F8_7695])
+            .register(5, 453)
+            .register(6, -754_i32 as u32)
+            .register(7, 98_764)
+            .register(8, 0)
+            .run()
+            .assert_register(7, 0xFFFC_4B92)
+            .assert_register(8, 0xFFFF_FFFF)
+            .assert_flags("Nzcv");
     }
 }
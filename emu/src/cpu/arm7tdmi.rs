@@ -1,5 +1,3 @@
-use std::convert::TryInto;
-
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "logger")]
@@ -8,22 +6,34 @@ use logger::log;
 use vecfixed::VecFixed;
 
 use crate::bitwise::Bits;
-use crate::bus::Bus;
+use crate::bus::{Bus, GbaBus};
 use crate::cpu::arm;
+#[cfg(feature = "disassembler")]
 use crate::cpu::arm::instructions::ArmModeInstruction;
 use crate::cpu::arm::mode::ArmModeOpcode;
+use crate::cpu::call_stack::{CallStack, FrameKind};
 use crate::cpu::cpu_modes::Mode;
+use crate::cpu::decode_cache::DecodeCache;
+use crate::cpu::dispatch::Handlers;
 use crate::cpu::psr::{CpuState, Psr};
 use crate::cpu::register_bank::RegisterBank;
+#[cfg(feature = "disassembler")]
 use crate::cpu::thumb::instruction::Instruction;
 use crate::cpu::thumb::mode::ThumbModeOpcode;
+use crate::debugger::annotations::Annotations;
+use crate::debugger::instruction_stats::InstructionStats;
+use crate::debugger::line_info::LineInfo;
+use crate::debugger::profiler::Profiler;
+use crate::debugger::symbols::Symbols;
+use crate::debugger::trace::{Trace, TraceEntry};
 
 use super::registers::Registers;
 use super::thumb;
 
 #[derive(Serialize, Deserialize)]
-pub struct Arm7tdmi {
-    pub bus: Bus,
+#[serde(bound(deserialize = "B: Bus + Deserialize<'de>"))]
+pub struct Arm7tdmi<B = GbaBus> {
+    pub bus: B,
 
     pub cpsr: Psr,
     pub spsr: Psr,
@@ -40,11 +50,47 @@ pub struct Arm7tdmi {
     decoded_thumb: Option<ThumbModeOpcode>,
 
     pub current_cycle: u128,
+
+    /// Whether the BIOS functions that can be are run by the emulator, the others still
+    /// take the exception. [`crate::gba::Gba`] sets it when the BIOS image is missing.
+    #[serde(default)]
+    pub hle_bios: bool,
+    /// Whether an `IntrWait` run by the emulator is waiting, it's executed again after each
+    /// interrupt and discards the old flags only the first time.
+    #[serde(default)]
+    pub(crate) hle_intr_wait: bool,
+
+    pub call_stack: CallStack,
+
+    /// Names shown by the disassembler and the trace log, loaded by the frontend.
+    #[serde(skip)]
+    pub symbols: Symbols,
+    /// Labels and comments of the user, shown in the disassembly.
+    #[serde(skip)]
+    pub annotations: Annotations,
+    /// Source lines of the code, for homebrew built with debug info.
+    #[serde(skip)]
+    pub line_info: LineInfo,
+    /// Cycles spent in each function, recorded only while it's `Some`.
+    #[serde(skip)]
+    pub profiler: Option<Profiler>,
+    /// Instructions executed by kind, recorded only while it's `Some`.
+    #[serde(skip)]
+    pub instruction_stats: Option<InstructionStats>,
+    /// The last instructions executed, recorded only while it's `Some`.
+    #[serde(skip)]
+    pub trace: Option<Trace>,
+    /// Instructions already decoded, used only while it's `Some`.
+    #[serde(skip)]
+    pub(crate) decode_cache: Option<DecodeCache>,
+    /// Handlers of the instructions by format, see [`Self::set_arm_handler`].
+    #[serde(skip)]
+    pub(crate) handlers: Handlers<B>,
 }
 
 #[derive(Copy, Clone)]
 #[allow(dead_code)]
-enum ExceptionType {
+pub(crate) enum ExceptionType {
     Reset,
     UndefinedInstruction,
     SoftwareInterrupt,
@@ -77,6 +123,15 @@ impl ExceptionType {
         }
     }
 
+    /// Where the code continues after the handler, from the `next_ins` saved in the link register.
+    pub const fn return_address(self, next_ins: usize) -> usize {
+        match self {
+            Self::Irq | Self::Fiq | Self::PrefetchAbort => next_ins - 4,
+            Self::DataAbort => next_ins - 8,
+            Self::Reset | Self::UndefinedInstruction | Self::SoftwareInterrupt => next_ins,
+        }
+    }
+
     pub fn next_instruction_func(
         self,
         current_state: CpuState,
@@ -112,8 +167,55 @@ impl ExceptionType {
 
 impl Default for Arm7tdmi {
     fn default() -> Self {
+        Self::new(GbaBus::default())
+    }
+}
+
+impl Arm7tdmi {
+    /// This function is used to execute the Data Processing instruction.
+    ///
+    /// # Panics
+    /// It can panics if `op_code` is not a `DataProcessing` instruction.
+    pub fn decode<T, V>(op_code: V) -> T
+    where
+        T: std::fmt::Display + TryFrom<V>,
+        <T as TryFrom<V>>::Error: std::fmt::Debug,
+    {
+        T::try_from(op_code).unwrap()
+    }
+
+    /// `count` instructions from `address` with their address, read without side effects.
+    /// `is_thumb` picks the instruction set, the memory can't tell which one it holds.
+    #[cfg(feature = "disassembler")]
+    #[must_use]
+    pub fn disassemble(&self, address: u32, count: usize, is_thumb: bool) -> Vec<(u32, String)> {
+        let size = if is_thumb { 2 } else { 4 };
+
+        (0..count)
+            .scan(address & !(size - 1), |address, _| {
+                let current = *address;
+                *address = address.wrapping_add(size);
+                Some(current)
+            })
+            .map(|address| {
+                let text = if is_thumb {
+                    Instruction::from(self.bus.read_half_word_raw(address as usize)).disassembler()
+                } else {
+                    ArmModeInstruction::from(self.bus.read_word_raw(address as usize))
+                        .disassembler()
+                };
+
+                (address, text)
+            })
+            .collect()
+    }
+}
+
+impl<B: Bus> Arm7tdmi<B> {
+    #[must_use]
+    pub fn new(bus: B) -> Self {
         let mut s = Self {
-            bus: Bus::default(),
+            bus,
             cpsr: Psr::from(Mode::Supervisor), // FIXME: Starting as Supervisor? Not sure
             spsr: Psr::default(),
             registers: Registers::default(),
@@ -125,6 +227,17 @@ impl Default for Arm7tdmi {
             fetched_thumb: None,
             decoded_thumb: None,
             current_cycle: u128::default(),
+            hle_bios: false,
+            hle_intr_wait: false,
+            call_stack: CallStack::default(),
+            symbols: Symbols::default(),
+            annotations: Annotations::default(),
+            line_info: LineInfo::default(),
+            profiler: None,
+            instruction_stats: None,
+            trace: None,
+            decode_cache: None,
+            handlers: Handlers::default(),
         };
 
         // Setting ARM mode at startup
@@ -134,14 +247,39 @@ impl Default for Arm7tdmi {
 
         s
     }
-}
 
-impl Arm7tdmi {
     pub fn flush_pipeline(&mut self) {
         self.decoded_arm = None;
         self.decoded_thumb = None;
         self.fetched_arm = None;
         self.fetched_thumb = None;
+
+        self.call_stack
+            .jumped_to(self.registers.program_counter() as u32);
+    }
+
+    /// Whether the CPU runs Thumb instructions, the T bit of CPSR.
+    #[must_use]
+    pub fn is_thumb(&self) -> bool {
+        matches!(self.cpsr.cpu_state(), CpuState::Thumb)
+    }
+
+    /// Address of the next instruction to execute, the program counter is ahead of it
+    /// by the instructions already in the pipeline.
+    #[must_use]
+    pub fn next_instruction_address(&self) -> u32 {
+        let (size, in_pipeline) = match self.cpsr.cpu_state() {
+            CpuState::Arm => (
+                arm::operations::SIZE_OF_INSTRUCTION,
+                u32::from(self.fetched_arm.is_some()) + u32::from(self.decoded_arm.is_some()),
+            ),
+            CpuState::Thumb => (
+                thumb::operations::SIZE_OF_INSTRUCTION,
+                u32::from(self.fetched_thumb.is_some()) + u32::from(self.decoded_thumb.is_some()),
+            ),
+        };
+
+        (self.registers.program_counter() as u32).wrapping_sub(size * in_pipeline)
     }
 
     #[must_use]
@@ -151,7 +289,7 @@ impl Arm7tdmi {
         pc.set_bit_off(1);
         self.registers.set_program_counter(pc);
 
-        self.bus.read_word(pc as usize)
+        self.bus.fetch32(pc as usize)
     }
 
     #[must_use]
@@ -160,280 +298,96 @@ impl Arm7tdmi {
         pc.set_bit_off(0);
         self.registers.set_program_counter(pc);
 
-        self.bus.read_half_word(pc as usize)
+        self.bus.fetch16(pc as usize)
     }
 
-    /// This function is used to execute the Data Processing instruction.
-    ///
-    /// # Panics
-    /// It can panics if `op_code` is not a `DataProcessing` instruction.
-    pub fn decode<T, V>(op_code: V) -> T
-    where
-        T: std::fmt::Display + TryFrom<V>,
-        <T as TryFrom<V>>::Error: std::fmt::Debug,
-    {
-        T::try_from(op_code).unwrap()
+    fn decode_arm(&mut self, op_code: u32) -> ArmModeOpcode {
+        self.decode_cache
+            .as_mut()
+            .map_or_else(|| Arm7tdmi::decode(op_code), |cache| cache.arm(op_code))
+    }
+
+    fn decode_thumb(&mut self, op_code: u16) -> ThumbModeOpcode {
+        self.decode_cache
+            .as_mut()
+            .map_or_else(|| Arm7tdmi::decode(op_code), |cache| cache.thumb(op_code))
     }
 
-    #[allow(clippy::too_many_lines)]
     pub fn execute_arm(&mut self, op_code: ArmModeOpcode) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(TraceEntry {
+                address: (self.registers.program_counter() as u32).wrapping_sub(8),
+                opcode: op_code.raw,
+                is_thumb: false,
+            });
+        }
+
         // Instruction functions should return whether PC has to be advanced
         // after instruction executed.
-        if !self.cpsr.can_execute(op_code.condition) {
+        if !op_code.condition.is_satisfied(&self.cpsr) {
             return;
         }
 
+        if let Some(stats) = &mut self.instruction_stats {
+            stats.record(false, op_code.instruction.kind());
+        }
+
         #[cfg(feature = "disassembler")]
         {
             let decimal_value = self.registers.program_counter();
             let padded_hex_value = format!("{decimal_value:#04X}");
-            self.disassembler_buffer.push(format!(
-                "{}: {}",
-                padded_hex_value,
-                op_code.instruction.disassembler()
-            ));
-        }
-
-        match op_code.instruction {
-            ArmModeInstruction::DataProcessing {
-                condition: _,
-                alu_instruction,
-                set_conditions,
-                op_kind,
-                rn,
-                destination,
-                op2: _,
-            } => self.data_processing(
-                op_code,
-                alu_instruction,
-                set_conditions,
-                op_kind,
-                rn,
-                destination,
-            ),
-            ArmModeInstruction::PSRTransfer { psr_kind, kind, .. } => {
-                self.psr_transfer(kind, psr_kind);
-            }
-            ArmModeInstruction::Multiply {
-                variant,
-                should_set_codes,
-                rd_destination_register,
-                rn_accumulate_register,
-                rm_operand_register,
-                rs_operand_register,
-                ..
-            } => self.multiply(
-                variant,
-                should_set_codes,
-                rd_destination_register,
-                rn_accumulate_register,
-                rm_operand_register,
-                rs_operand_register,
-            ),
-            ArmModeInstruction::MultiplyLong {
-                variant,
-                should_set_codes,
-                rdhi_destination_register,
-                rdlo_destination_register,
-                rm_operand_register,
-                rs_operand_register,
-                ..
-            } => self.multiply_long(
-                variant,
-                should_set_codes,
-                rdhi_destination_register,
-                rdlo_destination_register,
-                rm_operand_register,
-                rs_operand_register,
-            ),
-            ArmModeInstruction::SingleDataSwap => todo!(),
-            ArmModeInstruction::BranchAndExchange {
-                condition: _,
-                register,
-            } => self.branch_and_exchange(register),
-            ArmModeInstruction::HalfwordDataTransfer {
-                condition: _,
-                indexing,
-                offsetting,
-                write_back,
-                load_store_kind,
-                offset_kind,
-                base_register,
-                source_destination_register,
-                transfer_kind,
-            } => self.half_word_data_transfer(
-                indexing,
-                offsetting,
-                write_back,
-                load_store_kind,
-                offset_kind,
-                base_register,
-                source_destination_register,
-                transfer_kind,
-            ),
-            ArmModeInstruction::SingleDataTransfer {
-                condition: _,
-                kind,
-                quantity,
-                write_back,
-                indexing,
-                rd,
-                base_register,
-                offset_info,
-                offsetting,
-            } => self.single_data_transfer(
-                kind,
-                quantity,
-                write_back,
-                indexing,
-                rd,
-                base_register,
-                offset_info,
-                offsetting,
-            ),
-            ArmModeInstruction::Undefined => todo!(),
-            ArmModeInstruction::BlockDataTransfer {
-                condition: _,
-                indexing,
-                offsetting,
-                load_psr,
-                write_back,
-                load_store,
-                rn,
-                register_list: reg_list,
-            } => self.block_data_transfer(
-                indexing, offsetting, load_psr, write_back, load_store, rn, reg_list,
-            ),
-            ArmModeInstruction::Branch {
-                condition: _,
-                link,
-                offset,
-            } => self.branch(link, offset),
-            ArmModeInstruction::CoprocessorDataTransfer { .. } => todo!(),
-            ArmModeInstruction::CoprocessorDataOperation => todo!(),
-            ArmModeInstruction::CoprocessorRegisterTransfer => todo!(),
-            ArmModeInstruction::SoftwareInterrupt => {
-                self.handle_exception(ExceptionType::SoftwareInterrupt);
-            }
-        };
+            self.push_disassembly(
+                (decimal_value as u32).wrapping_sub(8),
+                format!(
+                    "{}: {}",
+                    padded_hex_value,
+                    op_code.instruction.disassembler()
+                ),
+            );
+        }
+
+        self.dispatch_arm(op_code);
     }
 
     /// This function is used to execute the Data Processing instruction.
     ///
     /// # Panics
     /// It can panics if destination register is None.
-    #[allow(clippy::too_many_lines)]
     pub fn execute_thumb(&mut self, op_code: ThumbModeOpcode) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(TraceEntry {
+                address: (self.registers.program_counter() as u32).wrapping_sub(4),
+                opcode: op_code.raw.into(),
+                is_thumb: true,
+            });
+        }
+
+        if let Some(stats) = &mut self.instruction_stats {
+            stats.record(true, op_code.instruction.kind());
+        }
+
         #[cfg(feature = "disassembler")]
         {
             let decimal_value = self.registers.program_counter();
             let padded_hex_value = format!("{decimal_value:#04X}");
-            self.disassembler_buffer.push(format!(
-                "{padded_hex_value}: {}",
-                op_code.instruction.disassembler()
-            ));
-        }
-
-        match op_code.instruction {
-            Instruction::MoveShiftedRegister {
-                shift_operation: op,
-                offset5,
-                source_register,
-                destination_register,
-            } => self.move_shifted_reg(op, offset5, source_register, destination_register),
-            Instruction::AddSubtract {
-                operation_kind,
-                op,
-                rn_offset3,
-                source_register: rs,
-                destination_register: rd,
-            } => self.add_subtract(operation_kind, op, rn_offset3, rs, rd),
-            Instruction::MoveCompareAddSubtractImm {
-                operation: op,
-                destination_register: r_destination,
-                offset,
-            } => self.move_compare_add_sub_imm(op, r_destination, offset),
-            Instruction::AluOp {
-                alu_operation: op,
-                source_register: rs,
-                destination_register: rd,
-            } => self.alu_op(op, rs, rd),
-            Instruction::HiRegisterOpBX {
-                register_operation: op,
-                source_register,
-                destination_register,
-            } => self.hi_reg_operation_branch_ex(op, source_register, destination_register),
-            Instruction::PCRelativeLoad {
-                destination_register: r_destination,
-                immediate_value,
-            } => self.pc_relative_load(r_destination, immediate_value),
-            Instruction::LoadStoreRegisterOffset {
-                load_store,
-                byte_word,
-                ro,
-                base_register: rb,
-                destination_register: rd,
-            } => self.load_store_register_offset(load_store, byte_word, ro, rb, rd),
-            Instruction::LoadStoreSignExtByteHalfword {
-                h: h_flag,
-                sign_extend_flag,
-                offset_register: r_offset,
-                base_register: r_base,
-                destination_register: r_destination,
-            } => self.load_store_sign_extend_byte_halfword(
-                h_flag,
-                sign_extend_flag,
-                r_offset,
-                r_base,
-                r_destination,
-            ),
-            Instruction::LoadStoreImmOffset => self.load_store_immediate_offset(op_code),
-            Instruction::LoadStoreHalfword {
-                load_store,
-                offset,
-                base_register,
-                source_destination_register,
-            } => self.load_store_halfword(
-                load_store,
-                offset,
-                base_register,
-                source_destination_register,
-            ),
-            Instruction::SPRelativeLoadStore {
-                load_store,
-                destination_register: r_destination,
-                word8,
-            } => self.sp_relative_load_store(load_store, r_destination, word8),
-            Instruction::LoadAddress {
-                sp,
-                destination_register: r_destination,
-                offset,
-            } => self.load_address(sp, r_destination.try_into().unwrap(), offset),
-            Instruction::AddOffsetSP { s, word7 } => self.add_offset_sp(s, word7),
-            Instruction::PushPopReg {
-                load_store,
-                pc_lr,
-                register_list,
-            } => self.push_pop_register(load_store, pc_lr, register_list),
-            Instruction::MultipleLoadStore {
-                load_store,
-                base_register,
-                register_list,
-            } => self.multiple_load_store(load_store, base_register as usize, register_list),
-            Instruction::CondBranch {
-                condition,
-                immediate_offset,
-            } => self.cond_branch(condition, immediate_offset),
-            Instruction::Swi => unimplemented!(),
-            Instruction::UncondBranch { offset } => self.uncond_branch(offset),
-            Instruction::LongBranchLink { h, offset } => self.long_branch_link(h, offset),
-        };
+            self.push_disassembly(
+                (decimal_value as u32).wrapping_sub(4),
+                format!("{padded_hex_value}: {}", op_code.instruction.disassembler()),
+            );
+        }
+
+        self.dispatch_thumb(op_code);
     }
 
-    fn handle_exception(&mut self, exception_type: ExceptionType) {
+    pub(crate) fn handle_exception(&mut self, exception_type: ExceptionType) {
         let next_ins = exception_type
             .next_instruction_func(self.cpsr.cpu_state(), self.registers.program_counter())(
         );
+        self.call_stack.push(
+            exception_type.address() as u32,
+            exception_type.return_address(next_ins) as u32,
+            FrameKind::Exception,
+        );
 
         let old_cpsr = self.cpsr;
 
@@ -454,32 +408,71 @@ impl Arm7tdmi {
         let new_pc = exception_type.address() as u32;
         self.registers.set_program_counter(new_pc);
 
-        self.decoded_arm = Some(Self::decode(self.fetch_arm()));
+        self.decoded_arm = Some(Arm7tdmi::decode(self.fetch_arm()));
         self.registers
             .set_program_counter(new_pc + arm::operations::SIZE_OF_INSTRUCTION);
 
         self.fetched_arm = Some(self.fetch_arm());
     }
 
+    /// Adds the line of the instruction at `address` to the disassembly, after its label
+    /// and with the comment of the user. Labels of the user take the place of the symbols.
+    #[cfg(feature = "disassembler")]
+    fn push_disassembly(&mut self, address: u32, line: String) {
+        let label = self
+            .annotations
+            .label(address)
+            .or_else(|| self.symbols.at(address).map(|symbol| symbol.name.as_str()));
+        if let Some(label) = label {
+            let label = format!("{label}:");
+            self.disassembler_buffer.push(label);
+        }
+
+        let line = match self.annotations.comment(address) {
+            Some(comment) => format!("{line:<40} ; {comment}"),
+            None => line,
+        };
+        self.disassembler_buffer.push(line);
+    }
+
+    /// Logs the name of the symbol starting at `address`, like a label in the trace.
+    #[cfg(feature = "logger")]
+    fn log_symbol(&self, address: usize) {
+        if let Some(symbol) = self.symbols.at(address as u32) {
+            log(format!("{}:", symbol.name));
+        }
+    }
+
     pub fn step(&mut self) {
         self.current_cycle += 1;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(&self.call_stack);
+        }
+
         match self.cpsr.cpu_state() {
             CpuState::Thumb => {
                 let to_execute = self.decoded_thumb;
 
-                self.decoded_thumb = self.fetched_thumb.map(Self::decode);
+                self.decoded_thumb = self.fetched_thumb.map(|op_code| self.decode_thumb(op_code));
                 self.fetched_thumb = Some(self.fetch_thumb());
 
                 if let Some(decoded) = to_execute {
-                    if !self.cpsr.irq_disable() && self.bus.is_irq_pending() {
+                    if !self.cpsr.irq_disable() && self.bus.irq_pending() {
                         self.handle_exception(ExceptionType::Irq);
 
                         return;
                     }
 
+                    self.bus.instruction_executed(
+                        self.registers.program_counter() - 4,
+                        thumb::operations::SIZE_OF_INSTRUCTION as usize,
+                    );
+
                     #[cfg(feature = "logger")]
                     let current_ins = self.registers.program_counter() - 4;
                     #[cfg(feature = "logger")]
+                    self.log_symbol(current_ins);
+                    #[cfg(feature = "logger")]
                     log(format!("PC: 0x{current_ins:X} {decoded}"));
 
                     self.execute_thumb(decoded);
@@ -498,19 +491,26 @@ impl Arm7tdmi {
             CpuState::Arm => {
                 let to_execute = self.decoded_arm;
 
-                self.decoded_arm = self.fetched_arm.map(Self::decode);
+                self.decoded_arm = self.fetched_arm.map(|op_code| self.decode_arm(op_code));
                 self.fetched_arm = Some(self.fetch_arm());
 
                 if let Some(decoded) = to_execute {
-                    if !self.cpsr.irq_disable() && self.bus.is_irq_pending() {
+                    if !self.cpsr.irq_disable() && self.bus.irq_pending() {
                         self.handle_exception(ExceptionType::Irq);
 
                         return;
                     }
 
+                    self.bus.instruction_executed(
+                        self.registers.program_counter() - 8,
+                        arm::operations::SIZE_OF_INSTRUCTION as usize,
+                    );
+
                     #[cfg(feature = "logger")]
                     let current_ins = self.registers.program_counter() - 8;
                     #[cfg(feature = "logger")]
+                    self.log_symbol(current_ins);
+                    #[cfg(feature = "logger")]
                     log(format!("PC: 0x{current_ins:X} {decoded}"));
 
                     self.execute_arm(decoded);
@@ -528,14 +528,6 @@ impl Arm7tdmi {
         }
     }
 
-    #[must_use]
-    pub fn new(bus: Bus) -> Self {
-        Self {
-            bus,
-            ..Default::default()
-        }
-    }
-
     #[allow(clippy::too_many_lines)]
     pub fn swap_mode(&mut self, new_mode: &Mode) {
         if self.cpsr.mode() == *new_mode {
@@ -666,7 +658,7 @@ impl Arm7tdmi {
         // in the address is 1.
 
         let rotation = ((address & 0b1) * 8) as u32;
-        let mut value = (self.bus.read_half_word(address) as u32).rotate_right(rotation);
+        let mut value = (self.bus.read16(address) as u32).rotate_right(rotation);
 
         if sign_extended {
             let is_halfword_aligned: bool = address & 0b1 == 0;
@@ -675,7 +667,7 @@ impl Arm7tdmi {
             // If the address was not halfword aligned we rotated it so that the selected halfword in now
             // in the lower 8 bits. We should extend only these 8 bits, making this operation equal to
             // a Load sign-extended Byte.
-            value = value.sign_extended(if is_halfword_aligned { 16 } else { 8 });
+            value.sign_extend(if is_halfword_aligned { 16 } else { 8 });
         }
 
         value
@@ -687,7 +679,7 @@ impl Arm7tdmi {
         // So if the last 2 bits of the address are 01, we still word-align the address but the byte 1 of the
         // read word will be in the lower 0-7 bits of the register. That's why we rotate it.
         let rotation = ((address & 0b11) * 8) as u32;
-        self.bus.read_word(address).rotate_right(rotation)
+        self.bus.read32(address).rotate_right(rotation)
     }
 }
 
@@ -720,9 +712,17 @@ impl std::fmt::Display for HalfwordTransferKind {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::cast_sign_loss,
+    clippy::should_panic_without_expect,
+    clippy::too_many_lines,
+    clippy::unreadable_literal,
+    clippy::unusual_byte_groupings
+)]
 mod tests {
     use pretty_assertions::assert_eq;
 
+    use crate::cpu::arm::instructions::ArmModeInstruction;
     use crate::cpu::condition::Condition;
     use crate::cpu::flags::{HalfwordDataTransferOffsetKind, Indexing, LoadStoreKind, Offsetting};
     use crate::cpu::registers::{REG_LR, REG_PROGRAM_COUNTER, REG_SP};
@@ -730,6 +730,35 @@ mod tests {
 
     use super::*;
 
+    #[cfg(feature = "disassembler")]
+    #[test]
+    fn disassemble() {
+        let mut cpu = Arm7tdmi::default();
+        // MOV R0, #1, then MOVS R1, #2 and ADDS R2, R1, R0 in Thumb.
+        for (address, byte) in (0x0300_0000..).zip([0x01, 0x00, 0xA0, 0xE3, 0x02, 0x21, 0x42, 0x18])
+        {
+            cpu.bus.write_raw(address, byte);
+        }
+
+        let arm = cpu.disassemble(0x0300_0002, 1, false);
+        assert_eq!(
+            arm,
+            vec![(
+                0x0300_0000,
+                ArmModeInstruction::from(0xE3A0_0001).disassembler()
+            )]
+        );
+
+        let thumb = cpu.disassemble(0x0300_0004, 2, true);
+        assert_eq!(
+            thumb,
+            vec![
+                (0x0300_0004, Instruction::from(0x2102).disassembler()),
+                (0x0300_0006, Instruction::from(0x1842).disassembler()),
+            ]
+        );
+    }
+
     #[test]
     fn arm_branch() {
         // Covers a positive offset
@@ -764,6 +793,42 @@ mod tests {
         assert_eq!(cpu.registers.register_at(14), 24 - 4);
     }
 
+    #[test]
+    fn arm_call_stack() {
+        use crate::cpu::call_stack::Frame;
+
+        let mut cpu = Arm7tdmi::default();
+
+        // BL +60 at 0x100
+        cpu.registers.set_program_counter(0x108);
+        cpu.execute_arm(Arm7tdmi::decode(0b1110_1011_0000_0000_0000_0000_0000_1111));
+        assert_eq!(
+            cpu.call_stack.frames(),
+            &[Frame {
+                function: 0x144,
+                return_address: 0x104,
+                kind: FrameKind::Call,
+            }]
+        );
+
+        // MOV LR, PC; BX R0 at 0x200
+        cpu.registers.set_program_counter(0x208);
+        cpu.registers.set_register_at(14, 0x204);
+        cpu.registers.set_register_at(0, 0x300);
+        cpu.execute_arm(Arm7tdmi::decode(0xE12F_FF10));
+        assert_eq!(cpu.call_stack.frames().len(), 2);
+        assert_eq!(cpu.call_stack.frames()[1].function, 0x300);
+
+        // BX LR returns to 0x204, then to 0x104
+        cpu.registers.set_program_counter(0x308);
+        cpu.execute_arm(Arm7tdmi::decode(0xE12F_FF1E));
+        assert_eq!(cpu.call_stack.frames().len(), 1);
+
+        cpu.registers.set_register_at(14, 0x104);
+        cpu.execute_arm(Arm7tdmi::decode(0xE12F_FF1E));
+        assert!(cpu.call_stack.frames().is_empty());
+    }
+
     #[test]
     #[should_panic]
     fn arm_unknown_instruction() {
@@ -1318,6 +1383,63 @@ mod tests {
         assert_eq!(cpu.registers.program_counter(), 1606);
     }
 
+    #[test]
+    fn thumb_uncond_branch_backward() {
+        let mut cpu = Arm7tdmi::default();
+        // B #-4, the offset is sign extended from 12 bits
+        let op_code = 0b1110_0111_1111_1110;
+        let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+        cpu.registers.set_program_counter(1000);
+
+        cpu.execute_thumb(op_code);
+
+        assert_eq!(cpu.registers.program_counter(), 996);
+    }
+
+    #[test]
+    fn thumb_cond_branch_conditions() {
+        // BHI #254, taken only with C set and Z clear
+        let op_code: ThumbModeOpcode = Arm7tdmi::decode(0b1101_1000_0111_1111);
+
+        for (carry, zero, taken) in [
+            (false, false, false),
+            (true, false, true),
+            (false, true, false),
+            (true, true, false),
+        ] {
+            let mut cpu = Arm7tdmi::default();
+            cpu.registers.set_program_counter(1000);
+            cpu.cpsr.set_carry_flag(carry);
+            cpu.cpsr.set_zero_flag(zero);
+
+            cpu.execute_thumb(op_code);
+
+            let expected = if taken { 1000 + 254 } else { 1000 };
+            assert_eq!(cpu.registers.program_counter(), expected);
+        }
+    }
+
+    #[test]
+    fn thumb_swi() {
+        let mut cpu = Arm7tdmi::default();
+        cpu.cpsr.set_cpu_state(CpuState::Thumb);
+        let op_code: ThumbModeOpcode = Arm7tdmi::decode(0b1101_1111_0000_0101);
+
+        assert_eq!(op_code.instruction, Instruction::Swi { comment: 5 });
+
+        // The SWI is at 0x08000100, PC is 4 bytes ahead of it.
+        cpu.registers.set_program_counter(0x0800_0104);
+
+        cpu.execute_thumb(op_code);
+
+        assert_eq!(cpu.cpsr.mode(), Mode::Supervisor);
+        assert_eq!(cpu.cpsr.cpu_state(), CpuState::Arm);
+        assert_eq!(cpu.spsr.cpu_state(), CpuState::Thumb);
+        assert_eq!(cpu.registers.register_at(REG_LR), 0x0800_0102);
+        assert_eq!(cpu.registers.program_counter(), 0x8 + 4);
+    }
+
     #[test]
     fn thumb_hi_reg_operation_branch_ex() {
         {
@@ -1457,6 +1579,60 @@ mod tests {
 
             assert_eq!(cpu.registers.register_at(9), 10);
         }
+        {
+            // Mov Hd, Hs reading PC, which is 4 bytes ahead of the instruction
+            let mut cpu = Arm7tdmi::default();
+            let op_code = 0b010001_10_1_1_111_000;
+            let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+            cpu.registers.set_program_counter(0x0800_0104);
+
+            cpu.execute_thumb(op_code);
+
+            assert_eq!(cpu.registers.register_at(8), 0x0800_0104);
+        }
+        {
+            // Mov PC, Hs is a branch which stays in Thumb state
+            let mut cpu = Arm7tdmi::default();
+            cpu.cpsr.set_cpu_state(CpuState::Thumb);
+            let op_code = 0b010001_10_1_1_110_111;
+            let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+            cpu.registers.set_register_at(REG_LR, 0x0800_0201);
+            cpu.fetched_thumb = Some(0);
+
+            cpu.execute_thumb(op_code);
+
+            assert_eq!(cpu.registers.program_counter(), 0x0800_0200);
+            assert_eq!(cpu.cpsr.cpu_state(), CpuState::Thumb);
+            assert_eq!(cpu.fetched_thumb, None);
+        }
+        {
+            // Add PC, Rs
+            let mut cpu = Arm7tdmi::default();
+            let op_code = 0b010001_00_1_0_001_111;
+            let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+            cpu.registers.set_program_counter(0x0800_0104);
+            cpu.registers.set_register_at(1, 0x11);
+
+            cpu.execute_thumb(op_code);
+
+            assert_eq!(cpu.registers.program_counter(), 0x0800_0114);
+        }
+        {
+            // BX Hs to ARM state is word aligned
+            let mut cpu = Arm7tdmi::default();
+            cpu.cpsr.set_cpu_state(CpuState::Thumb);
+            let op_code: u16 = 0b0100_0111_0100_0000;
+            let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+            cpu.registers.set_register_at(8, 0x0800_0102);
+            cpu.execute_thumb(op_code);
+
+            assert_eq!(cpu.registers.program_counter(), 0x0800_0100);
+            assert_eq!(cpu.cpsr.cpu_state(), CpuState::Arm);
+        }
     }
 
     #[test]
@@ -1622,6 +1798,19 @@ mod tests {
 
             assert_eq!(cpu.bus.read_word(100 + 0b11100), 999);
         }
+        {
+            // Load with the highest offset
+            let mut cpu = Arm7tdmi::default();
+            let op_code = 0b1001_1_111_11111111;
+            let op_code: ThumbModeOpcode = Arm7tdmi::decode(op_code);
+
+            cpu.registers.set_register_at(REG_SP, 0x0300_7C00);
+            cpu.bus.write_word(0x0300_7C00 + 1020, 0xDEAD_BEEF);
+
+            cpu.execute_thumb(op_code);
+
+            assert_eq!(cpu.registers.register_at(7), 0xDEAD_BEEF);
+        }
     }
 
     #[test]
@@ -0,0 +1,1204 @@
+//! BIOS functions run by the emulator in place of the BIOS code, for games run without a
+//! BIOS image (see [`Arm7tdmi::hle_bios`]). The image is replaced by [`stub_bios`], which
+//! only has the exception vectors and the IRQ handler, and the console boots straight to
+//! the game. Only the functions games need are here: the resets, the waits for
+//! interrupts, the arithmetic, the copies, the affine matrices and the decompression. The
+//! sound driver of the BIOS does nothing, so games using it are silent but keep running.
+//!
+//! The results are the ones of the BIOS to the bit, with the approximations of its
+//! arctangent and its table of sines.
+
+use std::f64::consts::TAU;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use crate::bus::Bus;
+use crate::cpu::arm7tdmi::Arm7tdmi;
+use crate::cpu::cpu_modes::Mode;
+use crate::cpu::psr::CpuState;
+use crate::cpu::registers::{REG_LR, REG_SP};
+use crate::memory_map::BIOS_SIZE;
+
+/// Width of the writes to the destination: VRAM can't be written by bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Granularity {
+    Byte,
+    HalfWord,
+}
+
+/// Address of SOUNDBIAS, the level is in bits 0-9.
+const SOUNDBIAS: usize = 0x0400_0088;
+
+/// Addresses of IE, IF and IME.
+const IE: usize = 0x0400_0200;
+const IF: usize = 0x0400_0202;
+const IME: usize = 0x0400_0208;
+
+/// The interrupts handled since the last `IntrWait`, the IRQ handlers of the games set
+/// their flags there.
+const BIOS_IF: usize = 0x0300_7FF8;
+
+/// `SoftReset` boots from EWRAM if this byte isn't 0, from the cartridge otherwise.
+const RESET_TO_EWRAM: usize = 0x0300_7FFA;
+
+const ROM_START: u32 = 0x0800_0000;
+const EWRAM_START: u32 = 0x0200_0000;
+
+/// What `GetBiosChecksum` returns with the BIOS of the GBA.
+const BIOS_CHECKSUM: u32 = 0xBAAE_187F;
+
+/// The code of [`stub_bios`] at its addresses. The exception vectors return at once but
+/// the reset one, calling `SoftReset`, and the IRQ one, going to the IRQ handler of the
+/// BIOS which calls the one of the game at 0x03FFFFFC.
+const STUB_CODE: [(usize, u32); 13] = [
+    (0x00, 0xEF00_0000),  // SWI 0x00
+    (0x04, 0xE1B0_F00E),  // MOVS PC, LR
+    (0x08, 0xE1B0_F00E),  // MOVS PC, LR
+    (0x0C, 0xE25E_F004),  // SUBS PC, LR, #4
+    (0x10, 0xE25E_F008),  // SUBS PC, LR, #8
+    (0x18, 0xEA00_0042),  // B 0x128
+    (0x1C, 0xE25E_F004),  // SUBS PC, LR, #4
+    (0x128, 0xE92D_500F), // STMFD SP!, {R0-R3, R12, LR}
+    (0x12C, 0xE3A0_0301), // MOV R0, #0x04000000
+    (0x130, 0xE28F_E000), // ADD LR, PC, #0
+    (0x134, 0xE510_F004), // LDR PC, [R0, #-4]
+    (0x138, 0xE8BD_500F), // LDMFD SP!, {R0-R3, R12, LR}
+    (0x13C, 0xE25E_F004), // SUBS PC, LR, #4
+];
+
+/// The areas `RegisterRamReset` clears with the bit of its flags picking them: EWRAM,
+/// IWRAM but its last 0x200 bytes (the stacks), the palettes, VRAM, OAM, the serial
+/// registers, the sound registers and the other registers.
+const RAM_RESET_AREAS: [(u32, Range<usize>); 11] = [
+    (0, 0x0200_0000..0x0204_0000),
+    (1, 0x0300_0000..0x0300_7E00),
+    (2, 0x0500_0000..0x0500_0400),
+    (3, 0x0600_0000..0x0601_8000),
+    (4, 0x0700_0000..0x0700_0400),
+    (5, 0x0400_0120..0x0400_0130),
+    (5, 0x0400_0140..0x0400_015C),
+    (6, 0x0400_0060..0x0400_00A8),
+    (7, 0x0400_0000..0x0400_0060),
+    (7, 0x0400_00B0..0x0400_0120),
+    (7, 0x0400_0200..0x0400_020A),
+];
+
+/// Addresses of DISPCNT and RCNT, the first register reset to 0x80 (forced blank), the
+/// second to 0x8000 (general purpose mode).
+const DISPCNT: usize = 0x0400_0000;
+const RCNT: usize = 0x0400_0134;
+
+/// Cycles halted at most before the SWI waiting for an interrupt runs again, a scanline.
+const HALT_CYCLES: u32 = 1232;
+
+/// The sines of the BIOS, a turn in 256 steps in 1.14 fixed point.
+static SINES: LazyLock<[i32; 256]> = LazyLock::new(|| {
+    std::array::from_fn(|step| ((step as f64 * TAU / 256.0).sin() * 16384.0).round() as i32)
+});
+
+/// Cycles of the BIOS functions, roughly the ones of the BIOS code: a fixed cost for the
+/// arithmetic and the calls doing next to nothing, a cost per matrix, per copied unit and
+/// per decompressed byte for the others.
+const DIV_CYCLES: u32 = 300;
+const SQRT_CYCLES: u32 = 400;
+const ARC_TAN_CYCLES: u32 = 120;
+const ARC_TAN_2_CYCLES: u32 = 350;
+const BG_AFFINE_SET_CYCLES: u32 = 110;
+const OBJ_AFFINE_SET_CYCLES: u32 = 60;
+/// `CpuSet` moves a unit per loop, `CpuFastSet` 8 words per `LDMIA`/`STMIA`.
+const CPU_SET_CYCLES: u32 = 10;
+const CPU_FAST_SET_CYCLES: u32 = 3;
+const DECOMPRESS_CYCLES: u32 = 20;
+const CALL_CYCLES: u32 = 20;
+
+/// Runs the BIOS function `function` and takes its cycles, it returns `false` if it's not
+/// one of these.
+pub fn call<B: Bus>(cpu: &mut Arm7tdmi<B>, function: u8) -> bool {
+    let [r0, r1, r2, r3] = [0, 1, 2, 3].map(|register| cpu.registers.register_at(register));
+
+    let cycles = match function {
+        0x00 => {
+            soft_reset(cpu);
+            CALL_CYCLES
+        }
+        0x01 => {
+            let half_words = register_ram_reset(&mut cpu.bus, r0);
+            CALL_CYCLES + (half_words / 2).saturating_mul(CPU_FAST_SET_CYCLES)
+        }
+        // Stop waits like Halt, the LCD and the sound keep running.
+        0x02 | 0x03 => {
+            halt(cpu);
+            CALL_CYCLES
+        }
+        0x04 => {
+            intr_wait(cpu, r0 != 0, r1 as u16);
+            CALL_CYCLES
+        }
+        0x05 => {
+            intr_wait(cpu, true, 1);
+            CALL_CYCLES
+        }
+        0x06 => {
+            div(cpu, r0 as i32, r1 as i32);
+            DIV_CYCLES
+        }
+        0x07 => {
+            div(cpu, r1 as i32, r0 as i32);
+            DIV_CYCLES
+        }
+        0x08 => {
+            cpu.registers.set_register_at(0, r0.isqrt());
+            SQRT_CYCLES
+        }
+        0x09 => {
+            let (angle, square, polynomial) = arc_tan(r0 as i32);
+            cpu.registers.set_register_at(0, angle as u32);
+            cpu.registers.set_register_at(1, square as u32);
+            cpu.registers.set_register_at(3, polynomial as u32);
+            ARC_TAN_CYCLES
+        }
+        0x0A => {
+            let angle = arc_tan_2(r0 as i32, r1 as i32);
+            cpu.registers.set_register_at(0, u32::from(angle as u16));
+            ARC_TAN_2_CYCLES
+        }
+        0x0B => {
+            let units = cpu_set(&mut cpu.bus, r0 as usize, r1 as usize, r2);
+            CALL_CYCLES + units.saturating_mul(CPU_SET_CYCLES)
+        }
+        0x0C => {
+            let words = cpu_fast_set(&mut cpu.bus, r0 as usize, r1 as usize, r2);
+            CALL_CYCLES + words.saturating_mul(CPU_FAST_SET_CYCLES)
+        }
+        0x0D => {
+            cpu.registers.set_register_at(0, BIOS_CHECKSUM);
+            CALL_CYCLES
+        }
+        0x0E => {
+            bg_affine_set(&mut cpu.bus, r0 as usize, r1 as usize, r2);
+            r2.saturating_mul(BG_AFFINE_SET_CYCLES)
+        }
+        0x0F => {
+            obj_affine_set(&mut cpu.bus, r0 as usize, r1 as usize, r2, r3 as usize);
+            r2.saturating_mul(OBJ_AFFINE_SET_CYCLES)
+        }
+        0x11..=0x18 => {
+            let size = decompress(&mut cpu.bus, function, r0 as usize, r1 as usize);
+            (size as u32).saturating_mul(DECOMPRESS_CYCLES)
+        }
+        0x19 => {
+            sound_bias(&mut cpu.bus, r0 != 0);
+            CALL_CYCLES
+        }
+        // SoundDriverInit, Mode, Main, VSync, ChannelClear, the unnamed 0x20-0x24 and
+        // VSyncOff, VSyncOn.
+        0x1A..=0x1E | 0x20..=0x24 | 0x28 | 0x29 => CALL_CYCLES,
+        0x1F => {
+            let frequency = midi_key_to_freq(cpu.bus.read32(r0 as usize + 4), r1, r2);
+            cpu.registers.set_register_at(0, frequency);
+            CALL_CYCLES
+        }
+        _ => return false,
+    };
+    cpu.bus.internal_cycles(cycles);
+
+    true
+}
+
+/// The BIOS image used in place of a missing one, see [`STUB_CODE`].
+#[must_use]
+pub fn stub_bios() -> [u8; BIOS_SIZE] {
+    let mut bios = [0; BIOS_SIZE];
+    for (address, opcode) in STUB_CODE {
+        bios[address..address + 4].copy_from_slice(&opcode.to_le_bytes());
+    }
+
+    bios
+}
+
+/// Whether `bios` is missing: blank, what the frontends load without a BIOS file, or
+/// already replaced by [`stub_bios`].
+#[must_use]
+pub fn is_missing(bios: &[u8]) -> bool {
+    bios.iter().all(|&byte| byte == 0) || bios == stub_bios()
+}
+
+/// Jumps to `entry` like the BIOS at the end of a reset: the stacks of the Supervisor, IRQ
+/// and System modes set, the other registers cleared and the CPU in System mode, in ARM
+/// state with the interrupts enabled.
+pub fn boot<B: Bus>(cpu: &mut Arm7tdmi<B>, entry: u32) {
+    for (mode, stack) in [
+        (Mode::Supervisor, 0x0300_7FE0),
+        (Mode::Irq, 0x0300_7FA0),
+        (Mode::System, 0x0300_7F00),
+    ] {
+        cpu.swap_mode(&mode);
+        cpu.registers.set_register_at(REG_SP, stack);
+        cpu.registers.set_register_at(REG_LR, 0);
+    }
+    for register in 0..=12 {
+        cpu.registers.set_register_at(register, 0);
+    }
+
+    cpu.cpsr.set_cpu_state(CpuState::Arm);
+    cpu.cpsr.set_irq_disable(false);
+    cpu.cpsr.set_fiq_disable(false);
+    cpu.registers.set_program_counter(entry);
+    cpu.flush_pipeline();
+}
+
+/// Clears the stacks and the flags of the BIOS at the top of IWRAM and boots again.
+pub fn soft_reset<B: Bus>(cpu: &mut Arm7tdmi<B>) {
+    let entry = if cpu.bus.read8(RESET_TO_EWRAM) == 0 {
+        ROM_START
+    } else {
+        EWRAM_START
+    };
+    for address in (0x0300_7E00..0x0300_8000).step_by(4) {
+        cpu.bus.write32(address, 0);
+    }
+
+    boot(cpu, entry);
+}
+
+/// Clears the areas of [`RAM_RESET_AREAS`] picked by the bits of `flags`, it returns the
+/// half words written.
+fn register_ram_reset<B: Bus>(bus: &mut B, flags: u32) -> u32 {
+    let mut half_words = 0;
+    for (bit, area) in RAM_RESET_AREAS {
+        if flags & (1 << bit) == 0 {
+            continue;
+        }
+
+        for address in area.step_by(2) {
+            bus.write16(address, 0);
+            half_words += 1;
+        }
+    }
+
+    if flags & (1 << 5) != 0 {
+        bus.write16(RCNT, 0x8000);
+    }
+    if flags & (1 << 7) != 0 {
+        bus.write16(DISPCNT, 0x80);
+    }
+
+    half_words
+}
+
+/// Halts until an interrupt enabled in IE is requested. The wait is cut in slices after
+/// which the SWI runs again, so the interrupt is taken before the SWI returns and the
+/// frontend gets its events.
+fn halt<B: Bus>(cpu: &mut Arm7tdmi<B>) {
+    if cpu.bus.read16(IE) & cpu.bus.read16(IF) != 0 {
+        return;
+    }
+
+    cpu.bus.halt(HALT_CYCLES);
+    run_again(cpu);
+}
+
+/// Waits for one of the interrupts of `flags` to be handled, the IRQ handler of the game
+/// tells it in [`BIOS_IF`], and acknowledges it there. The flags set before are
+/// discarded first if `discard`. It halts like [`halt`] until then, with IME set.
+fn intr_wait<B: Bus>(cpu: &mut Arm7tdmi<B>, discard: bool, flags: u16) {
+    if !cpu.hle_intr_wait {
+        cpu.bus.write16(IME, 1);
+        if discard {
+            let handled = cpu.bus.read16(BIOS_IF);
+            cpu.bus.write16(BIOS_IF, handled & !flags);
+        }
+    }
+
+    let handled = cpu.bus.read16(BIOS_IF);
+    if handled & flags != 0 {
+        cpu.bus.write16(BIOS_IF, handled & !flags);
+        cpu.hle_intr_wait = false;
+        return;
+    }
+
+    cpu.hle_intr_wait = true;
+    cpu.bus.halt(HALT_CYCLES);
+    run_again(cpu);
+}
+
+/// Goes back to the SWI being executed, the program counter is 2 instructions ahead.
+fn run_again<B: Bus>(cpu: &mut Arm7tdmi<B>) {
+    let size = match cpu.cpsr.cpu_state() {
+        CpuState::Arm => 4,
+        CpuState::Thumb => 2,
+    };
+
+    cpu.registers
+        .set_program_counter(cpu.registers.program_counter() as u32 - 2 * size);
+    cpu.flush_pipeline();
+}
+
+/// Quotient in r0, remainder in r1 and absolute quotient in r3. The BIOS never returns
+/// from a division by zero, here it gives the sign of the numerator.
+const fn div<B: Bus>(cpu: &mut Arm7tdmi<B>, numerator: i32, denominator: i32) {
+    let (quotient, remainder) = if denominator == 0 {
+        (if numerator < 0 { -1 } else { 1 }, numerator)
+    } else {
+        (
+            numerator.wrapping_div(denominator),
+            numerator.wrapping_rem(denominator),
+        )
+    };
+
+    cpu.registers.set_register_at(0, quotient as u32);
+    cpu.registers.set_register_at(1, remainder as u32);
+    cpu.registers.set_register_at(3, quotient.unsigned_abs());
+}
+
+/// The arctangent of a tangent in 1.14 fixed point, from -0x4000 to 0x4000 for -π/2 to
+/// π/2. It's the polynomial of the BIOS, the error grows near the ends. It returns the
+/// angle and what's left in r1 and r3.
+fn arc_tan(tangent: i32) -> (i32, i32, i32) {
+    let square = -(tangent.wrapping_mul(tangent) >> 14);
+    let polynomial = [0x390, 0x91C, 0xFB6, 0x16AA, 0x2081, 0x3651, 0xA2F9]
+        .into_iter()
+        .fold(0xA9_i32, |polynomial, coefficient| {
+            (polynomial.wrapping_mul(square) >> 14) + coefficient
+        });
+
+    let angle = i32::from((tangent.wrapping_mul(polynomial) >> 16) as i16);
+
+    (angle, square, polynomial)
+}
+
+/// Copies (or fills with the first unit if bit 24 of `control` is set) the number of units
+/// in bits 0-20 of `control`, half words or words if bit 26 is set. It returns the units.
+fn cpu_set<B: Bus>(bus: &mut B, source: usize, destination: usize, control: u32) -> u32 {
+    // The BIOS ignores sources in the BIOS and the unused area after it.
+    if source & 0x0E00_0000 == 0 {
+        return 0;
+    }
+
+    let units = control & 0x1F_FFFF;
+    let fill = control & (1 << 24) != 0;
+    let unit_size = if control & (1 << 26) == 0 { 2 } else { 4 };
+    // Like the BIOS, the addresses are aligned to the unit.
+    let source = source & !(unit_size - 1);
+    let destination = destination & !(unit_size - 1);
+
+    for index in 0..units as usize {
+        let from = if fill {
+            source
+        } else {
+            source + index * unit_size
+        };
+        let to = destination + index * unit_size;
+        if unit_size == 4 {
+            let value = bus.read32(from);
+            bus.write32(to, value);
+        } else {
+            let value = bus.read16(from);
+            bus.write16(to, value);
+        }
+    }
+
+    units
+}
+
+/// [`cpu_set`] of words only, their number in bits 0-20 of `control` is rounded up to a
+/// multiple of 8. It returns the words.
+fn cpu_fast_set<B: Bus>(bus: &mut B, source: usize, destination: usize, control: u32) -> u32 {
+    let words = (control & 0x1F_FFFF).next_multiple_of(8);
+
+    cpu_set(
+        bus,
+        source,
+        destination,
+        words | control & (1 << 24) | 1 << 26,
+    )
+}
+
+/// The angle of the point (`x`, `y`), a turn from 0 to 0x10000.
+fn arc_tan_2(x: i32, y: i32) -> i32 {
+    let from_x = || arc_tan((y << 14).wrapping_div(x)).0;
+    let from_y = || arc_tan((x << 14).wrapping_div(y)).0;
+    // Like the BIOS, the octant comes from the magnitudes, which don't overflow for i32::MIN.
+    let (x_magnitude, y_magnitude) = (x.unsigned_abs(), y.unsigned_abs());
+
+    match (x, y) {
+        (x, 0) if x >= 0 => 0,
+        (_, 0) => 0x8000,
+        (0, y) if y >= 0 => 0x4000,
+        (0, _) => 0xC000,
+        (x, y) if y >= 0 && x >= 0 && x_magnitude >= y_magnitude => from_x(),
+        (x, y) if y >= 0 && x < 0 && x_magnitude >= y_magnitude => from_x() + 0x8000,
+        (_, y) if y >= 0 => 0x4000 - from_y(),
+        (x, _) if x <= 0 && x_magnitude > y_magnitude => from_x() + 0x8000,
+        (x, _) if x > 0 && x_magnitude >= y_magnitude => from_x() + 0x10000,
+        _ => 0xC000 - from_y(),
+    }
+}
+
+/// The matrix scaling by `scale_x`, `scale_y` (8.8 fixed point) and rotating by the top
+/// byte of `angle`: PA, PB, PC and PD.
+fn affine_matrix(scale_x: i32, scale_y: i32, angle: u16) -> [i32; 4] {
+    let step = usize::from(angle >> 8);
+    let sin = SINES[step];
+    let cos = SINES[(step + 0x40) & 0xFF];
+
+    [
+        (scale_x * cos) >> 14,
+        (-scale_x * sin) >> 14,
+        (scale_y * sin) >> 14,
+        (scale_y * cos) >> 14,
+    ]
+}
+
+/// `count` BG matrices and their start point, from the centers of the rotation in the
+/// BG and on the screen, the scales and the angle.
+fn bg_affine_set<B: Bus>(bus: &mut B, source: usize, destination: usize, count: u32) {
+    for index in 0..count as usize {
+        let source = source + index * 20;
+        let destination = destination + index * 16;
+
+        let center_x = bus.read32(source) as i32;
+        let center_y = bus.read32(source + 4) as i32;
+        let [screen_x, screen_y, scale_x, scale_y] =
+            [8, 10, 12, 14].map(|offset| i32::from(bus.read16(source + offset) as i16));
+        let angle = bus.read16(source + 16);
+
+        let matrix = affine_matrix(scale_x, scale_y, angle);
+        for (offset, value) in (0..).step_by(2).zip(matrix) {
+            bus.write16(destination + offset, value as u16);
+        }
+
+        let [pa, pb, pc, pd] = matrix;
+        // Like the BIOS, the start point wraps around with extreme values.
+        let start_x = center_x.wrapping_sub(
+            pa.wrapping_mul(screen_x)
+                .wrapping_add(pb.wrapping_mul(screen_y)),
+        );
+        let start_y = center_y.wrapping_sub(
+            pc.wrapping_mul(screen_x)
+                .wrapping_add(pd.wrapping_mul(screen_y)),
+        );
+        bus.write32(destination + 8, start_x as u32);
+        bus.write32(destination + 12, start_y as u32);
+    }
+}
+
+/// `count` OBJ (or BG) matrices from the scales and the angle. The parameters are `stride`
+/// bytes apart: 2 for a BG, 8 for the OBJ attributes.
+fn obj_affine_set<B: Bus>(
+    bus: &mut B,
+    source: usize,
+    destination: usize,
+    count: u32,
+    stride: usize,
+) {
+    for index in 0..count as usize {
+        let source = source + index * 8;
+        let destination = destination + index * 4 * stride;
+
+        let scale_x = i32::from(bus.read16(source) as i16);
+        let scale_y = i32::from(bus.read16(source + 2) as i16);
+        let angle = bus.read16(source + 4);
+
+        let matrix = affine_matrix(scale_x, scale_y, angle);
+        for (parameter, value) in matrix.into_iter().enumerate() {
+            bus.write16(destination + parameter * stride, value as u16);
+        }
+    }
+}
+
+/// Moves the bias level to 0x200 (or to 0 when `on` is false). The BIOS gets there a step
+/// at a time, here it's immediate.
+fn sound_bias<B: Bus>(bus: &mut B, on: bool) {
+    let bias = bus.read16(SOUNDBIAS) & !0x3FF;
+    bus.write16(SOUNDBIAS, bias | if on { 0x200 } else { 0 });
+}
+
+/// The frequency to play the note `key` plus `fine` (in 1/256 of a semitone) of a sample
+/// recorded at `frequency` for the key 180.
+fn midi_key_to_freq(frequency: u32, key: u32, fine: u32) -> u32 {
+    let semitones = 180.0 - f64::from(key) - f64::from(fine) / 256.0;
+
+    (f64::from(frequency) / (semitones / 12.0).exp2()) as u32
+}
+
+/// Source and destination are in r0 and r1. The data starts with a header word: the type
+/// of compression in bits 4-7, a parameter in bits 0-3 and the decompressed size in bits
+/// 8-31. It returns the bytes written.
+fn decompress<B: Bus>(bus: &mut B, function: u8, source: usize, destination: usize) -> usize {
+    // The BIOS ignores sources in the BIOS and the unused area after it.
+    if source & 0x0E00_0000 == 0 {
+        return 0;
+    }
+
+    let data = match function {
+        0x11 | 0x12 => lz77(bus, source),
+        0x13 => huffman(bus, source),
+        0x14 | 0x15 => run_length(bus, source),
+        0x16 | 0x17 => diff_8(bus, source),
+        _ => diff_16(bus, source),
+    };
+
+    let granularity = match function {
+        0x11 | 0x14 | 0x16 => Granularity::Byte,
+        _ => Granularity::HalfWord,
+    };
+    write(bus, destination, &data, granularity);
+
+    data.len()
+}
+
+/// The decompressed size in the header at `source`.
+fn size<B: Bus>(bus: &mut B, source: usize) -> usize {
+    (bus.read32(source) >> 8) as usize
+}
+
+fn write<B: Bus>(bus: &mut B, destination: usize, data: &[u8], granularity: Granularity) {
+    match granularity {
+        Granularity::Byte => {
+            for (offset, &byte) in data.iter().enumerate() {
+                bus.write8(destination + offset, byte);
+            }
+        }
+        Granularity::HalfWord => {
+            for (offset, pair) in (0..).step_by(2).zip(data.chunks(2)) {
+                let address = destination + offset;
+                // The last byte of an odd size keeps the byte after it.
+                let high = pair
+                    .get(1)
+                    .copied()
+                    .unwrap_or_else(|| bus.read8(address + 1));
+                bus.write16(address, u16::from_le_bytes([pair[0], high]));
+            }
+        }
+    }
+}
+
+/// Blocks of 8, each a raw byte or a copy of 3 to 18 bytes already decompressed, told
+/// apart by the bits of a flag byte from the most significant.
+fn lz77<B: Bus>(bus: &mut B, source: usize) -> Vec<u8> {
+    let size = size(bus, source);
+    let mut data = Vec::with_capacity(size);
+    let mut address = source + 4;
+
+    while data.len() < size {
+        let flags = bus.read8(address);
+        address += 1;
+
+        for block in (0..8).rev() {
+            if data.len() >= size {
+                break;
+            }
+
+            if flags & (1 << block) == 0 {
+                data.push(bus.read8(address));
+                address += 1;
+                continue;
+            }
+
+            let first = bus.read8(address);
+            let second = bus.read8(address + 1);
+            address += 2;
+            let length = usize::from(first >> 4) + 3;
+            let distance = (usize::from(first & 0xF) << 8 | usize::from(second)) + 1;
+
+            for _ in 0..length {
+                // A distance past the start reads zeros, the real BIOS reads memory there.
+                let byte = data
+                    .len()
+                    .checked_sub(distance)
+                    .map_or(0, |start| data[start]);
+                data.push(byte);
+            }
+        }
+    }
+
+    data.truncate(size);
+    data
+}
+
+/// Runs of 3 to 130 copies of a byte, or 1 to 128 raw bytes.
+fn run_length<B: Bus>(bus: &mut B, source: usize) -> Vec<u8> {
+    let size = size(bus, source);
+    let mut data = Vec::with_capacity(size);
+    let mut address = source + 4;
+
+    while data.len() < size {
+        let flag = bus.read8(address);
+        address += 1;
+
+        if flag & 0x80 == 0 {
+            for _ in 0..=flag & 0x7F {
+                data.push(bus.read8(address));
+                address += 1;
+            }
+        } else {
+            let byte = bus.read8(address);
+            address += 1;
+            data.extend(std::iter::repeat_n(byte, usize::from(flag & 0x7F) + 3));
+        }
+    }
+
+    data.truncate(size);
+    data
+}
+
+/// Codes of 4 or 8 bits (bits 0-3 of the header) found by walking a tree with the bits of
+/// the stream, in words read from the most significant bit. The codes fill words from the
+/// least significant bits.
+fn huffman<B: Bus>(bus: &mut B, source: usize) -> Vec<u8> {
+    let code_bits = bus.read32(source) & 0xF;
+    let size = size(bus, source);
+    if !matches!(code_bits, 4 | 8) {
+        return Vec::new();
+    }
+    let tree = source + 4;
+    let root = tree + 1;
+    let mut stream = tree + (usize::from(bus.read8(tree)) + 1) * 2;
+
+    let mut data = Vec::with_capacity(size);
+    let mut word = 0_u32;
+    let mut word_bits = 0;
+    let mut node = root;
+
+    while data.len() < size {
+        let bits = bus.read32(stream);
+        stream += 4;
+
+        for bit in (0..32).rev() {
+            // Bits 0-5 are the offset of the children, bits 7 and 6 tell if they are codes.
+            let value = bus.read8(node);
+            let children = (node & !1) + usize::from(value & 0x3F) * 2 + 2;
+            let (child, is_code) = if bits & (1 << bit) == 0 {
+                (children, value & 0x80 != 0)
+            } else {
+                (children + 1, value & 0x40 != 0)
+            };
+
+            if !is_code {
+                node = child;
+                continue;
+            }
+
+            word |= (u32::from(bus.read8(child)) & ((1 << code_bits) - 1)) << word_bits;
+            word_bits += code_bits;
+            node = root;
+            if word_bits == 32 {
+                data.extend_from_slice(&word.to_le_bytes());
+                word = 0;
+                word_bits = 0;
+                if data.len() >= size {
+                    break;
+                }
+            }
+        }
+    }
+
+    data.truncate(size);
+    data
+}
+
+/// Bytes stored as the difference with the one before.
+fn diff_8<B: Bus>(bus: &mut B, source: usize) -> Vec<u8> {
+    let size = size(bus, source);
+    let mut previous = 0_u8;
+
+    (0..size)
+        .map(|offset| {
+            previous = previous.wrapping_add(bus.read8(source + 4 + offset));
+            previous
+        })
+        .collect()
+}
+
+/// Half words stored as the difference with the one before.
+fn diff_16<B: Bus>(bus: &mut B, source: usize) -> Vec<u8> {
+    let size = size(bus, source);
+    let mut previous = 0_u16;
+
+    (0..size)
+        .step_by(2)
+        .flat_map(|offset| {
+            previous = previous.wrapping_add(bus.read16(source + 4 + offset));
+            previous.to_le_bytes()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::cpu::test_bus::TestBus;
+    use crate::cpu::test_dsl::assemble;
+
+    const SOURCE: u32 = 0x0200_1000;
+    const DESTINATION: u32 = 0x0200_2000;
+
+    /// A CPU running the BIOS functions in HLE, with `registers` from r0.
+    fn hle_cpu(registers: &[u32]) -> Arm7tdmi<TestBus> {
+        let mut cpu = Arm7tdmi::new(TestBus::default());
+        cpu.hle_bios = true;
+        for (register, &value) in registers.iter().enumerate() {
+            cpu.registers.set_register_at(register, value);
+        }
+
+        cpu
+    }
+
+    fn swi(cpu: &mut Arm7tdmi<TestBus>, function: u8) {
+        cpu.execute_arm(Arm7tdmi::decode(0xEF00_0000 | u32::from(function) << 16));
+    }
+
+    /// Runs the SWI `function` on `compressed`, it returns the first `size` bytes of the
+    /// destination and the one after.
+    fn decompress(function: u8, compressed: &[u8], size: usize) -> Vec<u8> {
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION]);
+        for (offset, &byte) in compressed.iter().enumerate() {
+            cpu.bus.write8(SOURCE as usize + offset, byte);
+        }
+        cpu.bus.write8(DESTINATION as usize + size, 0xEE);
+
+        swi(&mut cpu, function);
+
+        (0..=size)
+            .map(|offset| cpu.bus.read8(DESTINATION as usize + offset))
+            .collect()
+    }
+
+    /// Header of `kind` (type and parameter) for `size` bytes.
+    fn header(kind: u8, size: u32) -> [u8; 4] {
+        (u32::from(kind) | size << 8).to_le_bytes()
+    }
+
+    #[test]
+    fn div() {
+        let mut cpu = hle_cpu(&[(-7_i32) as u32, 2]);
+        swi(&mut cpu, 0x06);
+        let results = [0, 1, 3].map(|register| cpu.registers.register_at(register) as i32);
+        assert_eq!(results, [-3, -1, 3]);
+
+        // DivArm takes the denominator first.
+        let mut cpu = hle_cpu(&[2, 7]);
+        swi(&mut cpu, 0x07);
+        assert_eq!(cpu.registers.register_at(0), 3);
+        assert_eq!(cpu.registers.register_at(1), 1);
+    }
+
+    #[test]
+    fn sqrt() {
+        let mut cpu = hle_cpu(&[1000]);
+        swi(&mut cpu, 0x08);
+        assert_eq!(cpu.registers.register_at(0), 31);
+    }
+
+    #[test]
+    fn arc_tan() {
+        // The angles of 1, 0.5 and -1 in 1.14 fixed point, π is 0x8000.
+        for (tangent, angle) in [(0x4000, 0x2000), (0x2000, 4836), (-0x4000, -0x2000)] {
+            let mut cpu = hle_cpu(&[tangent as u32]);
+            swi(&mut cpu, 0x09);
+            assert_eq!(cpu.registers.register_at(0) as i32, angle, "{tangent:#X}");
+        }
+    }
+
+    #[test]
+    fn arc_tan_2() {
+        for (x, y, angle) in [
+            (0x1000, 0, 0),
+            (0, 0x1000, 0x4000),
+            (-0x1000, 0, 0x8000),
+            (0, -0x1000, 0xC000),
+            (0x1000, 0x1000, 0x2000),
+            (-0x1000, 0x1000, 0x6000),
+            (-0x1000, -0x1000, 0xA000),
+            (0x1000, -0x1000, 0xE000),
+        ] {
+            let mut cpu = hle_cpu(&[x as u32, y as u32]);
+            swi(&mut cpu, 0x0A);
+            assert_eq!(cpu.registers.register_at(0), angle, "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn arc_tan_2_of_the_smallest_values() {
+        for (x, y, angle) in [
+            (i32::MIN, 0, 0x8000),
+            (i32::MIN, 1, 0x8000),
+            (0, i32::MIN, 0xC000),
+            (i32::MIN, -1, 0x8000),
+        ] {
+            let mut cpu = hle_cpu(&[x as u32, y as u32]);
+            swi(&mut cpu, 0x0A);
+            assert_eq!(cpu.registers.register_at(0), angle, "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn sines() {
+        assert_eq!(SINES[0], 0);
+        assert_eq!(SINES[0x20], 0x2D41);
+        assert_eq!(SINES[0x40], 0x4000);
+        assert_eq!(SINES[0xC0], -0x4000);
+    }
+
+    #[test]
+    fn obj_affine_set() {
+        // Scales of 1 and 2, a quarter of a turn. OAM parameters are 8 bytes apart.
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 1, 8]);
+        for (offset, value) in [0x100, 0x200, 0x4000].into_iter().enumerate() {
+            cpu.bus.write16(SOURCE as usize + offset * 2, value);
+        }
+        swi(&mut cpu, 0x0F);
+
+        let matrix = [0, 8, 16, 24].map(|offset| cpu.bus.read16(DESTINATION as usize + offset));
+        assert_eq!(matrix, [0, (-0x100_i16) as u16, 0x200, 0]);
+    }
+
+    #[test]
+    fn bg_affine_set() {
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 1]);
+        let source = SOURCE as usize;
+        // Center (256, 128) of the BG at (120, 80) on the screen, scales of 1, no rotation.
+        cpu.bus.write32(source, 256 << 8);
+        cpu.bus.write32(source + 4, 128 << 8);
+        for (offset, value) in [120, 80, 0x100, 0x100, 0].into_iter().enumerate() {
+            cpu.bus.write16(source + 8 + offset * 2, value);
+        }
+        swi(&mut cpu, 0x0E);
+
+        let destination = DESTINATION as usize;
+        let matrix = [0, 2, 4, 6].map(|offset| cpu.bus.read16(destination + offset));
+        assert_eq!(matrix, [0x100, 0, 0, 0x100]);
+        assert_eq!(cpu.bus.read32(destination + 8), (256 - 120) << 8);
+        assert_eq!(cpu.bus.read32(destination + 12), (128 - 80) << 8);
+    }
+
+    #[test]
+    fn bg_affine_set_wraps_around() {
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 1]);
+        let source = SOURCE as usize;
+        // The lowest center, screen point and scales: PA and PD are -0x8000.
+        cpu.bus.write32(source, i32::MIN as u32);
+        cpu.bus.write32(source + 4, i32::MIN as u32);
+        for (offset, value) in [0x8000, 0x8000, 0x8000, 0x8000, 0].into_iter().enumerate() {
+            cpu.bus.write16(source + 8 + offset * 2, value);
+        }
+        swi(&mut cpu, 0x0E);
+
+        let destination = DESTINATION as usize;
+        let matrix = [0, 2, 4, 6].map(|offset| cpu.bus.read16(destination + offset));
+        assert_eq!(matrix, [0x8000, 0, 0, 0x8000]);
+        assert_eq!(cpu.bus.read32(destination + 8), 0x4000_0000);
+        assert_eq!(cpu.bus.read32(destination + 12), 0x4000_0000);
+    }
+
+    #[test]
+    fn sound_bias() {
+        let mut cpu = hle_cpu(&[1]);
+        cpu.bus.write16(SOUNDBIAS, 0xC000);
+        swi(&mut cpu, 0x19);
+        assert_eq!(cpu.bus.read16(SOUNDBIAS), 0xC200);
+
+        cpu.registers.set_register_at(0, 0);
+        swi(&mut cpu, 0x19);
+        assert_eq!(cpu.bus.read16(SOUNDBIAS), 0xC000);
+    }
+
+    #[test]
+    fn midi_key_to_freq() {
+        // A sample at 0x3A0000 an octave down, then half a semitone up.
+        let mut cpu = hle_cpu(&[SOURCE, 180 - 12, 0]);
+        cpu.bus.write32(SOURCE as usize + 4, 0x3A_0000);
+        swi(&mut cpu, 0x1F);
+        assert_eq!(cpu.registers.register_at(0), 0x1D_0000);
+
+        let mut cpu = hle_cpu(&[SOURCE, 180, 128]);
+        cpu.bus.write32(SOURCE as usize + 4, 0x3A_0000);
+        swi(&mut cpu, 0x1F);
+        assert_eq!(cpu.registers.register_at(0), 0x3B_B314);
+    }
+
+    #[test]
+    fn sound_driver_does_nothing() {
+        let mut cpu = hle_cpu(&[SOURCE]);
+        cpu.registers.set_program_counter(0x0200_0108);
+        for function in [0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x28, 0x29] {
+            swi(&mut cpu, function);
+            assert_eq!(cpu.registers.program_counter(), 0x0200_0108);
+            assert_eq!(cpu.bus.read32(SOURCE as usize), 0);
+        }
+    }
+
+    #[test]
+    fn lz77() {
+        let mut data = header(0x10, 10).to_vec();
+        // Raw, raw, raw, copy of 6 from 3 back, raw.
+        data.extend([0b0001_0000, b'A', b'B', b'C', 0x30, 0x02, b'D']);
+
+        assert_eq!(decompress(0x11, &data, 10), b"ABCABCABCD\xEE");
+
+        // By half words, the byte after an odd size is kept.
+        let mut data = header(0x10, 3).to_vec();
+        data.extend([0, b'X', b'Y', b'Z']);
+        assert_eq!(decompress(0x12, &data, 3), b"XYZ\xEE");
+    }
+
+    #[test]
+    fn run_length() {
+        let mut data = header(0x30, 7).to_vec();
+        data.extend([0x82, b'A', 0x01, b'X', b'Y']);
+
+        assert_eq!(decompress(0x14, &data, 7), b"AAAAAXY\xEE");
+        assert_eq!(decompress(0x15, &data, 7), b"AAAAAXY\xEE");
+    }
+
+    #[test]
+    fn huffman() {
+        // A root with two codes, `a` for a 0 bit and `b` for a 1.
+        let mut data = header(0x28, 4).to_vec();
+        data.extend([1, 0xC0, b'a', b'b']);
+        data.extend(0x6000_0000_u32.to_le_bytes());
+
+        assert_eq!(decompress(0x13, &data, 4), b"abba\xEE");
+
+        let mut data = header(0x24, 4).to_vec();
+        data.extend([1, 0xC0, 0x1, 0x2]);
+        data.extend(0x5500_0000_u32.to_le_bytes());
+
+        assert_eq!(decompress(0x13, &data, 4), [0x21, 0x21, 0x21, 0x21, 0xEE]);
+    }
+
+    #[test]
+    fn diff() {
+        let mut data = header(0x81, 3).to_vec();
+        data.extend([1, 1, 0xFF]);
+        assert_eq!(decompress(0x16, &data, 3), [1, 2, 1, 0xEE]);
+
+        let mut data = header(0x82, 4).to_vec();
+        data.extend([0x00, 0x01, 0x00, 0x02]);
+        assert_eq!(decompress(0x18, &data, 4), [0x00, 0x01, 0x00, 0x03, 0xEE]);
+    }
+
+    #[test]
+    fn calls_take_cycles() {
+        let mut cpu = hle_cpu(&[100, 7]);
+        let start = cpu.bus.cycles();
+        swi(&mut cpu, 0x06);
+        assert!(cpu.bus.cycles() >= start + u128::from(DIV_CYCLES));
+
+        // Each of the 3 matrices, on top of its memory accesses.
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 3, 2]);
+        let start = cpu.bus.cycles();
+        swi(&mut cpu, 0x0F);
+        assert!(cpu.bus.cycles() >= start + 3 * u128::from(OBJ_AFFINE_SET_CYCLES));
+
+        // Each of the 16 decompressed bytes.
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION]);
+        for (offset, byte) in header(0x30, 16).into_iter().chain([0x8D, 0x55]).enumerate() {
+            cpu.bus.write8(SOURCE as usize + offset, byte);
+        }
+        let start = cpu.bus.cycles();
+        swi(&mut cpu, 0x14);
+        assert!(cpu.bus.cycles() >= start + 16 * u128::from(DECOMPRESS_CYCLES));
+
+        // Each of the 100 copied half words and of the 104 words of the rounded up count.
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 100]);
+        let start = cpu.bus.cycles();
+        swi(&mut cpu, 0x0B);
+        assert!(cpu.bus.cycles() >= start + 100 * u128::from(CPU_SET_CYCLES));
+
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, 100]);
+        let start = cpu.bus.cycles();
+        swi(&mut cpu, 0x0C);
+        assert!(cpu.bus.cycles() >= start + 104 * u128::from(CPU_FAST_SET_CYCLES));
+    }
+
+    /// Runs the SWI `function` with `control` in r2 on a source of words counting from 1,
+    /// it returns the first `words` words of the destination.
+    fn copy(function: u8, control: u32, words: usize) -> Vec<u32> {
+        let mut cpu = hle_cpu(&[SOURCE, DESTINATION, control]);
+        for index in 0..32 {
+            cpu.bus
+                .write32(SOURCE as usize + index * 4, index as u32 + 1);
+        }
+        swi(&mut cpu, function);
+
+        (0..words)
+            .map(|index| cpu.bus.read32(DESTINATION as usize + index * 4))
+            .collect()
+    }
+
+    #[test]
+    fn cpu_set() {
+        // 3 half words, 2 words, 2 words filled with the first one.
+        assert_eq!(copy(0x0B, 3, 3), [1, 2, 0]);
+        assert_eq!(copy(0x0B, 2 | 1 << 26, 3), [1, 2, 0]);
+        assert_eq!(copy(0x0B, 2 | 1 << 24 | 1 << 26, 3), [1, 1, 0]);
+        // Half words filled with the first one.
+        assert_eq!(copy(0x0B, 4 | 1 << 24, 3), [0x0001_0001, 0x0001_0001, 0]);
+    }
+
+    #[test]
+    fn cpu_fast_set() {
+        // The count is rounded up to 8 words.
+        assert_eq!(copy(0x0C, 1, 9), [1, 2, 3, 4, 5, 6, 7, 8, 0]);
+        assert_eq!(
+            copy(0x0C, 9 | 1 << 24, 17),
+            [[1; 16].as_slice(), &[0]].concat()
+        );
+    }
+
+    #[test]
+    fn cpu_set_ignores_the_bios() {
+        let mut cpu = hle_cpu(&[0x100, DESTINATION, 4 | 1 << 26]);
+        cpu.bus.write32(DESTINATION as usize, 0xEE);
+        swi(&mut cpu, 0x0B);
+
+        assert_eq!(cpu.bus.read32(DESTINATION as usize), 0xEE);
+    }
+
+    #[test]
+    fn stub_bios() {
+        let bios = super::stub_bios();
+        let opcode =
+            |address: usize| u32::from_le_bytes(bios[address..address + 4].try_into().unwrap());
+
+        // The code written with the instructions the assembler of the tests knows.
+        for (address, line) in [
+            (0x00, "SWI #0"),
+            (0x04, "MOVS PC, LR"),
+            (0x0C, "SUBS PC, LR, #4"),
+            (0x10, "SUBS PC, LR, #8"),
+            (0x18, "B #0x128"),
+            (0x12C, "MOV R0, #0x04000000"),
+            (0x130, "ADD LR, PC, #0"),
+            (0x134, "LDR PC, [R0, #-4]"),
+        ] {
+            let expected = if line.starts_with("SWI") {
+                0xEF00_0000
+            } else {
+                assemble(line, address as u32)
+            };
+            assert_eq!(opcode(address), expected, "{line}");
+        }
+
+        assert!(is_missing(&[0; BIOS_SIZE]));
+        assert!(is_missing(&bios));
+        assert!(!is_missing(&[[0; 0x100].as_slice(), &[1]].concat()));
+    }
+
+    #[test]
+    fn soft_reset() {
+        for (flag, entry) in [(0, ROM_START), (1, EWRAM_START)] {
+            let mut cpu = hle_cpu(&[1, 2, 3]);
+            cpu.bus.write8(RESET_TO_EWRAM, flag);
+            cpu.bus.write32(0x0300_7E00, 0x1234);
+            cpu.cpsr.set_irq_disable(true);
+            swi(&mut cpu, 0x00);
+
+            assert_eq!(cpu.registers.program_counter(), entry as usize);
+            assert_eq!(cpu.cpsr.mode(), Mode::System);
+            assert!(!cpu.cpsr.irq_disable());
+            assert_eq!(cpu.registers.register_at(REG_SP), 0x0300_7F00);
+            assert_eq!(cpu.registers.register_at(0), 0);
+            assert_eq!(cpu.bus.read32(0x0300_7E00), 0);
+            assert_eq!(cpu.bus.read8(RESET_TO_EWRAM), 0);
+
+            cpu.swap_mode(&Mode::Irq);
+            assert_eq!(cpu.registers.register_at(REG_SP), 0x0300_7FA0);
+        }
+    }
+
+    #[test]
+    fn register_ram_reset() {
+        // The addresses don't overlap in the 64 KBytes of the test bus.
+        let mut cpu = hle_cpu(&[0b10]);
+        for address in [0x0200_9000, 0x0300_0000, 0x0300_7DFC, 0x0300_7E00] {
+            cpu.bus.write32(address, 0xFFFF_FFFF);
+        }
+        swi(&mut cpu, 0x01);
+
+        // Only IWRAM, without the stacks at its end.
+        assert_eq!(cpu.bus.read32(0x0200_9000), 0xFFFF_FFFF);
+        assert_eq!(cpu.bus.read32(0x0300_0000), 0);
+        assert_eq!(cpu.bus.read32(0x0300_7DFC), 0);
+        assert_eq!(cpu.bus.read32(0x0300_7E00), 0xFFFF_FFFF);
+
+        let mut cpu = hle_cpu(&[0b1010_0000]);
+        swi(&mut cpu, 0x01);
+        assert_eq!(cpu.bus.read16(DISPCNT), 0x80);
+        assert_eq!(cpu.bus.read16(RCNT), 0x8000);
+    }
+
+    /// A CPU at the SWI at 0x02000100 with `registers`, PC is 8 bytes ahead of it.
+    fn waiting_cpu(registers: &[u32]) -> Arm7tdmi<TestBus> {
+        let mut cpu = hle_cpu(registers);
+        cpu.registers.set_program_counter(0x0200_0108);
+
+        cpu
+    }
+
+    #[test]
+    fn halt() {
+        // Without interrupt the SWI runs again.
+        let mut cpu = waiting_cpu(&[]);
+        cpu.bus.write16(IE, 1);
+        swi(&mut cpu, 0x02);
+        assert_eq!(cpu.registers.program_counter(), 0x0200_0100);
+
+        // An interrupt requested and enabled in IE ends it, IME doesn't matter.
+        let mut cpu = waiting_cpu(&[]);
+        cpu.bus.write16(IE, 1);
+        cpu.bus.write16(IF, 1);
+        swi(&mut cpu, 0x02);
+        assert_eq!(cpu.registers.program_counter(), 0x0200_0108);
+        assert_eq!(cpu.bus.read16(IME), 0);
+    }
+
+    #[test]
+    fn intr_wait() {
+        // Timer 0 handled before the call, the flag is kept without discarding.
+        let mut cpu = waiting_cpu(&[0, 0b1000]);
+        cpu.bus.write16(BIOS_IF, 0b1001);
+        swi(&mut cpu, 0x04);
+        assert_eq!(cpu.registers.program_counter(), 0x0200_0108);
+        assert_eq!(cpu.bus.read16(BIOS_IF), 0b0001);
+        assert_eq!(cpu.bus.read16(IME), 1);
+
+        // VBlankIntrWait discards it and waits, the next time the flag is the new one.
+        let mut cpu = waiting_cpu(&[]);
+        cpu.bus.write16(BIOS_IF, 1);
+        swi(&mut cpu, 0x05);
+        assert_eq!(cpu.registers.program_counter(), 0x0200_0100);
+        assert!(cpu.hle_intr_wait);
+
+        cpu.bus.write16(BIOS_IF, 1);
+        cpu.registers.set_program_counter(0x0200_0108);
+        swi(&mut cpu, 0x05);
+        assert_eq!(cpu.registers.program_counter(), 0x0200_0108);
+        assert_eq!(cpu.bus.read16(BIOS_IF), 0);
+        assert!(!cpu.hle_intr_wait);
+    }
+
+    #[test]
+    fn get_bios_checksum() {
+        let mut cpu = hle_cpu(&[]);
+        swi(&mut cpu, 0x0D);
+
+        assert_eq!(cpu.registers.register_at(0), 0xBAAE_187F);
+    }
+
+    #[test]
+    fn other_functions_take_the_exception() {
+        let mut cpu = hle_cpu(&[]);
+
+        // SWI 0x10 (BitUnPack) at 0x02000100, PC is 8 bytes ahead of it.
+        cpu.registers.set_program_counter(0x0200_0108);
+        swi(&mut cpu, 0x10);
+
+        assert_eq!(cpu.registers.register_at(14), 0x0200_0104);
+        assert_eq!(cpu.registers.program_counter(), 0x8 + 4);
+    }
+}
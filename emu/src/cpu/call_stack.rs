@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Frames kept at most, the oldest are dropped when a game never returns
+/// from its calls (eg. a scheduler switching between tasks).
+const MAX_FRAMES: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameKind {
+    /// `BL`, or `BX` with the link register pointing after it.
+    Call,
+    /// An exception (eg. an IRQ) interrupted the code.
+    Exception,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Address of the called function (or of the exception vector).
+    pub function: u32,
+    /// Address the code is going to continue from when the function returns.
+    pub return_address: u32,
+    pub kind: FrameKind,
+}
+
+/// Call stack rebuilt from the branches executed by the CPU.
+///
+/// Calls are recognized by `BL` (and `BX` right after setting the link register),
+/// returns by a jump to the return address of one of the frames, whatever
+/// instruction does it (`BX LR`, `POP {PC}`, `LDM` ...).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+}
+
+impl CallStack {
+    /// Frames from the outermost to the current function.
+    #[must_use]
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub(crate) fn push(&mut self, function: u32, return_address: u32, kind: FrameKind) {
+        if self.frames.len() == MAX_FRAMES {
+            self.frames.remove(0);
+        }
+
+        self.frames.push(Frame {
+            function,
+            return_address: return_address & !1,
+            kind,
+        });
+    }
+
+    /// Called on every jump, it drops the frames returned from.
+    pub(crate) fn jumped_to(&mut self, address: u32) {
+        let address = address & !1;
+
+        if let Some(index) = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.return_address == address)
+        {
+            self.frames.truncate(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn call_and_return() {
+        let mut call_stack = CallStack::default();
+
+        call_stack.push(0x0800_1000, 0x0800_0104, FrameKind::Call);
+        call_stack.push(0x0800_2000, 0x0800_1011, FrameKind::Call);
+        assert_eq!(call_stack.frames().len(), 2);
+        assert_eq!(call_stack.frames()[1].return_address, 0x0800_1010);
+
+        // A jump inside the function is not a return.
+        call_stack.jumped_to(0x0800_2040);
+        assert_eq!(call_stack.frames().len(), 2);
+
+        call_stack.jumped_to(0x0800_1011);
+        assert_eq!(call_stack.frames().len(), 1);
+
+        call_stack.jumped_to(0x0800_0104);
+        assert!(call_stack.frames().is_empty());
+    }
+
+    #[test]
+    fn return_skipping_frames() {
+        let mut call_stack = CallStack::default();
+
+        call_stack.push(0x0800_1000, 0x0800_0104, FrameKind::Call);
+        call_stack.push(0x0800_2000, 0x0800_1010, FrameKind::Call);
+        call_stack.push(0x0000_0018, 0x0800_2020, FrameKind::Exception);
+
+        // Like a `longjmp` back to the outermost function.
+        call_stack.jumped_to(0x0800_0104);
+        assert!(call_stack.frames().is_empty());
+    }
+
+    #[test]
+    fn max_frames() {
+        let mut call_stack = CallStack::default();
+
+        for address in 0..=u32::try_from(MAX_FRAMES).unwrap() {
+            call_stack.push(0x0800_0000, address * 4, FrameKind::Call);
+        }
+
+        assert_eq!(call_stack.frames().len(), MAX_FRAMES);
+        assert_eq!(call_stack.frames()[0].return_address, 4);
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::psr::Psr;
+
 /// In ARM state, all instructions are conditionally executed according to the state of the CPSR,
 /// condition codes and the instruction’s condition field.
 /// This field (bits 31:28) determines the circumstances under which an instruction is to be executed.
@@ -58,6 +60,41 @@ pub enum Condition {
     NV = 0xF,
 }
 
+impl Condition {
+    /// Whether an instruction with this condition is executed with the flags of `cpsr`,
+    /// the same for ARM instructions and Thumb conditional branches.
+    #[must_use]
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn is_satisfied(self, cpsr: &Psr) -> bool {
+        let (n, z, c, v) = (
+            cpsr.sign_flag(),
+            cpsr.zero_flag(),
+            cpsr.carry_flag(),
+            cpsr.overflow_flag(),
+        );
+
+        match self {
+            Self::EQ => z,
+            Self::NE => !z,
+            Self::CS => c,
+            Self::CC => !c,
+            Self::MI => n,
+            Self::PL => !n,
+            Self::VS => v,
+            Self::VC => !v,
+            Self::HI => c && !z,
+            Self::LS => !c || z,
+            Self::GE => n == v,
+            Self::LT => n != v,
+            Self::GT => !z && n == v,
+            Self::LE => z || n != v,
+            Self::AL => true,
+            // Reserved from ARMv3, "never" on older CPUs.
+            Self::NV => false,
+        }
+    }
+}
+
 impl From<u8> for Condition {
     fn from(item: u8) -> Self {
         match item {
@@ -104,3 +141,51 @@ impl std::fmt::Display for Condition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn truth_table() {
+        // Rows are the conditions from EQ to NV, columns the flags NZCV from 0b0000 to 0b1111:
+        // a `1` means the instruction is executed.
+        let table = [
+            "0000111100001111", // EQ
+            "1111000011110000", // NE
+            "0011001100110011", // CS
+            "1100110011001100", // CC
+            "0000000011111111", // MI
+            "1111111100000000", // PL
+            "0101010101010101", // VS
+            "1010101010101010", // VC
+            "0011000000110000", // HI
+            "1100111111001111", // LS
+            "1010101001010101", // GE
+            "0101010110101010", // LT
+            "1010000001010000", // GT
+            "0101111110101111", // LE
+            "1111111111111111", // AL
+            "0000000000000000", // NV
+        ];
+
+        for (condition, row) in (0..=0xF).zip(table) {
+            let condition = Condition::from(condition);
+
+            for (flags, expected) in row.chars().enumerate() {
+                let mut cpsr = Psr::default();
+                cpsr.set_sign_flag(flags & 0b1000 != 0);
+                cpsr.set_zero_flag(flags & 0b0100 != 0);
+                cpsr.set_carry_flag(flags & 0b0010 != 0);
+                cpsr.set_overflow_flag(flags & 0b0001 != 0);
+
+                assert_eq!(
+                    condition.is_satisfied(&cpsr),
+                    expected == '1',
+                    "{condition:?} with NZCV {flags:04b}"
+                );
+            }
+        }
+    }
+}
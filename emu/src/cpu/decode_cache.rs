@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::cpu::arm::mode::ArmModeOpcode;
+use crate::cpu::arm7tdmi::Arm7tdmi;
+use crate::cpu::thumb::mode::ThumbModeOpcode;
+
+/// ARM opcodes kept before the cache starts over, the code of a game uses far fewer.
+const MAX_ARM_OPCODES: usize = 1 << 16;
+
+/// Instructions already decoded, by opcode: the decoding depends only on the opcode, so
+/// the loops of a game are decoded once. Used by the fast profile.
+#[derive(Default)]
+pub struct DecodeCache {
+    arm: HashMap<u32, ArmModeOpcode>,
+    thumb: HashMap<u16, ThumbModeOpcode>,
+}
+
+impl DecodeCache {
+    pub(crate) fn arm(&mut self, op_code: u32) -> ArmModeOpcode {
+        if let Some(decoded) = self.arm.get(&op_code) {
+            return *decoded;
+        }
+
+        if self.arm.len() >= MAX_ARM_OPCODES {
+            self.arm.clear();
+        }
+        let decoded = Arm7tdmi::decode(op_code);
+        self.arm.insert(op_code, decoded);
+        decoded
+    }
+
+    pub(crate) fn thumb(&mut self, op_code: u16) -> ThumbModeOpcode {
+        *self
+            .thumb
+            .entry(op_code)
+            .or_insert_with(|| Arm7tdmi::decode(op_code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn decodes_once() {
+        let mut cache = DecodeCache::default();
+
+        let branch = cache.arm(0xEAFF_FFFE);
+        assert_eq!(branch.raw, 0xEAFF_FFFE);
+        assert_eq!(cache.arm(0xEAFF_FFFE).raw, 0xEAFF_FFFE);
+        assert_eq!(cache.thumb(0xE7FE).raw, 0xE7FE);
+        assert_eq!(cache.arm.len(), 1);
+        assert_eq!(cache.thumb.len(), 1);
+
+        let decoded: ThumbModeOpcode = Arm7tdmi::decode(0x1C08_u16);
+        assert_eq!(
+            cache.thumb(0x1C08).instruction.to_string(),
+            decoded.instruction.to_string()
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Checks driven by the tables of the encodings: every format can be reached and the
+//! decoders agree with the tables, so a format added to one can't be missed by the other.
+
+use pretty_assertions::assert_eq;
+
+use crate::cpu::arm::encodings::{ArmFormat, ARM_ENCODINGS};
+use crate::cpu::arm::instructions::ArmModeInstruction;
+use crate::cpu::encoding::{find, Encoding};
+use crate::cpu::thumb::encodings::{ThumbFormat, THUMB_ENCODINGS};
+use crate::cpu::thumb::instruction::Instruction;
+
+/// Values given to every field of an encoding, so that each field is tried with different bits.
+const FIELD_BITS: [u32; 3] = [u32::MAX, 0x5555_5555, 0xAAAA_AAAA];
+
+/// Opcodes of `encoding` with its fields filled with [`FIELD_BITS`].
+fn samples<F: Copy>(encoding: &Encoding<F>, width: usize) -> impl Iterator<Item = u32> + '_ {
+    let all_bits = if width == 32 {
+        u32::MAX
+    } else {
+        (1 << width) - 1
+    };
+
+    FIELD_BITS
+        .into_iter()
+        .map(move |bits| encoding.value | (bits & !encoding.mask & all_bits))
+}
+
+#[test]
+fn encodings_have_the_width_of_the_instructions() {
+    for encoding in &ARM_ENCODINGS {
+        assert_eq!(encoding.width(), 32, "{:?}", encoding.format);
+    }
+    for encoding in &THUMB_ENCODINGS {
+        assert_eq!(encoding.width(), 16, "{:?}", encoding.format);
+    }
+}
+
+#[test]
+fn every_format_is_reachable() {
+    for encoding in &ARM_ENCODINGS {
+        assert!(
+            samples(encoding, 32)
+                .any(|op_code| find(&ARM_ENCODINGS, op_code).unwrap().format == encoding.format),
+            "{:?} is hidden by the encodings before it",
+            encoding.format
+        );
+    }
+    for encoding in &THUMB_ENCODINGS {
+        assert!(
+            samples(encoding, 16)
+                .any(|op_code| find(&THUMB_ENCODINGS, op_code).unwrap().format == encoding.format),
+            "{:?} is hidden by the encodings before it",
+            encoding.format
+        );
+    }
+}
+
+#[test]
+fn arm_decoder_follows_the_table() {
+    for encoding in &ARM_ENCODINGS {
+        for op_code in samples(encoding, 32) {
+            let format = ArmFormat::decode(op_code).unwrap();
+
+            assert_eq!(
+                ArmModeInstruction::from(op_code).format(),
+                format,
+                "{op_code:08X}"
+            );
+        }
+    }
+}
+
+#[test]
+fn thumb_decoder_follows_the_table() {
+    for encoding in &THUMB_ENCODINGS {
+        for op_code in samples(encoding, 16) {
+            let op_code = u16::try_from(op_code).unwrap();
+            let format = ThumbFormat::decode(op_code).unwrap();
+
+            assert_eq!(Instruction::from(op_code).format(), format, "{op_code:04X}");
+        }
+    }
+}
+
+#[test]
+fn every_format_has_one_encoding() {
+    for encoding in &ARM_ENCODINGS {
+        let count = ARM_ENCODINGS
+            .iter()
+            .filter(|other| other.format == encoding.format)
+            .count();
+        assert_eq!(count, 1, "{:?}", encoding.format);
+    }
+    for encoding in &THUMB_ENCODINGS {
+        let count = THUMB_ENCODINGS
+            .iter()
+            .filter(|other| other.format == encoding.format)
+            .count();
+        assert_eq!(count, 1, "{:?}", encoding.format);
+    }
+}
@@ -0,0 +1,273 @@
+//! Snapshots of the disassembler output. Every line has the opcode, our disassembly
+//! and, as a reference, what `arm-none-eabi-objdump -D -b binary -marm` (with
+//! `-Mforce-thumb` for Thumb) prints for it, so a change in the formatting shows up
+//! as a snapshot diff next to the expected syntax.
+//!
+//! `objdump_agreement` compares the two columns: every opcode must disassemble like
+//! objdump does, up to case and register names, or be listed in [`KNOWN_DIFFERENCES`]
+//! with the reason it differs.
+//!
+//! Review the changes with `cargo insta review`.
+
+use std::fmt::Write;
+
+use crate::cpu::arm::instructions::ArmModeInstruction;
+use crate::cpu::thumb::instruction::Instruction;
+
+/// Opcodes covering every ARM format the disassembler knows, with the objdump output.
+const ARM_CORPUS: [(u32, &str); 24] = [
+    (0xE3A0_0005, "mov r0, #5"),
+    (0xE090_1000, "adds r1, r0, r0"),
+    (0x0090_1000, "addseq r1, r0, r0"),
+    (0xE3A0_04FF, "mov r0, #-16777216 @ 0xff000000"),
+    (0xE1A0_1182, "lsl r1, r2, #3"),
+    (0xE1A0_1022, "lsr r1, r2, #32"),
+    (0xE1A0_1042, "asr r1, r2, #32"),
+    (0xE1A0_1372, "ror r1, r2, r3"),
+    (0xE1A0_1062, "rrx r1, r2"),
+    (0xE0A1_1062, "adc r1, r1, r2, rrx"),
+    (0xE350_0001, "cmp r0, #1"),
+    (0xE110_0001, "tst r0, r1"),
+    (0xE1E0_0001, "mvn r0, r1"),
+    (0xE10F_0000, "mrs r0, CPSR"),
+    (0xE169_F000, "msr SPSR_fc, r0"),
+    (0xE328_F20F, "msr CPSR_f, #-268435456 @ 0xf0000000"),
+    (0xE12F_FF11, "bx r1"),
+    (0xE001_0392, "mul r1, r2, r3"),
+    (0xE081_0392, "umull r0, r1, r2, r3"),
+    (0xE1D0_10B2, "ldrh r1, [r0, #2]"),
+    (0xE591_0004, "ldr r0, [r1, #4]"),
+    (0xE561_0004, "strb r0, [r1, #-4]!"),
+    (0xE92D_4010, "push {r4, lr}"),
+    (0x0BFF_FFFE, "bleq 0x5c"),
+];
+
+/// Opcodes covering every Thumb format the disassembler knows, with the objdump output.
+const THUMB_CORPUS: [(u16, &str); 20] = [
+    (0x0081, "lsls r1, r0, #2"),
+    (0x1840, "adds r0, r0, r1"),
+    (0x1E48, "subs r0, r1, #1"),
+    (0x2005, "movs r0, #5"),
+    (0x4008, "ands r0, r1"),
+    (0x4770, "bx lr"),
+    (0x4918, "ldr r1, [pc, #96]"),
+    (0x5088, "str r0, [r1, r2]"),
+    (0x5E88, "ldrsh r0, [r1, r2]"),
+    (0x6848, "ldr r0, [r1, #4]"),
+    (0x8841, "ldrh r1, [r0, #2]"),
+    (0x9001, "str r0, [sp, #4]"),
+    (0xA901, "add r1, sp, #4"),
+    (0xB082, "sub sp, #8"),
+    (0xB510, "push {r4, lr}"),
+    (0xBD10, "pop {r4, pc}"),
+    (0xC90C, "ldmia r1!, {r2, r3}"),
+    (0xD0FE, "beq.n 0x22"),
+    (0xDF05, "svc 5"),
+    (0xE7FE, "b.n 0x26"),
+];
+
+/// Start of a ROM built with a devkitARM-style `crt0`: the branch over the header,
+/// the stacks set up in IRQ and System mode, and the jump to the Thumb code.
+/// The addresses start from the ROM base, the header words are skipped.
+const ROM_START: [(u32, u32, &str); 12] = [
+    (0x0800_0000, 0xEA00_002E, "b 0x80000c0"),
+    (0x0800_00C0, 0xE3A0_0012, "mov r0, #18"),
+    (0x0800_00C4, 0xE129_F000, "msr CPSR_fc, r0"),
+    (0x0800_00C8, 0xE59F_D028, "ldr sp, [pc, #40] @ 0x80000f8"),
+    (0x0800_00CC, 0xE3A0_001F, "mov r0, #31"),
+    (0x0800_00D0, 0xE129_F000, "msr CPSR_fc, r0"),
+    (0x0800_00D4, 0xE59F_D024, "ldr sp, [pc, #36] @ 0x8000100"),
+    (0x0800_00D8, 0xE3A0_0301, "mov r0, #67108864 @ 0x4000000"),
+    (0x0800_00DC, 0xE580_0208, "str r0, [r0, #520]"),
+    (0x0800_00E0, 0xE28F_0001, "add r0, pc, #1"),
+    (0x0800_00E4, 0xE12F_FF10, "bx r0"),
+    (0x0800_00E8, 0xEAFF_FFFE, "b 0x80000e8"),
+];
+
+fn line(output: &mut String, address: u32, raw: &str, ours: &str, objdump: &str) {
+    writeln!(output, "{address:08X}  {raw:>8}  {ours:<36}; {objdump}").unwrap();
+}
+
+fn disassemble_arm<'a>(opcodes: impl Iterator<Item = (u32, u32, &'a str)>) -> String {
+    let mut output = String::new();
+    for (address, opcode, objdump) in opcodes {
+        let ours = ArmModeInstruction::from(opcode).disassembler();
+        line(
+            &mut output,
+            address,
+            &format!("{opcode:08X}"),
+            &ours,
+            objdump,
+        );
+    }
+
+    output
+}
+
+#[test]
+fn arm_corpus() {
+    let opcodes = (0..)
+        .step_by(4)
+        .zip(ARM_CORPUS)
+        .map(|(address, (opcode, objdump))| (address, opcode, objdump));
+
+    insta::assert_snapshot!(disassemble_arm(opcodes));
+}
+
+#[test]
+fn thumb_corpus() {
+    let mut output = String::new();
+    for (address, (opcode, objdump)) in (0..).step_by(2).zip(THUMB_CORPUS) {
+        let ours = Instruction::from(opcode).disassembler();
+        line(
+            &mut output,
+            address,
+            &format!("{opcode:04X}"),
+            &ours,
+            objdump,
+        );
+    }
+
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn rom_start() {
+    insta::assert_snapshot!(disassemble_arm(ROM_START.into_iter()));
+}
+
+/// Opcodes, as written in the snapshots, whose disassembly doesn't match objdump.
+/// The syntax differences are accepted: the disassembler follows the pre-UAL syntax of
+/// GBATEK. The bugs are accepted until the disassembler is fixed, fixing one makes
+/// `objdump_agreement` fail until it's removed from the list.
+const KNOWN_DIFFERENCES: [(&str, &str); 36] = [
+    // Syntax.
+    ("00901000", "the condition comes before the S suffix"),
+    ("E3A004FF", "immediates are unsigned"),
+    ("E1A01182", "shifts are MOV with a shifted operand, not LSL"),
+    ("E1A01022", "shifts are MOV with a shifted operand, not LSR"),
+    ("E1A01042", "shifts are MOV with a shifted operand, not ASR"),
+    ("E1A01372", "shifts are MOV with a shifted operand, not ROR"),
+    ("E1A01062", "shifts are MOV with a shifted operand, not RRX"),
+    (
+        "E169F000",
+        "MSR without a field writes the whole PSR, objdump's `_fc`",
+    ),
+    (
+        "E129F000",
+        "MSR without a field writes the whole PSR, objdump's `_fc`",
+    ),
+    (
+        "E328F20F",
+        "`_flg` instead of `_f` and an unsigned immediate",
+    ),
+    ("E1D010B2", "halfword offsets are written `[Rn,#+offset]`"),
+    ("0081", "Thumb ALU operations have no S suffix"),
+    ("1840", "Thumb ALU operations have no S suffix"),
+    ("1E48", "Thumb ALU operations have no S suffix"),
+    ("2005", "Thumb ALU operations have no S suffix"),
+    ("4008", "Thumb ALU operations have no S suffix"),
+    ("5E88", "LDSH instead of LDRSH"),
+    ("DF05", "SWI instead of SVC"),
+    ("D0FE", "Thumb branches show the offset, not the target"),
+    // Bugs.
+    (
+        "E0010392",
+        "multiplies print the S bit as a bool and bare register numbers",
+    ),
+    (
+        "E0810392",
+        "multiplies print the S bit as a bool and bare register numbers",
+    ),
+    (
+        "E5910004",
+        "LDR/STR drop the base register and the indexing",
+    ),
+    (
+        "E5610004",
+        "LDR/STR drop the base register and the indexing",
+    ),
+    (
+        "E59FD028",
+        "LDR/STR drop the base register and the indexing",
+    ),
+    (
+        "E59FD024",
+        "LDR/STR drop the base register and the indexing",
+    ),
+    (
+        "E5800208",
+        "LDR/STR drop the base register and the indexing",
+    ),
+    (
+        "E92D4010",
+        "block transfers have a stray comma and a trailing separator",
+    ),
+    (
+        "0BFFFFFE",
+        "ARM branches print the offset as 26 bits, not the target",
+    ),
+    (
+        "EA00002E",
+        "ARM branches print the offset as 26 bits, not the target",
+    ),
+    (
+        "EAFFFFFE",
+        "ARM branches print the offset as 26 bits, not the target",
+    ),
+    ("4770", "BX with a high register prints a stray R0"),
+    (
+        "4918",
+        "PC-relative loads print Rd as the base and scale the offset twice",
+    ),
+    ("6848", "LDR with an immediate offset prints nothing"),
+    ("B510", "PUSH prints PC instead of LR"),
+    ("C90C", "register lists have a trailing separator"),
+    (
+        "E7FE",
+        "unconditional Thumb branches don't sign extend the offset",
+    ),
+];
+
+/// Lowercase, without the objdump comment and with `sp`, `lr` and `pc` for R13-R15.
+fn normalize(disassembly: &str) -> String {
+    let disassembly = disassembly.split(" @ ").next().unwrap_or_default();
+
+    disassembly
+        .to_lowercase()
+        .replace("r13", "sp")
+        .replace("r14", "lr")
+        .replace("r15", "pc")
+}
+
+#[test]
+fn objdump_agreement() {
+    let arm = ARM_CORPUS
+        .into_iter()
+        .chain(ROM_START.map(|(_, opcode, objdump)| (opcode, objdump)))
+        .map(|(opcode, objdump)| {
+            let ours = ArmModeInstruction::from(opcode).disassembler();
+            (format!("{opcode:08X}"), ours, objdump)
+        });
+    let thumb = THUMB_CORPUS.into_iter().map(|(opcode, objdump)| {
+        let ours = Instruction::from(opcode).disassembler();
+        (format!("{opcode:04X}"), ours, objdump)
+    });
+
+    let mut unexpected = Vec::new();
+    for (raw, ours, objdump) in arm.chain(thumb) {
+        let known = KNOWN_DIFFERENCES.iter().any(|(opcode, _)| *opcode == raw);
+        let matches = normalize(&ours) == normalize(objdump);
+
+        if matches == known {
+            let why = if known {
+                "matches, unlist it"
+            } else {
+                "differs"
+            };
+            unexpected.push(format!("{raw}: `{ours}` {why} (objdump: `{objdump}`)"));
+        }
+    }
+
+    assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+}
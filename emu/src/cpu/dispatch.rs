@@ -0,0 +1,714 @@
+//! Dispatch of the instructions through tables of handlers.
+//!
+//! The decoder looks up the format of an opcode by its decode signature (the bits the
+//! encodings are told apart by) in a table filled once, instead of trying the encodings
+//! one by one. The instruction keeps its format, so the handler is called from the table
+//! of the CPU without decoding anything again. A handler can be replaced to hook or trace a kind of instruction,
+//! see [`Arm7tdmi::set_arm_handler`].
+
+use std::sync::LazyLock;
+
+use crate::bitwise::Bits;
+use crate::bus::Bus;
+use crate::cpu::arm::encodings::{ArmFormat, ARM_ENCODINGS};
+use crate::cpu::arm::instructions::ArmModeInstruction;
+use crate::cpu::arm::mode::ArmModeOpcode;
+use crate::cpu::arm7tdmi::{Arm7tdmi, ExceptionType};
+use crate::cpu::bios_hle;
+use crate::cpu::condition::Condition;
+use crate::cpu::encoding::Encoding;
+use crate::cpu::thumb::encodings::{ThumbFormat, THUMB_ENCODINGS};
+use crate::cpu::thumb::instruction::Instruction;
+use crate::cpu::thumb::mode::ThumbModeOpcode;
+
+/// Executes an ARM instruction, its condition is already satisfied.
+pub type ArmHandler<B> = fn(&mut Arm7tdmi<B>, ArmModeOpcode);
+
+/// Executes a Thumb instruction.
+pub type ThumbHandler<B> = fn(&mut Arm7tdmi<B>, ThumbModeOpcode);
+
+/// Bits of an ARM opcode that tell its format apart: bits 20-27 and 4-7.
+const ARM_SIGNATURE_MASK: u32 = 0x0FF0_00F0;
+
+/// Bits of a Thumb opcode that tell its format apart: the top 8, enough for every format.
+const THUMB_SIGNATURE_MASK: u32 = 0xFF00;
+
+/// Format of the ARM opcodes by signature, `None` where bits outside of the signature
+/// are needed (eg. `BX` and `MRS` are data processing opcodes with fixed registers).
+static ARM_FORMATS: LazyLock<Vec<Option<ArmFormat>>> = LazyLock::new(|| {
+    (0..1 << 12)
+        .map(|signature| format_of(&ARM_ENCODINGS, ARM_SIGNATURE_MASK, arm_opcode(signature)))
+        .collect()
+});
+
+/// Format of the Thumb opcodes by signature.
+static THUMB_FORMATS: LazyLock<Vec<Option<ThumbFormat>>> = LazyLock::new(|| {
+    (0..1 << 8)
+        .map(|signature| format_of(&THUMB_ENCODINGS, THUMB_SIGNATURE_MASK, signature << 8))
+        .collect()
+});
+
+const fn arm_signature(op_code: u32) -> usize {
+    (((op_code >> 16) & 0xFF0) | ((op_code >> 4) & 0xF)) as usize
+}
+
+/// The bits of the signature in their place in an opcode.
+const fn arm_opcode(signature: u32) -> u32 {
+    ((signature & 0xFF0) << 16) | ((signature & 0xF) << 4)
+}
+
+/// Format of every opcode with the bits of `op_code` under `signature_mask`: the first
+/// encoding they can match, if it's decided by these bits only.
+fn format_of<F: Copy>(encodings: &[Encoding<F>], signature_mask: u32, op_code: u32) -> Option<F> {
+    encodings
+        .iter()
+        .find(|encoding| {
+            encoding.value & signature_mask == op_code & encoding.mask & signature_mask
+        })
+        .filter(|encoding| encoding.mask & !signature_mask == 0)
+        .map(|encoding| encoding.format)
+}
+
+/// Format of an ARM opcode, by the table of the signatures if it can tell, `None` if no
+/// encoding matches it.
+pub(crate) fn arm_format(op_code: u32) -> Option<ArmFormat> {
+    ARM_FORMATS[arm_signature(op_code)].or_else(|| ArmFormat::decode(op_code))
+}
+
+/// Format of a Thumb opcode, `None` if no encoding matches it.
+pub(crate) fn thumb_format(op_code: u16) -> Option<ThumbFormat> {
+    THUMB_FORMATS[usize::from(op_code >> 8)]
+}
+
+/// Handlers of the CPU by format, the defaults run the instructions.
+pub(crate) struct Handlers<B> {
+    arm: [ArmHandler<B>; ARM_ENCODINGS.len()],
+    thumb: [ThumbHandler<B>; THUMB_ENCODINGS.len()],
+}
+
+impl<B: Bus> Default for Handlers<B> {
+    fn default() -> Self {
+        Self {
+            arm: ARM_ENCODINGS.map(|encoding| arm_handler(encoding.format)),
+            thumb: THUMB_ENCODINGS.map(|encoding| thumb_handler(encoding.format)),
+        }
+    }
+}
+
+impl<B: Bus> Arm7tdmi<B> {
+    /// Runs `handler` in place of the instructions of `format`, eg. to count or trace
+    /// them. [`default_arm_handler`] gives the one executing them.
+    pub fn set_arm_handler(&mut self, format: ArmFormat, handler: ArmHandler<B>) {
+        self.handlers.arm[format as usize] = handler;
+    }
+
+    /// Runs `handler` in place of the instructions of `format`, see [`Self::set_arm_handler`].
+    pub fn set_thumb_handler(&mut self, format: ThumbFormat, handler: ThumbHandler<B>) {
+        self.handlers.thumb[format as usize] = handler;
+    }
+
+    pub(crate) fn dispatch_arm(&mut self, op_code: ArmModeOpcode) {
+        self.handlers.arm[op_code.instruction.format() as usize](self, op_code);
+    }
+
+    pub(crate) fn dispatch_thumb(&mut self, op_code: ThumbModeOpcode) {
+        self.handlers.thumb[op_code.instruction.format() as usize](self, op_code);
+    }
+}
+
+/// The handler executing the ARM instructions of `format`.
+#[must_use]
+pub fn default_arm_handler<B: Bus>(format: ArmFormat) -> ArmHandler<B> {
+    arm_handler(format)
+}
+
+/// The handler executing the Thumb instructions of `format`.
+#[must_use]
+pub fn default_thumb_handler<B: Bus>(format: ThumbFormat) -> ThumbHandler<B> {
+    thumb_handler(format)
+}
+
+fn arm_handler<B: Bus>(format: ArmFormat) -> ArmHandler<B> {
+    match format {
+        ArmFormat::DataProcessing => data_processing,
+        ArmFormat::Mrs | ArmFormat::Msr | ArmFormat::MsrFlg => psr_transfer,
+        ArmFormat::Multiply => multiply,
+        ArmFormat::MultiplyLong => multiply_long,
+        ArmFormat::BranchAndExchange => branch_and_exchange,
+        ArmFormat::HalfwordDataTransferRegister | ArmFormat::HalfwordDataTransferImmediate => {
+            half_word_data_transfer
+        }
+        ArmFormat::SingleDataTransfer => single_data_transfer,
+        ArmFormat::BlockDataTransfer => block_data_transfer,
+        ArmFormat::Branch => branch,
+        ArmFormat::SoftwareInterrupt => arm_software_interrupt,
+        ArmFormat::SingleDataSwap => single_data_swap,
+        // The GBA has no coprocessors, their instructions are undefined like the others.
+        ArmFormat::Undefined
+        | ArmFormat::CoprocessorDataTransfer
+        | ArmFormat::CoprocessorDataOperation
+        | ArmFormat::CoprocessorRegisterTransfer => undefined,
+    }
+}
+
+fn thumb_handler<B: Bus>(format: ThumbFormat) -> ThumbHandler<B> {
+    match format {
+        ThumbFormat::MoveShiftedRegister => move_shifted_register,
+        ThumbFormat::AddSubtract => add_subtract,
+        ThumbFormat::MoveCompareAddSubtractImm => move_compare_add_subtract_imm,
+        ThumbFormat::AluOp => alu_op,
+        ThumbFormat::HiRegisterOpBX => hi_register_op_bx,
+        ThumbFormat::PCRelativeLoad => pc_relative_load,
+        ThumbFormat::LoadStoreRegisterOffset => load_store_register_offset,
+        ThumbFormat::LoadStoreSignExtByteHalfword => load_store_sign_ext_byte_halfword,
+        ThumbFormat::LoadStoreImmOffset => Arm7tdmi::load_store_immediate_offset,
+        ThumbFormat::LoadStoreHalfword => load_store_halfword,
+        ThumbFormat::SPRelativeLoadStore => sp_relative_load_store,
+        ThumbFormat::LoadAddress => load_address,
+        ThumbFormat::AddOffsetSP => add_offset_sp,
+        ThumbFormat::PushPopReg => push_pop_reg,
+        ThumbFormat::MultipleLoadStore => multiple_load_store,
+        ThumbFormat::CondBranch => cond_branch,
+        ThumbFormat::Swi => thumb_software_interrupt,
+        ThumbFormat::UncondBranch => uncond_branch,
+        ThumbFormat::LongBranchLink => long_branch_link,
+    }
+}
+
+fn data_processing<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::DataProcessing {
+        alu_instruction,
+        set_conditions,
+        op_kind,
+        rn,
+        destination,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.data_processing(
+        op_code,
+        alu_instruction,
+        set_conditions,
+        op_kind,
+        rn,
+        destination,
+    );
+}
+
+fn psr_transfer<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::PSRTransfer { psr_kind, kind, .. } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.psr_transfer(kind, psr_kind);
+}
+
+fn multiply<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::Multiply {
+        variant,
+        should_set_codes,
+        rd_destination_register,
+        rn_accumulate_register,
+        rm_operand_register,
+        rs_operand_register,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.multiply(
+        variant,
+        should_set_codes,
+        rd_destination_register,
+        rn_accumulate_register,
+        rm_operand_register,
+        rs_operand_register,
+    );
+}
+
+fn multiply_long<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::MultiplyLong {
+        variant,
+        should_set_codes,
+        rdhi_destination_register,
+        rdlo_destination_register,
+        rm_operand_register,
+        rs_operand_register,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.multiply_long(
+        variant,
+        should_set_codes,
+        rdhi_destination_register,
+        rdlo_destination_register,
+        rm_operand_register,
+        rs_operand_register,
+    );
+}
+
+fn branch_and_exchange<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::BranchAndExchange { register, .. } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.branch_and_exchange(register);
+}
+
+fn half_word_data_transfer<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::HalfwordDataTransfer {
+        indexing,
+        offsetting,
+        write_back,
+        load_store_kind,
+        offset_kind,
+        base_register,
+        source_destination_register,
+        transfer_kind,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.half_word_data_transfer(
+        indexing,
+        offsetting,
+        write_back,
+        load_store_kind,
+        offset_kind,
+        base_register,
+        source_destination_register,
+        transfer_kind,
+    );
+}
+
+fn single_data_transfer<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::SingleDataTransfer {
+        kind,
+        quantity,
+        write_back,
+        indexing,
+        rd,
+        base_register,
+        offset_info,
+        offsetting,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.single_data_transfer(
+        kind,
+        quantity,
+        write_back,
+        indexing,
+        rd,
+        base_register,
+        offset_info,
+        offsetting,
+    );
+}
+
+fn single_data_swap<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::SingleDataSwap {
+        quantity,
+        rn,
+        rd,
+        rm,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.single_data_swap(quantity, rn, rd, rm);
+}
+
+fn block_data_transfer<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::BlockDataTransfer {
+        indexing,
+        offsetting,
+        load_psr,
+        write_back,
+        load_store,
+        rn,
+        register_list,
+        ..
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.block_data_transfer(
+        indexing,
+        offsetting,
+        load_psr,
+        write_back,
+        load_store,
+        rn,
+        register_list,
+    );
+}
+
+fn branch<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::Branch { link, offset, .. } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.branch(link, offset);
+}
+
+fn arm_software_interrupt<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ArmModeOpcode) {
+    let ArmModeInstruction::SoftwareInterrupt { comment, .. } = op_code.instruction else {
+        unreachable!()
+    };
+    software_interrupt(cpu, comment.get_bits_u8(16..=23));
+}
+
+fn undefined<B: Bus>(cpu: &mut Arm7tdmi<B>, _op_code: ArmModeOpcode) {
+    cpu.handle_exception(ExceptionType::UndefinedInstruction);
+}
+
+fn move_shifted_register<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::MoveShiftedRegister {
+        shift_operation,
+        offset5,
+        source_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.move_shifted_reg(
+        shift_operation,
+        offset5,
+        source_register,
+        destination_register,
+    );
+}
+
+fn add_subtract<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::AddSubtract {
+        operation_kind,
+        op,
+        rn_offset3,
+        source_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.add_subtract(
+        operation_kind,
+        op,
+        rn_offset3,
+        source_register,
+        destination_register,
+    );
+}
+
+fn move_compare_add_subtract_imm<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::MoveCompareAddSubtractImm {
+        operation,
+        destination_register,
+        offset,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.move_compare_add_sub_imm(operation, destination_register, offset);
+}
+
+fn alu_op<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::AluOp {
+        alu_operation,
+        source_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.alu_op(alu_operation, source_register, destination_register);
+}
+
+fn hi_register_op_bx<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::HiRegisterOpBX {
+        register_operation,
+        source_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.hi_reg_operation_branch_ex(register_operation, source_register, destination_register);
+}
+
+fn pc_relative_load<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::PCRelativeLoad {
+        destination_register,
+        immediate_value,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.pc_relative_load(destination_register, immediate_value);
+}
+
+fn load_store_register_offset<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::LoadStoreRegisterOffset {
+        load_store,
+        byte_word,
+        ro,
+        base_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.load_store_register_offset(
+        load_store,
+        byte_word,
+        ro,
+        base_register,
+        destination_register,
+    );
+}
+
+fn load_store_sign_ext_byte_halfword<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::LoadStoreSignExtByteHalfword {
+        h,
+        sign_extend_flag,
+        offset_register,
+        base_register,
+        destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.load_store_sign_extend_byte_halfword(
+        h,
+        sign_extend_flag,
+        offset_register,
+        base_register,
+        destination_register,
+    );
+}
+
+fn load_store_halfword<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::LoadStoreHalfword {
+        load_store,
+        offset,
+        base_register,
+        source_destination_register,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.load_store_halfword(
+        load_store,
+        offset,
+        base_register,
+        source_destination_register,
+    );
+}
+
+fn sp_relative_load_store<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::SPRelativeLoadStore {
+        load_store,
+        destination_register,
+        word8,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.sp_relative_load_store(load_store, destination_register, word8);
+}
+
+fn load_address<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::LoadAddress {
+        sp,
+        destination_register,
+        offset,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.load_address(sp, destination_register as usize, offset);
+}
+
+fn add_offset_sp<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::AddOffsetSP { s, word7 } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.add_offset_sp(s, word7);
+}
+
+fn push_pop_reg<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::PushPopReg {
+        load_store,
+        pc_lr,
+        register_list,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.push_pop_register(load_store, pc_lr, register_list);
+}
+
+fn multiple_load_store<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::MultipleLoadStore {
+        load_store,
+        base_register,
+        register_list,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    cpu.multiple_load_store(load_store, base_register as usize, register_list);
+}
+
+fn cond_branch<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::CondBranch {
+        condition,
+        immediate_offset,
+    } = op_code.instruction
+    else {
+        unreachable!()
+    };
+    // The `AL` condition is undefined in Thumb state, `0b1111` is SWI.
+    if condition == Condition::AL {
+        cpu.handle_exception(ExceptionType::UndefinedInstruction);
+    } else {
+        cpu.cond_branch(condition, immediate_offset);
+    }
+}
+
+fn thumb_software_interrupt<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::Swi { comment } = op_code.instruction else {
+        unreachable!()
+    };
+    software_interrupt(cpu, comment);
+}
+
+/// Calls the BIOS function `function`: run by the emulator or the bus if they can,
+/// otherwise by the BIOS code through the exception.
+fn software_interrupt<B: Bus>(cpu: &mut Arm7tdmi<B>, function: u8) {
+    if cpu.hle_bios && bios_hle::call(cpu, function) {
+        return;
+    }
+    if !cpu.bus.software_interrupt(function) {
+        cpu.handle_exception(ExceptionType::SoftwareInterrupt);
+    }
+}
+
+fn uncond_branch<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::UncondBranch { offset } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.uncond_branch(offset);
+}
+
+fn long_branch_link<B: Bus>(cpu: &mut Arm7tdmi<B>, op_code: ThumbModeOpcode) {
+    let Instruction::LongBranchLink { h, offset } = op_code.instruction else {
+        unreachable!()
+    };
+    cpu.long_branch_link(h, offset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::cpu::cpu_modes::Mode;
+    use crate::cpu::registers::REG_LR;
+    use crate::cpu::test_bus::TestBus;
+
+    #[test]
+    fn formats_are_in_the_order_of_the_encodings() {
+        for (index, encoding) in ARM_ENCODINGS.iter().enumerate() {
+            assert_eq!(encoding.format as usize, index, "{:?}", encoding.format);
+        }
+        for (index, encoding) in THUMB_ENCODINGS.iter().enumerate() {
+            assert_eq!(encoding.format as usize, index, "{:?}", encoding.format);
+        }
+    }
+
+    #[test]
+    fn thumb_signatures_follow_the_decoder() {
+        // Undefined opcodes have no format in either.
+        for op_code in 0..=u16::MAX {
+            assert_eq!(
+                THUMB_FORMATS[usize::from(op_code >> 8)],
+                ThumbFormat::decode(op_code),
+                "{op_code:#06X}"
+            );
+        }
+    }
+
+    #[test]
+    fn arm_signatures_follow_the_decoder() {
+        // Every signature with the other bits cleared, set and mixed.
+        for signature in 0..1 << 12 {
+            for others in [0, u32::MAX, 0x5555_5555, 0xAAAA_AAAA, 0x000F_FF0F] {
+                let op_code = arm_opcode(signature) | (others & !ARM_SIGNATURE_MASK);
+                if let Some(format) = ArmFormat::decode(op_code) {
+                    assert_eq!(arm_format(op_code), Some(format), "{op_code:#010X}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ambiguous_arm_signatures() {
+        // `BX r0` and `TEQ r1, r0` share the signature, `MOV r0, r1` doesn't.
+        assert_eq!(ARM_FORMATS[arm_signature(0xE12F_FF10)], None);
+        assert_eq!(arm_format(0xE12F_FF10), Some(ArmFormat::BranchAndExchange));
+        assert_eq!(arm_format(0xE131_0010), Some(ArmFormat::DataProcessing));
+        assert_eq!(
+            ArmModeInstruction::from(0xE12F_FF10).format(),
+            ArmFormat::BranchAndExchange
+        );
+        assert_eq!(
+            ARM_FORMATS[arm_signature(0xE1A0_0001)],
+            Some(ArmFormat::DataProcessing)
+        );
+    }
+
+    #[test]
+    fn undefined_instructions_take_the_exception() {
+        // An undefined opcode and a coprocessor data operation (CDP p1, 0, c0, c0, c0).
+        for op_code in [0xE7F0_00F0, 0xEE00_0100] {
+            let mut cpu = Arm7tdmi::new(TestBus::default());
+            // The instruction is at 0x08000100, PC is 8 bytes ahead of it.
+            cpu.registers.set_program_counter(0x0800_0108);
+
+            cpu.execute_arm(Arm7tdmi::decode(op_code));
+
+            assert_eq!(cpu.cpsr.mode(), Mode::Undefined, "{op_code:#010X}");
+            assert_eq!(cpu.registers.register_at(REG_LR), 0x0800_0104);
+            assert_eq!(cpu.registers.program_counter(), 0x4 + 4);
+        }
+    }
+
+    #[test]
+    fn hook() {
+        fn skip(cpu: &mut Arm7tdmi<TestBus>, _op_code: ArmModeOpcode) {
+            cpu.registers.set_register_at(7, 0xCAFE);
+        }
+
+        let mut cpu = Arm7tdmi::new(TestBus::default());
+        cpu.set_arm_handler(ArmFormat::DataProcessing, skip);
+
+        // MOV r0, #1
+        cpu.execute_arm(Arm7tdmi::decode(0xE3A0_0001));
+        assert_eq!(cpu.registers.register_at(0), 0);
+        assert_eq!(cpu.registers.register_at(7), 0xCAFE);
+
+        cpu.set_arm_handler(
+            ArmFormat::DataProcessing,
+            default_arm_handler(ArmFormat::DataProcessing),
+        );
+        cpu.execute_arm(Arm7tdmi::decode(0xE3A0_0001));
+        assert_eq!(cpu.registers.register_at(0), 1);
+    }
+}
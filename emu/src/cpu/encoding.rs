@@ -0,0 +1,87 @@
+//! Declarative tables of the instruction encodings.
+//!
+//! Every format is described once by the pattern of its bits, and the decoders, the
+//! `Display` of the opcodes and the decoder tests are driven by the same table.
+
+/// The encoding of a format of instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoding<F> {
+    pub format: F,
+    /// Bits from the most significant, `0` and `1` are fixed, any other letter is a field
+    /// and `_` separates them.
+    pub pattern: &'static str,
+    /// Bits fixed by the pattern.
+    pub mask: u32,
+    /// Value of the fixed bits.
+    pub value: u32,
+    /// Names of the fields, shown under the bits of an opcode.
+    pub layout: &'static str,
+}
+
+impl<F: Copy> Encoding<F> {
+    /// # Panics
+    /// It panics (at compile time in a const) if the pattern is longer than 32 bits.
+    #[must_use]
+    pub const fn new(format: F, pattern: &'static str, layout: &'static str) -> Self {
+        let bytes = pattern.as_bytes();
+        let mut mask = 0_u32;
+        let mut value = 0_u32;
+        let mut width = 0;
+
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'_' {
+                mask <<= 1;
+                value <<= 1;
+                width += 1;
+                if bytes[i] == b'0' || bytes[i] == b'1' {
+                    mask |= 1;
+                    value |= (bytes[i] - b'0') as u32;
+                }
+            }
+            i += 1;
+        }
+        assert!(width <= 32, "an encoding has at most 32 bits");
+
+        Self {
+            format,
+            pattern,
+            mask,
+            value,
+            layout,
+        }
+    }
+
+    #[must_use]
+    pub const fn matches(&self, op_code: u32) -> bool {
+        op_code & self.mask == self.value
+    }
+
+    /// Bits of the pattern, without the separators.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.pattern.bytes().filter(|&bit| bit != b'_').count()
+    }
+}
+
+/// The first encoding of `encodings` matching `op_code`, they are in order of priority.
+pub fn find<F: Copy>(encodings: &[Encoding<F>], op_code: u32) -> Option<&Encoding<F>> {
+    encodings.iter().find(|encoding| encoding.matches(op_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pattern() {
+        let encoding = Encoding::new((), "cccc_0001_0010_1111", "");
+
+        assert_eq!(encoding.mask, 0x0FFF);
+        assert_eq!(encoding.value, 0x012F);
+        assert_eq!(encoding.width(), 16);
+        assert!(encoding.matches(0xF12F));
+        assert!(!encoding.matches(0xF12E));
+    }
+}
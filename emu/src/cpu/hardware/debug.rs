@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use vecfixed::VecFixed;
+
+use crate::bitwise::Bits;
+
+/// Value to write in `REG_DEBUG_ENABLE` to enable mGBA debug output.
+const MGBA_ENABLE_REQUEST: u16 = 0xC0DE;
+
+/// Value read from `REG_DEBUG_ENABLE` once mGBA debug output is enabled.
+const MGBA_ENABLE_RESPONSE: u16 = 0x1DEA;
+
+/// Size of the mGBA string buffer (0x04FFF600 to 0x04FFF6FF).
+const MGBA_BUFFER_SIZE: usize = 0x100;
+
+/// Read from 0x04FFFA00 by programs checking if they're running on no$gba.
+const NOCASH_ID: &[u8; 16] = b"no$gba v2.7\0\0\0\0\0";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugLevel {
+    Fatal,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl From<u8> for DebugLevel {
+    fn from(value: u8) -> Self {
+        match value & 0b111 {
+            0 => Self::Fatal,
+            1 => Self::Error,
+            2 => Self::Warn,
+            3 => Self::Info,
+            _ => Self::Debug,
+        }
+    }
+}
+
+impl std::fmt::Display for DebugLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fatal => f.write_str("FATAL"),
+            Self::Error => f.write_str("ERROR"),
+            Self::Warn => f.write_str("WARN"),
+            Self::Info => f.write_str("INFO"),
+            Self::Debug => f.write_str("DEBUG"),
+        }
+    }
+}
+
+/// Protocol used by the program to print the message.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugSource {
+    #[default]
+    Mgba,
+    AgbPrint,
+    Nocash,
+}
+
+impl std::fmt::Display for DebugSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mgba => f.write_str("mGBA"),
+            Self::AgbPrint => f.write_str("AGBPrint"),
+            Self::Nocash => f.write_str("no$gba"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugMessage {
+    pub level: DebugLevel,
+    pub source: DebugSource,
+    pub text: String,
+}
+
+impl std::fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] [{}] {}", self.source, self.level, self.text)
+    }
+}
+
+/// Debug output channels used by homebrew to print messages:
+/// - mGBA registers: `REG_DEBUG_STRING` (0x04FFF600), `REG_DEBUG_FLAGS` (0x04FFF700)
+///   and `REG_DEBUG_ENABLE` (0x04FFF780).
+/// - no$gba registers: emulation ID (0x04FFFA00), string out (0x04FFFA10 and 0x04FFFA18)
+///   and char out (0x04FFFA1C).
+/// - VBA `AGBPrint`, flushed by SWI 0xFA (handled by the CPU).
+#[derive(Serialize, Deserialize)]
+pub struct DebugOutput {
+    mgba_enable: u16,
+    mgba_flags: u16,
+    mgba_buffer: Vec<u8>,
+    nocash_string_pointer: u32,
+    nocash_line: String,
+    pub messages: VecFixed<1000, DebugMessage>,
+}
+
+impl Default for DebugOutput {
+    fn default() -> Self {
+        Self {
+            mgba_enable: 0,
+            mgba_flags: 0,
+            mgba_buffer: vec![0; MGBA_BUFFER_SIZE],
+            nocash_string_pointer: 0,
+            nocash_line: String::new(),
+            messages: VecFixed::new(),
+        }
+    }
+}
+
+/// Something the bus has to do after a write in the debug registers.
+pub enum DebugRequest {
+    /// Read a zero terminated string at `pointer` and print it.
+    NocashString { pointer: u32, newline: bool },
+}
+
+impl DebugOutput {
+    const fn is_mgba_enabled(&self) -> bool {
+        self.mgba_enable == MGBA_ENABLE_RESPONSE
+    }
+
+    #[must_use]
+    pub fn read(&self, address: usize) -> u8 {
+        match address {
+            0x04FF_F600..=0x04FF_F6FF if self.is_mgba_enabled() => {
+                self.mgba_buffer[address - 0x04FF_F600]
+            }
+            0x04FF_F700 if self.is_mgba_enabled() => self.mgba_flags.get_byte(0),
+            0x04FF_F701 if self.is_mgba_enabled() => self.mgba_flags.get_byte(1),
+            0x04FF_F780 if self.is_mgba_enabled() => MGBA_ENABLE_RESPONSE.get_byte(0),
+            0x04FF_F781 if self.is_mgba_enabled() => MGBA_ENABLE_RESPONSE.get_byte(1),
+            0x04FF_FA00..=0x04FF_FA0F => NOCASH_ID[address - 0x04FF_FA00],
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: u8) -> Option<DebugRequest> {
+        match address {
+            0x04FF_F600..=0x04FF_F6FF if self.is_mgba_enabled() => {
+                self.mgba_buffer[address - 0x04FF_F600] = value;
+            }
+            0x04FF_F700 if self.is_mgba_enabled() => self.mgba_flags.set_byte(0, value),
+            0x04FF_F701 if self.is_mgba_enabled() => {
+                self.mgba_flags.set_byte(1, value);
+
+                // Bit 8 of `REG_DEBUG_FLAGS` prints the buffer with the level in bits 0-2.
+                if self.mgba_flags.get_bit(8) {
+                    self.flush_mgba();
+                }
+            }
+            0x04FF_F780 => self.mgba_enable.set_byte(0, value),
+            0x04FF_F781 => {
+                self.mgba_enable.set_byte(1, value);
+                if self.mgba_enable == MGBA_ENABLE_REQUEST {
+                    self.mgba_enable = MGBA_ENABLE_RESPONSE;
+                }
+            }
+            0x04FF_FA10..=0x04FF_FA13 | 0x04FF_FA18..=0x04FF_FA1B => {
+                let byte = (address & 0b11) as u8;
+                self.nocash_string_pointer.set_byte(byte, value);
+
+                // The pointer is used once its last byte is written.
+                if byte == 3 {
+                    return Some(DebugRequest::NocashString {
+                        pointer: self.nocash_string_pointer,
+                        newline: address & !0b11 == 0x04FF_FA18,
+                    });
+                }
+            }
+            0x04FF_FA1C => {
+                if value == b'\n' {
+                    let text = std::mem::take(&mut self.nocash_line);
+                    self.push(DebugLevel::Info, DebugSource::Nocash, text);
+                } else {
+                    self.nocash_line.push(char::from(value));
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Prints a no$gba string read by the bus.
+    pub fn print_nocash(&mut self, text: &str, newline: bool) {
+        self.nocash_line.push_str(text);
+
+        if newline {
+            let text = std::mem::take(&mut self.nocash_line);
+            self.push(DebugLevel::Info, DebugSource::Nocash, text);
+        }
+    }
+
+    fn flush_mgba(&mut self) {
+        let length = self
+            .mgba_buffer
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(MGBA_BUFFER_SIZE);
+        let text = String::from_utf8_lossy(&self.mgba_buffer[..length]).into_owned();
+
+        self.push(
+            DebugLevel::from(self.mgba_flags.get_byte(0)),
+            DebugSource::Mgba,
+            text,
+        );
+
+        self.mgba_buffer.fill(0);
+        self.mgba_flags = 0;
+    }
+
+    pub fn push(&mut self, level: DebugLevel, source: DebugSource, text: String) {
+        let message = DebugMessage {
+            level,
+            source,
+            text,
+        };
+
+        logger::log(message.to_string());
+        self.messages.push(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn write_half_word(debug: &mut DebugOutput, address: usize, value: u16) {
+        debug.write(address, value.get_byte(0));
+        debug.write(address + 1, value.get_byte(1));
+    }
+
+    #[test]
+    fn mgba_disabled_by_default() {
+        let mut debug = DebugOutput::default();
+        debug.write(0x04FF_F600, b'a');
+        write_half_word(&mut debug, 0x04FF_F700, 0x100);
+
+        assert_eq!(debug.read(0x04FF_F780), 0);
+        assert!(debug.messages.back().is_none());
+    }
+
+    #[test]
+    fn mgba_print() {
+        let mut debug = DebugOutput::default();
+        write_half_word(&mut debug, 0x04FF_F780, MGBA_ENABLE_REQUEST);
+        assert_eq!(debug.read(0x04FF_F780), 0xEA);
+        assert_eq!(debug.read(0x04FF_F781), 0x1D);
+
+        for (offset, c) in b"hello".iter().enumerate() {
+            debug.write(0x04FF_F600 + offset, *c);
+        }
+        write_half_word(&mut debug, 0x04FF_F700, 0x100 | 2);
+
+        assert_eq!(
+            debug.messages.back(),
+            Some(&DebugMessage {
+                level: DebugLevel::Warn,
+                source: DebugSource::Mgba,
+                text: "hello".to_string(),
+            })
+        );
+        assert_eq!(debug.read(0x04FF_F600), 0);
+    }
+
+    #[test]
+    fn nocash_output() {
+        let mut debug = DebugOutput::default();
+        assert_eq!(debug.read(0x04FF_FA00), b'n');
+
+        for c in b"hi\n" {
+            debug.write(0x04FF_FA1C, *c);
+        }
+        assert_eq!(debug.messages.back().unwrap().text, "hi");
+
+        let mut request = None;
+        for (offset, byte) in 0x0200_0000_u32.to_le_bytes().iter().enumerate() {
+            request = debug.write(0x04FF_FA18 + offset, *byte);
+        }
+        assert!(matches!(
+            request,
+            Some(DebugRequest::NocashString {
+                pointer: 0x0200_0000,
+                newline: true
+            })
+        ));
+    }
+}
@@ -1,14 +1,182 @@
 use serde::{Deserialize, Serialize};
 
+use crate::bitwise::Bits;
+
+/// Only the lower 27 bits of the source are used by DMA0 (internal memory only),
+/// the other channels use 28 bits.
+const SOURCE_MASKS: [u32; 4] = [0x07FF_FFFF, 0x0FFF_FFFF, 0x0FFF_FFFF, 0x0FFF_FFFF];
+
+/// Only DMA3 can write to the Game Pak.
+const DESTINATION_MASKS: [u32; 4] = [0x07FF_FFFF, 0x07FF_FFFF, 0x07FF_FFFF, 0x0FFF_FFFF];
+
+/// Word count is 14 bits for DMA0-2 and 16 bits for DMA3, 0 means the max count.
+const COUNT_MASKS: [u32; 4] = [0x3FFF, 0x3FFF, 0x3FFF, 0xFFFF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressControl {
+    Increment,
+    Decrement,
+    Fixed,
+    /// Increment and reload the destination on repeat, prohibited for the source.
+    IncrementReload,
+}
+
+impl From<u16> for AddressControl {
+    fn from(value: u16) -> Self {
+        match value & 0b11 {
+            0 => Self::Increment,
+            1 => Self::Decrement,
+            2 => Self::Fixed,
+            _ => Self::IncrementReload,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartTiming {
+    Immediately,
+    VBlank,
+    HBlank,
+    /// Sound FIFO for DMA1/2, video capture for DMA3, prohibited for DMA0.
+    Special,
+}
+
+impl From<u16> for StartTiming {
+    fn from(value: u16) -> Self {
+        match value & 0b11 {
+            0 => Self::Immediately,
+            1 => Self::VBlank,
+            2 => Self::HBlank,
+            _ => Self::Special,
+        }
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Registers {
     pub source_address: u32,
     pub destination_address: u32,
     pub word_count: u16,
     pub control: u16,
+
+    /// Internal registers, loaded from the visible ones when the channel gets enabled
+    /// and updated while transferring.
+    pub internal_source: u32,
+    pub internal_destination: u32,
+    pub internal_count: u32,
+}
+
+impl Registers {
+    #[must_use]
+    pub fn destination_control(&self) -> AddressControl {
+        self.control.get_bits(5..=6).into()
+    }
+
+    #[must_use]
+    pub fn source_control(&self) -> AddressControl {
+        self.control.get_bits(7..=8).into()
+    }
+
+    #[must_use]
+    pub fn is_repeat(&self) -> bool {
+        self.control.get_bit(9)
+    }
+
+    /// 32 bits transfer if `true`, 16 bits otherwise.
+    #[must_use]
+    pub fn is_word_transfer(&self) -> bool {
+        self.control.get_bit(10)
+    }
+
+    #[must_use]
+    pub fn start_timing(&self) -> StartTiming {
+        self.control.get_bits(12..=13).into()
+    }
+
+    #[must_use]
+    pub fn is_irq_enabled(&self) -> bool {
+        self.control.get_bit(14)
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.control.get_bit(15)
+    }
+
+    pub fn set_enabled(&mut self, value: bool) {
+        self.control.set_bit(15, value);
+    }
+
+    /// Loads the internal registers, `index` is the channel number (0-3).
+    pub fn latch(&mut self, index: usize) {
+        self.internal_source = self.source_address & SOURCE_MASKS[index];
+        self.internal_destination = self.destination_address & DESTINATION_MASKS[index];
+        self.reload_count(index);
+    }
+
+    /// Loads the word count, it's also done on each repeat.
+    pub fn reload_count(&mut self, index: usize) {
+        let count = u32::from(self.word_count) & COUNT_MASKS[index];
+        self.internal_count = if count == 0 {
+            COUNT_MASKS[index] + 1
+        } else {
+            count
+        };
+    }
+
+    pub const fn reload_destination(&mut self, index: usize) {
+        self.internal_destination = self.destination_address & DESTINATION_MASKS[index];
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Dma {
     pub channels: [Registers; 4],
+
+    /// Last value transferred by any channel. It's what DMA reads from
+    /// invalid sources (BIOS, unused regions and above the Game Pak ROM).
+    /// Halfwords are stored in both halves.
+    pub latch: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn control_fields() {
+        let channel = Registers {
+            control: 0b1101_0110_1100_0000,
+            ..Default::default()
+        };
+
+        assert_eq!(channel.destination_control(), AddressControl::Fixed);
+        assert_eq!(channel.source_control(), AddressControl::Decrement);
+        assert!(channel.is_repeat());
+        assert!(channel.is_word_transfer());
+        assert_eq!(channel.start_timing(), StartTiming::VBlank);
+        assert!(channel.is_irq_enabled());
+        assert!(channel.is_enabled());
+    }
+
+    #[test]
+    fn latch_masks() {
+        let mut channel = Registers {
+            source_address: 0xFFFF_FFFF,
+            destination_address: 0xFFFF_FFFF,
+            word_count: 0,
+            ..Default::default()
+        };
+
+        channel.latch(0);
+        assert_eq!(channel.internal_source, 0x07FF_FFFF);
+        assert_eq!(channel.internal_destination, 0x07FF_FFFF);
+        assert_eq!(channel.internal_count, 0x4000);
+
+        channel.latch(3);
+        assert_eq!(channel.internal_source, 0x0FFF_FFFF);
+        assert_eq!(channel.internal_destination, 0x0FFF_FFFF);
+        assert_eq!(channel.internal_count, 0x1_0000);
+    }
 }
@@ -0,0 +1,567 @@
+use serde::{Deserialize, Serialize};
+
+/// The GPIO port registers are mapped over the ROM, from 0x080000C4 to 0x080000C9.
+pub const GPIO_DATA: usize = 0x0800_00C4;
+pub const GPIO_DIRECTION: usize = 0x0800_00C6;
+pub const GPIO_CONTROL: usize = 0x0800_00C8;
+
+/// Cycles per second of the CPU, the clock of the RTC counts them.
+const CLOCK_FREQUENCY: u128 = 16_777_216;
+
+/// Cartridges with an RTC have the name of the library driving it in the ROM.
+const RTC_LIBRARY: &[u8] = b"SIIRTC_V";
+
+/// Game codes, without the region, of the cartridges with a rumble motor:
+/// Drill Dozer and `WarioWare`: Twisted!
+const RUMBLE_GAMES: [&[u8]; 2] = [b"V49", b"RZW"];
+
+/// Offset of the game code in the cartridge header.
+const GAME_CODE: usize = 0xAC;
+
+/// Serial clock, serial data and chip select pins of the RTC.
+const SCK: u8 = 1 << 0;
+const SIO: u8 = 1 << 1;
+const CS: u8 = 1 << 2;
+
+/// The rumble motor spins while this pin is high.
+const RUMBLE: u8 = 1 << 3;
+
+/// Commands of the S-3511 RTC, bits 4-6 of the command byte.
+const COMMAND_RESET: u8 = 0;
+const COMMAND_DATE_TIME: u8 = 2;
+const COMMAND_CONTROL: u8 = 4;
+const COMMAND_TIME: u8 = 6;
+
+/// Control register bit of the 24-hour mode, 12-hour mode otherwise.
+const CONTROL_24_HOURS: u8 = 1 << 6;
+
+/// General purpose I/O port of the cartridge, with the RTC or the rumble motor attached to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gpio {
+    /// Level of the 4 pins.
+    pins: u8,
+    /// A bit is set when its pin is driven by the GBA, otherwise by the cartridge.
+    direction: u8,
+    /// The registers read back as ROM unless this is set.
+    readable: bool,
+    has_rtc: bool,
+    rtc: Rtc,
+    has_rumble: bool,
+    /// Cycles the motor has spun since it was last asked, see [`Self::take_rumble`].
+    #[serde(skip)]
+    rumble_cycles: u128,
+    /// Cycle the motor was last counted at.
+    #[serde(skip)]
+    rumble_counted_at: u128,
+    /// Cycle of the last [`Self::take_rumble`].
+    #[serde(skip)]
+    rumble_taken_at: u128,
+}
+
+impl Gpio {
+    /// A port with the RTC or the rumble attached if the cartridge uses them, `None` otherwise.
+    #[must_use]
+    pub fn detect(rom: &[u8]) -> Option<Self> {
+        let has_rtc = rom
+            .windows(RTC_LIBRARY.len())
+            .any(|window| window == RTC_LIBRARY);
+        let has_rumble = rom
+            .get(GAME_CODE..GAME_CODE + 3)
+            .is_some_and(|code| RUMBLE_GAMES.contains(&code));
+
+        (has_rtc || has_rumble).then(|| Self {
+            pins: 0,
+            direction: 0,
+            readable: false,
+            has_rtc,
+            rtc: Rtc::default(),
+            has_rumble,
+            rumble_cycles: 0,
+            rumble_counted_at: 0,
+            rumble_taken_at: 0,
+        })
+    }
+
+    #[must_use]
+    pub const fn has_rtc(&self) -> bool {
+        self.has_rtc
+    }
+
+    /// Value of a register byte, `None` when the registers aren't readable.
+    #[must_use]
+    pub const fn read(&self, address: usize) -> Option<u8> {
+        if !self.readable {
+            return None;
+        }
+
+        match address {
+            GPIO_DATA => Some(self.pins),
+            GPIO_DIRECTION => Some(self.direction),
+            GPIO_CONTROL => Some(self.readable as u8),
+            _ => Some(0),
+        }
+    }
+
+    /// `cycles` are the ones run since the boot, the time of the RTC follows them.
+    pub fn write(&mut self, address: usize, value: u8, cycles: u128) {
+        self.count_rumble(cycles);
+
+        match address {
+            GPIO_DATA => {
+                self.pins = (self.pins & !self.direction) | (value & self.direction & 0xF);
+
+                if !self.has_rtc {
+                    return;
+                }
+                if let Some(output) = self.rtc.write_pins(self.pins, cycles) {
+                    self.pins = (self.pins & self.direction) | (output & !self.direction);
+                }
+            }
+            GPIO_DIRECTION => self.direction = value & 0xF,
+            GPIO_CONTROL => self.readable = value & 1 != 0,
+            _ => {}
+        }
+    }
+
+    const fn is_motor_on(&self) -> bool {
+        self.has_rumble && self.pins & self.direction & RUMBLE != 0
+    }
+
+    const fn count_rumble(&mut self, cycles: u128) {
+        if self.is_motor_on() {
+            self.rumble_cycles += cycles.saturating_sub(self.rumble_counted_at);
+        }
+        self.rumble_counted_at = cycles;
+    }
+
+    /// How much the motor spun since the last call, from 0 (never) to 1 (all the time),
+    /// `None` if the cartridge has no rumble. Games make it weaker turning it on and off
+    /// quickly, so this is more than the level of the pin.
+    pub fn take_rumble(&mut self, cycles: u128) -> Option<f32> {
+        if !self.has_rumble {
+            return None;
+        }
+
+        self.count_rumble(cycles);
+        let elapsed = cycles.saturating_sub(self.rumble_taken_at);
+        #[allow(clippy::cast_precision_loss)]
+        let strength = if elapsed == 0 {
+            f32::from(u8::from(self.is_motor_on()))
+        } else {
+            (self.rumble_cycles as f64 / elapsed as f64) as f32
+        };
+
+        self.rumble_cycles = 0;
+        self.rumble_taken_at = cycles;
+
+        Some(strength.min(1.0))
+    }
+
+    /// Unix time read by the game after `cycles` cycles.
+    #[must_use]
+    pub fn rtc_timestamp(&self, cycles: u128) -> u64 {
+        self.rtc.timestamp(cycles)
+    }
+
+    /// Control register of the RTC (eg. the 24-hour mode), kept by its battery.
+    #[must_use]
+    pub const fn rtc_control(&self) -> u8 {
+        self.rtc.control
+    }
+
+    pub const fn set_rtc_control(&mut self, control: u8) {
+        self.rtc.control = control;
+    }
+
+    /// Sets the clock so that it reads `timestamp` after `cycles` cycles.
+    pub fn set_rtc_timestamp(&mut self, timestamp: u64, cycles: u128) {
+        self.rtc.start_timestamp =
+            timestamp.saturating_sub(u64::try_from(cycles / CLOCK_FREQUENCY).unwrap_or(u64::MAX));
+    }
+}
+
+/// Seiko S-3511 real time clock, as used by Pokémon Ruby/Sapphire/Emerald and Boktai.
+///
+/// Its time isn't taken from the host while the game runs: it starts from
+/// `start_timestamp` and advances with the emulated cycles, so a run only
+/// depends on the ROM, the inputs and the starting time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Rtc {
+    /// Unix time at cycle 0.
+    start_timestamp: u64,
+    control: u8,
+    /// 0 and 1 wait for CS to go high with SCK high, 2 is a transfer.
+    transfer_step: u8,
+    /// Command byte, 0 if no command is active.
+    command: u8,
+    bits: u8,
+    bits_read: u8,
+    bytes_remaining: u8,
+    /// Year, month, day, weekday, hour, minute and second in BCD, latched by the commands.
+    date_time: [u8; 7],
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self {
+            // 2000-01-01 00:00:00, the first day the RTC can count.
+            start_timestamp: 946_684_800,
+            control: CONTROL_24_HOURS,
+            transfer_step: 0,
+            command: 0,
+            bits: 0,
+            bits_read: 0,
+            bytes_remaining: 0,
+            date_time: [0; 7],
+        }
+    }
+}
+
+impl Rtc {
+    fn timestamp(&self, cycles: u128) -> u64 {
+        let elapsed = u64::try_from(cycles / CLOCK_FREQUENCY).unwrap_or(u64::MAX);
+
+        self.start_timestamp.saturating_add(elapsed)
+    }
+
+    const fn is_reading(&self) -> bool {
+        self.command & 0x80 != 0
+    }
+
+    /// Level of the pins driven by the RTC after the GBA changed them, if any.
+    fn write_pins(&mut self, pins: u8, cycles: u128) -> Option<u8> {
+        match self.transfer_step {
+            0 => {
+                if pins & (SCK | CS) == SCK {
+                    self.transfer_step = 1;
+                }
+            }
+            1 => {
+                if pins & (SCK | CS) == SCK | CS {
+                    self.transfer_step = 2;
+                } else if pins & (SCK | CS) != SCK {
+                    self.transfer_step = 0;
+                }
+            }
+            _ => {
+                if pins & SCK == 0 {
+                    // The data bit is set while the clock is low, and sampled when it rises.
+                    self.bits &= !(1 << self.bits_read);
+                    self.bits |= ((pins & SIO) >> 1) << self.bits_read;
+                } else if pins & CS != 0 {
+                    if self.is_reading() {
+                        let output = SCK | CS | (self.output_bit() << 1);
+                        self.bits_read += 1;
+                        if self.bits_read == 8 {
+                            self.bits_read = 0;
+                            self.bytes_remaining = self.bytes_remaining.saturating_sub(1);
+                            if self.bytes_remaining == 0 {
+                                self.command = 0;
+                            }
+                        }
+
+                        return Some(output);
+                    }
+
+                    self.bits_read += 1;
+                    if self.bits_read == 8 {
+                        self.process_byte(cycles);
+                    }
+                } else {
+                    // CS low ends the transfer.
+                    self.bits_read = 0;
+                    self.bytes_remaining = 0;
+                    self.command = 0;
+                    self.transfer_step = pins & SCK;
+
+                    return Some(SCK);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn process_byte(&mut self, cycles: u128) {
+        if self.command == 0 {
+            // Commands are sent starting from the most significant bit: 0110 CCC R.
+            if self.bits & 0xF == 0b0110 {
+                let command = (self.bits >> 4) & 0b111;
+                self.bytes_remaining = match command {
+                    COMMAND_DATE_TIME => 7,
+                    COMMAND_CONTROL => 1,
+                    COMMAND_TIME => 3,
+                    _ => 0,
+                };
+
+                match command {
+                    COMMAND_RESET => self.control = 0,
+                    COMMAND_DATE_TIME | COMMAND_TIME => self.latch_date_time(cycles),
+                    _ => {}
+                }
+
+                if self.bytes_remaining > 0 {
+                    self.command = self.bits;
+                }
+            }
+        } else {
+            match (self.command >> 4) & 0b111 {
+                COMMAND_CONTROL => self.control = self.bits,
+                COMMAND_DATE_TIME | COMMAND_TIME => {
+                    // The time command only sends the last 3 registers.
+                    self.date_time[7 - usize::from(self.bytes_remaining.min(7))] = self.bits;
+                    if self.bytes_remaining == 1 {
+                        self.set_date_time(cycles);
+                    }
+                }
+                _ => {}
+            }
+
+            self.bytes_remaining = self.bytes_remaining.saturating_sub(1);
+            if self.bytes_remaining == 0 {
+                self.command = 0;
+            }
+        }
+
+        self.bits = 0;
+        self.bits_read = 0;
+    }
+
+    fn output_bit(&self) -> u8 {
+        let byte = match (self.command >> 4) & 0b111 {
+            COMMAND_CONTROL => self.control,
+            COMMAND_DATE_TIME | COMMAND_TIME => {
+                self.date_time[7 - usize::from(self.bytes_remaining.min(7))]
+            }
+            _ => 0,
+        };
+
+        (byte >> self.bits_read) & 1
+    }
+
+    fn latch_date_time(&mut self, cycles: u128) {
+        let is_24_hours = self.control & CONTROL_24_HOURS != 0;
+        self.date_time = date_time(self.timestamp(cycles), is_24_hours);
+    }
+
+    /// The game set the clock, it keeps counting from the time written.
+    fn set_date_time(&mut self, cycles: u128) {
+        let is_24_hours = self.control & CONTROL_24_HOURS != 0;
+        let timestamp = timestamp(self.date_time, is_24_hours);
+        let elapsed = u64::try_from(cycles / CLOCK_FREQUENCY).unwrap_or(u64::MAX);
+
+        self.start_timestamp = timestamp.saturating_sub(elapsed);
+    }
+}
+
+/// Registers of the RTC for a Unix time, in BCD.
+#[allow(clippy::cast_possible_truncation)] // Every field is less than 100.
+const fn date_time(timestamp: u64, is_24_hours: bool) -> [u8; 7] {
+    let days = timestamp / 86_400;
+    let seconds = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    // 1970-01-01 was a Thursday, Sunday is 0.
+    let weekday = (days + 4) % 7;
+    let hour = seconds / 3600;
+
+    let hour = if is_24_hours {
+        bcd(hour as u8)
+    } else {
+        // Bit 7 is set in the afternoon.
+        bcd((hour % 12) as u8) | if hour >= 12 { 0x80 } else { 0 }
+    };
+
+    [
+        bcd((year % 100) as u8),
+        bcd(month as u8),
+        bcd(day as u8),
+        bcd(weekday as u8),
+        hour,
+        bcd((seconds / 60 % 60) as u8),
+        bcd((seconds % 60) as u8),
+    ]
+}
+
+/// Unix time of the RTC registers, the inverse of [`date_time`]. Out of range
+/// values written by a game are clamped.
+fn timestamp(date_time: [u8; 7], is_24_hours: bool) -> u64 {
+    let from_bcd = |value: u8| u64::from(value >> 4) * 10 + u64::from(value & 0xF);
+
+    let year = 2000 + from_bcd(date_time[0]);
+    let month = from_bcd(date_time[1]).clamp(1, 12);
+    let day = from_bcd(date_time[2]).clamp(1, 31);
+    let hour = from_bcd(date_time[4] & 0x3F);
+    let hour = if is_24_hours {
+        hour
+    } else {
+        hour % 12 + if date_time[4] & 0x80 != 0 { 12 } else { 0 }
+    };
+    let minute = from_bcd(date_time[5]);
+    let second = from_bcd(date_time[6]);
+
+    days_from_civil(year, month, day) * 86_400
+        + hour.min(23) * 3600
+        + minute.min(59) * 60
+        + second.min(59)
+}
+
+/// Days since 1970-01-01 of a date from 1970 on, the inverse of [`civil_from_days`].
+const fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    // Days from March, the leap day is the last one of the year.
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of a number of days since 1970-01-01, in the proleptic Gregorian
+/// calendar (algorithm by Howard Hinnant).
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+const fn bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Sends a byte to the RTC the way the games do, starting from the most significant bit.
+    fn send(gpio: &mut Gpio, byte: u8) {
+        for bit in (0..8).rev() {
+            let sio = ((byte >> bit) & 1) << 1;
+            gpio.write(GPIO_DATA, CS | sio, 0);
+            gpio.write(GPIO_DATA, CS | SCK | sio, 0);
+        }
+    }
+
+    fn receive(gpio: &mut Gpio) -> u8 {
+        (0..8).fold(0, |byte, bit| {
+            gpio.write(GPIO_DATA, CS, 0);
+            gpio.write(GPIO_DATA, CS | SCK, 0);
+            byte | (((gpio.read(GPIO_DATA).unwrap() & SIO) >> 1) << bit)
+        })
+    }
+
+    fn start_command(gpio: &mut Gpio, command: u8) {
+        gpio.write(GPIO_DIRECTION, SCK | SIO | CS, 0);
+        gpio.write(GPIO_DATA, SCK, 0);
+        gpio.write(GPIO_DATA, SCK | CS, 0);
+        send(gpio, command);
+        // SIO is an input while reading.
+        gpio.write(GPIO_DIRECTION, SCK | CS, 0);
+    }
+
+    #[test]
+    fn detect() {
+        assert!(Gpio::detect(b"....SIIRTC_V001....").is_some());
+        assert!(Gpio::detect(b"....FLASH1M_V103...").is_none());
+    }
+
+    #[test]
+    fn date_time_bcd() {
+        // 2024-02-29 13:45:07, a Thursday
+        let timestamp = 1_709_214_307;
+
+        assert_eq!(
+            date_time(timestamp, true),
+            [0x24, 0x02, 0x29, 0x04, 0x13, 0x45, 0x07]
+        );
+        assert_eq!(date_time(timestamp, false)[4], 0x81);
+        assert_eq!(
+            date_time(946_684_800, true),
+            [0x00, 0x01, 0x01, 0x06, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn read_date_time() {
+        let mut gpio = Gpio::detect(RTC_LIBRARY).unwrap();
+        gpio.write(GPIO_CONTROL, 1, 0);
+        gpio.set_rtc_timestamp(1_709_214_307, 0);
+
+        // 0110 010 1: read the date and the time
+        start_command(&mut gpio, 0b0110_0101);
+        let date_time: Vec<_> = (0..7).map(|_| receive(&mut gpio)).collect();
+
+        assert_eq!(date_time, vec![0x24, 0x02, 0x29, 0x04, 0x13, 0x45, 0x07]);
+
+        // The clock follows the emulated cycles.
+        assert_eq!(gpio.rtc_timestamp(CLOCK_FREQUENCY * 60), 1_709_214_367);
+    }
+
+    #[test]
+    fn rumble() {
+        let mut rom = vec![0; 0xC0];
+        rom[GAME_CODE..GAME_CODE + 4].copy_from_slice(b"V49E");
+        let mut gpio = Gpio::detect(&rom).unwrap();
+        assert!(!gpio.has_rtc());
+
+        gpio.write(GPIO_DIRECTION, RUMBLE, 0);
+        gpio.write(GPIO_DATA, RUMBLE, 100);
+        gpio.write(GPIO_DATA, 0, 200);
+        gpio.write(GPIO_DATA, RUMBLE, 300);
+        assert_eq!(gpio.take_rumble(400), Some(0.5));
+        assert_eq!(gpio.take_rumble(500), Some(1.0));
+
+        assert_eq!(Gpio::detect(RTC_LIBRARY).unwrap().take_rumble(0), None);
+    }
+
+    #[test]
+    fn write_date_time() {
+        let mut gpio = Gpio::detect(RTC_LIBRARY).unwrap();
+        gpio.write(GPIO_CONTROL, 1, 0);
+
+        // 0110 010 0: write the date and the time, 2024-02-29 13:45:07
+        start_command(&mut gpio, 0b0110_0100);
+        gpio.write(GPIO_DIRECTION, SCK | SIO | CS, 0);
+        // Data bytes are sent starting from the least significant bit
+        for byte in [0x24_u8, 0x02, 0x29, 0x04, 0x13, 0x45, 0x07] {
+            send(&mut gpio, byte.reverse_bits());
+        }
+        assert_eq!(gpio.rtc_timestamp(0), 1_709_214_307);
+
+        // 0110 011 0 in 12-hour mode: write the time, 1:00:00 PM
+        gpio.set_rtc_control(0);
+        start_command(&mut gpio, 0b0110_0110);
+        gpio.write(GPIO_DIRECTION, SCK | SIO | CS, 0);
+        for byte in [0x81_u8, 0x00, 0x00] {
+            send(&mut gpio, byte.reverse_bits());
+        }
+        assert_eq!(gpio.rtc_timestamp(0), 1_709_211_600);
+    }
+
+    #[test]
+    fn days_round_trip() {
+        for days in [0, 10_956, 11_016, 19_782, 47_482] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn not_readable() {
+        let mut gpio = Gpio::detect(RTC_LIBRARY).unwrap();
+
+        assert_eq!(gpio.read(GPIO_DATA), None);
+        gpio.write(GPIO_CONTROL, 1, 0);
+        assert_eq!(gpio.read(GPIO_CONTROL), Some(1));
+    }
+}
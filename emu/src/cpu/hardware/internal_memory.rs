@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use logger::log;
 use serde::{Deserialize, Serialize};
@@ -28,9 +28,20 @@ pub struct InternalMemory {
     // 0E010000-0FFFFFFF Not used
     pub rom: Vec<u8>,
 
+    /// Writes past the end of the ROM. Debug cartridges have RAM there
+    /// (eg. the `AGBPrint` buffer), indexed like `rom`.
+    rom_ram: BTreeMap<usize, u8>,
+
+    /// From 0x0E000000 to 0x0E00FFFF (64 `KBytes`), on an 8 bits bus.
+    pub(crate) sram: Vec<u8>,
+
+    /// The ROM repeats past its end instead of reading the address bus, the Classic NES
+    /// Series carts check it.
+    rom_mirroring: bool,
+
     /// From 0x00004000 to `0x01FF_FFFF`.
     /// From 0x10000000 to `0xFFFF_FFFF`.
-    unused_region: HashMap<usize, u8>,
+    unused_region: BTreeMap<usize, u8>,
 }
 
 impl Default for InternalMemory {
@@ -42,18 +53,52 @@ impl Default for InternalMemory {
 impl InternalMemory {
     #[must_use]
     pub fn new(bios: [u8; 0x0000_4000], rom: Vec<u8>) -> Self {
+        // Their game codes start with F (eg. FBME for Bomberman).
+        let rom_mirroring = rom.get(0xAC) == Some(&b'F');
+
         Self {
             bios_system_rom: bios.to_vec(),
             working_ram: vec![0; 0x0004_0000],
             working_iram: vec![0; 0x0000_8000],
             rom,
-            unused_region: HashMap::new(),
+            rom_ram: BTreeMap::new(),
+            // Erased, like a new cartridge.
+            sram: vec![0xFF; 0x0001_0000],
+            rom_mirroring,
+            unused_region: BTreeMap::new(),
         }
     }
 
-    fn read_rom(&self, address: usize) -> u8 {
+    #[must_use]
+    pub fn bios(&self) -> &[u8] {
+        &self.bios_system_rom
+    }
+
+    /// EWRAM without its mirrors, for the bus.
+    pub(crate) fn working_ram(&self) -> &[u8] {
+        &self.working_ram
+    }
+
+    pub(crate) fn working_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.working_ram
+    }
+
+    /// IWRAM without its mirrors, for the bus.
+    pub(crate) fn working_iram(&self) -> &[u8] {
+        &self.working_iram
+    }
+
+    pub(crate) fn working_iram_mut(&mut self) -> &mut [u8] {
+        &mut self.working_iram
+    }
+
+    pub(crate) fn read_rom(&self, address: usize) -> u8 {
         if address < self.rom.len() {
             self.rom[address]
+        } else if self.rom_mirroring && !self.rom.is_empty() {
+            self.rom[address % self.rom.len().next_power_of_two() % self.rom.len()]
+        } else if let Some(value) = self.rom_ram.get(&address) {
+            *value
         } else {
             // Preamble:
             // The GamePak ROM is an halfword addressable memory
@@ -96,7 +141,7 @@ impl InternalMemory {
             0x0800_0000..=0x09FF_FFFF => self.read_rom(address - 0x0800_0000),
             0x0A00_0000..=0x0BFF_FFFF => self.read_rom(address - 0x0A00_0000),
             0x0C00_0000..=0x0DFF_FFFF => self.read_rom(address - 0x0C00_0000),
-            0x0E00_0000..=0x0E00_FFFF => unimplemented!("SRAM region is unimplemented"),
+            0x0E00_0000..=0x0E00_FFFF => self.sram[address - 0x0E00_0000],
             0x0000_4000..=0x01FF_FFFF | 0x1000_0000..=0xFFFF_FFFF => {
                 log(format!("read on unused memory {address:x}"));
                 self.unused_region.get(&address).map_or(0, |v| *v)
@@ -120,9 +165,15 @@ impl InternalMemory {
                 self.working_iram[get_unmasked_address(address, 0x00FF_F000, 0xFF00_0FFF, 12, 8)
                     - 0x0300_0000] = value;
             }
+            0x0E00_0000..=0x0E00_FFFF => self.sram[address - 0x0E00_0000] = value,
             0x0800_0000..=0x0FFF_FFFF => {
                 // TODO: this should be split
-                self.rom[address - 0x0800_0000] = value;
+                let offset = address - 0x0800_0000;
+                if offset < self.rom.len() {
+                    self.rom[offset] = value;
+                } else {
+                    self.rom_ram.insert(offset, value);
+                }
             }
             _ => unimplemented!("Unimplemented memory region {address:x}."),
         }
@@ -130,6 +181,7 @@ impl InternalMemory {
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
     use super::*;
 
@@ -189,6 +241,21 @@ mod tests {
         assert_eq!(im.read_at(address), 0xFF);
     }
 
+    #[test]
+    fn test_write_past_rom() {
+        let mut im = InternalMemory {
+            rom: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+
+        im.write_at(0x0800_0001, 10);
+        assert_eq!(im.rom, vec![1, 10, 3, 4]);
+
+        im.write_at(0x09FE_20F8, 0x20);
+        assert_eq!(im.read_at(0x09FE_20F8), 0x20);
+        assert_eq!(im.rom.len(), 4);
+    }
+
     #[test]
     fn test_mirror_3ffffxx() {
         let mut im = InternalMemory::default();
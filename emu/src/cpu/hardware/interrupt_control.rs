@@ -15,6 +15,8 @@ pub struct InterruptControl {
     pub post_boot_flag: u8,
     pub power_down_control: u8,
     pub purpose_unknown: u8,
+    /// Undocumented register at 0x04000800, mirrored every 64KB. Bits 24-27 set the
+    /// EWRAM wait states.
     pub internal_memory_control: u32,
 }
 
@@ -28,7 +30,23 @@ impl Default for InterruptControl {
             post_boot_flag: 0,
             power_down_control: 0,
             purpose_unknown: 0,
-            internal_memory_control: 0,
+            // Value set by the BIOS at boot.
+            internal_memory_control: 0x0D00_0020,
+        }
+    }
+}
+
+impl InterruptControl {
+    /// Wait states of an EWRAM access: 0-14 in bits 24-27 are 15-1 wait states, the BIOS
+    /// sets 2. 15 locks up the GBA, here it's treated as 14.
+    #[must_use]
+    pub const fn ewram_wait_states(&self) -> u128 {
+        let control = (self.internal_memory_control >> 24) & 0xF;
+
+        if control == 0xF {
+            1
+        } else {
+            15 - control as u128
         }
     }
 }
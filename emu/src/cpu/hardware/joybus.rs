@@ -0,0 +1,58 @@
+/// Commands sent by the JOY Bus master (eg. a `GameCube`) to the GBA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoybusCommand {
+    /// 0xFF: resets the device and asks its type.
+    Reset,
+    /// 0x00: asks the device type.
+    Status,
+    /// 0x14: reads `JOY_TRANS`.
+    Read,
+    /// 0x15: writes `JOY_RECV`.
+    Write(u32),
+}
+
+impl JoybusCommand {
+    /// Parses a command as sent on the wire, `None` if it's unknown or truncated.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xFF, ..] => Some(Self::Reset),
+            [0x00, ..] => Some(Self::Status),
+            [0x14, ..] => Some(Self::Read),
+            [0x15, data @ ..] if data.len() >= 4 => Some(Self::Write(u32::from_le_bytes([
+                data[0], data[1], data[2], data[3],
+            ]))),
+            _ => None,
+        }
+    }
+}
+
+/// A JOY Bus master attached to the link port. This is where a bridge to
+/// a `GameCube` emulator plugs in, see `Gba::connect_joybus`.
+pub trait JoybusDevice: Send {
+    /// Next command to send, polled every step.
+    fn poll(&mut self) -> Option<JoybusCommand>;
+
+    /// Answer of the GBA to the last command, empty if the GBA is not in JOY Bus mode.
+    fn respond(&mut self, response: &[u8]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            JoybusCommand::from_bytes(&[0xFF]),
+            Some(JoybusCommand::Reset)
+        );
+        assert_eq!(
+            JoybusCommand::from_bytes(&[0x15, 0x78, 0x56, 0x34, 0x12]),
+            Some(JoybusCommand::Write(0x1234_5678))
+        );
+        assert_eq!(JoybusCommand::from_bytes(&[0x15, 0x78]), None);
+        assert_eq!(JoybusCommand::from_bytes(&[0x42]), None);
+    }
+}
@@ -1,7 +1,122 @@
 use serde::{Deserialize, Serialize};
 
+/// `KEYINPUT` value when no key is pressed, a bit is cleared while its key is pressed.
+pub const NO_KEYS_PRESSED: u16 = 0x03FF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Key {
+    A,
+    B,
+    Select,
+    Start,
+    Right,
+    Left,
+    Up,
+    Down,
+    R,
+    L,
+}
+
+impl Key {
+    pub const ALL: [Self; 10] = [
+        Self::A,
+        Self::B,
+        Self::Select,
+        Self::Start,
+        Self::Right,
+        Self::Left,
+        Self::Up,
+        Self::Down,
+        Self::R,
+        Self::L,
+    ];
+
+    /// Bit of the key in `KEYINPUT`.
+    #[must_use]
+    pub const fn bit(self) -> u8 {
+        self as u8
+    }
+
+    /// `KEYINPUT` with `keys` pressed and the others released.
+    #[must_use]
+    pub fn pressed(keys: &[Self]) -> u16 {
+        keys.iter()
+            .fold(NO_KEYS_PRESSED, |input, key| input & !(1 << key.bit()))
+    }
+
+    /// The key named `name` as in the enum, the case doesn't matter (eg. `start`).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|key| format!("{key:?}").eq_ignore_ascii_case(name))
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Keypad {
     pub key_input: u16,
     pub key_interrupt_control: u16,
 }
+
+impl Keypad {
+    /// The condition of `KEYCNT` is met: bit 14 enables the interrupt, bit 15 asks for
+    /// all the keys selected by bits 0-9 to be pressed instead of any of them.
+    #[must_use]
+    pub const fn is_interrupt_requested(&self) -> bool {
+        let selected = self.key_interrupt_control & NO_KEYS_PRESSED;
+        let pressed = !self.key_input & selected;
+
+        if self.key_interrupt_control & (1 << 14) == 0 || selected == 0 {
+            false
+        } else if self.key_interrupt_control & (1 << 15) != 0 {
+            pressed == selected
+        } else {
+            pressed != 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupt_condition() {
+        // A+B+Start+Select, all of them
+        let mut keypad = Keypad {
+            key_input: NO_KEYS_PRESSED,
+            key_interrupt_control: 0b1100_0000_0000_1111,
+        };
+        assert!(!keypad.is_interrupt_requested());
+
+        keypad.key_input = NO_KEYS_PRESSED & !0b0111;
+        assert!(!keypad.is_interrupt_requested());
+
+        keypad.key_input = NO_KEYS_PRESSED & !0b1111;
+        assert!(keypad.is_interrupt_requested());
+
+        // Any of them
+        keypad.key_interrupt_control = 0b0100_0000_0000_1111;
+        keypad.key_input = NO_KEYS_PRESSED & !0b0010;
+        assert!(keypad.is_interrupt_requested());
+
+        // Disabled
+        keypad.key_interrupt_control = 0b0000_0000_0000_1111;
+        assert!(!keypad.is_interrupt_requested());
+    }
+
+    #[test]
+    fn pressed() {
+        assert_eq!(Key::pressed(&[]), NO_KEYS_PRESSED);
+        assert_eq!(Key::pressed(&[Key::A, Key::Start]), 0x03F6);
+        assert_eq!(Key::pressed(&[Key::L]), 0x01FF);
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Key::from_name("Start"), Some(Key::Start));
+        assert_eq!(Key::from_name("select"), Some(Key::Select));
+        assert_eq!(Key::from_name("X"), None);
+    }
+}
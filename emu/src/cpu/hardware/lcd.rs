@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use logger::log;
 use serde::Deserialize;
 use serde::Serialize;
@@ -6,6 +8,8 @@ use serde_with::serde_as;
 use crate::bitwise::Bits;
 use crate::cpu::hardware::lcd::layers::Layer;
 
+use self::compositor::{LayerId, LayerPixel, TopLayers};
+
 use self::layers::layer_0::Layer0;
 use self::layers::layer_1::Layer1;
 use self::layers::layer_2::Layer2;
@@ -14,12 +18,29 @@ use self::layers::layer_obj::LayerObj;
 use self::memory::Memory;
 use self::registers::Registers;
 
+mod affine_resolution;
+mod clip_buffer;
+mod compositor;
+mod frame_blending;
+mod frame_skip;
+#[cfg(test)]
+#[allow(clippy::decimal_bitwise_operands)]
+mod golden_tests;
+mod index_capture;
 mod layers;
 mod memory;
 mod object_attributes;
 mod point;
+mod recorder;
 mod registers;
 
+pub use self::affine_resolution::AffineResolution;
+pub use self::clip_buffer::ClipBuffer;
+pub use self::frame_blending::FrameBlending;
+pub use self::frame_skip::FrameSkip;
+pub use self::index_capture::{CapturedFrame, IndexCapture, IndexedLayer};
+pub use self::recorder::{Recorder, Recording};
+
 /// GBA display width
 const LCD_WIDTH: usize = 240;
 
@@ -30,6 +51,9 @@ const LCD_HEIGHT: usize = 160;
 /// World height
 const WORLD_HEIGHT: u16 = 256;
 
+/// World width
+const WORLD_WIDTH: u16 = 512;
+
 #[derive(Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Color(pub u16);
 
@@ -50,17 +74,17 @@ impl Color {
 
     #[must_use]
     pub fn red(&self) -> u8 {
-        self.0.get_bits(0..=4) as u8
+        self.0.get_bits_u8(0..=4)
     }
 
     #[must_use]
     pub fn green(&self) -> u8 {
-        self.0.get_bits(5..=9) as u8
+        self.0.get_bits_u8(5..=9)
     }
 
     #[must_use]
     pub fn blue(&self) -> u8 {
-        self.0.get_bits(10..=14) as u8
+        self.0.get_bits_u8(10..=14)
     }
 }
 
@@ -84,6 +108,11 @@ impl From<bool> for ObjMappingKind {
 struct PixelInfo {
     color: Color,
     priority: u8,
+    /// OBJ in semi-transparent mode, always alpha blended over a 2nd target.
+    semi_transparent: bool,
+    /// Index of the color in the BG or OBJ palette, `None` for direct colors (bitmap modes).
+    #[serde(skip)]
+    palette_index: Option<u8>,
 }
 
 #[serde_as]
@@ -103,9 +132,38 @@ pub struct Lcd {
     layer_2: Layer2,
     layer_3: Layer3,
     layer_obj: LayerObj,
+
+    /// Layers hidden by the debugger whatever `DISPCNT` says, bits 0-3 for BG0-3 and bit 4 for OBJ.
+    #[serde(skip)]
+    pub hidden_layers: u8,
+
+    #[serde(skip)]
+    pub frame_skip: FrameSkip,
+    /// Palette indices of the drawn pixels, captured only while it's set.
+    #[serde(skip)]
+    pub index_capture: Option<Box<IndexCapture>>,
+    /// The last frames, kept only while it's set.
+    #[serde(skip)]
+    pub clip_buffer: Option<ClipBuffer>,
+    /// Records every frame while it's set.
+    #[serde(skip)]
+    pub recorder: Option<Recorder>,
+    /// Mixes every frame with the previous one while it's set.
+    #[serde(skip)]
+    pub frame_blending: Option<Box<FrameBlending>>,
+    #[serde(skip)]
+    pub affine_resolution: AffineResolution,
+    /// The pixels of the current frame are not computed, see [`FrameSkip`].
+    #[serde(skip)]
+    is_frame_skipped: bool,
+    #[serde(skip)]
+    skipped_frames: u8,
+    #[serde(skip)]
+    last_frame: Option<Instant>,
 }
 
 impl Default for Lcd {
+    #[allow(clippy::large_stack_arrays)]
     fn default() -> Self {
         Self {
             registers: Registers::default(),
@@ -118,13 +176,33 @@ impl Default for Lcd {
             layer_2: Layer2::default(),
             layer_3: Layer3,
             layer_obj: LayerObj::default(),
+            hidden_layers: 0,
+            frame_skip: FrameSkip::default(),
+            index_capture: None,
+            clip_buffer: None,
+            recorder: None,
+            frame_blending: None,
+            affine_resolution: AffineResolution::default(),
+            is_frame_skipped: false,
+            skipped_frames: 0,
+            last_frame: None,
         }
     }
 }
 
-#[allow(clippy::module_name_repetitions)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct LcdStepOutput {
+    /// Set on the first cycle of `HBlank`, during visible lines only.
+    pub entered_hblank: bool,
+    /// Set on the first cycle of `VBlank`.
+    pub entered_vblank: bool,
+    /// Set once the last line of the frame has been drawn.
+    pub frame_completed: bool,
+    /// Set on the first cycle of `HBlank` of lines 2-161, when DMA3 video capture transfers.
+    pub video_capture: bool,
+    /// Set on the first cycle of line 162, where DMA3 video capture stops.
+    pub video_capture_ended: bool,
     pub request_vblank_irq: bool,
     pub request_hblank_irq: bool,
     pub request_vcount_irq: bool,
@@ -133,7 +211,12 @@ pub struct LcdStepOutput {
 impl Lcd {
     pub fn step(&mut self) -> LcdStepOutput {
         // This will be much more complex obviously
-        let mut output = LcdStepOutput::default();
+        // Video capture lags two lines behind the drawing, so it continues into VBlank.
+        let mut output = LcdStepOutput {
+            video_capture: (2..162).contains(&self.registers.vcount) && self.pixel_index == 240,
+            video_capture_ended: self.registers.vcount == 162 && self.pixel_index == 0,
+            ..Default::default()
+        };
 
         if self.registers.vcount < 160 {
             // We either are in Vdraw or Hblank
@@ -143,15 +226,24 @@ impl Lcd {
                 self.registers.set_hblank_flag(false);
                 self.registers.set_vblank_flag(false);
 
-                self.should_draw = true;
+                self.should_draw = !self.is_frame_skipped;
 
-                // Cache attributes and scanline
-                self.layer_obj
-                    .handle_enter_vdraw(&self.memory, &self.registers);
+                // Cache attributes and scanline, skipping what hasn't been written since the last one.
+                // In skipped frames what's written stays dirty until the next drawn line.
+                if self.should_draw {
+                    let dirty = self.memory.take_dirty();
+                    self.layer_obj.handle_enter_vdraw(
+                        &self.memory,
+                        &self.registers,
+                        &dirty,
+                        self.affine_resolution,
+                    );
+                }
             } else if self.pixel_index == 240 {
                 // We're entering Hblank
 
                 self.registers.set_hblank_flag(true);
+                output.entered_hblank = true;
 
                 if self.registers.get_hblank_irq_enable() {
                     output.request_hblank_irq = true;
@@ -163,6 +255,7 @@ impl Lcd {
             // We're drawing the first pixel of the Vblank period
 
             self.registers.set_vblank_flag(true);
+            output.entered_vblank = true;
 
             if self.registers.get_vblank_irq_enable() {
                 output.request_vblank_irq = true;
@@ -172,30 +265,7 @@ impl Lcd {
         }
 
         if self.should_draw {
-            let pixel_y = self.registers.vcount;
-            let pixel_x = self.pixel_index;
-
-            // We get the enabled layers (depending on BG mode and registers), we call render on them
-            // we filter out the `None` and we sort by priority.
-            let mut layers_with_pixel = self
-                .get_enabled_layers()
-                .into_iter()
-                .filter_map(|layer| {
-                    layer.render(
-                        pixel_x as usize,
-                        pixel_y as usize,
-                        &self.memory,
-                        &self.registers,
-                    )
-                })
-                .collect::<Vec<PixelInfo>>();
-
-            layers_with_pixel.sort_unstable_by_key(|pixel| pixel.priority);
-
-            let first_pixel = layers_with_pixel.first();
-
-            self.buffer[pixel_y as usize][pixel_x as usize] =
-                first_pixel.map_or_else(|| Color::from_rgb(31, 31, 31), |info| info.color);
+            self.draw_pixel();
         }
 
         log(format!(
@@ -219,6 +289,27 @@ impl Lcd {
             // We finished to draw the screen
             if self.registers.vcount == 228 {
                 self.registers.vcount = 0;
+                output.frame_completed = true;
+
+                if let Some(capture) = &mut self.index_capture {
+                    if !self.is_frame_skipped {
+                        capture.complete_frame(
+                            self.memory.bg_palette_ram.as_slice(),
+                            self.memory.obj_palette_ram.as_slice(),
+                        );
+                    }
+                }
+                // Skipped frames are kept too, as the last drawn one, to keep the timing.
+                if let Some(clip_buffer) = &mut self.clip_buffer {
+                    clip_buffer.push(&self.buffer);
+                }
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.push(&self.buffer);
+                }
+                if let Some(frame_blending) = &mut self.frame_blending {
+                    frame_blending.push(&self.buffer);
+                }
+                self.choose_next_frame();
             }
         }
 
@@ -235,32 +326,111 @@ impl Lcd {
         output
     }
 
-    fn get_enabled_layers(&self) -> Vec<&dyn Layer> {
-        let mut result: Vec<&dyn Layer> = Vec::new();
+    /// Composes the pixel being drawn from the enabled layers.
+    fn draw_pixel(&mut self) {
+        let pixel_y = self.registers.vcount;
+        let pixel_x = self.pixel_index;
+
+        let window_control =
+            compositor::window_control(pixel_x as usize, pixel_y as usize, &self.registers);
+
+        // We get the enabled layers (depending on BG mode, registers and window),
+        // we call render on them and we keep the two topmost pixels.
+        let layer_pixels = self
+            .get_enabled_layers()
+            .into_iter()
+            .filter(|(layer_id, _)| window_control.get_bit(*layer_id as u8))
+            .filter_map(|(layer_id, layer)| {
+                layer
+                    .render(
+                        pixel_x as usize,
+                        pixel_y as usize,
+                        &self.memory,
+                        &self.registers,
+                    )
+                    .map(|info| LayerPixel {
+                        layer: layer_id,
+                        info,
+                    })
+            });
+
+        let top_layers = if self.index_capture.is_some() {
+            let layer_pixels = layer_pixels.collect::<Vec<_>>();
+            let top_layers = TopLayers::new(layer_pixels.iter().copied(), self.backdrop_color());
+
+            if let Some(capture) = &mut self.index_capture {
+                capture.record(
+                    pixel_x as usize,
+                    pixel_y as usize,
+                    &layer_pixels,
+                    top_layers.first,
+                );
+            }
+
+            top_layers
+        } else {
+            TopLayers::new(layer_pixels, self.backdrop_color())
+        };
+
+        self.buffer[pixel_y as usize][pixel_x as usize] =
+            compositor::compose(&top_layers, &self.registers, window_control.get_bit(5));
+    }
+
+    /// Scanline (`VCOUNT`) and dot (0-307) being drawn.
+    #[must_use]
+    pub const fn position(&self) -> (u16, u32) {
+        (self.registers.vcount, self.pixel_index)
+    }
+
+    /// Decides if the frame starting is drawn, according to [`Self::frame_skip`].
+    fn choose_next_frame(&mut self) {
+        let now = Instant::now();
+        let frame_time = self.last_frame.replace(now).map(|last| now - last);
+
+        self.is_frame_skipped = !self.frame_skip.draws_next(self.skipped_frames, frame_time);
+        self.skipped_frames = if self.is_frame_skipped {
+            self.skipped_frames.saturating_add(1)
+        } else {
+            0
+        };
+    }
+
+    /// Color 0 of the BG palette, drawn where no layer is.
+    fn backdrop_color(&self) -> Color {
+        let low_nibble = u16::from(self.memory.bg_palette_ram[0]);
+        let high_nibble = u16::from(self.memory.bg_palette_ram[1]);
+
+        Color::from_palette_color((high_nibble << 8) | low_nibble)
+    }
+
+    fn get_enabled_layers(&self) -> Vec<(LayerId, &dyn Layer)> {
+        let mut result: Vec<(LayerId, &dyn Layer)> = Vec::new();
 
         let current_mode = self.registers.get_bg_mode();
 
         if matches!(current_mode, 0 | 1) && self.registers.get_bg0_enabled() {
-            result.push(&self.layer_0);
+            result.push((LayerId::Bg0, &self.layer_0));
         }
 
         if matches!(current_mode, 0 | 1) && self.registers.get_bg1_enabled() {
-            result.push(&self.layer_1);
+            result.push((LayerId::Bg1, &self.layer_1));
         }
 
         // BG2 is available in every mode
         if self.registers.get_bg2_enabled() {
-            result.push(&self.layer_2);
+            result.push((LayerId::Bg2, &self.layer_2));
         }
 
         if matches!(current_mode, 0 | 2) && self.registers.get_bg3_enabled() {
-            result.push(&self.layer_3);
+            result.push((LayerId::Bg3, &self.layer_3));
         }
 
         if self.registers.get_obj_enabled() {
-            result.push(&self.layer_obj);
+            result.push((LayerId::Obj, &self.layer_obj));
         }
 
+        result.retain(|(layer, _)| !self.hidden_layers.get_bit(*layer as u8));
+
         result
     }
 }
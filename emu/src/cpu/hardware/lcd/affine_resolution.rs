@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Resolution the affine sprites are sampled at, to smooth rotated and scaled sprites.
+///
+/// Each pixel is the average of `scale * scale` samples of the texture, the hardware
+/// takes one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AffineResolution {
+    /// One sample for each pixel, as the hardware does.
+    #[default]
+    Native,
+    Double,
+    Quadruple,
+}
+
+impl AffineResolution {
+    pub const ALL: [Self; 3] = [Self::Native, Self::Double, Self::Quadruple];
+
+    /// Samples for each side of a pixel.
+    #[must_use]
+    pub const fn scale(self) -> i32 {
+        match self {
+            Self::Native => 1,
+            Self::Double => 2,
+            Self::Quadruple => 4,
+        }
+    }
+}
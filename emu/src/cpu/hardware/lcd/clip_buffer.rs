@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use crate::render::gif;
+
+use super::{Color, LCD_HEIGHT, LCD_WIDTH};
+
+/// Refresh rate of the LCD, 280896 cycles at 16.78 MHz.
+pub(super) const FRAMES_PER_SECOND: f64 = 59.7275;
+
+/// Only a frame every this many is kept, GIF delays are in hundredths of a second
+/// and most viewers slow down faster animations anyway.
+pub(super) const KEEP_EVERY: u8 = 2;
+
+/// The last seconds of frames, to save a clip of something that just happened.
+#[derive(Debug, Clone)]
+pub struct ClipBuffer {
+    frames: VecDeque<Vec<u16>>,
+    capacity: usize,
+    /// Frames since the last one kept.
+    skipped: u8,
+}
+
+impl ClipBuffer {
+    #[must_use]
+    pub fn new(seconds: u8) -> Self {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let capacity = (f64::from(seconds) * FRAMES_PER_SECOND / f64::from(KEEP_EVERY)) as usize;
+
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+            skipped: 0,
+        }
+    }
+
+    /// Seconds of frames kept.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn seconds(&self) -> f64 {
+        self.frames.len() as f64 * f64::from(KEEP_EVERY) / FRAMES_PER_SECOND
+    }
+
+    pub(super) fn push(&mut self, buffer: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) {
+        self.skipped += 1;
+        if self.skipped < KEEP_EVERY || self.capacity == 0 {
+            return;
+        }
+        self.skipped = 0;
+
+        // The oldest frame is reused, it saves an allocation per frame.
+        let mut frame = if self.frames.len() == self.capacity {
+            self.frames.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(LCD_WIDTH * LCD_HEIGHT)
+        };
+        frame.clear();
+        frame.extend(buffer.iter().flatten().map(|color| color.0));
+
+        self.frames.push_back(frame);
+    }
+
+    /// The frames kept as an animated GIF.
+    #[must_use]
+    pub fn to_gif(&self) -> Vec<u8> {
+        let frames = self.frames.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        #[allow(clippy::cast_possible_truncation)]
+        gif::encode_animation(
+            LCD_WIDTH as u16,
+            LCD_HEIGHT as u16,
+            &frames,
+            100.0 * f64::from(KEEP_EVERY) / FRAMES_PER_SECOND,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    #[allow(clippy::large_stack_arrays)]
+    fn keeps_the_last_seconds() {
+        let mut clip = ClipBuffer::new(1);
+        let mut buffer = [[Color::default(); LCD_WIDTH]; LCD_HEIGHT];
+
+        for frame in 0..100 {
+            buffer[0][0] = Color(frame);
+            clip.push(&buffer);
+        }
+
+        // A frame every two, the last 29 of them
+        assert_eq!(clip.frames.len(), 29);
+        assert_eq!(clip.frames[0][0], 43);
+        assert_eq!(clip.frames[28][0], 99);
+        assert!((clip.seconds() - 0.97).abs() < 0.01);
+        assert!(clip.to_gif().starts_with(b"GIF89a"));
+    }
+}
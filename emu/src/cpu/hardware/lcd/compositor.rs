@@ -0,0 +1,378 @@
+use crate::bitwise::Bits;
+
+use super::registers::Registers;
+use super::{Color, PixelInfo, LCD_HEIGHT, LCD_WIDTH};
+
+/// Layers as numbered in `BLDCNT`, `WININ` and `WINOUT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerId {
+    Bg0,
+    Bg1,
+    Bg2,
+    Bg3,
+    Obj,
+    Backdrop,
+}
+
+impl LayerId {
+    const fn bit(self) -> u8 {
+        self as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    None,
+    Alpha,
+    Brighten,
+    Darken,
+}
+
+impl From<u16> for BlendMode {
+    fn from(value: u16) -> Self {
+        match value & 0b11 {
+            0 => Self::None,
+            1 => Self::Alpha,
+            2 => Self::Brighten,
+            _ => Self::Darken,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LayerPixel {
+    pub layer: LayerId,
+    pub info: PixelInfo,
+}
+
+/// The two topmost layers of a pixel, that's all the hardware needs for blending.
+/// The backdrop is below everything else.
+pub struct TopLayers {
+    pub first: LayerPixel,
+    pub second: LayerPixel,
+}
+
+impl TopLayers {
+    pub fn new(pixels: impl IntoIterator<Item = LayerPixel>, backdrop: Color) -> Self {
+        let backdrop = LayerPixel {
+            layer: LayerId::Backdrop,
+            info: PixelInfo {
+                color: backdrop,
+                // Lower than any layer priority
+                priority: 4,
+                semi_transparent: false,
+                palette_index: Some(0),
+            },
+        };
+
+        let mut top = Self {
+            first: backdrop,
+            second: backdrop,
+        };
+
+        for pixel in pixels {
+            if is_above(pixel, top.first) {
+                top.second = top.first;
+                top.first = pixel;
+            } else if is_above(pixel, top.second) {
+                top.second = pixel;
+            }
+        }
+
+        top
+    }
+}
+
+/// Lower priority value wins, with the same priority OBJ is above the BGs
+/// and BGs are sorted by their number.
+const fn is_above(pixel: LayerPixel, other: LayerPixel) -> bool {
+    if pixel.info.priority != other.info.priority {
+        return pixel.info.priority < other.info.priority;
+    }
+
+    match (pixel.layer, other.layer) {
+        (LayerId::Obj, LayerId::Obj) => false,
+        (LayerId::Obj, _) => true,
+        (_, LayerId::Obj) => false,
+        (layer, other_layer) => layer.bit() < other_layer.bit(),
+    }
+}
+
+/// Whether `position` is inside the window from `start` (included) to `end` (excluded).
+/// A start greater than the end wraps around the screen, an end past the screen is clamped.
+fn is_inside(position: usize, start: u16, end: u16, screen_size: usize) -> bool {
+    let start = usize::from(start);
+    let end = usize::from(end).min(screen_size);
+
+    if start <= end {
+        (start..end).contains(&position)
+    } else {
+        position >= start || position < end
+    }
+}
+
+/// Window control of the pixel: bits 0-4 enable BG0-3 and OBJ, bit 5 enables the color effects.
+/// All enabled if no window is.
+pub fn window_control(x: usize, y: usize, registers: &Registers) -> u8 {
+    let windows = [
+        (
+            registers.get_win0_enabled(),
+            registers.win0h,
+            registers.win0v,
+            registers.winin.get_byte(0),
+        ),
+        (
+            registers.get_win1_enabled(),
+            registers.win1h,
+            registers.win1v,
+            registers.winin.get_byte(1),
+        ),
+    ];
+
+    if !windows.iter().any(|(enabled, ..)| *enabled) && !registers.get_winobj_enabled() {
+        return 0b11_1111;
+    }
+
+    for (enabled, horizontal, vertical, control) in windows {
+        if enabled
+            && is_inside(
+                x,
+                horizontal.get_byte(1).into(),
+                horizontal.get_byte(0).into(),
+                LCD_WIDTH,
+            )
+            && is_inside(
+                y,
+                vertical.get_byte(1).into(),
+                vertical.get_byte(0).into(),
+                LCD_HEIGHT,
+            )
+        {
+            return control;
+        }
+    }
+
+    // TODO: OBJ window. Sprites in OBJ window mode are not rendered yet so every pixel is outside.
+    registers.winout.get_byte(0)
+}
+
+fn blend_alpha(first: Color, second: Color, eva: u16, evb: u16) -> Color {
+    let channel = |a: u8, b: u8| ((u16::from(a) * eva + u16::from(b) * evb) >> 4).min(31) as u8;
+
+    Color::from_rgb(
+        channel(first.red(), second.red()),
+        channel(first.green(), second.green()),
+        channel(first.blue(), second.blue()),
+    )
+}
+
+fn brighten(color: Color, evy: u16) -> Color {
+    let channel = |c: u8| c + (((31 - u16::from(c)) * evy) >> 4) as u8;
+
+    Color::from_rgb(
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue()),
+    )
+}
+
+fn darken(color: Color, evy: u16) -> Color {
+    let channel = |c: u8| c - ((u16::from(c) * evy) >> 4) as u8;
+
+    Color::from_rgb(
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue()),
+    )
+}
+
+/// Final color of the pixel applying the special effects of `BLDCNT`.
+/// `effects_enabled` comes from the window control of the pixel.
+pub fn compose(top: &TopLayers, registers: &Registers, effects_enabled: bool) -> Color {
+    let first = top.first;
+    let second = top.second;
+
+    if !effects_enabled {
+        return first.info.color;
+    }
+
+    let is_second_target = registers.is_blend_second_target(second.layer.bit());
+    let (eva, evb) = registers.get_blend_alpha_coefficients();
+
+    // Semi-transparent OBJs are always blended if they are above a 2nd target,
+    // whatever the mode and their 1st target bit are.
+    if first.info.semi_transparent && is_second_target {
+        return blend_alpha(first.info.color, second.info.color, eva, evb);
+    }
+
+    if !registers.is_blend_first_target(first.layer.bit()) {
+        return first.info.color;
+    }
+
+    match registers.get_blend_mode() {
+        BlendMode::Alpha if is_second_target => {
+            blend_alpha(first.info.color, second.info.color, eva, evb)
+        }
+        BlendMode::None | BlendMode::Alpha => first.info.color,
+        BlendMode::Brighten => brighten(first.info.color, registers.get_blend_brightness()),
+        BlendMode::Darken => darken(first.info.color, registers.get_blend_brightness()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::decimal_bitwise_operands)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pixel(layer: LayerId, priority: u8, color: Color) -> LayerPixel {
+        LayerPixel {
+            layer,
+            info: PixelInfo {
+                color,
+                priority,
+                semi_transparent: false,
+                palette_index: None,
+            },
+        }
+    }
+
+    #[test]
+    fn top_layers_order() {
+        let red = Color::from_rgb(31, 0, 0);
+        let green = Color::from_rgb(0, 31, 0);
+        let blue = Color::from_rgb(0, 0, 31);
+
+        let top = TopLayers::new(
+            [
+                pixel(LayerId::Bg2, 1, red),
+                pixel(LayerId::Obj, 1, green),
+                pixel(LayerId::Bg0, 2, blue),
+            ],
+            Color::default(),
+        );
+        assert_eq!(top.first.layer, LayerId::Obj);
+        assert_eq!(top.second.layer, LayerId::Bg2);
+
+        let top = TopLayers::new(
+            [pixel(LayerId::Bg3, 0, red), pixel(LayerId::Bg1, 0, green)],
+            Color::default(),
+        );
+        assert_eq!(top.first.layer, LayerId::Bg1);
+        assert_eq!(top.second.layer, LayerId::Bg3);
+
+        let top = TopLayers::new([pixel(LayerId::Bg3, 3, red)], blue);
+        assert_eq!(top.second.layer, LayerId::Backdrop);
+        assert_eq!(top.second.info.color.0, blue.0);
+    }
+
+    #[test]
+    fn alpha_blending() {
+        // BG0 1st target, backdrop 2nd target, alpha mode
+        let registers = Registers {
+            bldcnt: 0b10_0000_0100_0001,
+            bldalpha: (8 << 8) | 8,
+            ..Default::default()
+        };
+
+        let top = TopLayers::new(
+            [pixel(LayerId::Bg0, 0, Color::from_rgb(30, 0, 10))],
+            Color::from_rgb(0, 30, 10),
+        );
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(15, 15, 10).0
+        );
+
+        // Effects disabled by the window
+        assert_eq!(
+            compose(&top, &registers, false).0,
+            Color::from_rgb(30, 0, 10).0
+        );
+
+        // BG1 is not a 1st target
+        let top = TopLayers::new(
+            [pixel(LayerId::Bg1, 0, Color::from_rgb(30, 0, 10))],
+            Color::from_rgb(0, 30, 10),
+        );
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(30, 0, 10).0
+        );
+    }
+
+    #[test]
+    fn semi_transparent_obj() {
+        // Brighten mode, OBJ is not a 1st target, BG0 is a 2nd target
+        let registers = Registers {
+            bldcnt: 0b1_1000_0000,
+            bldalpha: (16 << 8) | 16,
+            bldy: 16,
+            ..Default::default()
+        };
+
+        let mut obj = pixel(LayerId::Obj, 0, Color::from_rgb(10, 0, 0));
+        obj.info.semi_transparent = true;
+
+        let top = TopLayers::new(
+            [obj, pixel(LayerId::Bg0, 1, Color::from_rgb(0, 10, 0))],
+            Color::default(),
+        );
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(10, 10, 0).0
+        );
+
+        // Not above a 2nd target: the OBJ is not a 1st target so nothing happens.
+        let top = TopLayers::new(
+            [obj, pixel(LayerId::Bg1, 1, Color::from_rgb(0, 10, 0))],
+            Color::default(),
+        );
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(10, 0, 0).0
+        );
+    }
+
+    #[test]
+    fn brightness() {
+        // Backdrop 1st target, brighten
+        let mut registers = Registers {
+            bldcnt: 0b1010_0000,
+            bldy: 8,
+            ..Default::default()
+        };
+
+        let top = TopLayers::new([], Color::from_rgb(1, 11, 31));
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(16, 21, 31).0
+        );
+
+        registers.bldcnt = 0b1110_0000;
+        assert_eq!(
+            compose(&top, &registers, true).0,
+            Color::from_rgb(1, 6, 16).0
+        );
+    }
+
+    #[test]
+    fn windows() {
+        let mut registers = Registers::default();
+        assert_eq!(window_control(0, 0, &registers), 0b11_1111);
+
+        // WIN0 from (10, 20) to (100, 50), WIN1 wraps horizontally from 200 to 20
+        registers.dispcnt = 0b0110_0000_0000_0000;
+        registers.win0h = (10 << 8) | 100;
+        registers.win0v = (20 << 8) | 50;
+        registers.win1h = (200 << 8) | 20;
+        registers.win1v = 160;
+        registers.winin = (0b10 << 8) | 0b01;
+        registers.winout = 0b10_0000;
+
+        assert_eq!(window_control(10, 20, &registers), 0b01);
+        assert_eq!(window_control(100, 20, &registers), 0b10_0000);
+        assert_eq!(window_control(5, 60, &registers), 0b10);
+        assert_eq!(window_control(239, 0, &registers), 0b10);
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Color, LCD_HEIGHT, LCD_WIDTH};
+
+/// Mixes every frame with the previous one, like the slow pixels of the LCD did. Some
+/// games show a sprite every other frame to make it look transparent, without the mix
+/// it flickers.
+#[derive(Clone)]
+pub struct FrameBlending {
+    /// The last completed frame, as drawn.
+    last: Box<[[Color; LCD_WIDTH]; LCD_HEIGHT]>,
+    /// The last completed frame mixed with the one before.
+    blended: Box<[[Color; LCD_WIDTH]; LCD_HEIGHT]>,
+}
+
+impl Default for FrameBlending {
+    #[allow(clippy::large_stack_arrays)]
+    fn default() -> Self {
+        Self {
+            last: Box::new([[Color::default(); LCD_WIDTH]; LCD_HEIGHT]),
+            blended: Box::new([[Color::default(); LCD_WIDTH]; LCD_HEIGHT]),
+        }
+    }
+}
+
+impl FrameBlending {
+    /// The frame to show in place of the one of the LCD.
+    #[must_use]
+    pub fn frame(&self) -> &[[Color; LCD_WIDTH]; LCD_HEIGHT] {
+        &self.blended
+    }
+
+    pub(super) fn push(&mut self, buffer: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) {
+        for ((blended, last), current) in self
+            .blended
+            .as_flattened_mut()
+            .iter_mut()
+            .zip(self.last.as_flattened_mut())
+            .zip(buffer.as_flattened())
+        {
+            *blended = mix(*last, *current);
+            *last = *current;
+        }
+    }
+}
+
+/// The average of the channels of the two colors.
+fn mix(first: Color, second: Color) -> Color {
+    Color::from_rgb(
+        u8::midpoint(first.red(), second.red()),
+        u8::midpoint(first.green(), second.green()),
+        u8::midpoint(first.blue(), second.blue()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    #[allow(clippy::large_stack_arrays)]
+    fn push() {
+        let mut blending = FrameBlending::default();
+        let mut buffer = [[Color::from_rgb(31, 0, 10); LCD_WIDTH]; LCD_HEIGHT];
+
+        blending.push(&buffer);
+        assert_eq!(blending.frame()[0][0].0, Color::from_rgb(15, 0, 5).0);
+
+        // A sprite shown every other frame looks half transparent.
+        buffer[0][0] = Color::from_rgb(1, 31, 0);
+        blending.push(&buffer);
+        assert_eq!(blending.frame()[0][0].0, Color::from_rgb(16, 15, 5).0);
+        assert_eq!(blending.frame()[0][1].0, Color::from_rgb(31, 0, 10).0);
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Duration of a frame on hardware: 280896 cycles at 16.78 MHz.
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+/// Frames that are not drawn, to keep full speed on slow hosts.
+///
+/// The LCD is still emulated (registers, interrupts, DMA timings), only the pixels
+/// of the skipped frames are not computed and the buffer keeps the last drawn frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameSkip {
+    #[default]
+    Off,
+    /// Draws a frame every `n + 1`.
+    Fixed(u8),
+    /// Skips the frames while the host is slower than the hardware, at most `max` in a row.
+    Auto { max: u8 },
+}
+
+impl FrameSkip {
+    /// Whether the next frame is drawn, after `skipped` frames in a row were skipped.
+    /// `frame_time` is how long the host took to emulate the last frame.
+    #[must_use]
+    pub fn draws_next(self, skipped: u8, frame_time: Option<Duration>) -> bool {
+        match self {
+            Self::Off => true,
+            Self::Fixed(n) => skipped >= n,
+            Self::Auto { max } => {
+                skipped >= max || frame_time.is_none_or(|time| time <= FRAME_DURATION)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn fixed_and_auto() {
+        let drawn = |frame_skip: FrameSkip, frame_times: &[u64]| -> String {
+            let mut skipped = 0;
+            frame_times
+                .iter()
+                .map(|&time| {
+                    let time = Some(Duration::from_millis(time));
+                    if frame_skip.draws_next(skipped, time) {
+                        skipped = 0;
+                        'D'
+                    } else {
+                        skipped += 1;
+                        '.'
+                    }
+                })
+                .collect()
+        };
+
+        assert_eq!(drawn(FrameSkip::Off, &[40; 6]), "DDDDDD");
+        assert_eq!(drawn(FrameSkip::Fixed(2), &[10; 6]), "..D..D");
+
+        // Only while the host is slow, never more than `max` frames in a row
+        let auto = FrameSkip::Auto { max: 2 };
+        assert_eq!(drawn(auto, &[10, 20, 20, 20, 10, 20]), "D..DD.");
+        assert!(auto.draws_next(0, None));
+    }
+}
@@ -0,0 +1,445 @@
+//! Frames drawn by the LCD from hand-made VRAM, OAM and register fixtures, compared pixel
+//! by pixel with the PNGs in `golden/`. A change of the renderer that moves a single pixel
+//! makes them fail, with the new frame written next to the old one as `<name>.new.png`.
+//!
+//! Run them with `UPDATE_GOLDEN=1` to replace the PNGs once the new frames are checked.
+//! Only what the LCD draws is covered: BG2 as a bitmap, sprites, windows and the effects
+//! (the text and affine backgrounds and the OBJ window are not drawn yet).
+
+use std::path::PathBuf;
+
+use super::{Color, Lcd, LCD_HEIGHT, LCD_WIDTH};
+
+/// `DISPCNT` bits.
+const MODE_4: u16 = 4;
+const OBJ_1D_MAPPING: u16 = 1 << 6;
+const BG2: u16 = 1 << 10;
+const OBJ: u16 = 1 << 12;
+const WIN0: u16 = 1 << 13;
+const WIN1: u16 = 1 << 14;
+
+/// OBJ VRAM starts with 32-byte tiles.
+const OBJ_TILES: usize = 0x10000;
+
+/// First tile of the `F` sprites, 16x16 in 4bpp is 4 tiles.
+const F_TILE: u16 = 1;
+/// First tile of the `F` sprite in 8bpp, 2 indices a tile.
+const F_TILE_8BPP: u16 = 5;
+/// First tile of a 32x32 filled square.
+const SQUARE_TILE: u16 = 13;
+
+/// The LCD with the memory written through its tracking, like the bus does.
+struct Fixture {
+    lcd: Lcd,
+}
+
+impl Fixture {
+    /// Every fixture has the same palettes and sprite tiles, see [`Self::shapes`].
+    fn new(dispcnt: u16) -> Self {
+        let mut fixture = Self {
+            lcd: Lcd::default(),
+        };
+        fixture.lcd.registers.dispcnt = dispcnt | OBJ_1D_MAPPING;
+        fixture.palettes();
+        fixture.shapes();
+        fixture.hide_sprites();
+
+        fixture
+    }
+
+    /// BG colors 0-15 are a gray backdrop and blues, 16-31 reds. OBJ palette 0 has
+    /// yellow, green and white for colors 1-3, palette 1 the same in purple; the 256
+    /// colors of 8bpp sprites are a gradient.
+    fn palettes(&mut self) {
+        self.bg_color(0, Color::from_rgb(6, 6, 6));
+        for shade in 1..16 {
+            self.bg_color(shade, Color::from_rgb(0, shade as u8, 16 + shade as u8));
+            self.bg_color(
+                16 + shade,
+                Color::from_rgb(16 + shade as u8, shade as u8, 0),
+            );
+        }
+
+        for (color, rgb) in [(1, (31, 31, 0)), (2, (0, 28, 4)), (3, (31, 31, 31))] {
+            let (red, green, blue) = rgb;
+            self.obj_color(color, Color::from_rgb(red, green, blue));
+            self.obj_color(16 + color, Color::from_rgb(red / 2 + 12, 0, blue / 2 + 12));
+        }
+        for color in 4..256 {
+            let value = color as u8;
+            self.obj_color(
+                color,
+                Color::from_rgb(value % 32, value / 8, 31 - value % 32),
+            );
+        }
+    }
+
+    /// A 16x16 `F` (so flips and rotations show) in 4bpp and 8bpp, and a 32x32 square.
+    fn shapes(&mut self) {
+        let f = |x: usize, y: usize| {
+            let is_stem = (2..5).contains(&x) && (1..15).contains(&y);
+            let is_top = (1..4).contains(&y) && (2..14).contains(&x);
+            let is_middle = (7..10).contains(&y) && (2..10).contains(&x);
+
+            if is_top {
+                3
+            } else if is_stem || is_middle {
+                1 + u8::from(x % 2 == y % 2)
+            } else {
+                0
+            }
+        };
+
+        self.sprite_4bpp(F_TILE, 16, 16, f);
+        self.sprite_8bpp(F_TILE_8BPP, 16, 16, |x, y| {
+            if f(x, y) == 0 {
+                0
+            } else {
+                (4 + x * 8 + y * 4) as u8
+            }
+        });
+        self.sprite_4bpp(SQUARE_TILE, 32, 32, |_, _| 1);
+    }
+
+    fn hide_sprites(&mut self) {
+        for obj in 0..128 {
+            self.obj(obj, [0x0200, 0, 0]);
+        }
+    }
+
+    fn bg_color(&mut self, index: usize, color: Color) {
+        for (offset, byte) in color.0.to_le_bytes().into_iter().enumerate() {
+            self.lcd
+                .memory
+                .write_bg_palette_ram(index * 2 + offset, byte);
+        }
+    }
+
+    fn obj_color(&mut self, index: usize, color: Color) {
+        for (offset, byte) in color.0.to_le_bytes().into_iter().enumerate() {
+            self.lcd
+                .memory
+                .write_obj_palette_ram(index * 2 + offset, byte);
+        }
+    }
+
+    /// Tiles of a sprite from `tile` with 1D mapping, `color` gives the palette index of
+    /// each pixel.
+    fn sprite_4bpp(
+        &mut self,
+        tile: u16,
+        width: usize,
+        height: usize,
+        color: impl Fn(usize, usize) -> u8,
+    ) {
+        for y in 0..height {
+            for x in (0..width).step_by(2) {
+                let index = usize::from(tile) + (y / 8) * (width / 8) + x / 8;
+                let offset = OBJ_TILES + index * 32 + (y % 8) * 4 + (x % 8) / 2;
+                let byte = (color(x + 1, y) << 4) | color(x, y);
+                self.lcd.memory.write_video_ram(offset, byte);
+            }
+        }
+    }
+
+    fn sprite_8bpp(
+        &mut self,
+        tile: u16,
+        width: usize,
+        height: usize,
+        color: impl Fn(usize, usize) -> u8,
+    ) {
+        for y in 0..height {
+            for x in 0..width {
+                let index = usize::from(tile) + ((y / 8) * (width / 8) + x / 8) * 2;
+                let offset = OBJ_TILES + index * 32 + (y % 8) * 8 + x % 8;
+                self.lcd.memory.write_video_ram(offset, color(x, y));
+            }
+        }
+    }
+
+    /// Mode 4 bitmap of 16x16 squares, blue ones and red ones, brighter to the right.
+    fn checkerboard(&mut self) {
+        for y in 0..LCD_HEIGHT {
+            for x in 0..LCD_WIDTH {
+                let shade = 1 + x * 15 / LCD_WIDTH;
+                let half = ((x / 16 + y / 16) % 2) * 16;
+                self.lcd
+                    .memory
+                    .write_video_ram(y * LCD_WIDTH + x, (half + shade) as u8);
+            }
+        }
+    }
+
+    fn obj(&mut self, obj: usize, attributes: [u16; 3]) {
+        for (index, attribute) in attributes.into_iter().enumerate() {
+            for (offset, byte) in attribute.to_le_bytes().into_iter().enumerate() {
+                self.lcd
+                    .memory
+                    .write_obj_attributes(obj * 8 + index * 2 + offset, byte);
+            }
+        }
+    }
+
+    /// `pa`, `pb`, `pc` and `pd` of a group, 8.8 fixed point.
+    fn rotation_scaling(&mut self, group: usize, params: [i16; 4]) {
+        for (index, param) in params.into_iter().enumerate() {
+            for (offset, byte) in param.to_le_bytes().into_iter().enumerate() {
+                self.lcd
+                    .memory
+                    .write_obj_attributes(group * 32 + index * 8 + 6 + offset, byte);
+            }
+        }
+    }
+
+    /// A row of 16x16 `F` sprites from `first`, every other one semi-transparent if
+    /// `semi_transparent`.
+    fn row_of_f(&mut self, first: usize, y: u16, semi_transparent: bool) {
+        for index in 0..8_u16 {
+            let mode = if semi_transparent && index % 2 == 1 {
+                1 << 10
+            } else {
+                0
+            };
+            self.obj(
+                first + usize::from(index),
+                [mode | y, (1 << 14) | (8 + index * 28), F_TILE],
+            );
+        }
+    }
+
+    /// Draws a whole frame.
+    fn render(mut self) -> Box<[[Color; LCD_WIDTH]; LCD_HEIGHT]> {
+        while !self.lcd.step().frame_completed {}
+
+        Box::new(self.lcd.buffer)
+    }
+}
+
+fn golden_path(name: &str, extension: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/cpu/hardware/lcd/golden")
+        .join(format!("{name}.{extension}"))
+}
+
+/// 8 bits a channel, the 3 high bits repeated in the low ones.
+fn frame_image(frame: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) -> image::RgbImage {
+    let expand = |channel: u8| (channel << 3) | (channel >> 2);
+
+    image::RgbImage::from_fn(LCD_WIDTH as u32, LCD_HEIGHT as u32, |x, y| {
+        let color = frame[y as usize][x as usize];
+        image::Rgb([
+            expand(color.red()),
+            expand(color.green()),
+            expand(color.blue()),
+        ])
+    })
+}
+
+fn assert_golden(name: &str, frame: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) {
+    let actual = frame_image(frame);
+    let path = golden_path(name, "png");
+    let new_path = golden_path(name, "new.png");
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&path).unwrap();
+        let _ = std::fs::remove_file(&new_path);
+        return;
+    }
+
+    let Ok(expected) = image::open(&path) else {
+        actual.save(&new_path).unwrap();
+        panic!(
+            "{} is missing, the frame is in {}",
+            path.display(),
+            new_path.display()
+        );
+    };
+    let expected = expected.to_rgb8();
+
+    let different = actual
+        .enumerate_pixels()
+        .filter(|&(x, y, pixel)| expected.get_pixel_checked(x, y) != Some(pixel))
+        .map(|(x, y, _)| (x, y))
+        .collect::<Vec<_>>();
+    if different.is_empty() && expected.dimensions() == actual.dimensions() {
+        let _ = std::fs::remove_file(&new_path);
+        return;
+    }
+
+    actual.save(&new_path).unwrap();
+    panic!(
+        "{} pixels of `{name}` changed, the first at {:?}: compare {} with {}",
+        different.len(),
+        different.first(),
+        path.display(),
+        new_path.display()
+    );
+}
+
+#[test]
+fn bitmap_mode_4() {
+    let mut fixture = Fixture::new(MODE_4 | BG2);
+    fixture.checkerboard();
+
+    assert_golden("bitmap_mode_4", &fixture.render());
+}
+
+#[test]
+fn sprites() {
+    let mut fixture = Fixture::new(OBJ);
+
+    // Flips: none, horizontal, vertical, both
+    for (obj, flip) in [0, 1 << 12, 1 << 13, 3 << 12].into_iter().enumerate() {
+        let x = 8 + obj as u16 * 24;
+        fixture.obj(obj, [8, (1 << 14) | flip | x, F_TILE]);
+    }
+    // Palette 1, 8bpp
+    fixture.obj(4, [8, (1 << 14) | 104, (1 << 12) | F_TILE]);
+    fixture.obj(5, [(1 << 13) | 8, (1 << 14) | 128, F_TILE_8BPP]);
+    // Wide and tall: the square cut as 32x16 and 16x32
+    fixture.obj(6, [(1 << 14) | 8, (2 << 14) | 152, SQUARE_TILE]);
+    fixture.obj(7, [(2 << 14) | 8, (2 << 14) | 200, SQUARE_TILE]);
+
+    // Overlapping: the lower OBJ number is above with the same priority, the priority
+    // goes first
+    fixture.obj(8, [48, (1 << 14) | 8, F_TILE]);
+    fixture.obj(9, [52, (1 << 14) | 12, (1 << 12) | F_TILE]);
+    fixture.obj(10, [48, (1 << 14) | 40, (1 << 10) | F_TILE]);
+    fixture.obj(11, [52, (1 << 14) | 44, (1 << 12) | F_TILE]);
+
+    // Across the borders of the screen, x = -8 and y = -8
+    fixture.obj(12, [80, (1 << 14) | 504, F_TILE]);
+    fixture.obj(13, [248, (1 << 14) | 100, F_TILE]);
+    fixture.obj(14, [152, (1 << 14) | 232, F_TILE]);
+
+    assert_golden("sprites", &fixture.render());
+}
+
+#[test]
+fn affine_sprites() {
+    let mut fixture = Fixture::new(OBJ);
+
+    // Identity, 45 degrees, twice as big, mirrored and half as big
+    fixture.rotation_scaling(0, [0x100, 0, 0, 0x100]);
+    fixture.rotation_scaling(1, [0xB5, -0xB5, 0xB5, 0xB5]);
+    fixture.rotation_scaling(2, [0x80, 0, 0, 0x80]);
+    fixture.rotation_scaling(3, [-0x200, 0, 0, 0x200]);
+
+    for group in 0..4_u16 {
+        let x = 8 + group * 40;
+        // Normal size: the sprite is clipped by its 16x16 box
+        fixture.obj(
+            usize::from(group),
+            [0x0100 | 16, (1 << 14) | (group << 9) | x, F_TILE],
+        );
+        // Double size: the box is 32x32
+        fixture.obj(
+            4 + usize::from(group),
+            [0x0300 | 64, (1 << 14) | (group << 9) | x, F_TILE],
+        );
+    }
+    // 8bpp
+    fixture.obj(
+        8,
+        [
+            0x0300 | (1 << 13) | 112,
+            (1 << 14) | (1 << 9) | 8,
+            F_TILE_8BPP,
+        ],
+    );
+
+    assert_golden("affine_sprites", &fixture.render());
+}
+
+#[test]
+fn mosaic() {
+    let mut fixture = Fixture::new(OBJ);
+    fixture.lcd.registers.mosaic = (3 << 12) | (1 << 8);
+
+    // Without and with the mosaic bit, the blocks are aligned to the screen
+    fixture.obj(0, [16, (1 << 14) | 16, F_TILE]);
+    fixture.obj(1, [(1 << 12) | 16, (1 << 14) | 40, F_TILE]);
+    fixture.obj(2, [(1 << 12) | 17, (1 << 14) | 65, F_TILE]);
+    // 8bpp and affine (the mosaic comes before the transformation)
+    fixture.obj(3, [(1 << 13) | (1 << 12) | 16, (1 << 14) | 96, F_TILE_8BPP]);
+    fixture.rotation_scaling(0, [0xB5, -0xB5, 0xB5, 0xB5]);
+    fixture.obj(4, [0x0300 | (1 << 12) | 8, (1 << 14) | 128, F_TILE]);
+
+    assert_golden("mosaic", &fixture.render());
+}
+
+#[test]
+fn windows() {
+    let mut fixture = Fixture::new(MODE_4 | BG2 | OBJ | WIN0 | WIN1);
+    fixture.checkerboard();
+    fixture.row_of_f(0, 40, false);
+    fixture.row_of_f(8, 90, false);
+
+    let registers = &mut fixture.lcd.registers;
+    // WIN0 from (20, 20) to (120, 100) shows BG2, WIN1 from (80, 60) to (200, 140)
+    // shows the sprites; WIN0 goes first where they overlap
+    registers.win0h = (20 << 8) | 120;
+    registers.win0v = (20 << 8) | 100;
+    registers.win1h = (80 << 8) | 200;
+    registers.win1v = (60 << 8) | 140;
+    registers.winin = (0b1_0000 << 8) | 0b0100;
+    // Outside both everything is shown
+    registers.winout = 0b1_0100;
+
+    assert_golden("windows", &fixture.render());
+}
+
+#[test]
+fn alpha_blending() {
+    let mut fixture = Fixture::new(MODE_4 | BG2 | OBJ);
+    fixture.checkerboard();
+    // The semi-transparent sprites are blended even if OBJ is not a 1st target
+    fixture.row_of_f(0, 30, true);
+    fixture.row_of_f(8, 100, false);
+
+    let registers = &mut fixture.lcd.registers;
+    // BG2 is the 2nd target, OBJ the 1st only in the bottom half (WIN0)
+    registers.dispcnt |= WIN0;
+    registers.win0h = 240;
+    registers.win0v = (80 << 8) | 160;
+    registers.winin = 0b11_0100;
+    registers.winout = 0b01_0100;
+    registers.bldcnt = (0b0100 << 8) | (1 << 6) | 0b1_0000;
+    registers.bldalpha = (6 << 8) | 10;
+
+    assert_golden("alpha_blending", &fixture.render());
+}
+
+#[test]
+fn brightness() {
+    let mut fixture = Fixture::new(MODE_4 | BG2 | OBJ);
+    fixture.checkerboard();
+    fixture.row_of_f(0, 72, false);
+
+    let registers = &mut fixture.lcd.registers;
+    // BG2 brighter, not the sprites; the effect is off inside WIN0
+    registers.dispcnt |= WIN0;
+    registers.win0h = (60 << 8) | 180;
+    registers.win0v = (40 << 8) | 120;
+    registers.winin = 0b01_0100;
+    registers.winout = 0b11_0100;
+    registers.bldcnt = (2 << 6) | 0b0100;
+    registers.bldy = 10;
+
+    assert_golden("brightness", &fixture.render());
+}
+
+#[test]
+fn darkness() {
+    let mut fixture = Fixture::new(MODE_4 | BG2 | OBJ);
+    fixture.checkerboard();
+    fixture.row_of_f(0, 72, false);
+
+    // BG2 and the sprites darker
+    let registers = &mut fixture.lcd.registers;
+    registers.bldcnt = (3 << 6) | 0b11_0100;
+    registers.bldy = 12;
+
+    assert_golden("darkness", &fixture.render());
+}
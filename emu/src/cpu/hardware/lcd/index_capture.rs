@@ -0,0 +1,276 @@
+use crate::render::png;
+
+use super::compositor::{LayerId, LayerPixel};
+use super::{LCD_HEIGHT, LCD_WIDTH};
+
+/// Planes captured for every pixel: the frame, then BG0-3 and OBJ.
+const PLANES: usize = 6;
+
+/// What an indexed export contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexedLayer {
+    /// The topmost pixels as drawn, without blending and brightness effects.
+    Frame,
+    Bg(u8),
+    Obj,
+}
+
+impl IndexedLayer {
+    pub const ALL: [Self; PLANES] = [
+        Self::Frame,
+        Self::Bg(0),
+        Self::Bg(1),
+        Self::Bg(2),
+        Self::Bg(3),
+        Self::Obj,
+    ];
+
+    const fn plane(self) -> usize {
+        match self {
+            Self::Frame => 0,
+            Self::Bg(bg) => 1 + bg as usize,
+            Self::Obj => 5,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Sample {
+    #[default]
+    Transparent,
+    Indexed {
+        is_obj: bool,
+        index: u8,
+    },
+    /// A bitmap mode color, it's in no palette.
+    Direct,
+}
+
+impl Sample {
+    fn of(pixel: LayerPixel) -> Self {
+        pixel
+            .info
+            .palette_index
+            .map_or(Self::Direct, |index| Self::Indexed {
+                is_obj: pixel.layer == LayerId::Obj,
+                index,
+            })
+    }
+}
+
+/// Palette indices of the pixels as they're drawn, for exports that keep them
+/// (eg. sprite rips). Capturing costs some speed, so it's only done while this is
+/// set in `Lcd::index_capture`.
+#[derive(Debug, Clone)]
+pub struct IndexCapture {
+    drawing: Vec<[Sample; PLANES]>,
+    last_frame: Option<CapturedFrame>,
+}
+
+impl Default for IndexCapture {
+    fn default() -> Self {
+        Self {
+            drawing: vec![[Sample::Transparent; PLANES]; LCD_WIDTH * LCD_HEIGHT],
+            last_frame: None,
+        }
+    }
+}
+
+impl IndexCapture {
+    /// The last frame drawn completely since the capture started.
+    #[must_use]
+    pub const fn last_frame(&self) -> Option<&CapturedFrame> {
+        self.last_frame.as_ref()
+    }
+
+    /// `layers` are the pixels of the visible layers, `top` the one drawn.
+    pub(super) fn record(&mut self, x: usize, y: usize, layers: &[LayerPixel], top: LayerPixel) {
+        let samples = &mut self.drawing[y * LCD_WIDTH + x];
+        *samples = [Sample::Transparent; PLANES];
+
+        for pixel in layers {
+            let plane = match pixel.layer {
+                LayerId::Obj => IndexedLayer::Obj.plane(),
+                LayerId::Backdrop => continue,
+                layer => IndexedLayer::Bg(layer as u8).plane(),
+            };
+
+            samples[plane] = Sample::of(*pixel);
+        }
+
+        samples[IndexedLayer::Frame.plane()] = Sample::of(top);
+    }
+
+    /// Keeps the frame just drawn with the palettes it was drawn with.
+    pub(super) fn complete_frame(&mut self, bg_palette_ram: &[u8], obj_palette_ram: &[u8]) {
+        let samples = std::mem::replace(
+            &mut self.drawing,
+            vec![[Sample::Transparent; PLANES]; LCD_WIDTH * LCD_HEIGHT],
+        );
+
+        self.last_frame = Some(CapturedFrame {
+            samples,
+            bg_palette: read_palette(bg_palette_ram),
+            obj_palette: read_palette(obj_palette_ram),
+        });
+    }
+}
+
+/// A frame with the palette indices of its pixels.
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    samples: Vec<[Sample; PLANES]>,
+    bg_palette: Vec<[u8; 3]>,
+    obj_palette: Vec<[u8; 3]>,
+}
+
+impl CapturedFrame {
+    /// An 8-bit paletted PNG of `layer` with the original indices and the whole palette.
+    /// Layers are transparent where they have no pixel, with index 0 like on hardware.
+    ///
+    /// # Errors
+    /// It returns an error if a pixel has no palette index (bitmap modes) or if the
+    /// frame mixes BG and OBJ pixels, their 256 colors palettes don't fit in a PNG.
+    pub fn to_png(&self, layer: IndexedLayer) -> Result<Vec<u8>, String> {
+        let plane = layer.plane();
+        let mut is_obj = None;
+        let mut indices = Vec::with_capacity(self.samples.len());
+
+        for samples in &self.samples {
+            let index = match samples[plane] {
+                Sample::Transparent => 0,
+                Sample::Direct => {
+                    return Err("The frame has direct colors, it's not paletted".to_string())
+                }
+                Sample::Indexed {
+                    is_obj: pixel_is_obj,
+                    index,
+                } => {
+                    if *is_obj.get_or_insert(pixel_is_obj) != pixel_is_obj {
+                        return Err(
+                            "The frame mixes BG and OBJ palettes, export the layers one by one"
+                                .to_string(),
+                        );
+                    }
+                    index
+                }
+            };
+
+            indices.push(index);
+        }
+
+        let palette = if is_obj.unwrap_or(layer == IndexedLayer::Obj) {
+            &self.obj_palette
+        } else {
+            &self.bg_palette
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        png::encode_indexed(
+            LCD_WIDTH as u32,
+            LCD_HEIGHT as u32,
+            &indices,
+            palette,
+            layer != IndexedLayer::Frame,
+        )
+    }
+}
+
+/// The 256 colors of a palette RAM with 8 bits per channel.
+fn read_palette(palette_ram: &[u8]) -> Vec<[u8; 3]> {
+    let widen = |channel: u16| {
+        let channel = (channel & 0x1F) as u8;
+        (channel << 3) | (channel >> 2)
+    };
+
+    palette_ram
+        .chunks_exact(2)
+        .map(|bytes| {
+            let color = u16::from_le_bytes([bytes[0], bytes[1]]);
+            [widen(color), widen(color >> 5), widen(color >> 10)]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::hardware::lcd::{Color, PixelInfo};
+    use pretty_assertions::assert_eq;
+
+    fn pixel(layer: LayerId, palette_index: Option<u8>) -> LayerPixel {
+        LayerPixel {
+            layer,
+            info: PixelInfo {
+                color: Color::default(),
+                priority: 0,
+                semi_transparent: false,
+                palette_index,
+            },
+        }
+    }
+
+    /// Indices of the pixels of a PNG made by `to_png`, its palette and whether index 0
+    /// is transparent.
+    fn decode(png: &[u8]) -> (Vec<u8>, Vec<u8>, bool) {
+        let mut reader = ::png::Decoder::new(png).read_info().unwrap();
+        let mut indices = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut indices).unwrap();
+        let info = reader.info();
+
+        (
+            indices,
+            info.palette.as_deref().unwrap().to_vec(),
+            info.trns.is_some(),
+        )
+    }
+
+    #[test]
+    fn layers_keep_their_indices() {
+        let mut capture = IndexCapture::default();
+        let bg2 = pixel(LayerId::Bg2, Some(7));
+        let obj = pixel(LayerId::Obj, Some(0x21));
+        capture.record(0, 0, &[bg2, obj], obj);
+        capture.record(1, 0, &[bg2], bg2);
+
+        let mut obj_palette_ram = [0; 0x200];
+        obj_palette_ram[0x42] = 0x1F;
+        capture.complete_frame(&[0; 0x200], &obj_palette_ram);
+        let frame = capture.last_frame().unwrap();
+
+        let (indices, palette, is_transparent) = decode(&frame.to_png(IndexedLayer::Obj).unwrap());
+        assert_eq!(indices[..2], [0x21, 0]);
+        assert_eq!(palette[0x21 * 3..0x22 * 3], [255, 0, 0]);
+        assert!(is_transparent);
+
+        let (indices, _, _) = decode(&frame.to_png(IndexedLayer::Bg(2)).unwrap());
+        assert_eq!(indices[..2], [7, 7]);
+
+        assert!(frame.to_png(IndexedLayer::Frame).is_err());
+    }
+
+    #[test]
+    fn frame_of_a_single_palette() {
+        let mut capture = IndexCapture::default();
+        capture.record(0, 0, &[], pixel(LayerId::Backdrop, Some(0)));
+        capture.record(1, 0, &[], pixel(LayerId::Bg2, Some(3)));
+        capture.complete_frame(&[0; 0x200], &[0; 0x200]);
+
+        let png = capture
+            .last_frame()
+            .unwrap()
+            .to_png(IndexedLayer::Frame)
+            .unwrap();
+        let (indices, _, is_transparent) = decode(&png);
+        assert_eq!(indices[..2], [0, 3]);
+        assert!(!is_transparent);
+
+        capture.record(0, 0, &[], pixel(LayerId::Bg2, None));
+        capture.complete_frame(&[0; 0x200], &[0; 0x200]);
+        assert!(capture
+            .last_frame()
+            .unwrap()
+            .to_png(IndexedLayer::Frame)
+            .is_err());
+    }
+}
@@ -38,7 +38,9 @@ impl Layer for Layer2 {
 
         Some(PixelInfo {
             color: Color::from_palette_color((high_nibble << 8) | low_nibble),
-            priority: 0,
+            priority: registers.get_bg_priority(2),
+            semi_transparent: false,
+            palette_index: Some(memory.video_ram[idx]),
         })
     }
 }
@@ -1,17 +1,85 @@
 use crate::cpu::hardware::lcd;
-use crate::cpu::hardware::lcd::memory::Memory;
+use crate::cpu::hardware::lcd::memory::{DirtyRegions, Memory, VRAM_BLOCK_SIZE};
 use crate::cpu::hardware::lcd::object_attributes;
 use crate::cpu::hardware::lcd::point::Point;
 use crate::cpu::hardware::lcd::registers::Registers;
-use crate::cpu::hardware::lcd::Color;
-use crate::cpu::hardware::lcd::{PixelInfo, LCD_WIDTH, WORLD_HEIGHT};
+use crate::cpu::hardware::lcd::{AffineResolution, Color};
+use crate::cpu::hardware::lcd::{PixelInfo, LCD_WIDTH, WORLD_HEIGHT, WORLD_WIDTH};
 
 use super::Layer;
-use crate::bitwise::Bits;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_with::serde_as;
 
+/// Where OBJ tiles start in VRAM.
+const OBJ_VRAM_START: usize = 0x10000;
+
+/// OBJ tiles are in the last 32k of VRAM.
+const OBJ_VRAM_SIZE: usize = 0x8000;
+
+/// Cycles available to render the sprites of a line: the whole line, or the visible part
+/// only when OAM is accessible during `HBlank`.
+const OBJ_CYCLES_PER_LINE: u16 = 1210;
+const OBJ_CYCLES_PER_LINE_HBLANK_FREE: u16 = 954;
+
+/// Lines of the OBJ tiles decoded to palette indices, one byte for each pixel.
+/// Lines are decoded the first time they are drawn and dropped when VRAM is written.
+struct TileLineCache {
+    /// One entry for each 4 bytes of OBJ VRAM.
+    lines_4bpp: Vec<Option<[u8; 8]>>,
+    /// One entry for each 8 bytes of OBJ VRAM.
+    lines_8bpp: Vec<Option<[u8; 8]>>,
+}
+
+impl Default for TileLineCache {
+    fn default() -> Self {
+        Self {
+            lines_4bpp: vec![None; OBJ_VRAM_SIZE / 4],
+            lines_8bpp: vec![None; OBJ_VRAM_SIZE / 8],
+        }
+    }
+}
+
+impl TileLineCache {
+    fn invalidate(&mut self, dirty: &DirtyRegions) {
+        for block in dirty.dirty_video_ram_blocks(OBJ_VRAM_START..OBJ_VRAM_START + OBJ_VRAM_SIZE) {
+            let offset = block - OBJ_VRAM_START;
+
+            self.lines_4bpp[offset / 4..(offset + VRAM_BLOCK_SIZE) / 4].fill(None);
+            self.lines_8bpp[offset / 8..(offset + VRAM_BLOCK_SIZE) / 8].fill(None);
+        }
+    }
+
+    /// `address` is the offset in OBJ VRAM of the first byte of the line.
+    fn get(
+        &mut self,
+        address: usize,
+        color_mode: object_attributes::ColorMode,
+        video_ram: &[u8],
+    ) -> [u8; 8] {
+        // Tile numbers can go past the end of OBJ VRAM, they wrap around.
+        let address = address % OBJ_VRAM_SIZE;
+        let bytes = &video_ram[OBJ_VRAM_START + address..];
+
+        match color_mode {
+            object_attributes::ColorMode::Palette4bpp => {
+                *self.lines_4bpp[address / 4].get_or_insert_with(|| {
+                    // Two pixels in each byte, the left one in the low nibble
+                    std::array::from_fn(|x| {
+                        if x % 2 == 0 {
+                            bytes[x / 2] & 0xF
+                        } else {
+                            bytes[x / 2] >> 4
+                        }
+                    })
+                })
+            }
+            object_attributes::ColorMode::Palette8bpp => *self.lines_8bpp[address / 8]
+                .get_or_insert_with(|| std::array::from_fn(|x| bytes[x])),
+        }
+    }
+}
+
 #[serde_as]
 #[derive(Serialize, Deserialize)]
 pub struct LayerObj {
@@ -23,6 +91,9 @@ pub struct LayerObj {
 
     #[serde_as(as = "[_; 240]")]
     sprite_pixels_scanline: [Option<PixelInfo>; LCD_WIDTH],
+
+    #[serde(skip)]
+    tile_lines: TileLineCache,
 }
 
 impl Default for LayerObj {
@@ -31,6 +102,7 @@ impl Default for LayerObj {
             obj_attributes_arr: [object_attributes::ObjAttributes::default(); 128],
             rotation_scaling_params: [object_attributes::RotationScaling::default(); 32],
             sprite_pixels_scanline: [None; LCD_WIDTH],
+            tile_lines: TileLineCache::default(),
         }
     }
 }
@@ -50,8 +122,8 @@ impl Layer for LayerObj {
 
 impl LayerObj {
     const fn read_color_from_obj_palette(color_idx: usize, obj_palette_ram: &[u8]) -> Color {
-        let low_nibble = obj_palette_ram[color_idx] as u16;
-        let high_nibble = obj_palette_ram[color_idx + 1] as u16;
+        let low_nibble = obj_palette_ram[color_idx * 2] as u16;
+        let high_nibble = obj_palette_ram[color_idx * 2 + 1] as u16;
 
         Color::from_palette_color((high_nibble << 8) | low_nibble)
     }
@@ -62,54 +134,245 @@ impl LayerObj {
         pixel_screen_sprite_origin: Point<u16>,
         transformation_kind: object_attributes::TransformationKind,
         obj_mode: object_attributes::ObjMode,
-    ) -> Point<f64> {
-        if let object_attributes::TransformationKind::RotationScaling {
-            rotation_scaling_parameter,
-        } = transformation_kind
+        scale: i32,
+        subpixel: Point<i32>,
+    ) -> Point<i32> {
+        // We use i32 because we might have negative values when using the pixel
+        // in the carthesian plane having the origin as the center of the sprite.
+        let sprite_size = sprite_size.map(i32::from);
+        let pixel_screen_sprite_origin = pixel_screen_sprite_origin.map(i32::from);
+
+        match transformation_kind {
+            object_attributes::TransformationKind::RotationScaling {
+                rotation_scaling_parameter,
+            } => {
+                // RotScale matrix
+                let rotscale_params =
+                    self.rotation_scaling_params[rotation_scaling_parameter as usize];
+
+                // This is the pixel coordinate in the screen space using the sprite center as origin of the reference system
+                // This is needed because the rotscale is applied taking the center of the sprite as the origin of the rotation
+                // If the sprite is in AffineDouble mode then it has double dimensions and the center is at +sprite_width/+sprite_height insted of
+                // just half ot that.
+                let pixel_screen_sprite_center = pixel_screen_sprite_origin
+                    - match obj_mode {
+                        object_attributes::ObjMode::Affine => sprite_size / 2,
+                        object_attributes::ObjMode::AffineDouble => sprite_size,
+                        _ => unreachable!(),
+                    };
+
+                // Sampling at a higher resolution, the pixel is split in `scale * scale` subpixels.
+                let subpixel_screen_sprite_center = pixel_screen_sprite_center * scale + subpixel;
+
+                // Applying transformation.
+                // The result will be a pixel in the texture space which still has the center of the sprite as the origin of the reference system
+                let subpixel_texture_sprite_center =
+                    subpixel_screen_sprite_center * rotscale_params;
+                let pixel_texture_sprite_center = Point::new(
+                    subpixel_texture_sprite_center.x.div_euclid(scale),
+                    subpixel_texture_sprite_center.y.div_euclid(scale),
+                );
+
+                // Moving back the reference system to the origin of the sprite (top-left corner).
+                // The result can be outside of the sprite, the caller has to check it.
+                pixel_texture_sprite_center + sprite_size / 2
+            }
+            object_attributes::TransformationKind::Flip {
+                horizontal_flip,
+                vertical_flip,
+            } => Point::new(
+                if horizontal_flip {
+                    sprite_size.x - 1 - pixel_screen_sprite_origin.x
+                } else {
+                    pixel_screen_sprite_origin.x
+                },
+                if vertical_flip {
+                    sprite_size.y - 1 - pixel_screen_sprite_origin.y
+                } else {
+                    pixel_screen_sprite_origin.y
+                },
+            ),
+        }
+    }
+
+    /// Index in the OBJ palette of the texel at `pixel_texture_sprite_origin`, `None` if it's
+    /// outside of the sprite or transparent.
+    fn texel(
+        &mut self,
+        obj: &object_attributes::ObjAttributes,
+        sprite_size: Point<u16>,
+        pixel_texture_sprite_origin: Point<i32>,
+        registers: &Registers,
+        memory: &Memory,
+    ) -> Option<u8> {
+        // Sprite size using tiles as dimensions
+        let sprite_size_tile = sprite_size / 8;
+
+        // We check that the pixel is inside the sprite.
+        // Texture coordinates don't wrap: outside of the sprite the pixel is transparent.
+        if pixel_texture_sprite_origin.x < 0
+            || pixel_texture_sprite_origin.y < 0
+            || pixel_texture_sprite_origin.x >= i32::from(sprite_size.x)
+            || pixel_texture_sprite_origin.y >= i32::from(sprite_size.y)
         {
-            // We have to use f64 for translating/rot/scale because we might have negative values when using the pixel
-            // in the carthesian plane having the origin as the center of the sprite.
-            // We could use i16 as well but then we would still need to use f64 to apply the transformation.
-
-            // RotScale matrix
-            let rotscale_params = self.rotation_scaling_params[rotation_scaling_parameter as usize];
-            let sprite_size = sprite_size.map(|el| el as f64);
-
-            // This is the pixel coordinate in the screen space using the sprite center as origin of the reference system
-            // This is needed because the rotscale is applied taking the center of the sprite as the origin of the rotation
-            // If the sprite is in AffineDouble mode then it has double dimensions and the center is at +sprite_width/+sprite_height insted of
-            // just half ot that.
-            let pixel_screen_sprite_center = pixel_screen_sprite_origin.map(|el| el as f64)
-                - match obj_mode {
-                    object_attributes::ObjMode::Affine => sprite_size / 2.0,
-                    object_attributes::ObjMode::AffineDouble => sprite_size,
-                    _ => unreachable!(),
+            return None;
+        }
+
+        let pixel_texture_sprite_origin = pixel_texture_sprite_origin.map(|el| el as u16);
+
+        // Pixel in texture space using tiles as dimensions
+        let pixel_texture_tile = pixel_texture_sprite_origin / 8;
+
+        // Offset of the pixel inside the tile
+        let y_tile_idx = pixel_texture_sprite_origin.y % 8;
+        let x_tile_idx = pixel_texture_sprite_origin.x % 8;
+
+        let obj_character_vram_mapping = registers.get_obj_character_vram_mapping();
+
+        let (tile_number, line_size) = match obj.attribute0.color_mode {
+            object_attributes::ColorMode::Palette8bpp => {
+                // We multiply *2 because in 8bpp tiles indeces are always even
+                let tile_number = obj.attribute2.tile_number
+                    + match obj_character_vram_mapping {
+                        lcd::ObjMappingKind::OneDimensional => {
+                            // In this case memory is seen as a single array.
+                            // tile_number is the offset of the first tile in memory.
+                            // then we access [y][x] by doing y*number_cols + x, as if we were to access an array as a matrix
+                            pixel_texture_tile.y * sprite_size_tile.x * 2 + pixel_texture_tile.x * 2
+                        }
+                        lcd::ObjMappingKind::TwoDimensional => {
+                            // A charblock is 32x32 tiles
+                            pixel_texture_tile.y * 32 + pixel_texture_tile.x * 2
+                        }
+                    };
+
+                // A tile is 8x8 mini-bitmap.
+                // A tile is 64bytes long in 8bpp.
+                (tile_number, 8)
+            }
+            object_attributes::ColorMode::Palette4bpp => {
+                let tile_number = obj.attribute2.tile_number
+                    + match obj_character_vram_mapping {
+                        lcd::ObjMappingKind::OneDimensional => {
+                            // In this case memory is seen as a single array.
+                            // tile_number is the offset of the first tile in memory.
+                            // then we access [y][x] by doing y*number_cols + x, as if we were to access an array as a matrix
+                            pixel_texture_tile.y * sprite_size_tile.x + pixel_texture_tile.x
+                        }
+                        lcd::ObjMappingKind::TwoDimensional => {
+                            // A charblock is 32x32 tiles
+                            pixel_texture_tile.y * 32 + pixel_texture_tile.x
+                        }
+                    };
+
+                // A tile is 32bytes long in 4bpp.
+                (tile_number, 4)
+            }
+        };
+
+        // Tile numbers are in units of 32 bytes whatever the color mode
+        let line = self.tile_lines.get(
+            tile_number as usize * 32 + y_tile_idx as usize * line_size,
+            obj.attribute0.color_mode,
+            memory.video_ram.as_slice(),
+        );
+        let palette_index = line[x_tile_idx as usize];
+
+        // Color 0 is transparent (in 4bpp color 0 of each palette)
+        if palette_index == 0 {
+            return None;
+        }
+
+        let color_offset = match obj.attribute0.color_mode {
+            object_attributes::ColorMode::Palette8bpp => palette_index,
+            object_attributes::ColorMode::Palette4bpp => {
+                (obj.attribute2.palette_number << 4) | palette_index
+            }
+        };
+
+        Some(color_offset)
+    }
+
+    /// The average of `scale * scale` texels evenly spread over the pixel, the first one
+    /// is the one the hardware takes. It's `None` if less than half of them are opaque, so
+    /// the sprite keeps its size.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn supersample(
+        &mut self,
+        obj: &object_attributes::ObjAttributes,
+        sprite_size: Point<u16>,
+        pixel_screen_sprite_origin: Point<u16>,
+        scale: i32,
+        registers: &Registers,
+        memory: &Memory,
+    ) -> Option<Color> {
+        let mut channels = [0_u32; 3];
+        let mut opaque = 0;
+
+        for sub_y in 0..scale {
+            for sub_x in 0..scale {
+                let pixel_texture_sprite_origin = self.get_texture_space_point(
+                    sprite_size,
+                    pixel_screen_sprite_origin,
+                    obj.attribute1.transformation_kind,
+                    obj.attribute0.obj_mode,
+                    scale,
+                    Point::new(sub_x, sub_y),
+                );
+                let Some(color_offset) = self.texel(
+                    obj,
+                    sprite_size,
+                    pixel_texture_sprite_origin,
+                    registers,
+                    memory,
+                ) else {
+                    continue;
                 };
 
-            // Applying transformation.
-            // The result will be a pixel in the texture space which still has the center of the sprite as the origin of the reference system
-            let pixel_texture_sprite_center = pixel_screen_sprite_center * rotscale_params;
+                let color = Self::read_color_from_obj_palette(
+                    color_offset as usize,
+                    memory.obj_palette_ram.as_slice(),
+                );
+                for (channel, value) in
+                    channels
+                        .iter_mut()
+                        .zip([color.red(), color.green(), color.blue()])
+                {
+                    *channel += u32::from(value);
+                }
+                opaque += 1;
+            }
+        }
 
-            // Moving back the reference system to the origin of the sprite (top-left corner).
-            pixel_texture_sprite_center + sprite_size / 2.0
-        } else {
-            // TODO: Implement flip
-            pixel_screen_sprite_origin.map(|el| el as f64)
+        if opaque * 2 < scale * scale {
+            return None;
         }
+
+        let opaque = opaque as u32;
+        let [red, green, blue] = channels.map(|sum| ((sum + opaque / 2) / opaque) as u8);
+        Some(Color::from_rgb(red, green, blue))
     }
 
     #[allow(clippy::too_many_lines)]
-    fn process_sprites_scanline(&mut self, registers: &Registers, memory: &Memory) {
+    fn process_sprites_scanline(
+        &mut self,
+        registers: &Registers,
+        memory: &Memory,
+        affine_resolution: AffineResolution,
+    ) {
         self.sprite_pixels_scanline = [None; LCD_WIDTH];
         let y = registers.vcount;
+        let (mosaic_h, mosaic_v) = registers.get_obj_mosaic_size();
+        let mut cycles_left = if registers.get_hblank_interval_free() {
+            OBJ_CYCLES_PER_LINE_HBLANK_FREE
+        } else {
+            OBJ_CYCLES_PER_LINE
+        };
 
         for obj in self.obj_attributes_arr {
             if matches!(
                 obj.attribute0.obj_mode,
                 object_attributes::ObjMode::Disabled
-            ) || matches!(
-                obj.attribute0.gfx_mode,
-                object_attributes::GfxMode::ObjectWindow
             ) {
                 continue;
             }
@@ -161,9 +424,6 @@ impl LayerObj {
             // We can represent the size of the sprite using a point.
             let sprite_size = Point::new(sprite_width as u16, sprite_height as u16);
 
-            // Sprite size using tiles as dimensions
-            let sprite_size_tile = sprite_size / 8;
-
             let sprite_position = Point::new(
                 obj.attribute1.x_coordinate,
                 obj.attribute0.y_coordinate as u16,
@@ -177,116 +437,124 @@ impl LayerObj {
             // Sprite size in screen space (takes into account double size sprites)
             let sprite_screen_size = sprite_size * if is_affine_double { 2 } else { 1 };
 
-            for idx in 0..sprite_screen_size.x {
-                // This is the pixel coordinate in the screen space using the sprite origin (top-left corner) as origin of the reference system
-                let pixel_screen_sprite_origin =
-                    Point::new(idx, (y + WORLD_HEIGHT - sprite_position.y) % WORLD_HEIGHT);
+            // Line of the sprite in the screen space using the sprite origin (top-left corner) as origin of the reference system
+            let sprite_line = (y + WORLD_HEIGHT - sprite_position.y) % WORLD_HEIGHT;
 
-                // We check that the coordinates in the screen space are inside the sprite
-                // Taking care of the fact that if the sprite in AffineDouble it has double the dimensions
-                if pixel_screen_sprite_origin.x > sprite_screen_size.x
-                    || pixel_screen_sprite_origin.y > sprite_screen_size.y
-                {
-                    continue;
-                }
+            // We check that the line is inside the sprite
+            // Taking care of the fact that if the sprite in AffineDouble it has double the dimensions
+            if sprite_line >= sprite_screen_size.y {
+                continue;
+            }
 
-                // We apply the transformation.
-                // The result is a pixel in the texture space with the origin of the sprite (top-left corner) as the origin of the reference system
-                let pixel_texture_sprite_origin = self.get_texture_space_point(
-                    sprite_size,
-                    pixel_screen_sprite_origin,
-                    obj.attribute1.transformation_kind,
-                    obj.attribute0.obj_mode,
-                );
+            // Sprites are rendered in OAM order, when the cycles run out the remaining ones
+            // are not drawn on this line. Affine sprites cost 2 cycles per pixel plus 10.
+            let cycles = if matches!(obj.attribute0.obj_mode, object_attributes::ObjMode::Normal) {
+                sprite_screen_size.x
+            } else {
+                10 + sprite_screen_size.x * 2
+            };
 
-                // We check that the pixel is inside the sprite
-                if pixel_texture_sprite_origin.x < 0.0
-                    || pixel_texture_sprite_origin.y < 0.0
-                    || pixel_texture_sprite_origin.x >= sprite_size.x as f64
-                    || pixel_texture_sprite_origin.y >= sprite_size.y as f64
-                {
-                    continue;
-                }
+            if cycles > cycles_left {
+                break;
+            }
+            cycles_left -= cycles;
 
-                let pixel_texture_sprite_origin = pixel_texture_sprite_origin.map(|el| el as u16);
-
-                // Pixel in texture space using tiles as dimensions
-                let pixel_texture_tile = pixel_texture_sprite_origin / 8;
-
-                // Offset of the pixel inside the tile
-                let y_tile_idx = pixel_texture_sprite_origin.y % 8;
-                let x_tile_idx = pixel_texture_sprite_origin.x % 8;
-
-                let obj_character_vram_mapping = registers.get_obj_character_vram_mapping();
-
-                let color_offset = match obj.attribute0.color_mode {
-                    object_attributes::ColorMode::Palette8bpp => {
-                        // We multiply *2 because in 8bpp tiles indeces are always even
-                        let tile_number = obj.attribute2.tile_number
-                            + match obj_character_vram_mapping {
-                                lcd::ObjMappingKind::OneDimensional => {
-                                    // In this case memory is seen as a single array.
-                                    // tile_number is the offset of the first tile in memory.
-                                    // then we access [y][x] by doing y*number_cols + x, as if we were to access an array as a matrix
-                                    pixel_texture_tile.y * sprite_size_tile.x * 2
-                                        + pixel_texture_tile.x * 2
-                                }
-                                lcd::ObjMappingKind::TwoDimensional => {
-                                    // A charblock is 32x32 tiles
-                                    pixel_texture_tile.y * 32 + pixel_texture_tile.x * 2
-                                }
-                            };
-
-                        // A tile is 8x8 mini-bitmap.
-                        // A tile is 64bytes long in 8bpp.
-                        let palette_offset =
-                            tile_number as u32 * 32 + y_tile_idx as u32 * 8 + x_tile_idx as u32;
-
-                        // TODO: Move 0x10000 to a variable. It is the offset where OBJ VRAM starts in vram
-                        memory.video_ram[0x10000 + palette_offset as usize]
-                    }
-                    object_attributes::ColorMode::Palette4bpp => {
-                        let tile_number = obj.attribute2.tile_number
-                            + match obj_character_vram_mapping {
-                                lcd::ObjMappingKind::OneDimensional => {
-                                    // In this case memory is seen as a single array.
-                                    // tile_number is the offset of the first tile in memory.
-                                    // then we access [y][x] by doing y*number_cols + x, as if we were to access an array as a matrix
-                                    pixel_texture_tile.y * sprite_size_tile.x + pixel_texture_tile.x
-                                }
-                                lcd::ObjMappingKind::TwoDimensional => {
-                                    // A charblock is 32x32 tiles
-                                    pixel_texture_tile.y * 32 + pixel_texture_tile.x
-                                }
-                            };
-
-                        // A tile is 32bytes long in 4bpp.
-                        let tile_data = tile_number * 32 + y_tile_idx * 4 + x_tile_idx / 2;
-
-                        let palette_offset_low = if tile_data % 2 == 0 {
-                            tile_data.get_bits(0..=3)
-                        } else {
-                            tile_data.get_bits(4..=7)
-                        };
+            if matches!(
+                obj.attribute0.gfx_mode,
+                object_attributes::GfxMode::ObjectWindow
+            ) {
+                continue;
+            }
 
-                        let palette_offset =
-                            (obj.attribute2.palette_number << 4) | (palette_offset_low as u8);
-                        memory.video_ram[0x10000 + palette_offset as usize]
-                    }
-                };
+            // Mosaic blocks are aligned to the screen, not to the sprite.
+            // A block starting before the sprite takes its first line/column.
+            let sprite_line = if obj.attribute0.obj_mosaic {
+                sprite_line - (y % mosaic_v).min(sprite_line)
+            } else {
+                sprite_line
+            };
 
-                let x_screen = sprite_position.x + idx;
+            for idx in 0..sprite_screen_size.x {
+                // The x coordinate is 9 bits, sprites past the right border wrap to the left one.
+                let x_screen = (sprite_position.x + idx) % WORLD_WIDTH;
 
                 if x_screen >= self.sprite_pixels_scanline.len() as u16 {
                     continue;
                 }
 
-                let get_pixel_info_closure = || PixelInfo {
-                    color: Self::read_color_from_obj_palette(
+                let sprite_column = if obj.attribute0.obj_mosaic {
+                    idx - (x_screen % mosaic_h).min(idx)
+                } else {
+                    idx
+                };
+
+                // This is the pixel coordinate in the screen space using the sprite origin (top-left corner) as origin of the reference system
+                // Mosaic is applied here so affine sprites get it before the transformation.
+                let pixel_screen_sprite_origin = Point::new(sprite_column, sprite_line);
+
+                // Affine sprites can be sampled at a higher resolution, mosaic keeps the
+                // blocks of the hardware.
+                let is_affine = matches!(
+                    obj.attribute1.transformation_kind,
+                    object_attributes::TransformationKind::RotationScaling { .. }
+                );
+                let scale = if is_affine && !obj.attribute0.obj_mosaic {
+                    affine_resolution.scale()
+                } else {
+                    1
+                };
+
+                let (color, palette_index) = if scale == 1 {
+                    // We apply the transformation.
+                    // The result is a pixel in the texture space with the origin of the sprite (top-left corner) as the origin of the reference system
+                    let pixel_texture_sprite_origin = self.get_texture_space_point(
+                        sprite_size,
+                        pixel_screen_sprite_origin,
+                        obj.attribute1.transformation_kind,
+                        obj.attribute0.obj_mode,
+                        1,
+                        Point::new(0, 0),
+                    );
+
+                    let Some(color_offset) = self.texel(
+                        &obj,
+                        sprite_size,
+                        pixel_texture_sprite_origin,
+                        registers,
+                        memory,
+                    ) else {
+                        continue;
+                    };
+
+                    let color = Self::read_color_from_obj_palette(
                         color_offset as usize,
                         memory.obj_palette_ram.as_slice(),
-                    ),
+                    );
+                    (color, Some(color_offset))
+                } else {
+                    let Some(color) = self.supersample(
+                        &obj,
+                        sprite_size,
+                        pixel_screen_sprite_origin,
+                        scale,
+                        registers,
+                        memory,
+                    ) else {
+                        continue;
+                    };
+
+                    // The average is not a color of the palette.
+                    (color, None)
+                };
+
+                let get_pixel_info_closure = || PixelInfo {
+                    color,
                     priority: obj.attribute2.priority,
+                    semi_transparent: matches!(
+                        obj.attribute0.gfx_mode,
+                        object_attributes::GfxMode::AlphaBlending
+                    ),
+                    palette_index,
                 };
 
                 self.sprite_pixels_scanline[x_screen as usize] =
@@ -304,10 +572,331 @@ impl LayerObj {
         }
     }
 
-    pub fn handle_enter_vdraw(&mut self, memory: &Memory, registers: &Registers) {
-        (self.obj_attributes_arr, self.rotation_scaling_params) =
-            object_attributes::get_attributes(memory.obj_attributes.as_slice());
+    /// `dirty` are the regions written since the last call, the attributes and the decoded tiles
+    /// are kept when they didn't change.
+    pub fn handle_enter_vdraw(
+        &mut self,
+        memory: &Memory,
+        registers: &Registers,
+        dirty: &DirtyRegions,
+        affine_resolution: AffineResolution,
+    ) {
+        if dirty.obj_attributes {
+            (self.obj_attributes_arr, self.rotation_scaling_params) =
+                object_attributes::get_attributes(memory.obj_attributes.as_slice());
+        }
+
+        self.tile_lines.invalidate(dirty);
+
+        self.process_sprites_scanline(registers, memory, affine_resolution);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::decimal_bitwise_operands)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Sprites with a fixed setup: colors `n` of the OBJ palette have red `n % 16`,
+    /// tile 1 (4bpp) has the color `x + 1` in each pixel, tile 2 (4bpp) the color `y + 1`.
+    /// All the sprites are disabled.
+    fn fixture() -> (Memory, Registers) {
+        let mut memory = Memory::default();
+        let registers = Registers {
+            // 1D mapping
+            dispcnt: 1 << 6,
+            ..Default::default()
+        };
+
+        for color in 0..256 {
+            let value = Color::from_rgb((color % 16) as u8, 0, 0).0.to_le_bytes();
+            memory.obj_palette_ram[color as usize * 2..][..2].copy_from_slice(&value);
+        }
+
+        for y in 0..8 {
+            for x in 0..4 {
+                memory.video_ram[0x10000 + 32 + y * 4 + x] =
+                    ((x as u8 * 2 + 2) << 4) | (x as u8 * 2 + 1);
+                memory.video_ram[0x10000 + 64 + y * 4 + x] = ((y as u8 + 1) << 4) | (y as u8 + 1);
+            }
+        }
+
+        for obj in 0..128 {
+            set_obj(&mut memory, obj, [0x0200, 0, 0]);
+        }
+
+        (memory, registers)
+    }
+
+    fn set_obj(memory: &mut Memory, obj: usize, attributes: [u16; 3]) {
+        for (idx, attribute) in attributes.into_iter().enumerate() {
+            memory.obj_attributes[obj * 8 + idx * 2..][..2]
+                .copy_from_slice(&attribute.to_le_bytes());
+        }
+    }
+
+    fn set_rotation_scaling(memory: &mut Memory, group: usize, params: [i16; 4]) {
+        for (idx, param) in params.into_iter().enumerate() {
+            memory.obj_attributes[group * 32 + idx * 8 + 6..][..2]
+                .copy_from_slice(&param.to_le_bytes());
+        }
+    }
+
+    /// Renders the top-left corner of the screen, `.` is a transparent pixel
+    /// and other pixels are the hex digit of their red channel.
+    fn render(
+        memory: &Memory,
+        registers: &mut Registers,
+        width: usize,
+        height: u16,
+    ) -> Vec<String> {
+        let mut layer = LayerObj::default();
+
+        (0..height)
+            .map(|y| {
+                registers.vcount = y;
+                layer.handle_enter_vdraw(
+                    memory,
+                    registers,
+                    &DirtyRegions::all(),
+                    AffineResolution::Native,
+                );
+
+                layer.sprite_pixels_scanline[..width]
+                    .iter()
+                    .map(|pixel| {
+                        pixel.map_or('.', |info| {
+                            char::from_digit(info.color.red().into(), 16).unwrap()
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normal_sprites() {
+        let (mut memory, mut registers) = fixture();
+
+        // 8x8 at (1, 0), tile 1
+        set_obj(&mut memory, 0, [0, 1, 1]);
+        assert_eq!(render(&memory, &mut registers, 10, 1), vec![".12345678."]);
+
+        // Horizontal flip, and the left border wraps (x = -4)
+        set_obj(&mut memory, 0, [0, (1 << 12) | 508, 1]);
+        assert_eq!(render(&memory, &mut registers, 6, 1), vec!["4321.."]);
+
+        // Vertical flip, 2 lines above the screen (y = -2)
+        set_obj(&mut memory, 0, [254, 1 << 13, 2]);
+        assert_eq!(
+            render(&memory, &mut registers, 1, 7),
+            vec!["6", "5", "4", "3", "2", "1", "."]
+        );
+
+        // Palette 1 (color 0 is still transparent)
+        memory.video_ram[0x10000 + 32] = 0x10;
+        set_obj(&mut memory, 0, [0, 0, (1 << 12) | 1]);
+        let value = Color::from_rgb(9, 0, 0).0.to_le_bytes();
+        memory.obj_palette_ram[17 * 2..][..2].copy_from_slice(&value);
+        assert_eq!(render(&memory, &mut registers, 3, 1), vec![".93"]);
+    }
+
+    #[test]
+    fn affine_sprites_edges() {
+        let (mut memory, mut registers) = fixture();
+
+        // Identity
+        set_rotation_scaling(&mut memory, 0, [0x100, 0, 0, 0x100]);
+        set_obj(&mut memory, 0, [0x0100, 0, 1]);
+        assert_eq!(render(&memory, &mut registers, 8, 1), vec!["12345678"]);
+
+        // Mirrored: rounding moves the sprite by a pixel and the first column is outside
+        set_rotation_scaling(&mut memory, 0, [-0x100, 0, 0, 0x100]);
+        assert_eq!(render(&memory, &mut registers, 8, 1), vec![".8765432"]);
+
+        // Scaled down by 2: the texture doesn't wrap, outside of it the sprite is transparent
+        set_rotation_scaling(&mut memory, 0, [0x200, 0, 0, 0x100]);
+        assert_eq!(render(&memory, &mut registers, 8, 1), vec!["..1357.."]);
+
+        // Scaled up by 2: only the center fits in the sprite
+        set_rotation_scaling(&mut memory, 0, [0x80, 0, 0, 0x80]);
+        assert_eq!(render(&memory, &mut registers, 8, 1), vec!["33445566"]);
+
+        // Same with double size, the whole texture fits
+        set_obj(&mut memory, 0, [0x0300, 0, 1]);
+        assert_eq!(
+            render(&memory, &mut registers, 17, 1),
+            vec!["1122334455667788."]
+        );
+
+        // Double size with the identity: the sprite is in the center of the 16x16 area
+        set_rotation_scaling(&mut memory, 0, [0x100, 0, 0, 0x100]);
+        assert_eq!(
+            render(&memory, &mut registers, 16, 16)[3..13],
+            vec![
+                "................",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "....12345678....",
+                "................",
+            ]
+        );
+    }
+
+    #[test]
+    fn affine_resolution() {
+        let (mut memory, mut registers) = fixture();
+        let line = |memory: &Memory, registers: &mut Registers, resolution| {
+            let mut layer = LayerObj::default();
+            registers.vcount = 0;
+            layer.handle_enter_vdraw(memory, registers, &DirtyRegions::all(), resolution);
+
+            layer.sprite_pixels_scanline[..8]
+                .iter()
+                .map(|pixel| {
+                    pixel.map_or('.', |info| {
+                        char::from_digit(info.color.red().into(), 16).unwrap()
+                    })
+                })
+                .collect::<String>()
+        };
+
+        // With the identity every sample of a pixel falls on the same texel.
+        set_rotation_scaling(&mut memory, 0, [0x100, 0, 0, 0x100]);
+        set_obj(&mut memory, 0, [0x0100, 0, 1]);
+        for resolution in AffineResolution::ALL {
+            assert_eq!(line(&memory, &mut registers, resolution), "12345678");
+        }
+
+        // Scaled down by 2: each pixel is the average of two texels.
+        set_rotation_scaling(&mut memory, 0, [0x200, 0, 0, 0x100]);
+        assert_eq!(
+            line(&memory, &mut registers, AffineResolution::Native),
+            "..1357.."
+        );
+        assert_eq!(
+            line(&memory, &mut registers, AffineResolution::Double),
+            "..2468.."
+        );
+
+        // Mosaic keeps one sample for each pixel.
+        set_obj(&mut memory, 0, [0x1100, 0, 1]);
+        assert_eq!(
+            line(&memory, &mut registers, AffineResolution::Quadruple),
+            "..1357.."
+        );
+    }
+
+    /// Draws a line the way the LCD does, with the regions written since the last one.
+    fn draw_line(layer: &mut LayerObj, memory: &mut Memory, registers: &Registers) {
+        let dirty = memory.take_dirty();
+        layer.handle_enter_vdraw(memory, registers, &dirty, AffineResolution::Native);
+    }
+
+    #[test]
+    fn dirty_tracking() {
+        let (mut memory, registers) = fixture();
+        let mut layer = LayerObj::default();
+
+        set_obj(&mut memory, 0, [0, 0, 1]);
+        draw_line(&mut layer, &mut memory, &registers);
+        assert_eq!(layer.sprite_pixels_scanline[0].unwrap().color.red(), 1);
+
+        // Not written through the tracking: the decoded line and the attributes are kept
+        memory.video_ram[0x10000 + 32] = 0x22;
+        memory.obj_attributes[2] = 2;
+        draw_line(&mut layer, &mut memory, &registers);
+        assert_eq!(layer.sprite_pixels_scanline[0].unwrap().color.red(), 1);
+        assert!(layer.sprite_pixels_scanline[8].is_none());
+
+        memory.write_video_ram(0x10000 + 32, 0x33);
+        draw_line(&mut layer, &mut memory, &registers);
+        assert_eq!(layer.sprite_pixels_scanline[0].unwrap().color.red(), 3);
+        assert!(layer.sprite_pixels_scanline[8].is_none());
+
+        // x = 2
+        memory.write_obj_attributes(2, 2);
+        draw_line(&mut layer, &mut memory, &registers);
+        assert!(layer.sprite_pixels_scanline[0].is_none());
+        assert_eq!(layer.sprite_pixels_scanline[2].unwrap().color.red(), 3);
+
+        // Only the written blocks are dirty
+        memory.write_video_ram(0x10000 + 64, 0);
+        let dirty = memory.take_dirty();
+        assert_eq!(
+            dirty.dirty_video_ram_blocks(0..0x18000).collect::<Vec<_>>(),
+            vec![0x10000 + 64]
+        );
+        assert!(!dirty.obj_attributes);
+        assert!(!memory.take_dirty().is_video_ram_dirty(0x10000 + 64));
+    }
+
+    #[test]
+    fn mosaic() {
+        let (mut memory, mut registers) = fixture();
+
+        // Blocks of 3x2 pixels
+        registers.mosaic = (1 << 12) | (2 << 8);
+
+        // Blocks are aligned to the screen, not to the sprite (x = 1)
+        set_obj(&mut memory, 0, [1 << 12, 1, 1]);
+        assert_eq!(render(&memory, &mut registers, 10, 1), vec![".11333666."]);
+
+        // Without the mosaic bit the sprite is unchanged
+        set_obj(&mut memory, 0, [0, 1, 1]);
+        assert_eq!(render(&memory, &mut registers, 10, 1), vec![".12345678."]);
+
+        // Vertically (y = 1)
+        set_obj(&mut memory, 0, [(1 << 12) | 1, 0, 2]);
+        assert_eq!(
+            render(&memory, &mut registers, 1, 10),
+            vec![".", "1", "2", "2", "4", "4", "6", "6", "8", "."]
+        );
+
+        // Affine sprites get the mosaic before the transformation
+        registers.mosaic = 1 << 8;
+        set_rotation_scaling(&mut memory, 0, [-0x100, 0, 0, 0x100]);
+        set_obj(&mut memory, 0, [(1 << 12) | 0x0100, 0, 1]);
+        assert_eq!(render(&memory, &mut registers, 8, 1), vec!["..775533"]);
+    }
+
+    #[test]
+    fn cycles_per_line() {
+        let (mut memory, mut registers) = fixture();
+
+        // 19 64x32 sprites at x = 200 need 1216 cycles, the last one and the
+        // following ones are not drawn
+        for obj in 0..19 {
+            set_obj(&mut memory, obj, [1 << 14, (3 << 14) | 200, 1]);
+        }
+        set_obj(&mut memory, 19, [0, 0, 1]);
+        assert_eq!(render(&memory, &mut registers, 2, 1), vec![".."]);
+
+        // 18 take 1152 cycles, an 8x8 sprite fits in the 58 left
+        set_obj(&mut memory, 18, [0x0200, 0, 0]);
+        assert_eq!(render(&memory, &mut registers, 2, 1), vec!["12"]);
+
+        // Affine sprites take 10 cycles plus 2 per pixel, 26 for an 8x8 one
+        // and 42 for an 8x8 double size one
+        set_rotation_scaling(&mut memory, 0, [0x100, 0, 0, 0x100]);
+        set_obj(&mut memory, 18, [0x0100, 200, 1]);
+        set_obj(&mut memory, 19, [0x0300, 0, 1]);
+        assert_eq!(render(&memory, &mut registers, 2, 1), vec![".."]);
+
+        // With the HBlank interval free only 954 cycles are available
+        for obj in 15..19 {
+            set_obj(&mut memory, obj, [0x0200, 0, 0]);
+        }
+        set_obj(&mut memory, 19, [0, 0, 1]);
+        assert_eq!(render(&memory, &mut registers, 2, 1), vec!["12"]);
 
-        self.process_sprites_scanline(registers, memory);
+        registers.dispcnt |= 1 << 5;
+        assert_eq!(render(&memory, &mut registers, 2, 1), vec![".."]);
     }
 }
@@ -17,15 +17,105 @@ pub struct Memory {
     /// From 0x07000000 to 0x070003FF (1kbyte)
     #[serde_as(as = "Box<[_; 1024]>")]
     pub obj_attributes: Box<[u8; 0x400]>,
+
+    /// Regions written since the renderer last looked at them.
+    /// Everything is dirty after loading a state.
+    #[serde(skip, default = "DirtyRegions::all")]
+    dirty: DirtyRegions,
+}
+
+/// Size of the VRAM blocks tracked by [`DirtyRegions`], a 4bpp tile.
+pub const VRAM_BLOCK_SIZE: usize = 32;
+
+const VRAM_BLOCKS: usize = 0x18000 / VRAM_BLOCK_SIZE;
+
+/// Video memory regions written by the CPU or the DMA.
+#[derive(Clone)]
+pub struct DirtyRegions {
+    /// One bit for each block of [`VRAM_BLOCK_SIZE`] bytes.
+    video_ram: [u64; VRAM_BLOCKS / 64],
+    pub obj_attributes: bool,
+    pub bg_palette: bool,
+    pub obj_palette: bool,
+}
+
+impl DirtyRegions {
+    pub const fn clean() -> Self {
+        Self {
+            video_ram: [0; VRAM_BLOCKS / 64],
+            obj_attributes: false,
+            bg_palette: false,
+            obj_palette: false,
+        }
+    }
+
+    pub const fn all() -> Self {
+        Self {
+            video_ram: [u64::MAX; VRAM_BLOCKS / 64],
+            obj_attributes: true,
+            bg_palette: true,
+            obj_palette: true,
+        }
+    }
+
+    const fn mark_video_ram(&mut self, index: usize) {
+        let block = index / VRAM_BLOCK_SIZE;
+        self.video_ram[block / 64] |= 1 << (block % 64);
+    }
+
+    /// Whether the block of [`VRAM_BLOCK_SIZE`] bytes containing `index` has been written.
+    pub const fn is_video_ram_dirty(&self, index: usize) -> bool {
+        let block = index / VRAM_BLOCK_SIZE;
+        self.video_ram[block / 64] & (1 << (block % 64)) != 0
+    }
+
+    /// Indices of the first byte of the dirty VRAM blocks in `range`.
+    pub fn dirty_video_ram_blocks(
+        &self,
+        range: std::ops::Range<usize>,
+    ) -> impl Iterator<Item = usize> + '_ {
+        range
+            .step_by(VRAM_BLOCK_SIZE)
+            .filter(|index| self.is_video_ram_dirty(*index))
+    }
+}
+
+impl Memory {
+    pub fn write_bg_palette_ram(&mut self, index: usize, value: u8) {
+        self.bg_palette_ram[index] = value;
+        self.dirty.bg_palette = true;
+    }
+
+    pub fn write_obj_palette_ram(&mut self, index: usize, value: u8) {
+        self.obj_palette_ram[index] = value;
+        self.dirty.obj_palette = true;
+    }
+
+    pub fn write_video_ram(&mut self, index: usize, value: u8) {
+        self.video_ram[index] = value;
+        self.dirty.mark_video_ram(index);
+    }
+
+    pub fn write_obj_attributes(&mut self, index: usize, value: u8) {
+        self.obj_attributes[index] = value;
+        self.dirty.obj_attributes = true;
+    }
+
+    /// Gives back the regions written since the last call and marks everything as clean.
+    pub const fn take_dirty(&mut self) -> DirtyRegions {
+        std::mem::replace(&mut self.dirty, DirtyRegions::clean())
+    }
 }
 
 impl Default for Memory {
+    #[allow(clippy::large_stack_arrays)]
     fn default() -> Self {
         Self {
             bg_palette_ram: Box::new([0; 0x200]),
             obj_palette_ram: Box::new([8; 0x200]),
             video_ram: Box::new([0; 0x18000]),
             obj_attributes: Box::new([0; 0x400]),
+            dirty: DirtyRegions::all(),
         }
     }
 }
@@ -115,7 +115,7 @@ pub struct ObjAttribute0 {
     pub y_coordinate: u8,
     pub obj_mode: ObjMode,
     pub gfx_mode: GfxMode,
-    obj_mosaic: bool,
+    pub obj_mosaic: bool,
     pub color_mode: ColorMode,
     pub obj_shape: ObjShape,
 }
@@ -124,12 +124,12 @@ impl TryFrom<u16> for ObjAttribute0 {
     type Error = &'static str;
     fn try_from(value: u16) -> Result<Self, Self::Error> {
         Ok(Self {
-            y_coordinate: value.get_bits(0..=7) as u8,
+            y_coordinate: value.get_bits_u8(0..=7),
             obj_mode: value.get_bits(8..=9).into(),
-            gfx_mode: value.get_bits(10..=11).try_into().unwrap(),
+            gfx_mode: value.get_bits(10..=11).try_into()?,
             obj_mosaic: value.get_bit(12),
             color_mode: value.get_bit(13).into(),
-            obj_shape: value.get_bits(14..=15).try_into().unwrap(),
+            obj_shape: value.get_bits(14..=15).try_into()?,
         })
     }
 }
@@ -169,7 +169,7 @@ impl ObjAttribute1 {
             x_coordinate: value.get_bits(0..=8),
             transformation_kind: match obj_mode {
                 ObjMode::Affine | ObjMode::AffineDouble => TransformationKind::RotationScaling {
-                    rotation_scaling_parameter: value.get_bits(9..=13) as u8,
+                    rotation_scaling_parameter: value.get_bits_u8(9..=13),
                 },
                 ObjMode::Normal | ObjMode::Disabled => TransformationKind::Flip {
                     horizontal_flip: value.get_bit(12),
@@ -204,8 +204,8 @@ impl From<u16> for ObjAttribute2 {
     fn from(value: u16) -> Self {
         Self {
             tile_number: value.get_bits(0..=9),
-            priority: value.get_bits(10..=11) as u8,
-            palette_number: value.get_bits(12..=15) as u8,
+            priority: value.get_bits_u8(10..=11),
+            palette_number: value.get_bits_u8(12..=15),
         }
     }
 }
@@ -246,21 +246,16 @@ impl RotationScaling {
     /// and
     /// T = [ x ]
     ///     [ y ]
-    /// pa, pb, pc, pd are first converted to floating number from the fixed point representation
+    /// pa, pb, pc, pd are 8.8 fixed point values, the result is rounded down as the hardware does.
     #[allow(clippy::many_single_char_names)]
-    pub fn apply(self, x: f64, y: f64) -> (f64, f64) {
-        let a = Self::get_float_from_fixed_point(self.pa);
-        let b = Self::get_float_from_fixed_point(self.pb);
-        let c = Self::get_float_from_fixed_point(self.pc);
-        let d = Self::get_float_from_fixed_point(self.pd);
-
-        (x.mul_add(a, y * b), x.mul_add(c, y * d))
-    }
-
-    fn get_float_from_fixed_point(value: u16) -> f64 {
-        // We interpret the value as signed and we divide by 2^8 since the rotation/scaling parameter
-        // is represented as an 8.8 fixed point value.
-        (value as i16) as f64 / 256.0
+    pub fn apply(self, x: i32, y: i32) -> (i32, i32) {
+        // We interpret the values as signed
+        let a = i32::from(self.pa as i16);
+        let b = i32::from(self.pb as i16);
+        let c = i32::from(self.pc as i16);
+        let d = i32::from(self.pd as i16);
+
+        ((a * x + b * y) >> 8, (c * x + d * y) >> 8)
     }
 }
 
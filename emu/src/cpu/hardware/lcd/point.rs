@@ -48,7 +48,7 @@ where
     }
 }
 
-impl ops::Mul<RotationScaling> for Point<f64> {
+impl ops::Mul<RotationScaling> for Point<i32> {
     type Output = Self;
     fn mul(self, rhs: RotationScaling) -> Self::Output {
         let r = rhs.apply(self.x, self.y);
@@ -0,0 +1,187 @@
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+
+use crate::render::gif::AnimationEncoder;
+
+use super::clip_buffer::{FRAMES_PER_SECOND, KEEP_EVERY};
+use super::{Color, LCD_HEIGHT, LCD_WIDTH};
+
+/// Frames waiting for the encoder, about a second. When the encoder can't keep up the
+/// next frames are dropped, the emulation never waits for it.
+const QUEUE_FRAMES: usize = 30;
+
+/// A frame for the encoder.
+struct QueuedFrame {
+    /// Frames dropped since the previous one was queued, the encoder shows the
+    /// previous one longer in their place so the timing is kept.
+    dropped_before: u32,
+    pixels: Vec<u16>,
+}
+
+/// A recording saved, with the frames that were dropped because the encoder was late.
+#[derive(Debug)]
+pub struct Recording {
+    pub gif: Vec<u8>,
+    pub frames: u32,
+    pub dropped: u32,
+}
+
+/// Records the frames from start to stop as an animated GIF. The frames are only copied
+/// by the emulation, the encoding is done by a thread of its own.
+pub struct Recorder {
+    sender: SyncSender<QueuedFrame>,
+    encoder: JoinHandle<Vec<u8>>,
+    /// Frames since the last one kept.
+    skipped: u8,
+    frames: u32,
+    dropped: u32,
+    /// Dropped since the last frame queued.
+    pending_drops: u32,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_queue(QUEUE_FRAMES)
+    }
+
+    fn with_queue(queue_frames: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<QueuedFrame>(queue_frames);
+
+        let encoder = thread::spawn(move || {
+            #[allow(clippy::cast_possible_truncation)]
+            let mut encoder = AnimationEncoder::new(
+                LCD_WIDTH as u16,
+                LCD_HEIGHT as u16,
+                100.0 * f64::from(KEEP_EVERY) / FRAMES_PER_SECOND,
+            );
+
+            // Ends when the recorder is finished and drops the sender.
+            for frame in receiver {
+                for _ in 0..frame.dropped_before {
+                    encoder.repeat_last();
+                }
+                encoder.push(&frame.pixels);
+            }
+
+            encoder.finish()
+        });
+
+        Self {
+            sender,
+            encoder,
+            skipped: 0,
+            frames: 0,
+            dropped: 0,
+            pending_drops: 0,
+        }
+    }
+
+    /// Frames recorded, dropped ones included.
+    #[must_use]
+    pub const fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Frames lost because the encoder couldn't keep up.
+    #[must_use]
+    pub const fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    pub(super) fn push(&mut self, buffer: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) {
+        self.skipped += 1;
+        if self.skipped < KEEP_EVERY {
+            return;
+        }
+        self.skipped = 0;
+
+        let frame = QueuedFrame {
+            dropped_before: self.pending_drops,
+            pixels: buffer.iter().flatten().map(|color| color.0).collect(),
+        };
+        self.frames += 1;
+
+        match self.sender.try_send(frame) {
+            Ok(()) => self.pending_drops = 0,
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                self.dropped += 1;
+                self.pending_drops += 1;
+            }
+        }
+    }
+
+    /// Waits for the encoder to write the frames queued.
+    ///
+    /// # Errors
+    /// It returns an error if the encoder thread panicked.
+    pub fn finish(self) -> Result<Recording, String> {
+        let Self {
+            sender,
+            encoder,
+            frames,
+            dropped,
+            ..
+        } = self;
+        drop(sender);
+
+        let gif = encoder
+            .join()
+            .map_err(|_| "the encoder of the recording stopped".to_string())?;
+
+        Ok(Recording {
+            gif,
+            frames,
+            dropped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    #[allow(clippy::large_stack_arrays)]
+    fn records_a_frame_every_two() {
+        let mut recorder = Recorder::new();
+        let mut buffer = [[Color::default(); LCD_WIDTH]; LCD_HEIGHT];
+
+        for frame in 0..10 {
+            buffer[0][0] = Color(frame);
+            recorder.push(&buffer);
+        }
+
+        let recording = recorder.finish().unwrap();
+        assert_eq!(recording.frames, 5);
+        assert_eq!(recording.dropped, 0);
+        assert!(recording.gif.starts_with(b"GIF89a"));
+        assert_eq!(recording.gif.last(), Some(&0x3B));
+    }
+
+    #[test]
+    #[allow(clippy::large_stack_arrays)]
+    fn full_queue_drops_frames() {
+        // A queue of 0 only hands a frame over if the encoder is waiting for it, the
+        // first one is pushed before the thread is even started.
+        let mut recorder = Recorder::with_queue(0);
+        let buffer = [[Color::default(); LCD_WIDTH]; LCD_HEIGHT];
+
+        for _ in 0..2000 {
+            recorder.push(&buffer);
+        }
+
+        assert_eq!(recorder.frames(), 1000);
+        assert!(recorder.dropped() > 0);
+
+        let recording = recorder.finish().unwrap();
+        assert_eq!(recording.frames, 1000);
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::bitwise::Bits;
 
+use super::compositor::BlendMode;
 use super::ObjMappingKind;
 
 #[derive(Default, Serialize, Deserialize)]
@@ -119,13 +120,63 @@ impl Registers {
 
     /// Info about vram fields used to render display.
     pub(super) fn get_bg_mode(&self) -> u8 {
-        self.dispcnt.get_bits(0..=2).try_into().unwrap()
+        self.dispcnt.get_bits_u8(0..=2)
+    }
+
+    /// OAM can be accessed during `HBlank`, leaving less time to render the sprites.
+    pub(super) fn get_hblank_interval_free(&self) -> bool {
+        self.dispcnt.get_bit(5)
     }
 
     pub(super) fn get_obj_character_vram_mapping(&self) -> ObjMappingKind {
         self.dispcnt.get_bit(6).into()
     }
 
+    /// Width and height of the OBJ mosaic blocks.
+    pub(super) fn get_obj_mosaic_size(&self) -> (u16, u16) {
+        (
+            self.mosaic.get_bits(8..=11) + 1,
+            self.mosaic.get_bits(12..=15) + 1,
+        )
+    }
+
+    pub(super) fn get_bg_priority(&self, bg: u8) -> u8 {
+        let control = match bg {
+            0 => self.bg0cnt,
+            1 => self.bg1cnt,
+            2 => self.bg2cnt,
+            _ => self.bg3cnt,
+        };
+
+        control.get_bits_u8(0..=1)
+    }
+
+    pub(super) fn get_blend_mode(&self) -> BlendMode {
+        self.bldcnt.get_bits(6..=7).into()
+    }
+
+    /// `layer` is the bit of the layer in `BLDCNT` (BG0-3, OBJ, backdrop).
+    pub(super) fn is_blend_first_target(&self, layer: u8) -> bool {
+        self.bldcnt.get_bit(layer)
+    }
+
+    pub(super) fn is_blend_second_target(&self, layer: u8) -> bool {
+        self.bldcnt.get_bit(layer + 8)
+    }
+
+    /// EVA and EVB, values above 16 are treated as 16.
+    pub(super) fn get_blend_alpha_coefficients(&self) -> (u16, u16) {
+        (
+            self.bldalpha.get_bits(0..=4).min(16),
+            self.bldalpha.get_bits(8..=12).min(16),
+        )
+    }
+
+    /// EVY, values above 16 are treated as 16.
+    pub(super) fn get_blend_brightness(&self) -> u16 {
+        self.bldy.get_bits(0..=4).min(16)
+    }
+
     pub(super) fn get_vcount_setting(&self) -> u8 {
         self.dispstat.get_byte(1)
     }
@@ -1,6 +1,10 @@
+#[allow(clippy::cast_possible_truncation)]
+pub mod debug;
 pub mod dma;
+pub mod gpio;
 pub mod internal_memory;
 pub mod interrupt_control;
+pub mod joybus;
 pub mod keypad;
 
 #[allow(clippy::cast_possible_truncation)]
@@ -1,5 +1,25 @@
 use serde::{Deserialize, Serialize};
 
+use crate::bitwise::Bits;
+
+use super::joybus::JoybusCommand;
+
+/// Device type answered to `Reset` and `Status` commands (GBA).
+const JOYBUS_DEVICE_TYPE: u16 = 0x0004;
+
+/// `JOYCNT` bits: device reset, receive complete and send complete are
+/// cleared by writing 1, bit 6 enables the serial IRQ.
+const JOYCNT_RESET: u8 = 0;
+const JOYCNT_RECEIVE: u8 = 1;
+const JOYCNT_SEND: u8 = 2;
+const JOYCNT_IRQ: u8 = 6;
+
+/// `JOYSTAT` bits: `JOY_RECV` full and `JOY_TRANS` pending, bits 4-5 are
+/// general purpose flags set by the GBA.
+const JOYSTAT_RECEIVE: u8 = 1;
+const JOYSTAT_SEND: u8 = 3;
+const JOYSTAT_GENERAL_PURPOSE: u16 = 0b11_0000;
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Serial {
     // This is SIODATA32 when single-player mode or two different 16bits registers in multiplayer mode
@@ -17,3 +37,90 @@ pub struct Serial {
     pub sio_joy_bus_transmit_data: u32,
     pub sio_joy_bus_receive_status: u16,
 }
+
+impl Serial {
+    /// JOY Bus mode is selected with bits 14-15 of `RCNT`.
+    #[must_use]
+    pub fn is_joy_bus_mode(&self) -> bool {
+        self.sio_mode_select.get_bits(14..=15) == 0b11
+    }
+
+    pub fn write_joy_bus_control(&mut self, byte: u8, value: u8) {
+        if byte != 0 {
+            return;
+        }
+
+        let mut control = self.sio_joy_bus_control;
+        for bit in [JOYCNT_RESET, JOYCNT_RECEIVE, JOYCNT_SEND] {
+            if value.get_bit(bit) {
+                control.set_bit(bit, false);
+            }
+        }
+        control.set_bit(JOYCNT_IRQ, value.get_bit(JOYCNT_IRQ));
+
+        self.sio_joy_bus_control = control;
+    }
+
+    pub fn write_joy_bus_status(&mut self, byte: u8, value: u8) {
+        if byte == 0 {
+            let status = self.sio_joy_bus_receive_status;
+            self.sio_joy_bus_receive_status =
+                (status & !JOYSTAT_GENERAL_PURPOSE) | (u16::from(value) & JOYSTAT_GENERAL_PURPOSE);
+        }
+    }
+
+    pub fn write_joy_bus_transmit(&mut self, byte: u8, value: u8) {
+        self.sio_joy_bus_transmit_data.set_byte(byte, value);
+
+        // The data is ready once its upper halfword is written.
+        if byte >= 2 {
+            self.sio_joy_bus_receive_status.set_bit(JOYSTAT_SEND, true);
+        }
+    }
+
+    /// The CPU read the upper halfword of `JOY_RECV`, the master can write again.
+    pub fn acknowledge_joy_bus_receive(&mut self) {
+        self.sio_joy_bus_receive_status
+            .set_bit(JOYSTAT_RECEIVE, false);
+    }
+
+    /// Runs a command of the JOY Bus master. It returns the response bytes (empty
+    /// if the GBA is not listening) and whether the serial IRQ has to be requested.
+    pub fn joy_bus_command(&mut self, command: JoybusCommand) -> (Vec<u8>, bool) {
+        if !self.is_joy_bus_mode() {
+            return (Vec::new(), false);
+        }
+
+        let mut response = Vec::with_capacity(5);
+        let flag = match command {
+            JoybusCommand::Reset | JoybusCommand::Status => {
+                if command == JoybusCommand::Reset {
+                    self.sio_joy_bus_receive_status &= JOYSTAT_GENERAL_PURPOSE;
+                }
+
+                response.extend_from_slice(&JOYBUS_DEVICE_TYPE.to_be_bytes());
+                (command == JoybusCommand::Reset).then_some(JOYCNT_RESET)
+            }
+            JoybusCommand::Read => {
+                response.extend_from_slice(&self.sio_joy_bus_transmit_data.to_le_bytes());
+                self.sio_joy_bus_receive_status.set_bit(JOYSTAT_SEND, false);
+                Some(JOYCNT_SEND)
+            }
+            JoybusCommand::Write(data) => {
+                self.sio_joy_bus_receive_data = data;
+                self.sio_joy_bus_receive_status
+                    .set_bit(JOYSTAT_RECEIVE, true);
+                Some(JOYCNT_RECEIVE)
+            }
+        };
+
+        response.push(self.sio_joy_bus_receive_status.get_byte(0));
+
+        let request_irq = flag.is_some_and(|flag| {
+            self.sio_joy_bus_control.set_bit(flag, true);
+            self.sio_joy_bus_control.get_bit(JOYCNT_IRQ)
+        });
+
+        (response, request_irq)
+    }
+}
@@ -1,5 +1,109 @@
+use logger::log;
 use serde::{Deserialize, Serialize};
 
+use crate::bitwise::Bits;
+
+use self::direct_sound::DirectSound;
+
+mod direct_sound;
+
+/// Cycles per second of the CPU (16.78 MHz).
+pub const CPU_FREQUENCY: u64 = 16_777_216;
+
+/// Output sample rate until the host sets the one of its device.
+pub const DEFAULT_SAMPLE_RATE: u32 = 32_768;
+
+/// Samples (stereo pairs) after which [`crate::events::CoreEvent::AudioBufferFull`] is emitted.
+pub const AUDIO_BUFFER_SIZE: usize = 1024;
+
+/// Samples kept when the host doesn't take them, the oldest ones are dropped.
+const MAX_BUFFERED_SAMPLES: usize = AUDIO_BUFFER_SIZE * 16;
+
+/// Rough cutoff of the GBA speaker, it doesn't reproduce much above it.
+const LOW_PASS_CUTOFF: f32 = 6_000.0;
+
+/// Master volume in percent.
+pub const MAX_VOLUME: u8 = 100;
+
+/// How the Direct Sound samples are resampled to the output rate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    /// Each sample is held until the next one, as the hardware does.
+    #[default]
+    Nearest,
+    Linear,
+    /// Windowed sinc, the smoothest one.
+    Sinc,
+}
+
+impl Interpolation {
+    pub const ALL: [Self; 3] = [Self::Nearest, Self::Linear, Self::Sinc];
+}
+
+impl std::fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nearest => write!(f, "Nearest"),
+            Self::Linear => write!(f, "Linear"),
+            Self::Sinc => write!(f, "Sinc"),
+        }
+    }
+}
+
+/// Times the FIFO of a Direct Sound channel went wrong, a sign of timers or DMAs set up
+/// badly by the game or of timing bugs of the emulator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FifoStats {
+    /// The timer asked for a sample with the FIFO empty.
+    pub underruns: u32,
+    /// Samples were written with the FIFO full and lost.
+    pub overruns: u32,
+}
+
+/// Output settings chosen by the host, they are not part of the emulated state.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSettings {
+    pub sample_rate: u32,
+    pub interpolation: Interpolation,
+    /// Approximates the GBA speaker, muffling the aliasing of low rate samples.
+    pub low_pass_filter: bool,
+    /// Master volume from 0 to [`MAX_VOLUME`].
+    pub volume: u8,
+    /// Silences the output keeping the volume.
+    pub muted: bool,
+    /// Plays the left side on the right and the other way around.
+    pub swap_channels: bool,
+    /// Plays the average of the two sides on both, for games panning hard on one side.
+    pub mono: bool,
+}
+
+impl AudioSettings {
+    /// Factor applied to the mixed output.
+    fn gain(self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            f32::from(self.volume.min(MAX_VOLUME)) / f32::from(MAX_VOLUME)
+        }
+    }
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            interpolation: Interpolation::default(),
+            low_pass_filter: false,
+            volume: MAX_VOLUME,
+            muted: false,
+            swap_channels: false,
+            mono: false,
+        }
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Sound {
     pub channel1_sweep: u16,
@@ -17,6 +121,302 @@ pub struct Sound {
     pub control_sound_on_off: u16,
     pub sound_pwm_control: u16,
     pub channel3_wave_pattern_ram: [u8; 16],
-    pub channel_a_fifo: u32,
-    pub channel_b_fifo: u32,
+
+    /// Channels A and B.
+    channels: [DirectSound; 2],
+    /// Cycles since power on, the Direct Sound samples are timed with it.
+    cycle: u64,
+    /// Increased by the sample rate every cycle, a sample is output every `CPU_FREQUENCY`.
+    sample_clock: u64,
+    /// Last output of the low-pass filter, left and right.
+    low_pass: [f32; 2],
+
+    #[serde(skip)]
+    pub settings: AudioSettings,
+    #[serde(skip)]
+    samples: Vec<[i16; 2]>,
+}
+
+impl Sound {
+    /// `channel` is 0 for FIFO A and 1 for FIFO B.
+    pub fn write_fifo(&mut self, channel: usize, value: u8) {
+        let direct_sound = &mut self.channels[channel];
+        let overflowing = direct_sound.is_overflowing();
+
+        direct_sound.push(value.cast_signed());
+        if !overflowing && direct_sound.is_overflowing() {
+            self.log_fifo_error(channel, "overrun");
+        }
+    }
+
+    /// Underruns and overruns of FIFO A and B since power on.
+    pub fn fifo_stats(&self) -> [FifoStats; 2] {
+        self.channels.each_ref().map(DirectSound::stats)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn log_fifo_error(&self, channel: usize, error: &str) {
+        let name = if channel == 0 { 'A' } else { 'B' };
+        let seconds = self.cycle as f64 / CPU_FREQUENCY as f64;
+
+        log(format!(
+            "FIFO {name} {error} at {seconds:.3}s (cycle {})",
+            self.cycle
+        ));
+    }
+
+    /// `SOUNDCNT_H`, bits 11 and 15 reset the FIFOs and always read as 0.
+    pub fn write_mixing_dma_control(&mut self, byte: u8, value: u8) {
+        self.control_mixing_dma_control.set_byte(byte, value);
+
+        if byte == 1 {
+            for (channel, bit) in [(0, 11), (1, 15)] {
+                if self.control_mixing_dma_control.get_bit(bit) {
+                    self.channels[channel].reset();
+                    self.control_mixing_dma_control.set_bit_off(bit);
+                }
+            }
+        }
+    }
+
+    const fn is_enabled(&self) -> bool {
+        self.control_sound_on_off & (1 << 7) != 0
+    }
+
+    /// Timer of the Direct Sound channel (0 or 1).
+    fn channel_timer(&self, channel: usize) -> usize {
+        let bit = if channel == 0 { 10 } else { 14 };
+        self.control_mixing_dma_control.get_bit(bit).into()
+    }
+
+    /// Timer 0 or 1 overflowed, the channels using it play their next sample.
+    /// It returns the channels (A, B) whose FIFO needs to be refilled by the DMA.
+    pub fn timer_overflow(&mut self, timer: usize) -> [bool; 2] {
+        let mut refill = [false; 2];
+
+        if !self.is_enabled() {
+            return refill;
+        }
+
+        for (channel, refill) in refill.iter_mut().enumerate() {
+            if self.channel_timer(channel) == timer {
+                let streaming = self.channels[channel].is_streaming();
+                *refill = self.channels[channel].next_sample(self.cycle);
+
+                if streaming && !self.channels[channel].is_streaming() {
+                    self.log_fifo_error(channel, "underrun");
+                }
+            }
+        }
+
+        refill
+    }
+
+    /// Left and right output from -1 to 1, before the filter.
+    fn mix(&self) -> [f32; 2] {
+        let mut output = [0.0, 0.0];
+
+        if !self.is_enabled() {
+            return output;
+        }
+
+        let control = self.control_mixing_dma_control;
+        for (channel, direct_sound) in self.channels.iter().enumerate() {
+            let shift = 4 * channel as u8;
+            // 100% or 50%, both channels at 100% fill the output range
+            let volume = if control.get_bit(2 + channel as u8) {
+                0.5
+            } else {
+                0.25
+            };
+            let sample = direct_sound.output(self.cycle, self.settings.interpolation) * volume;
+
+            if control.get_bit(9 + shift) {
+                output[0] += sample;
+            }
+            if control.get_bit(8 + shift) {
+                output[1] += sample;
+            }
+        }
+
+        if self.settings.mono {
+            output = [f32::midpoint(output[0], output[1]); 2];
+        }
+        if self.settings.swap_channels {
+            output.swap(0, 1);
+        }
+
+        output
+    }
+
+    /// Steps one cycle, it returns `true` when a buffer of [`AUDIO_BUFFER_SIZE`] samples is ready.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn step(&mut self) -> bool {
+        self.cycle += 1;
+        self.sample_clock += u64::from(self.settings.sample_rate);
+
+        if self.sample_clock < CPU_FREQUENCY {
+            return false;
+        }
+        self.sample_clock -= CPU_FREQUENCY;
+
+        let mut output = self.mix();
+
+        if self.settings.low_pass_filter {
+            let alpha = 1.0
+                - (-2.0 * std::f32::consts::PI * LOW_PASS_CUTOFF
+                    / self.settings.sample_rate as f32)
+                    .exp();
+
+            for (output, last) in output.iter_mut().zip(self.low_pass.iter_mut()) {
+                *last += alpha * (*output - *last);
+                *output = *last;
+            }
+        }
+
+        let gain = self.settings.gain();
+
+        if self.samples.len() == MAX_BUFFERED_SAMPLES {
+            self.samples.drain(..AUDIO_BUFFER_SIZE);
+        }
+        self.samples
+            .push(output.map(|value| (value * gain * f32::from(i16::MAX)) as i16));
+
+        self.samples.len().is_multiple_of(AUDIO_BUFFER_SIZE)
+    }
+
+    /// Takes the samples made since the last call, left and right interleaved.
+    pub fn take_samples(&mut self) -> Vec<[i16; 2]> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Puts back samples taken before, ahead of the ones made since.
+    pub(crate) fn push_samples(&mut self, mut samples: Vec<[i16; 2]>) {
+        samples.append(&mut self.samples);
+        self.samples = samples;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Sound enabled, channel A at 100% on both sides with timer 0, channel B muted with timer 1.
+    fn enabled_sound() -> Sound {
+        let mut sound = Sound {
+            control_sound_on_off: 1 << 7,
+            ..Default::default()
+        };
+        sound.write_mixing_dma_control(0, 0b0100);
+        sound.write_mixing_dma_control(1, 0b0100_0011);
+
+        sound
+    }
+
+    #[test]
+    fn fifo_reset() {
+        let mut sound = enabled_sound();
+        sound.write_fifo(0, 0x40);
+        sound.write_mixing_dma_control(1, 0b0100_1011);
+
+        assert_eq!(sound.control_mixing_dma_control, 0b0100_0011_0000_0100);
+        assert_eq!(sound.timer_overflow(0), [true, false]);
+        assert_eq!(sound.mix(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn mixing() {
+        let mut sound = enabled_sound();
+        sound.write_fifo(0, 0x40);
+        sound.write_fifo(1, 0x40);
+
+        // Channel B uses timer 1 and isn't played yet
+        assert_eq!(sound.timer_overflow(0), [true, false]);
+        assert_eq!(sound.mix(), [0.25, 0.25]);
+
+        // Channel B at 50% on the left with timer 1
+        sound.write_mixing_dma_control(1, 0b0110_0011);
+        assert_eq!(sound.timer_overflow(1), [false, true]);
+        assert_eq!(sound.mix(), [0.375, 0.25]);
+
+        // Master disabled
+        sound.control_sound_on_off = 0;
+        assert_eq!(sound.mix(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn swap_and_mono() {
+        let mut sound = enabled_sound();
+        // Channel A on the left only
+        sound.write_mixing_dma_control(1, 0b0100_0010);
+        sound.write_fifo(0, 0x40);
+        sound.timer_overflow(0);
+        assert_eq!(sound.mix(), [0.25, 0.0]);
+
+        sound.settings.swap_channels = true;
+        assert_eq!(sound.mix(), [0.0, 0.25]);
+
+        sound.settings.mono = true;
+        assert_eq!(sound.mix(), [0.125, 0.125]);
+    }
+
+    #[test]
+    fn fifo_stats() {
+        let mut sound = enabled_sound();
+        sound.write_fifo(0, 0x40);
+        sound.timer_overflow(0);
+        sound.timer_overflow(0);
+        for _ in 0..40 {
+            sound.write_fifo(1, 0x40);
+        }
+
+        assert_eq!(
+            sound.fifo_stats(),
+            [
+                FifoStats {
+                    underruns: 1,
+                    overruns: 0
+                },
+                FifoStats {
+                    underruns: 0,
+                    overruns: 1
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn output_rate() {
+        let mut sound = enabled_sound();
+        sound.settings.sample_rate = 32_768;
+
+        let buffer_cycles = CPU_FREQUENCY as usize / 32_768 * AUDIO_BUFFER_SIZE;
+        let full_buffers = (0..buffer_cycles * 2).filter(|_| sound.step()).count();
+
+        assert_eq!(full_buffers, 2);
+        assert_eq!(sound.take_samples().len(), AUDIO_BUFFER_SIZE * 2);
+        assert!(sound.take_samples().is_empty());
+    }
+
+    #[test]
+    fn volume() {
+        let mut sound = enabled_sound();
+        sound.write_fifo(0, 0x40);
+        sound.timer_overflow(0);
+
+        let next_sample = |sound: &mut Sound| {
+            while !sound.step() {}
+            *sound.take_samples().last().unwrap()
+        };
+
+        assert_eq!(next_sample(&mut sound), [8191, 8191]);
+
+        sound.settings.volume = 50;
+        assert_eq!(next_sample(&mut sound), [4095, 4095]);
+
+        sound.settings.muted = true;
+        assert_eq!(next_sample(&mut sound), [0, 0]);
+    }
 }
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::{FifoStats, Interpolation};
+
+/// The FIFO holds 32 samples (8 words).
+const FIFO_SIZE: usize = 32;
+
+/// Below this number of samples the FIFO asks the DMA to be refilled.
+const FIFO_REFILL_THRESHOLD: usize = 16;
+
+/// Samples kept for the interpolation, the sinc one uses half of them on each side.
+const HISTORY_SIZE: usize = 8;
+
+/// Missing samples for which the last one is held, a DMA a bit late doesn't make a gap.
+/// After them the output fades to silence.
+const HELD_MISSING_SAMPLES: u8 = 1;
+
+/// One of the two 8 bit PCM channels (A and B) fed by the DMA.
+#[derive(Default, Serialize, Deserialize)]
+pub struct DirectSound {
+    fifo: VecDeque<i8>,
+    /// Last samples played, the most recent last.
+    history: [i8; HISTORY_SIZE],
+    /// Cycle of the last sample.
+    last_sample_cycle: u64,
+    /// Cycles between the last two samples, the sample rate set by the timer.
+    sample_period: u64,
+    /// Samples played since the FIFO ran dry, up to the first full one.
+    missing_samples: u8,
+    /// The FIFO is playing the samples of the DMA, it stops on a reset or an underrun.
+    streaming: bool,
+    /// The last sample pushed was lost, the FIFO was full.
+    overflowing: bool,
+
+    #[serde(skip)]
+    stats: FifoStats,
+}
+
+impl DirectSound {
+    /// Samples written when the FIFO is full are lost, a run of them is an overrun.
+    pub fn push(&mut self, sample: i8) {
+        if self.fifo.len() < FIFO_SIZE {
+            self.fifo.push_back(sample);
+            self.overflowing = false;
+        } else if !self.overflowing {
+            self.overflowing = true;
+            self.stats.overruns += 1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.fifo.clear();
+        self.streaming = false;
+        self.overflowing = false;
+    }
+
+    pub const fn stats(&self) -> FifoStats {
+        self.stats
+    }
+
+    pub const fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    pub const fn is_overflowing(&self) -> bool {
+        self.overflowing
+    }
+
+    /// Plays the next sample of the FIFO, the timer of the channel overflowed.
+    /// It returns `true` if the FIFO needs to be refilled.
+    /// When the FIFO is empty the last sample is held for a bit and then fades out, so a
+    /// DMA that stops or falls behind doesn't leave a constant offset on the output. An
+    /// empty FIFO while streaming is an underrun.
+    pub fn next_sample(&mut self, cycle: u64) -> bool {
+        let last = self.history[HISTORY_SIZE - 1];
+        let sample = if let Some(sample) = self.fifo.pop_front() {
+            self.missing_samples = 0;
+            self.streaming = true;
+            sample
+        } else {
+            if self.streaming {
+                self.streaming = false;
+                self.stats.underruns += 1;
+            }
+            self.missing_samples = self.missing_samples.saturating_add(1);
+
+            if self.missing_samples <= HELD_MISSING_SAMPLES {
+                last
+            } else {
+                last / 2
+            }
+        };
+
+        self.history.rotate_left(1);
+        self.history[HISTORY_SIZE - 1] = sample;
+        self.sample_period = cycle - self.last_sample_cycle;
+        self.last_sample_cycle = cycle;
+
+        self.fifo.len() <= FIFO_REFILL_THRESHOLD
+    }
+
+    /// Output of the channel at `cycle`, from -1 to 1.
+    /// The hardware holds each sample until the next one (`Nearest`), the other
+    /// interpolations smooth the steps at the cost of a few samples of latency.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn output(&self, cycle: u64, interpolation: Interpolation) -> f32 {
+        let sample = |idx: usize| f32::from(self.history[idx]) / 128.0;
+
+        // Position between the last two samples, if the samples keep coming at the same rate.
+        let position = if self.sample_period == 0 {
+            1.0
+        } else {
+            ((cycle - self.last_sample_cycle) as f32 / self.sample_period as f32).min(1.0)
+        };
+
+        match interpolation {
+            Interpolation::Nearest => sample(HISTORY_SIZE - 1),
+            Interpolation::Linear => {
+                let previous = sample(HISTORY_SIZE - 2);
+                let last = sample(HISTORY_SIZE - 1);
+
+                (last - previous).mul_add(position, previous)
+            }
+            Interpolation::Sinc => {
+                // Lanczos window, centered between the two samples in the middle of the history.
+                let half = (HISTORY_SIZE / 2) as f32;
+                let center = half - 1.0 + position;
+
+                (0..HISTORY_SIZE)
+                    .map(|idx| {
+                        let distance = idx as f32 - center;
+                        sample(idx) * sinc(distance) * sinc(distance / half)
+                    })
+                    .sum()
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < f32::EPSILON {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn fifo() {
+        let mut channel = DirectSound::default();
+
+        for sample in 0..40 {
+            channel.push(sample);
+        }
+
+        // Samples 32 to 39 are lost
+        for cycle in 1..=15 {
+            assert!(!channel.next_sample(cycle));
+        }
+        assert!(channel.next_sample(16));
+        assert_eq!(channel.output(16, Interpolation::Nearest), 15.0 / 128.0);
+
+        channel.reset();
+        assert!(channel.next_sample(17));
+        assert_eq!(channel.output(17, Interpolation::Nearest), 15.0 / 128.0);
+    }
+
+    #[test]
+    fn underrun() {
+        let mut channel = DirectSound::default();
+
+        // Empty before the first sample, it's not an underrun.
+        channel.next_sample(1);
+        channel.push(64);
+        channel.next_sample(2);
+        assert!(channel.is_streaming());
+
+        // The last sample is held once, then it fades out.
+        for (cycle, expected) in [(3, 64_i8), (4, 32), (5, 16), (6, 8)] {
+            channel.next_sample(cycle);
+            assert_eq!(
+                channel.output(cycle, Interpolation::Nearest),
+                f32::from(expected) / 128.0
+            );
+        }
+        assert!(!channel.is_streaming());
+
+        // The stream restarts with the next sample.
+        channel.push(10);
+        channel.next_sample(7);
+        assert_eq!(channel.output(7, Interpolation::Nearest), 10.0 / 128.0);
+        channel.next_sample(8);
+
+        assert_eq!(
+            channel.stats(),
+            FifoStats {
+                underruns: 2,
+                overruns: 0
+            }
+        );
+    }
+
+    #[test]
+    fn overrun() {
+        let mut channel = DirectSound::default();
+
+        // Two runs of lost samples.
+        for sample in 0..40 {
+            channel.push(sample);
+        }
+        assert!(channel.is_overflowing());
+        channel.next_sample(1);
+        channel.push(0);
+        assert!(!channel.is_overflowing());
+        channel.push(0);
+
+        assert_eq!(channel.stats().overruns, 2);
+
+        channel.reset();
+        assert!(!channel.is_overflowing());
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut channel = DirectSound::default();
+
+        for sample in [0, 0, 0, 0, 64, 64, 64, 64] {
+            channel.push(sample);
+        }
+
+        for cycle in 1..=5 {
+            channel.next_sample(cycle * 100);
+        }
+
+        // Halfway between 0 and 64
+        assert_eq!(channel.output(550, Interpolation::Nearest), 0.5);
+        assert_eq!(channel.output(550, Interpolation::Linear), 0.25);
+
+        // The sinc is delayed by 3 samples, it's still before the step.
+        let before = channel.output(500, Interpolation::Sinc);
+        assert!(before.abs() < 0.05, "{before}");
+
+        for cycle in 6..=8 {
+            channel.next_sample(cycle * 100);
+        }
+
+        let after = channel.output(900, Interpolation::Sinc);
+        assert!((after - 0.5).abs() < 0.05, "{after}");
+    }
+}
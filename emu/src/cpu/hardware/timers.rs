@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::bitwise::Bits;
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Timers {
     /// Timer 0 Counter/Reload
@@ -18,4 +20,161 @@ pub struct Timers {
     pub tm3cnt_l: u16,
     /// Timer 3 Control
     pub tm3cnt_h: u16,
+
+    /// Values loaded in the counters when they start and when they overflow.
+    reloads: [u16; 4],
+    /// Cycles since the last increment of the counters.
+    prescaler_cycles: [u16; 4],
+}
+
+impl Timers {
+    /// `TMxCNT_L` reads the counter, `TMxCNT_H` the control.
+    const fn registers_mut(&mut self, index: usize) -> (&mut u16, &mut u16) {
+        match index {
+            0 => (&mut self.tm0cnt_l, &mut self.tm0cnt_h),
+            1 => (&mut self.tm1cnt_l, &mut self.tm1cnt_h),
+            2 => (&mut self.tm2cnt_l, &mut self.tm2cnt_h),
+            _ => (&mut self.tm3cnt_l, &mut self.tm3cnt_h),
+        }
+    }
+
+    const fn control(&self, index: usize) -> u16 {
+        match index {
+            0 => self.tm0cnt_h,
+            1 => self.tm1cnt_h,
+            2 => self.tm2cnt_h,
+            _ => self.tm3cnt_h,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self, index: usize) -> bool {
+        self.control(index) & (1 << 7) != 0
+    }
+
+    #[must_use]
+    pub const fn is_irq_enabled(&self, index: usize) -> bool {
+        self.control(index) & (1 << 6) != 0
+    }
+
+    /// Count-up timers are incremented by the overflow of the previous one.
+    /// Timer 0 has no previous timer, the bit is ignored.
+    const fn is_cascade(&self, index: usize) -> bool {
+        index != 0 && self.control(index) & (1 << 2) != 0
+    }
+
+    /// Cycles for each increment: 1, 64, 256 or 1024.
+    const fn prescaler(&self, index: usize) -> u16 {
+        match self.control(index) & 0b11 {
+            0 => 1,
+            1 => 64,
+            2 => 256,
+            _ => 1024,
+        }
+    }
+
+    /// Writes the reload value, the counter is loaded with it on the next start or overflow.
+    /// While the timer is stopped the counter is updated too.
+    pub fn write_reload(&mut self, index: usize, byte: u8, value: u8) {
+        self.reloads[index].set_byte(byte, value);
+
+        if !self.is_enabled(index) {
+            self.registers_mut(index).0.set_byte(byte, value);
+        }
+    }
+
+    pub fn write_control(&mut self, index: usize, byte: u8, value: u8) {
+        let was_enabled = self.is_enabled(index);
+        let reload = self.reloads[index];
+
+        let (counter, control) = self.registers_mut(index);
+        control.set_byte(byte, value);
+
+        // The counter restarts from the reload value when the timer is started.
+        if !was_enabled && control.get_bit(7) {
+            *counter = reload;
+            self.prescaler_cycles[index] = 0;
+        }
+    }
+
+    /// Steps one cycle, it returns the timers that overflowed (bit 0 for timer 0 and so on).
+    pub fn step(&mut self) -> u8 {
+        let mut overflows = 0;
+
+        for index in 0..4 {
+            if !self.is_enabled(index) {
+                continue;
+            }
+
+            let tick = if self.is_cascade(index) {
+                overflows.get_bit(index as u8 - 1)
+            } else {
+                self.prescaler_cycles[index] += 1;
+                if self.prescaler_cycles[index] == self.prescaler(index) {
+                    self.prescaler_cycles[index] = 0;
+                    true
+                } else {
+                    false
+                }
+            };
+
+            if !tick {
+                continue;
+            }
+
+            let reload = self.reloads[index];
+            let counter = self.registers_mut(index).0;
+            if *counter == u16::MAX {
+                *counter = reload;
+                overflows.set_bit_on(index as u8);
+            } else {
+                *counter += 1;
+            }
+        }
+
+        overflows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn overflow_and_reload() {
+        let mut timers = Timers::default();
+        timers.write_reload(0, 0, 0xFE);
+        timers.write_reload(0, 1, 0xFF);
+        // Enabled, prescaler 1
+        timers.write_control(0, 0, 0b1000_0000);
+
+        assert_eq!(timers.tm0cnt_l, 0xFFFE);
+        assert_eq!(timers.step(), 0);
+        assert_eq!(timers.tm0cnt_l, 0xFFFF);
+        assert_eq!(timers.step(), 0b1);
+        assert_eq!(timers.tm0cnt_l, 0xFFFE);
+
+        // Writing the reload of a running timer doesn't change the counter
+        timers.write_reload(0, 0, 0);
+        assert_eq!(timers.tm0cnt_l, 0xFFFE);
+    }
+
+    #[test]
+    fn prescaler_and_cascade() {
+        let mut timers = Timers::default();
+        timers.write_reload(0, 1, 0xFF);
+        timers.write_reload(1, 0, 0xFF);
+        timers.write_reload(1, 1, 0xFF);
+        // Timer 0 with prescaler 64, timer 1 counting up
+        timers.write_control(0, 0, 0b1000_0001);
+        timers.write_control(1, 0, 0b1000_0100);
+
+        let overflows = (0..64 * 256).map(|_| timers.step()).collect::<Vec<_>>();
+
+        assert_eq!(overflows.iter().filter(|o| **o != 0).count(), 1);
+        assert_eq!(overflows.last(), Some(&0b11));
+        assert_eq!(timers.tm0cnt_l, 0xFF00);
+        assert_eq!(timers.tm1cnt_l, 0xFFFF);
+    }
 }
@@ -5,8 +5,26 @@ mod arm;
 #[allow(clippy::large_stack_frames)]
 #[allow(clippy::module_name_repetitions)]
 pub mod arm7tdmi;
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_sign_loss)]
+pub(crate) mod bios_hle;
+pub mod call_stack;
 mod condition;
-mod cpu_modes;
+pub(crate) mod cpu_modes;
+
+pub(crate) mod decode_cache;
+
+pub mod dispatch;
+
+#[cfg(test)]
+mod decoder_coverage;
+
+pub mod encoding;
+
+#[cfg(all(test, feature = "disassembler"))]
+mod disassembly_snapshots;
 
 #[allow(clippy::cast_possible_truncation)]
 mod flags;
@@ -15,5 +33,16 @@ mod flags;
 pub mod hardware;
 mod psr;
 mod register_bank;
-mod registers;
+pub(crate) mod registers;
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) mod test_bus;
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) mod test_dsl;
 mod thumb;
+
+pub(crate) use arm::instructions::ARM_KINDS;
+pub(crate) use thumb::instruction::THUMB_KINDS;
@@ -2,58 +2,49 @@ use serde::{Deserialize, Serialize};
 
 use crate::bitwise::Bits;
 use crate::cpu::arm::alu_instruction::ArithmeticOpResult;
-use crate::cpu::{condition::Condition, cpu_modes::Mode};
+use crate::cpu::cpu_modes::Mode;
+
+/// Bits of the condition flags and of the control bits.
+const SIGN_FLAG: u8 = 31;
+const ZERO_FLAG: u8 = 30;
+const CARRY_FLAG: u8 = 29;
+const OVERFLOW_FLAG: u8 = 28;
+const STICKY_OVERFLOW: u8 = 27;
+const IRQ_DISABLE: u8 = 7;
+const FIQ_DISABLE: u8 = 6;
+const STATE_BIT: u8 = 5;
+
+/// M4-M0, the mode bits.
+const MODE_MASK: u32 = 0b1_1111;
 
 /// Program Status Register.
-#[derive(Default, Clone, Copy, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Psr(u32);
 
 impl Psr {
-    pub(crate) fn can_execute(self, cond: Condition) -> bool {
-        use Condition::{AL, CC, CS, EQ, GE, GT, HI, LE, LS, LT, MI, NE, NV, PL, VC, VS};
-        match cond {
-            EQ => self.zero_flag(),                         // Equal (Z=1)
-            NE => !self.zero_flag(),                        // Not equal (Z=0)
-            CS => self.carry_flag(),                        // Unsigned higher or same (C=1)
-            CC => !self.carry_flag(),                       // Unsigned lower (C=0)
-            MI => self.sign_flag(),                         // Negative (N=1)
-            PL => !self.sign_flag(),                        // Positive or zero (N=0)
-            VS => self.overflow_flag(),                     // Overflow (V=1)
-            VC => !self.overflow_flag(),                    // No overflow (V=0)
-            HI => self.carry_flag() && !self.zero_flag(),   // Unsigned higher (C=1 and Z=0)
-            LS => !self.carry_flag() || self.zero_flag(),   // Unsigned lower or same (C=0 and Z=1)
-            GE => self.sign_flag() == self.overflow_flag(), // Greater or equal (N=V)
-            LT => self.sign_flag() != self.overflow_flag(), // Less than (N<>V)
-            GT => !self.zero_flag() && (self.sign_flag() == self.overflow_flag()), // Greater than (Z=0 and N=V)
-            LE => self.zero_flag() || (self.sign_flag() != self.overflow_flag()), // Less or equal (Z=1 or N<>V)
-            AL => true,  // Always (the "AL" suffix can be omitted)
-            NV => false, // Never (ARMv1, v2 only) (Reserved ARMv3 and up)
-        }
-    }
-
     /// N => Bit 31, (0=Not Signed, 1=Signed)
     pub fn sign_flag(self) -> bool {
-        self.0.get_bit(31)
+        self.0.get_bit(SIGN_FLAG)
     }
 
     /// Z => Bit 30, (0=Not Zero, 1=Zero)
     pub fn zero_flag(self) -> bool {
-        self.0.get_bit(30)
+        self.0.get_bit(ZERO_FLAG)
     }
 
     /// C => Bit 29, (0=Borrow/No Carry, 1=Carry/No Borrow)
     pub fn carry_flag(self) -> bool {
-        self.0.get_bit(29)
+        self.0.get_bit(CARRY_FLAG)
     }
 
     /// V => Bit 28, (0=No Overflow, 1=Overflow)
     pub fn overflow_flag(self) -> bool {
-        self.0.get_bit(28)
+        self.0.get_bit(OVERFLOW_FLAG)
     }
 
     /// Q => Bit 27, (1=Sticky Overflow, `ARMv5TE` and up only)
     pub fn sticky_overflow(self) -> bool {
-        self.0.get_bit(27)
+        self.0.get_bit(STICKY_OVERFLOW)
     }
 
     /// Reserved => Bits 26-8, (For future use) - Do not change manually!
@@ -66,34 +57,39 @@ impl Psr {
 
     /// I => Bit 7, (0=Enable, 1=Disable)
     pub fn irq_disable(self) -> bool {
-        self.0.get_bit(7)
+        self.0.get_bit(IRQ_DISABLE)
     }
 
     /// F => Bit 6, (0=Enable, 1=Disable)
     pub fn fiq_disable(self) -> bool {
-        self.0.get_bit(6)
+        self.0.get_bit(FIQ_DISABLE)
     }
 
     /// T => Bit 5, (0=ARM, 1=THUMB) - Do not change manually!
     pub fn state_bit(self) -> bool {
-        self.0.get_bit(5)
+        self.0.get_bit(STATE_BIT)
     }
 
     /// M4-M0 => Bits 4-0
     pub fn mode(self) -> Mode {
-        Mode::try_from(self.0 & 0b11111).unwrap()
+        Mode::try_from(self.mode_raw()).unwrap()
+    }
+
+    /// M4-M0 => Bits 4-0, also when they are not a valid mode.
+    pub const fn mode_raw(self) -> u32 {
+        self.0 & MODE_MASK
     }
 
     pub fn set_sign_flag(&mut self, value: bool) {
-        self.0.set_bit(31, value);
+        self.0.set_bit(SIGN_FLAG, value);
     }
 
     pub fn set_zero_flag(&mut self, value: bool) {
-        self.0.set_bit(30, value);
+        self.0.set_bit(ZERO_FLAG, value);
     }
 
     pub fn set_carry_flag(&mut self, value: bool) {
-        self.0.set_bit(29, value);
+        self.0.set_bit(CARRY_FLAG, value);
     }
 
     pub fn set_flags(&mut self, op_result: &ArithmeticOpResult) {
@@ -104,51 +100,62 @@ impl Psr {
     }
 
     pub fn set_overflow_flag(&mut self, value: bool) {
-        self.0.set_bit(28, value);
+        self.0.set_bit(OVERFLOW_FLAG, value);
     }
 
-    /// Used by QADD, QSUB, QDADD, QDSUB, SMLAxy, and SMLAWy only.
+    /// Copies the N, Z, C and V flags of `other`, eg. the value written by MSR.
+    pub fn set_flags_from(&mut self, other: Self) {
+        self.set_sign_flag(other.sign_flag());
+        self.set_zero_flag(other.zero_flag());
+        self.set_carry_flag(other.carry_flag());
+        self.set_overflow_flag(other.overflow_flag());
+    }
+
+    /// Copies the I, F and T control bits of `other`, the mode bits are left alone.
+    pub fn set_control_from(&mut self, other: Self) {
+        self.set_irq_disable(other.irq_disable());
+        self.set_fiq_disable(other.fiq_disable());
+        self.set_state_bit(other.state_bit());
+    }
+
+    /// Used by QADD, QSUB, QDADD, QDSUB, `SMLAxy`, and `SMLAWy` only.
     /// The Q-flag can be tested/reset by MSR/MRS opcodes only.
     /// These opcodes set the Q-flag in case of overflows, but leave it unchanged otherwise.
     #[cfg(test)] // TODO: remove cfg when this API will be used at least one in prod code.
     pub fn set_sticky_overflow(&mut self, value: bool) {
         // TODO (value is true): Should we check the opcode is one of these (QADD, QSUB, QDADD, QDSUB, SMLAxy, and SMLAWy)?
         // TODO (value is false): Should we check the opcode is one of these (MSR/MRS)?
-        self.0.set_bit(27, value);
+        self.0.set_bit(STICKY_OVERFLOW, value);
     }
 
-    /// These bits [7-0] below may change when an exception occurs.
-    /// In privileged modes (non-user modes) they may be also changed manually.
+    // These bits [7-0] below may change when an exception occurs.
+    // In privileged modes (non-user modes) they may be also changed manually.
 
     /// The interrupt bit I is used to disable/enable IRQ interrupts respectively (1 means disabled and 0 means enabled).
     pub fn set_irq_disable(&mut self, value: bool) {
         // TODO: Should we check we are in privileged modes or it occurred an exeption?
-        self.0.set_bit(7, value);
+        self.0.set_bit(IRQ_DISABLE, value);
     }
 
     /// The interrupt bit F is used to disable/enable FIQ interrupts respectively (1 means disabled and 0 means enabled).
     pub fn set_fiq_disable(&mut self, value: bool) {
         // TODO: Should we check we are in privileged modes or it occurred an exeption?
-        self.0.set_bit(6, value);
+        self.0.set_bit(FIQ_DISABLE, value);
     }
 
     /// The T Bit is used to set the current state of the CPU on ARM/THUMB mode (1 means ARM and 0 means THUMB).
     pub fn set_state_bit(&mut self, value: bool) {
-        self.0.set_bit(5, value);
+        self.0.set_bit(STATE_BIT, value);
     }
 
     pub fn set_mode_raw(&mut self, m: u32) {
-        self.0 &= 0b1111_1111_1111_1111_1111_1111_1110_0000;
-
-        let mode_raw = m & 0b0001_1111;
-
-        self.0 |= mode_raw;
+        self.0.set_bits(0..=4, m);
     }
 
     /// The Mode Bits M4-M0 contain the current operating mode.
-    pub fn set_mode(&mut self, m: &Mode) {
+    pub const fn set_mode(&mut self, m: &Mode) {
         // Setting mode bits to 0
-        self.0 &= 0b1111_1111_1111_1111_1111_1111_1110_0000;
+        self.0 &= !MODE_MASK;
 
         // Setting mode bits according to the chosen mode
         match m {
@@ -181,6 +188,12 @@ impl From<Mode> for Psr {
     }
 }
 
+impl From<u32> for Psr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
 impl From<Psr> for u32 {
     fn from(p: Psr) -> Self {
         p.0
@@ -188,6 +201,7 @@ impl From<Psr> for u32 {
 }
 
 /// Represents the CPU state (ARM/THUMB).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CpuState {
     /// Which operates with 16-bit, halfword-aligned THUMB instructions.
     /// In this state, the PC uses bit 1 to select between alternate halfwords.
@@ -281,6 +295,21 @@ mod tests {
         assert!(cpsr.state_bit());
     }
 
+    #[test]
+    fn check_set_from() {
+        let value = Psr::from(0b1010_0000_0000_0000_0000_0000_1010_0000);
+
+        let mut cpsr = Psr::from(Mode::Irq);
+        cpsr.set_flags_from(value);
+        assert_eq!(u32::from(cpsr), 0b1010_0000_0000_0000_0000_0000_0001_0010);
+
+        cpsr.set_control_from(value);
+        assert_eq!(u32::from(cpsr), 0b1010_0000_0000_0000_0000_0000_1011_0010);
+        assert_eq!(cpsr.cpu_state(), CpuState::Thumb);
+        assert_eq!(cpsr.mode(), Mode::Irq);
+        assert_eq!(value.mode_raw(), 0);
+    }
+
     #[test]
     fn check_user() {
         let mut cpsr: Psr = Psr(0);
@@ -0,0 +1,117 @@
+use crate::bus::Bus;
+
+/// Flat RAM without I/O, wait states or interrupts, to test the CPU on its own.
+/// Addresses wrap around the size of the RAM and every access takes a cycle.
+pub struct TestBus {
+    pub memory: Vec<u8>,
+    cycles: u128,
+}
+
+impl TestBus {
+    pub fn new(size: usize) -> Self {
+        Self {
+            memory: vec![0; size],
+            cycles: 0,
+        }
+    }
+
+    fn index(&self, address: usize) -> usize {
+        address % self.memory.len()
+    }
+
+    fn read(&mut self, address: usize, size: usize) -> u32 {
+        self.cycles += 1;
+        let address = address & !(size - 1);
+
+        (0..size).rev().fold(0, |value, byte| {
+            value << 8 | u32::from(self.memory[self.index(address + byte)])
+        })
+    }
+
+    fn write(&mut self, address: usize, size: usize, value: u32) {
+        self.cycles += 1;
+        let address = address & !(size - 1);
+
+        for byte in 0..size {
+            let index = self.index(address + byte);
+            self.memory[index] = (value >> (byte * 8)) as u8;
+        }
+    }
+}
+
+impl Default for TestBus {
+    fn default() -> Self {
+        Self::new(0x1_0000)
+    }
+}
+
+impl Bus for TestBus {
+    fn read8(&mut self, address: usize) -> u8 {
+        self.read(address, 1) as u8
+    }
+
+    fn read16(&mut self, address: usize) -> u16 {
+        self.read(address, 2) as u16
+    }
+
+    fn read32(&mut self, address: usize) -> u32 {
+        self.read(address, 4)
+    }
+
+    fn write8(&mut self, address: usize, value: u8) {
+        self.write(address, 1, value.into());
+    }
+
+    fn write16(&mut self, address: usize, value: u16) {
+        self.write(address, 2, value.into());
+    }
+
+    fn write32(&mut self, address: usize, value: u32) {
+        self.write(address, 4, value);
+    }
+
+    fn cycles(&self) -> u128 {
+        self.cycles
+    }
+
+    fn internal_cycles(&mut self, cycles: u32) {
+        self.cycles += u128::from(cycles);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::arm7tdmi::Arm7tdmi;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn little_endian_and_aligned() {
+        let mut bus = TestBus::new(0x100);
+        bus.write32(0x10, 0x1122_3344);
+
+        assert_eq!(bus.read8(0x10), 0x44);
+        assert_eq!(bus.read16(0x13), 0x1122);
+        assert_eq!(bus.read32(0x112), 0x1122_3344);
+        assert_eq!(bus.cycles(), 4);
+    }
+
+    #[test]
+    fn cpu_runs_on_test_bus() {
+        let mut bus = TestBus::default();
+        // MOV R0, #5
+        bus.write32(0, 0xE3A0_0005);
+        // ADD R1, R0, R0
+        bus.write32(4, 0xE080_1000);
+        // STR R1, [R0, #0x7B]
+        bus.write32(8, 0xE580_107B);
+
+        let mut cpu = Arm7tdmi::new(bus);
+        // Two steps to fill the pipeline, then one per instruction.
+        (0..5).for_each(|_| cpu.step());
+
+        assert_eq!(cpu.registers.register_at(0), 5);
+        assert_eq!(cpu.registers.register_at(1), 10);
+        assert_eq!(cpu.bus.read32(0x80), 10);
+    }
+}
@@ -0,0 +1,541 @@
+//! Instruction-level tests: a program, written in ARM assembly or as raw opcodes, runs
+//! on a fresh CPU over a [`TestBus`] and the test checks registers, flags and memory.
+//!
+//! ```ignore
+//! CpuTest::arm(&["MOVS R0, R1, LSL #1"])
+//!     .register(1, 0x8000_0000)
+//!     .run()
+//!     .assert_register(0, 0)
+//!     .assert_flags("nZCv");
+//! ```
+//!
+//! The assembler knows data processing, MRS/MSR, LDR/STR with immediate offsets and
+//! branches, anything else can be written as a raw opcode.
+
+use crate::bus::Bus;
+use crate::cpu::arm7tdmi::Arm7tdmi;
+use crate::cpu::cpu_modes::Mode;
+use crate::cpu::psr::CpuState;
+use crate::cpu::test_bus::TestBus;
+
+/// Programs are loaded at address 0, where the CPU starts.
+const PROGRAM_START: usize = 0;
+
+/// Steps after which a program is considered stuck.
+const MAX_STEPS: usize = 10_000;
+
+const CONDITIONS: [&str; 16] = [
+    "EQ", "NE", "CS", "CC", "MI", "PL", "VS", "VC", "HI", "LS", "GE", "LT", "GT", "LE", "AL", "NV",
+];
+
+const ALU_OPERATIONS: [&str; 16] = [
+    "AND", "EOR", "SUB", "RSB", "ADD", "ADC", "SBC", "RSC", "TST", "TEQ", "CMP", "CMN", "ORR",
+    "MOV", "BIC", "MVN",
+];
+
+const SHIFTS: [&str; 4] = ["LSL", "LSR", "ASR", "ROR"];
+
+pub struct CpuTest {
+    pub cpu: Arm7tdmi<TestBus>,
+    program_end: usize,
+}
+
+impl CpuTest {
+    /// ARM program, one instruction per line, see [`assemble`].
+    pub fn arm(program: &[&str]) -> Self {
+        let opcodes: Vec<u32> = program
+            .iter()
+            .zip((PROGRAM_START..).step_by(4))
+            .map(|(line, address)| assemble(line, address as u32))
+            .collect();
+
+        Self::arm_opcodes(&opcodes)
+    }
+
+    pub fn arm_opcodes(opcodes: &[u32]) -> Self {
+        let mut bus = TestBus::default();
+        for (address, opcode) in (PROGRAM_START..).step_by(4).zip(opcodes) {
+            bus.write32(address, *opcode);
+        }
+
+        Self {
+            cpu: Arm7tdmi::new(bus),
+            program_end: PROGRAM_START + opcodes.len() * 4,
+        }
+    }
+
+    pub fn thumb_opcodes(opcodes: &[u16]) -> Self {
+        let mut bus = TestBus::default();
+        for (address, opcode) in (PROGRAM_START..).step_by(2).zip(opcodes) {
+            bus.write16(address, *opcode);
+        }
+
+        let mut cpu = Arm7tdmi::new(bus);
+        cpu.cpsr.set_cpu_state(CpuState::Thumb);
+
+        Self {
+            cpu,
+            program_end: PROGRAM_START + opcodes.len() * 2,
+        }
+    }
+
+    pub fn register(mut self, index: usize, value: u32) -> Self {
+        self.cpu.registers.set_register_at(index, value);
+        self
+    }
+
+    /// Sets the flags from a `NZCV` string, an uppercase letter is a set flag and a
+    /// lowercase one a clear flag (eg. `nZCv`).
+    pub fn flags(mut self, flags: &str) -> Self {
+        let [n, z, c, v] = parse_flags(flags);
+        self.cpu.cpsr.set_sign_flag(n);
+        self.cpu.cpsr.set_zero_flag(z);
+        self.cpu.cpsr.set_carry_flag(c);
+        self.cpu.cpsr.set_overflow_flag(v);
+        self
+    }
+
+    pub fn mode(mut self, mode: &Mode) -> Self {
+        self.cpu.swap_mode(mode);
+        self
+    }
+
+    pub fn memory(mut self, address: usize, value: u32) -> Self {
+        self.cpu.bus.write32(address, value);
+        self
+    }
+
+    /// Runs until the instruction after the program is the next one to execute.
+    ///
+    /// # Panics
+    /// If the program doesn't get there in [`MAX_STEPS`] steps.
+    pub fn run(mut self) -> Self {
+        for _ in 0..MAX_STEPS {
+            if self.cpu.next_instruction_address() as usize == self.program_end {
+                return self;
+            }
+
+            self.cpu.step();
+        }
+
+        panic!(
+            "the program didn't end in {MAX_STEPS} steps, next instruction at 0x{:08X}",
+            self.cpu.next_instruction_address()
+        );
+    }
+
+    pub fn assert_register(self, index: usize, expected: u32) -> Self {
+        let value = self.cpu.registers.register_at(index);
+        assert_eq!(
+            value, expected,
+            "R{index} is 0x{value:08X}, expected 0x{expected:08X}"
+        );
+        self
+    }
+
+    /// The flags as a `NZCV` string, see [`Self::flags`].
+    pub fn current_flags(&self) -> String {
+        let cpsr = self.cpu.cpsr;
+        let flags = [
+            cpsr.sign_flag(),
+            cpsr.zero_flag(),
+            cpsr.carry_flag(),
+            cpsr.overflow_flag(),
+        ];
+
+        "NZCV"
+            .chars()
+            .zip(flags)
+            .map(
+                |(name, set)| {
+                    if set {
+                        name
+                    } else {
+                        name.to_ascii_lowercase()
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Checks the flags against a `NZCV` string, see [`Self::flags`].
+    pub fn assert_flags(self, expected: &str) -> Self {
+        assert_eq!(self.current_flags(), expected, "unexpected flags");
+        self
+    }
+
+    pub fn assert_memory(mut self, address: usize, expected: u32) -> Self {
+        let value = self.cpu.bus.read32(address);
+        assert_eq!(
+            value, expected,
+            "memory at 0x{address:08X} is 0x{value:08X}, expected 0x{expected:08X}"
+        );
+        self
+    }
+}
+
+fn parse_flags(flags: &str) -> [bool; 4] {
+    let flags: Vec<char> = flags.chars().collect();
+    assert!(
+        flags.len() == 4
+            && flags
+                .iter()
+                .zip("NZCV".chars())
+                .all(|(flag, name)| flag.eq_ignore_ascii_case(&name)),
+        "flags must be written as NZCV, got {flags:?}"
+    );
+
+    [0, 1, 2, 3].map(|index| flags[index].is_ascii_uppercase())
+}
+
+/// Assembles an ARM instruction placed at `address`, which is used by branches.
+///
+/// # Panics
+/// If the instruction is not valid or not supported.
+pub fn assemble(line: &str, address: u32) -> u32 {
+    let line = line.trim().to_ascii_uppercase();
+    let (mnemonic, operands) = line.split_once(' ').unwrap_or((&line, ""));
+    let operands = split_operands(operands);
+
+    if let Some(opcode) = assemble_branch(mnemonic, &operands, address) {
+        return opcode;
+    }
+
+    let (operation, rest) = mnemonic.split_at(mnemonic.len().min(3));
+    let operands: Vec<&str> = operands.iter().map(String::as_str).collect();
+
+    match operation {
+        "MRS" => assemble_mrs(condition(rest, &line), &operands),
+        "MSR" => assemble_msr(condition(rest, &line), &operands),
+        "LDR" | "STR" => assemble_transfer(operation == "LDR", rest, &operands, &line),
+        _ => {
+            let opcode = ALU_OPERATIONS
+                .iter()
+                .position(|name| *name == operation)
+                .unwrap_or_else(|| panic!("unsupported instruction `{line}`"));
+
+            assemble_data_processing(opcode as u32, rest, &operands, &line)
+        }
+    }
+}
+
+/// Splits at commas outside brackets, `[R0, #4]` is a single operand.
+fn split_operands(operands: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut in_brackets = false;
+
+    for char in operands.chars() {
+        match char {
+            ',' if !in_brackets => parts.push(String::new()),
+            '[' | ']' => {
+                in_brackets = char == '[';
+                parts.last_mut().unwrap().push(char);
+            }
+            _ => parts.last_mut().unwrap().push(char),
+        }
+    }
+
+    parts
+        .into_iter()
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Condition code of a suffix, `AL` if it's empty.
+fn condition(suffix: &str, line: &str) -> u32 {
+    if suffix.is_empty() {
+        return 0xE;
+    }
+
+    CONDITIONS
+        .iter()
+        .position(|name| *name == suffix)
+        .unwrap_or_else(|| panic!("unknown condition `{suffix}` in `{line}`")) as u32
+}
+
+fn register(operand: &str) -> u32 {
+    match operand.trim() {
+        "SP" => 13,
+        "LR" => 14,
+        "PC" => 15,
+        register => register
+            .strip_prefix('R')
+            .and_then(|index| index.parse().ok())
+            .filter(|index| *index < 16)
+            .unwrap_or_else(|| panic!("`{operand}` is not a register")),
+    }
+}
+
+fn immediate(operand: &str) -> i64 {
+    let value = operand
+        .trim()
+        .strip_prefix('#')
+        .unwrap_or_else(|| panic!("`{operand}` is not an immediate"));
+    let (negative, value) = value
+        .strip_prefix('-')
+        .map_or((false, value), |value| (true, value));
+    let value = value.replace('_', "");
+    let (radix, digits) = value
+        .strip_prefix("0X")
+        .map(|hex| (16, hex))
+        .or_else(|| value.strip_prefix("0B").map(|binary| (2, binary)))
+        .unwrap_or((10, &value));
+    let value = i64::from_str_radix(digits, radix);
+    let value = value.unwrap_or_else(|_| panic!("`{operand}` is not a number"));
+
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// An 8 bits value rotated right by an even amount.
+fn rotated_immediate(operand: &str) -> u32 {
+    let value = immediate(operand) as u32;
+
+    (0..16)
+        .find(|rotation| value.rotate_left(rotation * 2) <= 0xFF)
+        .map_or_else(
+            || panic!("`{operand}` can't be encoded as an immediate"),
+            |rotation| rotation << 8 | value.rotate_left(rotation * 2),
+        )
+}
+
+/// The second operand, `I` bit included: an immediate or a register with optional shift.
+fn operand2(operands: &[&str]) -> u32 {
+    let [operand, shift @ ..] = operands else {
+        panic!("missing operand");
+    };
+
+    if operand.starts_with('#') {
+        return 1 << 25 | rotated_immediate(operand);
+    }
+
+    let rm = register(operand);
+    let Some(shift) = shift.first() else {
+        return rm;
+    };
+
+    if *shift == "RRX" {
+        return 0b11 << 5 | rm;
+    }
+
+    let (kind, amount) = shift.split_once(' ').expect("shift without amount");
+    let kind = SHIFTS
+        .iter()
+        .position(|name| *name == kind)
+        .unwrap_or_else(|| panic!("unknown shift `{kind}`")) as u32;
+    let amount = amount.trim();
+
+    if amount.starts_with('#') {
+        // LSR #32 and ASR #32 are encoded as #0.
+        let amount = (immediate(amount) as u32) & 0x1F;
+        amount << 7 | kind << 5 | rm
+    } else {
+        register(amount) << 8 | kind << 5 | 1 << 4 | rm
+    }
+}
+
+fn assemble_data_processing(opcode: u32, suffix: &str, operands: &[&str], line: &str) -> u32 {
+    // TST, TEQ, CMP and CMN always set the flags, MOV and MVN have no first operand.
+    let is_test = (0x8..=0xB).contains(&opcode);
+    let is_move = opcode == 0xD || opcode == 0xF;
+
+    let (set_flags, suffix) = match (suffix.strip_prefix('S'), suffix.strip_suffix('S')) {
+        (Some(rest), _) if rest.len() != 1 => (true, rest),
+        (_, Some(rest)) if rest.len() == 2 => (true, rest),
+        _ => (is_test, suffix),
+    };
+    let set_flags = set_flags || is_test;
+
+    let (rd, rn, op2) = if is_test {
+        (0, register(operands[0]), operand2(&operands[1..]))
+    } else if is_move {
+        (register(operands[0]), 0, operand2(&operands[1..]))
+    } else {
+        (
+            register(operands[0]),
+            register(operands[1]),
+            operand2(&operands[2..]),
+        )
+    };
+
+    condition(suffix, line) << 28
+        | opcode << 21
+        | u32::from(set_flags) << 20
+        | rn << 16
+        | rd << 12
+        | op2
+}
+
+/// `P` bit of a PSR operand, `SPSR` or `CPSR` with an optional field suffix.
+fn psr(operand: &str) -> (u32, &str) {
+    let (psr, field) = operand.split_once('_').unwrap_or((operand, ""));
+    let psr = match psr {
+        "CPSR" => 0,
+        "SPSR" => 1,
+        _ => panic!("`{operand}` is not a PSR"),
+    };
+
+    (psr, field)
+}
+
+fn assemble_mrs(condition: u32, operands: &[&str]) -> u32 {
+    let (psr, _) = psr(operands[1]);
+
+    condition << 28 | 0b00010 << 23 | psr << 22 | 0b00_1111 << 16 | register(operands[0]) << 12
+}
+
+fn assemble_msr(condition: u32, operands: &[&str]) -> u32 {
+    let (psr, field) = psr(operands[0]);
+
+    match field {
+        // Flags only, from a register or an immediate.
+        "F" | "FLG" => {
+            condition << 28
+                | 0b10 << 23
+                | psr << 22
+                | 0b10_1000_1111 << 12
+                | operand2(&operands[1..])
+        }
+        "" | "FC" | "ALL" => {
+            condition << 28
+                | 0b00010 << 23
+                | psr << 22
+                | 0b10_1001_1111 << 12
+                | register(operands[1])
+        }
+        _ => panic!("unsupported PSR field `{field}`"),
+    }
+}
+
+/// `LDR`/`STR` with `B` suffix and an immediate offset, pre or post indexed.
+fn assemble_transfer(load: bool, suffix: &str, operands: &[&str], line: &str) -> u32 {
+    let (byte, suffix) = suffix
+        .strip_suffix('B')
+        .map_or((false, suffix), |suffix| (true, suffix));
+
+    let address = operands[1];
+    let write_back = address.ends_with('!');
+    let address = address
+        .trim_end_matches('!')
+        .strip_prefix('[')
+        .unwrap_or_else(|| panic!("`{address}` is not an address"));
+
+    let (pre_indexed, base, offset) = match (address.strip_suffix(']'), operands.get(2)) {
+        (Some(inside), None) => {
+            let (base, offset) = inside.split_once(',').unwrap_or((inside, "#0"));
+            (true, base, immediate(offset))
+        }
+        (Some(base), Some(offset)) => (false, base, immediate(offset)),
+        (None, _) => panic!("`{line}` has an invalid address"),
+    };
+
+    assert!(offset.abs() < 0x1000, "offset out of range in `{line}`");
+
+    condition(suffix, line) << 28
+        | 0b01 << 26
+        | u32::from(pre_indexed) << 24
+        | u32::from(offset >= 0) << 23
+        | u32::from(byte) << 22
+        | u32::from(write_back) << 21
+        | u32::from(load) << 20
+        | register(base) << 16
+        | register(operands[0]) << 12
+        | offset.unsigned_abs() as u32
+}
+
+/// `B` and `BL` to an absolute address, with an optional condition.
+fn assemble_branch(mnemonic: &str, operands: &[String], address: u32) -> Option<u32> {
+    let rest = mnemonic.strip_prefix('B')?;
+
+    let (link, condition) = if rest.is_empty() || CONDITIONS.contains(&rest) {
+        (false, rest)
+    } else {
+        let condition = rest.strip_prefix('L')?;
+        if !condition.is_empty() && !CONDITIONS.contains(&condition) {
+            return None;
+        }
+        (true, condition)
+    };
+
+    let target = immediate(operands.first()?) as u32;
+    let offset = target.wrapping_sub(address.wrapping_add(8)) >> 2;
+
+    Some(
+        self::condition(condition, mnemonic) << 28
+            | 0b101 << 25
+            | u32::from(link) << 24
+            | offset & 0x00FF_FFFF,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn assemble_instructions() {
+        let cases = [
+            ("MOV R0, #5", 0xE3A0_0005),
+            ("ADDS R1, R0, R0", 0xE090_1000),
+            ("ADDEQS R1, R0, R0", 0x0090_1000),
+            ("ADDSEQ R1, R0, R0", 0x0090_1000),
+            ("MOV R0, #0xFF000000", 0xE3A0_04FF),
+            ("MOV R1, R2, LSL #3", 0xE1A0_1182),
+            ("MOV R1, R2, LSR #32", 0xE1A0_1022),
+            ("MOV R1, R2, ROR R3", 0xE1A0_1372),
+            ("MOV R1, R2, RRX", 0xE1A0_1062),
+            ("CMP R0, #1", 0xE350_0001),
+            ("TST R0, R1", 0xE110_0001),
+            ("MRS R0, CPSR", 0xE10F_0000),
+            ("MSR SPSR, R0", 0xE169_F000),
+            ("MSR CPSR_flg, #0xF0000000", 0xE328_F20F),
+            ("LDR R0, [R1, #4]", 0xE591_0004),
+            ("STRB R0, [R1, #-4]!", 0xE561_0004),
+            ("LDR R0, [R1], #4", 0xE491_0004),
+            ("B #0x0", 0xEAFF_FFFC),
+            ("BLNE #0x100", 0x1B00_003C),
+            ("BLS #0x8", 0x9AFF_FFFE),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(assemble(line, 0x8), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn run_program() {
+        CpuTest::arm(&[
+            "MOV R0, #5",
+            "ADD R1, R0, R0",
+            "STR R1, [R0, #0x7B]",
+            "LDR R2, [R0, #0x7F]",
+        ])
+        .memory(0x84, 7)
+        .run()
+        .assert_register(1, 10)
+        .assert_register(2, 7)
+        .assert_memory(0x80, 10)
+        .assert_flags("nzcv");
+    }
+
+    #[test]
+    fn run_thumb_program() {
+        // MOV R0, #200; LSL R1, R0, #24
+        CpuTest::thumb_opcodes(&[0x20C8, 0x0601])
+            .run()
+            .assert_register(1, 200 << 24)
+            .assert_flags("Nzcv");
+    }
+
+    #[test]
+    fn branches_are_followed() {
+        CpuTest::arm(&["MOV R0, #1", "B #0xC", "MOV R0, #2", "ADD R0, R0, #3"])
+            .run()
+            .assert_register(0, 4);
+    }
+}
@@ -20,6 +20,29 @@ pub enum ThumbModeAluInstruction {
     Mvn = 0xF,
 }
 
+impl ThumbModeAluInstruction {
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::And => "AND",
+            Self::Eor => "EOR",
+            Self::Lsl => "LSL",
+            Self::Lsr => "LSR",
+            Self::Asr => "ASR",
+            Self::Adc => "ADC",
+            Self::Sbc => "SBC",
+            Self::Ror => "ROR",
+            Self::Tst => "TST",
+            Self::Neg => "NEG",
+            Self::Cmp => "CMP",
+            Self::Cmn => "CMN",
+            Self::Orr => "ORR",
+            Self::Mul => "MUL",
+            Self::Bic => "BIC",
+            Self::Mvn => "MVN",
+        }
+    }
+}
+
 impl From<u16> for ThumbModeAluInstruction {
     fn from(alu_op_code: u16) -> Self {
         use ThumbModeAluInstruction::{
@@ -0,0 +1,142 @@
+use crate::cpu::encoding::Encoding;
+
+/// Formats of the Thumb instructions, as they are told apart by the decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbFormat {
+    Swi,
+    AddOffsetSP,
+    AluOp,
+    HiRegisterOpBX,
+    PushPopReg,
+    AddSubtract,
+    PCRelativeLoad,
+    LoadStoreRegisterOffset,
+    LoadStoreSignExtByteHalfword,
+    UncondBranch,
+    LoadStoreHalfword,
+    SPRelativeLoadStore,
+    LoadAddress,
+    MultipleLoadStore,
+    CondBranch,
+    LongBranchLink,
+    MoveShiftedRegister,
+    MoveCompareAddSubtractImm,
+    LoadStoreImmOffset,
+}
+
+/// Every Thumb format, in order of priority: the encodings of the first ones overlap the
+/// ones after (eg. a software interrupt is a conditional branch with condition `1111`).
+pub const THUMB_ENCODINGS: [Encoding<ThumbFormat>; 19] = [
+    Encoding::new(
+        ThumbFormat::Swi,
+        "1101_1111_vvvv_vvvv",
+        "FMT: |1_1_0_1_1_1_1_1|_____Value8____|",
+    ),
+    Encoding::new(
+        ThumbFormat::AddOffsetSP,
+        "1011_0000_swww_wwww",
+        "FMT: |1_0_1_1_0_0_0_0|S|____Word7____|",
+    ),
+    Encoding::new(
+        ThumbFormat::AluOp,
+        "0100_00oo_ooss_sddd",
+        "FMT: |0_1_0_0_0_0|__Op___|_Rs__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::HiRegisterOpBX,
+        "0100_01oo_hhss_sddd",
+        "FMT: |0_1_0_0_0_1|_Op|H|H|Rs/Hs|Rd/Hd|",
+    ),
+    Encoding::new(
+        ThumbFormat::PushPopReg,
+        "1011_l10r_rrrr_rrrr",
+        "FMT: |1_0_1_1|L|1_0|R|_____Rlist_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::AddSubtract,
+        "0001_1ion_nnss_sddd",
+        "FMT: |0_0_0_1_1|I|O|RnOff|_Rs__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::PCRelativeLoad,
+        "0100_1ddd_wwww_wwww",
+        "FMT: |0_1_0_0_1|_Rn__|_____Word8_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::LoadStoreRegisterOffset,
+        "0101_lb0o_oobb_bddd",
+        "FMT: |0_1_0_1|L|B|0|_Ro__|_Rb__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::LoadStoreSignExtByteHalfword,
+        "0101_hs1o_oobb_bddd",
+        "FMT: |0_1_0_1|H|S|1|_Ro__|_Rb__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::UncondBranch,
+        "1110_0ooo_oooo_oooo",
+        "FMT: |1_1_1_0_0|________Offset11_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::LoadStoreHalfword,
+        "1000_looo_oobb_bddd",
+        "FMT: |1_0_0_0|L|_Offset5_|_Rb__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::SPRelativeLoadStore,
+        "1001_lddd_wwww_wwww",
+        "FMT: |1_0_0_1|L|_Rd__|_____Word8_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::LoadAddress,
+        "1010_sddd_wwww_wwww",
+        "FMT: |1_0_1_0|S|_Rd__|_____Word8_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::MultipleLoadStore,
+        "1100_lbbb_rrrr_rrrr",
+        "FMT: |1_1_0_0|L|_Rb__|_____Rlist_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::CondBranch,
+        "1101_cccc_oooo_oooo",
+        "FMT: |1_1_0_1|_Cond__|_____Offset____|",
+    ),
+    Encoding::new(
+        ThumbFormat::LongBranchLink,
+        "1111_hooo_oooo_oooo",
+        "FMT: |1_1_1_1|H|_______Offset________|",
+    ),
+    Encoding::new(
+        ThumbFormat::MoveShiftedRegister,
+        "000o_oiii_iiss_sddd",
+        "FMT: |0_0_0|Op_|__Offset_|_Rs__|_Rd__|",
+    ),
+    Encoding::new(
+        ThumbFormat::MoveCompareAddSubtractImm,
+        "001o_oddd_iiii_iiii",
+        "FMT: |0_0_1|Op_|_Rn__|____Offset_____|",
+    ),
+    Encoding::new(
+        ThumbFormat::LoadStoreImmOffset,
+        "011b_liii_iibb_bddd",
+        "FMT: |0_1_1|B|L|_Offset5_|_Rb__|_Rd__|",
+    ),
+];
+
+impl ThumbFormat {
+    /// The format of `op_code`, `None` if no encoding matches it.
+    #[must_use]
+    pub fn decode(op_code: u16) -> Option<Self> {
+        crate::cpu::encoding::find(&THUMB_ENCODINGS, op_code.into()).map(|encoding| encoding.format)
+    }
+
+    /// Names of the fields of the format.
+    #[must_use]
+    pub fn layout(self) -> &'static str {
+        THUMB_ENCODINGS
+            .iter()
+            .find(|encoding| encoding.format == self)
+            .map_or("FMT: ||", |encoding| encoding.layout)
+    }
+}
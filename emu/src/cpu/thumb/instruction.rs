@@ -1,9 +1,11 @@
 use crate::bitwise::Bits;
 use crate::cpu::condition::Condition;
+use crate::cpu::dispatch;
 use crate::cpu::flags::{LoadStoreKind, OperandKind, Operation, ReadWriteKind, ShiftKind};
 #[cfg(feature = "disassembler")]
 use crate::cpu::registers::REG_PROGRAM_COUNTER;
 use crate::cpu::thumb::alu_instructions::{ThumbHighRegisterOperation, ThumbModeAluInstruction};
+use crate::cpu::thumb::encodings::ThumbFormat;
 use logger::log;
 use serde::{Deserialize, Serialize};
 
@@ -90,7 +92,10 @@ pub enum Instruction {
         condition: Condition,
         immediate_offset: i32,
     },
-    Swi,
+    Swi {
+        /// Ignored by the CPU, the BIOS uses it as function number.
+        comment: u8,
+    },
     UncondBranch {
         offset: u32,
     },
@@ -111,133 +116,290 @@ impl From<u16> for Instruction {
             SPRelativeLoadStore, Swi, UncondBranch,
         };
 
-        if op_code.get_bits(8..=15) == 0b1101_1111 {
-            Swi
-        } else if op_code.get_bits(8..=15) == 0b1011_0000 {
-            AddOffsetSP {
+        let Some(format) = dispatch::thumb_format(op_code) else {
+            log(format!("not identified instruction {op_code} "));
+            unimplemented!()
+        };
+
+        match format {
+            ThumbFormat::Swi => Swi {
+                comment: op_code.get_bits_u8(0..=7),
+            },
+            ThumbFormat::AddOffsetSP => AddOffsetSP {
                 // 0 - positive, 1 - negative TODO
                 s: op_code.get_bit(7),
                 // The offset supplied in #Imm is a full 10-bit address,
                 // but must always be word-aligned (ie bits 1:0 set to 0),
                 // since the assembler places #Imm >> 2 in the Word8 field.
-                word7: op_code.get_bits(0..=6) << 2,
-            }
-        } else if op_code.get_bits(10..=15) == 0b01_0000 {
-            AluOp {
-                alu_operation: op_code.get_bits(6..=9).into(),
-                source_register: op_code.get_bits(3..=5),
-                destination_register: op_code.get_bits(0..=2),
-            }
-        } else if op_code.get_bits(10..=15) == 0b01_0001 {
-            let h1 = op_code.get_bit(7);
-            let rd_hd = op_code.get_bits(0..=2);
-            let destination_register = if h1 { rd_hd | (1 << 3) } else { rd_hd };
-
-            HiRegisterOpBX {
-                register_operation: op_code.get_bits(8..=9).into(),
-                source_register: op_code.get_bits(3..=6),
-                destination_register,
+                word7: op_code.get_bits_const::<0, 6>() << 2,
+            },
+            ThumbFormat::AluOp => AluOp {
+                alu_operation: op_code.get_bits_const::<6, 9>().into(),
+                source_register: op_code.get_bits_const::<3, 5>(),
+                destination_register: op_code.get_bits_const::<0, 2>(),
+            },
+            ThumbFormat::HiRegisterOpBX => {
+                let h1 = op_code.get_bit(7);
+                let rd_hd = op_code.get_bits_const::<0, 2>();
+                let destination_register = if h1 { rd_hd | (1 << 3) } else { rd_hd };
+
+                HiRegisterOpBX {
+                    register_operation: op_code.get_bits_const::<8, 9>().into(),
+                    source_register: op_code.get_bits_const::<3, 6>(),
+                    destination_register,
+                }
             }
-        } else if op_code.get_bits(12..=15) == 0b1011 && op_code.get_bits(9..=10) == 0b10 {
-            PushPopReg {
+            ThumbFormat::PushPopReg => PushPopReg {
                 load_store: op_code.get_bit(11).into(),
                 pc_lr: op_code.get_bit(8),
-                register_list: op_code.get_bits(0..=7),
-            }
-        } else if op_code.get_bits(11..=15) == 0b00011 {
-            AddSubtract {
+                register_list: op_code.get_bits_const::<0, 7>(),
+            },
+            ThumbFormat::AddSubtract => AddSubtract {
                 operation_kind: op_code.get_bit(10).into(),
                 // 0 - Add, 1 - Sub TODO
                 op: op_code.get_bit(9),
-                rn_offset3: op_code.get_bits(6..=8),
-                source_register: op_code.get_bits(3..=5),
-                destination_register: op_code.get_bits(0..=2),
-            }
-        } else if op_code.get_bits(11..=15) == 0b01001 {
-            PCRelativeLoad {
-                destination_register: op_code.get_bits(8..=10),
-                immediate_value: op_code.get_bits(0..=7) << 2,
-            }
-        } else if op_code.get_bits(12..=15) == 0b0101 && !op_code.get_bit(9) {
-            LoadStoreRegisterOffset {
+                rn_offset3: op_code.get_bits_const::<6, 8>(),
+                source_register: op_code.get_bits_const::<3, 5>(),
+                destination_register: op_code.get_bits_const::<0, 2>(),
+            },
+            ThumbFormat::PCRelativeLoad => PCRelativeLoad {
+                destination_register: op_code.get_bits_const::<8, 10>(),
+                immediate_value: op_code.get_bits_const::<0, 7>() << 2,
+            },
+            ThumbFormat::LoadStoreRegisterOffset => LoadStoreRegisterOffset {
                 load_store: op_code.get_bit(11).into(),
                 byte_word: op_code.get_bit(10).into(),
-                ro: op_code.get_bits(6..=8),
-                base_register: op_code.get_bits(3..=5),
-                destination_register: op_code.get_bits(0..=2),
-            }
-        } else if op_code.get_bits(12..=15) == 0b0101 && op_code.get_bit(9) {
-            LoadStoreSignExtByteHalfword {
+                ro: op_code.get_bits_const::<6, 8>(),
+                base_register: op_code.get_bits_const::<3, 5>(),
+                destination_register: op_code.get_bits_const::<0, 2>(),
+            },
+            ThumbFormat::LoadStoreSignExtByteHalfword => LoadStoreSignExtByteHalfword {
                 h: op_code.get_bit(11),
                 sign_extend_flag: op_code.get_bit(10),
-                offset_register: op_code.get_bits(6..=8) as u32,
-                base_register: op_code.get_bits(3..=5) as u32,
-                destination_register: op_code.get_bits(0..=2) as u32,
-            }
-        } else if op_code.get_bits(11..=15) == 0b11100 {
-            UncondBranch {
-                offset: (op_code.get_bits(0..=10) << 1) as u32,
-            }
-        } else if op_code.get_bits(12..=15) == 0b1000 {
-            LoadStoreHalfword {
+                offset_register: op_code.get_bits_const::<6, 8>() as u32,
+                base_register: op_code.get_bits_const::<3, 5>() as u32,
+                destination_register: op_code.get_bits_const::<0, 2>() as u32,
+            },
+            ThumbFormat::UncondBranch => UncondBranch {
+                offset: (op_code.get_bits_const::<0, 10>() << 1) as u32,
+            },
+            ThumbFormat::LoadStoreHalfword => LoadStoreHalfword {
                 load_store: op_code.get_bit(11).into(),
-                offset: op_code.get_bits(6..=10) << 1,
-                base_register: op_code.get_bits(3..=5),
-                source_destination_register: op_code.get_bits(0..=2),
-            }
-        } else if op_code.get_bits(12..=15) == 0b1001 {
-            SPRelativeLoadStore {
+                offset: op_code.get_bits_const::<6, 10>() << 1,
+                base_register: op_code.get_bits_const::<3, 5>(),
+                source_destination_register: op_code.get_bits_const::<0, 2>(),
+            },
+            ThumbFormat::SPRelativeLoadStore => SPRelativeLoadStore {
                 load_store: op_code.get_bit(11).into(),
-                destination_register: op_code.get_bits(8..=10),
+                destination_register: op_code.get_bits_const::<8, 10>(),
                 // The offset supplied in #Imm is a full 10-bit address,
                 // but must always be word-aligned (ie bits 1:0 set to 0),
                 // since the assembler places #Imm >> 2 in the Word8 field.
-                word8: op_code.get_bits(0..=7) << 2,
-            }
-        } else if op_code.get_bits(12..=15) == 0b1010 {
-            LoadAddress {
+                word8: op_code.get_bits_const::<0, 7>() << 2,
+            },
+            ThumbFormat::LoadAddress => LoadAddress {
                 sp: op_code.get_bit(11),
-                destination_register: op_code.get_bits(8..=10) as u32,
-                offset: (op_code.get_bits(0..=7) as u32) << 2,
-            }
-        } else if op_code.get_bits(12..=15) == 0b1100 {
-            MultipleLoadStore {
+                destination_register: op_code.get_bits_const::<8, 10>() as u32,
+                offset: (op_code.get_bits_const::<0, 7>() as u32) << 2,
+            },
+            ThumbFormat::MultipleLoadStore => MultipleLoadStore {
                 load_store: op_code.get_bit(11).into(),
-                base_register: op_code.get_bits(8..=10),
-                register_list: op_code.get_bits(0..=7),
-            }
-        } else if op_code.get_bits(12..=15) == 0b1101 {
-            // 9 bits signed offset (assembler puts `label` >> 1 in this field so we should <<1)
-            let offset = (op_code.get_bits(0..=7) << 1) as u32;
-            let immediate_offset = offset.sign_extended(9) as i32;
-
-            CondBranch {
-                condition: Condition::from(op_code.get_bits(8..=11) as u8),
-                immediate_offset,
+                base_register: op_code.get_bits_const::<8, 10>(),
+                register_list: op_code.get_bits_const::<0, 7>(),
+            },
+            ThumbFormat::CondBranch => {
+                // 9 bits signed offset (assembler puts `label` >> 1 in this field so we should <<1)
+                let offset = (op_code.get_bits_const::<0, 7>() << 1) as u32;
+                let immediate_offset = offset.sign_extended(9) as i32;
+
+                CondBranch {
+                    condition: Condition::from(op_code.get_bits_u8(8..=11)),
+                    immediate_offset,
+                }
             }
-        } else if op_code.get_bits(12..=15) == 0b1111 {
-            LongBranchLink {
+            ThumbFormat::LongBranchLink => LongBranchLink {
                 h: op_code.get_bit(11),
-                offset: op_code.get_bits(0..=10) as u32,
-            }
-        } else if op_code.get_bits(13..=15) == 0b000 {
-            MoveShiftedRegister {
-                shift_operation: op_code.get_bits(11..=12).into(),
-                offset5: op_code.get_bits(6..=10),
-                source_register: op_code.get_bits(3..=5),
-                destination_register: op_code.get_bits(0..=2),
-            }
-        } else if op_code.get_bits(13..=15) == 0b001 {
-            MoveCompareAddSubtractImm {
-                operation: op_code.get_bits(11..=12).into(),
-                destination_register: op_code.get_bits(8..=10),
-                offset: op_code.get_bits(0..=7).into(),
-            }
-        } else if op_code.get_bits(13..=15) == 0b011 {
-            LoadStoreImmOffset
-        } else {
-            log(format!("not identified instruction {op_code} "));
-            unimplemented!()
+                offset: op_code.get_bits_const::<0, 10>() as u32,
+            },
+            ThumbFormat::MoveShiftedRegister => MoveShiftedRegister {
+                shift_operation: op_code.get_bits_const::<11, 12>().into(),
+                offset5: op_code.get_bits_const::<6, 10>(),
+                source_register: op_code.get_bits_const::<3, 5>(),
+                destination_register: op_code.get_bits_const::<0, 2>(),
+            },
+            ThumbFormat::MoveCompareAddSubtractImm => MoveCompareAddSubtractImm {
+                operation: op_code.get_bits_const::<11, 12>().into(),
+                destination_register: op_code.get_bits_const::<8, 10>(),
+                offset: op_code.get_bits_const::<0, 7>().into(),
+            },
+            ThumbFormat::LoadStoreImmOffset => LoadStoreImmOffset,
+        }
+    }
+}
+
+/// Every name returned by [`Instruction::kind`], in the order of the formats.
+pub const THUMB_KINDS: [&str; 55] = [
+    "LSL #imm",
+    "LSR #imm",
+    "ASR #imm",
+    "ADD Rd, Rs, Rn",
+    "SUB Rd, Rs, Rn",
+    "ADD Rd, Rs, #imm",
+    "SUB Rd, Rs, #imm",
+    "MOV #imm",
+    "CMP #imm",
+    "ADD #imm",
+    "SUB #imm",
+    "AND",
+    "EOR",
+    "LSL",
+    "LSR",
+    "ASR",
+    "ADC",
+    "SBC",
+    "ROR",
+    "TST",
+    "NEG",
+    "CMP",
+    "CMN",
+    "ORR",
+    "MUL",
+    "BIC",
+    "MVN",
+    "ADD Hi",
+    "CMP Hi",
+    "MOV Hi",
+    "BX",
+    "LDR [PC, #imm]",
+    "LDR [Rb, Ro]",
+    "LDRB [Rb, Ro]",
+    "STR [Rb, Ro]",
+    "STRB [Rb, Ro]",
+    "STRH [Rb, Ro]",
+    "LDRH [Rb, Ro]",
+    "LDSB [Rb, Ro]",
+    "LDSH [Rb, Ro]",
+    "LDR/STR [Rb, #imm]",
+    "LDRH [Rb, #imm]",
+    "STRH [Rb, #imm]",
+    "LDR [SP, #imm]",
+    "STR [SP, #imm]",
+    "ADD Rd, PC/SP, #imm",
+    "ADD SP, #imm",
+    "PUSH",
+    "POP",
+    "LDMIA",
+    "STMIA",
+    "B<cond>",
+    "SWI",
+    "B",
+    "BL",
+];
+
+impl Instruction {
+    /// The format of the encoding the instruction was decoded from.
+    pub(crate) const fn format(&self) -> ThumbFormat {
+        match self {
+            Self::MoveShiftedRegister { .. } => ThumbFormat::MoveShiftedRegister,
+            Self::AddSubtract { .. } => ThumbFormat::AddSubtract,
+            Self::MoveCompareAddSubtractImm { .. } => ThumbFormat::MoveCompareAddSubtractImm,
+            Self::AluOp { .. } => ThumbFormat::AluOp,
+            Self::HiRegisterOpBX { .. } => ThumbFormat::HiRegisterOpBX,
+            Self::PCRelativeLoad { .. } => ThumbFormat::PCRelativeLoad,
+            Self::LoadStoreRegisterOffset { .. } => ThumbFormat::LoadStoreRegisterOffset,
+            Self::LoadStoreSignExtByteHalfword { .. } => ThumbFormat::LoadStoreSignExtByteHalfword,
+            Self::LoadStoreImmOffset => ThumbFormat::LoadStoreImmOffset,
+            Self::LoadStoreHalfword { .. } => ThumbFormat::LoadStoreHalfword,
+            Self::SPRelativeLoadStore { .. } => ThumbFormat::SPRelativeLoadStore,
+            Self::LoadAddress { .. } => ThumbFormat::LoadAddress,
+            Self::AddOffsetSP { .. } => ThumbFormat::AddOffsetSP,
+            Self::PushPopReg { .. } => ThumbFormat::PushPopReg,
+            Self::MultipleLoadStore { .. } => ThumbFormat::MultipleLoadStore,
+            Self::CondBranch { .. } => ThumbFormat::CondBranch,
+            Self::Swi { .. } => ThumbFormat::Swi,
+            Self::UncondBranch { .. } => ThumbFormat::UncondBranch,
+            Self::LongBranchLink { .. } => ThumbFormat::LongBranchLink,
+        }
+    }
+
+    /// Name of the operation, by format and by ALU operation.
+    pub(crate) const fn kind(&self) -> &'static str {
+        match self {
+            Self::MoveShiftedRegister {
+                shift_operation, ..
+            } => match shift_operation {
+                ShiftKind::Lsl => "LSL #imm",
+                ShiftKind::Lsr => "LSR #imm",
+                ShiftKind::Asr => "ASR #imm",
+                ShiftKind::Ror => "ROR #imm",
+            },
+            Self::AddSubtract {
+                operation_kind, op, ..
+            } => match (operation_kind, op) {
+                (OperandKind::Register, false) => "ADD Rd, Rs, Rn",
+                (OperandKind::Register, true) => "SUB Rd, Rs, Rn",
+                (OperandKind::Immediate, false) => "ADD Rd, Rs, #imm",
+                (OperandKind::Immediate, true) => "SUB Rd, Rs, #imm",
+            },
+            Self::MoveCompareAddSubtractImm { operation, .. } => match operation {
+                Operation::Mov => "MOV #imm",
+                Operation::Cmp => "CMP #imm",
+                Operation::Add => "ADD #imm",
+                Operation::Sub => "SUB #imm",
+            },
+            Self::AluOp { alu_operation, .. } => alu_operation.name(),
+            Self::HiRegisterOpBX {
+                register_operation, ..
+            } => match register_operation {
+                ThumbHighRegisterOperation::Add => "ADD Hi",
+                ThumbHighRegisterOperation::Cmp => "CMP Hi",
+                ThumbHighRegisterOperation::Mov => "MOV Hi",
+                ThumbHighRegisterOperation::BxOrBlx => "BX",
+            },
+            Self::PCRelativeLoad { .. } => "LDR [PC, #imm]",
+            Self::LoadStoreRegisterOffset {
+                load_store,
+                byte_word,
+                ..
+            } => match (load_store, byte_word) {
+                (LoadStoreKind::Load, ReadWriteKind::Word) => "LDR [Rb, Ro]",
+                (LoadStoreKind::Load, ReadWriteKind::Byte) => "LDRB [Rb, Ro]",
+                (LoadStoreKind::Store, ReadWriteKind::Word) => "STR [Rb, Ro]",
+                (LoadStoreKind::Store, ReadWriteKind::Byte) => "STRB [Rb, Ro]",
+            },
+            Self::LoadStoreSignExtByteHalfword {
+                h,
+                sign_extend_flag,
+                ..
+            } => match (h, sign_extend_flag) {
+                (false, false) => "STRH [Rb, Ro]",
+                (true, false) => "LDRH [Rb, Ro]",
+                (false, true) => "LDSB [Rb, Ro]",
+                (true, true) => "LDSH [Rb, Ro]",
+            },
+            Self::LoadStoreImmOffset => "LDR/STR [Rb, #imm]",
+            Self::LoadStoreHalfword { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "LDRH [Rb, #imm]",
+                LoadStoreKind::Store => "STRH [Rb, #imm]",
+            },
+            Self::SPRelativeLoadStore { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "LDR [SP, #imm]",
+                LoadStoreKind::Store => "STR [SP, #imm]",
+            },
+            Self::LoadAddress { .. } => "ADD Rd, PC/SP, #imm",
+            Self::AddOffsetSP { .. } => "ADD SP, #imm",
+            Self::PushPopReg { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "POP",
+                LoadStoreKind::Store => "PUSH",
+            },
+            Self::MultipleLoadStore { load_store, .. } => match load_store {
+                LoadStoreKind::Load => "LDMIA",
+                LoadStoreKind::Store => "STMIA",
+            },
+            Self::CondBranch { .. } => "B<cond>",
+            Self::Swi { .. } => "SWI",
+            Self::UncondBranch { .. } => "B",
+            Self::LongBranchLink { .. } => "BL",
         }
     }
 }
@@ -457,7 +619,7 @@ impl Instruction {
             } => {
                 format!("B{condition} #{immediate_offset}")
             }
-            Self::Swi => panic!("not implemented"),
+            Self::Swi { comment } => format!("SWI #0x{comment:X}"),
             Self::UncondBranch { offset } => {
                 format!("B #{offset}")
             }
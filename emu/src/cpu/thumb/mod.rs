@@ -1,4 +1,5 @@
 pub mod alu_instructions;
+pub mod encodings;
 
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_possible_wrap)]
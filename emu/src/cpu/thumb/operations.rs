@@ -1,6 +1,8 @@
 use crate::bitwise::Bits;
+use crate::bus::Bus;
 use crate::cpu::arm::alu_instruction::shift; // TODO: Move this to a more appropriate location, extract common code in "alu" module for example
 use crate::cpu::arm7tdmi::Arm7tdmi;
+use crate::cpu::call_stack::FrameKind;
 use crate::cpu::condition::Condition;
 use crate::cpu::flags::{LoadStoreKind, OperandKind, Operation, ReadWriteKind, ShiftKind};
 use crate::cpu::registers::{REG_LR, REG_PROGRAM_COUNTER, REG_SP};
@@ -11,7 +13,7 @@ use std::ops::Mul;
 
 pub const SIZE_OF_INSTRUCTION: u32 = 2;
 
-impl Arm7tdmi {
+impl<B: Bus> Arm7tdmi<B> {
     pub fn move_shifted_reg(&mut self, op: ShiftKind, offset5: u16, rs: u16, rd: u16) {
         let source = self.registers.register_at(rs.into());
         let r = shift(op, offset5.into(), source, self.cpsr.carry_flag());
@@ -85,7 +87,7 @@ impl Arm7tdmi {
                 self.registers.set_register_at(dest, sub_result.result);
                 self.cpsr.set_flags(&sub_result);
             }
-        };
+        }
     }
 
     pub fn alu_op(&mut self, op: ThumbModeAluInstruction, rs: u16, rd: u16) {
@@ -199,36 +201,52 @@ impl Arm7tdmi {
         match op {
             ThumbHighRegisterOperation::Add => {
                 let r = d_value.wrapping_add(s_value);
-                self.registers.set_register_at(reg_destination as usize, r);
-
-                if reg_destination == REG_PROGRAM_COUNTER as u16 {
-                    self.flush_pipeline();
-                }
+                self.set_hi_register(reg_destination, r);
             }
             ThumbHighRegisterOperation::Cmp => {
                 let sub_result = Self::sub_inner_op(d_value, s_value);
 
                 self.cpsr.set_flags(&sub_result);
             }
-            ThumbHighRegisterOperation::Mov => {
-                self.registers
-                    .set_register_at(reg_destination as usize, s_value);
-
-                if reg_destination == REG_PROGRAM_COUNTER as u16 {
-                    self.flush_pipeline();
-                }
-            }
+            ThumbHighRegisterOperation::Mov => self.set_hi_register(reg_destination, s_value),
             ThumbHighRegisterOperation::BxOrBlx => {
+                let next_instruction =
+                    (self.registers.program_counter() as u32).wrapping_sub(SIZE_OF_INSTRUCTION);
+                // `MOV LR, PC` followed by `BX` is how functions are called through a register.
+                let is_call = reg_source != REG_LR as u16
+                    && self.registers.register_at(REG_LR) & !1 == next_instruction;
+
                 let new_state = s_value.get_bit(0);
                 self.cpsr.set_cpu_state(new_state.into());
-                let new_pc = s_value & !1;
+                // ARM instructions are word aligned, Thumb ones half-word aligned.
+                let new_pc = if new_state {
+                    s_value & !1
+                } else {
+                    s_value & !3
+                };
                 self.registers.set_program_counter(new_pc);
 
                 self.flush_pipeline();
+
+                if is_call {
+                    self.call_stack
+                        .push(new_pc, next_instruction, FrameKind::Call);
+                }
             }
         }
     }
 
+    /// Writes the destination of ADD and MOV, a write to PC is a branch in Thumb state.
+    fn set_hi_register(&mut self, reg_destination: u16, value: u32) {
+        if reg_destination == REG_PROGRAM_COUNTER as u16 {
+            self.registers.set_program_counter(value & !1);
+            self.flush_pipeline();
+        } else {
+            self.registers
+                .set_register_at(reg_destination as usize, value);
+        }
+    }
+
     pub fn pc_relative_load(&mut self, r_destination: u16, immediate_value: u16) {
         let mut pc = self.registers.program_counter() as u32;
         // word alignment
@@ -256,21 +274,21 @@ impl Arm7tdmi {
         match (load_store, byte_word) {
             (LoadStoreKind::Store, ReadWriteKind::Byte) => {
                 let rd = (self.registers.register_at(rd) & 0xFF) as u8;
-                self.bus.write_byte(address, rd);
+                self.bus.write8(address, rd);
             }
             (LoadStoreKind::Store, ReadWriteKind::Word) => {
                 let rd = self.registers.register_at(rd);
-                self.bus.write_word(address, rd);
+                self.bus.write32(address, rd);
             }
             (LoadStoreKind::Load, ReadWriteKind::Byte) => {
-                let value = self.bus.read_byte(address);
+                let value = self.bus.read8(address);
                 self.registers.set_register_at(rd, value as u32);
             }
             (LoadStoreKind::Load, ReadWriteKind::Word) => {
                 let value = self.read_word(address);
                 self.registers.set_register_at(rd, value);
             }
-        };
+        }
     }
 
     pub fn load_store_sign_extend_byte_halfword(
@@ -292,7 +310,7 @@ impl Arm7tdmi {
                     .registers
                     .register_at(r_destination.try_into().unwrap());
 
-                self.bus.write_half_word(address, value as u16);
+                self.bus.write16(address, value as u16);
             }
             // Load halfword/sign-extended halfword
             (_, true) => {
@@ -303,8 +321,8 @@ impl Arm7tdmi {
             }
             // Load sign-extended byte
             (true, false) => {
-                let mut value = self.bus.read_byte(address) as u32;
-                value = value.sign_extended(8);
+                let mut value = self.bus.read8(address) as u32;
+                value.sign_extend(8);
 
                 self.registers
                     .set_register_at(r_destination.try_into().unwrap(), value);
@@ -331,18 +349,18 @@ impl Arm7tdmi {
         match (load_store, byte_word) {
             (LoadStoreKind::Store, ReadWriteKind::Word) => {
                 let v = self.registers.register_at(rd);
-                self.bus.write_word(address, v);
+                self.bus.write32(address, v);
             }
             (LoadStoreKind::Store, ReadWriteKind::Byte) => {
                 let v = self.registers.register_at(rd);
-                self.bus.write_byte(address, v as u8);
+                self.bus.write8(address, v as u8);
             }
             (LoadStoreKind::Load, ReadWriteKind::Word) => {
                 let v = self.read_word(address);
                 self.registers.set_register_at(rd, v);
             }
             (LoadStoreKind::Load, ReadWriteKind::Byte) => {
-                let v = self.bus.read_byte(address);
+                let v = self.bus.read8(address);
                 self.registers.set_register_at(rd, v as u32);
             }
         }
@@ -366,7 +384,7 @@ impl Arm7tdmi {
                     .set_register_at(source_destination_register as usize, value);
             }
             LoadStoreKind::Store => {
-                self.bus.write_half_word(
+                self.bus.write16(
                     address,
                     self.registers
                         .register_at(source_destination_register as usize)
@@ -382,7 +400,10 @@ impl Arm7tdmi {
         r_destination: u16,
         word8: u16,
     ) {
-        let address = self.registers.register_at(REG_SP) + (word8 as u32);
+        let address = self
+            .registers
+            .register_at(REG_SP)
+            .wrapping_add(word8 as u32);
 
         let rd = r_destination.into();
         match load_store {
@@ -393,7 +414,7 @@ impl Arm7tdmi {
             }
             LoadStoreKind::Store => {
                 self.bus
-                    .write_word(address.try_into().unwrap(), self.registers.register_at(rd));
+                    .write32(address.try_into().unwrap(), self.registers.register_at(rd));
             }
         }
     }
@@ -431,7 +452,7 @@ impl Arm7tdmi {
             LoadStoreKind::Store => {
                 if pc_lr {
                     reg_sp -= 4;
-                    self.bus.write_word(
+                    self.bus.write32(
                         reg_sp.try_into().unwrap(),
                         self.registers.register_at(REG_LR),
                     );
@@ -440,7 +461,7 @@ impl Arm7tdmi {
                 for r in (0..=7).rev() {
                     if register_list.get_bit(r) {
                         reg_sp -= 4;
-                        self.bus.write_word(
+                        self.bus.write32(
                             reg_sp.try_into().unwrap(),
                             self.registers.register_at(r.into()),
                         );
@@ -517,7 +538,7 @@ impl Arm7tdmi {
 
                         first_written = true;
 
-                        self.bus.write_word(address as usize, value);
+                        self.bus.write32(address as usize, value);
 
                         address += 4;
                     }
@@ -544,7 +565,7 @@ impl Arm7tdmi {
     }
 
     pub fn cond_branch(&mut self, condition: Condition, immediate_offset: i32) {
-        if self.cpsr.can_execute(condition) {
+        if condition.is_satisfied(&self.cpsr) {
             let pc = self.registers.program_counter() as i32;
             let new_pc = pc.wrapping_add(immediate_offset);
             self.registers.set_program_counter(new_pc as u32);
@@ -573,6 +594,9 @@ impl Arm7tdmi {
             self.registers.set_register_at(REG_LR, next_instruction | 1);
 
             self.flush_pipeline();
+
+            self.call_stack
+                .push(lr.wrapping_add(offset), next_instruction, FrameKind::Call);
         } else {
             let offset = offset << 12;
             let offset = offset.sign_extended(23);
@@ -0,0 +1,198 @@
+//! What the emulator was doing when it panicked, bundled in a zip the user can attach
+//! to an issue.
+
+use std::fmt::Write;
+
+use crate::cartridge::hash::RomHash;
+use crate::gba::Gba;
+
+/// Files of the zip, the state is left out if it can't be serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    files: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl CrashReport {
+    /// A report of the panic with `message` and `backtrace`, with the last instructions
+    /// of `gba` (if its trace was recorded) and its state.
+    #[must_use]
+    pub fn new(gba: &Gba, message: &str, backtrace: &str) -> Self {
+        let hash = RomHash::new(&gba.cpu.bus.internal_memory.rom);
+        let header = &gba.cartridge_header;
+
+        let mut report = String::new();
+        // Writing to a `String` never fails.
+        let _ = writeln!(report, "Clementine {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "Panic: {message}");
+        let _ = writeln!(report, "Game: {} ({})", header.game_title, header.game_code);
+        let _ = writeln!(
+            report,
+            "ROM: {} bytes, CRC32 {:08x}, MD5 {}, SHA-1 {}",
+            hash.size, hash.crc32, hash.md5, hash.sha1
+        );
+        let _ = writeln!(report, "PC: {:08X}", gba.cpu.registers.program_counter());
+
+        let mut trace = String::new();
+        match &gba.cpu.trace {
+            Some(entries) => {
+                for entry in entries.entries() {
+                    let _ = writeln!(trace, "{entry}");
+                }
+            }
+            None => trace.push_str("The trace was not recorded.\n"),
+        }
+
+        let state = gba.save_state_file();
+        if let Err(err) = &state {
+            let _ = writeln!(report, "The state can't be saved: {err}");
+        }
+
+        let mut files = vec![
+            ("report.txt", report.into_bytes()),
+            ("backtrace.txt", backtrace.as_bytes().to_vec()),
+            ("trace.txt", trace.into_bytes()),
+        ];
+        if let Ok(state) = state {
+            files.push(("state.clm", state));
+        }
+
+        Self { files }
+    }
+
+    /// The files as a zip, stored without compression. Stored entries are a few lines to
+    /// write, it's not worth a dependency for one archive.
+    ///
+    /// # Errors
+    /// It returns an error if the files are too big for a zip without the 64-bit extension.
+    pub fn to_zip(&self) -> Result<Vec<u8>, String> {
+        let too_big = |_| "the crash report is too big".to_string();
+
+        let mut zip = Vec::new();
+        let mut central_directory = Vec::new();
+
+        for (name, data) in &self.files {
+            let offset = u32::try_from(zip.len()).map_err(too_big)?;
+            let size = u32::try_from(data.len()).map_err(too_big)?;
+            let name_len = u16::try_from(name.len()).map_err(too_big)?;
+            let crc = crc32fast::hash(data);
+
+            // Version 1.0, no flags, stored, 1980-01-01 00:00, then crc and sizes
+            let mut common = Vec::new();
+            common.extend_from_slice(&[10, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+            common.extend_from_slice(&crc.to_le_bytes());
+            common.extend_from_slice(&size.to_le_bytes());
+            common.extend_from_slice(&size.to_le_bytes());
+            common.extend_from_slice(&name_len.to_le_bytes());
+            // No extra field
+            common.extend_from_slice(&[0, 0]);
+
+            zip.extend_from_slice(b"PK\x03\x04");
+            zip.extend_from_slice(&common);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(data);
+
+            // Made by version 2.0
+            central_directory.extend_from_slice(b"PK\x01\x02\x14\x00");
+            central_directory.extend_from_slice(&common);
+            // No comment, disk 0, no attributes
+            central_directory.extend_from_slice(&[0; 10]);
+            central_directory.extend_from_slice(&offset.to_le_bytes());
+            central_directory.extend_from_slice(name.as_bytes());
+        }
+
+        let entries = u16::try_from(self.files.len()).map_err(too_big)?;
+        let directory_size = u32::try_from(central_directory.len()).map_err(too_big)?;
+        let directory_offset = u32::try_from(zip.len()).map_err(too_big)?;
+        zip.append(&mut central_directory);
+
+        // End of central directory: a single disk, no comment
+        zip.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+        zip.extend_from_slice(&entries.to_le_bytes());
+        zip.extend_from_slice(&entries.to_le_bytes());
+        zip.extend_from_slice(&directory_size.to_le_bytes());
+        zip.extend_from_slice(&directory_offset.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+
+        Ok(zip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::debugger::trace::Trace;
+    use crate::gba::test_gba;
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([data[offset], data[offset + 1]])
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// The files of a zip read from its central directory, checking their CRC.
+    fn unzip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(&zip[end..end + 4], b"PK\x05\x06");
+
+        let mut entry = u32_at(zip, end + 16) as usize;
+        (0..u16_at(zip, end + 10))
+            .map(|_| {
+                assert_eq!(&zip[entry..entry + 4], b"PK\x01\x02");
+                let crc = u32_at(zip, entry + 16);
+                let size = u32_at(zip, entry + 20) as usize;
+                let name_len = usize::from(u16_at(zip, entry + 28));
+                let name = String::from_utf8(zip[entry + 46..entry + 46 + name_len].to_vec());
+                let local = u32_at(zip, entry + 42) as usize;
+
+                // The local header repeats the stored method, the CRC, the sizes and
+                // the name of the central directory.
+                assert_eq!(&zip[local..local + 4], b"PK\x03\x04");
+                assert_eq!(u16_at(zip, local + 8), 0);
+                assert_eq!(zip[local + 14..local + 26], zip[entry + 16..entry + 28]);
+                assert_eq!(u16_at(zip, local + 26), u16_at(zip, entry + 28));
+                assert_eq!(
+                    zip[local + 30..local + 30 + name_len],
+                    zip[entry + 46..entry + 46 + name_len]
+                );
+                let data_start = local + 30 + name_len + usize::from(u16_at(zip, local + 28));
+                let data = zip[data_start..data_start + size].to_vec();
+                assert_eq!(crc32fast::hash(&data), crc);
+                entry += 46 + name_len;
+
+                (name.unwrap(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zip_has_every_file() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.cpu.trace = Some(Trace::new(8));
+        for _ in 0..4 {
+            gba.step();
+        }
+
+        let report = CrashReport::new(&gba, "attempt to divide by zero", "0: main");
+        let files = unzip(&report.to_zip().unwrap());
+
+        let names = files
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["report.txt", "backtrace.txt", "trace.txt", "state.clm"]
+        );
+
+        let text = String::from_utf8(files[0].1.clone()).unwrap();
+        assert!(text.contains("Panic: attempt to divide by zero"));
+        assert!(text.contains("SHA-1"));
+        assert_eq!(files[1].1, b"0: main");
+        assert!(!files[2].1.is_empty());
+        assert!(Gba::state_file_thumbnail(&files[3].1).is_ok());
+    }
+}
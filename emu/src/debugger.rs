@@ -0,0 +1,12 @@
+pub mod analysis;
+pub mod annotations;
+pub mod coverage;
+pub mod divergence;
+pub mod instruction_stats;
+pub mod line_info;
+pub mod profiler;
+pub mod ram_search;
+pub mod symbols;
+pub mod timeline;
+pub mod trace;
+pub mod watch;
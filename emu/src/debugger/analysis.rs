@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The cartridge is mapped from here, it's also where it starts running.
+const ROM_START: u32 = 0x0800_0000;
+
+/// Functions and literal pools of a ROM, found following the code from the entry point.
+///
+/// It's a recursive descent: every branch is followed, `BL` targets and the addresses
+/// loaded from a literal pool and jumped to with `BX` start a new function, the words
+/// read with a PC relative `LDR` are data. Code only reached through jump tables or
+/// pointers set at runtime (eg. the interrupt handler) is not found.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CodeAnalysis {
+    /// Entry points, with `true` for the Thumb ones.
+    functions: BTreeMap<u32, bool>,
+    /// Words of the literal pools with their value.
+    literals: BTreeMap<u32, u32>,
+}
+
+/// Code still to visit, with `true` for Thumb.
+type Queue = Vec<(u32, bool)>;
+
+/// Registers loaded from a literal pool, reset at the start of every block.
+type Loaded = [Option<u32>; 16];
+
+impl CodeAnalysis {
+    #[must_use]
+    pub fn analyze(rom: &[u8]) -> Self {
+        let mut analysis = Self::default();
+        let mut visited = BTreeSet::new();
+        let mut queue = vec![(ROM_START, false)];
+        analysis.functions.insert(ROM_START, false);
+
+        while let Some((address, is_thumb)) = queue.pop() {
+            let mut loaded = [None; 16];
+            let mut address = address;
+
+            // Follows the block until it jumps away or leaves the ROM.
+            while read(rom, address, if is_thumb { 2 } else { 4 }).is_some()
+                && !analysis.literals.contains_key(&(address & !3))
+                && visited.insert(address)
+            {
+                let next = if is_thumb {
+                    analysis.step_thumb(rom, address, &mut loaded, &mut queue)
+                } else {
+                    analysis.step_arm(rom, address, &mut loaded, &mut queue)
+                };
+
+                match next {
+                    Some(next) => address = next,
+                    None => break,
+                }
+            }
+        }
+
+        analysis
+    }
+
+    /// Entry points in order, with `true` for the Thumb ones.
+    pub fn functions(&self) -> impl Iterator<Item = (u32, bool)> + '_ {
+        self.functions
+            .iter()
+            .map(|(&address, &is_thumb)| (address, is_thumb))
+    }
+
+    /// `Some(true)` if a Thumb function starts at `address`, `Some(false)` for ARM.
+    #[must_use]
+    pub fn function_at(&self, address: u32) -> Option<bool> {
+        self.functions.get(&address).copied()
+    }
+
+    /// Value of the literal pool word `address` is in.
+    #[must_use]
+    pub fn literal_at(&self, address: u32) -> Option<u32> {
+        self.literals.get(&(address & !3)).copied()
+    }
+
+    fn add_function(&mut self, address: u32, is_thumb: bool, queue: &mut Queue) {
+        if self.functions.insert(address, is_thumb).is_none() {
+            queue.push((address, is_thumb));
+        }
+    }
+
+    /// A jump to the address in a register, `BX` picks the instruction set from bit 0.
+    fn add_jump(&mut self, value: Option<u32>, queue: &mut Queue) {
+        if let Some(value) = value {
+            self.add_function(value & !1, value & 1 == 1, queue);
+        }
+    }
+
+    fn load_literal(&mut self, rom: &[u8], address: u32) -> Option<u32> {
+        let value = read(rom, address, 4)?;
+        self.literals.insert(address, value);
+
+        Some(value)
+    }
+
+    /// It returns the address of the next instruction, `None` at the end of the block.
+    fn step_arm(
+        &mut self,
+        rom: &[u8],
+        address: u32,
+        loaded: &mut Loaded,
+        queue: &mut Queue,
+    ) -> Option<u32> {
+        let opcode = read(rom, address, 4)?;
+        let is_always = opcode >> 28 == 0xE;
+        let rd = ((opcode >> 12) & 0xF) as usize;
+        let next = address.wrapping_add(4);
+
+        if opcode & 0x0E00_0000 == 0x0A00_0000 {
+            // B and BL
+            #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+            let offset = (((opcode << 8) as i32) >> 6) as u32;
+            let target = address.wrapping_add(8).wrapping_add(offset);
+
+            if opcode & (1 << 24) != 0 {
+                self.add_function(target, false, queue);
+            } else {
+                queue.push((target, false));
+                if is_always {
+                    return None;
+                }
+            }
+        } else if opcode & 0x0FFF_FFF0 == 0x012F_FF10 {
+            // BX
+            self.add_jump(loaded[(opcode & 0xF) as usize], queue);
+            if is_always {
+                return None;
+            }
+        } else if opcode & 0x0F7F_0000 == 0x051F_0000 {
+            // LDR Rd, [PC, #offset]
+            let offset = opcode & 0xFFF;
+            let base = address.wrapping_add(8);
+            let literal = if opcode & (1 << 23) != 0 {
+                base.wrapping_add(offset)
+            } else {
+                base.wrapping_sub(offset)
+            };
+            let value = self.load_literal(rom, literal);
+
+            if rd == 15 {
+                self.add_jump(value, queue);
+                if is_always {
+                    return None;
+                }
+            }
+            loaded[rd] = value;
+        } else if opcode & 0x0E10_8000 == 0x0810_8000 || opcode & 0x0C00_F000 == 0x0000_F000 {
+            // LDM with PC in the list and data processing writing PC, eg. returns.
+            if is_always {
+                return None;
+            }
+        } else if opcode & 0x0C00_0000 == 0 {
+            loaded[rd] = None;
+        }
+
+        Some(next)
+    }
+
+    /// It returns the address of the next instruction, `None` at the end of the block.
+    fn step_thumb(
+        &mut self,
+        rom: &[u8],
+        address: u32,
+        loaded: &mut Loaded,
+        queue: &mut Queue,
+    ) -> Option<u32> {
+        let opcode = read(rom, address, 2)?;
+        let next = address.wrapping_add(2);
+
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        let sign_extend =
+            |value: u32, bits: u32| (((value << (32 - bits)) as i32) >> (32 - bits)) as u32;
+
+        if opcode & 0xF800 == 0xF000 {
+            // BL, two halfwords with the high and low part of the offset.
+            let low = read(rom, next, 2)?;
+            if low & 0xF800 == 0xF800 {
+                let offset = (sign_extend(opcode & 0x7FF, 11) << 12) | ((low & 0x7FF) << 1);
+                self.add_function(address.wrapping_add(4).wrapping_add(offset), true, queue);
+                return Some(address.wrapping_add(4));
+            }
+        } else if opcode & 0xF000 == 0xD000 && opcode & 0x0F00 < 0x0E00 {
+            // Conditional B, 0xDF is SWI.
+            let offset = sign_extend(opcode & 0xFF, 8) << 1;
+            queue.push((address.wrapping_add(4).wrapping_add(offset), true));
+        } else if opcode & 0xF800 == 0xE000 {
+            // B
+            let offset = sign_extend(opcode & 0x7FF, 11) << 1;
+            queue.push((address.wrapping_add(4).wrapping_add(offset), true));
+            return None;
+        } else if opcode & 0xF800 == 0x4800 {
+            // LDR Rd, [PC, #offset]
+            let literal = (address.wrapping_add(4) & !3).wrapping_add((opcode & 0xFF) << 2);
+            loaded[((opcode >> 8) & 7) as usize] = self.load_literal(rom, literal);
+        } else if opcode & 0xFF80 == 0x4700 {
+            // BX
+            self.add_jump(loaded[((opcode >> 3) & 0xF) as usize], queue);
+            return None;
+        } else if opcode & 0xFF00 == 0xBD00
+            || opcode & 0xFF87 == 0x4687
+            || opcode & 0xFF87 == 0x4487
+        {
+            // POP with PC, MOV PC and ADD PC (eg. jump tables).
+            return None;
+        }
+
+        Some(next)
+    }
+}
+
+/// Little endian value of `size` bytes at `address` of the ROM.
+fn read(rom: &[u8], address: u32, size: usize) -> Option<u32> {
+    let offset = address.checked_sub(ROM_START)? as usize;
+    let bytes = rom.get(offset..offset.checked_add(size)?)?;
+
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | u32::from(byte)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rom(code: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut rom = vec![0; 0x100];
+        for (offset, bytes) in code {
+            let offset = *offset as usize;
+            rom[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+
+        rom
+    }
+
+    #[test]
+    fn functions_and_literal_pools() {
+        let rom = rom(&[
+            // B 0x080000C0
+            (0x00, &0xEA00_002E_u32.to_le_bytes()),
+            // LDR R0, [PC, #4] and BX R0
+            (0xC0, &0xE59F_0004_u32.to_le_bytes()),
+            (0xC4, &0xE12F_FF10_u32.to_le_bytes()),
+            // A Thumb function at 0x080000D0
+            (0xCC, &0x0800_00D1_u32.to_le_bytes()),
+            // BL 0x080000E0, LDR R1, [PC, #4] and POP {PC}
+            (0xD0, &[0x00, 0xF0, 0x06, 0xF8, 0x01, 0x49, 0x00, 0xBD]),
+            (0xDC, &0x1234_5678_u32.to_le_bytes()),
+            // BX LR
+            (0xE0, &[0x70, 0x47]),
+        ]);
+
+        let analysis = CodeAnalysis::analyze(&rom);
+
+        assert_eq!(
+            analysis.functions().collect::<Vec<_>>(),
+            vec![
+                (0x0800_0000, false),
+                (0x0800_00D0, true),
+                (0x0800_00E0, true)
+            ]
+        );
+        assert_eq!(analysis.function_at(0x0800_00C0), None);
+        assert_eq!(analysis.literal_at(0x0800_00CE), Some(0x0800_00D1));
+        assert_eq!(analysis.literal_at(0x0800_00DC), Some(0x1234_5678));
+        assert_eq!(analysis.literal_at(0x0800_00D8), None);
+    }
+
+    #[test]
+    fn conditional_branches() {
+        let rom = rom(&[
+            // BEQ 0x08000010, then MOV PC, LR
+            (0x00, &0x0A00_0002_u32.to_le_bytes()),
+            (0x04, &0xE1A0_F00E_u32.to_le_bytes()),
+            // BL 0x08000020 and BX LR
+            (0x10, &0xEB00_0002_u32.to_le_bytes()),
+            (0x14, &0xE12F_FF1E_u32.to_le_bytes()),
+            // BX LR
+            (0x20, &0xE12F_FF1E_u32.to_le_bytes()),
+        ]);
+
+        let analysis = CodeAnalysis::analyze(&rom);
+
+        assert_eq!(
+            analysis.functions().collect::<Vec<_>>(),
+            vec![(0x0800_0000, false), (0x0800_0020, false)]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Extension of the file next to the ROM the annotations are saved in.
+pub const EXTENSION: &str = "notes";
+
+/// A label and a comment given by the user to an address, either can be empty.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub label: String,
+    pub comment: String,
+}
+
+/// Labels and comments added to the disassembly while reversing a game.
+///
+/// They're saved as text, one address per line: `08000120 main_loop ; waits for VBlank`,
+/// the label is a single word and the comment follows the `;`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Annotations {
+    annotations: BTreeMap<u32, Annotation>,
+}
+
+impl Annotations {
+    /// Lines starting with `#` and empty lines are skipped.
+    ///
+    /// # Errors
+    /// It returns an error with the line number if a line is not `ADDRESS [LABEL] [; COMMENT]`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut annotations = Self::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = || {
+                format!(
+                    "Line {}: expected `ADDRESS [LABEL] [; COMMENT]`",
+                    number + 1
+                )
+            };
+            let (fields, comment) = line.split_once(';').unwrap_or((line, ""));
+            let mut fields = fields.split_whitespace();
+
+            let address = fields
+                .next()
+                .and_then(|address| u32::from_str_radix(address, 16).ok())
+                .ok_or_else(error)?;
+            let label = fields.next().unwrap_or_default();
+            if fields.next().is_some() {
+                return Err(error());
+            }
+
+            annotations.set(address, label, comment.trim());
+        }
+
+        Ok(annotations)
+    }
+
+    /// The annotations in the format read by [`Self::parse`].
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (address, annotation) in &self.annotations {
+            let mut line = format!("{address:08X}");
+            if !annotation.label.is_empty() {
+                write!(line, " {}", annotation.label).unwrap();
+            }
+            if !annotation.comment.is_empty() {
+                write!(line, " ; {}", annotation.comment).unwrap();
+            }
+            writeln!(text, "{line}").unwrap();
+        }
+
+        text
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, address: u32) -> Option<&Annotation> {
+        self.annotations.get(&address)
+    }
+
+    /// Non empty label of `address`.
+    #[must_use]
+    pub fn label(&self, address: u32) -> Option<&str> {
+        self.get(address)
+            .map(|annotation| annotation.label.as_str())
+            .filter(|label| !label.is_empty())
+    }
+
+    /// Non empty comment of `address`.
+    #[must_use]
+    pub fn comment(&self, address: u32) -> Option<&str> {
+        self.get(address)
+            .map(|annotation| annotation.comment.as_str())
+            .filter(|comment| !comment.is_empty())
+    }
+
+    #[must_use]
+    pub fn address_of(&self, label: &str) -> Option<u32> {
+        self.annotations
+            .iter()
+            .find(|(_, annotation)| annotation.label == label)
+            .map(|(&address, _)| address)
+    }
+
+    /// Annotated addresses in order, the bookmarks of the disassembly.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Annotation)> {
+        self.annotations
+            .iter()
+            .map(|(&address, annotation)| (address, annotation))
+    }
+
+    /// Whitespace in the label is replaced by `_` to keep it a single word,
+    /// the annotation is removed when both the label and the comment are empty.
+    pub fn set(&mut self, address: u32, label: &str, comment: &str) {
+        let label = label.split_whitespace().collect::<Vec<_>>().join("_");
+        // A `;` in the comment is fine, but a newline would start a new line in the file.
+        let comment = comment.lines().collect::<Vec<_>>().join(" ");
+
+        if label.is_empty() && comment.trim().is_empty() {
+            self.annotations.remove(&address);
+        } else {
+            self.annotations.insert(
+                address,
+                Annotation {
+                    label,
+                    comment: comment.trim().to_string(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_and_save() {
+        let text = "# notes\n\
+                    08000120 main_loop ; waits for VBlank\n\
+                    \n\
+                    3001a40 player_x\n\
+                    080001F0 ; copies; the palette\n";
+        let annotations = Annotations::parse(text).unwrap();
+
+        assert_eq!(annotations.label(0x0800_0120), Some("main_loop"));
+        assert_eq!(annotations.comment(0x0800_0120), Some("waits for VBlank"));
+        assert_eq!(annotations.comment(0x0300_1A40), None);
+        assert_eq!(annotations.label(0x0800_01F0), None);
+        assert_eq!(
+            annotations.comment(0x0800_01F0),
+            Some("copies; the palette")
+        );
+        assert_eq!(annotations.address_of("player_x"), Some(0x0300_1A40));
+
+        assert_eq!(
+            annotations.to_text(),
+            "03001A40 player_x\n\
+             08000120 main_loop ; waits for VBlank\n\
+             080001F0 ; copies; the palette\n"
+        );
+        assert_eq!(Annotations::parse(&annotations.to_text()), Ok(annotations));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in ["main_loop", "08000120 main loop", "zz ; comment"] {
+            assert!(Annotations::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn set() {
+        let mut annotations = Annotations::default();
+
+        annotations.set(0x0800_0000, " start game ", "line\nbreak");
+        assert_eq!(
+            annotations.get(0x0800_0000),
+            Some(&Annotation {
+                label: "start_game".to_string(),
+                comment: "line break".to_string(),
+            })
+        );
+
+        annotations.set(0x0800_0000, "", " ");
+        assert!(annotations.is_empty());
+    }
+}
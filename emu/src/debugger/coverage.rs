@@ -0,0 +1,185 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The byte has been executed as an instruction.
+pub const EXECUTED: u8 = 1 << 0;
+/// The byte has been read as data (by the CPU or a DMA).
+pub const READ: u8 = 1 << 1;
+
+/// ROM is mapped three times, with different wait states.
+const ROM_MIRRORS: Range<usize> = 0x0800_0000..0x0E00_0000;
+const ROM_MIRROR_SIZE: usize = 0x0200_0000;
+
+/// Totals of [`Coverage`], in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageStats {
+    pub executed: usize,
+    pub read: usize,
+    pub total: usize,
+}
+
+/// Which bytes of the ROM have been executed or read as data.
+///
+/// The map has a byte of flags for each ROM byte, exported as is it's a
+/// code/data log like the ones of other emulators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    flags: Vec<u8>,
+}
+
+impl Coverage {
+    #[must_use]
+    pub fn new(rom_size: usize) -> Self {
+        Self {
+            flags: vec![0; rom_size],
+        }
+    }
+
+    /// Flags of every ROM byte, [`EXECUTED`] and [`READ`].
+    #[must_use]
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+
+    pub fn clear(&mut self) {
+        self.flags.fill(0);
+    }
+
+    pub(crate) fn mark_executed(&mut self, address: usize, size: usize) {
+        self.mark(address, size, EXECUTED);
+    }
+
+    pub(crate) fn mark_read(&mut self, address: usize, size: usize) {
+        self.mark(address, size, READ);
+    }
+
+    fn mark(&mut self, address: usize, size: usize, flag: u8) {
+        if !ROM_MIRRORS.contains(&address) {
+            return;
+        }
+
+        let offset = (address - ROM_MIRRORS.start) % ROM_MIRROR_SIZE;
+        let end = (offset + size).min(self.flags.len());
+        if let Some(flags) = self.flags.get_mut(offset..end) {
+            for flags in flags {
+                *flags |= flag;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> CoverageStats {
+        self.flags.iter().fold(
+            CoverageStats {
+                total: self.flags.len(),
+                ..CoverageStats::default()
+            },
+            |mut stats, &flags| {
+                stats.executed += usize::from(flags & EXECUTED != 0);
+                stats.read += usize::from(flags & READ != 0);
+                stats
+            },
+        )
+    }
+
+    /// Consecutive ROM offsets with the same flags, the untouched ones are skipped.
+    #[must_use]
+    pub fn ranges(&self) -> Vec<(Range<usize>, u8)> {
+        let mut ranges: Vec<(Range<usize>, u8)> = Vec::new();
+
+        for (offset, &flags) in self.flags.iter().enumerate() {
+            if flags == 0 {
+                continue;
+            }
+
+            match ranges.last_mut() {
+                Some((range, last_flags)) if range.end == offset && *last_flags == flags => {
+                    range.end += 1;
+                }
+                _ => ranges.push((offset..offset + 1, flags)),
+            }
+        }
+
+        ranges
+    }
+
+    /// Text listing of [`Self::ranges`], one `START-END code|data|code+data` line
+    /// each with inclusive bus addresses.
+    #[must_use]
+    pub fn ranges_listing(&self) -> String {
+        let mut listing = String::new();
+
+        for (range, flags) in self.ranges() {
+            let kind = match flags {
+                EXECUTED => "code",
+                READ => "data",
+                _ => "code+data",
+            };
+
+            // Writing to a `String` can't fail.
+            let _ = writeln!(
+                listing,
+                "{:08X}-{:08X} {kind}",
+                ROM_MIRRORS.start + range.start,
+                ROM_MIRRORS.start + range.end - 1
+            );
+        }
+
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn mark() {
+        let mut coverage = Coverage::new(0x100);
+
+        coverage.mark_executed(0x0800_0000, 4);
+        coverage.mark_read(0x0800_0002, 2);
+        // Mirror of 0x08000010
+        coverage.mark_read(0x0A00_0010, 4);
+        // Outside of the ROM
+        coverage.mark_read(0x0300_0000, 4);
+        coverage.mark_read(0x0800_00FE, 4);
+
+        assert_eq!(&coverage.flags()[0..4], &[EXECUTED, EXECUTED, 3, 3]);
+        assert_eq!(&coverage.flags()[0x10..0x14], &[READ; 4]);
+        assert_eq!(
+            coverage.stats(),
+            CoverageStats {
+                executed: 4,
+                read: 8,
+                total: 0x100
+            }
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let mut coverage = Coverage::new(0x100);
+
+        coverage.mark_executed(0x0800_0000, 8);
+        coverage.mark_read(0x0800_0004, 4);
+        coverage.mark_read(0x0800_0020, 2);
+
+        assert_eq!(
+            coverage.ranges(),
+            vec![
+                (0..4, EXECUTED),
+                (4..8, EXECUTED | READ),
+                (0x20..0x22, READ)
+            ]
+        );
+        assert_eq!(
+            coverage.ranges_listing(),
+            "08000000-08000003 code\n08000004-08000007 code+data\n08000020-08000021 data\n"
+        );
+
+        coverage.clear();
+        assert!(coverage.ranges().is_empty());
+    }
+}
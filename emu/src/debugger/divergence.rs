@@ -0,0 +1,103 @@
+use std::fmt;
+
+/// Two runs from the same save state ended in different states: some emulation
+/// state is not saved, rollback netplay and rewind would desync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// CPU cycle the runs started from.
+    pub cycle: u128,
+    /// Frames run from the state.
+    pub frames: u64,
+    /// Part of the state that differs (eg. `lcd`), `cpu` if it's none of the bus ones.
+    pub part: &'static str,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` diverged running {} frames from cycle {}",
+            self.part, self.frames, self.cycle
+        )
+    }
+}
+
+/// Debug mode running a divergence check every `interval` frames, see
+/// `Gba::check_divergence`. It slows down the emulation a lot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivergenceCheck {
+    pub interval: u64,
+    /// Frames run twice by each check.
+    pub frames: u64,
+    frames_left: u64,
+    /// Checks run so far.
+    pub checks: u64,
+    /// Every divergence found, the checks go on after one.
+    pub divergences: Vec<Divergence>,
+}
+
+impl DivergenceCheck {
+    #[must_use]
+    pub const fn new(interval: u64, frames: u64) -> Self {
+        Self {
+            interval,
+            frames,
+            frames_left: interval,
+            checks: 0,
+            divergences: Vec::new(),
+        }
+    }
+
+    /// Counts a frame, it returns `true` when it's time for a check.
+    pub(crate) const fn frame_completed(&mut self) -> bool {
+        self.frames_left = self.frames_left.saturating_sub(1);
+        if self.frames_left > 0 {
+            return false;
+        }
+
+        self.frames_left = self.interval;
+        self.checks += 1;
+
+        true
+    }
+}
+
+/// Name of the first part that differs between two states split in parts.
+#[must_use]
+pub(crate) fn first_difference(
+    first: &[(&'static str, Vec<u8>)],
+    second: &[(&'static str, Vec<u8>)],
+) -> Option<&'static str> {
+    first
+        .iter()
+        .zip(second)
+        .find(|((_, first), (_, second))| first != second)
+        .map(|((name, _), _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn interval() {
+        let mut check = DivergenceCheck::new(3, 1);
+
+        let checks: Vec<_> = (0..7).map(|_| check.frame_completed()).collect();
+
+        assert_eq!(checks, vec![false, false, true, false, false, true, false]);
+        assert_eq!(check.checks, 2);
+    }
+
+    #[test]
+    fn difference() {
+        let first = vec![("lcd", vec![1, 2]), ("sound", vec![3])];
+        let mut second = first.clone();
+
+        assert_eq!(first_difference(&first, &second), None);
+
+        second[1].1[0] = 4;
+        assert_eq!(first_difference(&first, &second), Some("sound"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::cpu::{ARM_KINDS, THUMB_KINDS};
+
+/// Times an instruction has been executed, `kind` is its operation (eg. `MOV`,
+/// `LDR [SP, #imm]`) with the data processing ones split by ALU operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionCount {
+    pub is_thumb: bool,
+    pub kind: &'static str,
+    pub count: u64,
+}
+
+/// Instructions executed by the CPU by kind, to see what a game uses and which
+/// decoder paths it never takes.
+#[derive(Debug, Default, Clone)]
+pub struct InstructionStats {
+    arm: HashMap<&'static str, u64>,
+    thumb: HashMap<&'static str, u64>,
+}
+
+impl InstructionStats {
+    pub(crate) fn record(&mut self, is_thumb: bool, kind: &'static str) {
+        let counts = if is_thumb {
+            &mut self.thumb
+        } else {
+            &mut self.arm
+        };
+
+        *counts.entry(kind).or_default() += 1;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Instructions executed so far.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.arm.values().chain(self.thumb.values()).sum()
+    }
+
+    /// Every kind executed at least once, the most used first.
+    #[must_use]
+    pub fn executed(&self) -> Vec<InstructionCount> {
+        let mut counts = self
+            .arm
+            .iter()
+            .map(|(&kind, &count)| (false, kind, count))
+            .chain(self.thumb.iter().map(|(&kind, &count)| (true, kind, count)))
+            .map(|(is_thumb, kind, count)| InstructionCount {
+                is_thumb,
+                kind,
+                count,
+            })
+            .collect::<Vec<_>>();
+
+        counts.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| (a.is_thumb, a.kind).cmp(&(b.is_thumb, b.kind)))
+        });
+
+        counts
+    }
+
+    /// Kinds the decoder knows but never executed, ARM first, in the order of the formats.
+    #[must_use]
+    pub fn never_executed(&self) -> Vec<(bool, &'static str)> {
+        let arm = ARM_KINDS
+            .iter()
+            .filter(|kind| !self.arm.contains_key(*kind))
+            .map(|&kind| (false, kind));
+        let thumb = THUMB_KINDS
+            .iter()
+            .filter(|kind| !self.thumb.contains_key(*kind))
+            .map(|&kind| (true, kind));
+
+        arm.chain(thumb).collect()
+    }
+
+    /// The counts as plain text, to paste in a bug report.
+    #[must_use]
+    pub fn report(&self) -> String {
+        let set = |is_thumb| if is_thumb { "Thumb" } else { "ARM" };
+        let mut report = format!("Executed instructions: {}\n", self.total());
+
+        for count in self.executed() {
+            writeln!(
+                report,
+                "{:<5} {:<20} {}",
+                set(count.is_thumb),
+                count.kind,
+                count.count
+            )
+            .unwrap();
+        }
+
+        report.push_str("\nNever executed:\n");
+        for (is_thumb, kind) in self.never_executed() {
+            writeln!(report, "{:<5} {kind}", set(is_thumb)).unwrap();
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::arm7tdmi::Arm7tdmi;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn counts_by_kind() {
+        let mut stats = InstructionStats::default();
+        stats.record(false, "MOV");
+        stats.record(true, "MOV #imm");
+        stats.record(true, "MOV #imm");
+
+        assert_eq!(stats.total(), 3);
+        assert_eq!(
+            stats.executed(),
+            vec![
+                InstructionCount {
+                    is_thumb: true,
+                    kind: "MOV #imm",
+                    count: 2
+                },
+                InstructionCount {
+                    is_thumb: false,
+                    kind: "MOV",
+                    count: 1
+                },
+            ]
+        );
+
+        let never_executed = stats.never_executed();
+        assert_eq!(
+            never_executed.len(),
+            ARM_KINDS.len() + THUMB_KINDS.len() - 2
+        );
+        assert!(!never_executed.contains(&(false, "MOV")));
+        assert!(never_executed.contains(&(false, "ADD")));
+        assert!(stats.report().starts_with("Executed instructions: 3\n"));
+
+        stats.clear();
+        assert_eq!(stats.total(), 0);
+    }
+
+    #[test]
+    fn records_executed_instructions() {
+        let mut cpu = Arm7tdmi::default();
+        cpu.instruction_stats = Some(InstructionStats::default());
+
+        // MOV R0, #1 and, not executed, MOVEQ R0, #2
+        cpu.execute_arm(Arm7tdmi::decode(0xE3A0_0001_u32));
+        cpu.execute_arm(Arm7tdmi::decode(0x03A0_0002_u32));
+        // LDR R0, [SP, #4]
+        cpu.execute_thumb(Arm7tdmi::decode(0x9801_u16));
+
+        let stats = cpu.instruction_stats.unwrap();
+        assert_eq!(
+            stats
+                .executed()
+                .iter()
+                .map(|count| (count.is_thumb, count.kind, count.count))
+                .collect::<Vec<_>>(),
+            vec![(false, "MOV", 1), (true, "LDR [SP, #imm]", 1)]
+        );
+    }
+
+    #[test]
+    fn kinds_are_unique() {
+        for kinds in [&ARM_KINDS[..], &THUMB_KINDS[..]] {
+            let mut sorted = kinds.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+
+            assert_eq!(sorted.len(), kinds.len());
+        }
+    }
+}
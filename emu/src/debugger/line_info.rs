@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use object::{Object, ObjectSection};
+
+/// Line of a source file, `file` is the index in [`LineInfo::files`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub file: usize,
+    pub line: u32,
+}
+
+/// Code from `address` up to the next row comes from `location`, `None` after
+/// the end of a sequence (eg. padding between functions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Row {
+    address: u32,
+    location: Option<Location>,
+}
+
+/// Address to source line table, from the DWARF `.debug_line` section of an ELF.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineInfo {
+    files: Vec<String>,
+    /// Sorted by address.
+    rows: Vec<Row>,
+}
+
+impl LineInfo {
+    /// Reads the line programs of every compilation unit of `elf`.
+    /// An ELF built without debug info gives an empty table.
+    ///
+    /// # Errors
+    /// It returns an error if `elf` is not an ELF or its DWARF is not valid.
+    pub fn parse(elf: &[u8]) -> Result<Self, String> {
+        let file = object::File::parse(elf).map_err(|err| err.to_string())?;
+
+        parse_dwarf(&file).map_err(|err| format!("Invalid DWARF: {err}"))
+    }
+
+    fn from_rows(files: Vec<String>, mut rows: Vec<Row>) -> Self {
+        // Where a sequence ends and another one starts the latter wins.
+        rows.sort_by_key(|row| (row.address, row.location.is_none()));
+        rows.dedup_by_key(|row| row.address);
+
+        Self { files, rows }
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Paths of the source files, as written by the compiler.
+    #[must_use]
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Source line `address` has been compiled from.
+    #[must_use]
+    pub fn location(&self, address: u32) -> Option<Location> {
+        let index = self.rows.partition_point(|row| row.address <= address);
+
+        self.rows[index.checked_sub(1)?].location
+    }
+
+    /// Start of the code of `location`, a line can be split in more parts (eg. loops).
+    #[must_use]
+    pub fn addresses(&self, location: Location) -> Vec<u32> {
+        self.rows
+            .iter()
+            .filter(|row| row.location == Some(location))
+            .map(|row| row.address)
+            .collect()
+    }
+}
+
+fn parse_dwarf(file: &object::File) -> Result<LineInfo, gimli::Error> {
+    let load_section = |id: gimli::SectionId| -> Result<Cow<[u8]>, gimli::Error> {
+        Ok(file
+            .section_by_name(id.name())
+            .and_then(|section| section.data().ok())
+            .map_or(Cow::Borrowed(&[][..]), Cow::Borrowed))
+    };
+    let sections = gimli::DwarfSections::load(load_section)?;
+    let dwarf = sections.borrow(|section| gimli::EndianSlice::new(section, gimli::LittleEndian));
+
+    let mut files = Vec::new();
+    let mut file_indices = HashMap::new();
+    let mut rows = Vec::new();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let Some(program) = unit.line_program.clone() else {
+            continue;
+        };
+
+        let mut program_rows = program.rows();
+        while let Some((header, row)) = program_rows.next_row()? {
+            let Ok(address) = u32::try_from(row.address()) else {
+                continue;
+            };
+
+            if row.end_sequence() {
+                rows.push(Row {
+                    address,
+                    location: None,
+                });
+                continue;
+            }
+
+            let (Some(file), Some(line)) = (row.file(header), row.line()) else {
+                continue;
+            };
+
+            let mut path = PathBuf::new();
+            if let Some(comp_dir) = &unit.comp_dir {
+                path.push(comp_dir.to_string_lossy().as_ref());
+            }
+            // Index 0 is the compilation directory itself in DWARF 5.
+            if file.directory_index() != 0 {
+                if let Some(directory) = file.directory(header) {
+                    path.push(
+                        dwarf
+                            .attr_string(&unit, directory)?
+                            .to_string_lossy()
+                            .as_ref(),
+                    );
+                }
+            }
+            path.push(
+                dwarf
+                    .attr_string(&unit, file.path_name())?
+                    .to_string_lossy()
+                    .as_ref(),
+            );
+
+            let path = path.to_string_lossy().into_owned();
+            let file = *file_indices.entry(path).or_insert_with_key(|path| {
+                files.push(path.clone());
+                files.len() - 1
+            });
+
+            rows.push(Row {
+                address,
+                location: Some(Location {
+                    file,
+                    line: u32::try_from(line.get()).unwrap_or(u32::MAX),
+                }),
+            });
+        }
+    }
+
+    Ok(LineInfo::from_rows(files, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const fn row(address: u32, line: Option<u32>) -> Row {
+        Row {
+            address,
+            location: match line {
+                Some(line) => Some(Location { file: 0, line }),
+                None => None,
+            },
+        }
+    }
+
+    fn line_info() -> LineInfo {
+        LineInfo::from_rows(
+            vec!["main.c".to_string()],
+            vec![
+                row(0x0800_0100, Some(3)),
+                row(0x0800_0104, Some(4)),
+                row(0x0800_010C, Some(3)),
+                row(0x0800_0110, None),
+                // The next function starts where the previous one ends.
+                row(0x0800_0110, Some(10)),
+                row(0x0800_0118, None),
+            ],
+        )
+    }
+
+    #[test]
+    fn location() {
+        let line_info = line_info();
+
+        assert_eq!(line_info.location(0x0800_00FE), None);
+        assert_eq!(line_info.location(0x0800_0100).unwrap().line, 3);
+        assert_eq!(line_info.location(0x0800_0106).unwrap().line, 4);
+        assert_eq!(line_info.location(0x0800_010E).unwrap().line, 3);
+        assert_eq!(line_info.location(0x0800_0112).unwrap().line, 10);
+        assert_eq!(line_info.location(0x0800_0118), None);
+    }
+
+    #[test]
+    fn addresses() {
+        let line_info = line_info();
+
+        assert_eq!(
+            line_info.addresses(Location { file: 0, line: 3 }),
+            vec![0x0800_0100, 0x0800_010C]
+        );
+        assert!(line_info
+            .addresses(Location { file: 0, line: 5 })
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_not_elf() {
+        assert!(LineInfo::parse(b"08000000 main").is_err());
+    }
+}
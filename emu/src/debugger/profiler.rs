@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::cpu::call_stack::CallStack;
+
+/// Cycles spent in a function, `function` is `None` for the code outside of
+/// every known call (eg. the main loop before the first `BL`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub function: Option<u32>,
+    /// Times the function has been called.
+    pub calls: u64,
+    /// Cycles spent in the function itself.
+    pub self_cycles: u64,
+    /// Cycles spent in the function and in the ones it called.
+    pub total_cycles: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    calls: u64,
+    self_cycles: u64,
+    total_cycles: u64,
+    /// Last cycle added to `total_cycles`, a recursive function is on the
+    /// stack more than once but it's counted once.
+    last_cycle: u64,
+}
+
+/// Attributes every emulated cycle to the functions on the [`CallStack`].
+#[derive(Debug, Default, Clone)]
+pub struct Profiler {
+    functions: HashMap<Option<u32>, Entry>,
+    cycles: u64,
+    /// Frames on the call stack at the previous cycle, to count the calls.
+    last_depth: usize,
+}
+
+impl Profiler {
+    /// Cycles recorded so far.
+    #[must_use]
+    pub const fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn record(&mut self, call_stack: &CallStack) {
+        self.cycles += 1;
+        let cycle = self.cycles;
+        let frames = call_stack.frames();
+
+        let functions =
+            std::iter::once(None).chain(frames.iter().map(|frame| Some(frame.function)));
+        for (depth, function) in functions.enumerate() {
+            let entry = self.functions.entry(function).or_insert(Entry {
+                calls: 0,
+                self_cycles: 0,
+                total_cycles: 0,
+                last_cycle: 0,
+            });
+
+            if depth > self.last_depth {
+                entry.calls += 1;
+            }
+            if depth == frames.len() {
+                entry.self_cycles += 1;
+            }
+            if entry.last_cycle != cycle {
+                entry.total_cycles += 1;
+                entry.last_cycle = cycle;
+            }
+        }
+
+        self.last_depth = frames.len();
+    }
+
+    /// Every function seen so far, the most expensive ones first.
+    #[must_use]
+    pub fn report(&self) -> Vec<FunctionProfile> {
+        let mut report: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(&function, entry)| FunctionProfile {
+                function,
+                calls: entry.calls,
+                self_cycles: entry.self_cycles,
+                total_cycles: entry.total_cycles,
+            })
+            .collect();
+
+        report.sort_by(|a, b| {
+            b.self_cycles
+                .cmp(&a.self_cycles)
+                .then_with(|| a.function.cmp(&b.function))
+        });
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::call_stack::FrameKind;
+    use pretty_assertions::assert_eq;
+
+    fn profile(
+        function: Option<u32>,
+        calls: u64,
+        self_cycles: u64,
+        total_cycles: u64,
+    ) -> FunctionProfile {
+        FunctionProfile {
+            function,
+            calls,
+            self_cycles,
+            total_cycles,
+        }
+    }
+
+    #[test]
+    fn record() {
+        let mut profiler = Profiler::default();
+        let mut call_stack = CallStack::default();
+
+        profiler.record(&call_stack);
+        call_stack.push(0x0800_1000, 0x0800_0104, FrameKind::Call);
+        profiler.record(&call_stack);
+        profiler.record(&call_stack);
+        // Recursive call
+        call_stack.push(0x0800_1000, 0x0800_1010, FrameKind::Call);
+        profiler.record(&call_stack);
+        call_stack.push(0x0800_2000, 0x0800_1020, FrameKind::Call);
+        profiler.record(&call_stack);
+        call_stack.jumped_to(0x0800_0104);
+        profiler.record(&call_stack);
+
+        assert_eq!(profiler.cycles(), 6);
+        assert_eq!(
+            profiler.report(),
+            vec![
+                profile(Some(0x0800_1000), 2, 3, 4),
+                profile(None, 0, 2, 6),
+                profile(Some(0x0800_2000), 1, 1, 1),
+            ]
+        );
+
+        profiler.clear();
+        assert!(profiler.report().is_empty());
+    }
+}
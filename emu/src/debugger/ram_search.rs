@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use crate::boot_patch::PatchWidth;
+use crate::gba::Gba;
+
+/// Memory the games keep their variables in: EWRAM and IWRAM.
+const SEARCHED: [Range<u32>; 2] = [0x0200_0000..0x0204_0000, 0x0300_0000..0x0300_8000];
+
+/// How the values of the candidates are compared with the ones of the last search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    EqualTo(u32),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl Comparison {
+    const fn keeps(self, previous: u32, current: u32) -> bool {
+        match self {
+            Self::EqualTo(value) => current == value,
+            Self::Changed => current != previous,
+            Self::Unchanged => current == previous,
+            Self::Increased => current > previous,
+            Self::Decreased => current < previous,
+        }
+    }
+}
+
+/// An address still matching the search, with its value at the last search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub address: u32,
+    pub value: u32,
+}
+
+/// Finds the address of a value of the game (eg. the lives) by narrowing every address
+/// of RAM down to the ones changing the same way while playing.
+#[derive(Debug, Clone)]
+pub struct RamSearch {
+    pub width: PatchWidth,
+    candidates: Vec<Candidate>,
+}
+
+impl RamSearch {
+    /// Starts with every address aligned to `width`.
+    #[must_use]
+    pub fn new(gba: &Gba, width: PatchWidth) -> Self {
+        let step = width.bytes();
+        let candidates = SEARCHED
+            .into_iter()
+            .flat_map(|range| range.step_by(step))
+            .map(|address| Candidate {
+                address,
+                value: read(gba, address, width),
+            })
+            .collect();
+
+        Self { width, candidates }
+    }
+
+    /// Keeps the candidates whose current value passes `comparison`.
+    pub fn narrow(&mut self, gba: &Gba, comparison: Comparison) {
+        let width = self.width;
+
+        self.candidates.retain_mut(|candidate| {
+            let current = read(gba, candidate.address, width);
+            let keep = comparison.keeps(candidate.value, current);
+            candidate.value = current;
+
+            keep
+        });
+    }
+
+    #[must_use]
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+}
+
+/// The value of `width` bytes at `address`, little endian, read without side effects.
+#[must_use]
+pub fn read(gba: &Gba, address: u32, width: PatchWidth) -> u32 {
+    let mut bytes = [0; 4];
+    for (byte, address) in bytes.iter_mut().zip(address..).take(width.bytes()) {
+        *byte = gba.peek(address);
+    }
+
+    u32::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::gba::test_gba;
+
+    #[test]
+    fn narrow() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.load_memory(0x0300_0010, &[3, 0]).unwrap();
+        gba.load_memory(0x0200_0100, &[3, 0]).unwrap();
+
+        let mut search = RamSearch::new(&gba, PatchWidth::HalfWord);
+        assert_eq!(search.candidates().len(), 0x4_0000 / 2 + 0x8000 / 2);
+
+        search.narrow(&gba, Comparison::EqualTo(3));
+        assert_eq!(search.candidates().len(), 2);
+
+        // A life lost
+        gba.load_memory(0x0300_0010, &[2, 0]).unwrap();
+        search.narrow(&gba, Comparison::Decreased);
+        assert_eq!(
+            search.candidates(),
+            [Candidate {
+                address: 0x0300_0010,
+                value: 2
+            }]
+        );
+
+        search.narrow(&gba, Comparison::Unchanged);
+        assert_eq!(search.candidates().len(), 1);
+        search.narrow(&gba, Comparison::Changed);
+        assert!(search.candidates().is_empty());
+    }
+
+    #[test]
+    fn read_little_endian() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.load_memory(0x0300_0000, &[0x78, 0x56, 0x34, 0x12])
+            .unwrap();
+
+        assert_eq!(read(&gba, 0x0300_0000, PatchWidth::Byte), 0x78);
+        assert_eq!(read(&gba, 0x0300_0000, PatchWidth::HalfWord), 0x5678);
+        assert_eq!(read(&gba, 0x0300_0000, PatchWidth::Word), 0x1234_5678);
+    }
+}
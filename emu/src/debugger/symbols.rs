@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+
+use object::{Object, ObjectSymbol, SymbolKind};
+
+/// File extensions looked up next to the ROM, in order of preference.
+pub const EXTENSIONS: [&str; 3] = ["elf", "sym", "map"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u32,
+    /// Size in bytes, 0 when unknown (eg. labels or `.sym` files).
+    pub size: u32,
+}
+
+/// Names of the functions and labels of a program, from its ELF or from a
+/// `.sym` (no$gba) or `.map` (GNU ld) file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Symbols {
+    /// Sorted by address.
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl Symbols {
+    /// Parses an ELF file or, if `data` doesn't start with the ELF magic, a `.sym`/`.map` file.
+    ///
+    /// # Errors
+    /// It returns an error if the ELF can't be read or if no symbol is found.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let symbols = if data.starts_with(b"\x7fELF") {
+            parse_elf(data)?
+        } else {
+            parse_text(&String::from_utf8_lossy(data))
+        };
+
+        if symbols.is_empty() {
+            return Err("No symbols found".to_string());
+        }
+
+        Ok(Self::from_symbols(symbols))
+    }
+
+    fn from_symbols(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by(|a, b| a.address.cmp(&b.address).then_with(|| a.name.cmp(&b.name)));
+        symbols.dedup();
+
+        let by_name = symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (symbol.name.clone(), index))
+            .collect();
+
+        Self { symbols, by_name }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    #[must_use]
+    pub fn address_of(&self, name: &str) -> Option<u32> {
+        self.by_name
+            .get(name)
+            .map(|&index| self.symbols[index].address)
+    }
+
+    /// Symbol starting exactly at `address`.
+    #[must_use]
+    pub fn at(&self, address: u32) -> Option<&Symbol> {
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address < address);
+
+        self.symbols
+            .get(index)
+            .filter(|symbol| symbol.address == address)
+    }
+
+    /// Symbol `address` belongs to, with the offset from its start.
+    /// Symbols without a size cover everything up to the next one.
+    #[must_use]
+    pub fn lookup(&self, address: u32) -> Option<(&Symbol, u32)> {
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address);
+        let symbol = &self.symbols[index.checked_sub(1)?];
+        let offset = address - symbol.address;
+
+        (symbol.size == 0 || offset < symbol.size).then_some((symbol, offset))
+    }
+
+    /// Address typed by the user: a symbol name or a number in hex, with or without `0x`.
+    #[must_use]
+    pub fn parse_address(&self, text: &str) -> Option<u32> {
+        let text = text.trim();
+        let hex = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+
+        self.address_of(text)
+            .or_else(|| u32::from_str_radix(hex, 16).ok())
+    }
+
+    /// `address` as `name+0xOFFSET`, `None` if no symbol contains it.
+    #[must_use]
+    pub fn describe(&self, address: u32) -> Option<String> {
+        self.lookup(address).map(|(symbol, offset)| {
+            if offset == 0 {
+                symbol.name.clone()
+            } else {
+                format!("{}+0x{offset:X}", symbol.name)
+            }
+        })
+    }
+}
+
+fn parse_elf(data: &[u8]) -> Result<Vec<Symbol>, String> {
+    let file = object::File::parse(data).map_err(|err| err.to_string())?;
+
+    let symbols = file
+        .symbols()
+        .filter(|symbol| {
+            !symbol.is_undefined()
+                && !matches!(symbol.kind(), SymbolKind::Section | SymbolKind::File)
+        })
+        .filter_map(|symbol| {
+            let name = symbol.name().ok()?;
+            // `$a`, `$t` and `$d` only mark where ARM, Thumb code and data start.
+            if name.is_empty() || name.starts_with('$') {
+                return None;
+            }
+
+            let mut address = u32::try_from(symbol.address()).ok()?;
+            // Thumb functions have the lowest bit set, like the address given to `BX`.
+            if symbol.kind() == SymbolKind::Text {
+                address &= !1;
+            }
+
+            Some(Symbol {
+                name: name.to_string(),
+                address,
+                size: u32::try_from(symbol.size()).unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(symbols)
+}
+
+/// Parses the `ADDRESS NAME` lines of `.sym` and `.map` files, the others are skipped.
+fn parse_text(text: &str) -> Vec<Symbol> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.split(';').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let (address, name) = (tokens.next()?, tokens.next()?);
+            if tokens.next().is_some() || !is_symbol_name(name) {
+                return None;
+            }
+
+            let address = address
+                .strip_prefix("0x")
+                .or_else(|| address.strip_prefix("0X"))
+                .unwrap_or(address);
+            let address = u64::from_str_radix(address, 16).ok()?;
+
+            Some(Symbol {
+                name: name.to_string(),
+                address: u32::try_from(address).ok()?,
+                size: 0,
+            })
+        })
+        .collect()
+}
+
+/// Excludes directives (eg. `.arm` in `.sym` files) and the other tokens of `.map` files.
+fn is_symbol_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn push_u16(data: &mut Vec<u8>, value: usize) {
+        data.extend_from_slice(&u16::try_from(value).unwrap().to_le_bytes());
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: usize) {
+        data.extend_from_slice(&u32::try_from(value).unwrap().to_le_bytes());
+    }
+
+    /// ELF with only a symbol table: a Thumb function `main`, a label `loop` and a mapping symbol.
+    fn elf() -> Vec<u8> {
+        let strtab = b"\0main\0$t\0loop\0";
+        let shstrtab = b"\0.symtab\0.strtab\0.shstrtab\0";
+        // name, value, size, info
+        let symbols = [
+            (0, 0, 0, 0),
+            (6, 0x0800_0100, 0, 0x00),
+            (9, 0x0800_0110, 0, 0x00),
+            (1, 0x0800_0101, 0x20, 0x12),
+        ];
+
+        let symtab_offset = 52;
+        let strtab_offset = symtab_offset + 16 * symbols.len();
+        let shstrtab_offset = strtab_offset + strtab.len();
+        let section_headers_offset = (shstrtab_offset + shstrtab.len()).next_multiple_of(4);
+
+        let mut data = b"\x7fELF\x01\x01\x01".to_vec();
+        data.resize(16, 0);
+        push_u16(&mut data, 2); // executable
+        push_u16(&mut data, 40); // ARM
+        push_u32(&mut data, 1);
+        push_u32(&mut data, 0x0800_0000);
+        push_u32(&mut data, 0);
+        push_u32(&mut data, section_headers_offset);
+        push_u32(&mut data, 0);
+        push_u16(&mut data, 52);
+        push_u16(&mut data, 32);
+        push_u16(&mut data, 0);
+        push_u16(&mut data, 40);
+        push_u16(&mut data, 4);
+        push_u16(&mut data, 3);
+
+        for (name, value, size, info) in symbols {
+            push_u32(&mut data, name);
+            push_u32(&mut data, value);
+            push_u32(&mut data, size);
+            data.extend_from_slice(&[info, 0]);
+            push_u16(&mut data, usize::from(name != 0));
+        }
+        data.extend_from_slice(strtab);
+        data.extend_from_slice(shstrtab);
+        data.resize(section_headers_offset, 0);
+
+        // name, type, offset, size, link, info, entry size
+        let sections = [
+            (0, 0, 0, 0, 0, 0, 0),
+            (1, 2, symtab_offset, 16 * symbols.len(), 2, 3, 16),
+            (9, 3, strtab_offset, strtab.len(), 0, 0, 0),
+            (17, 3, shstrtab_offset, shstrtab.len(), 0, 0, 0),
+        ];
+        for (name, kind, offset, size, link, info, entry_size) in sections {
+            for value in [name, kind, 0, 0, offset, size, link, info, 1, entry_size] {
+                push_u32(&mut data, value);
+            }
+        }
+
+        data
+    }
+
+    #[test]
+    fn parse_elf_symbols() {
+        let symbols = Symbols::parse(&elf()).unwrap();
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols.address_of("main"), Some(0x0800_0100));
+        assert_eq!(symbols.address_of("loop"), Some(0x0800_0110));
+        assert_eq!(symbols.address_of("$t"), None);
+
+        assert_eq!(symbols.at(0x0800_0100).unwrap().size, 0x20);
+        assert_eq!(symbols.describe(0x0800_0104), Some("main+0x4".to_string()));
+        assert_eq!(symbols.describe(0x0800_0114), Some("loop+0x4".to_string()));
+        assert_eq!(symbols.describe(0x0800_00FC), None);
+    }
+
+    #[test]
+    fn parse_sym() {
+        let sym = "; no$gba symbols\n\
+                   08000000 .arm\n\
+                   08000000 _start\n\
+                   080000C0 .thumb\n\
+                   080000C0 main\n";
+        let symbols = Symbols::parse(sym.as_bytes()).unwrap();
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols.address_of("_start"), Some(0x0800_0000));
+        assert_eq!(symbols.describe(0x0800_00C2), Some("main+0x2".to_string()));
+        assert_eq!(symbols.at(0x0800_00C0).unwrap().name, "main");
+
+        assert_eq!(symbols.parse_address(" main "), Some(0x0800_00C0));
+        assert_eq!(symbols.parse_address("0x0300_0000"), None);
+        assert_eq!(symbols.parse_address("0x03000000"), Some(0x0300_0000));
+        assert_eq!(symbols.parse_address("80000C4"), Some(0x0800_00C4));
+        assert_eq!(symbols.parse_address("missing"), None);
+    }
+
+    #[test]
+    fn parse_map() {
+        let map = "Memory Configuration\n\
+                   \n\
+                   .text           0x0000000008000000      0x1f4\n \
+                   *(.text.main)\n \
+                   .text.main     0x00000000080000c0       0x34 main.o\n                \
+                   0x00000000080000c0                main\n                \
+                   0x0000000003000000                __iwram_start = .\n";
+        let symbols = Symbols::parse(map.as_bytes()).unwrap();
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols.address_of("main"), Some(0x0800_00C0));
+    }
+
+    #[test]
+    fn parse_garbage() {
+        assert!(Symbols::parse(b"\x7fELF\x01").is_err());
+        assert!(Symbols::parse(b"hello world").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Something done by the hardware besides the CPU, recorded in the [`Timeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEvent {
+    /// A DMA channel (0-3) transferred `units` halfwords or words.
+    Dma { channel: u8, units: u32 },
+    /// A timer (0-3) overflowed.
+    TimerOverflow(u8),
+    /// An interrupt was requested, `bit` is its bit in `IF`.
+    Irq { bit: u8 },
+}
+
+impl fmt::Display for TimelineEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dma { channel, units } => write!(f, "DMA{channel} {units} units"),
+            Self::TimerOverflow(timer) => write!(f, "TM{timer} overflow"),
+            Self::Irq { bit } => write!(f, "IRQ {}", IRQ_NAMES[usize::from(*bit)]),
+        }
+    }
+}
+
+/// Sources of the interrupts, in the order of the bits of `IF`.
+const IRQ_NAMES: [&str; 14] = [
+    "VBlank", "HBlank", "VCount", "Timer0", "Timer1", "Timer2", "Timer3", "Serial", "DMA0", "DMA1",
+    "DMA2", "DMA3", "Keypad", "GamePak",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimelineEntry {
+    /// Cycles since the start, see `GbaBus::cycles_count`.
+    pub cycle: u128,
+    /// Scanline (0-227) and dot (0-307) of the LCD when it happened.
+    pub line: u16,
+    pub dot: u32,
+    pub event: TimelineEvent,
+}
+
+/// The last DMA transfers, timer overflows and interrupt requests with the scanline
+/// they happened on, to find the ones happening a line too late or too early.
+/// Older entries are dropped past the capacity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    entries: VecDeque<TimelineEntry>,
+    capacity: usize,
+}
+
+impl Timeline {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Entries from the oldest one.
+    #[must_use]
+    pub const fn entries(&self) -> &VecDeque<TimelineEntry> {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn record(&mut self, cycle: u128, line: u16, dot: u32, event: TimelineEvent) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(TimelineEntry {
+            cycle,
+            line,
+            dot,
+            event,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn keeps_the_last_entries() {
+        let mut timeline = Timeline::new(2);
+
+        timeline.record(1, 0, 240, TimelineEvent::Irq { bit: 1 });
+        timeline.record(2, 0, 241, TimelineEvent::TimerOverflow(1));
+        timeline.record(
+            3,
+            1,
+            0,
+            TimelineEvent::Dma {
+                channel: 3,
+                units: 4,
+            },
+        );
+
+        let events = timeline
+            .entries()
+            .iter()
+            .map(|entry| entry.event.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec!["TM1 overflow", "DMA3 4 units"]);
+        assert_eq!(timeline.entries()[1].line, 1);
+
+        timeline.clear();
+        assert!(timeline.entries().is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub address: u32,
+    /// The whole word in ARM state, a halfword in THUMB state.
+    pub opcode: u32,
+    pub is_thumb: bool,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_thumb {
+            write!(f, "{:08X}: {:04X} (THUMB)", self.address, self.opcode)
+        } else {
+            write!(f, "{:08X}: {:08X} (ARM)", self.address, self.opcode)
+        }
+    }
+}
+
+/// The last instructions executed, conditions failed or not, to see how the CPU got
+/// where it is (eg. in a crash report). Older entries are dropped past the capacity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    entries: VecDeque<TraceEntry>,
+    capacity: usize,
+}
+
+impl Trace {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Entries from the oldest one.
+    #[must_use]
+    pub const fn entries(&self) -> &VecDeque<TraceEntry> {
+        &self.entries
+    }
+
+    pub(crate) fn record(&mut self, entry: TraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn keeps_the_last_entries() {
+        let mut trace = Trace::new(2);
+        for address in [0x0800_0000, 0x0800_0004, 0x0800_0008] {
+            trace.record(TraceEntry {
+                address,
+                opcode: 0xE1A0_0000,
+                is_thumb: false,
+            });
+        }
+
+        let addresses = trace
+            .entries()
+            .iter()
+            .map(|entry| entry.address)
+            .collect::<Vec<_>>();
+        assert_eq!(addresses, vec![0x0800_0004, 0x0800_0008]);
+    }
+
+    #[test]
+    fn display() {
+        let arm = TraceEntry {
+            address: 0x0800_0000,
+            opcode: 0xE1A0_0000,
+            is_thumb: false,
+        };
+        let thumb = TraceEntry {
+            address: 0x0300_0102,
+            opcode: 0x4770,
+            is_thumb: true,
+        };
+
+        assert_eq!(arm.to_string(), "08000000: E1A00000 (ARM)");
+        assert_eq!(thumb.to_string(), "03000102: 4770 (THUMB)");
+    }
+}
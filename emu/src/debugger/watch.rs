@@ -0,0 +1,284 @@
+use crate::debugger::symbols::Symbols;
+use crate::gba::Gba;
+
+/// Size of a value read from memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchSize {
+    Byte,
+    HalfWord,
+    Word,
+}
+
+impl WatchSize {
+    const fn bytes(self) -> u32 {
+        match self {
+            Self::Byte => 1,
+            Self::HalfWord => 2,
+            Self::Word => 4,
+        }
+    }
+
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix.to_ascii_lowercase().as_str() {
+            "b" => Some(Self::Byte),
+            "h" => Some(Self::HalfWord),
+            "w" | "" => Some(Self::Word),
+            _ => None,
+        }
+    }
+}
+
+/// How a watched value is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WatchFormat {
+    #[default]
+    Hex,
+    Unsigned,
+    Signed,
+}
+
+impl WatchFormat {
+    pub const ALL: [Self; 3] = [Self::Hex, Self::Unsigned, Self::Signed];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchValue {
+    pub value: u32,
+    pub size: WatchSize,
+}
+
+impl WatchValue {
+    #[must_use]
+    pub fn format(self, format: WatchFormat) -> String {
+        let bits = self.size.bytes() * 8;
+
+        match format {
+            WatchFormat::Hex => format!("0x{:01$X}", self.value, self.size.bytes() as usize * 2),
+            WatchFormat::Unsigned => self.value.to_string(),
+            #[allow(clippy::cast_possible_wrap)]
+            WatchFormat::Signed => {
+                // Moves the sign bit of the size to bit 31 and back.
+                (((self.value << (32 - bits)) as i32) >> (32 - bits)).to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Term {
+    Register(usize),
+    Cpsr,
+    Constant(u32),
+}
+
+/// Terms added together, the `bool` is `true` for the subtracted ones.
+type Sum = Vec<(bool, Term)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    Value(Sum),
+    Memory { address: Sum, size: WatchSize },
+}
+
+/// An expression evaluated while the game runs, one of:
+/// - a register (`r0`-`r15`, `sp`, `lr`, `pc`, `cpsr`), an address or a symbol;
+/// - `[address]` with an optional size suffix (`b`, `h` or `w`, the default),
+///   the value in memory (eg. `[0x03001A40]h`, `[r0+8]`);
+/// - `*symbol`, the value of a variable, of the size of the symbol when it's known.
+///
+/// Terms can be added and subtracted, numbers are in hex like the other addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watch {
+    pub text: String,
+    expression: Expression,
+}
+
+impl Watch {
+    /// Symbols are resolved when the watch is added.
+    ///
+    /// # Errors
+    /// It returns an error if the expression is not valid or a name is not a register or a symbol.
+    pub fn parse(text: &str, symbols: &Symbols) -> Result<Self, String> {
+        let trimmed = text.trim();
+
+        let expression = if let Some(inner) = trimmed.strip_prefix('[') {
+            let (address, suffix) = inner
+                .split_once(']')
+                .ok_or_else(|| format!("Missing `]` in `{trimmed}`"))?;
+            let size = WatchSize::from_suffix(suffix.trim())
+                .ok_or_else(|| format!("Unknown size `{suffix}`, use b, h or w"))?;
+
+            Expression::Memory {
+                address: parse_sum(address, symbols)?,
+                size,
+            }
+        } else if let Some(name) = trimmed.strip_prefix('*') {
+            let size = symbols
+                .address_of(name.trim())
+                .and_then(|address| symbols.at(address))
+                .and_then(|symbol| match symbol.size {
+                    1 => Some(WatchSize::Byte),
+                    2 => Some(WatchSize::HalfWord),
+                    _ => None,
+                })
+                .unwrap_or(WatchSize::Word);
+
+            Expression::Memory {
+                address: parse_sum(name, symbols)?,
+                size,
+            }
+        } else {
+            Expression::Value(parse_sum(trimmed, symbols)?)
+        };
+
+        Ok(Self {
+            text: trimmed.to_string(),
+            expression,
+        })
+    }
+
+    #[must_use]
+    pub fn evaluate(&self, gba: &Gba) -> WatchValue {
+        let sum = |terms: &Sum| {
+            terms.iter().fold(0_u32, |total, &(is_negative, term)| {
+                let value = match term {
+                    Term::Register(index) => gba.cpu.registers.register_at(index),
+                    Term::Cpsr => gba.cpu.cpsr.into(),
+                    Term::Constant(value) => value,
+                };
+
+                if is_negative {
+                    total.wrapping_sub(value)
+                } else {
+                    total.wrapping_add(value)
+                }
+            })
+        };
+
+        match &self.expression {
+            Expression::Value(terms) => WatchValue {
+                value: sum(terms),
+                size: WatchSize::Word,
+            },
+            Expression::Memory { address, size } => {
+                let address = sum(address);
+                // Little endian, without side effects on the I/O registers.
+                let value = (0..size.bytes()).rev().fold(0, |value, byte| {
+                    let address = address.wrapping_add(byte) as usize;
+                    (value << 8) | u32::from(gba.cpu.bus.read_raw(address))
+                });
+
+                WatchValue { value, size: *size }
+            }
+        }
+    }
+}
+
+fn parse_sum(text: &str, symbols: &Symbols) -> Result<Sum, String> {
+    let mut terms = Vec::new();
+    let mut is_negative = false;
+    let mut rest = text.trim();
+
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        terms.push((is_negative, parse_term(&rest[..end], symbols)?));
+
+        let Some(operator) = rest[end..].chars().next() else {
+            return Ok(terms);
+        };
+        is_negative = operator == '-';
+        rest = &rest[end + 1..];
+    }
+}
+
+fn parse_term(text: &str, symbols: &Symbols) -> Result<Term, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Missing value".to_string());
+    }
+
+    let register = match text.to_ascii_lowercase().as_str() {
+        "sp" => Some(13),
+        "lr" => Some(14),
+        "pc" => Some(15),
+        "cpsr" => return Ok(Term::Cpsr),
+        name => name
+            .strip_prefix('r')
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|&index| index < 16),
+    };
+
+    register.map_or_else(
+        || {
+            symbols
+                .parse_address(text)
+                .map(Term::Constant)
+                .ok_or_else(|| format!("`{text}` is not a register, an address or a symbol"))
+        },
+        |index| Ok(Term::Register(index)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gba::test_gba;
+    use pretty_assertions::assert_eq;
+
+    fn gba() -> Gba {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.cpu.registers.set_register_at(0, 0x0300_0000);
+        gba.cpu.registers.set_register_at(13, 0x0300_7F00);
+        gba.load_memory(0x0300_1A40, &[0xFE, 0xFF, 0x34, 0x12])
+            .unwrap();
+
+        gba
+    }
+
+    #[test]
+    fn evaluate() {
+        let gba = gba();
+        let symbols = Symbols::parse(b"03001A40 player_x\n").unwrap();
+        let value = |text: &str| {
+            Watch::parse(text, &symbols)
+                .unwrap()
+                .evaluate(&gba)
+                .format(WatchFormat::Hex)
+        };
+
+        assert_eq!(value("[0x03001A40]w"), "0x1234FFFE");
+        assert_eq!(value("[03001A40]"), "0x1234FFFE");
+        assert_eq!(value("[r0 + 1A42]H"), "0x1234");
+        assert_eq!(value("[player_x+3]b"), "0x12");
+        assert_eq!(value("*player_x"), "0x1234FFFE");
+        assert_eq!(value("r0-100"), "0x02FFFF00");
+        assert_eq!(value("SP"), "0x03007F00");
+        assert_eq!(value("cpsr"), "0x000000D3");
+    }
+
+    #[test]
+    fn formats() {
+        let half = WatchValue {
+            value: 0xFFFE,
+            size: WatchSize::HalfWord,
+        };
+        assert_eq!(half.format(WatchFormat::Hex), "0xFFFE");
+        assert_eq!(half.format(WatchFormat::Unsigned), "65534");
+        assert_eq!(half.format(WatchFormat::Signed), "-2");
+
+        let byte = WatchValue {
+            value: 0x7F,
+            size: WatchSize::Byte,
+        };
+        assert_eq!(byte.format(WatchFormat::Signed), "127");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let symbols = Symbols::default();
+
+        for text in ["[0x100", "[0x100]q", "r0+", "player_x", "r16", ""] {
+            assert!(Watch::parse(text, &symbols).is_err(), "{text}");
+        }
+    }
+}
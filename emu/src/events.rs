@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gba::Gba;
+
+/// Emulated events the host can hook with [`Gba::on_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoreEvent {
+    /// The LCD entered `VBlank` (line 160), whether or not the IRQ is enabled.
+    VBlank,
+    /// The last line of the frame has been drawn, the next step starts a new frame.
+    FrameComplete,
+    /// A buffer of audio samples is ready, take them with [`Gba::take_audio_samples`].
+    AudioBufferFull,
+    /// The program set the start bit of `SIOCNT`.
+    SerialTransferStarted,
+}
+
+/// Identifies a registered callback, used to remove it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallbackId(u64);
+
+pub type EventCallback = Box<dyn FnMut(&mut Gba) + Send>;
+
+/// Callbacks registered by the host, grouped by event.
+#[derive(Default)]
+pub struct EventHooks {
+    callbacks: Vec<(CallbackId, CoreEvent, EventCallback)>,
+    next_id: u64,
+}
+
+impl EventHooks {
+    pub fn add(&mut self, event: CoreEvent, callback: EventCallback) -> CallbackId {
+        let id = CallbackId(self.next_id);
+        self.next_id += 1;
+        self.callbacks.push((id, event, callback));
+
+        id
+    }
+
+    /// Returns `false` if there was no callback with this `id`.
+    pub fn remove(&mut self, id: CallbackId) -> bool {
+        let len = self.callbacks.len();
+        self.callbacks
+            .retain(|(callback_id, _, _)| *callback_id != id);
+
+        self.callbacks.len() != len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    /// Calls the callbacks of `event`, in registration order.
+    pub fn dispatch(&mut self, event: CoreEvent, gba: &mut Gba) {
+        for (_, _, callback) in self
+            .callbacks
+            .iter_mut()
+            .filter(|(_, callback_event, _)| *callback_event == event)
+        {
+            callback(gba);
+        }
+    }
+
+    /// Takes the callbacks out, ids keep increasing in `self` so the callbacks
+    /// can register new ones while they are dispatched.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            callbacks: std::mem::take(&mut self.callbacks),
+            next_id: self.next_id,
+        }
+    }
+
+    /// Moves the callbacks of `other` (registered during a dispatch) into `self`.
+    pub(crate) fn append(&mut self, other: &mut Self) {
+        self.callbacks.append(&mut other.callbacks);
+        self.next_id = self.next_id.max(other.next_id);
+    }
+}
@@ -1,39 +1,1662 @@
 use std::sync::{Arc, Mutex};
 
+use logger::log;
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    bus::Bus,
-    cartridge_header::CartridgeHeader,
-    cpu::{arm7tdmi::Arm7tdmi, hardware::internal_memory::InternalMemory},
-    render::gba_lcd::GbaLcd,
+    boot_patch::{BootPatch, BootPatches},
+    bus::GbaBus,
+    cartridge::{header::Header, patch},
+    cheats::{Cheat, CheatMode},
+    core_profile::{is_idle_loop, CoreProfile},
+    cpu::{
+        arm7tdmi::Arm7tdmi,
+        bios_hle,
+        decode_cache::DecodeCache,
+        hardware::{
+            gpio::Gpio,
+            internal_memory::InternalMemory,
+            joybus::JoybusDevice,
+            keypad::NO_KEYS_PRESSED,
+            lcd,
+            sound::{AudioSettings, FifoStats},
+        },
+    },
+    debugger::{
+        divergence::{first_difference, Divergence, DivergenceCheck},
+        line_info::{LineInfo, Location},
+        symbols::Symbols,
+    },
+    events::{CallbackId, CoreEvent, EventHooks},
+    handle::{CommandQueue, CoreHandle},
+    render::{
+        color::{Color, PaletteType},
+        gba_lcd::GbaLcd,
+        LCD_HEIGHT, LCD_WIDTH,
+    },
+    run_hash::{RunHash, RunHasher},
 };
 
+/// BIOS is mapped from 0x00000000 to 0x00003FFF.
+const BIOS_END: usize = 0x0000_3FFF;
+
+/// Backup memory is left out of dumps and loads, it's the save of the game.
+const SRAM: std::ops::Range<u64> = 0x0E00_0000..0x0E01_0000;
+
+/// Palette RAM, BG colors first and OBJ colors after.
+const PALETTE_RAM_START: usize = 0x0500_0000;
+const OBJ_PALETTE_OFFSET: usize = 0x200;
+
+/// Multiboot images are loaded at the start of EWRAM and executed from there.
+pub const MULTIBOOT_START: u32 = 0x0200_0000;
+
+/// Multiboot images can't be bigger than EWRAM (256 `KBytes`).
+const MULTIBOOT_MAX_SIZE: usize = 0x0004_0000;
+
+/// Cycles skipped at most at once in an idle loop with the fast profile, a scanline.
+const IDLE_SKIP_CYCLES: u32 = 1232;
+
+/// Screenshots in state files are the screen halved on both sides.
+pub const THUMBNAIL_WIDTH: usize = LCD_WIDTH / 2;
+pub const THUMBNAIL_HEIGHT: usize = LCD_HEIGHT / 2;
+
+/// Start of the state files made by [`Gba::save_state_file`], older files are bare states.
+const STATE_FILE_MAGIC: &[u8; 8] = b"CLMSTATE";
+
+/// A state file, with a screenshot to recognize it without loading it.
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    /// `THUMBNAIL_WIDTH` x `THUMBNAIL_HEIGHT` colors, row by row.
+    thumbnail: Vec<u16>,
+    state: Vec<u8>,
+}
+
+/// Frames the soft reset keys are held, games check them at most once per frame.
+const SOFT_RESET_FRAMES: u8 = 10;
+
+/// `KEYINPUT` with A, B, Select and Start pressed.
+const SOFT_RESET_KEYS: u16 = NO_KEYS_PRESSED & !0b1111;
+
+/// Keys held for some frames, see [`Gba::hold_keys`].
+#[derive(Debug, Clone, Copy)]
+struct HeldKeys {
+    frames_left: u32,
+    /// Keys pressed before, restored at the end.
+    keys: u16,
+}
+
+/// A CPU on a console with `bios` and `rom`. A missing BIOS is replaced by the stub of
+/// [`bios_hle`], the emulator runs the BIOS functions and the console boots to the game.
+fn new_cpu(bios: &[u8; 0x0000_4000], rom: Vec<u8>) -> Arm7tdmi {
+    let hle_bios = bios_hle::is_missing(bios);
+    let bios = if hle_bios {
+        bios_hle::stub_bios()
+    } else {
+        *bios
+    };
+
+    let mut cpu = Arm7tdmi::new(GbaBus::with_memory(InternalMemory::new(bios, rom)));
+    if hle_bios {
+        cpu.hle_bios = true;
+        bios_hle::soft_reset(&mut cpu);
+    }
+
+    cpu
+}
+
 pub struct Gba {
     pub cpu: Arm7tdmi,
 
-    pub cartridge_header: CartridgeHeader,
+    pub cartridge_header: Header,
     pub lcd: Arc<Mutex<Box<GbaLcd>>>,
+
+    /// Divergence checks run while the game runs, see [`Self::check_divergence`].
+    pub divergence_check: Option<DivergenceCheck>,
+    /// Written at the end of every frame while enabled.
+    pub cheats: Vec<Cheat>,
+
+    hooks: EventHooks,
+    joybus_device: Option<Box<dyn JoybusDevice>>,
+    held_keys: Option<HeldKeys>,
+    commands: CommandQueue,
+    boot_patches: BootPatches,
 }
 
 impl Gba {
     #[must_use]
-    pub fn new(
-        cartridge_header: CartridgeHeader,
-        bios: [u8; 0x0000_4000],
-        cartridge: Vec<u8>,
-    ) -> Self {
+    pub fn new(cartridge_header: Header, bios: [u8; 0x0000_4000], cartridge: Vec<u8>) -> Self {
         let lcd = Arc::new(Mutex::new(Box::default()));
-        let memory = InternalMemory::new(bios, cartridge);
-        let bus = Bus::with_memory(memory);
-        let arm = Arm7tdmi::new(bus);
 
         Self {
-            cpu: arm,
+            cpu: new_cpu(&bios, cartridge),
             cartridge_header,
             lcd,
+            divergence_check: None,
+            cheats: Vec::new(),
+            hooks: EventHooks::default(),
+            joybus_device: None,
+            held_keys: None,
+            commands: CommandQueue::default(),
+            boot_patches: BootPatches::default(),
+        }
+    }
+
+    /// Creates a `Gba` running a multiboot image without cartridge, as if the BIOS had just
+    /// received it: the image is copied in EWRAM and the execution starts from there.
+    ///
+    /// # Errors
+    /// It returns an error if the image doesn't fit in EWRAM or if its header is truncated.
+    pub fn with_multiboot(bios: [u8; 0x0000_4000], image: &[u8]) -> Result<Self, String> {
+        if image.len() > MULTIBOOT_MAX_SIZE {
+            return Err(format!(
+                "Multiboot image is {} bytes but max size is {MULTIBOOT_MAX_SIZE} bytes",
+                image.len()
+            ));
+        }
+
+        let header = Header::new(image)?;
+        let mut gba = Self::new(header, bios, Vec::new());
+
+        for (offset, &value) in image.iter().enumerate() {
+            gba.cpu
+                .bus
+                .internal_memory
+                .write_at(MULTIBOOT_START as usize + offset, value);
+        }
+
+        // Stack pointers and mode are the ones the BIOS leaves before jumping to the image.
+        bios_hle::boot(&mut gba.cpu, MULTIBOOT_START);
+
+        Ok(gba)
+    }
+
+    /// Applies an IPS/UPS/BPS patch to the loaded cartridge and restarts from the BIOS.
+    /// The patch is applied only in memory, the ROM file is left untouched.
+    ///
+    /// # Errors
+    /// It returns an error if the patch can't be applied, the current cartridge is kept.
+    pub fn apply_patch(&mut self, patch: &[u8]) -> Result<(), String> {
+        let memory = &self.cpu.bus.internal_memory;
+        let rom = patch::apply(&memory.rom, patch)?;
+        let bios = memory
+            .bios()
+            .try_into()
+            .map_err(|_| "BIOS must be 16 KBytes".to_string())?;
+
+        self.cartridge_header = Header::new(&rom)?;
+        self.restart(&bios, rom);
+
+        Ok(())
+    }
+
+    /// Restarts from the BIOS with a new build of the cartridge (eg. a homebrew just
+    /// rebuilt) and, if given, a new BIOS. The saves of the game in the backup memory are
+    /// kept if `keep_backup`, otherwise it's erased like with a new cartridge.
+    ///
+    /// # Errors
+    /// It returns an error if the header of `rom` can't be read, the current cartridge is kept.
+    pub fn replace_cartridge(
+        &mut self,
+        rom: Vec<u8>,
+        bios: Option<[u8; 0x0000_4000]>,
+        keep_backup: bool,
+    ) -> Result<(), String> {
+        let bios = match bios {
+            Some(bios) => bios,
+            None => self
+                .cpu
+                .bus
+                .internal_memory
+                .bios()
+                .try_into()
+                .map_err(|_| "BIOS must be 16 KBytes".to_string())?,
+        };
+        let header = Header::new(&rom)?;
+
+        let sram = std::mem::take(&mut self.cpu.bus.internal_memory.sram);
+        self.cartridge_header = header;
+        self.restart(&bios, rom);
+        if keep_backup {
+            self.cpu.bus.internal_memory.sram = sram;
+        }
+
+        Ok(())
+    }
+
+    /// Unix time of the cartridge RTC, `None` if there is no RTC.
+    #[must_use]
+    pub fn rtc_timestamp(&self) -> Option<u64> {
+        let bus = &self.cpu.bus;
+
+        bus.gpio
+            .as_ref()
+            .filter(|gpio| gpio.has_rtc())
+            .map(|gpio| gpio.rtc_timestamp(bus.cycles_count))
+    }
+
+    /// Status register of the cartridge RTC (eg. the 12/24-hour mode), `None` if there
+    /// is no RTC.
+    #[must_use]
+    pub fn rtc_control(&self) -> Option<u8> {
+        self.cpu
+            .bus
+            .gpio
+            .as_ref()
+            .filter(|gpio| gpio.has_rtc())
+            .map(Gpio::rtc_control)
+    }
+
+    pub const fn set_rtc_control(&mut self, control: u8) {
+        if let Some(gpio) = &mut self.cpu.bus.gpio {
+            gpio.set_rtc_control(control);
+        }
+    }
+
+    /// Cycles of the system clock since the console was turned on, they follow the
+    /// loaded states.
+    #[must_use]
+    pub const fn cycles(&self) -> u128 {
+        self.cpu.bus.cycles_count
+    }
+
+    /// How much the rumble motor of the cartridge spun since the last call, from 0 to 1,
+    /// `None` if the cartridge has no rumble.
+    pub fn take_rumble(&mut self) -> Option<f32> {
+        let bus = &mut self.cpu.bus;
+
+        bus.gpio
+            .as_mut()
+            .and_then(|gpio| gpio.take_rumble(bus.cycles_count))
+    }
+
+    /// Sets the time of the cartridge RTC. The core never reads the host clock:
+    /// the RTC advances with the emulated cycles, so runs starting from the same
+    /// time with the same inputs are identical.
+    pub fn set_rtc_timestamp(&mut self, timestamp: u64) {
+        let bus = &mut self.cpu.bus;
+
+        if let Some(gpio) = &mut bus.gpio {
+            gpio.set_rtc_timestamp(timestamp, bus.cycles_count);
+        }
+    }
+
+    /// Restarts from the BIOS with the same cartridge, as if the console was turned off and on.
+    /// The backup memory is erased too, like with a new cartridge, so that every core
+    /// starts from the same state (eg. in netplay). See [`Self::hard_reset`] to keep it.
+    ///
+    /// # Errors
+    /// It returns an error if the loaded BIOS is not 16 `KBytes`.
+    pub fn reset(&mut self) -> Result<(), String> {
+        let memory = &self.cpu.bus.internal_memory;
+        let bios = memory
+            .bios()
+            .try_into()
+            .map_err(|_| "BIOS must be 16 KBytes".to_string())?;
+        let rom = memory.rom.clone();
+        self.restart(&bios, rom);
+
+        Ok(())
+    }
+
+    /// Turns the console off and on with `bios` and `rom`, the cartridge clock keeps running.
+    fn restart(&mut self, bios: &[u8; 0x0000_4000], rom: Vec<u8>) {
+        // The battery of the RTC keeps it running.
+        let rtc_timestamp = self.rtc_timestamp();
+        let rtc_control = self.rtc_control();
+
+        self.replace_cpu(new_cpu(bios, rom));
+        if let Some(timestamp) = rtc_timestamp {
+            self.set_rtc_timestamp(timestamp);
+        }
+        if let Some(control) = rtc_control {
+            self.set_rtc_control(control);
+        }
+        self.boot_patches.pending = !self.boot_patches.patches.is_empty();
+    }
+
+    /// Restarts from the BIOS like [`Self::reset`], keeping the saves of the game in the
+    /// backup memory.
+    ///
+    /// # Errors
+    /// It returns an error if the loaded BIOS is not 16 `KBytes`.
+    pub fn hard_reset(&mut self) -> Result<(), String> {
+        let sram = std::mem::take(&mut self.cpu.bus.internal_memory.sram);
+        let result = self.reset();
+        self.cpu.bus.internal_memory.sram = sram;
+
+        result
+    }
+
+    /// The backup memory of the cartridge, what `.sav` files contain.
+    #[must_use]
+    pub fn backup(&self) -> &[u8] {
+        &self.cpu.bus.internal_memory.sram
+    }
+
+    /// Replaces the backup memory, eg. with a `.sav` file. Smaller saves (eg. of 32 `KBytes`)
+    /// are padded with erased bytes.
+    ///
+    /// # Errors
+    /// It returns an error if `data` is bigger than the backup memory, the current one is kept.
+    pub fn set_backup(&mut self, data: &[u8]) -> Result<(), String> {
+        let sram = &mut self.cpu.bus.internal_memory.sram;
+        if data.len() > sram.len() {
+            return Err(format!(
+                "the save is {} bytes, the backup memory {}",
+                data.len(),
+                sram.len()
+            ));
+        }
+
+        sram.fill(0xFF);
+        sram[..data.len()].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    /// Writes `data` in the backup memory from `offset`, eg. from an editor of the saves.
+    ///
+    /// # Errors
+    /// It returns an error if `data` goes past the backup memory, nothing is written then.
+    pub fn write_backup(&mut self, offset: usize, data: &[u8]) -> Result<(), String> {
+        let sram = &mut self.cpu.bus.internal_memory.sram;
+        let range = offset..offset.saturating_add(data.len());
+        if range.end > sram.len() {
+            return Err(format!(
+                "{:#X}..{:#X} is past the backup memory ({:#X} bytes)",
+                range.start,
+                range.end,
+                sram.len()
+            ));
+        }
+
+        sram[range].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    /// Holds A+B+Start+Select for a few frames, the combination most games handle by
+    /// going back to their title screen (from the keypad interrupt or polling the keys).
+    /// The keys pressed before are restored after.
+    pub fn soft_reset(&mut self) {
+        self.hold_keys(SOFT_RESET_KEYS, SOFT_RESET_FRAMES.into());
+    }
+
+    /// Sets `KEYINPUT` to `keys` for `frames` frames (at least one), then the keys
+    /// pressed before are restored. Holding other keys meanwhile replaces them.
+    pub fn hold_keys(&mut self, keys: u16, frames: u32) {
+        let previous = self
+            .held_keys
+            .map_or_else(|| self.cpu.bus.key_input(), |held_keys| held_keys.keys);
+
+        self.held_keys = Some(HeldKeys {
+            frames_left: frames.max(1),
+            keys: previous,
+        });
+        self.set_key_input(keys);
+    }
+
+    fn count_held_keys_frame(&mut self) {
+        let Some(held_keys) = &mut self.held_keys else {
+            return;
+        };
+
+        held_keys.frames_left -= 1;
+        if held_keys.frames_left == 0 {
+            let keys = held_keys.keys;
+            self.held_keys = None;
+            self.set_key_input(keys);
+        }
+    }
+
+    /// Swaps the emulated machine, keeping the settings of the host.
+    fn replace_cpu(&mut self, cpu: Arm7tdmi) {
+        let audio_settings = self.audio_settings();
+        let hidden_layers = self.cpu.bus.lcd.hidden_layers;
+        let frame_skip = self.cpu.bus.lcd.frame_skip;
+        let index_capture = self.cpu.bus.lcd.index_capture.take();
+        let clip_buffer = self.cpu.bus.lcd.clip_buffer.take();
+        let recorder = self.cpu.bus.lcd.recorder.take();
+        let frame_blending = self.cpu.bus.lcd.frame_blending.take();
+        let affine_resolution = self.cpu.bus.lcd.affine_resolution;
+        let fast_ewram = self.cpu.bus.fast_ewram;
+        let profile = self.cpu.bus.profile;
+        let decode_cache = self.cpu.decode_cache.take();
+        let symbols = std::mem::take(&mut self.cpu.symbols);
+        let annotations = std::mem::take(&mut self.cpu.annotations);
+        let line_info = std::mem::take(&mut self.cpu.line_info);
+        let coverage = self.cpu.bus.coverage.take();
+        let timeline = self.cpu.bus.timeline.take();
+        let profiler = self.cpu.profiler.take();
+        let instruction_stats = self.cpu.instruction_stats.take();
+        let trace = self.cpu.trace.take();
+        self.cpu = cpu;
+        self.cpu.bus.coverage = coverage;
+        self.cpu.bus.timeline = timeline;
+        self.cpu.profiler = profiler;
+        self.cpu.instruction_stats = instruction_stats;
+        self.cpu.trace = trace;
+        self.cpu.symbols = symbols;
+        self.cpu.annotations = annotations;
+        self.cpu.line_info = line_info;
+        self.set_audio_settings(audio_settings);
+        self.cpu.bus.lcd.hidden_layers = hidden_layers;
+        self.cpu.bus.lcd.frame_skip = frame_skip;
+        self.cpu.bus.lcd.index_capture = index_capture;
+        self.cpu.bus.lcd.clip_buffer = clip_buffer;
+        self.cpu.bus.lcd.recorder = recorder;
+        self.cpu.bus.lcd.frame_blending = frame_blending;
+        self.cpu.bus.lcd.affine_resolution = affine_resolution;
+        self.cpu.bus.fast_ewram = fast_ewram;
+        self.cpu.bus.profile = profile;
+        self.cpu.decode_cache = decode_cache;
+    }
+
+    #[must_use]
+    pub const fn profile(&self) -> CoreProfile {
+        self.cpu.bus.profile
+    }
+
+    /// Switches between the accuracy and the fast profile, see [`CoreProfile`]. It can be
+    /// done while the game runs.
+    pub fn set_profile(&mut self, profile: CoreProfile) {
+        self.cpu.bus.profile = profile;
+        if !profile.is_fast() {
+            self.cpu.decode_cache = None;
+        } else if self.cpu.decode_cache.is_none() {
+            self.cpu.decode_cache = Some(DecodeCache::default());
         }
     }
 
+    #[must_use]
+    pub const fn audio_settings(&self) -> AudioSettings {
+        self.cpu.bus.sound.settings
+    }
+
+    pub const fn set_audio_settings(&mut self, settings: AudioSettings) {
+        self.cpu.bus.sound.settings = settings;
+    }
+
+    /// Underruns and overruns of the Direct Sound FIFOs A and B.
+    #[must_use]
+    pub fn audio_fifo_stats(&self) -> [FifoStats; 2] {
+        self.cpu.bus.sound.fifo_stats()
+    }
+
+    /// Takes the audio samples made since the last call, left and right at the sample rate
+    /// of [`Self::audio_settings`].
+    pub fn take_audio_samples(&mut self) -> Vec<[i16; 2]> {
+        self.cpu.bus.sound.take_samples()
+    }
+
     pub fn step(&mut self) {
+        if self.boot_patches.pending && !self.is_in_bios() {
+            self.apply_boot_patches();
+        }
+        if self.cpu.bus.profile.is_fast() {
+            self.skip_idle_loop();
+        }
         self.cpu.step();
+
+        if let Some(device) = &mut self.joybus_device {
+            if let Some(command) = device.poll() {
+                device.respond(&self.cpu.bus.joy_bus_command(command));
+            }
+        }
+
+        if !self.cpu.bus.events.is_empty() {
+            if self.cpu.bus.events.contains(&CoreEvent::FrameComplete) {
+                self.count_held_keys_frame();
+                if !self.cheats.is_empty() {
+                    self.apply_cheats();
+                }
+                if self.divergence_check.is_some() {
+                    self.run_divergence_check();
+                }
+                self.run_commands();
+            }
+
+            self.dispatch_events();
+        }
+    }
+
+    /// Sets the values written when the BIOS jumps to the game, now if it already has.
+    /// They are written again after every reset.
+    pub fn set_boot_patches(&mut self, patches: Vec<BootPatch>) {
+        self.boot_patches.pending = !patches.is_empty();
+        self.boot_patches.patches = patches;
+    }
+
+    fn apply_cheats(&mut self) {
+        let mut cheats = std::mem::take(&mut self.cheats);
+        for cheat in cheats.iter_mut().filter(|cheat| cheat.enabled) {
+            if let Err(e) = self.load_memory(cheat.address, &cheat.bytes()) {
+                log(format!("can't apply the cheat {}: {e}", cheat.name));
+            }
+            if cheat.mode == CheatMode::Once {
+                cheat.enabled = false;
+            }
+        }
+        self.cheats = cheats;
+    }
+
+    fn apply_boot_patches(&mut self) {
+        self.boot_patches.pending = false;
+
+        let patches = std::mem::take(&mut self.boot_patches.patches);
+        for patch in &patches {
+            if let Err(e) = self.load_memory(patch.address, &patch.bytes()) {
+                log(format!(
+                    "can't apply the patch at {:#010X}: {e}",
+                    patch.address
+                ));
+            }
+        }
+        self.boot_patches.patches = patches;
+    }
+
+    /// Runs the hardware without the CPU while it waits for an interrupt in a branch to
+    /// itself, the loop would only spend the same cycles.
+    fn skip_idle_loop(&mut self) {
+        let is_thumb = self.cpu.is_thumb();
+        let address = self.cpu.next_instruction_address() as usize;
+        let op_code = if is_thumb {
+            u32::from(self.cpu.bus.read_half_word_raw(address))
+        } else {
+            self.cpu.bus.read_word_raw(address)
+        };
+
+        if is_idle_loop(op_code, is_thumb) {
+            self.cpu.bus.idle(IDLE_SKIP_CYCLES);
+        }
+    }
+
+    fn run_divergence_check(&mut self) {
+        // Taken out during the check, the frames it runs must not start another one.
+        let Some(mut check) = self.divergence_check.take() else {
+            return;
+        };
+
+        if check.frame_completed() {
+            match self.check_divergence(check.frames) {
+                Ok(Some(divergence)) => {
+                    log(format!("state divergence: {divergence}"));
+                    check.divergences.push(divergence);
+                }
+                Ok(None) => {}
+                Err(err) => log(format!("divergence check failed: {err}")),
+            }
+        }
+
+        self.divergence_check = Some(check);
+    }
+
+    /// Attaches a JOY Bus master (eg. a `GameCube` bridge) to the link port,
+    /// it returns the previous one.
+    pub fn connect_joybus(
+        &mut self,
+        device: Box<dyn JoybusDevice>,
+    ) -> Option<Box<dyn JoybusDevice>> {
+        self.joybus_device.replace(device)
+    }
+
+    pub fn disconnect_joybus(&mut self) -> Option<Box<dyn JoybusDevice>> {
+        self.joybus_device.take()
+    }
+
+    /// Registers `callback` to be called right after the step in which `event` happened.
+    /// Callbacks registered during a dispatch are called from the next event.
+    pub fn on_event(
+        &mut self,
+        event: CoreEvent,
+        callback: impl FnMut(&mut Self) + Send + 'static,
+    ) -> CallbackId {
+        self.hooks.add(event, Box::new(callback))
+    }
+
+    /// Returns `false` if there was no callback with this `id`.
+    /// A callback can't remove itself or another callback while it's being called.
+    pub fn remove_callback(&mut self, id: CallbackId) -> bool {
+        self.hooks.remove(id)
+    }
+
+    fn dispatch_events(&mut self) {
+        let events = std::mem::take(&mut self.cpu.bus.events);
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let mut hooks = self.hooks.take();
+        for event in events {
+            hooks.dispatch(event, self);
+        }
+
+        hooks.append(&mut self.hooks);
+        self.hooks = hooks;
+    }
+
+    /// A handle to send commands to this core from other threads, see [`CoreHandle`].
+    #[must_use]
+    pub fn handle(&self) -> CoreHandle {
+        self.commands.handle()
+    }
+
+    /// Runs the commands sent with the handles, they also run at the end of every frame.
+    pub fn run_commands(&mut self) {
+        while let Some(command) = self.commands.next() {
+            command(self);
+        }
+    }
+
+    /// Steps until the last line of the current frame has been drawn.
+    pub fn run_frame(&mut self) {
+        loop {
+            let vcount = self.cpu.bus.lcd.registers.vcount;
+            self.step();
+
+            if vcount != 0 && self.cpu.bus.lcd.registers.vcount == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Sets the state of the keys (`KEYINPUT`), a bit is cleared while its key is pressed.
+    pub fn set_key_input(&mut self, keys: u16) {
+        self.cpu.bus.set_key_input(keys);
+    }
+
+    /// Runs `frames` frames hashing the video and audio output, see [`RunHash`].
+    /// The audio samples are taken, the frontend doesn't get them.
+    pub fn hash_run(&mut self, frames: u64) -> RunHash {
+        let mut hasher = RunHasher::default();
+
+        for _ in 0..frames {
+            self.run_frame();
+            hasher.add_frame(&self.cpu.bus.lcd.buffer);
+            hasher.add_samples(&self.take_audio_samples());
+        }
+
+        hasher.hash()
+    }
+
+    /// Serializes the emulated machine, the same format used by save state files.
+    ///
+    /// # Errors
+    /// It returns an error if the serialization fails.
+    pub fn save_state(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(&self.cpu).map_err(|err| err.to_string())
+    }
+
+    /// Restores a state made by [`Self::save_state`].
+    ///
+    /// # Errors
+    /// It returns an error if `state` is not a valid state, the current one is kept.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        let cpu = bincode::deserialize(state).map_err(|err| err.to_string())?;
+        self.replace_cpu(cpu);
+
+        Ok(())
+    }
+
+    /// The screen of a state made by [`Self::save_state`], without loading it
+    /// (eg. for the thumbnail of a save slot).
+    ///
+    /// # Errors
+    /// It returns an error if `state` is not a valid state.
+    pub fn state_screen(
+        state: &[u8],
+    ) -> Result<Box<[[lcd::Color; LCD_WIDTH]; LCD_HEIGHT]>, String> {
+        let cpu: Arm7tdmi = bincode::deserialize(state).map_err(|err| err.to_string())?;
+
+        Ok(Box::new(cpu.bus.lcd.buffer))
+    }
+
+    /// A state with a thumbnail of the screen, the format of save state files.
+    ///
+    /// # Errors
+    /// It returns an error if the serialization fails.
+    pub fn save_state_file(&self) -> Result<Vec<u8>, String> {
+        let file = StateFile {
+            thumbnail: thumbnail(&self.cpu.bus.lcd.buffer)
+                .into_iter()
+                .map(|color| color.0)
+                .collect(),
+            state: self.save_state()?,
+        };
+
+        let mut encoded = STATE_FILE_MAGIC.to_vec();
+        encoded.extend(bincode::serialize(&file).map_err(|err| err.to_string())?);
+
+        Ok(encoded)
+    }
+
+    /// Restores a state file made by [`Self::save_state_file`] or an older one
+    /// made by [`Self::save_state`].
+    ///
+    /// # Errors
+    /// It returns an error if `data` is not a valid state file, the current state is kept.
+    pub fn load_state_file(&mut self, data: &[u8]) -> Result<(), String> {
+        match data.strip_prefix(STATE_FILE_MAGIC) {
+            Some(file) => {
+                let file: StateFile = bincode::deserialize(file).map_err(|err| err.to_string())?;
+                self.load_state(&file.state)
+            }
+            None => self.load_state(data),
+        }
+    }
+
+    /// The thumbnail of a state file, `THUMBNAIL_WIDTH` x `THUMBNAIL_HEIGHT` colors row
+    /// by row. Older files without one are decoded to make it.
+    ///
+    /// # Errors
+    /// It returns an error if `data` is not a valid state file.
+    pub fn state_file_thumbnail(data: &[u8]) -> Result<Vec<lcd::Color>, String> {
+        match data.strip_prefix(STATE_FILE_MAGIC) {
+            Some(file) => {
+                let file: StateFile = bincode::deserialize(file).map_err(|err| err.to_string())?;
+                if file.thumbnail.len() != THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT {
+                    return Err("Invalid thumbnail".to_owned());
+                }
+
+                Ok(file.thumbnail.into_iter().map(lcd::Color).collect())
+            }
+            None => Ok(thumbnail(&*Self::state_screen(data)?)),
+        }
+    }
+
+    /// Saves a state, runs `frames` frames, loads the state back and runs them again:
+    /// the two runs must end in the same state, otherwise some emulation state is not
+    /// saved and rollback netplay and rewind would desync.
+    /// The game goes on from the end of the first run. No callback is called during the
+    /// runs, their events are dropped, and coverage and profiler only see the first one.
+    ///
+    /// # Errors
+    /// It returns an error if the state can't be saved or loaded.
+    pub fn check_divergence(&mut self, frames: u64) -> Result<Option<Divergence>, String> {
+        let cycle = self.cpu.bus.cycles_count;
+        let start = self.save_state()?;
+        let hooks = self.hooks.take();
+        let events = std::mem::take(&mut self.cpu.bus.events);
+        let mut samples = self.take_audio_samples();
+
+        for _ in 0..frames {
+            self.run_frame();
+        }
+        samples.append(&mut self.take_audio_samples());
+        let first_parts = self.cpu.bus.state_parts();
+        let first = self.save_state();
+
+        let coverage = self.cpu.bus.coverage.take();
+        let timeline = self.cpu.bus.timeline.take();
+        let profiler = self.cpu.profiler.take();
+        let second = self.load_state(&start).map(|()| {
+            for _ in 0..frames {
+                self.run_frame();
+            }
+
+            (self.cpu.bus.state_parts(), self.save_state())
+        });
+
+        // Back at the end of the first run, whatever happened to the second one.
+        let restored = first
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|first| self.load_state(first));
+        self.hooks = hooks;
+        self.cpu.bus.events = events;
+        self.cpu.bus.coverage = coverage;
+        self.cpu.bus.timeline = timeline;
+        self.cpu.profiler = profiler;
+        self.cpu.bus.sound.push_samples(samples);
+
+        let (first, first_parts) = (first?, first_parts?);
+        restored?;
+        let (second_parts, second) = second?;
+        if first == second? {
+            return Ok(None);
+        }
+
+        Ok(Some(Divergence {
+            cycle,
+            frames,
+            part: first_difference(&first_parts, &second_parts?).unwrap_or("cpu"),
+        }))
+    }
+
+    #[must_use]
+    pub fn is_in_bios(&self) -> bool {
+        self.cpu.registers.program_counter() <= BIOS_END
+    }
+
+    /// Fast-forwards the BIOS intro (Nintendo logo animation) for at most `max_steps` steps.
+    /// The BIOS still runs so its initialization is preserved, only the wait is skipped.
+    /// It returns `true` once the BIOS has jumped to the cartridge.
+    pub fn skip_bios_intro(&mut self, max_steps: u64) -> bool {
+        for _ in 0..max_steps {
+            if !self.is_in_bios() {
+                return true;
+            }
+
+            self.step();
+        }
+
+        !self.is_in_bios()
+    }
+
+    /// Loads the symbols of an ELF, `.sym` or `.map` file and, for ELFs, the source lines.
+    ///
+    /// # Errors
+    /// It returns an error if the file can't be read, the current debug info is kept.
+    pub fn load_debug_info(&mut self, data: &[u8]) -> Result<(), String> {
+        let symbols = Symbols::parse(data)?;
+        let line_info = if data.starts_with(b"\x7fELF") {
+            LineInfo::parse(data)?
+        } else {
+            LineInfo::default()
+        };
+
+        self.cpu.symbols = symbols;
+        self.cpu.line_info = line_info;
+
+        Ok(())
+    }
+
+    /// Source line of the next instruction, `None` without debug info for it.
+    #[must_use]
+    pub fn source_location(&self) -> Option<Location> {
+        self.cpu
+            .line_info
+            .location(self.cpu.next_instruction_address())
+    }
+
+    /// Runs until the next instruction is on a source line other than `from`, for at most
+    /// `max_steps` steps. Code without debug info (eg. the BIOS) is run through.
+    /// It returns `true` once a new line is reached.
+    pub fn step_line(&mut self, from: Option<Location>, max_steps: u64) -> bool {
+        for _ in 0..max_steps {
+            self.step();
+
+            let location = self.source_location();
+            if location.is_some() && location != from {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Reads `length` bytes from `address` without side effects, eg. to extract assets.
+    ///
+    /// # Errors
+    /// It returns an error if the range goes past the address space or touches SRAM.
+    #[allow(clippy::cast_possible_truncation)] // Addresses of the range fit in 32 bits.
+    pub fn dump_memory(&self, address: u32, length: u32) -> Result<Vec<u8>, String> {
+        let range = checked_range(address, u64::from(length))?;
+
+        Ok(range
+            .map(|address| self.cpu.bus.read_raw(address as usize))
+            .collect())
+    }
+
+    /// Reads a byte without side effects, SRAM included, eg. for achievements.
+    #[must_use]
+    pub fn peek(&self, address: u32) -> u8 {
+        self.cpu.bus.read_raw(address as usize)
+    }
+
+    /// Writes `data` starting from `address` without side effects, like [`Self::dump_memory`].
+    ///
+    /// # Errors
+    /// It returns an error if the range goes past the address space or touches SRAM,
+    /// nothing is written then.
+    #[allow(clippy::cast_possible_truncation)] // Addresses of the range fit in 32 bits.
+    pub fn load_memory(&mut self, address: u32, data: &[u8]) -> Result<(), String> {
+        let range = checked_range(address, data.len() as u64)?;
+
+        for (address, &value) in range.zip(data) {
+            self.cpu.bus.write_raw(address as usize, value);
+        }
+
+        Ok(())
+    }
+
+    /// Color `index` (0-255) of a palette, the BG color 0 is the backdrop.
+    #[must_use]
+    pub fn palette_color(&self, palette: &PaletteType, index: usize) -> Color {
+        let address = palette_address(palette, index);
+
+        Color(u16::from_le_bytes([
+            self.cpu.bus.read_raw(address),
+            self.cpu.bus.read_raw(address + 1),
+        ]))
+    }
+
+    /// Changes a color through the bus, the frame is rendered with it at once and
+    /// the game can still overwrite it.
+    pub fn set_palette_color(&mut self, palette: &PaletteType, index: usize, color: Color) {
+        let address = palette_address(palette, index);
+        let [low, high] = color.0.to_le_bytes();
+
+        self.cpu.bus.write_raw(address, low);
+        self.cpu.bus.write_raw(address + 1, high);
+    }
+
+    /// Runs until the next instruction is at one of `addresses`, for at most `max_steps` steps.
+    /// It returns `true` once one is reached.
+    pub fn run_to(&mut self, addresses: &[u32], max_steps: u64) -> bool {
+        for _ in 0..max_steps {
+            self.step();
+
+            if addresses.contains(&self.cpu.next_instruction_address()) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+const fn palette_address(palette: &PaletteType, index: usize) -> usize {
+    let offset = match palette {
+        PaletteType::BG => 0,
+        PaletteType::OBJ => OBJ_PALETTE_OFFSET,
+    };
+
+    PALETTE_RAM_START + offset + (index % 256) * 2
+}
+
+fn checked_range(address: u32, length: u64) -> Result<std::ops::Range<u64>, String> {
+    let range = u64::from(address)..u64::from(address) + length;
+
+    if range.end > 1 << 32 {
+        return Err("The range goes past the end of the address space".to_string());
+    }
+    if range.start < SRAM.end && SRAM.start < range.end {
+        return Err("SRAM can't be dumped or loaded".to_string());
+    }
+
+    Ok(range)
+}
+
+/// The screen halved on both sides, every pixel the average of four.
+#[allow(clippy::cast_possible_truncation)]
+fn thumbnail(buffer: &[[lcd::Color; LCD_WIDTH]; LCD_HEIGHT]) -> Vec<lcd::Color> {
+    let average = |colors: [lcd::Color; 4], channel: fn(&lcd::Color) -> u8| {
+        let sum: u16 = colors.iter().map(|color| u16::from(channel(color))).sum();
+        (sum / 4) as u8
+    };
+
+    buffer
+        .chunks_exact(2)
+        .flat_map(|rows| {
+            (0..THUMBNAIL_WIDTH).map(move |x| {
+                let colors = [
+                    rows[0][2 * x],
+                    rows[0][2 * x + 1],
+                    rows[1][2 * x],
+                    rows[1][2 * x + 1],
+                ];
+
+                lcd::Color::from_rgb(
+                    average(colors, lcd::Color::red),
+                    average(colors, lcd::Color::green),
+                    average(colors, lcd::Color::blue),
+                )
+            })
+        })
+        .collect()
+}
+
+/// A console with `rom` and a BIOS looping on its first instruction (`B .`), for the tests
+/// where the CPU only has to keep the time going.
+#[cfg(test)]
+pub(crate) fn test_gba(rom: Vec<u8>) -> Gba {
+    test_gba_with_bios(rom, &[0xEAFF_FFFE])
+}
+
+/// A console with `rom` and a BIOS starting with the ARM `program`, for the tests.
+#[cfg(test)]
+pub(crate) fn test_gba_with_bios(rom: Vec<u8>, program: &[u32]) -> Gba {
+    let mut bios = [0; 0x0000_4000];
+    for (bytes, opcode) in bios.chunks_mut(4).zip(program) {
+        bytes.copy_from_slice(&opcode.to_le_bytes());
+    }
+    let header = Header::new(&rom).unwrap();
+
+    Gba::new(header, bios, rom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boot_patch::PatchWidth;
+    use crate::cpu::cpu_modes::Mode;
+    use crate::cpu::hardware::lcd::FrameSkip;
+    use crate::cpu::registers::REG_SP;
+    use crate::cpu::test_dsl::assemble;
+
+    /// MOV R0, #0x08000000 and BX R0: the BIOS jumps to the game.
+    const BOOT_TO_ROM: [u32; 2] = [0xE3A0_0302, 0xE12F_FF10];
+
+    #[test]
+    fn skip_bios_intro() {
+        let mut gba = test_gba_with_bios(vec![0; 0x200], &BOOT_TO_ROM);
+
+        assert!(gba.is_in_bios());
+        assert!(!gba.skip_bios_intro(1));
+        assert!(gba.skip_bios_intro(100));
+        assert!(!gba.is_in_bios());
+    }
+
+    #[test]
+    fn boot_patches() {
+        let mut gba = test_gba_with_bios(vec![0; 0x200], &BOOT_TO_ROM);
+        gba.set_boot_patches(vec![
+            BootPatch {
+                address: 0x0300_0000,
+                value: 0x1234,
+                width: PatchWidth::HalfWord,
+            },
+            BootPatch {
+                address: 0x0800_0100,
+                value: 0x42,
+                width: PatchWidth::Byte,
+            },
+        ]);
+
+        // Written once the BIOS has jumped to the game.
+        gba.step();
+        assert_eq!(gba.peek(0x0300_0000), 0);
+        assert!(gba.skip_bios_intro(100));
+        gba.step();
+        assert_eq!(gba.peek(0x0300_0000), 0x34);
+        assert_eq!(gba.peek(0x0300_0001), 0x12);
+        assert_eq!(gba.peek(0x0800_0100), 0x42);
+
+        // Only once, the game can change the value.
+        gba.load_memory(0x0300_0000, &[7]).unwrap();
+        gba.step();
+        assert_eq!(gba.peek(0x0300_0000), 7);
+
+        gba.reset().unwrap();
+        assert!(gba.skip_bios_intro(100));
+        gba.step();
+        assert_eq!(gba.peek(0x0300_0000), 0x34);
+    }
+
+    #[test]
+    fn run_to() {
+        // MOV R0, #1; MOV R1, #2; MOV R2, #3
+        let program = [0xE3A0_0001, 0xE3A0_1002, 0xE3A0_2003];
+        let mut gba = test_gba_with_bios(vec![0; 0x200], &program);
+
+        assert_eq!(gba.cpu.next_instruction_address(), 0);
+        assert!(!gba.run_to(&[8], 2));
+        assert!(gba.run_to(&[8], 100));
+        assert_eq!(gba.cpu.next_instruction_address(), 8);
+        assert_eq!(gba.cpu.registers.register_at(1), 2);
+        assert_eq!(gba.cpu.registers.register_at(2), 0);
+    }
+
+    #[test]
+    fn dump_and_load_memory() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        gba.load_memory(0x0200_0010, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(
+            gba.dump_memory(0x0200_000E, 8).unwrap(),
+            vec![0, 0, 1, 2, 3, 4, 0, 0]
+        );
+        // Mirror of EWRAM
+        assert_eq!(gba.dump_memory(0x0204_0010, 2).unwrap(), vec![1, 2]);
+        assert_eq!(gba.cpu.bus.read_word(0x0200_0010), 0x0403_0201);
+
+        assert!(gba.dump_memory(0x0DFF_FFFF, 2).is_err());
+        assert!(gba.load_memory(0xFFFF_FFFF, &[1, 2]).is_err());
+        assert!(gba.dump_memory(0xFFFF_FFFF, 1).is_ok());
+    }
+
+    #[test]
+    fn palette_color() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        gba.set_palette_color(&PaletteType::OBJ, 3, Color::from_rgb(31, 0, 1));
+        assert_eq!(gba.palette_color(&PaletteType::OBJ, 3).0, 0x041F);
+        assert_eq!(gba.palette_color(&PaletteType::BG, 3).0, 0);
+        assert_eq!(gba.cpu.bus.lcd.memory.obj_palette_ram[6..8], [0x1F, 0x04]);
+    }
+
+    #[test]
+    fn deterministic_state() {
+        let run = || {
+            let mut rom = vec![0; 0x200];
+            rom[0x100..0x108].copy_from_slice(b"SIIRTC_V");
+            let mut gba = test_gba(rom);
+            gba.set_rtc_timestamp(1_000_000_000);
+
+            // Unused memory and debug RAM past the ROM are kept in maps.
+            for address in [
+                0x0000_5000,
+                0x0000_4000,
+                0x1000_0000,
+                0x0800_1000,
+                0x0800_0400,
+            ] {
+                gba.load_memory(address, &[1, 2, 3]).unwrap();
+            }
+            for _ in 0..1000 {
+                gba.step();
+            }
+
+            gba.save_state().unwrap()
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn hash_run() {
+        let run = || test_gba(vec![0; 0x200]).hash_run(1);
+
+        let hash = run();
+        assert_eq!(hash.frames, 1);
+        assert_eq!(hash, run());
+    }
+
+    #[test]
+    fn frame_skip() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.cpu.bus.lcd.frame_skip = FrameSkip::Fixed(1);
+
+        let frame_color = |gba: &mut Gba, red| {
+            gba.set_palette_color(&PaletteType::BG, 0, Color::from_rgb(red, 0, 0));
+            gba.run_frame();
+            gba.cpu.bus.lcd.buffer[159][239].red()
+        };
+
+        // Every other frame is drawn, the buffer keeps the last one meanwhile
+        assert_eq!(frame_color(&mut gba, 1), 1);
+        assert_eq!(frame_color(&mut gba, 2), 1);
+        assert_eq!(frame_color(&mut gba, 3), 3);
+
+        gba.reset().unwrap();
+        assert_eq!(gba.cpu.bus.lcd.frame_skip, FrameSkip::Fixed(1));
+    }
+
+    #[test]
+    fn fast_profile() {
+        let new_gba = || {
+            // The BIOS waits in `b .` from the start.
+            test_gba_with_bios(vec![0; 0x200], &[0xEAFF_FFFE])
+        };
+        let run = |gba: &mut Gba| {
+            for _ in 0..100 {
+                gba.step();
+            }
+            gba.cpu.bus.cycles_count
+        };
+
+        let mut accurate = new_gba();
+        let mut fast = new_gba();
+        fast.set_profile(CoreProfile::Fast);
+        assert!(fast.cpu.decode_cache.is_some());
+
+        // The loop is skipped a scanline at a time.
+        assert!(run(&mut fast) > run(&mut accurate) * 100);
+        assert_eq!(fast.cpu.next_instruction_address(), 0);
+
+        // Unused memory reads as zero instead of the last instruction.
+        assert_eq!(accurate.cpu.bus.read_raw(0x1000_0000), 0xFE);
+        assert_eq!(fast.cpu.bus.read_raw(0x1000_0000), 0);
+
+        fast.reset().unwrap();
+        assert_eq!(fast.profile(), CoreProfile::Fast);
+        assert!(fast.cpu.decode_cache.is_some());
+
+        fast.set_profile(CoreProfile::Accuracy);
+        assert!(fast.cpu.decode_cache.is_none());
+    }
+
+    #[test]
+    fn cheats() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        let cheat = |address, mode| Cheat {
+            name: String::new(),
+            address,
+            value: 0x63,
+            width: PatchWidth::Byte,
+            mode,
+            enabled: true,
+        };
+        gba.cheats = vec![
+            cheat(0x0300_0000, CheatMode::Freeze),
+            cheat(0x0300_0004, CheatMode::Once),
+        ];
+
+        gba.run_frame();
+        assert_eq!(gba.peek(0x0300_0000), 0x63);
+        assert_eq!(gba.peek(0x0300_0004), 0x63);
+        assert!(!gba.cheats[1].enabled);
+
+        // The game changes both, only the frozen one is written again.
+        gba.load_memory(0x0300_0000, &[1]).unwrap();
+        gba.load_memory(0x0300_0004, &[1]).unwrap();
+        gba.run_frame();
+        assert_eq!(gba.peek(0x0300_0000), 0x63);
+        assert_eq!(gba.peek(0x0300_0004), 1);
+    }
+
+    #[test]
+    fn check_divergence() {
+        // Loading the states needs more than the default stack of test threads.
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let new_gba = || test_gba(vec![0; 0x200]);
+                let mut gba = new_gba();
+                let mut expected = new_gba();
+
+                assert_eq!(gba.check_divergence(1), Ok(None));
+
+                expected.run_frame();
+                assert_eq!(gba.save_state(), expected.save_state());
+            })
+            .unwrap();
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn rtc_timestamp() {
+        let mut rom = vec![0; 0x200];
+        assert_eq!(test_gba(rom.clone()).rtc_timestamp(), None);
+
+        rom[0x100..0x108].copy_from_slice(b"SIIRTC_V");
+        let mut gba = test_gba(rom);
+        gba.set_rtc_timestamp(1_000_000_000);
+        assert_eq!(gba.rtc_timestamp(), Some(1_000_000_000));
+
+        gba.reset().unwrap();
+        assert_eq!(gba.rtc_timestamp(), Some(1_000_000_000));
+    }
+
+    #[test]
+    fn hard_reset_keeps_the_backup() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        gba.cpu.bus.internal_memory.sram[3] = 0x42;
+        gba.hard_reset().unwrap();
+        assert_eq!(gba.cpu.bus.internal_memory.sram[3], 0x42);
+
+        gba.reset().unwrap();
+        assert_eq!(gba.cpu.bus.internal_memory.sram[3], 0xFF);
+    }
+
+    #[test]
+    fn replace_cartridge() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.cpu.bus.internal_memory.sram[3] = 0x42;
+
+        let mut rom = vec![0; 0x400];
+        rom[0xA0..0xA4].copy_from_slice(b"TEST");
+        gba.replace_cartridge(rom.clone(), None, true).unwrap();
+        assert!(gba.cartridge_header.game_title.starts_with("TEST"));
+        assert_eq!(gba.cpu.bus.internal_memory.rom, rom);
+        assert_eq!(gba.cpu.bus.internal_memory.sram[3], 0x42);
+
+        gba.replace_cartridge(rom, Some([1; 0x0000_4000]), false)
+            .unwrap();
+        assert_eq!(gba.cpu.bus.internal_memory.bios()[0], 1);
+        assert_eq!(gba.cpu.bus.internal_memory.sram[3], 0xFF);
+
+        assert!(gba.replace_cartridge(vec![0; 4], None, true).is_err());
+        assert_eq!(gba.cpu.bus.internal_memory.rom.len(), 0x400);
+    }
+
+    #[test]
+    fn write_backup() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        gba.write_backup(0x10, &[1, 2]).unwrap();
+        assert_eq!(gba.backup()[0x0F..0x13], [0xFF, 1, 2, 0xFF]);
+
+        assert!(gba.write_backup(0xFFFF, &[1, 2]).is_err());
+        assert_eq!(gba.backup()[0xFFFF], 0xFF);
+    }
+
+    #[test]
+    fn set_backup_pads_small_saves() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        gba.cpu.bus.internal_memory.sram[0x9000] = 0x42;
+        gba.set_backup(&[1, 2, 3]).unwrap();
+        assert_eq!(&gba.backup()[..4], [1, 2, 3, 0xFF]);
+        assert_eq!(gba.backup()[0x9000], 0xFF);
+        assert_eq!(gba.backup().len(), 0x1_0000);
+
+        assert!(gba.set_backup(&vec![0; 0x2_0000]).is_err());
+        assert_eq!(gba.backup()[0], 1);
+    }
+
+    #[test]
+    fn soft_reset_holds_the_keys() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.set_key_input(NO_KEYS_PRESSED & !0x10);
+
+        gba.soft_reset();
+        assert_eq!(gba.cpu.bus.key_input(), 0x03F0);
+        for _ in 1..SOFT_RESET_FRAMES {
+            gba.run_frame();
+        }
+        assert_eq!(gba.cpu.bus.key_input(), 0x03F0);
+
+        gba.run_frame();
+        assert_eq!(gba.cpu.bus.key_input(), NO_KEYS_PRESSED & !0x10);
+    }
+
+    #[test]
+    fn hold_keys() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        gba.set_key_input(NO_KEYS_PRESSED);
+
+        gba.hold_keys(NO_KEYS_PRESSED & !0x01, 2);
+        gba.run_frame();
+        assert_eq!(gba.cpu.bus.key_input(), NO_KEYS_PRESSED & !0x01);
+
+        // Held again before the end, the keys of before are still restored.
+        gba.hold_keys(NO_KEYS_PRESSED & !0x08, 2);
+        gba.run_frame();
+        assert_eq!(gba.cpu.bus.key_input(), NO_KEYS_PRESSED & !0x08);
+        gba.run_frame();
+        assert_eq!(gba.cpu.bus.key_input(), NO_KEYS_PRESSED);
+    }
+
+    #[test]
+    fn start_multiboot_image() {
+        let mut image = vec![0; 0x200];
+        // MOV R0, #1
+        image[0x100..0x104].copy_from_slice(&0xE3A0_0001_u32.to_le_bytes());
+        // B image start + 0x100
+        image[0..4].copy_from_slice(&0xEA00_003E_u32.to_le_bytes());
+
+        let mut gba = Gba::with_multiboot([0; 0x0000_4000], &image).unwrap();
+
+        assert_eq!(
+            gba.cpu.bus.read_word(MULTIBOOT_START as usize + 0x100),
+            0xE3A0_0001
+        );
+        assert_eq!(gba.cpu.registers.register_at(REG_SP), 0x0300_7F00);
+        assert_eq!(gba.cpu.cpsr.mode(), Mode::System);
+
+        // Branch is decoded and executed after 3 steps, MOV after other 3 steps.
+        (0..6).for_each(|_| gba.step());
+        assert_eq!(gba.cpu.registers.register_at(0), 1);
+
+        assert!(Gba::with_multiboot([0; 0x0000_4000], &vec![0; MULTIBOOT_MAX_SIZE + 1]).is_err());
+    }
+
+    /// A ROM of 0x200 bytes with the ARM `code` from each of the offsets. The lines are
+    /// assembled at their address, the opcodes are kept.
+    fn assembled_rom(code: &[(u32, &[&str])]) -> Vec<u8> {
+        let mut rom = vec![0; 0x200];
+        for &(start, lines) in code {
+            for (offset, line) in (start..).step_by(4).zip(lines) {
+                let opcode = u32::from_str_radix(line, 16)
+                    .unwrap_or_else(|_| assemble(line, 0x0800_0000 + offset));
+                let offset = offset as usize;
+                rom[offset..offset + 4].copy_from_slice(&opcode.to_le_bytes());
+            }
+        }
+
+        rom
+    }
+
+    #[test]
+    fn boot_to_the_game_without_bios() {
+        let rom = vec![0; 0x200];
+        let mut gba = Gba::new(Header::new(&rom).unwrap(), [0; 0x0000_4000], rom);
+
+        for _ in 0..2 {
+            assert!(gba.cpu.hle_bios);
+            assert_eq!(gba.cpu.bus.internal_memory.bios(), bios_hle::stub_bios());
+            assert_eq!(gba.cpu.next_instruction_address(), 0x0800_0000);
+            assert_eq!(gba.cpu.cpsr.mode(), Mode::System);
+            assert_eq!(gba.cpu.registers.register_at(REG_SP), 0x0300_7F00);
+
+            // The stub is kept in place of the BIOS.
+            gba.reset().unwrap();
+        }
+    }
+
+    #[test]
+    fn vblank_intr_wait_without_bios() {
+        let rom = assembled_rom(&[
+            (
+                0,
+                &[
+                    // The IRQ handler at 0x08000100.
+                    "MOV R0, #0x03000000",
+                    "ADD R0, R0, #0x7F00",
+                    "ADD R0, R0, #0xFC",
+                    "MOV R1, #0x08000000",
+                    "ADD R1, R1, #0x100",
+                    "STR R1, [R0]",
+                    // The VBlank interrupt in DISPSTAT and IE.
+                    "MOV R2, #0x04000000",
+                    "MOV R3, #8",
+                    "STR R3, [R2, #4]",
+                    "MOV R3, #1",
+                    "STR R3, [R2, #0x200]",
+                    // SWI 0x05 (VBlankIntrWait)
+                    "EF050000",
+                    "MOV R4, #1",
+                    "B #0x08000034",
+                ],
+            ),
+            (
+                0x100,
+                &[
+                    // Sets the flag of the BIOS and acknowledges the interrupt.
+                    "MOV R0, #0x03000000",
+                    "ADD R0, R0, #0x7F00",
+                    "ADD R0, R0, #0xF8",
+                    "MOV R1, #1",
+                    "STR R1, [R0]",
+                    "MOV R0, #0x04000000",
+                    "MOV R1, #0x10000",
+                    "ADD R1, R1, #1",
+                    "STR R1, [R0, #0x200]",
+                    // BX LR
+                    "E12FFF1E",
+                ],
+            ),
+        ]);
+        let mut gba = Gba::new(Header::new(&rom).unwrap(), [0; 0x0000_4000], rom);
+
+        for _ in 0..10_000 {
+            if gba.cpu.registers.register_at(4) == 1 {
+                break;
+            }
+            gba.step();
+        }
+
+        // It returns after the first VBlank, at line 160, with the flag acknowledged.
+        assert_eq!(gba.cpu.registers.register_at(4), 1);
+        assert!(gba.cycles() >= 160 * 1232);
+        assert_eq!(gba.peek(0x0300_7FF8), 0);
+        assert_eq!(gba.cpu.cpsr.mode(), Mode::System);
+    }
+
+    #[test]
+    fn sound_driver_without_bios() {
+        let rom = assembled_rom(&[(
+            0,
+            &[
+                "MOV R0, #1",
+                // SWI 0x19 (SoundBias), 0x1A (SoundDriverInit) and 0x1C (SoundDriverMain)
+                "EF190000",
+                "EF1A0000",
+                "EF1C0000",
+                "MOV R4, #1",
+                "B #0x08000014",
+            ],
+        )]);
+        let mut gba = Gba::new(Header::new(&rom).unwrap(), [0; 0x0000_4000], rom);
+
+        (0..20).for_each(|_| gba.step());
+
+        // The bias is set and the driver calls return without doing anything.
+        assert_eq!(gba.cpu.bus.read_half_word(0x0400_0088) & 0x3FF, 0x200);
+        assert_eq!(gba.cpu.registers.register_at(4), 1);
+        assert_eq!(gba.cpu.cpsr.mode(), Mode::System);
+    }
+
+    #[test]
+    fn functions_left_to_the_bios_return_without_bios() {
+        // SWI 0x10 (BitUnPack) goes to the exception vector of the stub.
+        let rom = assembled_rom(&[(0, &["EF100000", "MOV R4, #1", "B #0x08000008"])]);
+        let mut gba = Gba::new(Header::new(&rom).unwrap(), [0; 0x0000_4000], rom);
+
+        (0..20).for_each(|_| gba.step());
+
+        assert_eq!(gba.cpu.registers.register_at(4), 1);
+        assert_eq!(gba.cpu.cpsr.mode(), Mode::System);
+    }
+
+    #[test]
+    fn event_callbacks() {
+        let mut gba = test_gba(vec![0; 0x200]);
+
+        let vblanks = Arc::new(Mutex::new(0));
+        let frames = Arc::new(Mutex::new(0));
+        let vblanks_clone = Arc::clone(&vblanks);
+        let vblank_id = gba.on_event(CoreEvent::VBlank, move |gba| {
+            assert_eq!(gba.cpu.bus.lcd.registers.vcount, 160);
+            *vblanks_clone.lock().unwrap() += 1;
+        });
+        let frames_clone = Arc::clone(&frames);
+        gba.on_event(CoreEvent::FrameComplete, move |_| {
+            *frames_clone.lock().unwrap() += 1;
+        });
+
+        gba.cpu.bus.lcd.registers.vcount = 159;
+        while gba.cpu.bus.lcd.registers.vcount != 161 {
+            gba.step();
+        }
+        assert_eq!(*vblanks.lock().unwrap(), 1);
+        assert_eq!(*frames.lock().unwrap(), 0);
+
+        assert!(gba.remove_callback(vblank_id));
+        assert!(!gba.remove_callback(vblank_id));
+
+        gba.cpu.bus.lcd.registers.vcount = 227;
+        while gba.cpu.bus.lcd.registers.vcount != 1 {
+            gba.step();
+        }
+        assert_eq!(*frames.lock().unwrap(), 1);
+
+        gba.cpu.bus.lcd.registers.vcount = 159;
+        while gba.cpu.bus.lcd.registers.vcount != 161 {
+            gba.step();
+        }
+        assert_eq!(*vblanks.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn save_and_load_state() {
+        // Deserializing the machine needs more than the default stack of test threads.
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let mut gba = test_gba(vec![0; 0x200]);
+
+                let state = gba.save_state().unwrap();
+                let program_counter = gba.cpu.registers.program_counter();
+                (0..10).for_each(|_| gba.step());
+                assert_ne!(gba.cpu.registers.program_counter(), program_counter);
+
+                gba.load_state(&state).unwrap();
+                assert_eq!(gba.cpu.registers.program_counter(), program_counter);
+                assert!(gba.load_state(&[1, 2, 3]).is_err());
+
+                gba.cpu.bus.lcd.buffer[1][2] = lcd::Color(0x7FFF);
+                let screen = Gba::state_screen(&gba.save_state().unwrap()).unwrap();
+                assert_eq!(screen[1][2].0, 0x7FFF);
+                assert!(Gba::state_screen(&[1, 2, 3]).is_err());
+            })
+            .unwrap();
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn save_and_load_state_file() {
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let mut gba = test_gba(vec![0; 0x200]);
+
+                gba.cpu.bus.lcd.buffer[2][4] = lcd::Color(0x7FFF);
+                gba.cpu.bus.lcd.buffer[3][5] = lcd::Color::from_rgb(8, 4, 0);
+                let file = gba.save_state_file().unwrap();
+                let program_counter = gba.cpu.registers.program_counter();
+                (0..10).for_each(|_| gba.step());
+
+                gba.load_state_file(&file).unwrap();
+                assert_eq!(gba.cpu.registers.program_counter(), program_counter);
+
+                let thumbnail = Gba::state_file_thumbnail(&file).unwrap();
+                assert_eq!(thumbnail.len(), THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT);
+                let pixel = thumbnail[THUMBNAIL_WIDTH + 2];
+                assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (9, 8, 7));
+
+                // Files saved before the thumbnails are bare states.
+                let old_file = gba.save_state().unwrap();
+                gba.load_state_file(&old_file).unwrap();
+                let old_thumbnail = Gba::state_file_thumbnail(&old_file).unwrap();
+                assert_eq!(old_thumbnail[THUMBNAIL_WIDTH + 2].0, pixel.0);
+
+                assert!(gba.load_state_file(b"CLMSTATE garbage").is_err());
+                assert!(Gba::state_file_thumbnail(&[1, 2, 3]).is_err());
+            })
+            .unwrap();
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn classic_nes_protections() {
+        use crate::cpu::test_dsl::assemble;
+
+        let program = [
+            "MOV R0, #0x08000000",
+            // Past the end of the ROM
+            "LDR R1, [R0, #0x400]",
+            "MOV R2, #0x10000000",
+            // Unmapped, the last instruction fetched
+            "LDR R3, [R2]",
+            "MOV R4, #0x03000000",
+            "MOV R5, #0x03000000",
+        ];
+        let mut opcodes: Vec<u32> = (0..)
+            .step_by(4)
+            .zip(program)
+            .map(|(address, line)| assemble(line, address))
+            .collect();
+        // STMIA R5!, {R4, R5}: the written back base is stored as second register.
+        opcodes.push(0xE8A5_0030);
+
+        let mut rom = vec![0; 0x400];
+        rom[0..4].copy_from_slice(&0x1122_3344_u32.to_le_bytes());
+        rom[0xAC..0xB0].copy_from_slice(b"FBME");
+        let mut gba = test_gba_with_bios(rom, &opcodes);
+
+        assert!(gba.run_to(&[28], 100));
+        let registers = &gba.cpu.registers;
+        assert_eq!(registers.register_at(1), 0x1122_3344);
+        // The LDR is at 12, the CPU has fetched 8 bytes ahead.
+        assert_eq!(registers.register_at(3), assemble(program[5], 20));
+        assert_eq!(gba.cpu.bus.read_word(0x0300_0004), 0x0300_0008);
+        assert_eq!(gba.cpu.bus.read_word(0x0300_0000), 0x0300_0000);
     }
 }
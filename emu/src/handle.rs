@@ -0,0 +1,216 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::gba::Gba;
+
+/// Something to do with the core, run by the thread of the emulation between two steps.
+pub type Command = Box<dyn FnOnce(&mut Gba) + Send>;
+
+/// Commands sent with a [`CoreHandle`], waiting for the core to run them.
+pub(crate) struct CommandQueue {
+    sender: Sender<Command>,
+    receiver: Receiver<Command>,
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self { sender, receiver }
+    }
+}
+
+impl CommandQueue {
+    pub(crate) fn handle(&self) -> CoreHandle {
+        CoreHandle {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// The next command waiting, if any.
+    pub(crate) fn next(&self) -> Option<Command> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Talks to a core owned by another thread without locking it.
+///
+/// The commands are queued and run by the core at the end of the frame, or when the host
+/// calls [`Gba::run_commands`] (eg. while paused). It can be cloned and sent to any
+/// thread, such as the one of an async frontend or of a debugger stub.
+#[derive(Clone)]
+pub struct CoreHandle {
+    sender: Sender<Command>,
+}
+
+impl CoreHandle {
+    /// Queues `command` without waiting for it. It returns `false` if the core is gone.
+    pub fn send(&self, command: impl FnOnce(&mut Gba) + Send + 'static) -> bool {
+        self.sender.send(Box::new(command)).is_ok()
+    }
+
+    /// Queues `query`, its result can be polled or awaited with the returned [`Reply`].
+    pub fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&mut Gba) -> T + Send + 'static,
+    ) -> Reply<T> {
+        let slot = Arc::new(Mutex::new(ReplySlot::default()));
+        let answer = Answer {
+            slot: Arc::clone(&slot),
+        };
+
+        // When the core is gone the command is dropped with the answer, closing the reply.
+        self.send(move |gba| answer.set(query(gba)));
+
+        Reply { slot }
+    }
+}
+
+struct ReplySlot<T> {
+    value: Option<T>,
+    /// The query was run or dropped, nothing more will come.
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl<T> Default for ReplySlot<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            closed: false,
+            waker: None,
+        }
+    }
+}
+
+/// The side of the reply held by the queued query.
+struct Answer<T> {
+    slot: Arc<Mutex<ReplySlot<T>>>,
+}
+
+impl<T> Answer<T> {
+    fn set(self, value: T) {
+        self.slot.lock().unwrap().value = Some(value);
+    }
+}
+
+impl<T> Drop for Answer<T> {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().unwrap();
+        slot.closed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The result of a [`CoreHandle::query`]. It can be checked without blocking with
+/// [`Self::try_take`] or awaited, the error tells that the core stopped before running it.
+pub struct Reply<T> {
+    slot: Arc<Mutex<ReplySlot<T>>>,
+}
+
+impl<T> Reply<T> {
+    /// The result if the core has run the query, `None` if it hasn't yet.
+    ///
+    /// # Errors
+    /// It returns an error if the core stopped before running the query, or if the result
+    /// was already taken.
+    ///
+    /// # Panics
+    /// It panics if the thread of the core panicked while answering.
+    pub fn try_take(&self) -> Result<Option<T>, String> {
+        let mut slot = self.slot.lock().unwrap();
+
+        match slot.value.take() {
+            Some(value) => Ok(Some(value)),
+            None if slot.closed => Err("The core stopped before answering".to_string()),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> Future for Reply<T> {
+    type Output = Result<T, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap();
+
+        if let Some(value) = slot.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if slot.closed {
+            return Poll::Ready(Err("The core stopped before answering".to_string()));
+        }
+
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::gba::test_gba;
+
+    #[test]
+    fn core_and_handle_are_send() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<Gba>();
+        assert_send::<CoreHandle>();
+        assert_send::<Reply<u32>>();
+    }
+
+    #[test]
+    fn commands_run_on_the_thread_of_the_core() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        let handle = gba.handle();
+
+        let reply = std::thread::spawn(move || {
+            assert!(handle.send(|gba| gba.cpu.registers.set_register_at(0, 42)));
+            handle.query(|gba| gba.cpu.registers.register_at(0))
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(reply.try_take(), Ok(None));
+        gba.run_commands();
+        assert_eq!(reply.try_take(), Ok(Some(42)));
+    }
+
+    #[test]
+    fn commands_run_at_the_end_of_the_frame() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        let reply = gba.handle().query(|gba| gba.cpu.bus.lcd.registers.vcount);
+
+        gba.run_frame();
+        assert_eq!(reply.try_take(), Ok(Some(0)));
+    }
+
+    #[test]
+    fn reply_can_be_awaited() {
+        let mut gba = test_gba(vec![0; 0x200]);
+        let mut reply = gba.handle().query(|_| "done");
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(Pin::new(&mut reply).poll(&mut cx), Poll::Pending);
+        gba.run_commands();
+        assert_eq!(Pin::new(&mut reply).poll(&mut cx), Poll::Ready(Ok("done")));
+    }
+
+    #[test]
+    fn reply_of_a_stopped_core() {
+        let gba = test_gba(vec![0; 0x200]);
+        let handle = gba.handle();
+        drop(gba);
+
+        assert!(!handle.send(|_| {}));
+        assert!(handle.query(|_| 1).try_take().is_err());
+    }
+}
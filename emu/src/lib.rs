@@ -6,11 +6,23 @@ mod bitwise;
 #[allow(clippy::missing_panics_doc)]
 #[allow(clippy::cast_lossless)]
 #[allow(clippy::large_stack_frames)]
+#[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::unreadable_literal)]
 pub mod bus;
 
-#[allow(clippy::similar_names)]
-pub mod cartridge_header;
+pub mod achievements;
+pub mod boot_patch;
+pub mod cartridge;
+pub mod cheats;
+pub mod core_profile;
 pub mod cpu;
+pub mod crash_report;
+pub mod debugger;
+pub mod events;
 pub mod gba;
+pub mod handle;
+mod memory_map;
+pub mod movie;
+pub mod netplay;
 pub mod render;
+pub mod run_hash;
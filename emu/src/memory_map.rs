@@ -0,0 +1,106 @@
+//! The memory map by pages of 16 `MBytes`, the top byte of the address. Every page holds
+//! one region, and the mirrors of a region are the bits of the address it ignores.
+
+/// Size of the BIOS, the rest of its page is unused.
+pub const BIOS_SIZE: usize = 0x4000;
+
+/// Size of the SRAM of the cartridge, the rest of its page is unused.
+pub const SRAM_SIZE: usize = 0x1_0000;
+
+/// Size of the BG palette, the OBJ palette follows it.
+pub const BG_PALETTE_SIZE: usize = 0x200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Bios,
+    Ewram,
+    Iwram,
+    Io,
+    Palette,
+    Vram,
+    Oam,
+    /// The three copies of the ROM with their wait states.
+    Rom,
+    Sram,
+    Unused,
+}
+
+/// Region of every page.
+const PAGES: [Region; 256] = {
+    let mut pages = [Region::Unused; 256];
+    pages[0x00] = Region::Bios;
+    pages[0x02] = Region::Ewram;
+    pages[0x03] = Region::Iwram;
+    pages[0x04] = Region::Io;
+    pages[0x05] = Region::Palette;
+    pages[0x06] = Region::Vram;
+    pages[0x07] = Region::Oam;
+    let mut page = 0x08;
+    while page <= 0x0D {
+        pages[page] = Region::Rom;
+        page += 1;
+    }
+    pages[0x0E] = Region::Sram;
+    pages
+};
+
+impl Region {
+    /// The region of the page of `address`.
+    pub const fn of(address: usize) -> Self {
+        PAGES[(address >> 24) & 0xFF]
+    }
+
+    /// Bits of the address inside the region, the others pick a mirror.
+    const fn mirror_mask(self) -> usize {
+        match self {
+            Self::Ewram => 0x3_FFFF,
+            Self::Iwram => 0x7FFF,
+            Self::Palette | Self::Oam => 0x3FF,
+            Self::Vram => 0x1_FFFF,
+            Self::Rom => 0x1FF_FFFF,
+            Self::Bios | Self::Io | Self::Sram | Self::Unused => 0xFF_FFFF,
+        }
+    }
+
+    /// Offset of `address` in the region, the same for all its mirrors.
+    pub const fn offset(self, address: usize) -> usize {
+        let offset = address & self.mirror_mask();
+
+        // VRAM is 64k+32k+32k with the last two 32k being mirrors of each other.
+        if matches!(self, Self::Vram) && offset >= 0x1_8000 {
+            offset - 0x8000
+        } else {
+            offset
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn regions() {
+        assert_eq!(Region::of(0x0000_3FFF), Region::Bios);
+        assert_eq!(Region::of(0x0100_0000), Region::Unused);
+        assert_eq!(Region::of(0x0300_7FFF), Region::Iwram);
+        assert_eq!(Region::of(0x0BFF_FFFF), Region::Rom);
+        assert_eq!(Region::of(0x0E00_FFFF), Region::Sram);
+        assert_eq!(Region::of(0x0F00_0000), Region::Unused);
+        assert_eq!(Region::of(0x1300_0000), Region::Unused);
+    }
+
+    #[test]
+    fn mirrors() {
+        assert_eq!(Region::Ewram.offset(0x0204_0010), 0x10);
+        assert_eq!(Region::Iwram.offset(0x03FF_FFFC), 0x7FFC);
+        assert_eq!(Region::Palette.offset(0x0500_0604), 0x204);
+        assert_eq!(Region::Oam.offset(0x07FF_FC00), 0);
+        assert_eq!(Region::Rom.offset(0x0A00_0100), 0x100);
+        assert_eq!(Region::Rom.offset(0x0D00_0100), 0x100_0100);
+        assert_eq!(Region::Vram.offset(0x0601_7FFF), 0x1_7FFF);
+        assert_eq!(Region::Vram.offset(0x0601_8000), 0x1_0000);
+        assert_eq!(Region::Vram.offset(0x0603_0000), 0x1_0000);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::cpu::hardware::keypad::{Key, NO_KEYS_PRESSED};
+
+/// Keys held at every frame of a run from power on, eg. a TAS. The same ROM with the same
+/// movie always plays the same way.
+///
+/// The text has a line for each frame with the keys held, `.` for none, and the number of
+/// frames to hold them for in front if it's more than one. `#` starts a comment:
+///
+/// ```text
+/// # wait for the title screen
+/// 300 .
+/// Start
+/// 20 .
+/// A Right
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Movie {
+    /// `KEYINPUT` of each frame.
+    inputs: Vec<u16>,
+}
+
+impl Movie {
+    /// # Errors
+    /// It returns an error with the line if a key or a number of frames is not valid.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut inputs = Vec::new();
+
+        for (number, line) in (1..).zip(text.lines()) {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace().peekable();
+            if words.peek().is_none() {
+                continue;
+            }
+
+            let frames = match words.peek().map(|word| word.parse::<usize>()) {
+                Some(Ok(frames)) => {
+                    words.next();
+                    frames
+                }
+                _ => 1,
+            };
+
+            let mut keys = NO_KEYS_PRESSED;
+            for word in words.filter(|&word| word != ".") {
+                let key = Key::from_name(word)
+                    .ok_or_else(|| format!("Line {number}: unknown key `{word}`"))?;
+                keys &= !(1 << key.bit());
+            }
+
+            inputs.extend(std::iter::repeat_n(keys, frames));
+        }
+
+        Ok(Self { inputs })
+    }
+
+    /// Frames of the run.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// `KEYINPUT` of each frame, in order.
+    #[must_use]
+    pub fn inputs(&self) -> &[u16] {
+        &self.inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse() {
+        let movie = Movie::parse("# intro\n2 .\n\nstart\n3 A Right # run\n").unwrap();
+
+        assert_eq!(movie.len(), 6);
+        assert_eq!(
+            movie.inputs(),
+            &[
+                NO_KEYS_PRESSED,
+                NO_KEYS_PRESSED,
+                Key::pressed(&[Key::Start]),
+                Key::pressed(&[Key::A, Key::Right]),
+                Key::pressed(&[Key::A, Key::Right]),
+                Key::pressed(&[Key::A, Key::Right]),
+            ]
+        );
+        assert_eq!(
+            Movie::parse(". \nA Jump").unwrap_err(),
+            "Line 2: unknown key `Jump`"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::cpu::hardware::keypad::NO_KEYS_PRESSED;
+use crate::gba::Gba;
+
+/// How a session hides the network latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Local inputs are applied `frames` frames later, the emulation waits
+    /// for the remote input of each frame.
+    InputDelay { frames: u32 },
+    /// Remote inputs are predicted, when a prediction turns out wrong the
+    /// state is rolled back and the frames are emulated again.
+    /// The emulation waits if it's more than `max_frames` frames ahead of the remote player.
+    Rollback { max_frames: u32 },
+}
+
+impl std::fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InputDelay { frames } => write!(f, "input delay ({frames} frames)"),
+            Self::Rollback { max_frames } => write!(f, "rollback (max {max_frames} frames)"),
+        }
+    }
+}
+
+/// Messages exchanged by the peers, the transport is up to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    /// First message of both peers, the client uses the mode chosen by the host.
+    Hello {
+        rom_crc32: u32,
+        mode: SyncMode,
+    },
+    Input {
+        frame: u32,
+        keys: u16,
+    },
+}
+
+impl Message {
+    /// # Errors
+    /// It returns an error if writing to `writer` fails.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut bytes = Vec::with_capacity(10);
+
+        match *self {
+            Self::Hello { rom_crc32, mode } => {
+                let (mode_kind, frames) = match mode {
+                    SyncMode::InputDelay { frames } => (0, frames),
+                    SyncMode::Rollback { max_frames } => (1, max_frames),
+                };
+
+                bytes.push(0);
+                bytes.extend_from_slice(&rom_crc32.to_le_bytes());
+                bytes.push(mode_kind);
+                bytes.extend_from_slice(&frames.to_le_bytes());
+            }
+            Self::Input { frame, keys } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&frame.to_le_bytes());
+                bytes.extend_from_slice(&keys.to_le_bytes());
+            }
+        }
+
+        writer.write_all(&bytes)
+    }
+
+    /// # Errors
+    /// It returns an error if reading from `reader` fails or the message is invalid.
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<Self> {
+        let mut kind = [0; 1];
+        reader.read_exact(&mut kind)?;
+
+        match kind[0] {
+            0 => {
+                let mut bytes = [0; 9];
+                reader.read_exact(&mut bytes)?;
+
+                let rom_crc32 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let frames = u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]);
+                let mode = match bytes[4] {
+                    0 => SyncMode::InputDelay { frames },
+                    1 => SyncMode::Rollback { max_frames: frames },
+                    kind => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("Invalid netplay mode {kind}"),
+                        ))
+                    }
+                };
+
+                Ok(Self::Hello { rom_crc32, mode })
+            }
+            1 => {
+                let mut bytes = [0; 6];
+                reader.read_exact(&mut bytes)?;
+
+                Ok(Self::Input {
+                    frame: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    keys: u16::from_le_bytes([bytes[4], bytes[5]]),
+                })
+            }
+            kind => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid netplay message {kind}"),
+            )),
+        }
+    }
+}
+
+/// What a session needs from the emulator.
+pub trait NetplayCore {
+    /// Emulates one frame, `inputs` are the keys (`KEYINPUT`) of player 1 and 2.
+    fn run_frame(&mut self, inputs: [u16; 2]);
+
+    /// # Errors
+    /// It returns an error if the state can't be saved.
+    fn save_state(&self) -> Result<Vec<u8>, String>;
+
+    /// # Errors
+    /// It returns an error if the state can't be loaded.
+    fn load_state(&mut self, state: &[u8]) -> Result<(), String>;
+}
+
+/// Both players drive the same keypad: a key is pressed if any of them presses it.
+impl NetplayCore for Gba {
+    fn run_frame(&mut self, inputs: [u16; 2]) {
+        self.set_key_input(inputs[0] & inputs[1]);
+        Self::run_frame(self);
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, String> {
+        Self::save_state(self)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        Self::load_state(self, state)
+    }
+}
+
+/// Keeps two cores in sync exchanging only their inputs. Both peers must
+/// start from the same state (same ROM, just reset).
+pub struct Session {
+    mode: SyncMode,
+    local_player: usize,
+    /// Next frame to emulate.
+    frame: u32,
+    /// Next frame without local input.
+    next_local_frame: u32,
+    /// Confirmed inputs of each player, by frame.
+    inputs: [BTreeMap<u32, u16>; 2],
+    /// Remote inputs used for frames emulated before receiving them.
+    predictions: BTreeMap<u32, u16>,
+    /// State before each frame emulated with a prediction.
+    states: BTreeMap<u32, Vec<u8>>,
+    /// First frame emulated with a wrong prediction.
+    rollback_from: Option<u32>,
+    /// Number of frames emulated again because of wrong predictions.
+    pub rollback_frames: u64,
+}
+
+impl Session {
+    /// `local_player` is 0 for the host and 1 for the client.
+    #[must_use]
+    pub const fn new(mode: SyncMode, local_player: usize) -> Self {
+        Self {
+            mode,
+            local_player,
+            frame: 0,
+            next_local_frame: 0,
+            inputs: [BTreeMap::new(), BTreeMap::new()],
+            predictions: BTreeMap::new(),
+            states: BTreeMap::new(),
+            rollback_from: None,
+            rollback_frames: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn mode(&self) -> SyncMode {
+        self.mode
+    }
+
+    #[must_use]
+    pub const fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    const fn delay(&self) -> u32 {
+        match self.mode {
+            SyncMode::InputDelay { frames } => frames,
+            SyncMode::Rollback { .. } => 0,
+        }
+    }
+
+    const fn remote_player(&self) -> usize {
+        1 - self.local_player
+    }
+
+    /// Records the local keys for the next frame that still needs them and returns
+    /// the message to send to the remote player, `None` if the local input is
+    /// already ahead enough.
+    pub fn poll_local_input(&mut self, keys: u16) -> Option<Message> {
+        let frame = self.next_local_frame;
+        if frame > self.frame + self.delay() {
+            return None;
+        }
+
+        self.inputs[self.local_player].insert(frame, keys);
+        self.next_local_frame += 1;
+
+        Some(Message::Input { frame, keys })
+    }
+
+    pub fn add_remote_input(&mut self, frame: u32, keys: u16) {
+        let remote = self.remote_player();
+        self.inputs[remote].insert(frame, keys);
+
+        if let Some(predicted) = self.predictions.remove(&frame) {
+            if predicted != keys {
+                self.rollback_from = Some(self.rollback_from.map_or(frame, |f| f.min(frame)));
+            }
+        }
+    }
+
+    /// The remote input for `frame`, predicted as the last confirmed one if it's unknown.
+    fn remote_input(&self, frame: u32) -> (u16, bool) {
+        let inputs = &self.inputs[self.remote_player()];
+
+        inputs.get(&frame).map_or_else(
+            || {
+                let last = inputs.range(..frame).next_back();
+                (last.map_or(NO_KEYS_PRESSED, |(_, keys)| *keys), false)
+            },
+            |keys| (*keys, true),
+        )
+    }
+
+    /// Emulates `frame` with the known or predicted remote input, saving the state
+    /// before it if the input is predicted.
+    fn emulate(&mut self, core: &mut impl NetplayCore, frame: u32) -> Result<(), String> {
+        let local = self.inputs[self.local_player][&frame];
+        let (remote, is_confirmed) = self.remote_input(frame);
+
+        if is_confirmed {
+            self.states.remove(&frame);
+        } else {
+            self.states.insert(frame, core.save_state()?);
+            self.predictions.insert(frame, remote);
+        }
+
+        let mut inputs = [0; 2];
+        inputs[self.local_player] = local;
+        inputs[self.remote_player()] = remote;
+        core.run_frame(inputs);
+
+        Ok(())
+    }
+
+    /// Emulates the next frame if possible, it returns `false` if the session
+    /// has to wait for inputs.
+    ///
+    /// # Errors
+    /// It returns an error if a state can't be saved or loaded during a rollback.
+    pub fn advance(&mut self, core: &mut impl NetplayCore) -> Result<bool, String> {
+        if let Some(from) = self.rollback_from.take() {
+            let state = self
+                .states
+                .get(&from)
+                .ok_or_else(|| format!("Missing state of frame {from}"))?;
+            core.load_state(state)?;
+
+            for frame in from..self.frame {
+                self.emulate(core, frame)?;
+                self.rollback_frames += 1;
+            }
+        }
+
+        if !self.inputs[self.local_player].contains_key(&self.frame) {
+            return Ok(false);
+        }
+
+        match self.mode {
+            SyncMode::InputDelay { .. } => {
+                if !self.inputs[self.remote_player()].contains_key(&self.frame) {
+                    return Ok(false);
+                }
+            }
+            SyncMode::Rollback { max_frames } => {
+                let confirmed = self.inputs[self.remote_player()]
+                    .last_key_value()
+                    .map_or(0, |(frame, _)| frame + 1);
+
+                if self.frame >= confirmed + max_frames {
+                    return Ok(false);
+                }
+            }
+        }
+
+        self.emulate(core, self.frame)?;
+        self.frame += 1;
+        self.discard_confirmed();
+
+        Ok(true)
+    }
+
+    /// Forgets the inputs and states that can't be needed by a rollback anymore.
+    fn discard_confirmed(&mut self) {
+        let oldest_needed = self
+            .predictions
+            .first_key_value()
+            .map_or(self.frame, |(frame, _)| *frame)
+            .min(self.frame);
+
+        // The last input of the remote player is kept for predictions.
+        let remote = self.remote_player();
+        let last_remote = self.inputs[remote]
+            .last_key_value()
+            .map(|(frame, _)| *frame);
+        self.inputs[remote]
+            .retain(|frame, _| *frame >= oldest_needed || Some(*frame) == last_remote);
+        self.inputs[self.local_player].retain(|frame, _| *frame >= oldest_needed);
+        self.states.retain(|frame, _| *frame >= oldest_needed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Records the inputs of each emulated frame, the state is the whole history.
+    #[derive(Default)]
+    struct FakeCore {
+        frames: Vec<[u16; 2]>,
+    }
+
+    impl NetplayCore for FakeCore {
+        fn run_frame(&mut self, inputs: [u16; 2]) {
+            self.frames.push(inputs);
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>, String> {
+            Ok(self
+                .frames
+                .iter()
+                .flat_map(|inputs| inputs.iter().flat_map(|keys| keys.to_le_bytes()))
+                .collect())
+        }
+
+        fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+            self.frames = state
+                .chunks(4)
+                .map(|c| {
+                    [
+                        u16::from_le_bytes([c[0], c[1]]),
+                        u16::from_le_bytes([c[2], c[3]]),
+                    ]
+                })
+                .collect();
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let messages = [
+            Message::Hello {
+                rom_crc32: 0xDEAD_BEEF,
+                mode: SyncMode::Rollback { max_frames: 8 },
+            },
+            Message::Input {
+                frame: 42,
+                keys: 0x03FE,
+            },
+        ];
+
+        let mut bytes = Vec::new();
+        for message in &messages {
+            message.write_to(&mut bytes).unwrap();
+        }
+
+        let mut reader = bytes.as_slice();
+        for message in messages {
+            assert_eq!(Message::read_from(&mut reader).unwrap(), message);
+        }
+        assert!(Message::read_from(&mut [7_u8].as_slice()).is_err());
+    }
+
+    #[test]
+    fn input_delay() {
+        let mut session = Session::new(SyncMode::InputDelay { frames: 2 }, 0);
+        let mut core = FakeCore::default();
+
+        // Local inputs are sent for frames 0, 1 and 2 before the first frame.
+        assert_eq!(
+            session.poll_local_input(1),
+            Some(Message::Input { frame: 0, keys: 1 })
+        );
+        assert!(session.poll_local_input(2).is_some());
+        assert!(session.poll_local_input(3).is_some());
+        assert_eq!(session.poll_local_input(4), None);
+
+        assert!(!session.advance(&mut core).unwrap());
+
+        session.add_remote_input(0, 10);
+        assert!(session.advance(&mut core).unwrap());
+        assert!(!session.advance(&mut core).unwrap());
+        assert_eq!(core.frames, vec![[1, 10]]);
+        assert_eq!(session.frame(), 1);
+
+        assert_eq!(
+            session.poll_local_input(4),
+            Some(Message::Input { frame: 3, keys: 4 })
+        );
+    }
+
+    #[test]
+    fn rollback() {
+        let mut session = Session::new(SyncMode::Rollback { max_frames: 3 }, 1);
+        let mut core = FakeCore::default();
+
+        for keys in 0..3 {
+            session.poll_local_input(keys);
+            assert!(session.advance(&mut core).unwrap());
+        }
+
+        // Too far ahead of the remote player.
+        session.poll_local_input(3);
+        assert!(!session.advance(&mut core).unwrap());
+
+        // No remote input yet: it's predicted as not pressed.
+        assert_eq!(core.frames[2], [NO_KEYS_PRESSED, 2]);
+
+        // Frame 0 was predicted right, frame 1 wrong: frames 1 and 2 are emulated again.
+        session.add_remote_input(0, NO_KEYS_PRESSED);
+        session.add_remote_input(1, 0x0001);
+        assert!(session.advance(&mut core).unwrap());
+
+        assert_eq!(
+            core.frames,
+            vec![[NO_KEYS_PRESSED, 0], [0x0001, 1], [0x0001, 2], [0x0001, 3]]
+        );
+        assert_eq!(session.rollback_frames, 2);
+    }
+}
@@ -22,19 +22,19 @@ impl Color {
     #[allow(clippy::cast_possible_truncation)]
     #[must_use]
     pub fn red(&self) -> u8 {
-        self.0.get_bits(0..=4) as u8
+        self.0.get_bits_u8(0..=4)
     }
 
     #[allow(clippy::cast_possible_truncation)]
     #[must_use]
     pub fn green(&self) -> u8 {
-        self.0.get_bits(5..=9) as u8
+        self.0.get_bits_u8(5..=9)
     }
 
     #[allow(clippy::cast_possible_truncation)]
     #[must_use]
     pub fn blue(&self) -> u8 {
-        self.0.get_bits(10..=14) as u8
+        self.0.get_bits_u8(10..=14)
     }
 }
 
@@ -56,6 +56,7 @@ impl From<[u8; 2]> for Color {
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod test {
     use super::*;
 
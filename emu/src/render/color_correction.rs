@@ -0,0 +1,182 @@
+//! Color correction for the presentation of the frames: the raw colors are too saturated
+//! and too bright compared to what the screens of the consoles showed, games were tuned
+//! for them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::cpu::hardware::lcd::Color;
+
+/// Gamma of the monitor the frames are presented on (sRGB).
+const DISPLAY_GAMMA: f32 = 2.2;
+
+/// Every color a pixel can have, 5 bits per channel.
+const COLORS: usize = 1 << 15;
+
+/// Screen whose colors are replicated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorCorrection {
+    /// The raw colors, every channel scaled to 8 bits.
+    #[default]
+    Off,
+    /// The original GBA (AGB-001), dim and without a light.
+    GbaLcd,
+    /// The backlit GBA SP (AGS-101).
+    GbaSp,
+    /// The Nintendo DS running GBA games.
+    Nds,
+}
+
+/// How a screen shows the colors: the channels are made linear with the gamma of the
+/// screen, mixed by the matrix (a row for each channel shown) and scaled.
+struct Profile {
+    lcd_gamma: f32,
+    matrix: [[f32; 3]; 3],
+    brightness: f32,
+}
+
+impl ColorCorrection {
+    pub const ALL: [Self; 4] = [Self::Off, Self::GbaLcd, Self::GbaSp, Self::Nds];
+
+    const fn profile(self) -> Option<Profile> {
+        match self {
+            Self::Off => None,
+            Self::GbaLcd => Some(Profile {
+                lcd_gamma: 4.0,
+                matrix: [
+                    [1.0, 50.0 / 255.0, 0.0],
+                    [10.0 / 255.0, 230.0 / 255.0, 30.0 / 255.0],
+                    [50.0 / 255.0, 10.0 / 255.0, 220.0 / 255.0],
+                ],
+                brightness: 255.0 / 280.0,
+            }),
+            Self::GbaSp => Some(Profile {
+                lcd_gamma: 2.2,
+                matrix: [
+                    [0.96, 0.11, -0.07],
+                    [0.03, 0.94, 0.03],
+                    [0.005, 0.08, 0.915],
+                ],
+                brightness: 1.0,
+            }),
+            Self::Nds => Some(Profile {
+                lcd_gamma: 2.2,
+                matrix: [[0.86, 0.10, 0.04], [0.06, 0.82, 0.12], [0.03, 0.14, 0.83]],
+                brightness: 1.0,
+            }),
+        }
+    }
+
+    /// The color as shown by the screen, with 8 bits per channel.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn correct(self, color: Color) -> [u8; 3] {
+        let channels = [color.red(), color.green(), color.blue()];
+
+        let Some(profile) = self.profile() else {
+            return channels.map(|channel| (channel << 3) | (channel >> 2));
+        };
+
+        let linear = channels.map(|channel| (f32::from(channel) / 31.0).powf(profile.lcd_gamma));
+
+        profile.matrix.map(|row| {
+            let value = row
+                .iter()
+                .zip(linear)
+                .map(|(weight, channel)| weight * channel)
+                .sum::<f32>()
+                * profile.brightness;
+
+            (value.clamp(0.0, 1.0).powf(DISPLAY_GAMMA.recip()) * 255.0).round() as u8
+        })
+    }
+}
+
+/// Every color corrected once, presenting a frame is then only lookups.
+pub struct ColorTable(Box<[[u8; 3]]>);
+
+impl ColorTable {
+    #[must_use]
+    pub fn new(correction: ColorCorrection) -> Self {
+        Self(
+            (0..=u16::MAX >> 1)
+                .map(|color| correction.correct(Color(color)))
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    pub fn get(&self, color: Color) -> [u8; 3] {
+        // The unused top bit is ignored.
+        self.0[usize::from(color.0) % COLORS]
+    }
+
+    /// Red, green and blue of every color one after the other, in the order of the colors.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_flattened()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn off_scales_the_channels() {
+        let correct = |color| ColorCorrection::Off.correct(color);
+
+        assert_eq!(correct(Color::from_rgb(0, 0, 0)), [0, 0, 0]);
+        assert_eq!(correct(Color::from_rgb(31, 31, 31)), [255, 255, 255]);
+        assert_eq!(correct(Color::from_rgb(16, 1, 30)), [132, 8, 247]);
+    }
+
+    #[test]
+    fn black_and_white() {
+        for correction in ColorCorrection::ALL {
+            assert_eq!(correction.correct(Color::from_rgb(0, 0, 0)), [0, 0, 0]);
+        }
+
+        // The backlit screens show white, the original GBA has a tint.
+        assert_eq!(
+            ColorCorrection::GbaSp.correct(Color::from_rgb(31, 31, 31)),
+            [255, 255, 255]
+        );
+        assert_eq!(
+            ColorCorrection::Nds.correct(Color::from_rgb(31, 31, 31)),
+            [255, 255, 255]
+        );
+        assert_ne!(
+            ColorCorrection::GbaLcd.correct(Color::from_rgb(31, 31, 31)),
+            [255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn colors_are_less_saturated() {
+        let red = Color::from_rgb(31, 0, 0);
+
+        for correction in [
+            ColorCorrection::GbaLcd,
+            ColorCorrection::GbaSp,
+            ColorCorrection::Nds,
+        ] {
+            let [_, green, blue] = correction.correct(red);
+            assert!(green > 0 || blue > 0, "{correction:?} leaves red pure");
+        }
+    }
+
+    #[test]
+    fn table_matches_the_correction() {
+        let table = ColorTable::new(ColorCorrection::GbaLcd);
+
+        for color in [0, 0x1F, 0x3E0, 0x7C00, 0x1234, 0x7FFF] {
+            assert_eq!(
+                table.get(Color(color)),
+                ColorCorrection::GbaLcd.correct(Color(color))
+            );
+        }
+        assert_eq!(table.get(Color(0x8000)), table.get(Color(0)));
+    }
+}
@@ -5,6 +5,7 @@ pub struct GbaLcd {
 }
 
 impl Default for GbaLcd {
+    #[allow(clippy::large_stack_arrays)]
     fn default() -> Self {
         Self {
             pixels: [[Color::default(); LCD_WIDTH]; LCD_HEIGHT],
@@ -13,11 +14,11 @@ impl Default for GbaLcd {
 }
 
 impl GbaLcd {
-    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+    pub const fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
         self.pixels[x][y] = color;
     }
 
-    pub fn set_gbc_pixel(&mut self, x: usize, y: usize, color: Color) {
+    pub const fn set_gbc_pixel(&mut self, x: usize, y: usize, color: Color) {
         // GBC is rendered at the center of the screen
         let x_offset = (LCD_WIDTH - GBC_LCD_WIDTH) / 2;
         let y_offset = (LCD_HEIGHT - GBC_LCD_HEIGHT) / 2;
@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+
+/// Codes of the LZW compression are at most 12 bits.
+const MAX_CODES: u16 = 4096;
+
+/// Encodes an animated GIF that loops forever. `frames` are row after row of 15-bit
+/// colors, as in the LCD buffer, each shown for `frame_duration` hundredths of a second.
+///
+/// To keep the file small, only the rectangle that changed since the previous frame
+/// is stored and identical frames are merged into a longer one. Each frame has its
+/// own palette, frames with more than 256 colors lose the low bits of the channels.
+#[must_use]
+pub fn encode_animation(
+    width: u16,
+    height: u16,
+    frames: &[&[u16]],
+    frame_duration: f64,
+) -> Vec<u8> {
+    let mut encoder = AnimationEncoder::new(width, height, frame_duration);
+    for frame in frames {
+        encoder.push(frame);
+    }
+
+    encoder.finish()
+}
+
+/// Encodes an animation a frame at a time, as [`encode_animation`] does with all of them.
+/// A frame is written only once the next one differs, its duration is known then.
+pub struct AnimationEncoder {
+    width: u16,
+    height: u16,
+    frame_duration: f64,
+    gif: Vec<u8>,
+    /// Frames pushed or repeated until now.
+    frames: u32,
+    /// The last frame pushed, `None` before the first.
+    previous: Option<Vec<u16>>,
+    /// The rectangle of `previous` that changed, and how long it has been shown.
+    pending: Option<(Rectangle, u16)>,
+}
+
+impl AnimationEncoder {
+    #[must_use]
+    pub fn new(width: u16, height: u16, frame_duration: f64) -> Self {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        // No global color table, background color and aspect ratio
+        gif.extend_from_slice(&[0, 0, 0]);
+        // NETSCAPE2.0 extension, loops forever
+        gif.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        Self {
+            width,
+            height,
+            frame_duration,
+            gif,
+            frames: 0,
+            previous: None,
+            pending: None,
+        }
+    }
+
+    pub fn push(&mut self, frame: &[u16]) {
+        let delay = self.next_delay();
+
+        match Rectangle::changed(self.width, self.height, self.previous.as_deref(), frame) {
+            Some(rectangle) => {
+                self.write_pending();
+                self.pending = Some((rectangle, delay));
+
+                // The buffer of the previous frame is reused.
+                let previous = self.previous.get_or_insert_with(Vec::new);
+                previous.clear();
+                previous.extend_from_slice(frame);
+            }
+            None => self.extend_pending(delay),
+        }
+    }
+
+    /// Shows the last frame for one more frame, eg. in place of a frame that was lost.
+    pub fn repeat_last(&mut self) {
+        let delay = self.next_delay();
+        self.extend_pending(delay);
+    }
+
+    #[must_use]
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_pending();
+        self.gif.push(0x3B);
+        self.gif
+    }
+
+    /// Hundredths of a second of the next frame, rounded so that the errors don't add up.
+    fn next_delay(&mut self) -> u16 {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let end_time = |frames: u32| (self.frame_duration * f64::from(frames)).round() as u32;
+
+        let delay = end_time(self.frames + 1) - end_time(self.frames);
+        self.frames += 1;
+
+        u16::try_from(delay).unwrap_or(u16::MAX)
+    }
+
+    const fn extend_pending(&mut self, delay: u16) {
+        if let Some((_, pending_delay)) = &mut self.pending {
+            *pending_delay = pending_delay.saturating_add(delay);
+        }
+    }
+
+    fn write_pending(&mut self) {
+        if let (Some((rectangle, delay)), Some(frame)) = (self.pending.take(), &self.previous) {
+            write_frame(&mut self.gif, self.width, rectangle, frame, delay);
+        }
+    }
+}
+
+/// Part of the screen stored in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rectangle {
+    left: u16,
+    top: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Rectangle {
+    /// Smallest rectangle with all the pixels different from `previous`, `None` if
+    /// there are none. The first frame is stored whole.
+    fn changed(width: u16, height: u16, previous: Option<&[u16]>, frame: &[u16]) -> Option<Self> {
+        let Some(previous) = previous else {
+            return Some(Self {
+                left: 0,
+                top: 0,
+                width,
+                height,
+            });
+        };
+
+        let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+        for y in 0..height {
+            for x in 0..width {
+                let index = usize::from(y) * usize::from(width) + usize::from(x);
+                if frame[index] != previous[index] {
+                    left = left.min(x);
+                    top = top.min(y);
+                    right = right.max(x + 1);
+                    bottom = bottom.max(y + 1);
+                }
+            }
+        }
+
+        (left < right).then(|| Self {
+            left,
+            top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+}
+
+fn write_frame(gif: &mut Vec<u8>, width: u16, rectangle: Rectangle, frame: &[u16], delay: u16) {
+    let pixels = (rectangle.top..rectangle.top + rectangle.height)
+        .flat_map(|y| {
+            let start = usize::from(y) * usize::from(width) + usize::from(rectangle.left);
+            frame[start..start + usize::from(rectangle.width)]
+                .iter()
+                .copied()
+        })
+        .collect::<Vec<_>>();
+
+    let mask = color_mask(&pixels);
+    let mut palette = Vec::new();
+    let mut palette_indices = HashMap::new();
+    let indices = pixels
+        .iter()
+        .map(|&color| {
+            *palette_indices.entry(color & mask).or_insert_with(|| {
+                palette.push(color & mask);
+                #[allow(clippy::cast_possible_truncation)]
+                let index = palette.len() as u8 - 1;
+                index
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // The color table has 2^bits entries, at least 2
+    let bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(1);
+    palette.resize(1 << bits, 0);
+
+    // Graphic control extension: the frame is left on screen for the next one
+    gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+    gif.extend_from_slice(&delay.to_le_bytes());
+    gif.extend_from_slice(&[0, 0]);
+
+    gif.push(0x2C);
+    for value in [
+        rectangle.left,
+        rectangle.top,
+        rectangle.width,
+        rectangle.height,
+    ] {
+        gif.extend_from_slice(&value.to_le_bytes());
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    gif.push(0x80 | (bits - 1) as u8);
+
+    let widen = |channel: u16| {
+        let channel = (channel & 0x1F) as u8;
+        (channel << 3) | (channel >> 2)
+    };
+    for color in palette {
+        gif.extend_from_slice(&[widen(color), widen(color >> 5), widen(color >> 10)]);
+    }
+
+    // LZW needs at least 2 bits
+    #[allow(clippy::cast_possible_truncation)]
+    let min_code_size = bits.max(2) as u8;
+    gif.push(min_code_size);
+    for block in lzw_encode(&indices, min_code_size).chunks(255) {
+        #[allow(clippy::cast_possible_truncation)]
+        gif.push(block.len() as u8);
+        gif.extend_from_slice(block);
+    }
+    gif.push(0);
+}
+
+/// Mask of the color bits kept so that the pixels have at most 256 colors.
+fn color_mask(pixels: &[u16]) -> u16 {
+    (0..5)
+        .map(|dropped_bits| {
+            let channel = (0x1F << dropped_bits) & 0x1F;
+            channel | (channel << 5) | (channel << 10)
+        })
+        .find(|mask| {
+            let mut colors = HashSet::new();
+            pixels.iter().all(|color| {
+                colors.insert(color & mask);
+                colors.len() <= 256
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// Variable length LZW as used by GIF, the codes packed from the least significant bit.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code = 1_u16 << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut output = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = u32::from(min_code_size) + 1;
+
+    output.write(clear_code, code_size);
+
+    let mut pixels = indices.iter().copied();
+    let Some(first) = pixels.next() else {
+        output.write(end_code, code_size);
+        return output.finish();
+    };
+    let mut prefix = u16::from(first);
+
+    for pixel in pixels {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        output.write(prefix, code_size);
+
+        if next_code < MAX_CODES {
+            table.insert((prefix, pixel), next_code);
+            next_code += 1;
+            // The decoder adds its entries a code later, it widens when this passes the limit.
+            if u32::from(next_code) > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            output.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = u32::from(min_code_size) + 1;
+        }
+
+        prefix = u16::from(pixel);
+    }
+
+    output.write(prefix, code_size);
+    output.write(end_code, code_size);
+    output.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += size;
+
+        while self.bits >= 8 {
+            #[allow(clippy::cast_possible_truncation)]
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// The decoder of the GIF specification, to check the encoder against.
+    #[allow(clippy::cast_possible_truncation)]
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear_code = 1_usize << min_code_size;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = u32::from(min_code_size) + 1;
+        let mut previous: Option<usize> = None;
+        let mut output = Vec::new();
+        let (mut buffer, mut bits, mut bytes) = (0_u32, 0, data.iter());
+
+        loop {
+            while bits < code_size {
+                buffer |= u32::from(*bytes.next().unwrap()) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            bits -= code_size;
+
+            if code == clear_code {
+                table = (0..clear_code + 2).map(|index| vec![index as u8]).collect();
+                code_size = u32::from(min_code_size) + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear_code + 1 {
+                return output;
+            }
+
+            let entry = match (table.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(table[previous][0]);
+                    entry
+                }
+                (None, None) => panic!("code {code} is not in the table"),
+            };
+            if let Some(previous) = previous {
+                if table.len() < usize::from(MAX_CODES) {
+                    let mut new_entry = table[previous].clone();
+                    new_entry.push(entry[0]);
+                    table.push(new_entry);
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+
+            output.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    /// The delays of the graphic control extensions.
+    fn delays(gif: &[u8]) -> Vec<u16> {
+        gif.windows(4)
+            .enumerate()
+            .filter(|(_, window)| *window == [0x21, 0xF9, 0x04, 0x04])
+            .map(|(index, _)| u16::from_le_bytes([gif[index + 4], gif[index + 5]]))
+            .collect()
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        // Enough pixels with enough variety to fill the table more than once
+        let indices = (0..40_000_u32)
+            .map(|index| ((index * 7 + index / 13) % 251) as u8)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lzw_decode(&lzw_encode(&indices, 8), 8), indices);
+        assert_eq!(
+            lzw_decode(&lzw_encode(&[0, 1, 1, 1, 1, 0], 2), 2),
+            [0, 1, 1, 1, 1, 0]
+        );
+    }
+
+    #[test]
+    fn changed_rectangle() {
+        let previous = [0; 16];
+        let mut frame = [0; 16];
+        frame[5] = 1;
+        frame[10] = 1;
+
+        assert_eq!(Rectangle::changed(4, 4, Some(&previous), &previous), None);
+        assert_eq!(
+            Rectangle::changed(4, 4, Some(&previous), &frame),
+            Some(Rectangle {
+                left: 1,
+                top: 1,
+                width: 2,
+                height: 2
+            })
+        );
+    }
+
+    #[test]
+    fn same_frames_are_merged() {
+        let black = [0; 4];
+        let white = [0x7FFF; 4];
+        let gif = encode_animation(2, 2, &[&black, &black, &white], 3.3);
+
+        assert!(gif.starts_with(b"GIF89a\x02\x00\x02\x00"));
+        assert_eq!(gif.last(), Some(&0x3B));
+
+        assert_eq!(delays(&gif), vec![7, 3]);
+    }
+
+    #[test]
+    fn lost_frames_repeat_the_last() {
+        let black = [0; 4];
+        let white = [0x7FFF; 4];
+
+        let mut encoder = AnimationEncoder::new(2, 2, 3.3);
+        encoder.push(&black);
+        encoder.repeat_last();
+        encoder.push(&white);
+        encoder.repeat_last();
+
+        let gif = encoder.finish();
+        assert_eq!(
+            gif,
+            encode_animation(2, 2, &[&black, &black, &white, &white], 3.3)
+        );
+        assert_eq!(delays(&gif), vec![7, 6]);
+    }
+
+    #[test]
+    fn too_many_colors_lose_precision() {
+        let pixels = (0..1024).collect::<Vec<u16>>();
+
+        assert_eq!(color_mask(&pixels[..256]), 0x7FFF);
+        assert_eq!(color_mask(&pixels), 0b11110_11110_11110);
+    }
+}
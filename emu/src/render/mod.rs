@@ -1,6 +1,9 @@
 /// This module contains all the data structures used to render the GBA display.
 pub mod color;
+pub mod color_correction;
 pub mod gba_lcd;
+pub mod gif;
+pub mod png;
 
 /// GBA display width
 pub const LCD_WIDTH: usize = 240;
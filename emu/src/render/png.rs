@@ -0,0 +1,72 @@
+use ::png::{BitDepth, ColorType, Encoder};
+
+/// Encodes an 8-bit paletted PNG, `indices` has a byte per pixel row after row.
+/// The palette has 8 bits per channel, index 0 is fully transparent if `transparent_zero`.
+///
+/// # Errors
+/// It returns an error if there isn't a byte for every pixel or the palette is empty or
+/// has more than 256 colors.
+pub fn encode_indexed(
+    width: u32,
+    height: u32,
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    transparent_zero: bool,
+) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+
+    let mut encoder = Encoder::new(&mut png, width, height);
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.set_palette(palette.concat());
+    if transparent_zero {
+        encoder.set_trns(vec![0]);
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(indices)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn indexed_png() {
+        let palette = [[0, 0, 0], [255, 132, 8]];
+        let indices = [0, 1, 0, 1, 1, 1];
+        let png = encode_indexed(3, 2, &indices, &palette, true).unwrap();
+
+        let decoder = ::png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, ColorType::Indexed);
+        assert_eq!(info.bit_depth, BitDepth::Eight);
+        assert_eq!(info.palette.as_deref(), Some(&[0, 0, 0, 255, 132, 8][..]));
+        assert_eq!(info.trns.as_deref(), Some(&[0][..]));
+
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, indices);
+    }
+
+    #[test]
+    fn opaque_png() {
+        let png = encode_indexed(1, 1, &[0], &[[1, 2, 3]], false).unwrap();
+        let reader = ::png::Decoder::new(png.as_slice()).read_info().unwrap();
+
+        assert_eq!(reader.info().trns, None);
+    }
+
+    #[test]
+    fn missing_pixels() {
+        assert!(encode_indexed(3, 2, &[0; 5], &[[0, 0, 0]], false).is_err());
+    }
+}
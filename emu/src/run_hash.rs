@@ -0,0 +1,102 @@
+use std::fmt;
+
+use crate::cpu::hardware::lcd::Color;
+use crate::render::LCD_WIDTH;
+
+/// Hashes of the video and audio output of a run. The same ROM with the same
+/// inputs always gives the same hashes, a different hash after a change of the
+/// emulator means its output changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunHash {
+    pub frames: u64,
+    pub video: u32,
+    pub samples: u64,
+    pub audio: u32,
+}
+
+impl fmt::Display for RunHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frames={} video={:08x} samples={} audio={:08x}",
+            self.frames, self.video, self.samples, self.audio
+        )
+    }
+}
+
+/// Rolling CRC32 of the frames and of the audio samples, in the order they are made.
+#[derive(Debug, Default, Clone)]
+pub struct RunHasher {
+    video: crc32fast::Hasher,
+    audio: crc32fast::Hasher,
+    frames: u64,
+    samples: u64,
+}
+
+impl RunHasher {
+    /// `frame` is a row of pixels after the other.
+    pub fn add_frame(&mut self, frame: &[[Color; LCD_WIDTH]]) {
+        for pixel in frame.iter().flatten() {
+            self.video.update(&pixel.0.to_le_bytes());
+        }
+        self.frames += 1;
+    }
+
+    pub fn add_samples(&mut self, samples: &[[i16; 2]]) {
+        for [left, right] in samples {
+            self.audio.update(&left.to_le_bytes());
+            self.audio.update(&right.to_le_bytes());
+        }
+        self.samples += samples.len() as u64;
+    }
+
+    /// Hashes of everything added so far.
+    #[must_use]
+    pub fn hash(&self) -> RunHash {
+        RunHash {
+            frames: self.frames,
+            video: self.video.clone().finalize(),
+            samples: self.samples,
+            audio: self.audio.clone().finalize(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::LCD_HEIGHT;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn order_matters() {
+        let black = vec![[Color(0); LCD_WIDTH]; LCD_HEIGHT];
+        let mut red = black.clone();
+        red[10][20] = Color(0x1F);
+
+        let mut first = RunHasher::default();
+        first.add_frame(&black);
+        first.add_frame(&red);
+        first.add_samples(&[[1, -1], [2, -2]]);
+
+        let mut second = RunHasher::default();
+        second.add_frame(&red);
+        second.add_frame(&black);
+        second.add_samples(&[[1, -1], [2, -2]]);
+
+        let (first, second) = (first.hash(), second.hash());
+        assert_ne!(first.video, second.video);
+        assert_eq!(first.audio, second.audio);
+        assert_eq!((first.frames, first.samples), (2, 2));
+        assert_eq!(
+            RunHash {
+                frames: 1,
+                video: 0xAB,
+                samples: 0,
+                audio: 0,
+            }
+            .to_string(),
+            "frames=1 video=000000ab samples=0 audio=00000000"
+        );
+    }
+}
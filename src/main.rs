@@ -6,7 +6,13 @@ use logger::log;
 use logger::{init_logger, LogKind};
 
 fn main() {
-    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let mut args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    // The data is kept next to the executable, eg. to run it from a USB stick.
+    if let Some(index) = args.iter().position(|arg| arg == "--portable") {
+        args.remove(index);
+        ui::paths::set_portable(true);
+    }
 
     #[cfg(feature = "logger")]
     if args.len() > 1 {
@@ -17,6 +23,11 @@ fn main() {
         init_logger(LogKind::STDOUT);
     }
 
+    if args.first().is_some_and(|command| command == "test-roms") {
+        test_roms(&args[1..]);
+        return;
+    }
+
     let cartridge_name = args.first().map_or_else(
         || {
             log("no cartridge found :(");
@@ -28,17 +39,137 @@ fn main() {
         },
     );
 
+    // Headless run for CI: prints the hashes of the output and exits, the result
+    // depends only on the ROM (the cartridge clock starts from its default time).
+    if let Some(index) = args.iter().position(|arg| arg == "--hash-frames") {
+        let Some(frames) = args.get(index + 1).and_then(|frames| frames.parse().ok()) else {
+            eprintln!("--hash-frames needs the number of frames to run");
+            std::process::exit(1);
+        };
+
+        println!("{}", ui::app::load_gba(&cartridge_name).hash_run(frames));
+        return;
+    }
+
+    // Headless render of a movie to a video file, as fast as the host can run it.
+    if let Some(index) = args.iter().position(|arg| arg == "--render-movie") {
+        let (Some(movie), Some(output)) = (args.get(index + 1), args.get(index + 2)) else {
+            eprintln!("--render-movie needs the movie and the video file to write");
+            std::process::exit(1);
+        };
+
+        let movie = match std::fs::read_to_string(movie)
+            .map_err(|e| e.to_string())
+            .and_then(|text| emu::movie::Movie::parse(&text))
+        {
+            Ok(movie) => movie,
+            Err(e) => {
+                eprintln!("can't read the movie: {e}");
+                std::process::exit(1);
+            }
+        };
+
+        let mut gba = ui::app::load_gba(&cartridge_name);
+        if let Err(e) = ui::movie_render::render(&mut gba, &movie, output.as_ref()) {
+            eprintln!("can't render the movie: {e}");
+            std::process::exit(1);
+        }
+        println!("{} frames written to {output}", movie.len());
+        return;
+    }
+
+    // Commands from scripts and other tools, see `ui::app::App::listen`.
+    #[cfg(unix)]
+    let control_socket = args
+        .iter()
+        .position(|arg| arg == "--control")
+        .and_then(|index| args.get(index + 1))
+        .map(std::path::PathBuf::from);
+
+    ui::crash::install_panic_hook();
+    ui::paths::migrate();
+    let config = ui::config::Config::load();
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([1200.0, 800.0])
+        .with_drag_and_drop(true)
+        .with_fullscreen(config.fullscreen);
+    if let Some(window) = config.window {
+        viewport = viewport.with_inner_size(window.size);
+        if let Some(position) = window.position {
+            viewport = viewport.with_position(position);
+        }
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1200.0, 800.0])
-            .with_drag_and_drop(true),
+        viewport,
+        vsync: config.present_mode.vsync(),
         ..Default::default()
     };
 
     eframe::run_native(
         "Clementine - A GBA Emulator",
         options,
-        Box::new(|_cc| Ok(Box::new(ui::app::App::new(cartridge_name)))),
+        Box::new(move |cc| {
+            #[allow(unused_mut)]
+            let mut app = ui::app::App::new(&cartridge_name, config);
+            #[cfg(unix)]
+            if let Some(path) = control_socket {
+                if let Err(e) = app.listen(&path, &cc.egui_ctx) {
+                    eprintln!("can't listen on {}: {e}", path.display());
+                }
+            }
+            #[cfg(not(unix))]
+            let _ = cc;
+
+            Ok(Box::new(app))
+        }),
     )
     .ok();
 }
+
+/// `test-roms <dir> [--junit <file>] [--threads <count>]`: runs the ROMs of the directory
+/// in parallel, see `ui::test_runner`. It exits with 1 if any of them didn't pass.
+fn test_roms(args: &[String]) {
+    let Some(dir) = args.first().filter(|dir| !dir.starts_with("--")) else {
+        eprintln!("test-roms needs the directory of the ROMs");
+        std::process::exit(1);
+    };
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+    };
+    let threads = option("--threads")
+        .and_then(|threads| threads.parse().ok())
+        .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1);
+
+    let results = match ui::test_runner::run(dir.as_ref(), threads) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("can't run the tests: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let xml = ui::test_runner::junit_xml(dir, &results);
+    match option("--junit") {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, xml) {
+                eprintln!("can't write {path}: {e}");
+                std::process::exit(1);
+            }
+        }
+        None => print!("{xml}"),
+    }
+
+    let passed = results
+        .iter()
+        .filter(|result| result.outcome == ui::test_runner::Outcome::Passed)
+        .count();
+    eprintln!("{passed} of {} ROMs passed", results.len());
+    if passed < results.len() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,528 @@
+# English, the reference catalog: every message has to be here.
+
+## Tools
+
+tool-about-clementine = About Clementine
+tool-cpu-registers = Cpu Registers
+tool-cpu-handler = Cpu Handler
+tool-gba-display = Gba Display
+tool-save-game = Save Game
+tool-rom-info = ROM Info
+tool-debug-output = Debug Output
+tool-netplay = Netplay
+tool-audio = Audio
+tool-call-stack = Call Stack
+tool-source = Source
+tool-coverage = Coverage
+tool-profiler = Profiler
+tool-memory = Memory
+tool-palette-viewer = Palette Viewer
+tool-second-core = Second Core
+tool-timeline = Timeline
+tool-watch = Watch
+tool-instruction-stats = Instruction Stats
+tool-rumble = Rumble
+tool-library = Library
+tool-play-time = Play Time
+tool-achievements = Achievements
+tool-cheats = Cheats
+tool-backup-memory = Backup Memory
+tool-disassembler = Disassembler
+
+## Side panel
+
+tools-heading = ✒ Clementine Tools
+links = Links
+settings = Settings
+preset = Profile
+preset-hint = Settings for the device Clementine runs on, they can still be changed one by one
+preset-none = Custom
+preset-desktop = Desktop
+preset-desktop-description = A window next to the tools, drawn at every refresh of the monitor.
+preset-handheld = Handheld
+preset-handheld-description = Fullscreen with the screen scaled by a whole factor, and the window drawn only when the game completes a frame to save the battery. The menus and the pause menu (Start) work with the gamepad.
+preset-picker = Welcome to Clementine
+preset-picker-hint = Choose the device Clementine runs on, the profile can be changed in the settings.
+language = Language
+theme = Theme
+theme-dark = Dark
+theme-light = Light
+color-correction = Colors
+color-correction-hint = Replicates the screen of a console, the games were made for its less saturated colors
+color-correction-off = Raw
+color-correction-gba = GBA
+color-correction-gba-sp = GBA SP (AGS-101)
+color-correction-nds = Nintendo DS
+frame-blending = Frame blending
+frame-blending-hint = Mixes every frame with the previous one like the screen of the console, for the games making sprites transparent by showing them every other frame
+frame-blending-on = On
+frame-blending-off = Off
+affine-resolution = Affine sprites
+affine-resolution-hint = Samples rotated and scaled sprites at a higher resolution and averages them, smoothing their edges in games like Mario Kart. Not accurate to the hardware.
+affine-resolution-native = Native
+affine-resolution-double = 2x
+affine-resolution-quadruple = 4x
+game-enhancements = Effects for this game
+game-enhancements-hint = Used in place of the ones of every game, eg. the frame blending only for the games that flicker without it
+game-enhancement-default = Same as every game
+border = Border
+border-none = None
+border-shell = GBA
+border-open = Open PNG…
+border-png = PNG image
+border-scaling = Scaling
+border-scaling-fit = Fit
+border-scaling-integer = Whole factor
+border-scaling-hint = A whole factor keeps every pixel of the screen and of the border the same size
+border-error = Can't load the border: { $error }
+save-sync = Save sync
+save-sync-hint = Every save of the game is written to a .sav file next to the ROM, these keep it in sync between computers (eg. with Syncthing or Dropbox)
+save-sync-copy-to = Copy to
+save-sync-no-directory = No directory
+save-sync-choose = Choose…
+save-sync-clear = Clear
+save-sync-command = Command after saving
+save-sync-command-hint = Run after every save with the path of the .sav file as its last argument
+save-sync-reload = Reload when changed
+save-sync-reload-hint = Loads the .sav file again when another program changes it, unless the game is saving
+
+hot-reload = Hot reload
+hot-reload-hint = Loads the cartridge again when its file or the BIOS changes, eg. after a homebrew was rebuilt
+hot-reload-watch = Watch the files
+hot-reload-automatic = Reload automatically
+hot-reload-keep-backup = Keep the saves
+hot-reload-keep-backup-hint = Keeps the backup memory of the game, otherwise it starts erased like with a new cartridge
+hot-reload-title = Files changed
+hot-reload-changed = { $file } changed on disk.
+hot-reload-cartridge = The cartridge
+hot-reload-bios = The BIOS
+hot-reload-now = Reload
+hot-reload-ignore = Ignore
+hot-reload-done = Cartridge reloaded
+hot-reload-error = Can't reload: { $error }
+fullscreen = Fullscreen
+ui-scale = UI scale
+fixed-rtc = Fixed clock
+fixed-rtc-hint = Start the cartridge clock from the same time on every run, for reproducible runs (eg. TAS movies). Applied the next time a game is started.
+unix-time = Unix time:
+fast-ewram = Fast EWRAM
+fast-ewram-hint = Use the fastest EWRAM timing whatever the game sets ("EWRAM overclock"), it reduces slowdowns in some games but it is not accurate. Applied the next time a game is started.
+discord-presence = Discord presence
+discord-presence-hint = Show the game you are playing and for how long on your Discord profile. Everyone who can see your profile sees it.
+discord-presence-not-built = Built without a Discord application id (`CLEMENTINE_DISCORD_CLIENT_ID`).
+frame-skip = Frame skip
+frame-skip-hint = Frames not drawn to keep full speed on slow computers, the game and the audio run as usual. Automatic skips only while the emulation is late, at most the given frames in a row; fixed draws a frame and skips the given ones. Applied the next time a game is started.
+frame-skip-off = Off
+frame-skip-auto = Automatic
+frame-skip-fixed = Fixed
+present-mode = Presentation
+present-mode-hint = Vsync draws the window at every refresh of the monitor, smooth at 60 Hz. Adaptive draws it as soon as the game completes a frame, for monitors with variable refresh rate (FreeSync, G-Sync) where 60 Hz games judder at other rates; without it the frames are paced by the audio.
+present-mode-vsync = Vsync
+present-mode-adaptive = Adaptive (VRR)
+present-mode-restart = Applied the next time Clementine is started
+renderer = Renderer
+renderer-hint = How the frames of the game are drawn. OpenGL converts the colors on the graphics card, it's experimental and falls back to Software where it can't run.
+renderer-software = Software
+renderer-opengl = OpenGL (experimental)
+screen-filter = Screen filter
+screen-filter-hint = How the screen is scaled to the window by the Software renderer
+screen-filter-nearest = Nearest
+screen-filter-sharp-bilinear = Sharp bilinear
+screen-filter-scale2x = Scale2x
+screen-filter-scale3x = Scale3x
+core-profile = Core profile
+core-profile-hint = Accuracy follows the timing of the hardware, for testing and for the games that need it. Fast simplifies the timing, skips the loops waiting for an interrupt and decodes every instruction once, for slow devices; a few games may break.
+core-profile-game = Different for this game
+core-profile-accuracy = Accuracy
+core-profile-fast = Fast
+
+## Command palette
+
+command-palette-hint = Type a command
+no-commands = No matching commands
+invalid-argument = Not valid, try again
+show-tool = Show { $tool }
+hide-tool = Hide { $tool }
+use-theme = Use the { $theme } theme
+run = Run
+pause = Pause
+step-cycle = Step one CPU cycle
+reset = Reset
+hard-reset = Hard reset (keeps the saves)
+soft-reset = Soft reset (A+B+Start+Select)
+add-breakpoint-at = Add breakpoint at
+breakpoint-address-hint = Address in hex, eg. 0x08000000
+clear-breakpoints = Clear breakpoints
+save-state = Save state to file
+load-state = Load state from file
+save-slot = Save state to slot { $slot }
+load-slot = Load state from slot { $slot }
+empty-slot = Slot { $slot } is empty
+toggle-mute = Toggle mute
+clear-debug-output = Clear debug output
+hide-layer = Hide layer { $layer }
+show-layer = Show layer { $layer }
+export-indexed-png = Export { $layer } as indexed PNG…
+indexed-frame = Frame
+indexed-png = Indexed PNG
+indexed-export-waiting = Capturing the palette indices of the next frame…
+record-clips = Record clips of the last seconds
+clip-seconds-hint = Seconds to keep, 0 stops recording
+save-clip = Save the last seconds as a GIF (Ctrl+G)
+clips-recording = Keeping the last { $seconds } seconds, Ctrl+G saves them
+clips-stopped = Clips not recorded anymore
+clip-saved = Clip saved
+start-recording = Record every frame as a GIF
+stop-recording = Stop recording and save the GIF
+recording-started = Recording, the frames are encoded in the background
+recording-status = Recording: { $frames } frames, { $dropped } dropped
+recording-saved = Recording saved, { $frames } frames of which { $dropped } dropped because the encoder was late
+crash-report-saved = Clementine crashed, sorry! The emulation was stopped and a report was saved in { $path }. Please attach it to an issue on { $issues }, it has the state of the game and the last instructions run.
+crash-report-error = Clementine crashed, sorry! The emulation was stopped but the report could not be saved: { $error }. Please describe what happened in an issue on { $issues }.
+watchdog-title = The emulation is not responding
+watchdog-stalled = No frame in { $seconds } seconds: the emulator may be stuck in an endless loop or waiting for another thread.
+watchdog-wait = Keep waiting
+watchdog-debug = Pause and open the debugger
+watchdog-pausing = Waiting for the emulation to stop…
+gif-animation = GIF animation
+
+## About
+
+about-text =
+    Clementine is an emulator in early developing phase.
+    The community is working hard to realize this emulator for a pure educational scope.
+    Feel free to contribute.
+
+about-config-dir = Config
+about-data-dir = Data
+about-cache-dir = Cache
+
+## Common
+
+no-file-selected = No file selected
+clementine-save-file = Clementine save file
+
+## Audio
+
+muted = 🔇 Muted
+volume-osd = 🔊 Volume { $volume }%
+device-lost = { $device } disconnected, using the default device
+default-device = Default
+output-device = Output device
+refresh-devices = Refresh the devices
+volume = Volume
+mute = Mute
+toggle-with = Toggle with { $shortcut }
+interpolation = Interpolation
+interpolation-nearest = Nearest
+interpolation-linear = Linear
+interpolation-sinc = Sinc
+low-pass-filter = Low-pass filter
+low-pass-filter-hint = Muffle the high frequencies like the GBA speaker
+swap-channels = Swap left and right
+mono = Mono
+mono-hint = Play both sides on both speakers, for games panning hard on one side
+sample-rate = Sample rate
+fifo-underruns = FIFO underruns
+fifo-overruns = FIFO overruns
+fifo-errors-hint = Times the Direct Sound FIFOs ran dry or overflowed, the details are in the log
+no-audio-output = No audio output: { $error }
+built-without-audio = Built without the `audio` feature, nothing is played.
+
+## Library
+
+add-rom-folder = Add ROM folder
+box-art-folder = Box art folder
+rescan = Rescan
+rescan-library = Rescan the library
+no-games-found = No games found, add the folders with your ROMs
+double-click-to-play = Double-click a game to play it
+never-played = Never played
+played-today = Played today
+played-yesterday = Played yesterday
+played-days-ago = Played { $days } days ago
+play-time = { $hours }h { $minutes }m played
+
+## Play time
+
+play-time-total = { $games } games, { $time } in total
+play-time-game = Game
+play-time-played = Played
+play-time-sessions = Sessions
+play-time-last-played = Last played
+
+## Achievements
+
+achievement-unlocked = 🏆 { $title } ({ $points } points)
+achievement-title = { $title } ({ $points })
+achievements-progress = { $unlocked } of { $total } unlocked
+achievements-unsupported = { $count } achievements use conditions not supported yet, they are left out
+achievements-loading = Talking to RetroAchievements…
+username = Username
+password = Password
+login = Log in
+logout = Log out
+logged-in-as = Logged in as { $username }
+achievements-not-built = Built without the `achievements` feature, RetroAchievements are not available.
+
+## Rumble
+
+rumble-enabled = Rumble
+rumble-intensity = Intensity
+rumble-motor = Motor
+no-rumble-cartridge = The cartridge has no rumble
+no-rumble-gamepads = No gamepad with force feedback connected
+no-rumble-output = No rumble output: { $error }
+rumble-not-built = Built without the `rumble` feature, the gamepads don't rumble.
+enable-rumble = Enable rumble
+disable-rumble = Disable rumble
+
+## Call Stack
+
+call-stack-running = Running, updated when the CPU stops
+call-stack-empty = No calls
+function = Function
+return-address = Return address
+frame-kind = Kind
+frame-call = Call
+frame-exception = Exception
+
+## Source
+
+step-line = Step line
+run-to-line = Run to line
+run-to-line-hint = Run until the selected line is reached
+stop = Stop
+no-debug-info = Load an ELF built with debug info (-g) to see the source.
+no-source-location = No source for { $address }
+source-not-found = Can't read { $file }
+
+## Coverage
+
+record-coverage = Record coverage
+export-coverage = Export coverage…
+export-coverage-hint = Save the flags of every ROM byte (.cdl) or the list of ranges (.txt)
+code-data-log = Code/data log
+coverage-ranges = Coverage ranges
+coverage-not-recording = Coverage is not being recorded
+coverage-executed = Executed: { $bytes } bytes ({ $percent }%)
+coverage-read = Read as data: { $bytes } bytes ({ $percent }%)
+coverage-legend = Green: code, blue: data
+
+## Timeline
+
+record-timeline = Record timeline
+timeline-not-recording = The timeline is not being recorded
+timers = Timers
+timeline-dma = DMA transfers, with the units moved
+timeline-timers = Timer overflows
+timeline-irqs = Interrupt requests, also the ones not enabled in IE
+timeline-position = Line { $line }, dot { $dot }
+timeline-legend = Last frame, the visible area is lighter. Blue: DMA, green: timers, red: IRQ
+
+## Watch
+
+add-watch = Add watch
+clear-watches = Clear watches
+watch-expression-hint = eg. [0x03001A40]h
+watch-syntax = Registers (r0-r15, sp, lr, pc, cpsr), memory ([address] with b, h or w for the size) or variables (*symbol). Terms can be added and subtracted, numbers are in hex.
+watch-hex = Hex
+watch-unsigned = Decimal
+watch-signed = Signed
+
+## Cheats
+
+cheat-search = Search
+cheat-start-search = Start a new search
+cheat-search-hint = Start a search with the size of the value, then narrow it while playing: eg. equal to 3 lives, decreased after losing one.
+cheat-byte = Byte
+cheat-half-word = Half word
+cheat-word = Word
+cheat-equal-to = Equal to
+cheat-changed = Changed
+cheat-unchanged = Unchanged
+cheat-increased = Increased
+cheat-decreased = Decreased
+cheat-narrow = Narrow
+cheat-narrow-equal = Narrow the cheat search to a value
+cheat-narrow-hint = Too many addresses to list, keep narrowing the search.
+cheat-candidates = { $count } addresses
+cheat-invalid-value = Not a number, type it in decimal or in hex starting with 0x
+cheat-create = Cheat at { $address }
+cheat-name = Name
+cheat-value = Value
+cheat-mode = Mode
+cheat-freeze = Freeze
+cheat-once = Write once
+cheat-add = Add cheat
+cheat-list = Cheats
+cheat-gameshark-hint = GameShark Advance code, it keeps the value frozen
+cheat-copy = Copy
+
+## Backup Memory
+
+backup-kind = { $kind }, { $size } bytes
+backup-unknown = Unknown kind
+backup-export = Export the backup memory
+backup-import = Import a backup memory
+backup-file = Save file
+backup-edit = Byte { $offset }
+backup-write = Write
+backup-invalid-byte = Not a byte, type two hex digits
+
+## Disassembler
+
+listing = Listing
+go-to-address = Go to address
+go-to-address-hint = Address, label or symbol
+invalid-address = Not an address, a label or a symbol
+follow-pc = Follow PC
+annotate-hint = Click an address to give it a label or a comment, they're saved next to the ROM
+label = Label
+comment = Comment
+bookmarks = Bookmarks
+no-bookmarks = No labels or comments yet
+analyze-rom = Analyze ROM
+analyze-rom-hint = Follows the code from the entry point to find the functions and the literal pools, shown as data
+functions = Functions
+trace = Trace
+
+## Profiler
+
+record-profile = Record profile
+record-profile-hint = Count the cycles spent in each function, the emulation is a bit slower meanwhile
+reset-profile = Reset profile
+profiler-not-recording = Start recording to see where the cycles are spent.
+profiler-outside-calls = (outside of any call)
+sort-by-column = Sort by this column
+cycles = Cycles
+calls = Calls
+self-cycles = Self cycles
+total-cycles = Total cycles
+
+## Instruction Stats
+
+record-instruction-stats = Record instruction stats
+record-instruction-stats-hint = Count the instructions executed by kind, the emulation is a bit slower meanwhile
+reset-instruction-stats = Reset instruction stats
+instruction-stats-not-recording = Start recording to see which instructions the game executes.
+instruction-set = Set
+instruction = Instruction
+executed = Executed
+never-executed = Never executed
+copy-report = Copy report
+copy-report-hint = Copy the counts as text, eg. to attach them to a bug report
+
+## Memory
+
+length-hex = Length (hex)
+dump-memory = Dump memory…
+dump-memory-hint = Save the bytes of the range to a file
+dump-memory-argument-hint = Address and length in hex, eg. 0x06000000 0x18000
+load-memory = Load into memory…
+load-memory-hint = Write a whole file starting from the address
+binary-file = Binary file
+
+## Palette Viewer
+
+palette-entry = { $palette } color { $index }
+backdrop = backdrop
+palette-select-hint = Click a color to edit it, the game may overwrite the change.
+
+## Cpu Handler
+
+cartridge-name = Cartridge name:
+skip-bios-intro = Skip BIOS intro
+skip-bios-intro-hint = Fast-forward the Nintendo logo animation when the BIOS is running
+cpu-advanced-controls = CPU Advanced controls
+current-cpu-cycle = Current CPU cycle: { $cycle }
+step-cpu-cycles = Step CPU cycles:
+step-custom-cpu-cycles = Step (custom) CPU cycles:
+step = Step
+breakpoints = Breakpoints
+equal-to = Equal to
+greater-than = Greater than
+address-hex = address (HEX):
+set = Set
+active-breakpoints = Active breakpoints:
+divergence-check = Divergence check
+divergence-check-interval = Every (frames):
+divergence-check-frames = Frames run twice:
+divergence-check-enabled = Check for divergences
+divergence-check-hint = Saves a state, runs some frames, loads the state and runs them again: the two runs must end the same, otherwise some state is not saved and netplay and rewind would desync. It slows down the emulation.
+divergence-check-count = { $checks } checks, { $divergences } divergences
+
+## Pause menu
+
+paused = Paused
+resume = Resume
+quit = Quit
+slot-saved = Saved to slot { $slot }
+empty-slot-thumbnail = Empty
+
+## Cpu Registers
+
+registers = Registers
+decimal = Decimal
+hexadecimal = Hexadecimal
+
+## Debug Output
+
+clear = Clear
+
+## Netplay
+
+address = Address
+input-delay = Input delay
+rollback = Rollback
+max-rollback-frames = Max rollback frames
+delay-frames = Delay frames
+host = Host
+connect = Connect
+disconnect = Disconnect
+not-connected = Not connected
+connecting-to = Connecting to { $address }…
+waiting-for-player = Waiting for a player on { $address }…
+cancelled = Cancelled
+unexpected-message = Unexpected message from the other player
+different-rom = The other player is running a different ROM
+connected = Connected, { $mode }
+netplay-frame = Frame { $frame }, { $rollback } frames rolled back
+netplay-hint = The session drives the emulation, keep the CPU paused while playing.
+
+## Second Core
+
+start-second-core = Start
+stop-second-core = Stop
+copy-main-state = Copy main state
+copy-main-state-hint = Put this core in the same state of the main one, to compare what happens next
+second-core-hint = Runs a second emulator with the same cartridge and settings. The keys go to it while the pointer is on its screen.
+
+## ROM Info
+
+valid = ✔ valid
+invalid = ✖ invalid
+header = Header
+title = Title
+game-code = Game code
+maker-code = Maker code
+version = Version
+nintendo-logo = Nintendo logo
+complement-check = Complement check
+size = Size
+size-bytes = { $size } bytes
+bad-dump = This ROM may be a bad dump:
+verify-with-dat = Verify with DAT…
+verify-with-dat-hint = Check the ROM against a No-Intro DAT file
+apply-patch = Apply patch…
+apply-patch-hint = Apply an IPS/UPS/BPS patch in memory and restart
+load-symbols = Load symbols…
+load-symbols-hint = Load an ELF, .sym or .map file to show names instead of addresses
+symbol-file = Symbols
+symbols-loaded = { $count } symbols
+rom-patch = ROM patch
+
+## Save Game
+
+save = Save
+load = Load
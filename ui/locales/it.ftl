@@ -0,0 +1,528 @@
+# Italiano
+
+## Tools
+
+tool-about-clementine = Informazioni su Clementine
+tool-cpu-registers = Registri CPU
+tool-cpu-handler = Controllo CPU
+tool-gba-display = Schermo GBA
+tool-save-game = Salvataggi
+tool-rom-info = Informazioni ROM
+tool-debug-output = Output di debug
+tool-netplay = Gioco in rete
+tool-audio = Audio
+tool-call-stack = Stack delle chiamate
+tool-source = Sorgente
+tool-coverage = Copertura
+tool-profiler = Profiler
+tool-memory = Memoria
+tool-palette-viewer = Visualizzatore palette
+tool-second-core = Secondo Core
+tool-timeline = Linea temporale
+tool-watch = Osservati
+tool-instruction-stats = Statistiche istruzioni
+tool-rumble = Vibrazione
+tool-library = Libreria
+tool-play-time = Tempo di gioco
+tool-achievements = Obiettivi
+tool-cheats = Trucchi
+tool-backup-memory = Memoria di salvataggio
+tool-disassembler = Disassembler
+
+## Side panel
+
+tools-heading = ✒ Strumenti di Clementine
+links = Link
+settings = Impostazioni
+preset = Profilo
+preset-hint = Impostazioni per il dispositivo su cui gira Clementine, si possono comunque cambiare una per una
+preset-none = Personalizzato
+preset-desktop = Desktop
+preset-desktop-description = Una finestra accanto agli strumenti, disegnata a ogni aggiornamento del monitor.
+preset-handheld = Portatile
+preset-handheld-description = Schermo intero con lo schermo scalato di un fattore intero, e la finestra disegnata solo quando il gioco completa un frame per risparmiare la batteria. I menu e il menu di pausa (Start) funzionano con il gamepad.
+preset-picker = Benvenuto in Clementine
+preset-picker-hint = Scegli il dispositivo su cui gira Clementine, il profilo si può cambiare nelle impostazioni.
+language = Lingua
+theme = Tema
+theme-dark = Scuro
+theme-light = Chiaro
+color-correction = Colori
+color-correction-hint = Riproduce lo schermo di una console, i giochi erano fatti per i suoi colori meno saturi
+color-correction-off = Originali
+color-correction-gba = GBA
+color-correction-gba-sp = GBA SP (AGS-101)
+color-correction-nds = Nintendo DS
+frame-blending = Fusione dei fotogrammi
+frame-blending-hint = Mescola ogni fotogramma con il precedente come lo schermo della console, per i giochi che rendono trasparenti gli sprite mostrandoli un fotogramma sì e uno no
+frame-blending-on = Attiva
+frame-blending-off = Disattiva
+affine-resolution = Sprite affini
+affine-resolution-hint = Campiona gli sprite ruotati e scalati a una risoluzione più alta e ne fa la media, ammorbidendo i bordi in giochi come Mario Kart. Non fedele all'hardware.
+affine-resolution-native = Nativa
+affine-resolution-double = 2x
+affine-resolution-quadruple = 4x
+game-enhancements = Effetti per questo gioco
+game-enhancements-hint = Usati al posto di quelli di tutti i giochi, ad es. la fusione dei fotogrammi solo per i giochi che senza sfarfallano
+game-enhancement-default = Come tutti i giochi
+border = Cornice
+border-none = Nessuna
+border-shell = GBA
+border-open = Apri PNG…
+border-png = Immagine PNG
+border-scaling = Scala
+border-scaling-fit = Adatta
+border-scaling-integer = Fattore intero
+border-scaling-hint = Un fattore intero mantiene tutti i pixel dello schermo e della cornice della stessa dimensione
+border-error = Impossibile caricare la cornice: { $error }
+save-sync = Sincronizzazione dei salvataggi
+save-sync-hint = Ogni salvataggio del gioco è scritto in un file .sav accanto alla ROM, queste opzioni lo tengono sincronizzato tra computer (es. con Syncthing o Dropbox)
+save-sync-copy-to = Copia in
+save-sync-no-directory = Nessuna cartella
+save-sync-choose = Scegli…
+save-sync-clear = Rimuovi
+save-sync-command = Comando dopo il salvataggio
+save-sync-command-hint = Eseguito dopo ogni salvataggio con il percorso del file .sav come ultimo argomento
+save-sync-reload = Ricarica se cambia
+save-sync-reload-hint = Carica di nuovo il file .sav quando un altro programma lo modifica, a meno che il gioco stia salvando
+
+hot-reload = Ricaricamento automatico
+hot-reload-hint = Carica di nuovo la cartuccia quando il suo file o il BIOS cambiano, ad es. dopo aver ricompilato un homebrew
+hot-reload-watch = Controlla i file
+hot-reload-automatic = Ricarica senza chiedere
+hot-reload-keep-backup = Mantieni i salvataggi
+hot-reload-keep-backup-hint = Mantiene la memoria di backup del gioco, altrimenti riparte cancellata come con una cartuccia nuova
+hot-reload-title = File modificati
+hot-reload-changed = { $file } è cambiato sul disco.
+hot-reload-cartridge = La cartuccia
+hot-reload-bios = Il BIOS
+hot-reload-now = Ricarica
+hot-reload-ignore = Ignora
+hot-reload-done = Cartuccia ricaricata
+hot-reload-error = Impossibile ricaricare: { $error }
+fullscreen = Schermo intero
+ui-scale = Scala dell'interfaccia
+fixed-rtc = Orologio fisso
+fixed-rtc-hint = Fai partire l'orologio della cartuccia dalla stessa ora a ogni avvio, per esecuzioni riproducibili (es. filmati TAS). Applicato al prossimo avvio di un gioco.
+unix-time = Tempo Unix:
+fast-ewram = EWRAM veloce
+fast-ewram-hint = Usa la temporizzazione più veloce della EWRAM qualunque cosa imposti il gioco ("overclock della EWRAM"), riduce i rallentamenti di alcuni giochi ma non è accurato. Applicato al prossimo avvio di un gioco.
+discord-presence = Presenza su Discord
+discord-presence-hint = Mostra sul tuo profilo Discord il gioco a cui stai giocando e da quanto tempo. Lo vede chiunque possa vedere il tuo profilo.
+discord-presence-not-built = Compilato senza un id di applicazione Discord (`CLEMENTINE_DISCORD_CLIENT_ID`).
+frame-skip = Salto dei frame
+frame-skip-hint = Frame non disegnati per mantenere la piena velocità sui computer lenti, il gioco e l'audio funzionano come sempre. Automatico salta solo mentre l'emulazione è in ritardo, al massimo i frame indicati di fila; fisso disegna un frame e salta quelli indicati. Applicato al prossimo avvio di un gioco.
+frame-skip-off = Disattivato
+frame-skip-auto = Automatico
+frame-skip-fixed = Fisso
+present-mode = Presentazione
+present-mode-hint = Vsync disegna la finestra a ogni aggiornamento del monitor, fluido a 60 Hz. Adattiva la disegna appena il gioco completa un frame, per i monitor con frequenza variabile (FreeSync, G-Sync) dove i giochi a 60 Hz scattano alle altre frequenze; senza, i frame seguono l'audio.
+present-mode-vsync = Vsync
+present-mode-adaptive = Adattiva (VRR)
+present-mode-restart = Applicato al prossimo avvio di Clementine
+renderer = Renderer
+renderer-hint = Come vengono disegnati i frame del gioco. OpenGL converte i colori sulla scheda grafica, è sperimentale e torna a Software dove non può funzionare.
+renderer-software = Software
+renderer-opengl = OpenGL (sperimentale)
+screen-filter = Filtro dello schermo
+screen-filter-hint = Come lo schermo viene scalato alla finestra dal renderer Software
+screen-filter-nearest = Più vicino
+screen-filter-sharp-bilinear = Bilineare nitido
+screen-filter-scale2x = Scale2x
+screen-filter-scale3x = Scale3x
+core-profile = Profilo del core
+core-profile-hint = Accuratezza segue la temporizzazione dell'hardware, per i test e per i giochi che ne hanno bisogno. Veloce semplifica la temporizzazione, salta i cicli che aspettano un interrupt e decodifica ogni istruzione una volta sola, per i dispositivi lenti; alcuni giochi potrebbero non funzionare.
+core-profile-game = Diverso per questo gioco
+core-profile-accuracy = Accuratezza
+core-profile-fast = Veloce
+
+## Command palette
+
+command-palette-hint = Scrivi un comando
+no-commands = Nessun comando trovato
+invalid-argument = Non valido, riprova
+show-tool = Mostra { $tool }
+hide-tool = Nascondi { $tool }
+use-theme = Usa il tema { $theme }
+run = Avvia
+pause = Pausa
+step-cycle = Esegui un ciclo della CPU
+reset = Reset
+hard-reset = Reset completo (mantiene i salvataggi)
+soft-reset = Reset soft (A+B+Start+Select)
+add-breakpoint-at = Aggiungi breakpoint a
+breakpoint-address-hint = Indirizzo in esadecimale, es. 0x08000000
+clear-breakpoints = Rimuovi i breakpoint
+save-state = Salva lo stato su file
+load-state = Carica lo stato da file
+save-slot = Salva lo stato nello slot { $slot }
+load-slot = Carica lo stato dallo slot { $slot }
+empty-slot = Lo slot { $slot } è vuoto
+toggle-mute = Attiva/disattiva l'audio
+clear-debug-output = Pulisci l'output di debug
+hide-layer = Nascondi il layer { $layer }
+show-layer = Mostra il layer { $layer }
+export-indexed-png = Esporta { $layer } come PNG indicizzato…
+indexed-frame = Fotogramma
+indexed-png = PNG indicizzato
+indexed-export-waiting = Cattura degli indici della palette del prossimo fotogramma…
+record-clips = Registra clip degli ultimi secondi
+clip-seconds-hint = Secondi da tenere, 0 ferma la registrazione
+save-clip = Salva gli ultimi secondi come GIF (Ctrl+G)
+clips-recording = Tengo gli ultimi { $seconds } secondi, Ctrl+G li salva
+clips-stopped = Clip non più registrate
+clip-saved = Clip salvata
+start-recording = Registra ogni frame come GIF
+stop-recording = Ferma la registrazione e salva la GIF
+recording-started = Registrazione avviata, i frame sono codificati in background
+recording-status = Registrazione: { $frames } frame, { $dropped } persi
+recording-saved = Registrazione salvata, { $frames } frame di cui { $dropped } persi perché il codificatore era in ritardo
+crash-report-saved = Clementine si è bloccato, ci dispiace! L'emulazione è stata fermata e un report è stato salvato in { $path }. Allegalo a una issue su { $issues }, contiene lo stato del gioco e le ultime istruzioni eseguite.
+crash-report-error = Clementine si è bloccato, ci dispiace! L'emulazione è stata fermata ma non è stato possibile salvare il report: { $error }. Descrivi cosa è successo in una issue su { $issues }.
+watchdog-title = L'emulazione non risponde
+watchdog-stalled = Nessun frame in { $seconds } secondi: l'emulatore potrebbe essere bloccato in un ciclo infinito o in attesa di un altro thread.
+watchdog-wait = Continua ad aspettare
+watchdog-debug = Metti in pausa e apri il debugger
+watchdog-pausing = In attesa che l'emulazione si fermi…
+gif-animation = Animazione GIF
+
+## About
+
+about-text =
+    Clementine è un emulatore in una fase iniziale di sviluppo.
+    La comunità sta lavorando duramente per realizzarlo a scopo puramente didattico.
+    Sentiti libero di contribuire.
+
+about-config-dir = Configurazione
+about-data-dir = Dati
+about-cache-dir = Cache
+
+## Common
+
+no-file-selected = Nessun file selezionato
+clementine-save-file = Salvataggio di Clementine
+
+## Audio
+
+muted = 🔇 Audio disattivato
+volume-osd = 🔊 Volume { $volume }%
+device-lost = { $device } scollegato, uso il dispositivo predefinito
+default-device = Predefinito
+output-device = Dispositivo di uscita
+refresh-devices = Aggiorna i dispositivi
+volume = Volume
+mute = Muto
+toggle-with = Attiva o disattiva con { $shortcut }
+interpolation = Interpolazione
+interpolation-nearest = Più vicino
+interpolation-linear = Lineare
+interpolation-sinc = Sinc
+low-pass-filter = Filtro passa-basso
+low-pass-filter-hint = Attenua le alte frequenze come l'altoparlante del GBA
+swap-channels = Scambia sinistra e destra
+mono = Mono
+mono-hint = Riproduci entrambi i lati su entrambi gli altoparlanti, per i giochi che spostano molto il suono da un lato
+sample-rate = Frequenza di campionamento
+fifo-underruns = Svuotamenti FIFO
+fifo-overruns = Riempimenti FIFO
+fifo-errors-hint = Volte in cui le FIFO del Direct Sound si sono svuotate o sono traboccate, i dettagli sono nel log
+no-audio-output = Nessuna uscita audio: { $error }
+built-without-audio = Compilato senza la feature `audio`, non viene riprodotto nulla.
+
+## Library
+
+add-rom-folder = Aggiungi cartella ROM
+box-art-folder = Cartella delle copertine
+rescan = Aggiorna
+rescan-library = Aggiorna la libreria
+no-games-found = Nessun gioco trovato, aggiungi le cartelle con le tue ROM
+double-click-to-play = Doppio clic su un gioco per giocarci
+never-played = Mai giocato
+played-today = Giocato oggi
+played-yesterday = Giocato ieri
+played-days-ago = Giocato { $days } giorni fa
+play-time = { $hours }h { $minutes }m di gioco
+
+## Play time
+
+play-time-total = { $games } giochi, { $time } in totale
+play-time-game = Gioco
+play-time-played = Tempo
+play-time-sessions = Sessioni
+play-time-last-played = Ultima partita
+
+## Achievements
+
+achievement-unlocked = 🏆 { $title } ({ $points } punti)
+achievement-title = { $title } ({ $points })
+achievements-progress = { $unlocked } di { $total } sbloccati
+achievements-unsupported = { $count } obiettivi usano condizioni non ancora supportate, sono esclusi
+achievements-loading = Connessione a RetroAchievements…
+username = Nome utente
+password = Password
+login = Accedi
+logout = Esci
+logged-in-as = Accesso effettuato come { $username }
+achievements-not-built = Compilato senza la feature `achievements`, RetroAchievements non è disponibile.
+
+## Rumble
+
+rumble-enabled = Vibrazione
+rumble-intensity = Intensità
+rumble-motor = Motore
+no-rumble-cartridge = La cartuccia non vibra
+no-rumble-gamepads = Nessun gamepad con force feedback collegato
+no-rumble-output = Nessuna uscita per la vibrazione: { $error }
+rumble-not-built = Compilato senza la feature `rumble`, i gamepad non vibrano.
+enable-rumble = Attiva la vibrazione
+disable-rumble = Disattiva la vibrazione
+
+## Call Stack
+
+call-stack-running = In esecuzione, si aggiorna quando la CPU si ferma
+call-stack-empty = Nessuna chiamata
+function = Funzione
+return-address = Indirizzo di ritorno
+frame-kind = Tipo
+frame-call = Chiamata
+frame-exception = Eccezione
+
+## Source
+
+step-line = Avanza di una riga
+run-to-line = Esegui fino alla riga
+run-to-line-hint = Esegui finché non si arriva alla riga selezionata
+stop = Ferma
+no-debug-info = Carica un ELF compilato con le informazioni di debug (-g) per vedere il sorgente.
+no-source-location = Nessun sorgente per { $address }
+source-not-found = Impossibile leggere { $file }
+
+## Coverage
+
+record-coverage = Registra la copertura
+export-coverage = Esporta la copertura…
+export-coverage-hint = Salva i flag di ogni byte della ROM (.cdl) o l'elenco degli intervalli (.txt)
+code-data-log = Log codice/dati
+coverage-ranges = Intervalli di copertura
+coverage-not-recording = La copertura non viene registrata
+coverage-executed = Eseguiti: { $bytes } byte ({ $percent }%)
+coverage-read = Letti come dati: { $bytes } byte ({ $percent }%)
+coverage-legend = Verde: codice, blu: dati
+
+## Timeline
+
+record-timeline = Registra la linea temporale
+timeline-not-recording = La linea temporale non viene registrata
+timers = Timer
+timeline-dma = Trasferimenti DMA, con le unità copiate
+timeline-timers = Overflow dei timer
+timeline-irqs = Richieste di interrupt, anche quelle non abilitate in IE
+timeline-position = Linea { $line }, punto { $dot }
+timeline-legend = Ultimo frame, l'area visibile è più chiara. Blu: DMA, verde: timer, rosso: IRQ
+
+## Watch
+
+add-watch = Aggiungi espressione
+clear-watches = Rimuovi le espressioni
+watch-expression-hint = es. [0x03001A40]h
+watch-syntax = Registri (r0-r15, sp, lr, pc, cpsr), memoria ([indirizzo] con b, h o w per la dimensione) o variabili (*simbolo). I termini si possono sommare e sottrarre, i numeri sono in esadecimale.
+watch-hex = Esadecimale
+watch-unsigned = Decimale
+watch-signed = Con segno
+
+## Cheats
+
+cheat-search = Ricerca
+cheat-start-search = Inizia una nuova ricerca
+cheat-search-hint = Inizia una ricerca con la dimensione del valore, poi restringila mentre giochi: ad es. uguale a 3 vite, diminuito dopo averne persa una.
+cheat-byte = Byte
+cheat-half-word = Mezza parola
+cheat-word = Parola
+cheat-equal-to = Uguale a
+cheat-changed = Cambiato
+cheat-unchanged = Invariato
+cheat-increased = Aumentato
+cheat-decreased = Diminuito
+cheat-narrow = Restringi
+cheat-narrow-equal = Restringi la ricerca dei trucchi a un valore
+cheat-narrow-hint = Troppi indirizzi da elencare, continua a restringere la ricerca.
+cheat-candidates = { $count } indirizzi
+cheat-invalid-value = Non è un numero, scrivilo in decimale o in esadecimale iniziando con 0x
+cheat-create = Trucco a { $address }
+cheat-name = Nome
+cheat-value = Valore
+cheat-mode = Modalità
+cheat-freeze = Blocca
+cheat-once = Scrivi una volta
+cheat-add = Aggiungi trucco
+cheat-list = Trucchi
+cheat-gameshark-hint = Codice GameShark Advance, mantiene il valore bloccato
+cheat-copy = Copia
+
+## Backup Memory
+
+backup-kind = { $kind }, { $size } byte
+backup-unknown = Tipo sconosciuto
+backup-export = Esporta la memoria di salvataggio
+backup-import = Importa una memoria di salvataggio
+backup-file = File di salvataggio
+backup-edit = Byte { $offset }
+backup-write = Scrivi
+backup-invalid-byte = Non è un byte, scrivi due cifre esadecimali
+
+## Disassembler
+
+listing = Listato
+go-to-address = Vai all'indirizzo
+go-to-address-hint = Indirizzo, etichetta o simbolo
+invalid-address = Non è un indirizzo, un'etichetta o un simbolo
+follow-pc = Segui il PC
+annotate-hint = Clicca un indirizzo per dargli un'etichetta o un commento, vengono salvati accanto alla ROM
+label = Etichetta
+comment = Commento
+bookmarks = Segnalibri
+no-bookmarks = Ancora nessuna etichetta o commento
+analyze-rom = Analizza la ROM
+analyze-rom-hint = Segue il codice dal punto di ingresso per trovare le funzioni e i literal pool, mostrati come dati
+functions = Funzioni
+trace = Traccia
+
+## Profiler
+
+record-profile = Registra il profilo
+record-profile-hint = Conta i cicli spesi in ogni funzione, nel frattempo l'emulazione è un po' più lenta
+reset-profile = Azzera il profilo
+profiler-not-recording = Avvia la registrazione per vedere dove vengono spesi i cicli.
+profiler-outside-calls = (fuori da ogni chiamata)
+sort-by-column = Ordina per questa colonna
+cycles = Cicli
+calls = Chiamate
+self-cycles = Cicli propri
+total-cycles = Cicli totali
+
+## Instruction Stats
+
+record-instruction-stats = Registra le statistiche delle istruzioni
+record-instruction-stats-hint = Conta le istruzioni eseguite per tipo, nel frattempo l'emulazione è un po' più lenta
+reset-instruction-stats = Azzera le statistiche delle istruzioni
+instruction-stats-not-recording = Avvia la registrazione per vedere quali istruzioni esegue il gioco.
+instruction-set = Set
+instruction = Istruzione
+executed = Eseguite
+never-executed = Mai eseguite
+copy-report = Copia il resoconto
+copy-report-hint = Copia i conteggi come testo, ad es. per allegarli a una segnalazione di bug
+
+## Memory
+
+length-hex = Lunghezza (hex)
+dump-memory = Salva la memoria…
+dump-memory-hint = Salva i byte dell'intervallo in un file
+dump-memory-argument-hint = Indirizzo e lunghezza in hex, es. 0x06000000 0x18000
+load-memory = Carica in memoria…
+load-memory-hint = Scrivi un intero file a partire dall'indirizzo
+binary-file = File binario
+
+## Palette Viewer
+
+palette-entry = Colore { $index } { $palette }
+backdrop = sfondo
+palette-select-hint = Clicca un colore per modificarlo, il gioco potrebbe sovrascrivere la modifica.
+
+## Cpu Handler
+
+cartridge-name = Nome della cartuccia:
+skip-bios-intro = Salta l'intro del BIOS
+skip-bios-intro-hint = Manda avanti veloce l'animazione del logo Nintendo quando il BIOS è in esecuzione
+cpu-advanced-controls = Controlli avanzati della CPU
+current-cpu-cycle = Ciclo CPU attuale: { $cycle }
+step-cpu-cycles = Avanza di cicli CPU:
+step-custom-cpu-cycles = Avanza di cicli CPU (a scelta):
+step = Avanza
+breakpoints = Breakpoint
+equal-to = Uguale a
+greater-than = Maggiore di
+address-hex = indirizzo (HEX):
+set = Imposta
+active-breakpoints = Breakpoint attivi:
+divergence-check = Controllo divergenze
+divergence-check-interval = Ogni (frame):
+divergence-check-frames = Frame eseguiti due volte:
+divergence-check-enabled = Cerca divergenze
+divergence-check-hint = Salva uno stato, esegue alcuni frame, carica lo stato e li esegue di nuovo: le due esecuzioni devono finire uguali, altrimenti parte dello stato non viene salvata e netplay e rewind andrebbero fuori sincrono. Rallenta l'emulazione.
+divergence-check-count = { $checks } controlli, { $divergences } divergenze
+
+## Pause menu
+
+paused = In pausa
+resume = Riprendi
+quit = Esci
+slot-saved = Salvato nello slot { $slot }
+empty-slot-thumbnail = Vuoto
+
+## Cpu Registers
+
+registers = Registri
+decimal = Decimale
+hexadecimal = Esadecimale
+
+## Debug Output
+
+clear = Pulisci
+
+## Netplay
+
+address = Indirizzo
+input-delay = Ritardo degli input
+rollback = Rollback
+max-rollback-frames = Frame massimi di rollback
+delay-frames = Frame di ritardo
+host = Ospita
+connect = Connetti
+disconnect = Disconnetti
+not-connected = Non connesso
+connecting-to = Connessione a { $address }…
+waiting-for-player = In attesa di un giocatore su { $address }…
+cancelled = Annullato
+unexpected-message = Messaggio inatteso dall'altro giocatore
+different-rom = L'altro giocatore sta usando una ROM diversa
+connected = Connesso, { $mode }
+netplay-frame = Frame { $frame }, { $rollback } frame riavvolti
+netplay-hint = La sessione guida l'emulazione, tieni la CPU in pausa mentre giochi.
+
+## Second Core
+
+start-second-core = Avvia
+stop-second-core = Ferma
+copy-main-state = Copia lo stato principale
+copy-main-state-hint = Porta questo core nello stesso stato di quello principale, per confrontare cosa succede dopo
+second-core-hint = Esegue un secondo emulatore con la stessa cartuccia e le stesse impostazioni. I tasti vanno a lui mentre il puntatore è sul suo schermo.
+
+## ROM Info
+
+valid = ✔ valido
+invalid = ✖ non valido
+header = Intestazione
+title = Titolo
+game-code = Codice del gioco
+maker-code = Codice del produttore
+version = Versione
+nintendo-logo = Logo Nintendo
+complement-check = Complement check
+size = Dimensione
+size-bytes = { $size } byte
+bad-dump = Questa ROM potrebbe essere un dump corrotto:
+verify-with-dat = Verifica con un DAT…
+verify-with-dat-hint = Controlla la ROM con un file DAT di No-Intro
+apply-patch = Applica una patch…
+apply-patch-hint = Applica una patch IPS/UPS/BPS in memoria e riavvia
+load-symbols = Carica simboli…
+load-symbols-hint = Carica un file ELF, .sym o .map per mostrare i nomi al posto degli indirizzi
+symbol-file = Simboli
+symbols-loaded = { $count } simboli
+rom-patch = Patch della ROM
+
+## Save Game
+
+save = Salva
+load = Carica
@@ -1,4 +1,6 @@
-use crate::ui_traits::UiTool;
+use crate::i18n::tr;
+use crate::paths;
+use crate::ui_traits::{tool_window, UiTool};
 
 #[derive(Default)]
 pub struct About {}
@@ -9,7 +11,7 @@ impl UiTool for About {
     }
 
     fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
+        tool_window(ctx, self.name())
             .default_width(320.0)
             .open(open)
             .show(ctx, |ui| {
@@ -19,8 +21,26 @@ impl UiTool for About {
 
     fn ui(&mut self, ui: &mut egui::Ui) {
         ui.heading("🍊Clementine");
-        ui.label(
-            "Clementine is an emulator in early developing phase.\nThe community is working hard to realize this emulator for a pure educational scope.\nFeel free to contribute.".to_string()
-        );
+        ui.label(tr("about-text"));
+
+        let Some(dirs) = paths::dirs() else {
+            return;
+        };
+
+        ui.separator();
+        egui::Grid::new("Directories")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                for (name, dir) in [
+                    ("about-config-dir", &dirs.config),
+                    ("about-data-dir", &dirs.data),
+                    ("about-cache-dir", &dirs.cache),
+                ] {
+                    ui.label(tr(name));
+                    ui.monospace(dir.display().to_string());
+                    ui.end_row();
+                }
+            });
     }
 }
@@ -0,0 +1,378 @@
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "achievements")]
+use std::{error::Error, fs, path::PathBuf, sync::mpsc};
+
+#[cfg(feature = "achievements")]
+use emu::cartridge::hash::RomHash;
+use emu::{
+    achievements::{self, Achievement},
+    events::{CallbackId, CoreEvent},
+    gba::Gba,
+};
+
+use crate::i18n::{tr, tr_args};
+use crate::osd::Osd;
+#[cfg(feature = "achievements")]
+use crate::paths;
+#[cfg(feature = "achievements")]
+use crate::retro_achievements::{self, GameSet, Session};
+use crate::ui_traits::{tool_window, UiTool};
+
+#[cfg(feature = "achievements")]
+const SESSION_FILE_NAME: &str = "achievements.json";
+
+/// The achievements of the game, checked by a callback at the end of every frame.
+#[derive(Default)]
+struct Runtime {
+    achievements: Vec<Achievement>,
+    /// Unlocked and not yet shown and sent to the server.
+    new_unlocks: Vec<Achievement>,
+}
+
+impl Runtime {
+    fn do_frame(&mut self, gba: &Gba) {
+        let peek = |address| achievements::peek(gba, address);
+
+        for achievement in &mut self.achievements {
+            if achievement.do_frame(&peek) {
+                self.new_unlocks.push(achievement.clone());
+            }
+        }
+    }
+}
+
+/// What a request made in the background returned.
+#[cfg(feature = "achievements")]
+enum Reply {
+    LoggedIn(Session),
+    Game(GameSet),
+}
+
+/// `RetroAchievements` of the game: the user logs in, the ROM is recognized by its MD5
+/// and the achievements earned pop up on screen.
+pub struct Achievements {
+    gba: Arc<Mutex<Gba>>,
+    runtime: Arc<Mutex<Runtime>>,
+    callback_id: CallbackId,
+    osd: Osd,
+    /// Last error or warning, eg. a ROM that's not recognized.
+    message: Option<String>,
+    #[cfg(feature = "achievements")]
+    hash: String,
+    #[cfg(feature = "achievements")]
+    session: Option<Session>,
+    #[cfg(feature = "achievements")]
+    game_title: Option<String>,
+    #[cfg(feature = "achievements")]
+    username: String,
+    #[cfg(feature = "achievements")]
+    password: String,
+    /// The request being made, one at a time.
+    #[cfg(feature = "achievements")]
+    pending: Option<mpsc::Receiver<Result<Reply, String>>>,
+}
+
+impl Achievements {
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        let runtime = Arc::new(Mutex::new(Runtime::default()));
+
+        let mut locked_gba = gba.lock().unwrap();
+        let callback_runtime = Arc::clone(&runtime);
+        let callback_id = locked_gba.on_event(CoreEvent::FrameComplete, move |gba| {
+            callback_runtime.lock().unwrap().do_frame(gba);
+        });
+        #[cfg(feature = "achievements")]
+        let hash = RomHash::new(&locked_gba.cpu.bus.internal_memory.rom).md5;
+        drop(locked_gba);
+
+        #[cfg_attr(not(feature = "achievements"), allow(unused_mut))]
+        let mut achievements = Self {
+            gba,
+            runtime,
+            callback_id,
+            osd: Osd::default(),
+            message: None,
+            #[cfg(feature = "achievements")]
+            hash,
+            #[cfg(feature = "achievements")]
+            session: load_session(),
+            #[cfg(feature = "achievements")]
+            game_title: None,
+            #[cfg(feature = "achievements")]
+            username: String::new(),
+            #[cfg(feature = "achievements")]
+            password: String::new(),
+            #[cfg(feature = "achievements")]
+            pending: None,
+        };
+
+        #[cfg(feature = "achievements")]
+        achievements.load_game();
+
+        achievements
+    }
+
+    /// Shows the achievements unlocked since the last frame of the UI and sends them.
+    fn update(&mut self) {
+        let new_unlocks = std::mem::take(&mut self.runtime.lock().unwrap().new_unlocks);
+        for achievement in new_unlocks {
+            self.osd.show_message(tr_args(
+                "achievement-unlocked",
+                &[
+                    ("title", &achievement.title),
+                    ("points", &achievement.points),
+                ],
+            ));
+
+            #[cfg(feature = "achievements")]
+            if let Some(session) = self.session.clone() {
+                let id = achievement.id;
+                let hash = self.hash.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = retro_achievements::award(&session, id, &hash) {
+                        logger::log(format!("can't send achievement {id}: {e}"));
+                    }
+                });
+            }
+        }
+
+        #[cfg(feature = "achievements")]
+        self.receive();
+    }
+
+    /// Runs `request` in the background, its reply is read by [`Self::receive`].
+    #[cfg(feature = "achievements")]
+    fn send(&mut self, request: impl FnOnce() -> Result<Reply, Box<dyn Error>> + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The receiver is gone if the window was closed, there's no one to tell.
+            let _ = sender.send(request().map_err(|e| e.to_string()));
+        });
+
+        self.pending = Some(receiver);
+        self.message = None;
+    }
+
+    #[cfg(feature = "achievements")]
+    fn receive(&mut self) {
+        let Some(reply) = self
+            .pending
+            .as_ref()
+            .and_then(|pending| pending.try_recv().ok())
+        else {
+            return;
+        };
+        self.pending = None;
+
+        match reply {
+            Ok(Reply::LoggedIn(session)) => {
+                if let Err(e) = save_session(Some(&session)) {
+                    logger::log(format!("can't save achievements session: {e}"));
+                }
+                self.session = Some(session);
+                self.password.clear();
+                self.load_game();
+            }
+            Ok(Reply::Game(set)) => {
+                if set.unsupported > 0 {
+                    self.message = Some(tr_args(
+                        "achievements-unsupported",
+                        &[("count", &set.unsupported)],
+                    ));
+                }
+                self.game_title = Some(set.title);
+                self.runtime.lock().unwrap().achievements = set.achievements;
+            }
+            Err(e) => self.message = Some(e),
+        }
+    }
+
+    #[cfg(feature = "achievements")]
+    fn load_game(&mut self) {
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+
+        let hash = self.hash.clone();
+        self.send(move || Ok(Reply::Game(retro_achievements::load_game(&session, &hash)?)));
+    }
+
+    #[cfg(feature = "achievements")]
+    fn login(&mut self) {
+        let username = self.username.trim().to_owned();
+        let password = self.password.clone();
+
+        self.send(move || {
+            Ok(Reply::LoggedIn(retro_achievements::login(
+                &username, &password,
+            )?))
+        });
+    }
+
+    #[cfg(feature = "achievements")]
+    fn logout(&mut self) {
+        if let Err(e) = save_session(None) {
+            logger::log(format!("can't remove achievements session: {e}"));
+        }
+
+        self.session = None;
+        self.game_title = None;
+        self.runtime.lock().unwrap().achievements.clear();
+    }
+
+    #[cfg(feature = "achievements")]
+    fn account_ui(&mut self, ui: &mut egui::Ui) {
+        if self.pending.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(tr("achievements-loading"));
+            });
+        }
+
+        let Some(username) = self
+            .session
+            .as_ref()
+            .map(|session| session.username.clone())
+        else {
+            egui::Grid::new("Achievements login")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(tr("username"));
+                    ui.text_edit_singleline(&mut self.username);
+                    ui.end_row();
+
+                    ui.label(tr("password"));
+                    ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                    ui.end_row();
+                });
+
+            let can_login = self.pending.is_none() && !self.username.trim().is_empty();
+            if ui
+                .add_enabled(can_login, egui::Button::new(tr("login")))
+                .clicked()
+            {
+                self.login();
+            }
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(tr_args("logged-in-as", &[("username", &username)]));
+            if ui.button(tr("logout")).clicked() {
+                self.logout();
+            }
+        });
+
+        if let Some(title) = &self.game_title {
+            ui.heading(title);
+        }
+    }
+}
+
+/// The session is kept in `achievements.json` of the data directory.
+#[cfg(feature = "achievements")]
+fn session_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(SESSION_FILE_NAME))
+}
+
+#[cfg(feature = "achievements")]
+fn load_session() -> Option<Session> {
+    let path = session_path().filter(|path| path.is_file())?;
+
+    fs::read_to_string(&path)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|data| Ok(serde_json::from_str(&data)?))
+        .map_err(|e| logger::log(format!("can't read {}: {e}", path.display())))
+        .ok()
+}
+
+/// Saves `session`, or removes the saved one if it's `None`.
+#[cfg(feature = "achievements")]
+fn save_session(session: Option<&Session>) -> Result<(), Box<dyn Error>> {
+    let path = session_path().ok_or("No data directory")?;
+
+    match session {
+        Some(session) => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(session)?)?;
+        }
+        None if path.is_file() => fs::remove_file(path)?,
+        None => {}
+    }
+
+    Ok(())
+}
+
+impl Drop for Achievements {
+    fn drop(&mut self) {
+        self.gba.lock().unwrap().remove_callback(self.callback_id);
+    }
+}
+
+impl UiTool for Achievements {
+    fn name(&self) -> &'static str {
+        "Achievements"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        self.update();
+        self.osd.show(ctx);
+
+        tool_window(ctx, self.name())
+            .default_width(360.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        #[cfg(feature = "achievements")]
+        self.account_ui(ui);
+        #[cfg(not(feature = "achievements"))]
+        ui.small(tr("achievements-not-built"));
+
+        if let Some(message) = &self.message {
+            ui.colored_label(egui::Color32::YELLOW, message);
+        }
+
+        let achievements = self.runtime.lock().unwrap().achievements.clone();
+        if achievements.is_empty() {
+            return;
+        }
+
+        let unlocked = achievements
+            .iter()
+            .filter(|achievement| achievement.is_unlocked())
+            .count();
+        ui.label(tr_args(
+            "achievements-progress",
+            &[("unlocked", &unlocked), ("total", &achievements.len())],
+        ));
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for achievement in &achievements {
+                ui.horizontal(|ui| {
+                    ui.label(if achievement.is_unlocked() {
+                        "🏆"
+                    } else {
+                        "🔒"
+                    });
+                    ui.vertical(|ui| {
+                        ui.strong(tr_args(
+                            "achievement-title",
+                            &[
+                                ("title", &achievement.title),
+                                ("points", &achievement.points),
+                            ],
+                        ));
+                        ui.small(&achievement.description);
+                    });
+                });
+            }
+        });
+    }
+}
@@ -1,23 +1,125 @@
+#[cfg(unix)]
+use crate::control_socket::{ControlSocket, Request};
 #[cfg(feature = "disassembler")]
 use crate::disassembler::Disassembler;
-use emu::{cartridge_header::CartridgeHeader, gba::Gba};
+#[cfg(feature = "gamepad")]
+use crate::gamepad_input::GamepadInput;
+use emu::{
+    boot_patch::BootPatch,
+    cartridge::{hash::game_key, header::Header, patch},
+    core_profile::CoreProfile,
+    cpu::hardware::{
+        keypad::Key,
+        lcd::{AffineResolution, FrameSkip},
+        sound::Interpolation,
+    },
+    debugger::{
+        annotations::{self, Annotations},
+        symbols,
+        trace::Trace,
+    },
+    events::{CallbackId, CoreEvent},
+    gba::Gba,
+    render::color_correction::ColorCorrection,
+};
 use logger::log;
-use std::io::Read;
+use native_dialog::FileDialog;
+use std::io::{ErrorKind, Read};
 
 use super::cpu_registers::CpuRegisters;
 use crate::{
-    about, cpu_handler::CpuHandler, gba_display::GbaDisplay, savegame::SaveGame, ui_traits::UiTool,
+    about,
+    achievements::Achievements,
+    audio::{self, Audio},
+    backup_memory::BackupMemory,
+    battery_save::BatterySave,
+    border::{BorderScaling, BorderSettings, BorderSource},
+    call_stack::CallStack,
+    cheats::Cheats,
+    command_palette::{CommandPalette, PALETTE_SHORTCUT},
+    config::{Config, GameEnhancements, WindowGeometry},
+    coverage::Coverage,
+    cpu_handler::CpuHandler,
+    crash,
+    debug_output::DebugOutput,
+    discord_presence::{self, DiscordPresence},
+    frame_pacing::PresentMode,
+    gba_display::{self, GbaDisplay},
+    hot_reload::{HotReload, Reload},
+    i18n::{self, tool_title, tr, tr_args, Language},
+    instruction_stats::InstructionStats,
+    library::Library,
+    memory::Memory,
+    netplay::Netplay,
+    network_rom,
+    palette_viewer::PaletteViewer,
+    paths,
+    play_time::{PlayLog, PlayTime},
+    preset::Preset,
+    profiler::Profiler,
+    renderer::{self, RendererKind},
+    rom_info::RomInfo,
+    rtc_battery,
+    rumble::Rumble,
+    savegame::SaveGame,
+    screen_filter::{self, ScreenFilter},
+    second_core::SecondCore,
+    source::Source,
+    theme::{Theme, UI_SCALE_RANGE},
+    timeline::Timeline,
+    ui_traits::{saved_position_id, Command, UiTool},
+    watch::Watches,
+    watchdog::{Watchdog, WatchdogStatus},
 };
 
 use std::{
     collections::BTreeSet,
     env, error,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+/// Tools opened when the user pauses an emulation that got stuck.
+const DEBUGGER_TOOLS: [&str; 4] = ["Cpu Handler", "Cpu Registers", "Call Stack", "Disassembler"];
+
 pub struct App {
+    /// The emulated machine, for what the app keeps of it between sessions.
+    gba: Arc<Mutex<Gba>>,
     tools: Vec<Box<dyn UiTool>>,
     open: BTreeSet<String>,
+    /// Layout of the last session, updated and saved when the application is closed.
+    config: Config,
+    is_layout_restored: bool,
+    /// Value of the UI scale slider, applied when the user releases it.
+    ui_scale: f32,
+    palette: CommandPalette,
+    discord: DiscordPresence,
+    /// Shared with the display, which loads the border when it changes.
+    border: Arc<Mutex<BorderSettings>>,
+    watchdog: Watchdog,
+    battery: BatterySave,
+    hot_reload: HotReload,
+    /// Key of the game in the config, for its own settings.
+    game_key: String,
+    /// The one of the config when the window opened, see [`Config::present_mode`].
+    present_mode: PresentMode,
+    /// Draws the window when a frame is completed, with [`PresentMode::Adaptive`].
+    repaint_callback: Option<CallbackId>,
+    /// Commands of scripts and other tools, see [`Self::listen`].
+    #[cfg(unix)]
+    control_socket: Option<ControlSocket>,
+    /// Moves around the UI like the keyboard.
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<GamepadInput>,
+}
+
+/// What a command of the palette acts on.
+#[derive(Clone, Copy)]
+enum CommandTarget {
+    ToggleTool(usize),
+    Theme(Theme),
+    Tool { tool: usize, command: usize },
 }
 
 impl App {
@@ -26,41 +128,67 @@ impl App {
     /// # Panics
     /// It panics if the cartridge can't be opened.
     #[must_use]
-    pub fn new(cartridge_name: String) -> Self {
-        let data = match read_file(cartridge_name) {
-            Ok(d) => d,
-            Err(e) => {
-                log(format!("{e}"));
-                std::process::exit(2);
-            }
-        };
-
-        let bios_file = env::current_dir().unwrap().join("gba_bios.bin");
-        let bios = match std::fs::read(bios_file) {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("can't open bios file: {e}");
-                std::process::exit(3);
-            }
-        };
+    pub fn new(cartridge_name: &str, config: Config) -> Self {
+        let mut gba = load_gba(cartridge_name);
+        let settings = CoreSettings::new(&config, &game_key(&gba.cpu.bus.internal_memory.rom));
+        settings.apply(&mut gba);
+        // A downloaded cartridge keeps its files in the working directory, and it's not watched.
+        let local_name = network_rom::local_name(cartridge_name);
+        let battery = BatterySave::new(local_name, &mut gba);
+        let is_reloadable = !is_multiboot(cartridge_name) && !network_rom::is_url(cartridge_name);
+        let hot_reload = HotReload::new(cartridge_name, is_reloadable, bios_path());
+        gba.cpu.trace = Some(Trace::new(crash::TRACE_LENGTH));
 
-        let cartridge_header =
-            CartridgeHeader::new(data.as_slice()).expect("Cartridge must be opened");
-        let arc_gba = Arc::new(Mutex::new(Gba::new(
-            cartridge_header,
-            bios[0..0x0000_4000].try_into().unwrap(),
-            data,
-        )));
+        let arc_gba = Arc::new(Mutex::new(gba));
 
         #[cfg(feature = "disassembler")]
-        let disassembler = Disassembler::new(Arc::clone(&arc_gba));
+        let disassembler = Disassembler::new(
+            Arc::clone(&arc_gba),
+            Path::new(local_name).with_extension(annotations::EXTENSION),
+        );
+
+        let border = Arc::new(Mutex::new(config.border.clone()));
+        let play_log = Arc::new(Mutex::new(PlayLog::load()));
+        let library = Library::new(Arc::clone(&play_log));
+        let play_time = PlayTime::new(Arc::clone(&arc_gba), play_log, cartridge_name);
+
+        // The second core plays the same cartridge with the same settings.
+        let cartridge_name = cartridge_name.to_owned();
+        let new_core = Box::new(move || {
+            let mut gba = load_gba(&cartridge_name);
+            settings.apply(&mut gba);
+            gba
+        });
+
+        let cpu_handler = CpuHandler::new(Arc::clone(&arc_gba));
+        let watchdog = Watchdog::new(Arc::clone(&arc_gba), cpu_handler.running());
 
         let tools: Vec<Box<dyn UiTool>> = vec![
             Box::<about::About>::default(),
             Box::new(CpuRegisters::new(Arc::clone(&arc_gba))),
-            Box::new(CpuHandler::new(Arc::clone(&arc_gba))),
-            Box::new(GbaDisplay::new(Arc::clone(&arc_gba))),
+            Box::new(cpu_handler),
+            Box::new(GbaDisplay::new(Arc::clone(&arc_gba), Arc::clone(&border))),
             Box::new(SaveGame::new(Arc::clone(&arc_gba))),
+            Box::new(RomInfo::new(Arc::clone(&arc_gba))),
+            Box::new(DebugOutput::new(Arc::clone(&arc_gba))),
+            Box::new(Netplay::new(Arc::clone(&arc_gba))),
+            Box::new(Audio::new(Arc::clone(&arc_gba))),
+            Box::new(CallStack::new(Arc::clone(&arc_gba))),
+            Box::new(Source::new(Arc::clone(&arc_gba))),
+            Box::new(Coverage::new(Arc::clone(&arc_gba))),
+            Box::new(Profiler::new(Arc::clone(&arc_gba))),
+            Box::new(InstructionStats::new(Arc::clone(&arc_gba))),
+            Box::new(Memory::new(Arc::clone(&arc_gba))),
+            Box::new(PaletteViewer::new(Arc::clone(&arc_gba))),
+            Box::new(SecondCore::new(Arc::clone(&arc_gba), new_core)),
+            Box::new(Timeline::new(Arc::clone(&arc_gba))),
+            Box::new(Watches::new(Arc::clone(&arc_gba))),
+            Box::new(Rumble::new(Arc::clone(&arc_gba))),
+            Box::new(library),
+            Box::new(play_time),
+            Box::new(Achievements::new(Arc::clone(&arc_gba))),
+            Box::new(Cheats::new(Arc::clone(&arc_gba))),
+            Box::new(BackupMemory::new(Arc::clone(&arc_gba))),
         ];
 
         #[cfg(feature = "disassembler")]
@@ -68,33 +196,828 @@ impl App {
         #[cfg(feature = "disassembler")]
         tools.push(Box::new(disassembler));
 
-        Self::from_tools(tools)
+        Self::from_tools(
+            arc_gba, tools, config, border, watchdog, battery, hot_reload,
+        )
     }
 
-    fn from_tools(tools: Vec<Box<dyn UiTool>>) -> Self {
-        let mut open = BTreeSet::new();
+    fn from_tools(
+        gba: Arc<Mutex<Gba>>,
+        tools: Vec<Box<dyn UiTool>>,
+        config: Config,
+        border: Arc<Mutex<BorderSettings>>,
+        watchdog: Watchdog,
+        battery: BatterySave,
+        hot_reload: HotReload,
+    ) -> Self {
+        let (mut discord, game_key) = {
+            let gba = gba.lock().unwrap();
+            (
+                DiscordPresence::new(&gba.cartridge_header.game_title),
+                game_key(&gba.cpu.bus.internal_memory.rom),
+            )
+        };
+        discord.set_enabled(config.discord_presence);
+
+        i18n::set_language(config.language.unwrap_or_else(Language::from_env));
+        gba_display::set_color_correction(config.color_correction_of(&game_key));
+        renderer::set_renderer(config.renderer);
+        screen_filter::set_screen_filter(config.screen_filter);
 
-        open.insert(tools[1].name().to_owned());
-        open.insert(tools[2].name().to_owned());
-        open.insert(tools[3].name().to_owned());
-        open.insert(tools[4].name().to_owned());
-        #[cfg(feature = "disassembler")]
-        open.insert(tools[5].name().to_owned());
+        let open = config.open_tools.clone().unwrap_or_else(|| {
+            let mut open = BTreeSet::new();
+
+            open.insert(tools[1].name().to_owned());
+            open.insert(tools[2].name().to_owned());
+            open.insert(tools[3].name().to_owned());
+            open.insert(tools[4].name().to_owned());
+            #[cfg(feature = "disassembler")]
+            open.insert(tools[25].name().to_owned());
+
+            open
+        });
+
+        Self {
+            gba,
+            tools,
+            open,
+            present_mode: config.present_mode,
+            repaint_callback: None,
+            #[cfg(unix)]
+            control_socket: None,
+            #[cfg(feature = "gamepad")]
+            gamepad: GamepadInput::new()
+                .map_err(|err| log(format!("no gamepad input: {err}")))
+                .ok(),
+            ui_scale: config
+                .ui_scale
+                .unwrap_or(1.0)
+                .clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end()),
+            config,
+            is_layout_restored: false,
+            palette: CommandPalette::default(),
+            discord,
+            border,
+            watchdog,
+            battery,
+            hot_reload,
+            game_key,
+        }
+    }
+
+    /// Makes the tool windows of the last session appear where they were left.
+    fn restore_layout(&mut self, ctx: &egui::Context) {
+        for (name, position) in &self.config.tool_positions {
+            let position = egui::Pos2::from(*position);
+            ctx.data_mut(|data| data.insert_temp(saved_position_id(name), position));
+        }
+
+        ctx.set_visuals(self.config.theme.visuals());
+        ctx.set_zoom_factor(self.ui_scale);
+
+        self.is_layout_restored = true;
+    }
+
+    fn save_layout(&mut self, ctx: &egui::Context) {
+        self.config.open_tools = Some(self.open.clone());
+        // The zoom can also be changed with Ctrl +/-.
+        self.config.ui_scale = Some(ctx.zoom_factor());
+
+        for tool in &self.tools {
+            // Tools never shown in this session keep their old position.
+            if let Some(rect) = ctx.memory(|memory| memory.area_rect(egui::Id::new(tool.name()))) {
+                self.config
+                    .tool_positions
+                    .insert(tool.name().to_owned(), rect.min.into());
+            }
+        }
+
+        let window = ctx.input(|input| {
+            let viewport = input.viewport();
+            viewport.inner_rect.map(|rect| WindowGeometry {
+                size: rect.size().into(),
+                position: viewport.outer_rect.map(|rect| rect.min.into()),
+            })
+        });
+        if window.is_some() {
+            self.config.window = window;
+        }
+
+        if let Err(e) = self.config.save() {
+            log(format!("can't save config: {e}"));
+        }
 
-        Self { tools, open }
+        self.battery
+            .flush(&self.gba.lock().unwrap(), &self.config.save_sync);
+
+        // A fixed clock starts from the same time every session.
+        if self.config.fixed_rtc_timestamp.is_none() {
+            if let Err(e) = rtc_battery::save(&self.gba.lock().unwrap(), host_timestamp()) {
+                log(format!("can't save the cartridge clock: {e}"));
+            }
+        }
     }
 
     pub fn checkboxes(&mut self, ui: &mut egui::Ui) {
-        let Self { tools, open } = self;
+        let Self { tools, open, .. } = self;
         for tool in tools {
             let mut is_open = open.contains(tool.name());
-            ui.toggle_value(&mut is_open, tool.name());
+            ui.toggle_value(&mut is_open, tool_title(tool.name()));
             set_open(open, tool.name(), is_open);
         }
     }
 
+    fn settings(&mut self, ui: &mut egui::Ui) {
+        let mut preset = self.config.preset;
+        ui.horizontal(|ui| {
+            ui.label(tr("preset")).on_hover_text(tr("preset-hint"));
+            egui::ComboBox::from_id_source("Preset")
+                .selected_text(preset.map_or_else(|| tr("preset-none"), Preset::name))
+                .show_ui(ui, |ui| {
+                    for option in Preset::ALL {
+                        ui.selectable_value(&mut preset, Some(option), option.name())
+                            .on_hover_text(option.description());
+                    }
+                });
+        });
+        if let Some(preset) = preset.filter(|preset| Some(*preset) != self.config.preset) {
+            self.apply_preset(ui.ctx(), preset);
+        }
+
+        let mut language = i18n::language();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("language"));
+            egui::ComboBox::from_id_source("Language")
+                .selected_text(language.to_string())
+                .show_ui(ui, |ui| {
+                    for option in Language::ALL {
+                        ui.selectable_value(&mut language, option, option.to_string());
+                    }
+                });
+        });
+
+        if language != i18n::language() {
+            i18n::set_language(language);
+            self.config.language = Some(language);
+        }
+
+        let mut theme = self.config.theme;
+        ui.horizontal(|ui| {
+            ui.label(tr("theme"));
+            egui::ComboBox::from_id_source("Theme")
+                .selected_text(theme.name())
+                .show_ui(ui, |ui| {
+                    for option in Theme::ALL {
+                        ui.selectable_value(&mut theme, option, option.name());
+                    }
+                });
+        });
+
+        if theme != self.config.theme {
+            self.set_theme(ui.ctx(), theme);
+        }
+
+        self.color_correction_settings(ui);
+        self.game_enhancement_settings(ui);
+        self.border_settings(ui);
+        self.save_sync_settings(ui);
+        self.hot_reload_settings(ui);
+        self.core_profile_settings(ui);
+
+        ui.label(tr("ui-scale"));
+        // Zooming while dragging would move the slider away from the pointer.
+        if !ui.ctx().is_using_pointer() {
+            self.ui_scale = ui.ctx().zoom_factor();
+        }
+        let response = ui.add(
+            egui::Slider::new(&mut self.ui_scale, UI_SCALE_RANGE)
+                .step_by(0.05)
+                .fixed_decimals(2),
+        );
+        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+            ui.ctx().set_zoom_factor(self.ui_scale);
+        }
+
+        if ui
+            .checkbox(&mut self.config.fullscreen, tr("fullscreen"))
+            .changed()
+        {
+            ui.ctx()
+                .send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.config.fullscreen));
+        }
+
+        let mut is_rtc_fixed = self.config.fixed_rtc_timestamp.is_some();
+        if ui
+            .checkbox(&mut is_rtc_fixed, tr("fixed-rtc"))
+            .on_hover_text(tr("fixed-rtc-hint"))
+            .changed()
+        {
+            self.config.fixed_rtc_timestamp = is_rtc_fixed.then(host_timestamp);
+        }
+        if let Some(timestamp) = &mut self.config.fixed_rtc_timestamp {
+            ui.horizontal(|ui| {
+                ui.label(tr("unix-time"));
+                ui.add(egui::DragValue::new(timestamp));
+            });
+        }
+
+        ui.checkbox(&mut self.config.fast_ewram, tr("fast-ewram"))
+            .on_hover_text(tr("fast-ewram-hint"));
+
+        let discord_presence = ui
+            .add_enabled(
+                discord_presence::CLIENT_ID.is_some(),
+                egui::Checkbox::new(&mut self.config.discord_presence, tr("discord-presence")),
+            )
+            .on_hover_text(tr("discord-presence-hint"))
+            .on_disabled_hover_text(tr("discord-presence-not-built"));
+        if discord_presence.changed() {
+            self.discord.set_enabled(self.config.discord_presence);
+        }
+
+        self.frame_settings(ui);
+    }
+
+    /// How the frames are skipped and shown.
+    fn frame_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("frame-skip"))
+                .on_hover_text(tr("frame-skip-hint"));
+
+            let frame_skip = &mut self.config.frame_skip;
+            egui::ComboBox::from_id_source("FrameSkip")
+                .selected_text(frame_skip_name(*frame_skip))
+                .show_ui(ui, |ui| {
+                    for option in [
+                        FrameSkip::Off,
+                        FrameSkip::Auto { max: 3 },
+                        FrameSkip::Fixed(1),
+                    ] {
+                        // The number of frames is kept when the same kind is chosen again.
+                        let is_selected =
+                            std::mem::discriminant(frame_skip) == std::mem::discriminant(&option);
+                        if ui
+                            .selectable_label(is_selected, frame_skip_name(option))
+                            .clicked()
+                            && !is_selected
+                        {
+                            *frame_skip = option;
+                        }
+                    }
+                });
+
+            if let FrameSkip::Fixed(frames) | FrameSkip::Auto { max: frames } = frame_skip {
+                ui.add(egui::DragValue::new(frames).range(1..=9));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("present-mode"))
+                .on_hover_text(tr("present-mode-hint"));
+
+            let present_mode = &mut self.config.present_mode;
+            egui::ComboBox::from_id_source("PresentMode")
+                .selected_text(present_mode.name())
+                .show_ui(ui, |ui| {
+                    for option in PresentMode::ALL {
+                        ui.selectable_value(present_mode, option, option.name());
+                    }
+                });
+        });
+        if self.config.present_mode != self.present_mode {
+            ui.small(tr("present-mode-restart"));
+        }
+
+        let mut renderer = self.config.renderer;
+        ui.horizontal(|ui| {
+            ui.label(tr("renderer")).on_hover_text(tr("renderer-hint"));
+            egui::ComboBox::from_id_source("Renderer")
+                .selected_text(renderer.name())
+                .show_ui(ui, |ui| {
+                    for option in RendererKind::ALL {
+                        ui.selectable_value(&mut renderer, option, option.name());
+                    }
+                });
+        });
+        if renderer != self.config.renderer {
+            self.config.renderer = renderer;
+            renderer::set_renderer(renderer);
+        }
+
+        let mut screen_filter = self.config.screen_filter;
+        ui.horizontal(|ui| {
+            ui.label(tr("screen-filter"))
+                .on_hover_text(tr("screen-filter-hint"));
+            egui::ComboBox::from_id_source("ScreenFilter")
+                .selected_text(screen_filter.name())
+                .show_ui(ui, |ui| {
+                    for option in ScreenFilter::ALL {
+                        ui.selectable_value(&mut screen_filter, option, option.name());
+                    }
+                });
+        });
+        if screen_filter != self.config.screen_filter {
+            self.config.screen_filter = screen_filter;
+            screen_filter::set_screen_filter(screen_filter);
+        }
+    }
+
+    fn color_correction_settings(&mut self, ui: &mut egui::Ui) {
+        let mut color_correction = self.config.color_correction;
+        ui.horizontal(|ui| {
+            ui.label(tr("color-correction"))
+                .on_hover_text(tr("color-correction-hint"));
+            egui::ComboBox::from_id_source("ColorCorrection")
+                .selected_text(color_correction_name(color_correction))
+                .show_ui(ui, |ui| {
+                    for option in ColorCorrection::ALL {
+                        ui.selectable_value(
+                            &mut color_correction,
+                            option,
+                            color_correction_name(option),
+                        );
+                    }
+                });
+        });
+
+        if color_correction != self.config.color_correction {
+            self.config.color_correction = color_correction;
+            gba_display::set_color_correction(self.config.color_correction_of(&self.game_key));
+        }
+
+        if ui
+            .checkbox(&mut self.config.frame_blending, tr("frame-blending"))
+            .on_hover_text(tr("frame-blending-hint"))
+            .changed()
+        {
+            self.apply_enhancements();
+        }
+
+        let mut affine_resolution = self.config.affine_resolution;
+        ui.horizontal(|ui| {
+            ui.label(tr("affine-resolution"))
+                .on_hover_text(tr("affine-resolution-hint"));
+            egui::ComboBox::from_id_source("AffineResolution")
+                .selected_text(affine_resolution_name(affine_resolution))
+                .show_ui(ui, |ui| {
+                    for option in AffineResolution::ALL {
+                        ui.selectable_value(
+                            &mut affine_resolution,
+                            option,
+                            affine_resolution_name(option),
+                        );
+                    }
+                });
+        });
+        if affine_resolution != self.config.affine_resolution {
+            self.config.affine_resolution = affine_resolution;
+            self.apply_enhancements();
+        }
+    }
+
+    /// Video and audio effects of the game being played, over the ones of every game.
+    fn game_enhancement_settings(&mut self, ui: &mut egui::Ui) {
+        let previous = self
+            .config
+            .game_enhancements
+            .get(&self.game_key)
+            .copied()
+            .unwrap_or_default();
+        let mut enhancements = previous;
+
+        ui.label(tr("game-enhancements"))
+            .on_hover_text(tr("game-enhancements-hint"));
+        egui::Grid::new("GameEnhancements")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr("color-correction"));
+                override_combo(
+                    ui,
+                    "GameColorCorrection",
+                    &mut enhancements.color_correction,
+                    &ColorCorrection::ALL,
+                    color_correction_name,
+                );
+                ui.end_row();
+
+                ui.label(tr("frame-blending"));
+                override_combo(
+                    ui,
+                    "GameFrameBlending",
+                    &mut enhancements.frame_blending,
+                    &[false, true],
+                    frame_blending_name,
+                );
+                ui.end_row();
+
+                ui.label(tr("affine-resolution"));
+                override_combo(
+                    ui,
+                    "GameAffineResolution",
+                    &mut enhancements.affine_resolution,
+                    &AffineResolution::ALL,
+                    affine_resolution_name,
+                );
+                ui.end_row();
+
+                ui.label(tr("interpolation"));
+                override_combo(
+                    ui,
+                    "GameInterpolation",
+                    &mut enhancements.interpolation,
+                    &Interpolation::ALL,
+                    audio::interpolation_name,
+                );
+                ui.end_row();
+            });
+
+        if enhancements != previous {
+            if enhancements == GameEnhancements::default() {
+                self.config.game_enhancements.remove(&self.game_key);
+            } else {
+                self.config
+                    .game_enhancements
+                    .insert(self.game_key.clone(), enhancements);
+            }
+            self.apply_enhancements();
+        }
+    }
+
+    /// Applies the video and audio effects of the game being played after they changed.
+    fn apply_enhancements(&self) {
+        gba_display::set_color_correction(self.config.color_correction_of(&self.game_key));
+
+        let mut gba = self.gba.lock().unwrap();
+        let frame_blending = self.config.frame_blending_of(&self.game_key);
+        if frame_blending != gba.cpu.bus.lcd.frame_blending.is_some() {
+            gba.cpu.bus.lcd.frame_blending = frame_blending.then(Box::default);
+        }
+        gba.cpu.bus.lcd.affine_resolution = self.config.affine_resolution_of(&self.game_key);
+        if let Some(interpolation) = self.config.interpolation_of(&self.game_key) {
+            let mut settings = gba.audio_settings();
+            settings.interpolation = interpolation;
+            gba.set_audio_settings(settings);
+        }
+    }
+
+    fn save_sync_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.config.save_sync;
+
+        ui.label(tr("save-sync"))
+            .on_hover_text(tr("save-sync-hint"));
+        ui.horizontal(|ui| {
+            ui.label(tr("save-sync-copy-to"));
+            match &settings.copy_to {
+                Some(dir) => {
+                    ui.label(dir.display().to_string());
+                    if ui.button(tr("save-sync-clear")).clicked() {
+                        settings.copy_to = None;
+                    }
+                }
+                None => {
+                    ui.label(tr("save-sync-no-directory"));
+                }
+            }
+            if ui.button(tr("save-sync-choose")).clicked() {
+                match FileDialog::new().show_open_single_dir() {
+                    Ok(Some(dir)) => settings.copy_to = Some(dir),
+                    Ok(None) => {}
+                    Err(e) => log(format!("can't choose a directory: {e}")),
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("save-sync-command"))
+                .on_hover_text(tr("save-sync-command-hint"));
+            ui.text_edit_singleline(&mut settings.command);
+        });
+        ui.checkbox(&mut settings.reload_on_change, tr("save-sync-reload"))
+            .on_hover_text(tr("save-sync-reload-hint"));
+    }
+
+    fn hot_reload_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.config.hot_reload;
+
+        ui.label(tr("hot-reload"))
+            .on_hover_text(tr("hot-reload-hint"));
+        ui.checkbox(&mut settings.watch, tr("hot-reload-watch"));
+        ui.add_enabled_ui(settings.watch, |ui| {
+            ui.checkbox(&mut settings.automatic, tr("hot-reload-automatic"));
+            ui.checkbox(&mut settings.keep_backup, tr("hot-reload-keep-backup"))
+                .on_hover_text(tr("hot-reload-keep-backup-hint"));
+        });
+    }
+
+    fn core_profile_settings(&mut self, ui: &mut egui::Ui) {
+        let previous = self.config.profile_of(&self.game_key);
+
+        ui.horizontal(|ui| {
+            ui.label(tr("core-profile"))
+                .on_hover_text(tr("core-profile-hint"));
+            profile_combo(ui, "CoreProfile", &mut self.config.core_profile);
+        });
+
+        let mut is_game_profile = self.config.game_core_profiles.contains_key(&self.game_key);
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut is_game_profile, tr("core-profile-game"))
+                .changed()
+            {
+                if is_game_profile {
+                    self.config
+                        .game_core_profiles
+                        .insert(self.game_key.clone(), previous);
+                } else {
+                    self.config.game_core_profiles.remove(&self.game_key);
+                }
+            }
+            if let Some(profile) = self.config.game_core_profiles.get_mut(&self.game_key) {
+                profile_combo(ui, "GameCoreProfile", profile);
+            }
+        });
+
+        let profile = self.config.profile_of(&self.game_key);
+        if profile != previous {
+            self.gba.lock().unwrap().set_profile(profile);
+        }
+    }
+
+    /// Loads the cartridge again after it changed on disk.
+    fn hot_reload(&mut self, reload: Reload) {
+        let keep_backup = self.config.hot_reload.keep_backup;
+        let mut gba = self.gba.lock().unwrap();
+
+        let result = reload_gba(
+            &mut gba,
+            self.hot_reload.cartridge_name(),
+            reload.bios,
+            keep_backup,
+        );
+        if result.is_ok() && !keep_backup {
+            // The erased backup memory is written to the file only once the game saves.
+            self.battery.replaced(&gba);
+        }
+        drop(gba);
+
+        if let Err(e) = &result {
+            log(format!("can't reload the cartridge: {e}"));
+        }
+        self.hot_reload.finished(result.map_err(|e| e.to_string()));
+    }
+
+    fn border_settings(&mut self, ui: &mut egui::Ui) {
+        let mut settings = self.config.border.clone();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("border"));
+            egui::ComboBox::from_id_source("Border")
+                .selected_text(border_name(&settings.source))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut settings.source,
+                        BorderSource::None,
+                        tr("border-none"),
+                    );
+                    ui.selectable_value(
+                        &mut settings.source,
+                        BorderSource::Shell,
+                        tr("border-shell"),
+                    );
+                    if let BorderSource::File(_) = &settings.source {
+                        let _ = ui.selectable_label(true, border_name(&settings.source));
+                    }
+                });
+
+            if ui.button(tr("border-open")).clicked() {
+                match FileDialog::new()
+                    .add_filter(tr("border-png"), &["png"])
+                    .show_open_single_file()
+                {
+                    Ok(Some(path)) => settings.source = BorderSource::File(path),
+                    Ok(None) => {}
+                    Err(e) => log(format!("can't choose a border: {e}")),
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("border-scaling"))
+                .on_hover_text(tr("border-scaling-hint"));
+            ui.radio_value(
+                &mut settings.scaling,
+                BorderScaling::Fit,
+                tr("border-scaling-fit"),
+            );
+            ui.radio_value(
+                &mut settings.scaling,
+                BorderScaling::Integer,
+                tr("border-scaling-integer"),
+            );
+        });
+
+        if settings != self.config.border {
+            self.border.lock().unwrap().clone_from(&settings);
+            self.config.border = settings;
+        }
+    }
+
+    /// Changes the settings to the ones of `preset`, the presentation is applied at the
+    /// next start like when it's chosen by hand.
+    fn apply_preset(&mut self, ctx: &egui::Context, preset: Preset) {
+        preset.apply(&mut self.config);
+
+        self.border.lock().unwrap().clone_from(&self.config.border);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.config.fullscreen));
+    }
+
+    /// Asks the device Clementine runs on the first time it's started.
+    fn preset_picker(&mut self, ctx: &egui::Context) {
+        let mut chosen = None;
+
+        egui::Window::new(tr("preset-picker"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr("preset-picker-hint"));
+                for preset in Preset::ALL {
+                    ui.separator();
+                    if ui
+                        .button(egui::RichText::new(preset.name()).size(18.0))
+                        .clicked()
+                    {
+                        chosen = Some(preset);
+                    }
+                    ui.label(preset.description());
+                }
+            });
+
+        if let Some(preset) = chosen {
+            self.apply_preset(ctx, preset);
+        }
+    }
+
+    fn set_theme(&mut self, ctx: &egui::Context, theme: Theme) {
+        ctx.set_visuals(theme.visuals());
+        self.config.theme = theme;
+    }
+
+    /// Every action of the application: showing the tools, the settings and the commands of the tools.
+    fn commands(&self) -> Vec<(Command, CommandTarget)> {
+        let mut commands = Vec::new();
+
+        for (index, tool) in self.tools.iter().enumerate() {
+            let title = tool_title(tool.name());
+            let message = if self.open.contains(tool.name()) {
+                "hide-tool"
+            } else {
+                "show-tool"
+            };
+
+            commands.push((
+                Command::new(tr_args(message, &[("tool", &title)])),
+                CommandTarget::ToggleTool(index),
+            ));
+        }
+
+        for theme in Theme::ALL {
+            commands.push((
+                Command::new(tr_args("use-theme", &[("theme", &theme.name())])),
+                CommandTarget::Theme(theme),
+            ));
+        }
+
+        for (tool, ui_tool) in self.tools.iter().enumerate() {
+            for (command, tool_command) in ui_tool.commands().into_iter().enumerate() {
+                commands.push((tool_command, CommandTarget::Tool { tool, command }));
+            }
+        }
+
+        commands
+    }
+
+    fn command_palette(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|input| input.consume_shortcut(&PALETTE_SHORTCUT)) {
+            self.palette.toggle();
+        }
+
+        if !self.palette.is_open() {
+            return;
+        }
+
+        let (commands, targets): (Vec<_>, Vec<_>) = self.commands().into_iter().unzip();
+        let Some((index, argument)) = self.palette.show(ctx, &commands) else {
+            return;
+        };
+
+        match targets[index] {
+            CommandTarget::ToggleTool(tool) => {
+                let name = self.tools[tool].name();
+                let is_open = self.open.contains(name);
+                set_open(&mut self.open, name, !is_open);
+            }
+            CommandTarget::Theme(theme) => self.set_theme(ctx, theme),
+            CommandTarget::Tool { tool, command } => {
+                if !self.tools[tool].run_command(command, &argument) {
+                    self.palette.reject_argument(index, argument);
+                }
+            }
+        }
+    }
+
+    /// Accepts commands on a socket at `path`, see [`crate::control_socket`].
+    ///
+    /// # Errors
+    /// It returns an error if the socket can't be made.
+    #[cfg(unix)]
+    pub fn listen(
+        &mut self,
+        path: &Path,
+        ctx: &egui::Context,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let ctx = ctx.clone();
+        self.control_socket = Some(ControlSocket::listen(path, move || ctx.request_repaint())?);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn run_control_requests(&mut self) {
+        while let Some(pending) = self.control_socket.as_ref().and_then(ControlSocket::next) {
+            let result = self
+                .run_control_request(&pending.request)
+                .map_err(|e| e.to_string());
+            pending.reply(result);
+        }
+    }
+
+    #[cfg(unix)]
+    fn run_control_request(&mut self, request: &Request) -> Result<(), Box<dyn error::Error>> {
+        match request {
+            Request::LoadRom { path } => self.load_cartridge(path)?,
+            Request::Pause => self.run_tool_command("Cpu Handler", 1),
+            Request::Resume => self.run_tool_command("Cpu Handler", 0),
+            Request::SaveState { path } => {
+                let state = self.gba.lock().unwrap().save_state_file()?;
+                std::fs::write(path, state)?;
+            }
+            Request::LoadState { path } => {
+                let data = std::fs::read(path)?;
+                self.gba.lock().unwrap().load_state_file(&data)?;
+            }
+            Request::Press { keys, frames } => {
+                self.gba
+                    .lock()
+                    .unwrap()
+                    .hold_keys(Key::pressed(keys), *frames);
+            }
+            Request::Screenshot { path } => {
+                gba_display::save_screenshot(&self.gba.lock().unwrap(), path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a command of the palette of the tool named `name`.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn run_tool_command(&mut self, name: &str, command: usize) {
+        if let Some(tool) = self.tools.iter_mut().find(|tool| tool.name() == name) {
+            tool.run_command(command, "");
+        }
+    }
+
+    /// Plays another cartridge, with its own save and settings.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn load_cartridge(&mut self, cartridge_name: &str) -> Result<(), Box<dyn error::Error>> {
+        let mut gba = self.gba.lock().unwrap();
+        self.battery.flush(&gba, &self.config.save_sync);
+        reload_gba(&mut gba, cartridge_name, false, false)?;
+
+        self.battery = BatterySave::new(network_rom::local_name(cartridge_name), &mut gba);
+        self.game_key = game_key(&gba.cpu.bus.internal_memory.rom);
+        CoreSettings::new(&self.config, &self.game_key).apply(&mut gba);
+        drop(gba);
+
+        let is_reloadable = !is_multiboot(cartridge_name) && !network_rom::is_url(cartridge_name);
+        self.hot_reload = HotReload::new(cartridge_name, is_reloadable, bios_path());
+        gba_display::set_color_correction(self.config.color_correction_of(&self.game_key));
+
+        Ok(())
+    }
+
+    /// Shows the tools to see where the emulation was stuck.
+    fn open_debugger(&mut self) {
+        for tool in &self.tools {
+            if DEBUGGER_TOOLS.contains(&tool.name()) {
+                set_open(&mut self.open, tool.name(), true);
+            }
+        }
+    }
+
     fn windows(&mut self, ctx: &egui::Context) {
-        let Self { tools, open } = self;
+        let Self { tools, open, .. } = self;
         for tool in tools {
             let mut is_open = open.contains(tool.name());
             tool.show(ctx, &mut is_open);
@@ -104,19 +1027,57 @@ impl App {
 }
 
 impl eframe::App for App {
+    #[cfg(feature = "gamepad")]
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        if let Some(gamepad) = &mut self.gamepad {
+            let is_anything_focused = ctx.memory(|memory| memory.focused().is_some());
+            gamepad.poll(raw_input, is_anything_focused);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
+        match self.present_mode {
+            PresentMode::Vsync => ctx.request_repaint(),
+            PresentMode::Adaptive => {
+                if self.repaint_callback.is_none() {
+                    let ctx = ctx.clone();
+                    self.repaint_callback = Some(
+                        self.gba
+                            .lock()
+                            .unwrap()
+                            .on_event(CoreEvent::FrameComplete, move |_| ctx.request_repaint()),
+                    );
+                }
+            }
+        }
+
+        match self.watchdog.show(ctx) {
+            WatchdogStatus::Running => {}
+            // Everything else locks the emulator.
+            WatchdogStatus::Stuck => return,
+            WatchdogStatus::Paused => self.open_debugger(),
+        }
+        #[cfg(unix)]
+        self.run_control_requests();
+        self.battery.update(&self.gba, &self.config.save_sync);
+        if let Some(reload) = self.hot_reload.show(ctx, self.config.hot_reload) {
+            self.hot_reload(reload);
+        }
+
+        if !self.is_layout_restored {
+            self.restore_layout(ctx);
+        }
 
         egui::SidePanel::right("Clementine Tools")
             .resizable(false)
             .default_width(200.0)
             .show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
-                    ui.heading("✒ Clementine Tools");
+                    ui.heading(tr("tools-heading"));
                 });
 
                 ui.separator();
-                ui.label("Links");
+                ui.label(tr("links"));
                 ui.hyperlink_to(
                     format!("{} Clementine", egui::special_emojis::GITHUB),
                     "https://github.com/RIP-Comm/clementine",
@@ -125,13 +1086,373 @@ impl eframe::App for App {
                 ui.separator();
 
                 self.checkboxes(ui);
+
+                ui.separator();
+                ui.label(tr("settings"));
+                self.settings(ui);
             });
 
         self.windows(ctx);
+        self.command_palette(ctx);
+        if self.config.preset.is_none() {
+            self.preset_picker(ctx);
+        }
+
+        if ctx.input(|input| input.viewport().close_requested()) {
+            self.save_layout(ctx);
+        }
+    }
+
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        // The OpenGL objects of the screens must be deleted while the context is there.
+        self.tools.clear();
+        if let Some(gl) = gl {
+            renderer::delete_released_screens(gl);
+        }
+    }
+}
+
+/// The BIOS in the data directory, or in the working directory like the older versions.
+fn bios_path() -> PathBuf {
+    let local = env::current_dir().unwrap().join(paths::BIOS_FILE_NAME);
+
+    paths::data_dir()
+        .map(|dir| dir.join(paths::BIOS_FILE_NAME))
+        .filter(|path| path.is_file())
+        .unwrap_or(local)
+}
+
+/// The BIOS (see [`bios_path`]), blank without a BIOS file: the emulator runs the BIOS
+/// functions then.
+fn read_bios() -> Result<[u8; 0x0000_4000], Box<dyn error::Error>> {
+    let bios = match std::fs::read(bios_path()) {
+        Ok(bios) => bios,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok([0; 0x0000_4000]),
+        Err(e) => return Err(e.into()),
+    };
+    let bios = bios.get(0..0x0000_4000).ok_or("BIOS must be 16 KBytes")?;
+
+    Ok(bios.try_into()?)
+}
+
+/// Loads the cartridge (or multiboot image) with its patch and debug info, and the BIOS
+/// (see [`read_bios`]). It exits the process if they can't be loaded.
+///
+/// # Panics
+/// It panics if the cartridge can't be opened.
+#[must_use]
+pub fn load_gba(cartridge_name: &str) -> Gba {
+    let data = match read_file(cartridge_name) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("can't load the cartridge: {e}");
+            std::process::exit(2);
+        }
+    };
+
+    let data = match apply_patch_next_to(cartridge_name, data) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("can't apply patch: {e}");
+            std::process::exit(4);
+        }
+    };
+
+    let bios = match read_bios() {
+        Ok(bios) => bios,
+        Err(e) => {
+            eprintln!("can't open bios file: {e}");
+            std::process::exit(3);
+        }
+    };
+    if !bios_path().is_file() {
+        eprintln!("warning: no bios file, the emulator runs the BIOS functions itself");
+    }
+
+    let mut gba = if is_multiboot(cartridge_name) {
+        match Gba::with_multiboot(bios, &data) {
+            Ok(gba) => gba,
+            Err(e) => {
+                eprintln!("can't load multiboot image: {e}");
+                std::process::exit(5);
+            }
+        }
+    } else {
+        let cartridge_header = Header::new(data.as_slice()).expect("Cartridge must be opened");
+        Gba::new(cartridge_header, bios, data)
+    };
+
+    for warning in gba.cartridge_header.warnings() {
+        eprintln!("warning: {warning}, the cartridge may be a bad dump");
+    }
+
+    if let Err(e) = load_debug_info_next_to(cartridge_name, &mut gba) {
+        eprintln!("warning: can't load symbols: {e}");
+    }
+
+    if let Err(e) = load_annotations_next_to(cartridge_name, &mut gba) {
+        eprintln!("warning: can't load annotations: {e}");
+    }
+
+    gba
+}
+
+/// Loads the cartridge (or multiboot image) with its patch and the BIOS like [`load_gba`],
+/// without printing anything or exiting (eg. for one of many ROMs of a test run).
+///
+/// # Errors
+/// It returns an error if the cartridge or the BIOS can't be loaded.
+#[allow(clippy::large_stack_frames)]
+pub fn try_load_gba(cartridge_name: &str) -> Result<Gba, Box<dyn error::Error>> {
+    let data = apply_patch_next_to(cartridge_name, read_file(cartridge_name)?)?;
+    let bios = read_bios()?;
+
+    if is_multiboot(cartridge_name) {
+        return Ok(Gba::with_multiboot(bios, &data)?);
+    }
+
+    Ok(Gba::new(Header::new(&data)?, bios, data))
+}
+
+/// Loads the cartridge again with its patch and debug info, and the BIOS if `reload_bios`,
+/// eg. after a homebrew was rebuilt. The current ones are kept if they can't be loaded.
+fn reload_gba(
+    gba: &mut Gba,
+    cartridge_name: &str,
+    reload_bios: bool,
+    keep_backup: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let data = apply_patch_next_to(cartridge_name, read_file(cartridge_name)?)?;
+    let bios = if reload_bios {
+        Some(read_bios()?)
+    } else {
+        None
+    };
+
+    gba.replace_cartridge(data, bios, keep_backup)?;
+    load_debug_info_next_to(cartridge_name, gba)?;
+
+    Ok(())
+}
+
+/// Multiboot images run from EWRAM, without a cartridge.
+fn is_multiboot(cartridge_name: &str) -> bool {
+    Path::new(network_rom::local_name(cartridge_name))
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mb"))
+}
+
+/// Settings of the emulated machine from the config, the same for every core of the session.
+struct CoreSettings {
+    fixed_rtc_timestamp: Option<u64>,
+    fast_ewram: bool,
+    frame_skip: FrameSkip,
+    profile: CoreProfile,
+    boot_patches: Vec<BootPatch>,
+    frame_blending: bool,
+    affine_resolution: AffineResolution,
+    interpolation: Option<Interpolation>,
+}
+
+impl CoreSettings {
+    fn new(config: &Config, game_key: &str) -> Self {
+        Self {
+            fixed_rtc_timestamp: config.fixed_rtc_timestamp,
+            fast_ewram: config.fast_ewram,
+            frame_skip: config.frame_skip,
+            profile: config.profile_of(game_key),
+            boot_patches: config
+                .boot_patches
+                .get(game_key)
+                .cloned()
+                .unwrap_or_default(),
+            frame_blending: config.frame_blending_of(game_key),
+            affine_resolution: config.affine_resolution_of(game_key),
+            interpolation: config.interpolation_of(game_key),
+        }
+    }
+
+    fn apply(&self, gba: &mut Gba) {
+        // Only the starting time comes from the host, then the clock follows the emulation.
+        if let Some(timestamp) = self.fixed_rtc_timestamp {
+            gba.set_rtc_timestamp(timestamp);
+        } else {
+            let timestamp = host_timestamp();
+            gba.set_rtc_timestamp(timestamp);
+            if let Err(e) = rtc_battery::restore(gba, timestamp) {
+                log(format!("can't restore the cartridge clock: {e}"));
+            }
+        }
+        gba.cpu.bus.fast_ewram = self.fast_ewram;
+        gba.cpu.bus.lcd.frame_skip = self.frame_skip;
+        gba.set_profile(self.profile);
+        gba.set_boot_patches(self.boot_patches.clone());
+        gba.cpu.bus.lcd.frame_blending = self.frame_blending.then(Box::default);
+        gba.cpu.bus.lcd.affine_resolution = self.affine_resolution;
+        if let Some(interpolation) = self.interpolation {
+            let mut settings = gba.audio_settings();
+            settings.interpolation = interpolation;
+            gba.set_audio_settings(settings);
+        }
+    }
+}
+
+/// Seconds since the Unix epoch on the host, 0 if the host clock is before it.
+fn host_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+fn border_name(source: &BorderSource) -> String {
+    match source {
+        BorderSource::None => tr("border-none").to_owned(),
+        BorderSource::Shell => tr("border-shell").to_owned(),
+        BorderSource::File(path) => path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        ),
+    }
+}
+
+fn color_correction_name(color_correction: ColorCorrection) -> &'static str {
+    match color_correction {
+        ColorCorrection::Off => tr("color-correction-off"),
+        ColorCorrection::GbaLcd => tr("color-correction-gba"),
+        ColorCorrection::GbaSp => tr("color-correction-gba-sp"),
+        ColorCorrection::Nds => tr("color-correction-nds"),
+    }
+}
+
+fn frame_blending_name(frame_blending: bool) -> &'static str {
+    if frame_blending {
+        tr("frame-blending-on")
+    } else {
+        tr("frame-blending-off")
+    }
+}
+
+fn affine_resolution_name(affine_resolution: AffineResolution) -> &'static str {
+    match affine_resolution {
+        AffineResolution::Native => tr("affine-resolution-native"),
+        AffineResolution::Double => tr("affine-resolution-double"),
+        AffineResolution::Quadruple => tr("affine-resolution-quadruple"),
+    }
+}
+
+/// Chooses one of `options` for the game, or `None` for the one of every game.
+fn override_combo<T: Copy + PartialEq>(
+    ui: &mut egui::Ui,
+    id: &str,
+    value: &mut Option<T>,
+    options: &[T],
+    name: fn(T) -> &'static str,
+) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(value.map_or_else(|| tr("game-enhancement-default"), name))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, tr("game-enhancement-default"));
+            for &option in options {
+                ui.selectable_value(value, Some(option), name(option));
+            }
+        });
+}
+
+fn profile_combo(ui: &mut egui::Ui, id: &str, profile: &mut CoreProfile) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(profile_name(*profile))
+        .show_ui(ui, |ui| {
+            for option in CoreProfile::ALL {
+                ui.selectable_value(profile, option, profile_name(option));
+            }
+        });
+}
+
+fn profile_name(profile: CoreProfile) -> &'static str {
+    match profile {
+        CoreProfile::Accuracy => tr("core-profile-accuracy"),
+        CoreProfile::Fast => tr("core-profile-fast"),
+    }
+}
+
+fn frame_skip_name(frame_skip: FrameSkip) -> &'static str {
+    match frame_skip {
+        FrameSkip::Off => tr("frame-skip-off"),
+        FrameSkip::Auto { .. } => tr("frame-skip-auto"),
+        FrameSkip::Fixed(_) => tr("frame-skip-fixed"),
+    }
+}
+
+/// Looks for a patch with the same name of the cartridge (eg. `game.gba` and `game.ips`)
+/// and applies it in memory, the cartridge file is not modified.
+fn apply_patch_next_to(
+    cartridge_name: &str,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    let cartridge_path = Path::new(cartridge_name);
+
+    for extension in patch::PatchKind::EXTENSIONS {
+        let patch_path = cartridge_path.with_extension(extension);
+        if !patch_path.is_file() {
+            continue;
+        }
+
+        log(format!("applying patch {}", patch_path.display()));
+        let patch_data = std::fs::read(patch_path)?;
+
+        return Ok(patch::apply(&data, &patch_data)?);
+    }
+
+    Ok(data)
+}
+
+/// Symbols and source lines of homebrew ROMs, from the ELF or `.sym`/`.map` file with the same name.
+fn load_debug_info_next_to(
+    cartridge_name: &str,
+    gba: &mut Gba,
+) -> Result<(), Box<dyn error::Error>> {
+    let cartridge_path = Path::new(cartridge_name);
+
+    for extension in symbols::EXTENSIONS {
+        let symbols_path = cartridge_path.with_extension(extension);
+        if !symbols_path.is_file() {
+            continue;
+        }
+
+        log(format!("loading symbols {}", symbols_path.display()));
+        let data = std::fs::read(symbols_path)?;
+
+        return Ok(gba.load_debug_info(&data)?);
+    }
+
+    Ok(())
+}
+
+/// Labels and comments of the disassembly, saved next to the cartridge (eg. `game.notes`).
+fn load_annotations_next_to(
+    cartridge_name: &str,
+    gba: &mut Gba,
+) -> Result<(), Box<dyn error::Error>> {
+    let annotations_path = Path::new(cartridge_name).with_extension(annotations::EXTENSION);
+    if !annotations_path.is_file() {
+        return Ok(());
     }
+
+    log(format!(
+        "loading annotations {}",
+        annotations_path.display()
+    ));
+    let text = std::fs::read_to_string(annotations_path)?;
+    gba.cpu.annotations = Annotations::parse(&text)?;
+
+    Ok(())
 }
 
-fn read_file(filepath: String) -> Result<Vec<u8>, Box<dyn error::Error>> {
+fn read_file(filepath: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    if network_rom::is_url(filepath) {
+        return network_rom::download(filepath);
+    }
+
     let mut f = std::fs::File::open(filepath)?;
     let mut buf = vec![];
     f.read_to_end(&mut buf)?;
@@ -0,0 +1,245 @@
+use std::sync::{Arc, Mutex};
+
+use emu::cpu::hardware::sound::{Interpolation, MAX_VOLUME};
+use emu::gba::Gba;
+
+#[cfg(feature = "audio")]
+use crate::audio_output::{self, AudioOutput};
+use crate::i18n::{tr, tr_args};
+use crate::osd::Osd;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Toggles the mute from anywhere in the application.
+const MUTE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::M);
+
+pub struct Audio {
+    gba: Arc<Mutex<Gba>>,
+    osd: Osd,
+    #[cfg(feature = "audio")]
+    output: Result<AudioOutput, String>,
+    /// Device chosen by the user, `None` for the default one.
+    #[cfg(feature = "audio")]
+    device: Option<String>,
+    #[cfg(feature = "audio")]
+    device_names: Vec<String>,
+}
+
+impl Audio {
+    #[cfg_attr(not(feature = "audio"), allow(clippy::missing_const_for_fn))]
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            #[cfg(feature = "audio")]
+            output: AudioOutput::new(Arc::clone(&gba), None).map_err(|err| err.to_string()),
+            #[cfg(feature = "audio")]
+            device: None,
+            #[cfg(feature = "audio")]
+            device_names: audio_output::output_device_names(),
+            gba,
+            osd: Osd::default(),
+        }
+    }
+
+    fn toggle_mute(&mut self) {
+        let mut gba = self.gba.lock().unwrap();
+        let mut settings = gba.audio_settings();
+        settings.muted = !settings.muted;
+        gba.set_audio_settings(settings);
+        drop(gba);
+
+        self.osd.show_message(if settings.muted {
+            tr("muted").to_string()
+        } else {
+            tr_args("volume-osd", &[("volume", &settings.volume)])
+        });
+    }
+
+    #[cfg(feature = "audio")]
+    fn open_device(&mut self, device: Option<String>) {
+        // The old stream has to stop before the new one takes the samples.
+        self.output = Err(String::new());
+        self.output = AudioOutput::new(Arc::clone(&self.gba), device.as_deref())
+            .map_err(|err| err.to_string());
+        self.device = device;
+    }
+
+    /// Falls back to the default device when the current one disappears.
+    #[cfg(feature = "audio")]
+    fn check_device(&mut self) {
+        let Ok(output) = &self.output else {
+            return;
+        };
+
+        if output.is_device_lost() {
+            let lost_name = output.device_name().to_string();
+            self.device_names = audio_output::output_device_names();
+            self.open_device(None);
+            self.osd
+                .show_message(tr_args("device-lost", &[("device", &lost_name)]));
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    fn device_ui(&mut self, ui: &mut egui::Ui) {
+        let selected = self
+            .device
+            .clone()
+            .unwrap_or_else(|| tr("default-device").to_string());
+        let mut device = self.device.clone();
+
+        ui.label(tr("output-device"));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("Output device")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut device, None, tr("default-device"));
+                    for name in &self.device_names {
+                        ui.selectable_value(&mut device, Some(name.clone()), name);
+                    }
+                });
+
+            if ui
+                .button("⟳")
+                .on_hover_text(tr("refresh-devices"))
+                .clicked()
+            {
+                self.device_names = audio_output::output_device_names();
+            }
+        });
+        ui.end_row();
+
+        if device != self.device {
+            self.open_device(device);
+        }
+    }
+}
+
+pub fn interpolation_name(interpolation: Interpolation) -> &'static str {
+    tr(match interpolation {
+        Interpolation::Nearest => "interpolation-nearest",
+        Interpolation::Linear => "interpolation-linear",
+        Interpolation::Sinc => "interpolation-sinc",
+    })
+}
+
+impl UiTool for Audio {
+    fn name(&self) -> &'static str {
+        "Audio"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        if ctx.input_mut(|input| input.consume_shortcut(&MUTE_SHORTCUT)) {
+            self.toggle_mute();
+        }
+
+        #[cfg(feature = "audio")]
+        self.check_device();
+
+        tool_window(ctx, self.name())
+            .default_width(240.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+
+        self.osd.show(ctx);
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut settings = self.gba.lock().unwrap().audio_settings();
+        let shortcut = ui.ctx().format_shortcut(&MUTE_SHORTCUT);
+
+        egui::Grid::new("Audio settings")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .show(ui, |ui| {
+                ui.label(tr("volume"));
+                ui.horizontal(|ui| {
+                    ui.add_enabled(
+                        !settings.muted,
+                        egui::Slider::new(&mut settings.volume, 0..=MAX_VOLUME).suffix("%"),
+                    );
+                    ui.checkbox(&mut settings.muted, tr("mute"))
+                        .on_hover_text(tr_args("toggle-with", &[("shortcut", &shortcut)]));
+                });
+                ui.end_row();
+
+                ui.label(tr("interpolation"));
+                egui::ComboBox::from_id_source("Interpolation")
+                    .selected_text(interpolation_name(settings.interpolation))
+                    .show_ui(ui, |ui| {
+                        for interpolation in Interpolation::ALL {
+                            ui.selectable_value(
+                                &mut settings.interpolation,
+                                interpolation,
+                                interpolation_name(interpolation),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr("low-pass-filter"));
+                ui.checkbox(&mut settings.low_pass_filter, "")
+                    .on_hover_text(tr("low-pass-filter-hint"));
+                ui.end_row();
+
+                ui.label(tr("swap-channels"));
+                ui.checkbox(&mut settings.swap_channels, "");
+                ui.end_row();
+
+                ui.label(tr("mono"));
+                ui.checkbox(&mut settings.mono, "")
+                    .on_hover_text(tr("mono-hint"));
+                ui.end_row();
+
+                #[cfg(feature = "audio")]
+                self.device_ui(ui);
+
+                ui.label(tr("sample-rate"));
+                ui.label(format!("{} Hz", settings.sample_rate));
+                ui.end_row();
+
+                let [fifo_a, fifo_b] = self.gba.lock().unwrap().audio_fifo_stats();
+                ui.label(tr("fifo-underruns"))
+                    .on_hover_text(tr("fifo-errors-hint"));
+                ui.label(format!("A {} / B {}", fifo_a.underruns, fifo_b.underruns));
+                ui.end_row();
+
+                ui.label(tr("fifo-overruns"))
+                    .on_hover_text(tr("fifo-errors-hint"));
+                ui.label(format!("A {} / B {}", fifo_a.overruns, fifo_b.overruns));
+                ui.end_row();
+            });
+
+        let mut gba = self.gba.lock().unwrap();
+        // The sample rate may have been changed by a new device.
+        settings.sample_rate = gba.audio_settings().sample_rate;
+        if settings != gba.audio_settings() {
+            gba.set_audio_settings(settings);
+        }
+        drop(gba);
+
+        ui.add_space(8.0);
+
+        #[cfg(feature = "audio")]
+        if let Err(err) = &self.output {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                tr_args("no-audio-output", &[("error", err)]),
+            );
+        }
+
+        #[cfg(not(feature = "audio"))]
+        ui.colored_label(egui::Color32::GRAY, tr("built-without-audio"));
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::new(tr("toggle-mute"))]
+    }
+
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        self.toggle_mute();
+
+        true
+    }
+}
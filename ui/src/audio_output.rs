@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample, StreamError};
+
+use emu::events::{CallbackId, CoreEvent};
+use emu::gba::Gba;
+
+use crate::frame_pacing;
+
+/// Samples waiting to be played, older ones are dropped past this latency.
+const MAX_QUEUED_SECONDS: u32 = 1;
+
+type SampleQueue = Arc<Mutex<VecDeque<[i16; 2]>>>;
+
+/// Names of the output devices currently available.
+pub fn output_device_names() -> Vec<String> {
+    cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Plays the samples of the emulator on an output device.
+pub struct AudioOutput {
+    gba: Arc<Mutex<Gba>>,
+    callback_id: CallbackId,
+    device_name: String,
+    /// Set by the stream when the device is unplugged or disabled.
+    device_lost: Arc<AtomicBool>,
+    _stream: cpal::Stream,
+}
+
+impl AudioOutput {
+    /// Opens the device named `device_name`, the default one if `None`.
+    pub fn new(gba: Arc<Mutex<Gba>>, device_name: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let host = cpal::default_host();
+        let device = match device_name {
+            Some(name) => host
+                .output_devices()?
+                .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+                .ok_or_else(|| format!("Audio device {name} not found"))?,
+            None => host
+                .default_output_device()
+                .ok_or("No audio output device")?,
+        };
+        let config = device.default_output_config()?;
+        let sample_rate = config.sample_rate().0;
+
+        let queue = SampleQueue::default();
+        let device_lost = Arc::new(AtomicBool::new(false));
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => {
+                build_stream::<f32>(&device, &config.into(), &queue, &device_lost)?
+            }
+            cpal::SampleFormat::I16 => {
+                build_stream::<i16>(&device, &config.into(), &queue, &device_lost)?
+            }
+            cpal::SampleFormat::U16 => {
+                build_stream::<u16>(&device, &config.into(), &queue, &device_lost)?
+            }
+            format => return Err(format!("Unsupported sample format {format}").into()),
+        };
+        stream.play()?;
+
+        let mut locked_gba = gba.lock().unwrap();
+        let mut settings = locked_gba.audio_settings();
+        settings.sample_rate = sample_rate;
+        locked_gba.set_audio_settings(settings);
+
+        let max_queued = (sample_rate * MAX_QUEUED_SECONDS) as usize;
+        let callback_id = locked_gba.on_event(CoreEvent::AudioBufferFull, move |gba| {
+            let samples = gba.take_audio_samples();
+            let mut queue = queue.lock().unwrap();
+
+            queue.extend(samples);
+            if queue.len() > max_queued {
+                let excess = queue.len() - max_queued;
+                queue.drain(..excess);
+            }
+            frame_pacing::set_queued_audio(queue.len(), sample_rate);
+        });
+        drop(locked_gba);
+
+        Ok(Self {
+            gba,
+            callback_id,
+            device_name: device.name()?,
+            device_lost,
+            _stream: stream,
+        })
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        self.gba.lock().unwrap().remove_callback(self.callback_id);
+        frame_pacing::set_queued_audio(0, 0);
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: &SampleQueue,
+    device_lost: &Arc<AtomicBool>,
+) -> Result<cpal::Stream, Box<dyn Error>>
+where
+    T: SizedSample + FromSample<i16>,
+{
+    let channels = usize::from(config.channels);
+    let sample_rate = config.sample_rate.0;
+    let queue = Arc::clone(queue);
+    let device_lost = Arc::clone(device_lost);
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            let mut queue = queue.lock().unwrap();
+
+            for frame in data.chunks_mut(channels) {
+                // Silence when the emulator is paused or too slow.
+                let [left, right] = queue.pop_front().unwrap_or_default();
+
+                match frame {
+                    [mono] => {
+                        *mono = T::from_sample(i16::midpoint(left, right));
+                    }
+                    [first, second, others @ ..] => {
+                        *first = T::from_sample(left);
+                        *second = T::from_sample(right);
+                        others.fill(T::EQUILIBRIUM);
+                    }
+                    [] => {}
+                }
+            }
+            frame_pacing::set_queued_audio(queue.len(), sample_rate);
+            drop(queue);
+        },
+        move |err| match err {
+            StreamError::DeviceNotAvailable => device_lost.store(true, Ordering::Relaxed),
+            StreamError::BackendSpecific { .. } => {
+                logger::log(format!("audio output error: {err}"));
+            }
+        },
+        None,
+    )?;
+
+    Ok(stream)
+}
@@ -0,0 +1,240 @@
+use std::{
+    error::Error,
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use egui::{ScrollArea, TextStyle};
+use emu::{
+    cartridge::{backup::BackupKind, hash::game_key},
+    gba::Gba,
+};
+use native_dialog::FileDialog;
+
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+const BYTES_PER_ROW: usize = 16;
+
+/// The saves of the game in the backup memory, shown and edited as hex while the game
+/// runs. The changes go through the backup memory like the ones of the game, so the
+/// `.sav` file is written by the battery save once they stop.
+pub struct BackupMemory {
+    gba: Arc<Mutex<Gba>>,
+    /// Detected the first time the window is shown, the whole ROM is scanned.
+    kind: Option<BackupKind>,
+    selected: Option<usize>,
+    /// New value of the selected byte, as typed.
+    value: String,
+    error: Option<String>,
+}
+
+impl BackupMemory {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            kind: None,
+            selected: None,
+            value: String::new(),
+            error: None,
+        }
+    }
+
+    fn export(&self) -> Result<(), Box<dyn Error>> {
+        // Copied at once, the game can't be halfway through a save.
+        let (backup, game) = {
+            let gba = self.gba.lock().unwrap();
+            (
+                gba.backup().to_vec(),
+                game_key(&gba.cpu.bus.internal_memory.rom),
+            )
+        };
+        let size = self.shown_size(backup.len());
+
+        let path = FileDialog::new()
+            .set_location("~")
+            .set_filename(&format!("{game}.sav"))
+            .add_filter(tr("backup-file"), &["sav"])
+            .show_save_single_file()?;
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+
+        fs::write(path, &backup[..size])?;
+
+        Ok(())
+    }
+
+    fn import(&self) -> Result<(), Box<dyn Error>> {
+        let path = FileDialog::new()
+            .set_location("~")
+            .add_filter(tr("backup-file"), &["sav"])
+            .show_open_single_file()?;
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        let data = fs::read(path)?;
+
+        self.gba.lock().unwrap().set_backup(&data)?;
+
+        Ok(())
+    }
+
+    /// Bytes used by the kind of backup, the whole memory if it's not known.
+    fn shown_size(&self, backup_size: usize) -> usize {
+        self.kind
+            .and_then(BackupKind::size)
+            .map_or(backup_size, |size| size.min(backup_size))
+    }
+
+    fn write_selected(&mut self) {
+        let Some(offset) = self.selected else {
+            return;
+        };
+        let Ok(value) = u8::from_str_radix(self.value.trim(), 16) else {
+            self.error = Some(tr("backup-invalid-byte").to_owned());
+            return;
+        };
+
+        self.error = self
+            .gba
+            .lock()
+            .unwrap()
+            .write_backup(offset, &[value])
+            .err();
+        if self.error.is_none() {
+            self.selected = Some(offset + 1);
+            self.value.clear();
+        }
+    }
+
+    fn rows_ui(&mut self, ui: &mut egui::Ui, backup: &[u8]) {
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        let rows = backup.len().div_ceil(BYTES_PER_ROW);
+
+        ScrollArea::vertical()
+            .id_source("Backup bytes")
+            .max_height(320.0)
+            .show_rows(ui, row_height, rows, |ui, rows| {
+                for row in rows {
+                    let start = row * BYTES_PER_ROW;
+                    let bytes = &backup[start..(start + BYTES_PER_ROW).min(backup.len())];
+
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("{start:05X}"));
+                        for (offset, byte) in (start..).zip(bytes) {
+                            let is_selected = self.selected == Some(offset);
+                            let label = egui::SelectableLabel::new(
+                                is_selected,
+                                egui::RichText::new(format!("{byte:02X}")).monospace(),
+                            );
+                            if ui.add(label).clicked() {
+                                self.selected = Some(offset);
+                                self.value = format!("{byte:02X}");
+                            }
+                        }
+                        ui.monospace(printable(bytes));
+                    });
+                }
+            });
+    }
+}
+
+/// The bytes as text, with a dot for the ones that are not printable.
+fn printable(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+fn kind_name(kind: BackupKind) -> &'static str {
+    match kind {
+        BackupKind::Eeprom => "EEPROM",
+        BackupKind::Sram => "SRAM",
+        BackupKind::Flash64K => "Flash 64K",
+        BackupKind::Flash128K => "Flash 128K",
+        BackupKind::Unknown => tr("backup-unknown"),
+    }
+}
+
+impl UiTool for BackupMemory {
+    fn name(&self) -> &'static str {
+        "Backup Memory"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(560.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let backup = {
+            let gba = self.gba.lock().unwrap();
+            self.kind
+                .get_or_insert_with(|| BackupKind::detect(&gba.cpu.bus.internal_memory.rom));
+            gba.backup().to_vec()
+        };
+        let size = self.shown_size(backup.len());
+        let kind = self.kind.unwrap_or(BackupKind::Unknown);
+
+        ui.label(tr_args(
+            "backup-kind",
+            &[("kind", &kind_name(kind)), ("size", &size)],
+        ));
+        ui.horizontal(|ui| {
+            if ui.button(tr("backup-export")).clicked() {
+                self.error = self.export().err().map(|e| e.to_string());
+            }
+            if ui.button(tr("backup-import")).clicked() {
+                self.error = self.import().err().map(|e| e.to_string());
+            }
+        });
+
+        if let Some(offset) = self.selected {
+            ui.horizontal(|ui| {
+                ui.label(tr_args(
+                    "backup-edit",
+                    &[("offset", &format!("{offset:05X}"))],
+                ));
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut self.value).desired_width(32.0));
+                let is_submitted =
+                    response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if ui.button(tr("backup-write")).clicked() || is_submitted {
+                    self.write_selected();
+                }
+            });
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.separator();
+        self.rows_ui(ui, &backup[..size]);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("backup-export")),
+            Command::new(tr("backup-import")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        let result = match index {
+            0 => self.export(),
+            _ => self.import(),
+        };
+        self.error = result.err().map(|e| e.to_string());
+
+        true
+    }
+}
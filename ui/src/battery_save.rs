@@ -0,0 +1,207 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use emu::gba::Gba;
+use logger::log;
+
+use crate::paths;
+
+/// How often the backup memory and the file are compared.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What is done with the `.sav` file to keep it in sync between machines (eg. with
+/// Syncthing or Dropbox).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveSyncSettings {
+    /// Run after every save, split on spaces with the path of the save as the last argument.
+    pub command: String,
+    /// Directory where a copy of every save is written.
+    pub copy_to: Option<PathBuf>,
+    /// Loads the file again when another program changes it.
+    pub reload_on_change: bool,
+}
+
+/// Keeps the backup memory of the cartridge in a `.sav` file.
+///
+/// A save is written once the game has stopped writing it for a check, so that a save
+/// in progress is never written half done, and the file is replaced at once so a sync
+/// program never reads half of it. Changes of other programs are loaded the same way,
+/// once the file has stayed the same for a check.
+pub struct BatterySave {
+    path: PathBuf,
+    /// The backup memory as it is in the file.
+    saved: Vec<u8>,
+    /// The backup memory at the last check, the game is still saving if it changed since.
+    last_seen: Vec<u8>,
+    /// Modification time of the file when it was last written or read.
+    modified: Option<SystemTime>,
+    /// Modification time of a change of another program, loaded if it's the same at the next check.
+    changed: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl BatterySave {
+    /// Loads the save of `cartridge_name` (eg. `game.gba` has `game.sav`), if there is one.
+    /// In portable mode the saves are kept in its directory instead of next to the ROMs.
+    pub fn new(cartridge_name: &str, gba: &mut Gba) -> Self {
+        let path = Path::new(cartridge_name).with_extension("sav");
+        let path = match (paths::portable_dir(), path.file_name()) {
+            (Some(dir), Some(name)) => dir.join("saves").join(name),
+            _ => path,
+        };
+
+        let mut battery = Self {
+            path,
+            saved: gba.backup().to_vec(),
+            last_seen: gba.backup().to_vec(),
+            modified: None,
+            changed: None,
+            last_check: Instant::now(),
+        };
+
+        if battery.path.is_file() {
+            if let Err(e) = battery.load(gba) {
+                log(format!("can't load {}: {e}", battery.path.display()));
+            }
+        }
+
+        battery
+    }
+
+    /// Saves the backup memory if the game changed it, or loads the file if another
+    /// program did.
+    pub fn update(&mut self, gba: &Mutex<Gba>, settings: &SaveSyncSettings) {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+
+        let mut gba = gba.lock().unwrap();
+        let backup = gba.backup();
+
+        if backup != self.last_seen {
+            self.last_seen.copy_from_slice(backup);
+            return;
+        }
+        if backup != self.saved {
+            let backup = backup.to_vec();
+            drop(gba);
+            if let Err(e) = self.save(backup, settings) {
+                log(format!("can't save {}: {e}", self.path.display()));
+            }
+            return;
+        }
+
+        if settings.reload_on_change {
+            if let Err(e) = self.reload_if_changed(&mut gba) {
+                log(format!("can't reload {}: {e}", self.path.display()));
+            }
+        }
+    }
+
+    /// Writes what the game saved last, eg. when the application is closed.
+    pub fn flush(&mut self, gba: &Gba, settings: &SaveSyncSettings) {
+        if gba.backup() == self.saved {
+            return;
+        }
+
+        if let Err(e) = self.save(gba.backup().to_vec(), settings) {
+            log(format!("can't save {}: {e}", self.path.display()));
+        }
+    }
+
+    /// The backup memory was replaced (eg. when the cartridge was loaded again), the file
+    /// is written only once the game saves.
+    pub fn replaced(&mut self, gba: &Gba) {
+        self.saved = gba.backup().to_vec();
+        self.last_seen.clone_from(&self.saved);
+    }
+
+    fn load(&mut self, gba: &mut Gba) -> Result<(), Box<dyn Error>> {
+        let modified = fs::metadata(&self.path)?.modified().ok();
+        gba.set_backup(&fs::read(&self.path)?)?;
+
+        self.saved = gba.backup().to_vec();
+        self.last_seen.clone_from(&self.saved);
+        self.modified = modified;
+
+        Ok(())
+    }
+
+    fn save(&mut self, backup: Vec<u8>, settings: &SaveSyncSettings) -> Result<(), Box<dyn Error>> {
+        write_replacing(&self.path, &backup)?;
+        self.modified = fs::metadata(&self.path)?.modified().ok();
+        self.changed = None;
+        self.saved = backup;
+
+        if let Some(dir) = &settings.copy_to {
+            let name = self.path.file_name().ok_or("The save has no file name")?;
+            write_replacing(&dir.join(name), &self.saved)?;
+        }
+        if !settings.command.trim().is_empty() {
+            run_command(&settings.command, &self.path)?;
+        }
+
+        Ok(())
+    }
+
+    fn reload_if_changed(&mut self, gba: &mut Gba) -> Result<(), Box<dyn Error>> {
+        let Ok(modified) = fs::metadata(&self.path).and_then(|metadata| metadata.modified()) else {
+            return Ok(());
+        };
+        if Some(modified) == self.modified {
+            return Ok(());
+        }
+
+        // A sync program may still be writing it.
+        if self.changed != Some(modified) {
+            self.changed = Some(modified);
+            return Ok(());
+        }
+
+        self.load(gba)?;
+        self.changed = None;
+        log(format!("{} changed, loaded again", self.path.display()));
+
+        Ok(())
+    }
+}
+
+/// Writes a file next to `path` then renames it over `path`, so that other programs
+/// see either the old content or the new one.
+fn write_replacing(path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&temporary, data)?;
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+/// Starts the command of the user, it's not waited for.
+fn run_command(command: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("The command is empty")?;
+
+    let mut child = Command::new(program).args(words).arg(path).spawn()?;
+    let command = command.to_owned();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => log(format!("`{command}` failed: {status}")),
+        Ok(_) => {}
+        Err(e) => log(format!("`{command}` failed: {e}")),
+    });
+
+    Ok(())
+}
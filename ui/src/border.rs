@@ -0,0 +1,145 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use egui::{Color32, ColorImage, Pos2, Rect, TextureHandle, TextureOptions, Vec2};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use emu::render::{LCD_HEIGHT, LCD_WIDTH};
+
+/// Shell of a GBA, shipped with the application.
+const SHELL_BORDER: &[u8] = include_bytes!("../assets/gba_shell.png");
+
+/// Image drawn around the screen.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BorderSource {
+    #[default]
+    None,
+    /// The default border, a GBA.
+    Shell,
+    /// A PNG chosen by the user, eg. a Super Game Boy or Game Boy Player border.
+    File(PathBuf),
+}
+
+/// How the border and the screen are scaled to the space available.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BorderScaling {
+    /// As big as it fits.
+    #[default]
+    Fit,
+    /// As big as it fits by a whole factor, every pixel of the border keeps the same size.
+    Integer,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BorderSettings {
+    pub source: BorderSource,
+    pub scaling: BorderScaling,
+}
+
+/// A border ready to be drawn.
+pub struct Border {
+    texture: TextureHandle,
+    size: Vec2,
+    /// Where the screen goes, in pixels of the image.
+    screen: Rect,
+}
+
+impl Border {
+    /// Reads the image of `source`, `None` if there's no border.
+    pub fn load(
+        ctx: &egui::Context,
+        source: &BorderSource,
+    ) -> Result<Option<Self>, Box<dyn Error>> {
+        let image = match source {
+            BorderSource::None => return Ok(None),
+            BorderSource::Shell => image::load_from_memory(SHELL_BORDER)?,
+            BorderSource::File(path) => image::open(path)?,
+        }
+        .to_rgba8();
+
+        let (width, height) = image.dimensions();
+        let screen = find_screen(&image);
+        let color_image = ColorImage::from_rgba_unmultiplied(
+            [width as usize, height as usize],
+            image.as_flat_samples().as_slice(),
+        );
+
+        Ok(Some(Self {
+            texture: ctx.load_texture("border", color_image, TextureOptions::NEAREST),
+            #[allow(clippy::cast_precision_loss)]
+            size: Vec2::new(width as f32, height as f32),
+            screen,
+        }))
+    }
+
+    /// Draws the border in the space available, with `screen` in its window.
+    pub fn show(
+        &self,
+        ui: &mut egui::Ui,
+        screen: &TextureHandle,
+        scaling: BorderScaling,
+    ) -> egui::Response {
+        let fit = (ui.available_size() / self.size).min_elem();
+        let scale = match scaling {
+            BorderScaling::Fit => fit,
+            BorderScaling::Integer => fit.floor().max(1.0),
+        };
+
+        let (rect, response) = ui.allocate_exact_size(self.size * scale, egui::Sense::hover());
+        let screen_rect = Rect::from_min_size(
+            rect.min + self.screen.min.to_vec2() * scale,
+            self.screen.size() * scale,
+        );
+
+        // The border goes over the screen, it can round its corners.
+        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        let painter = ui.painter_at(rect);
+        painter.image(screen.id(), screen_rect, uv, Color32::WHITE);
+        painter.image(self.texture.id(), rect, uv, Color32::WHITE);
+
+        response
+    }
+}
+
+/// The transparent window in the middle of the border, or a window of the size of the
+/// LCD in the middle if there's none.
+#[allow(clippy::cast_precision_loss)]
+fn find_screen(image: &RgbaImage) -> Rect {
+    let (width, height) = image.dimensions();
+    let center = Pos2::new((width / 2) as f32, (height / 2) as f32);
+    let centered = Rect::from_center_size(center, Vec2::new(LCD_WIDTH as f32, LCD_HEIGHT as f32));
+
+    let (center_x, center_y) = (width / 2, height / 2);
+    let is_clear = |x, y| image.get_pixel(x, y)[3] == 0;
+    if width == 0 || height == 0 || !is_clear(center_x, center_y) {
+        return centered;
+    }
+
+    let left = (0..center_x)
+        .rev()
+        .take_while(|x| is_clear(*x, center_y))
+        .last()
+        .unwrap_or(center_x);
+    let right = (center_x..width)
+        .take_while(|x| is_clear(*x, center_y))
+        .last()
+        .unwrap_or(center_x);
+    let top = (0..center_y)
+        .rev()
+        .take_while(|y| is_clear(center_x, *y))
+        .last()
+        .unwrap_or(center_y);
+    let bottom = (center_y..height)
+        .take_while(|y| is_clear(center_x, *y))
+        .last()
+        .unwrap_or(center_y);
+
+    Rect::from_min_max(
+        Pos2::new(left as f32, top as f32),
+        Pos2::new((right + 1) as f32, (bottom + 1) as f32),
+    )
+}
@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use emu::cpu::call_stack::{Frame, FrameKind};
+use emu::gba::Gba;
+
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, UiTool};
+
+/// Functions the CPU is in, updated every time the emulation stops.
+pub struct CallStack {
+    gba: Arc<Mutex<Gba>>,
+    /// Frames with the names of their function and return address.
+    frames: Vec<(Frame, Option<String>, Option<String>)>,
+    /// Cycle seen on the last frame, the CPU is paused while it doesn't change.
+    last_cycle: u128,
+    /// Cycle `frames` has been taken at, and the number of symbols used for the names.
+    snapshot: Option<(u128, usize)>,
+}
+
+impl CallStack {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            frames: Vec::new(),
+            last_cycle: 0,
+            snapshot: None,
+        }
+    }
+
+    /// Takes a new snapshot if the CPU has stopped at a different cycle.
+    /// It returns `true` if the CPU is running.
+    fn update(&mut self) -> bool {
+        let gba = self.gba.lock().unwrap();
+        let cycle = gba.cpu.current_cycle;
+        let is_running = cycle != self.last_cycle;
+
+        let snapshot = (cycle, gba.cpu.symbols.len());
+
+        if !is_running && self.snapshot != Some(snapshot) {
+            let symbols = &gba.cpu.symbols;
+            self.frames = gba
+                .cpu
+                .call_stack
+                .frames()
+                .iter()
+                .map(|frame| {
+                    (
+                        *frame,
+                        symbols.describe(frame.function),
+                        symbols.describe(frame.return_address),
+                    )
+                })
+                .collect();
+            self.snapshot = Some(snapshot);
+        }
+        drop(gba);
+
+        self.last_cycle = cycle;
+
+        is_running
+    }
+}
+
+fn kind_name(kind: FrameKind) -> &'static str {
+    match kind {
+        FrameKind::Call => tr("frame-call"),
+        FrameKind::Exception => tr("frame-exception"),
+    }
+}
+
+/// The symbol name if there is one, with the address on hover.
+fn address_label(ui: &mut egui::Ui, address: u32, name: Option<&str>) {
+    let address = format!("0x{address:08X}");
+
+    match name {
+        Some(name) => ui.monospace(name).on_hover_text(address),
+        None => ui.monospace(address),
+    };
+}
+
+impl UiTool for CallStack {
+    fn name(&self) -> &'static str {
+        "Call Stack"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(320.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if self.update() {
+            ui.colored_label(egui::Color32::GRAY, tr("call-stack-running"));
+            ui.separator();
+        }
+
+        if self.frames.is_empty() {
+            ui.label(tr("call-stack-empty"));
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("Call stack")
+                .num_columns(4)
+                .spacing([20.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong(tr("function"));
+                    ui.strong(tr("return-address"));
+                    ui.strong(tr("frame-kind"));
+                    ui.end_row();
+
+                    // The current function first, like the other debuggers.
+                    for (depth, (frame, function, return_address)) in
+                        self.frames.iter().rev().enumerate()
+                    {
+                        ui.label(depth.to_string());
+                        address_label(ui, frame.function, function.as_deref());
+                        address_label(ui, frame.return_address, return_address.as_deref());
+                        ui.label(kind_name(frame.kind));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
@@ -0,0 +1,342 @@
+use std::sync::{Arc, Mutex};
+
+use emu::{
+    boot_patch::PatchWidth,
+    cheats::{Cheat, CheatMode},
+    debugger::ram_search::{self, Comparison, RamSearch},
+    gba::Gba,
+};
+
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Candidates listed, the others are only counted until the search is narrowed more.
+const MAX_LISTED: usize = 100;
+
+/// Comparisons offered, the value of `EqualTo` is typed by the user.
+const COMPARISONS: [Comparison; 5] = [
+    Comparison::EqualTo(0),
+    Comparison::Changed,
+    Comparison::Unchanged,
+    Comparison::Increased,
+    Comparison::Decreased,
+];
+
+/// Guides from a value seen in the game to a cheat: search the RAM, narrow the candidates
+/// while playing, pick the address, choose how it's written and share its `GameShark` code.
+pub struct Cheats {
+    gba: Arc<Mutex<Gba>>,
+    width: PatchWidth,
+    search: Option<RamSearch>,
+    comparison: Comparison,
+    /// Value of the `EqualTo` comparison, as typed.
+    equal_to: String,
+    /// Address picked among the candidates.
+    selected: Option<u32>,
+    name: String,
+    value: String,
+    mode: CheatMode,
+    error: Option<String>,
+}
+
+impl Cheats {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            width: PatchWidth::Byte,
+            search: None,
+            comparison: Comparison::EqualTo(0),
+            equal_to: String::new(),
+            selected: None,
+            name: String::new(),
+            value: String::new(),
+            mode: CheatMode::Freeze,
+            error: None,
+        }
+    }
+
+    fn start_search(&mut self) {
+        self.search = Some(RamSearch::new(&self.gba.lock().unwrap(), self.width));
+        self.selected = None;
+    }
+
+    /// It returns `false` if the value to compare with is not a number, the error is shown.
+    fn narrow(&mut self) -> bool {
+        let mut comparison = self.comparison;
+        if let Comparison::EqualTo(value) = &mut comparison {
+            let Some(typed) = parse_value(&self.equal_to) else {
+                self.error = Some(tr("cheat-invalid-value").to_owned());
+                return false;
+            };
+            *value = typed;
+        }
+
+        if let Some(search) = &mut self.search {
+            search.narrow(&self.gba.lock().unwrap(), comparison);
+        }
+        self.error = None;
+        true
+    }
+
+    fn add_cheat(&mut self, address: u32) {
+        let Some(width) = self.search.as_ref().map(|search| search.width) else {
+            return;
+        };
+        let Some(value) = parse_value(&self.value) else {
+            self.error = Some(tr("cheat-invalid-value").to_owned());
+            return;
+        };
+
+        let name = if self.name.trim().is_empty() {
+            format!("{address:08X}")
+        } else {
+            self.name.trim().to_owned()
+        };
+        self.gba.lock().unwrap().cheats.push(Cheat {
+            name,
+            address,
+            value,
+            width,
+            mode: self.mode,
+            enabled: true,
+        });
+        self.name.clear();
+        self.error = None;
+    }
+
+    fn search_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading(tr("cheat-search"));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("Cheat width")
+                .selected_text(width_name(self.width))
+                .show_ui(ui, |ui| {
+                    for option in PatchWidth::ALL {
+                        ui.selectable_value(&mut self.width, option, width_name(option));
+                    }
+                });
+            if ui.button(tr("cheat-start-search")).clicked() {
+                self.start_search();
+            }
+        });
+
+        let Some(count) = self.search.as_ref().map(|search| search.candidates().len()) else {
+            ui.small(tr("cheat-search-hint"));
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("Cheat comparison")
+                .selected_text(comparison_name(self.comparison))
+                .show_ui(ui, |ui| {
+                    for option in COMPARISONS {
+                        let is_selected = std::mem::discriminant(&self.comparison)
+                            == std::mem::discriminant(&option);
+                        if ui
+                            .selectable_label(is_selected, comparison_name(option))
+                            .clicked()
+                        {
+                            self.comparison = option;
+                        }
+                    }
+                });
+            if matches!(self.comparison, Comparison::EqualTo(_)) {
+                ui.add(egui::TextEdit::singleline(&mut self.equal_to).desired_width(80.0));
+            }
+            if ui.button(tr("cheat-narrow")).clicked() {
+                self.narrow();
+            }
+        });
+        ui.label(tr_args("cheat-candidates", &[("count", &count)]));
+
+        if count > MAX_LISTED {
+            ui.small(tr("cheat-narrow-hint"));
+            return;
+        }
+
+        // Current values, the ones of the last search may be old.
+        let rows = match &self.search {
+            Some(search) => {
+                let gba = self.gba.lock().unwrap();
+                search
+                    .candidates()
+                    .iter()
+                    .map(|candidate| {
+                        let current = ram_search::read(&gba, candidate.address, search.width);
+                        (candidate.address, current)
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        egui::ScrollArea::vertical()
+            .id_source("Cheat candidates")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                for (address, current) in rows {
+                    let text = format!("{address:08X}  {current}");
+                    let is_selected = self.selected == Some(address);
+
+                    if ui.selectable_label(is_selected, text).clicked() {
+                        self.selected = Some(address);
+                        self.value = current.to_string();
+                    }
+                }
+            });
+    }
+
+    fn create_ui(&mut self, ui: &mut egui::Ui, address: u32) {
+        ui.heading(tr_args(
+            "cheat-create",
+            &[("address", &format!("{address:08X}"))],
+        ));
+        egui::Grid::new("New cheat").num_columns(2).show(ui, |ui| {
+            ui.label(tr("cheat-name"));
+            ui.text_edit_singleline(&mut self.name);
+            ui.end_row();
+
+            ui.label(tr("cheat-value"));
+            ui.add(egui::TextEdit::singleline(&mut self.value).desired_width(80.0));
+            ui.end_row();
+
+            ui.label(tr("cheat-mode"));
+            ui.horizontal(|ui| {
+                for option in CheatMode::ALL {
+                    ui.radio_value(&mut self.mode, option, mode_name(option));
+                }
+            });
+            ui.end_row();
+        });
+
+        if ui.button(tr("cheat-add")).clicked() {
+            self.add_cheat(address);
+        }
+    }
+
+    fn list_ui(&self, ui: &mut egui::Ui) {
+        let mut gba = self.gba.lock().unwrap();
+        if gba.cheats.is_empty() {
+            return;
+        }
+
+        ui.heading(tr("cheat-list"));
+        let mut removed = None;
+        egui::Grid::new("Cheats")
+            .num_columns(5)
+            .spacing([12.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, cheat) in gba.cheats.iter_mut().enumerate() {
+                    ui.checkbox(&mut cheat.enabled, &cheat.name);
+                    ui.monospace(format!("{:08X} = {}", cheat.address, cheat.value));
+                    ui.label(mode_name(cheat.mode));
+
+                    let code = cheat.gameshark_code();
+                    ui.monospace(&code)
+                        .on_hover_text(tr("cheat-gameshark-hint"));
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("cheat-copy")).clicked() {
+                            ui.output_mut(|output| output.copied_text = code);
+                        }
+                        if ui.button("X").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed {
+            gba.cheats.remove(index);
+        }
+    }
+}
+
+/// A decimal number, or a hex one starting with `0x`.
+fn parse_value(text: &str) -> Option<u32> {
+    let text = text.trim();
+
+    text.strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .map_or_else(
+            || text.parse().ok(),
+            |hex| u32::from_str_radix(hex, 16).ok(),
+        )
+}
+
+fn width_name(width: PatchWidth) -> &'static str {
+    match width {
+        PatchWidth::Byte => tr("cheat-byte"),
+        PatchWidth::HalfWord => tr("cheat-half-word"),
+        PatchWidth::Word => tr("cheat-word"),
+    }
+}
+
+fn comparison_name(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::EqualTo(_) => tr("cheat-equal-to"),
+        Comparison::Changed => tr("cheat-changed"),
+        Comparison::Unchanged => tr("cheat-unchanged"),
+        Comparison::Increased => tr("cheat-increased"),
+        Comparison::Decreased => tr("cheat-decreased"),
+    }
+}
+
+fn mode_name(mode: CheatMode) -> &'static str {
+    match mode {
+        CheatMode::Freeze => tr("cheat-freeze"),
+        CheatMode::Once => tr("cheat-once"),
+    }
+}
+
+impl UiTool for Cheats {
+    fn name(&self) -> &'static str {
+        "Cheats"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(360.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.search_ui(ui);
+
+        if let Some(address) = self.selected {
+            ui.separator();
+            self.create_ui(ui, address);
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.separator();
+        self.list_ui(ui);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("cheat-start-search")),
+            Command::with_argument(tr("cheat-narrow-equal"), tr("cheat-value")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, argument: &str) -> bool {
+        if index == 0 {
+            self.start_search();
+            return true;
+        }
+
+        if self.search.is_none() {
+            self.start_search();
+        }
+        self.comparison = Comparison::EqualTo(0);
+        argument.clone_into(&mut self.equal_to);
+        self.narrow()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::i18n::tr;
+use crate::ui_traits::Command;
+
+/// Opens and closes the palette from anywhere in the application.
+pub const PALETTE_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::CTRL.plus(egui::Modifiers::SHIFT),
+    egui::Key::P,
+);
+
+/// Matches shown at once, the others are reached refining the search.
+const MAX_MATCHES: usize = 12;
+
+/// Searchable list of every action of the application.
+#[derive(Default)]
+pub struct CommandPalette {
+    is_open: bool,
+    query: String,
+    /// Position of the highlighted command among the matches.
+    selected: usize,
+    /// Command waiting for its argument, with the text typed so far.
+    pending: Option<(usize, String)>,
+    /// The argument of the pending command was rejected.
+    is_argument_invalid: bool,
+}
+
+impl CommandPalette {
+    pub const fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn toggle(&mut self) {
+        *self = Self {
+            is_open: !self.is_open,
+            ..Self::default()
+        };
+    }
+
+    /// Tells the palette the argument of the last command was not valid, so it asks it again.
+    pub fn reject_argument(&mut self, index: usize, argument: String) {
+        self.is_open = true;
+        self.pending = Some((index, argument));
+        self.is_argument_invalid = true;
+    }
+
+    /// Shows the palette, it returns the index of the command to run in `commands`
+    /// with its argument, once the user has chosen it.
+    pub fn show(&mut self, ctx: &egui::Context, commands: &[Command]) -> Option<(usize, String)> {
+        if !self.is_open {
+            return None;
+        }
+
+        if ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.toggle();
+            return None;
+        }
+
+        let mut chosen = None;
+
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .default_width(420.0)
+            .resizable(false)
+            .show(ctx, |ui| {
+                chosen = if self.pending.is_some() {
+                    self.argument_ui(ui, commands)
+                } else {
+                    self.search_ui(ui, commands)
+                };
+            });
+
+        if chosen.is_some() {
+            self.toggle();
+        }
+
+        chosen
+    }
+
+    fn search_ui(&mut self, ui: &mut egui::Ui, commands: &[Command]) -> Option<(usize, String)> {
+        let (up, down, enter) = ui.input_mut(|input| {
+            (
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                input.key_pressed(egui::Key::Enter),
+            )
+        });
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .hint_text(tr("command-palette-hint"))
+                .desired_width(f32::INFINITY),
+        );
+        response.request_focus();
+        if response.changed() {
+            self.selected = 0;
+        }
+
+        let matches = search(&self.query, commands);
+        if matches.is_empty() {
+            ui.label(tr("no-commands"));
+            return None;
+        }
+
+        if down {
+            self.selected = (self.selected + 1).min(matches.len() - 1);
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len() - 1);
+
+        let mut clicked = None;
+        ui.separator();
+        for (position, &index) in matches.iter().enumerate() {
+            let command = &commands[index];
+            let title = match command.argument {
+                Some(_) => format!("{}…", command.title),
+                None => command.title.clone(),
+            };
+
+            if ui
+                .selectable_label(position == self.selected, title)
+                .clicked()
+            {
+                clicked = Some(index);
+            }
+        }
+
+        let index = clicked.or_else(|| enter.then(|| matches[self.selected]))?;
+
+        if commands[index].argument.is_some() {
+            self.pending = Some((index, String::new()));
+            None
+        } else {
+            Some((index, String::new()))
+        }
+    }
+
+    fn argument_ui(&mut self, ui: &mut egui::Ui, commands: &[Command]) -> Option<(usize, String)> {
+        let (index, argument) = self.pending.as_mut()?;
+        let command = &commands[*index];
+
+        ui.label(&command.title);
+        let response = ui.add(
+            egui::TextEdit::singleline(argument)
+                .hint_text(command.argument.unwrap_or_default())
+                .desired_width(f32::INFINITY),
+        );
+        response.request_focus();
+
+        if self.is_argument_invalid {
+            ui.colored_label(egui::Color32::RED, tr("invalid-argument"));
+        }
+
+        if ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+            return self.pending.take();
+        }
+
+        None
+    }
+}
+
+/// Indices of the commands matching `query`, best matches first.
+fn search(query: &str, commands: &[Command]) -> Vec<usize> {
+    let mut matches = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, command)| {
+            fuzzy_score(query, &command.title).map(|score| (index, score))
+        })
+        .collect::<Vec<_>>();
+
+    // Stable, commands with the same score keep their order.
+    matches.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    matches.truncate(MAX_MATCHES);
+
+    matches.into_iter().map(|(index, _)| index).collect()
+}
+
+/// How well `text` matches `query`, `None` if the characters of the query
+/// don't all appear in order. Consecutive characters and starts of words count more.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last_match = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[position..].iter().position(|c| *c == query_char)?;
+        let index = position + offset;
+
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if last_match.is_some() {
+            score -= i32::try_from(offset).unwrap_or(i32::MAX).min(10);
+        }
+
+        last_match = Some(index);
+        position = index + 1;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use emu::boot_patch::BootPatch;
+use emu::core_profile::CoreProfile;
+use emu::cpu::hardware::lcd::{AffineResolution, FrameSkip};
+use emu::cpu::hardware::sound::Interpolation;
+use emu::render::color_correction::ColorCorrection;
+use logger::log;
+
+use crate::battery_save::SaveSyncSettings;
+use crate::border::BorderSettings;
+use crate::frame_pacing::PresentMode;
+use crate::hot_reload::HotReloadSettings;
+use crate::i18n::Language;
+use crate::paths;
+use crate::preset::Preset;
+use crate::renderer::RendererKind;
+use crate::screen_filter::ScreenFilter;
+use crate::theme::Theme;
+
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Size and position of the main window, in points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub size: [f32; 2],
+    /// Not every platform reports it (eg. Wayland).
+    pub position: Option<[f32; 2]>,
+}
+
+/// Video and audio effects chosen for a single game, `None` to use the ones of every
+/// game (eg. the frame blending only for the games making sprites transparent by flicker).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameEnhancements {
+    pub color_correction: Option<ColorCorrection>,
+    pub frame_blending: Option<bool>,
+    pub affine_resolution: Option<AffineResolution>,
+    /// The one of every game is chosen in the Audio tool and not kept between sessions.
+    pub interpolation: Option<Interpolation>,
+}
+
+/// Settings of the frontend kept between sessions, in `config.json` of the config directory.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    /// Device the settings were chosen for, `None` until the user picks one.
+    pub preset: Option<Preset>,
+    pub window: Option<WindowGeometry>,
+    /// Read when the window opens, it's changed at once from the settings.
+    pub fullscreen: bool,
+    /// Names of the open tools, `None` until the first save so the defaults are used.
+    pub open_tools: Option<BTreeSet<String>>,
+    /// Top-left corner of the tool windows, by name.
+    pub tool_positions: BTreeMap<String, [f32; 2]>,
+    /// `None` to follow the language of the system.
+    pub language: Option<Language>,
+    pub theme: Theme,
+    /// Colors of the screen of a console, the raw ones look too saturated.
+    pub color_correction: ColorCorrection,
+    /// Mixes every frame with the previous one, see [`emu::cpu::hardware::lcd::FrameBlending`].
+    pub frame_blending: bool,
+    /// Smooths rotated and scaled sprites, off by default as the hardware doesn't.
+    pub affine_resolution: AffineResolution,
+    /// Effects chosen for single games, by game key.
+    pub game_enhancements: BTreeMap<String, GameEnhancements>,
+    /// Image around the screen of the game.
+    pub border: BorderSettings,
+    /// Hooks to keep the `.sav` files in sync between machines.
+    pub save_sync: SaveSyncSettings,
+    /// Loads the cartridge again when its file changes.
+    pub hot_reload: HotReloadSettings,
+    /// Zoom factor of the whole interface, `None` for 1.
+    pub ui_scale: Option<f32>,
+    /// Time the cartridge clock starts from (Unix seconds) for reproducible runs,
+    /// `None` to start from the time of the host.
+    pub fixed_rtc_timestamp: Option<u64>,
+    /// EWRAM with 1 wait state whatever the game sets, it makes some slow games smoother.
+    pub fast_ewram: bool,
+    pub frame_skip: FrameSkip,
+    /// Read when the window opens, changing it takes effect at the next start.
+    pub present_mode: PresentMode,
+    /// Back end drawing the screen.
+    pub renderer: RendererKind,
+    /// Scaling of the screen with the software renderer.
+    pub screen_filter: ScreenFilter,
+    /// Accuracy or speed of the core, for the games without their own.
+    pub core_profile: CoreProfile,
+    /// Profiles chosen for single games, by game key.
+    pub game_core_profiles: BTreeMap<String, CoreProfile>,
+    /// Values written in memory when the BIOS jumps to the game, by game key.
+    pub boot_patches: BTreeMap<String, Vec<BootPatch>>,
+    /// Shows the game being played on the Discord profile, off unless the user opts in.
+    pub discord_presence: bool,
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        paths::dirs().map(|dirs| dirs.config.join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the config, the default one if there is none or it can't be read.
+    #[must_use]
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        if !path.is_file() {
+            return Self::default();
+        }
+
+        Self::load_from(&path).unwrap_or_else(|e| {
+            log(format!("can't read config {}: {e}", path.display()));
+            Self::default()
+        })
+    }
+
+    /// The core profile of the game with `game_key`, its own or the one of every game.
+    #[must_use]
+    pub fn profile_of(&self, game_key: &str) -> CoreProfile {
+        self.game_core_profiles
+            .get(game_key)
+            .copied()
+            .unwrap_or(self.core_profile)
+    }
+
+    fn enhancements_of(&self, game_key: &str) -> GameEnhancements {
+        self.game_enhancements
+            .get(game_key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The color correction of the game with `game_key`, its own or the one of every game.
+    #[must_use]
+    pub fn color_correction_of(&self, game_key: &str) -> ColorCorrection {
+        self.enhancements_of(game_key)
+            .color_correction
+            .unwrap_or(self.color_correction)
+    }
+
+    /// Whether the frames of the game with `game_key` are blended, see [`Self::frame_blending`].
+    #[must_use]
+    pub fn frame_blending_of(&self, game_key: &str) -> bool {
+        self.enhancements_of(game_key)
+            .frame_blending
+            .unwrap_or(self.frame_blending)
+    }
+
+    /// The resolution of the affine sprites of the game with `game_key`, its own or the one
+    /// of every game.
+    #[must_use]
+    pub fn affine_resolution_of(&self, game_key: &str) -> AffineResolution {
+        self.enhancements_of(game_key)
+            .affine_resolution
+            .unwrap_or(self.affine_resolution)
+    }
+
+    /// The audio interpolation of the game with `game_key`, `None` to keep the current one.
+    #[must_use]
+    pub fn interpolation_of(&self, game_key: &str) -> Option<Interpolation> {
+        self.enhancements_of(game_key).interpolation
+    }
+
+    fn load_from(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let data = std::fs::read_to_string(path)?;
+
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub(crate) fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path().ok_or("No config directory")?;
+
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! A local socket to drive the emulator from scripts and other tools, eg. a test runner
+//! loading a ROM, pressing keys and taking screenshots. Every line sent is a JSON command,
+//! every line received is the result of one:
+//!
+//! ```text
+//! {"command": "press", "keys": ["A", "Start"], "frames": 10}
+//! {"ok": true}
+//! {"command": "save-state", "path": "/tmp/missing/state.clm"}
+//! {"ok": false, "error": "No such file or directory (os error 2)"}
+//! ```
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use emu::cpu::hardware::keypad::Key;
+use logger::log;
+
+/// What the socket can ask, run by the application between two of its frames.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    /// Replaces the cartridge, a file or a URL like on the command line.
+    LoadRom {
+        path: String,
+    },
+    Pause,
+    Resume,
+    SaveState {
+        path: PathBuf,
+    },
+    LoadState {
+        path: PathBuf,
+    },
+    /// Holds `keys` for `frames` frames, the others are released.
+    Press {
+        keys: Vec<Key>,
+        frames: u32,
+    },
+    /// Saves the screen, the format follows the extension (eg. `.png`).
+    Screenshot {
+        path: PathBuf,
+    },
+}
+
+#[derive(Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<(), String>> for Response {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+/// A request with the way to send its result back to the client.
+pub struct Pending {
+    pub request: Request,
+    reply: Sender<Result<(), String>>,
+}
+
+impl Pending {
+    pub fn reply(self, result: Result<(), String>) {
+        // The client may be gone, nothing to tell.
+        let _ = self.reply.send(result);
+    }
+}
+
+/// Listens on the socket in a thread, the requests are taken with [`Self::next`].
+pub struct ControlSocket {
+    path: PathBuf,
+    requests: Receiver<Pending>,
+}
+
+impl ControlSocket {
+    /// Listens at `path`, a socket left there by an instance that didn't stop is replaced.
+    /// `on_request` is called from the socket threads when a request arrives (eg. to wake
+    /// up the application).
+    ///
+    /// # Errors
+    /// It returns an error if the socket can't be made.
+    pub fn listen(
+        path: &Path,
+        on_request: impl Fn() + Send + Clone + 'static,
+    ) -> Result<Self, Box<dyn Error>> {
+        if path.exists() && UnixStream::connect(path).is_err() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let sender = sender.clone();
+                        let on_request = on_request.clone();
+                        thread::spawn(move || serve(stream, &sender, on_request));
+                    }
+                    Err(e) => log(format!("control socket: {e}")),
+                }
+            }
+        });
+
+        Ok(Self {
+            path: path.to_owned(),
+            requests,
+        })
+    }
+
+    /// The next request waiting, if any.
+    pub fn next(&self) -> Option<Pending> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answers the requests of a client until it disconnects, one at a time.
+fn serve(stream: UnixStream, requests: &Sender<Pending>, on_request: impl Fn()) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = match serde_json::from_str(&line) {
+            Ok(request) => {
+                let (reply, result) = mpsc::channel();
+                if requests.send(Pending { request, reply }).is_err() {
+                    return;
+                }
+                on_request();
+                result
+                    .recv()
+                    .unwrap_or_else(|_| Err("The emulator stopped".to_string()))
+            }
+            Err(e) => Err(format!("Invalid command: {e}")),
+        };
+
+        let Ok(response) = serde_json::to_string(&Response::from(result)) else {
+            return;
+        };
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn parse(line: &str) -> Result<Request, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    #[test]
+    fn parse_requests() {
+        assert!(matches!(
+            parse(r#"{"command": "load-rom", "path": "game.gba"}"#),
+            Ok(Request::LoadRom { path }) if path == "game.gba"
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "pause"}"#),
+            Ok(Request::Pause)
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "save-state", "path": "/tmp/state.clm"}"#),
+            Ok(Request::SaveState { path }) if path == Path::new("/tmp/state.clm")
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "press", "keys": ["A", "Start"], "frames": 10}"#),
+            Ok(Request::Press { keys, frames: 10 }) if keys == [Key::A, Key::Start]
+        ));
+
+        // Unknown commands and keys, missing fields.
+        assert!(parse(r#"{"command": "jump"}"#).is_err());
+        assert!(parse(r#"{"command": "press", "keys": ["X"], "frames": 1}"#).is_err());
+        assert!(parse(r#"{"command": "screenshot"}"#).is_err());
+    }
+
+    #[test]
+    fn responses() {
+        let response = |result| serde_json::to_string(&Response::from(result)).unwrap();
+
+        assert_eq!(response(Ok(())), r#"{"ok":true}"#);
+        assert_eq!(
+            response(Err("No ROM".to_string())),
+            r#"{"ok":false,"error":"No ROM"}"#
+        );
+    }
+
+    #[test]
+    fn serve_a_client() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let (sender, requests) = mpsc::channel();
+        let server = thread::spawn(move || serve(server, &sender, || {}));
+        let replier = thread::spawn(move || {
+            for pending in requests {
+                let result = match pending.request {
+                    Request::Pause => Ok(()),
+                    _ => Err("Not now".to_string()),
+                };
+                pending.reply(result);
+            }
+        });
+
+        let mut writer = client.try_clone().unwrap();
+        writeln!(
+            writer,
+            "{{\"command\": \"pause\"}}\n\n{{\"command\": \"resume\"}}"
+        )
+        .unwrap();
+        writeln!(writer, "pause").unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let lines: Vec<String> = BufReader::new(client).lines().map(Result::unwrap).collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], r#"{"ok":true}"#);
+        assert_eq!(lines[1], r#"{"ok":false,"error":"Not now"}"#);
+        assert!(lines[2].starts_with(r#"{"ok":false,"error":"Invalid command: "#));
+
+        server.join().unwrap();
+        replier.join().unwrap();
+    }
+}
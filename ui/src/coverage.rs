@@ -0,0 +1,283 @@
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use egui::{load::SizedTexture, ColorImage, ImageSource, TextureHandle, TextureOptions};
+use emu::{
+    cartridge::hash::game_key,
+    debugger::coverage::{Coverage as CoverageMap, CoverageStats, EXECUTED, READ},
+    gba::Gba,
+};
+use native_dialog::FileDialog;
+
+use crate::dialog::show_error;
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Pixels in a row of the heatmap.
+const HEATMAP_WIDTH: usize = 256;
+/// The heatmap has at most this many pixels, a pixel covers more bytes on big ROMs.
+const HEATMAP_PIXELS: usize = 256 * 256;
+/// Rebuilding the heatmap goes through the whole ROM, it's not done every frame.
+const HEATMAP_REFRESH: Duration = Duration::from_millis(500);
+
+/// Which parts of the ROM have been executed as code or read as data.
+pub struct Coverage {
+    gba: Arc<Mutex<Gba>>,
+    stats: CoverageStats,
+    heatmap: Option<TextureHandle>,
+    /// ROM bytes covered by a pixel of the heatmap.
+    block_size: usize,
+    last_refresh: Option<Instant>,
+}
+
+impl Coverage {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            stats: CoverageStats {
+                executed: 0,
+                read: 0,
+                total: 0,
+            },
+            heatmap: None,
+            block_size: 1,
+            last_refresh: None,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.gba.lock().unwrap().cpu.bus.coverage.is_some()
+    }
+
+    fn set_recording(&self, record: bool) {
+        let bus = &mut self.gba.lock().unwrap().cpu.bus;
+
+        bus.coverage = record.then(|| CoverageMap::new(bus.internal_memory.rom.len()));
+    }
+
+    fn clear(&mut self) {
+        if let Some(coverage) = &mut self.gba.lock().unwrap().cpu.bus.coverage {
+            coverage.clear();
+        }
+        self.last_refresh = None;
+    }
+
+    /// Saves the flags of every ROM byte (`.cdl`) or the list of ranges (`.txt`).
+    fn export(&self) -> Result<(), Box<dyn Error>> {
+        let filename = format!(
+            "{}.cdl",
+            game_key(&self.gba.lock().unwrap().cpu.bus.internal_memory.rom)
+        );
+
+        let path = FileDialog::new()
+            .set_location("~")
+            .set_filename(&filename)
+            .add_filter(tr("code-data-log"), &["cdl"])
+            .add_filter(tr("coverage-ranges"), &["txt"])
+            .show_save_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+
+        let gba = self.gba.lock().unwrap();
+        let coverage = gba
+            .cpu
+            .bus
+            .coverage
+            .as_ref()
+            .ok_or_else(|| tr("coverage-not-recording"))?;
+
+        let data = if path.extension().is_some_and(|extension| extension == "txt") {
+            coverage.ranges_listing().into_bytes()
+        } else {
+            coverage.flags().to_vec()
+        };
+        drop(gba);
+
+        std::fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    /// Recomputes the stats and the heatmap if enough time has passed.
+    fn refresh(&mut self, ctx: &egui::Context) {
+        if self
+            .last_refresh
+            .is_some_and(|last| last.elapsed() < HEATMAP_REFRESH)
+        {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+
+        let gba = self.gba.lock().unwrap();
+        let Some(coverage) = &gba.cpu.bus.coverage else {
+            self.heatmap = None;
+            return;
+        };
+
+        let flags = coverage.flags();
+        self.stats = coverage.stats();
+        self.block_size = flags.len().div_ceil(HEATMAP_PIXELS).max(1);
+
+        let pixels = flags
+            .chunks(self.block_size)
+            .map(heat_color)
+            .collect::<Vec<_>>();
+        drop(gba);
+
+        let height = pixels.len().div_ceil(HEATMAP_WIDTH).max(1);
+        let mut image = ColorImage::new([HEATMAP_WIDTH, height], egui::Color32::BLACK);
+        image.pixels[..pixels.len()].copy_from_slice(&pixels);
+
+        match &mut self.heatmap {
+            Some(texture) => texture.set(image, TextureOptions::NEAREST),
+            None => {
+                self.heatmap = Some(ctx.load_texture("coverage", image, TextureOptions::NEAREST));
+            }
+        }
+    }
+
+    fn stats_ui(&self, ui: &mut egui::Ui) {
+        #[allow(clippy::cast_precision_loss)]
+        let percent = |bytes: usize| bytes as f64 * 100.0 / self.stats.total.max(1) as f64;
+
+        ui.label(tr_args(
+            "coverage-executed",
+            &[
+                ("bytes", &self.stats.executed),
+                ("percent", &format!("{:.2}", percent(self.stats.executed))),
+            ],
+        ));
+        ui.label(tr_args(
+            "coverage-read",
+            &[
+                ("bytes", &self.stats.read),
+                ("percent", &format!("{:.2}", percent(self.stats.read))),
+            ],
+        ));
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn heatmap_ui(&self, ui: &mut egui::Ui) {
+        let Some(texture) = &self.heatmap else {
+            return;
+        };
+
+        let [width, height] = texture.size();
+        let scale = (ui.available_width() / width as f32).max(1.0);
+        let size = egui::vec2(width as f32, height as f32) * scale;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let response = ui.add(
+                egui::Image::new(ImageSource::Texture(SizedTexture {
+                    id: texture.id(),
+                    size,
+                }))
+                .sense(egui::Sense::hover()),
+            );
+
+            if let Some(position) = response.hover_pos() {
+                let offset = (position - response.rect.min) / scale;
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let pixel = offset.y as usize * width + offset.x as usize;
+                let start = 0x0800_0000 + pixel * self.block_size;
+
+                response.on_hover_text(format!(
+                    "0x{start:08X}-0x{:08X}",
+                    start + self.block_size - 1
+                ));
+            }
+        });
+    }
+}
+
+/// Green for code, blue for data, brighter the more bytes of the block are covered.
+fn heat_color(block: &[u8]) -> egui::Color32 {
+    let count = |flag: u8| block.iter().filter(|&&flags| flags & flag != 0).count();
+    #[allow(clippy::cast_possible_truncation)]
+    let intensity = |flag: u8| (count(flag) * 255 / block.len()) as u8;
+
+    egui::Color32::from_rgb(0, intensity(EXECUTED), intensity(READ))
+}
+
+impl UiTool for Coverage {
+    fn name(&self) -> &'static str {
+        "Coverage"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(300.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut is_recording = self.is_recording();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut is_recording, tr("record-coverage"))
+                .changed()
+            {
+                self.set_recording(is_recording);
+                self.last_refresh = None;
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("clear")))
+                .clicked()
+            {
+                self.clear();
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("export-coverage")))
+                .on_hover_text(tr("export-coverage-hint"))
+                .clicked()
+            {
+                if let Err(err) = self.export() {
+                    show_error(err.as_ref());
+                }
+            }
+        });
+
+        if !is_recording {
+            ui.label(tr("coverage-not-recording"));
+            return;
+        }
+
+        self.refresh(ui.ctx());
+        ui.separator();
+        self.stats_ui(ui);
+        ui.colored_label(egui::Color32::GRAY, tr("coverage-legend"));
+        self.heatmap_ui(ui);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("record-coverage")),
+            Command::new(tr("export-coverage")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        match index {
+            0 => {
+                self.set_recording(!self.is_recording());
+                self.last_refresh = None;
+            }
+            _ => {
+                if let Err(err) = self.export() {
+                    show_error(err.as_ref());
+                }
+            }
+        }
+
+        true
+    }
+}
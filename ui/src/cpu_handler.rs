@@ -7,9 +7,28 @@ use std::thread;
 use egui::text_selection::text_cursor_state::byte_index_from_char_index;
 use egui::{TextBuffer, TextEdit};
 
-use emu::gba::Gba;
+use emu::{debugger::divergence::DivergenceCheck, events::CoreEvent, gba::Gba};
+use logger::log;
 
-use crate::ui_traits::UiTool;
+use crate::crash;
+use crate::frame_pacing::FramePacer;
+use crate::i18n::{tr, tr_args};
+use crate::pause_menu::{MenuEvent, PauseMenu};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Steps run for each lock of the emulator while skipping the BIOS intro,
+/// the other tools can lock it in between.
+const BIOS_SKIP_CHUNK: u64 = 100_000;
+
+/// Presses A+B+Start+Select for the game, from anywhere in the application.
+const SOFT_RESET_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::R);
+
+/// Restarts the console, keeping the saves of the game.
+const HARD_RESET_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::CTRL.plus(egui::Modifiers::SHIFT),
+    egui::Key::R,
+);
 
 pub struct CpuHandler {
     gba: Arc<Mutex<Gba>>,
@@ -19,6 +38,10 @@ pub struct CpuHandler {
     b_address: UpperHexString,
     breakpoint_combo: BreakpointType,
     cycle_to_skip_custom_value: u64,
+    skip_bios_intro: bool,
+    divergence_interval: u64,
+    divergence_frames: u64,
+    pause_menu: PauseMenu,
 }
 
 impl CpuHandler {
@@ -31,8 +54,169 @@ impl CpuHandler {
             b_address: UpperHexString::default(),
             breakpoint_combo: BreakpointType::Equal,
             cycle_to_skip_custom_value: 5000,
+            skip_bios_intro: false,
+            divergence_interval: 60,
+            divergence_frames: 10,
+            pause_menu: PauseMenu::new(),
+        }
+    }
+
+    /// Set while the emulation runs, clearing it pauses it.
+    pub fn running(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.play)
+    }
+
+    /// Settings and results of the divergence check, see `Gba::check_divergence`.
+    fn divergence_check_ui(&mut self, ui: &mut egui::Ui) {
+        let mut gba = self.gba.lock().unwrap();
+        let mut enabled = gba.divergence_check.is_some();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("divergence-check-interval"));
+            ui.add_enabled(
+                !enabled,
+                egui::DragValue::new(&mut self.divergence_interval).range(1..=3600),
+            );
+            ui.label(tr("divergence-check-frames"));
+            ui.add_enabled(
+                !enabled,
+                egui::DragValue::new(&mut self.divergence_frames).range(1..=600),
+            );
+        });
+
+        if ui
+            .checkbox(&mut enabled, tr("divergence-check-enabled"))
+            .on_hover_text(tr("divergence-check-hint"))
+            .changed()
+        {
+            gba.divergence_check = enabled
+                .then(|| DivergenceCheck::new(self.divergence_interval, self.divergence_frames));
+        }
+
+        let Some(check) = gba.divergence_check.clone() else {
+            return;
+        };
+        drop(gba);
+
+        ui.label(tr_args(
+            "divergence-check-count",
+            &[
+                ("checks", &check.checks),
+                ("divergences", &check.divergences.len()),
+            ],
+        ));
+        egui::ScrollArea::vertical()
+            .id_source("divergences")
+            .max_height(100.0)
+            .show(ui, |ui| {
+                for divergence in &check.divergences {
+                    ui.monospace(divergence.to_string());
+                }
+            });
+    }
+
+    /// Runs the emulator in a thread until it's paused or hits a breakpoint.
+    fn play(&mut self) {
+        if self.play.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+
+        let gba_clone = Arc::clone(&self.gba);
+        let play_clone = Arc::clone(&self.play);
+        let breakpoints_clone = Arc::clone(&self.breakpoints);
+        let skip_bios_intro = self.skip_bios_intro;
+
+        self.play.swap(true, std::sync::atomic::Ordering::Relaxed);
+
+        self.thread_handle = Some(thread::spawn(move || {
+            crash::catch_crash(&gba_clone, || {
+                if skip_bios_intro {
+                    while play_clone.load(std::sync::atomic::Ordering::Relaxed)
+                        && !gba_clone.lock().unwrap().skip_bios_intro(BIOS_SKIP_CHUNK)
+                    {
+                    }
+                }
+
+                // The pacer waits outside of the lock, the other tools can draw meanwhile.
+                let frame_completed = Arc::new(AtomicBool::new(false));
+                let frame_flag = Arc::clone(&frame_completed);
+                let callback_id =
+                    gba_clone
+                        .lock()
+                        .unwrap()
+                        .on_event(CoreEvent::FrameComplete, move |_| {
+                            frame_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                        });
+                let mut pacer = FramePacer::default();
+
+                while play_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                    breakpoints_clone.lock().unwrap().iter().for_each(|&b| {
+                        let pc = u32::try_from(
+                            gba_clone.lock().unwrap().cpu.registers.program_counter(),
+                        )
+                        .expect("Failed to convert u16 to u32");
+                        match b.kind {
+                            BreakpointType::Equal => {
+                                if pc == b.address {
+                                    play_clone.swap(false, std::sync::atomic::Ordering::Relaxed);
+                                }
+                            }
+                            BreakpointType::Greater => {
+                                if pc > b.address {
+                                    play_clone.swap(false, std::sync::atomic::Ordering::Relaxed);
+                                }
+                            }
+                        }
+                    });
+
+                    gba_clone.lock().unwrap().step();
+
+                    if frame_completed.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                        pacer.wait();
+                    }
+                }
+
+                gba_clone.lock().unwrap().remove_callback(callback_id);
+            });
+            // Paused, or stopped by a crash.
+            play_clone.store(false, std::sync::atomic::Ordering::Relaxed);
+        }));
+    }
+
+    fn pause(&mut self) {
+        self.play.swap(false, std::sync::atomic::Ordering::Relaxed);
+        self.thread_handle = None;
+    }
+
+    /// Restarts the console keeping the saves, the game goes on if it was running.
+    fn hard_reset(&mut self) {
+        let was_running = self.play.load(std::sync::atomic::Ordering::Relaxed);
+        self.pause();
+
+        let result = self.gba.lock().unwrap().hard_reset();
+        if let Err(e) = result {
+            log(format!("can't reset: {e}"));
+        }
+
+        if was_running {
+            self.play();
         }
     }
+
+    /// Adds a breakpoint at `address`, a symbol name or an address in hex with or without `0x`.
+    /// It returns `false` if the address is not valid.
+    fn add_breakpoint(&self, address: &str, kind: BreakpointType) -> bool {
+        let Some(address) = self.gba.lock().unwrap().cpu.symbols.parse_address(address) else {
+            return false;
+        };
+
+        self.breakpoints
+            .lock()
+            .unwrap()
+            .insert(Breakpoint { address, kind });
+
+        true
+    }
 }
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq, Ord, PartialOrd)]
@@ -103,7 +287,23 @@ impl UiTool for CpuHandler {
     }
 
     fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
+        crash::show_pending_report();
+
+        // The longer shortcut first, Ctrl+R would also match Ctrl+Shift+R.
+        if ctx.input_mut(|input| input.consume_shortcut(&HARD_RESET_SHORTCUT)) {
+            self.hard_reset();
+        }
+        if ctx.input_mut(|input| input.consume_shortcut(&SOFT_RESET_SHORTCUT)) {
+            self.gba.lock().unwrap().soft_reset();
+        }
+
+        match self.pause_menu.show(ctx, &self.gba) {
+            Some(MenuEvent::Opened) => self.pause(),
+            Some(MenuEvent::Closed) => self.play(),
+            None => {}
+        }
+
+        tool_window(ctx, self.name())
             .default_width(320.0)
             .open(open)
             .show(ctx, |ui| {
@@ -114,7 +314,7 @@ impl UiTool for CpuHandler {
     #[allow(clippy::too_many_lines)]
     fn ui(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            ui.label("Cartridge name:");
+            ui.label(tr("cartridge-name"));
             let mut cartridge_name = String::default();
             if let Ok(gba) = self.gba.lock() {
                 cartridge_name.clone_from(&gba.cartridge_header.game_title);
@@ -128,42 +328,7 @@ impl UiTool for CpuHandler {
                 )
                 .clicked()
             {
-                if self.play.load(std::sync::atomic::Ordering::Relaxed) {
-                    return;
-                }
-
-                let gba_clone = Arc::clone(&self.gba);
-                let play_clone = Arc::clone(&self.play);
-                let breakpoints_clone = Arc::clone(&self.breakpoints);
-
-                self.play.swap(true, std::sync::atomic::Ordering::Relaxed);
-
-                self.thread_handle = Some(thread::spawn(move || {
-                    while play_clone.load(std::sync::atomic::Ordering::Relaxed) {
-                        breakpoints_clone.lock().unwrap().iter().for_each(|&b| {
-                            let pc = u32::try_from(
-                                gba_clone.lock().unwrap().cpu.registers.program_counter(),
-                            )
-                            .expect("Failed to convert u16 to u32");
-                            match b.kind {
-                                BreakpointType::Equal => {
-                                    if pc == b.address {
-                                        play_clone
-                                            .swap(false, std::sync::atomic::Ordering::Relaxed);
-                                    }
-                                }
-                                BreakpointType::Greater => {
-                                    if pc > b.address {
-                                        play_clone
-                                            .swap(false, std::sync::atomic::Ordering::Relaxed);
-                                    }
-                                }
-                            }
-                        });
-
-                        gba_clone.lock().unwrap().step();
-                    }
-                }));
+                self.play();
             }
 
             if ui
@@ -173,19 +338,19 @@ impl UiTool for CpuHandler {
                 )
                 .clicked()
             {
-                self.play.swap(false, std::sync::atomic::Ordering::Relaxed);
-                self.thread_handle = None;
+                self.pause();
             }
         });
 
-        ui.collapsing("CPU Advanced controls", |ui| {
-            ui.label(format!(
-                "Current CPU cycle: {}",
-                &mut self.gba.lock().unwrap().cpu.current_cycle
-            ));
+        ui.checkbox(&mut self.skip_bios_intro, tr("skip-bios-intro"))
+            .on_hover_text(tr("skip-bios-intro-hint"));
+
+        ui.collapsing(tr("cpu-advanced-controls"), |ui| {
+            let cycle = self.gba.lock().unwrap().cpu.current_cycle;
+            ui.label(tr_args("current-cpu-cycle", &[("cycle", &cycle)]));
 
             ui.horizontal(|ui| {
-                ui.label("Step CPU cycles:");
+                ui.label(tr("step-cpu-cycles"));
 
                 if ui.button("⏭x1").clicked() {
                     if let Ok(mut gba) = self.gba.lock() {
@@ -219,10 +384,10 @@ impl UiTool for CpuHandler {
             });
 
             ui.horizontal(|ui| {
-                ui.label("Step (custom) CPU cycles:");
+                ui.label(tr("step-custom-cpu-cycles"));
                 ui.add(egui::DragValue::new(&mut self.cycle_to_skip_custom_value).speed(100));
 
-                if ui.button("Step").clicked() {
+                if ui.button(tr("step")).clicked() {
                     if let Ok(mut gba) = self.gba.lock() {
                         (0..self.cycle_to_skip_custom_value).for_each(|_| gba.step());
                     }
@@ -230,13 +395,17 @@ impl UiTool for CpuHandler {
             })
         });
 
-        ui.collapsing("Breakpoints", |ui| {
+        ui.collapsing(tr("divergence-check"), |ui| {
+            self.divergence_check_ui(ui);
+        });
+
+        ui.collapsing(tr("breakpoints"), |ui| {
             ui.horizontal(|ui| {
                 egui::ComboBox::from_id_source("breakpoint-type")
                     .selected_text(if self.breakpoint_combo == BreakpointType::Equal {
-                        "Equal to"
+                        tr("equal-to")
                     } else {
-                        "Greater than"
+                        tr("greater-than")
                     })
                     .show_ui(ui, |ui| {
                         ui.set_width(40.0);
@@ -244,16 +413,16 @@ impl UiTool for CpuHandler {
                         ui.selectable_value(
                             &mut self.breakpoint_combo,
                             BreakpointType::Equal,
-                            "Equal to",
+                            tr("equal-to"),
                         );
                         ui.selectable_value(
                             &mut self.breakpoint_combo,
                             BreakpointType::Greater,
-                            "Greater than",
+                            tr("greater-than"),
                         );
                     });
 
-                ui.label("address (HEX):");
+                ui.label(tr("address-hex"));
 
                 ui.add(
                     TextEdit::singleline(&mut self.b_address)
@@ -261,42 +430,59 @@ impl UiTool for CpuHandler {
                         .char_limit(16),
                 );
 
-                if ui.button("Set").clicked() {
-                    if self.b_address.is_empty() {
-                        return;
+                if ui.button(tr("set")).clicked() {
+                    let address = self.b_address.clone();
+                    if self.add_breakpoint(&address, self.breakpoint_combo) {
+                        self.b_address.clear();
                     }
-
-                    let a = if self.b_address.starts_with("0x") {
-                        self.b_address[2..].to_string()
-                    } else {
-                        self.b_address.clone()
-                    };
-
-                    let address = u32::from_str_radix(&a, 16).unwrap();
-                    let b = Breakpoint {
-                        address,
-                        kind: self.breakpoint_combo,
-                    };
-
-                    self.breakpoints.lock().unwrap().insert(b);
-
-                    self.b_address.clear();
                 }
             });
 
             egui::containers::ScrollArea::new([false, true]).show(ui, |ui| {
-                ui.label("Active breakpoints:");
+                ui.label(tr("active-breakpoints"));
                 let breakpoints = self.breakpoints.lock().unwrap().clone();
+                let gba = self.gba.lock().unwrap();
 
                 for b in &breakpoints {
                     ui.horizontal(|ui| {
                         ui.label(format!("0x{:08X}", b.address));
+                        if let Some(name) = gba.cpu.symbols.describe(b.address) {
+                            ui.monospace(name);
+                        }
                         if ui.button("X").clicked() {
                             self.breakpoints.lock().unwrap().remove(b);
                         }
                     });
                 }
+                drop(gba);
             });
         });
     }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("run")),
+            Command::new(tr("pause")),
+            Command::new(tr("step-cycle")),
+            Command::new(tr("hard-reset")),
+            Command::with_argument(tr("add-breakpoint-at"), tr("breakpoint-address-hint")),
+            Command::new(tr("clear-breakpoints")),
+            Command::new(tr("soft-reset")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, argument: &str) -> bool {
+        match index {
+            0 => self.play(),
+            1 => self.pause(),
+            2 => self.gba.lock().unwrap().step(),
+            3 => self.hard_reset(),
+            4 => return self.add_breakpoint(argument, BreakpointType::Equal),
+            5 => self.breakpoints.lock().unwrap().clear(),
+            6 => self.gba.lock().unwrap().soft_reset(),
+            _ => {}
+        }
+
+        true
+    }
 }
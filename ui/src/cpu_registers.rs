@@ -1,6 +1,7 @@
 use emu::gba::Gba;
 
-use crate::ui_traits::UiTool;
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, UiTool};
 
 use std::sync::{Arc, Mutex};
 
@@ -30,7 +31,7 @@ impl UiTool for CpuRegisters {
     }
 
     fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
+        tool_window(ctx, self.name())
             .default_width(320.0)
             .open(open)
             .show(ctx, |ui| {
@@ -39,11 +40,11 @@ impl UiTool for CpuRegisters {
     }
 
     fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Registers");
+        ui.heading(tr("registers"));
         ui.add_space(8.0);
 
-        ui.radio_value(&mut self.base_kind, BaseKind::Dec, "Decimal");
-        ui.radio_value(&mut self.base_kind, BaseKind::Hex, "Hexadecimal");
+        ui.radio_value(&mut self.base_kind, BaseKind::Dec, tr("decimal"));
+        ui.radio_value(&mut self.base_kind, BaseKind::Hex, tr("hexadecimal"));
         ui.add_space(8.0);
 
         let registers = self.gba.lock().unwrap().cpu.registers.to_vec();
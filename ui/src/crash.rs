@@ -0,0 +1,103 @@
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use emu::{crash_report::CrashReport, gba::Gba};
+
+use crate::dialog::show_error_text;
+use crate::i18n::tr_args;
+use crate::paths;
+
+/// Instructions kept in the trace of the reports.
+pub(crate) const TRACE_LENGTH: usize = 256;
+
+/// Message and backtrace of the last panic, taken by the hook before the thread unwinds.
+static LAST_PANIC: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+/// Where the report of a crash was saved, until the UI thread shows it.
+static PENDING_REPORT: Mutex<Option<Result<PathBuf, String>>> = Mutex::new(None);
+
+/// Keeps the message and the backtrace of the panics for the reports, they are still
+/// printed as usual.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        let message = match info.location() {
+            Some(location) => format!("{message} at {location}"),
+            None => message,
+        };
+
+        *LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((message, Backtrace::force_capture().to_string()));
+
+        default_hook(info);
+    }));
+}
+
+/// Runs the emulation with `run`. If it panics the report is saved with the state of
+/// `gba`, and it's shown by [`show_pending_report`] instead of the application dying.
+pub(crate) fn catch_crash(gba: &Mutex<Gba>, run: impl FnOnce()) {
+    if panic::catch_unwind(AssertUnwindSafe(run)).is_ok() {
+        return;
+    }
+
+    // The panic happened while the emulator was locked, what's left of it goes in the
+    // report and the other tools can keep using it.
+    gba.clear_poison();
+    let (message, backtrace) = LAST_PANIC
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .unwrap_or_default();
+
+    let saved = save_report(&gba.lock().unwrap(), &message, &backtrace);
+    *PENDING_REPORT.lock().unwrap() = Some(saved.map_err(|e| e.to_string()));
+}
+
+/// Reports are kept in `crashes` of the data directory.
+fn save_report(gba: &Gba, message: &str, backtrace: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = paths::data_dir()
+        .ok_or("No data directory")?
+        .join("crashes");
+    fs::create_dir_all(&dir)?;
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = dir.join(format!("crash-{seconds}.zip"));
+    fs::write(&path, CrashReport::new(gba, message, backtrace).to_zip()?)?;
+
+    Ok(path)
+}
+
+/// Tells the user about the last crash, if there was one since the last call.
+pub(crate) fn show_pending_report() {
+    let Some(saved) = PENDING_REPORT.lock().unwrap().take() else {
+        return;
+    };
+
+    let issues = concat!(env!("CARGO_PKG_REPOSITORY"), "/issues");
+    let text = match saved {
+        Ok(path) => tr_args(
+            "crash-report-saved",
+            &[("path", &path.display().to_string()), ("issues", &issues)],
+        ),
+        Err(error) => tr_args(
+            "crash-report-error",
+            &[("error", &error), ("issues", &issues)],
+        ),
+    };
+
+    show_error_text(&text);
+}
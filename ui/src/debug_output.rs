@@ -0,0 +1,83 @@
+use emu::{cpu::hardware::debug::DebugLevel, gba::Gba};
+
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+use std::sync::{Arc, Mutex};
+
+/// Messages printed by the game with mGBA, `AGBPrint` or no$gba debug output.
+pub struct DebugOutput {
+    gba: Arc<Mutex<Gba>>,
+}
+
+impl DebugOutput {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self { gba }
+    }
+}
+
+const fn level_color(level: DebugLevel) -> egui::Color32 {
+    match level {
+        DebugLevel::Fatal | DebugLevel::Error => egui::Color32::RED,
+        DebugLevel::Warn => egui::Color32::YELLOW,
+        DebugLevel::Info => egui::Color32::LIGHT_GRAY,
+        DebugLevel::Debug => egui::Color32::GRAY,
+    }
+}
+
+impl UiTool for DebugOutput {
+    fn name(&self) -> &'static str {
+        "Debug Output"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(480.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut gba = self.gba.lock().unwrap();
+        let messages = &mut gba.cpu.bus.debug_output.messages;
+
+        if ui.button(tr("clear")).clicked() {
+            messages.clear();
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for message in messages.iter() {
+                    ui.colored_label(
+                        level_color(message.level),
+                        egui::RichText::new(message.to_string()).monospace(),
+                    );
+                }
+            });
+
+        drop(gba);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::new(tr("clear-debug-output"))]
+    }
+
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        self.gba
+            .lock()
+            .unwrap()
+            .cpu
+            .bus
+            .debug_output
+            .messages
+            .clear();
+
+        true
+    }
+}
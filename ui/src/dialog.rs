@@ -0,0 +1,19 @@
+use std::error::Error;
+
+use native_dialog::{MessageDialog, MessageType};
+
+/// Tells the user about `err` in a message box.
+pub fn show_error(err: &dyn Error) {
+    show_error_text(&err.to_string());
+}
+
+/// Shows `text` in an error message box and waits for the user to close it.
+pub fn show_error_text(text: &str) {
+    // Looking at the code of `MessageDialog` it seems like `.show_alert()` can never return `Err`
+    MessageDialog::new()
+        .set_title("Clementine")
+        .set_type(MessageType::Error)
+        .set_text(text)
+        .show_alert()
+        .unwrap();
+}
@@ -1,33 +1,331 @@
-use crate::ui_traits::UiTool;
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
 use egui::{ScrollArea, TextEdit, TextStyle};
+use emu::debugger::analysis::CodeAnalysis;
 use emu::gba::Gba;
+use logger::log;
+use std::error::Error;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+/// Instructions listed from the address the listing is at.
+const LISTED_INSTRUCTIONS: usize = 32;
+
 pub struct Disassembler {
     gba: Arc<Mutex<Gba>>,
+    /// File next to the cartridge the labels and comments are saved in.
+    annotations_path: PathBuf,
+    /// Address typed by the user, a label, a symbol or a number in hex.
+    go_to: String,
+    /// Start of the listing, it follows the CPU while it's `None`.
+    listing: Option<(u32, bool)>,
+    /// Address being annotated, with its label and comment.
+    selected: Option<(u32, String, String)>,
+    /// Functions and literal pools of the ROM, once analyzed.
+    analysis: Option<CodeAnalysis>,
+    error: Option<String>,
 }
 
 impl Disassembler {
-    pub(crate) fn new(arc_gba: Arc<Mutex<Gba>>) -> Self {
-        Self { gba: arc_gba }
+    pub(crate) const fn new(arc_gba: Arc<Mutex<Gba>>, annotations_path: PathBuf) -> Self {
+        Self {
+            gba: arc_gba,
+            annotations_path,
+            go_to: String::new(),
+            listing: None,
+            selected: None,
+            analysis: None,
+            error: None,
+        }
     }
-}
 
-impl UiTool for Disassembler {
-    fn name(&self) -> &'static str {
-        "Disassembler"
+    /// Moves the listing to an address typed by the user, it returns `false` if it's not valid.
+    fn go_to(&mut self, text: &str) -> bool {
+        let gba = self.gba.lock().unwrap();
+        let address = gba
+            .cpu
+            .annotations
+            .address_of(text.trim())
+            .or_else(|| gba.cpu.symbols.parse_address(text));
+        drop(gba);
+
+        let Some(address) = address else {
+            self.error = Some(format!("{}: {text}", tr("invalid-address")));
+            return false;
+        };
+
+        self.show_at(address);
+        self.error = None;
+        true
     }
 
-    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
-            .resizable(true)
-            .open(open)
-            .show(ctx, |ui| {
-                self.ui(ui);
+    /// Moves the listing to `address`, in the instruction set of the function starting
+    /// there if the analysis found one, else in the one of the listing.
+    fn show_at(&mut self, address: u32) {
+        let is_thumb = self
+            .analysis
+            .as_ref()
+            .and_then(|analysis| analysis.function_at(address))
+            .or_else(|| self.listing.map(|(_, is_thumb)| is_thumb))
+            .unwrap_or_else(|| self.gba.lock().unwrap().cpu.is_thumb());
+
+        self.listing = Some((address, is_thumb));
+    }
+
+    fn analyze(&mut self) {
+        let analysis = CodeAnalysis::analyze(&self.gba.lock().unwrap().cpu.bus.internal_memory.rom);
+        log(format!(
+            "found {} functions in the ROM",
+            analysis.functions().count()
+        ));
+
+        self.analysis = Some(analysis);
+    }
+
+    fn save_annotation(&mut self) {
+        let Some((address, label, comment)) = &self.selected else {
+            return;
+        };
+
+        let mut gba = self.gba.lock().unwrap();
+        gba.cpu.annotations.set(*address, label, comment);
+        let text = gba.cpu.annotations.to_text();
+        drop(gba);
+
+        self.error = self
+            .write_annotations(&text)
+            .err()
+            .map(|err| err.to_string());
+    }
+
+    fn write_annotations(&self, text: &str) -> Result<(), Box<dyn Error>> {
+        log(format!(
+            "saving annotations {}",
+            self.annotations_path.display()
+        ));
+        std::fs::write(&self.annotations_path, text)?;
+
+        Ok(())
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut egui::Ui, start: u32, is_thumb: bool) {
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut self.go_to)
+                    .hint_text(tr("go-to-address-hint"))
+                    .desired_width(140.0),
+            );
+            let is_submitted =
+                response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+            if ui.button(tr("go-to-address")).clicked() || is_submitted {
+                self.go_to(&self.go_to.clone());
+            }
+
+            let mut is_thumb = is_thumb;
+            if ui.checkbox(&mut is_thumb, "Thumb").changed() {
+                self.listing = Some((start, is_thumb));
+            }
+
+            if ui
+                .add_enabled(self.listing.is_some(), egui::Button::new(tr("follow-pc")))
+                .clicked()
+            {
+                self.listing = None;
+            }
+
+            if ui
+                .button(tr("analyze-rom"))
+                .on_hover_text(tr("analyze-rom-hint"))
+                .clicked()
+            {
+                self.analyze();
+            }
+        });
+    }
+
+    fn listing_ui(&mut self, ui: &mut egui::Ui) {
+        let gba = self.gba.lock().unwrap();
+        let next_address = gba.cpu.next_instruction_address();
+        let (start, is_thumb) = self
+            .listing
+            .unwrap_or_else(|| (next_address, gba.cpu.is_thumb()));
+
+        let analysis = self.analysis.as_ref();
+
+        let lines = gba
+            .cpu
+            .disassemble(start, LISTED_INSTRUCTIONS, is_thumb)
+            .into_iter()
+            .filter_map(|(address, text)| {
+                // Literal pools are data, shown once for both halves in Thumb.
+                let literal = analysis.and_then(|analysis| analysis.literal_at(address));
+                let text = match literal {
+                    Some(_) if address & 3 != 0 => return None,
+                    Some(value) => format!(".word 0x{value:08X}"),
+                    None => text,
+                };
+
+                let annotations = &gba.cpu.annotations;
+                let label = annotations
+                    .label(address)
+                    .map(str::to_string)
+                    .or_else(|| {
+                        gba.cpu
+                            .symbols
+                            .at(address)
+                            .map(|symbol| symbol.name.clone())
+                    })
+                    .or_else(|| {
+                        analysis
+                            .and_then(|analysis| analysis.function_at(address))
+                            .map(|_| function_name(address))
+                    });
+                let comment = annotations.comment(address).map(str::to_string);
+
+                Some((address, label, text, comment))
+            })
+            .collect::<Vec<_>>();
+        drop(gba);
+
+        self.toolbar_ui(ui, start, is_thumb);
+
+        egui::Grid::new("disassembly listing")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (address, label, text, comment) in lines {
+                    if let Some(label) = label {
+                        ui.strong(format!("{label}:"));
+                        ui.end_row();
+                    }
+
+                    let marker = if address == next_address { ">" } else { " " };
+                    let is_selected = self
+                        .selected
+                        .as_ref()
+                        .is_some_and(|(selected, _, _)| *selected == address);
+
+                    if ui
+                        .selectable_label(
+                            is_selected,
+                            egui::RichText::new(format!("{marker}{address:08X}")).monospace(),
+                        )
+                        .on_hover_text(tr("annotate-hint"))
+                        .clicked()
+                    {
+                        let annotation = self
+                            .gba
+                            .lock()
+                            .unwrap()
+                            .cpu
+                            .annotations
+                            .get(address)
+                            .cloned();
+                        let annotation = annotation.unwrap_or_default();
+                        self.selected = Some((address, annotation.label, annotation.comment));
+                    }
+                    ui.monospace(text);
+                    if let Some(comment) = comment {
+                        ui.colored_label(egui::Color32::GRAY, format!("; {comment}"));
+                    }
+                    ui.end_row();
+                }
             });
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
+    fn annotation_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((address, label, comment)) = &mut self.selected else {
+            ui.small(tr("annotate-hint"));
+            return;
+        };
+
+        let mut is_saved = false;
+        let mut is_closed = false;
+        ui.horizontal(|ui| {
+            ui.monospace(format!("{address:08X}"));
+            ui.add(
+                TextEdit::singleline(label)
+                    .hint_text(tr("label"))
+                    .desired_width(120.0),
+            );
+            ui.add(TextEdit::singleline(comment).hint_text(tr("comment")));
+
+            is_saved = ui.button(tr("save")).clicked();
+            is_closed = ui.button("X").clicked();
+        });
+
+        if is_saved {
+            self.save_annotation();
+        }
+        if is_closed {
+            self.selected = None;
+        }
+    }
+
+    /// Annotated addresses, a click moves the listing to them.
+    fn bookmarks_ui(&mut self, ui: &mut egui::Ui) {
+        let gba = self.gba.lock().unwrap();
+        let bookmarks = gba
+            .cpu
+            .annotations
+            .iter()
+            .map(|(address, annotation)| {
+                let name = if annotation.label.is_empty() {
+                    annotation.comment.clone()
+                } else {
+                    annotation.label.clone()
+                };
+
+                (address, name)
+            })
+            .collect::<Vec<_>>();
+        drop(gba);
+
+        if bookmarks.is_empty() {
+            ui.small(tr("no-bookmarks"));
+            return;
+        }
+
+        for (address, name) in bookmarks {
+            if ui.link(format!("{address:08X} {name}")).clicked() {
+                self.show_at(address);
+            }
+        }
+    }
+
+    /// Functions found by the analysis, a click moves the listing to them.
+    fn functions_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(analysis) = &self.analysis else {
+            ui.small(tr("analyze-rom-hint"));
+            return;
+        };
+
+        let functions = analysis.functions().collect::<Vec<_>>();
+        let row_height = ui.text_style_height(&TextStyle::Body);
+        let mut clicked = None;
+
+        ScrollArea::vertical()
+            .id_source("functions")
+            .max_height(200.0)
+            .show_rows(ui, row_height, functions.len(), |ui, rows| {
+                for &(address, is_thumb) in &functions[rows] {
+                    let set = if is_thumb { "Thumb" } else { "ARM" };
+                    if ui
+                        .link(format!("{} ({set})", function_name(address)))
+                        .clicked()
+                    {
+                        clicked = Some(address);
+                    }
+                }
+            });
+
+        if let Some(address) = clicked {
+            self.show_at(address);
+        }
+    }
+
+    fn trace_ui(&self, ui: &mut egui::Ui) {
         let mut s = self.gba.lock().unwrap().cpu.disassembler_buffer.join("\n");
 
         ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
@@ -48,3 +346,62 @@ impl UiTool for Disassembler {
         });
     }
 }
+
+/// Name of the functions without a symbol or a label.
+fn function_name(address: u32) -> String {
+    format!("sub_{address:08X}")
+}
+
+impl UiTool for Disassembler {
+    fn name(&self) -> &'static str {
+        "Disassembler"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .resizable(true)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(tr("listing"))
+            .default_open(true)
+            .show(ui, |ui| {
+                self.listing_ui(ui);
+                ui.separator();
+                self.annotation_ui(ui);
+            });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        egui::CollapsingHeader::new(tr("bookmarks")).show(ui, |ui| {
+            self.bookmarks_ui(ui);
+        });
+
+        egui::CollapsingHeader::new(tr("functions")).show(ui, |ui| {
+            self.functions_ui(ui);
+        });
+
+        egui::CollapsingHeader::new(tr("trace"))
+            .default_open(true)
+            .show(ui, |ui| {
+                self.trace_ui(ui);
+            });
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::with_argument(
+            tr("go-to-address"),
+            tr("go-to-address-hint"),
+        )]
+    }
+
+    fn run_command(&mut self, _index: usize, argument: &str) -> bool {
+        self.go_to(argument)
+    }
+}
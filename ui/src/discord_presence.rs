@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::io::{Read, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+/// Id of the Discord application the presence is shown for, it names what the user is
+/// playing ("Playing Clementine"). Set at build time, the presence can't be enabled without.
+pub const CLIENT_ID: Option<&str> = option_env!("CLEMENTINE_DISCORD_CLIENT_ID");
+
+/// How often the connection is tried again when Discord is not running.
+const RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Discord answers every frame, a stuck client must not block the worker forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+/// What is shown on the profile of the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Activity {
+    /// From the cartridge header, `None` if it has none (eg. multiboot images).
+    title: Option<String>,
+    /// Unix seconds of the start of the session, Discord shows the time elapsed.
+    start: u64,
+}
+
+/// Discord Rich Presence with the game being played, off by default since it makes
+/// it public. The IPC connection is handled by a worker thread so Discord not
+/// running or not answering never slows down the emulator.
+pub struct DiscordPresence {
+    activity: Activity,
+    is_enabled: bool,
+    /// Dropped with `self`, then the worker closes the connection and the presence goes away.
+    sender: mpsc::Sender<Option<Activity>>,
+}
+
+impl DiscordPresence {
+    pub fn new(title: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || worker(&receiver));
+
+        Self {
+            activity: Activity {
+                title: Some(title.trim().to_owned()).filter(|title| !title.is_empty()),
+                start: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs()),
+            },
+            is_enabled: false,
+            sender,
+        }
+    }
+
+    pub fn set_enabled(&mut self, is_enabled: bool) {
+        if is_enabled == self.is_enabled || CLIENT_ID.is_none() {
+            return;
+        }
+
+        self.is_enabled = is_enabled;
+        // The worker only stops when `self` is dropped.
+        let _ = self.sender.send(is_enabled.then(|| self.activity.clone()));
+    }
+}
+
+/// A connection to the Discord client, through a Unix socket or a Windows named pipe.
+trait Pipe: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Pipe for T {}
+
+struct Connection {
+    pipe: Box<dyn Pipe>,
+    nonce: u64,
+}
+
+impl Connection {
+    /// Connects to the first Discord client found and identifies the application.
+    fn open(client_id: &str) -> Result<Self, Box<dyn Error>> {
+        let mut connection = Self {
+            pipe: open_pipe()?,
+            nonce: 0,
+        };
+
+        connection.send(OP_HANDSHAKE, &json!({ "v": 1, "client_id": client_id }))?;
+
+        Ok(connection)
+    }
+
+    /// Sends a frame and reads the answer of Discord.
+    fn send(&mut self, op: u32, payload: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+        let payload = payload.to_string();
+        let mut frame = Vec::with_capacity(8 + payload.len());
+        frame.extend(op.to_le_bytes());
+        frame.extend(u32::try_from(payload.len())?.to_le_bytes());
+        frame.extend(payload.as_bytes());
+        self.pipe.write_all(&frame)?;
+
+        let mut header = [0; 8];
+        self.pipe.read_exact(&mut header)?;
+        let length = u32::from_le_bytes(header[4..].try_into()?);
+        let mut answer = vec![0; length as usize];
+        self.pipe.read_exact(&mut answer)?;
+
+        let answer: serde_json::Value = serde_json::from_slice(&answer)?;
+        if answer["evt"] == "ERROR" {
+            return Err(answer["data"]["message"].to_string().into());
+        }
+
+        Ok(())
+    }
+
+    fn set_activity(&mut self, activity: Option<&Activity>) -> Result<(), Box<dyn Error>> {
+        self.nonce += 1;
+
+        let activity = activity.map(|activity| {
+            let mut value = json!({ "timestamps": { "start": activity.start } });
+            if let Some(title) = &activity.title {
+                value["details"] = json!(title);
+            }
+            value
+        });
+
+        self.send(
+            OP_FRAME,
+            &json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": std::process::id(), "activity": activity },
+                "nonce": self.nonce.to_string(),
+            }),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn open_pipe() -> Result<Box<dyn Pipe>, Box<dyn Error>> {
+    use std::os::unix::net::UnixStream;
+
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(std::env::var_os)
+        .map_or_else(|| std::path::PathBuf::from("/tmp"), Into::into);
+
+    // Flatpak and Snap builds of Discord put the socket in a subfolder.
+    for subfolder in ["", "app/com.discordapp.Discord", "snap.discord"] {
+        for index in 0..10 {
+            let path = dir.join(subfolder).join(format!("discord-ipc-{index}"));
+            if let Ok(stream) = UnixStream::connect(path) {
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                return Ok(Box::new(stream));
+            }
+        }
+    }
+
+    Err("Discord is not running".into())
+}
+
+#[cfg(windows)]
+fn open_pipe() -> Result<Box<dyn Pipe>, Box<dyn Error>> {
+    for index in 0..10 {
+        let pipe = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(format!(r"\\?\pipe\discord-ipc-{index}"));
+        if let Ok(pipe) = pipe {
+            return Ok(Box::new(pipe));
+        }
+    }
+
+    Err("Discord is not running".into())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open_pipe() -> Result<Box<dyn Pipe>, Box<dyn Error>> {
+    Err("Discord Rich Presence is not supported on this platform".into())
+}
+
+/// Keeps the presence in sync with the last activity received, connecting again
+/// when Discord is restarted.
+fn worker(receiver: &mpsc::Receiver<Option<Activity>>) {
+    let Some(client_id) = CLIENT_ID else {
+        return;
+    };
+
+    let mut activity = None;
+    let mut connection: Option<Connection> = None;
+    let mut is_sent = false;
+
+    loop {
+        match receiver.recv_timeout(RETRY_INTERVAL) {
+            Ok(new_activity) => {
+                activity = new_activity;
+                is_sent = false;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if activity.is_none() && connection.is_none() {
+            continue;
+        }
+
+        if connection.is_none() {
+            match Connection::open(client_id) {
+                Ok(new_connection) => connection = Some(new_connection),
+                Err(e) => {
+                    logger::log(format!("no Discord presence: {e}"));
+                    continue;
+                }
+            }
+            is_sent = false;
+        }
+
+        if let (Some(open), false) = (&mut connection, is_sent) {
+            match open.set_activity(activity.as_ref()) {
+                Ok(()) => is_sent = true,
+                Err(e) => {
+                    logger::log(format!("Discord presence lost: {e}"));
+                    connection = None;
+                }
+            }
+        }
+
+        // Nothing to show, Discord clears the presence when the connection is closed.
+        if activity.is_none() {
+            connection = None;
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Refresh rate of the LCD, 280896 cycles at 16.78 MHz.
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+/// Audio queued ahead of the device, the emulation waits above it. Enough to survive a
+/// late frame without the samples running out.
+const AUDIO_LATENCY: Duration = Duration::from_millis(50);
+
+/// Samples waiting for the audio device, updated by the output while it's open.
+static QUEUED_SAMPLES: AtomicUsize = AtomicUsize::new(0);
+
+/// Sample rate of the audio device, 0 when there's no audio output.
+static AUDIO_SAMPLE_RATE: AtomicU32 = AtomicU32::new(0);
+
+/// How the frames of the game reach the monitor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentMode {
+    /// The window is drawn at every refresh of the monitor. At 60 Hz it's smooth, at other
+    /// rates (eg. 144 Hz) a frame of the game stays on screen for 2 or 3 refreshes and it
+    /// judders.
+    #[default]
+    Vsync,
+    /// The window is drawn as soon as the game completes a frame, without waiting for the
+    /// monitor. With variable refresh rate (`FreeSync`, G-Sync) the monitor follows the
+    /// 59.73 Hz of the console; without it the frames are still paced by the audio.
+    Adaptive,
+}
+
+impl PresentMode {
+    pub const ALL: [Self; 2] = [Self::Vsync, Self::Adaptive];
+
+    #[must_use]
+    pub const fn vsync(self) -> bool {
+        matches!(self, Self::Vsync)
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vsync => tr("present-mode-vsync"),
+            Self::Adaptive => tr("present-mode-adaptive"),
+        }
+    }
+}
+
+/// Tells the pacing how much audio is waiting for a device with `sample_rate`, see
+/// [`FramePacer::wait`].
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+pub fn set_queued_audio(queued_samples: usize, sample_rate: u32) {
+    QUEUED_SAMPLES.store(queued_samples, Ordering::Relaxed);
+    AUDIO_SAMPLE_RATE.store(sample_rate, Ordering::Relaxed);
+}
+
+/// Duration of the audio waiting for the device, `None` without audio output.
+fn queued_audio() -> Option<Duration> {
+    let sample_rate = AUDIO_SAMPLE_RATE.load(Ordering::Relaxed);
+    let queued = u64::try_from(QUEUED_SAMPLES.load(Ordering::Relaxed)).unwrap_or(u64::MAX);
+
+    (sample_rate > 0).then(|| {
+        Duration::from_nanos(queued.saturating_mul(1_000_000_000) / u64::from(sample_rate))
+    })
+}
+
+/// Keeps the emulation at the speed of the console, the monitor doesn't set it.
+#[derive(Debug, Default)]
+pub struct FramePacer {
+    /// When the next frame is due, without audio output.
+    next_frame: Option<Instant>,
+}
+
+impl FramePacer {
+    /// Waits after a frame is completed. With audio output the clock of the device sets
+    /// the speed, so the sound never skips; otherwise the clock of the host does.
+    pub fn wait(&mut self) {
+        if let Some(queued) = queued_audio() {
+            self.next_frame = None;
+            if let Some(ahead) = queued.checked_sub(AUDIO_LATENCY) {
+                thread::sleep(ahead);
+            }
+            return;
+        }
+
+        let now = Instant::now();
+        let next_frame = self.next_frame.map_or(now + FRAME_DURATION, |next_frame| {
+            next_frame + FRAME_DURATION
+        });
+
+        // Too late to catch up (eg. after a pause), the frames start again from now.
+        if next_frame + FRAME_DURATION < now {
+            self.next_frame = Some(now);
+            return;
+        }
+
+        self.next_frame = Some(next_frame);
+        if let Some(early) = next_frame.checked_duration_since(now) {
+            thread::sleep(early);
+        }
+    }
+}
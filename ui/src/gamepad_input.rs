@@ -0,0 +1,63 @@
+use std::error::Error;
+
+use gilrs::{Button, EventType, Gilrs};
+
+/// Buttons of the gamepads connected to the host, to use the menus from the couch. They
+/// become the keys that drive the UI from the keyboard, so every panel works with them.
+pub struct GamepadInput {
+    gilrs: Gilrs,
+}
+
+impl GamepadInput {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            gilrs: Gilrs::new().map_err(|err| err.to_string())?,
+        })
+    }
+
+    /// Adds to `raw_input` the keys of the buttons pressed and released on any gamepad
+    /// since the last call. With nothing focused the D-pad focuses the first widget, the
+    /// arrows only move the focus from a widget.
+    pub fn poll(&mut self, raw_input: &mut egui::RawInput, is_anything_focused: bool) {
+        while let Some(event) = self.gilrs.next_event() {
+            let (button, pressed) = match event.event {
+                EventType::ButtonPressed(button, _) => (button, true),
+                EventType::ButtonReleased(button, _) => (button, false),
+                _ => continue,
+            };
+            let Some((key, modifiers)) = key_of(button, is_anything_focused) else {
+                continue;
+            };
+
+            raw_input.events.push(egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers,
+            });
+        }
+    }
+}
+
+/// Start opens the pause menu, A clicks, B goes back, L and R jump between widgets.
+fn key_of(button: Button, is_anything_focused: bool) -> Option<(egui::Key, egui::Modifiers)> {
+    let previous = (egui::Key::Tab, egui::Modifiers::SHIFT);
+    let next = (egui::Key::Tab, egui::Modifiers::NONE);
+    let key = |key| (key, egui::Modifiers::NONE);
+
+    Some(match button {
+        Button::Start | Button::Mode => key(egui::Key::Escape),
+        Button::DPadUp | Button::DPadLeft if !is_anything_focused => previous,
+        Button::DPadDown | Button::DPadRight if !is_anything_focused => next,
+        Button::DPadUp => key(egui::Key::ArrowUp),
+        Button::DPadDown => key(egui::Key::ArrowDown),
+        Button::DPadLeft => key(egui::Key::ArrowLeft),
+        Button::DPadRight => key(egui::Key::ArrowRight),
+        Button::LeftTrigger => previous,
+        Button::RightTrigger => next,
+        Button::South => key(egui::Key::Enter),
+        Button::East => key(egui::Key::Z),
+        _ => return None,
+    })
+}
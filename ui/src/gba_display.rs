@@ -1,60 +1,355 @@
-use egui::{self, ColorImage, ImageSource, Ui};
+use egui::{self, ColorImage, Ui};
 
-use eframe::epaint::textures::TextureOptions;
-use egui::load::SizedTexture;
-use std::sync::{Arc, Mutex};
+use native_dialog::FileDialog;
+use std::error::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
 
 use emu::{
+    cpu::hardware::lcd::{ClipBuffer, Color, IndexedLayer, Recorder},
     gba::Gba,
-    render::{LCD_HEIGHT, LCD_WIDTH},
+    render::{
+        color_correction::{ColorCorrection, ColorTable},
+        LCD_HEIGHT, LCD_WIDTH,
+    },
 };
 
-use crate::ui_traits::UiTool;
+use crate::border::{Border, BorderScaling, BorderSettings, BorderSource};
+use crate::dialog::show_error;
+use crate::i18n::{tr, tr_args};
+use crate::osd::Osd;
+use crate::renderer::{Frame, Screen};
+use crate::screen_filter;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Layers that can be hidden, in the order of the bits of `Lcd::hidden_layers`.
+const LAYER_NAMES: [&str; 5] = ["BG0", "BG1", "BG2", "BG3", "OBJ"];
+
+/// Saves the last seconds as a GIF from anywhere in the application.
+const CLIP_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::G);
+
+/// Seconds kept when the clips are started with the shortcut.
+const DEFAULT_CLIP_SECONDS: u8 = 10;
+
+/// Commands before the ones of the indexed export.
+const CLIP_COMMANDS: usize = 3;
+
+/// A table for each of [`ColorCorrection::ALL`], built the first time a frame is shown.
+static COLOR_TABLES: LazyLock<Vec<ColorTable>> = LazyLock::new(|| {
+    ColorCorrection::ALL
+        .into_iter()
+        .map(ColorTable::new)
+        .collect()
+});
+
+/// Index in [`ColorCorrection::ALL`] of the correction applied to every image of the screen.
+static CURRENT_CORRECTION: AtomicU8 = AtomicU8::new(0);
+
+#[allow(clippy::cast_possible_truncation)]
+pub fn set_color_correction(correction: ColorCorrection) {
+    let index = ColorCorrection::ALL
+        .iter()
+        .position(|option| *option == correction)
+        .unwrap_or_default();
+
+    CURRENT_CORRECTION.store(index as u8, Ordering::Relaxed);
+}
+
+/// The correction applied to every image of the screen.
+pub fn color_correction() -> ColorCorrection {
+    ColorCorrection::ALL[usize::from(CURRENT_CORRECTION.load(Ordering::Relaxed))]
+}
+
+/// The colors shown with `correction`.
+pub fn color_table(correction: ColorCorrection) -> &'static ColorTable {
+    let index = ColorCorrection::ALL
+        .iter()
+        .position(|option| *option == correction)
+        .unwrap_or_default();
+
+    &COLOR_TABLES[index]
+}
 
 pub struct GbaDisplay {
     gba: Arc<Mutex<Gba>>,
+    osd: Osd,
+    /// Indexed export waiting for the palette indices of a whole frame.
+    pending_export: Option<IndexedLayer>,
+    /// Chosen in the settings of the application.
+    border_settings: Arc<Mutex<BorderSettings>>,
+    border: Option<Border>,
+    /// Source of `border`, it's loaded again when the settings change.
+    border_source: BorderSource,
+    screen: Screen,
 }
 
 impl GbaDisplay {
-    pub(crate) const fn new(gba: Arc<Mutex<Gba>>) -> Self {
-        Self { gba }
+    pub(crate) fn new(gba: Arc<Mutex<Gba>>, border_settings: Arc<Mutex<BorderSettings>>) -> Self {
+        Self {
+            gba,
+            osd: Osd::default(),
+            pending_export: None,
+            border_settings,
+            border: None,
+            border_source: BorderSource::None,
+            screen: Screen::new("gba_display"),
+        }
+    }
+
+    /// Loads the border chosen if it changed, a border that can't be read is not retried.
+    fn update_border(&mut self, ctx: &egui::Context) {
+        let source = self.border_settings.lock().unwrap().source.clone();
+        if source == self.border_source {
+            return;
+        }
+
+        self.border = Border::load(ctx, &source).unwrap_or_else(|e| {
+            self.osd
+                .show_message(tr_args("border-error", &[("error", &e.to_string())]));
+            None
+        });
+        self.border_source = source;
+    }
+
+    /// Keeps the last `seconds` of frames for the clips, 0 stops.
+    fn record_clips(&mut self, seconds: u8) {
+        self.gba.lock().unwrap().cpu.bus.lcd.clip_buffer =
+            (seconds > 0).then(|| ClipBuffer::new(seconds));
+
+        self.osd.show_message(if seconds > 0 {
+            tr_args("clips-recording", &[("seconds", &seconds)])
+        } else {
+            tr("clips-stopped").to_string()
+        });
+    }
+
+    /// Saves the frames kept as a GIF, or starts keeping them if it wasn't.
+    fn save_clip(&mut self) {
+        let gba = self.gba.lock().unwrap();
+        let Some(clip_buffer) = &gba.cpu.bus.lcd.clip_buffer else {
+            drop(gba);
+            self.record_clips(DEFAULT_CLIP_SECONDS);
+            return;
+        };
+        // The frames are encoded without holding up the emulation.
+        let clip_buffer = clip_buffer.clone();
+        drop(gba);
+        let gif = clip_buffer.to_gif();
+
+        match save_file("clip.gif", tr("gif-animation"), "gif", &gif) {
+            Ok(()) => self.osd.show_message(tr("clip-saved")),
+            Err(err) => show_error(err.as_ref()),
+        }
+    }
+
+    /// Starts recording every frame, or saves the recording if it was.
+    fn toggle_recording(&mut self) {
+        let mut gba = self.gba.lock().unwrap();
+        let Some(recorder) = gba.cpu.bus.lcd.recorder.take() else {
+            gba.cpu.bus.lcd.recorder = Some(Recorder::new());
+            drop(gba);
+            self.osd.show_message(tr("recording-started"));
+            return;
+        };
+        drop(gba);
+
+        let saved = recorder
+            .finish()
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|recording| {
+                save_file("recording.gif", tr("gif-animation"), "gif", &recording.gif)?;
+                Ok(recording)
+            });
+
+        match saved {
+            Ok(recording) => self.osd.show_message(tr_args(
+                "recording-saved",
+                &[
+                    ("frames", &recording.frames),
+                    ("dropped", &recording.dropped),
+                ],
+            )),
+            Err(err) => show_error(err.as_ref()),
+        }
     }
 
     #[allow(clippy::needless_pass_by_ref_mut)]
     fn ui(&mut self, ui: &mut Ui) {
-        //TODO: Fix this .lock().unwrap() repeated two times
-        let rgb_data = self
-            .gba
-            .lock()
-            .unwrap()
-            .cpu
-            .bus
-            .lcd
-            .buffer
-            .iter()
-            .flat_map(|row| {
-                row.iter().flat_map(|pixel| {
-                    let red = (pixel.red() << 3) | (pixel.red() >> 2);
-                    let green = (pixel.green() << 3) | (pixel.green() >> 2);
-                    let blue = (pixel.blue() << 3) | (pixel.blue() >> 2);
-                    [red, green, blue]
-                })
-            })
-            .collect::<Vec<_>>();
-
-        let image = ColorImage::from_rgb([LCD_WIDTH, LCD_HEIGHT], &rgb_data);
-
-        let texture = ui
-            .ctx()
-            .load_texture("gba_display", image, TextureOptions::NEAREST);
-
-        ui.image(ImageSource::Texture(SizedTexture {
-            id: texture.id(),
-            size: ui.available_size(),
-        }));
+        if self.pending_export.is_some() {
+            ui.small(tr("indexed-export-waiting"));
+        }
+        if let Some(recorder) = &self.gba.lock().unwrap().cpu.bus.lcd.recorder {
+            ui.small(tr_args(
+                "recording-status",
+                &[
+                    ("frames", &recorder.frames()),
+                    ("dropped", &recorder.dropped()),
+                ],
+            ));
+        }
+
+        let scaling = self.border_settings.lock().unwrap().scaling;
+        let Some(border) = &self.border else {
+            show_screen(ui, &self.gba.lock().unwrap(), &mut self.screen, scaling);
+            return;
+        };
+
+        let texture = screen_texture(ui, &self.gba.lock().unwrap(), "gba_display");
+        border.show(ui, &texture, scaling);
+    }
+
+    /// Starts capturing the palette indices, the export is saved after the next frame.
+    fn request_export(&mut self, layer: IndexedLayer) {
+        self.gba.lock().unwrap().cpu.bus.lcd.index_capture = Some(Box::default());
+        self.pending_export = Some(layer);
+    }
+
+    /// Saves the pending export once a frame has been captured, then stops capturing.
+    fn finish_export(&mut self) {
+        let Some(layer) = self.pending_export else {
+            return;
+        };
+
+        let mut gba = self.gba.lock().unwrap();
+        let lcd = &mut gba.cpu.bus.lcd;
+        let Some(frame) = lcd
+            .index_capture
+            .as_ref()
+            .and_then(|capture| capture.last_frame())
+        else {
+            return;
+        };
+
+        let png = frame.to_png(layer);
+        lcd.index_capture = None;
+        drop(gba);
+        self.pending_export = None;
+
+        let filename = format!("{}.png", layer_name(layer).to_lowercase());
+        let saved = png
+            .map_err(Into::into)
+            .and_then(|png| save_file(&filename, tr("indexed-png"), "png", &png));
+
+        if let Err(err) = saved {
+            show_error(err.as_ref());
+        }
     }
 }
 
+fn layer_name(layer: IndexedLayer) -> String {
+    match layer {
+        IndexedLayer::Frame => tr("indexed-frame").to_string(),
+        IndexedLayer::Bg(bg) => format!("BG{bg}"),
+        IndexedLayer::Obj => "OBJ".to_string(),
+    }
+}
+
+fn save_file(
+    filename: &str,
+    filter: &str,
+    extension: &str,
+    data: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let path = FileDialog::new()
+        .set_location("~")
+        .set_filename(filename)
+        .add_filter(filter, &[extension])
+        .show_save_single_file()?;
+
+    let path = path.ok_or_else(|| tr("no-file-selected"))?;
+    std::fs::write(path, data)?;
+
+    Ok(())
+}
+
+/// Draws the last frame of `gba` in the available space with the back end chosen in the
+/// settings. It's stretched over all of it, or centered in it by a whole factor with
+/// [`BorderScaling::Integer`].
+pub fn show_screen(
+    ui: &mut Ui,
+    gba: &Gba,
+    screen: &mut Screen,
+    scaling: BorderScaling,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+    let rect = match scaling {
+        BorderScaling::Fit => rect,
+        BorderScaling::Integer => integer_rect(rect),
+    };
+    screen.paint(ui, rect, shown_frame(gba));
+
+    response
+}
+
+/// The biggest rect with the LCD scaled by a whole factor in the middle of `space`.
+#[allow(clippy::cast_precision_loss)]
+fn integer_rect(space: egui::Rect) -> egui::Rect {
+    let lcd = egui::vec2(LCD_WIDTH as f32, LCD_HEIGHT as f32);
+    let scale = (space.size() / lcd).min_elem().floor().max(1.0);
+
+    egui::Rect::from_center_size(space.center(), lcd * scale)
+}
+
+/// The last frame of `gba`, blended with the one before if it's set.
+fn shown_frame(gba: &Gba) -> &Frame {
+    let lcd = &gba.cpu.bus.lcd;
+
+    lcd.frame_blending
+        .as_ref()
+        .map_or(&lcd.buffer, |frame_blending| frame_blending.frame())
+}
+
+/// The last frame of `gba` in a texture, for the border that goes over it.
+fn screen_texture(ui: &Ui, gba: &Gba, texture_name: &str) -> egui::TextureHandle {
+    let image = screen_image(shown_frame(gba));
+
+    screen_filter::load_texture(ui.ctx(), texture_name, image, ui.available_size())
+}
+
+/// Saves the last frame of `gba` with the color correction, the format follows the
+/// extension of `path`.
+#[cfg_attr(not(unix), allow(dead_code))]
+pub fn save_screenshot(gba: &Gba, path: &Path) -> Result<(), Box<dyn Error>> {
+    #[allow(clippy::cast_possible_truncation)]
+    image::RgbImage::from_raw(
+        LCD_WIDTH as u32,
+        LCD_HEIGHT as u32,
+        screen_rgb(&gba.cpu.bus.lcd.buffer),
+    )
+    .ok_or("The screen has the wrong size")?
+    .save(path)?;
+
+    Ok(())
+}
+
+/// A frame of the LCD with 8 bits per channel.
+pub fn screen_image(buffer: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) -> ColorImage {
+    color_image([LCD_WIDTH, LCD_HEIGHT], buffer.as_flattened())
+}
+
+/// A frame of the LCD as 8 bits red, green and blue bytes, a pixel after the other.
+pub fn screen_rgb(buffer: &[[Color; LCD_WIDTH]; LCD_HEIGHT]) -> Vec<u8> {
+    screen_image(buffer)
+        .pixels
+        .iter()
+        .flat_map(|pixel| [pixel.r(), pixel.g(), pixel.b()])
+        .collect()
+}
+
+/// An image of `size` from its colors row by row, with 8 bits per channel and the
+/// color correction chosen in the settings.
+pub fn color_image(size: [usize; 2], pixels: &[Color]) -> ColorImage {
+    let table = color_table(color_correction());
+    let rgb_data = pixels
+        .iter()
+        .flat_map(|pixel| table.get(*pixel))
+        .collect::<Vec<_>>();
+
+    ColorImage::from_rgb(size, &rgb_data)
+}
+
 impl UiTool for GbaDisplay {
     fn name(&self) -> &'static str {
         "Gba Display"
@@ -62,7 +357,14 @@ impl UiTool for GbaDisplay {
 
     #[allow(clippy::cast_precision_loss)]
     fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
+        if ctx.input_mut(|input| input.consume_shortcut(&CLIP_SHORTCUT)) {
+            self.save_clip();
+        }
+        self.finish_export();
+        self.update_border(ctx);
+        self.osd.show(ctx);
+
+        tool_window(ctx, self.name())
             .open(open)
             .default_width(LCD_WIDTH as f32)
             .default_height(LCD_HEIGHT as f32)
@@ -75,4 +377,56 @@ impl UiTool for GbaDisplay {
     fn ui(&mut self, _ui: &mut Ui) {
         todo!()
     }
+
+    fn commands(&self) -> Vec<Command> {
+        let gba = self.gba.lock().unwrap();
+        let hidden_layers = gba.cpu.bus.lcd.hidden_layers;
+        let recording = if gba.cpu.bus.lcd.recorder.is_some() {
+            "stop-recording"
+        } else {
+            "start-recording"
+        };
+        drop(gba);
+
+        let toggles = LAYER_NAMES.iter().enumerate().map(|(index, layer)| {
+            let id = if hidden_layers & (1 << index) != 0 {
+                "show-layer"
+            } else {
+                "hide-layer"
+            };
+
+            Command::new(tr_args(id, &[("layer", layer)]))
+        });
+        let exports = IndexedLayer::ALL.into_iter().map(|layer| {
+            Command::new(tr_args(
+                "export-indexed-png",
+                &[("layer", &layer_name(layer))],
+            ))
+        });
+
+        let clips = [
+            Command::with_argument(tr("record-clips"), tr("clip-seconds-hint")),
+            Command::new(tr("save-clip")),
+            Command::new(tr(recording)),
+        ];
+
+        toggles.chain(clips).chain(exports).collect()
+    }
+
+    fn run_command(&mut self, index: usize, argument: &str) -> bool {
+        match index.checked_sub(LAYER_NAMES.len()) {
+            None => self.gba.lock().unwrap().cpu.bus.lcd.hidden_layers ^= 1 << index,
+            Some(0) => {
+                let Ok(seconds) = argument.trim().parse() else {
+                    return false;
+                };
+                self.record_clips(seconds);
+            }
+            Some(1) => self.save_clip(),
+            Some(2) => self.toggle_recording(),
+            Some(export) => self.request_export(IndexedLayer::ALL[export - CLIP_COMMANDS]),
+        }
+
+        true
+    }
 }
@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, tr_args};
+use crate::osd::Osd;
+
+/// How often the files are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What is done when the cartridge or the BIOS file changes, eg. when a homebrew is rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotReloadSettings {
+    /// Checks the files for changes.
+    pub watch: bool,
+    /// Reloads without asking.
+    pub automatic: bool,
+    /// Keeps the saves of the game in the backup memory, otherwise it's erased.
+    pub keep_backup: bool,
+}
+
+impl Default for HotReloadSettings {
+    fn default() -> Self {
+        Self {
+            watch: true,
+            automatic: false,
+            keep_backup: true,
+        }
+    }
+}
+
+/// The files that changed, the cartridge is always loaded again.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reload {
+    pub bios: bool,
+}
+
+/// A file and its modification time, a change is reported once the time stays the same
+/// for a check so that a file still being written is not loaded.
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    changed: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+
+        Self {
+            path,
+            modified,
+            changed: None,
+        }
+    }
+
+    fn has_changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            self.changed = None;
+            return false;
+        }
+
+        if self.changed != modified {
+            self.changed = modified;
+            return false;
+        }
+
+        self.modified = modified;
+        self.changed = None;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Watches the cartridge and the BIOS files to load them again when they change, so that
+/// a homebrew can be rebuilt and tried without restarting the emulator.
+pub struct HotReload {
+    cartridge_name: String,
+    /// `None` for multiboot images and downloaded cartridges, they can't be reloaded.
+    cartridge: Option<WatchedFile>,
+    bios: WatchedFile,
+    /// Changes the user hasn't reloaded or ignored yet.
+    pending: Option<Reload>,
+    last_check: Instant,
+    osd: Osd,
+}
+
+impl HotReload {
+    pub fn new(cartridge_name: &str, is_reloadable: bool, bios: PathBuf) -> Self {
+        Self {
+            cartridge_name: cartridge_name.to_owned(),
+            cartridge: is_reloadable.then(|| WatchedFile::new(PathBuf::from(cartridge_name))),
+            bios: WatchedFile::new(bios),
+            pending: None,
+            last_check: Instant::now(),
+            osd: Osd::default(),
+        }
+    }
+
+    pub fn cartridge_name(&self) -> &str {
+        &self.cartridge_name
+    }
+
+    /// Checks the files, with the dialog if they changed. It returns what to reload, if
+    /// the user asked for it or the reload is automatic.
+    pub fn show(&mut self, ctx: &egui::Context, settings: HotReloadSettings) -> Option<Reload> {
+        self.osd.show(ctx);
+
+        if !settings.watch {
+            self.pending = None;
+            return None;
+        }
+        self.check();
+
+        let reload = self.pending?;
+        if settings.automatic {
+            self.pending = None;
+            return Some(reload);
+        }
+
+        let mut result = None;
+        egui::Window::new(tr("hot-reload-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+            .show(ctx, |ui| {
+                let file = if reload.bios {
+                    tr("hot-reload-bios")
+                } else {
+                    tr("hot-reload-cartridge")
+                };
+                ui.label(tr_args("hot-reload-changed", &[("file", &file)]));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("hot-reload-now")).clicked() {
+                        result = Some(reload);
+                    }
+                    if ui.button(tr("hot-reload-ignore")).clicked() {
+                        self.pending = None;
+                    }
+                });
+            });
+
+        if result.is_some() {
+            self.pending = None;
+        }
+        result
+    }
+
+    /// Tells the user how the reload went.
+    pub fn finished(&mut self, result: Result<(), String>) {
+        self.osd.show_message(match result {
+            Ok(()) => tr("hot-reload-done").to_owned(),
+            Err(error) => tr_args("hot-reload-error", &[("error", &error)]),
+        });
+    }
+
+    fn check(&mut self) {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+
+        let Some(cartridge) = &mut self.cartridge else {
+            return;
+        };
+        let cartridge_changed = cartridge.has_changed();
+        let bios_changed = self.bios.has_changed();
+
+        if cartridge_changed || bios_changed {
+            let pending = self.pending.get_or_insert_with(Reload::default);
+            pending.bios |= bios_changed;
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! String catalogs of the user-visible text, one file per language in `ui/locales`.
+//!
+//! The files use a small subset of [Fluent](https://projectfluent.org):
+//! `id = value` messages, `{ $name }` arguments, `#` comments and values continued
+//! on the following indented lines.
+//! Messages missing in a language fall back to English.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "it")]
+    Italian,
+}
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::English, Self::Italian];
+
+    const fn catalog_source(self) -> &'static str {
+        match self {
+            Self::English => include_str!("../locales/en.ftl"),
+            Self::Italian => include_str!("../locales/it.ftl"),
+        }
+    }
+
+    const fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Italian => "it",
+        }
+    }
+
+    /// Language of the system, from the `LANG` environment variable (eg. `it_IT.UTF-8`).
+    #[must_use]
+    pub fn from_env() -> Self {
+        let lang = std::env::var("LANG").unwrap_or_default();
+
+        Self::ALL
+            .into_iter()
+            .find(|language| lang.starts_with(language.code()))
+            .unwrap_or_default()
+    }
+}
+
+impl Display for Language {
+    /// Name of the language in the language itself.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::English => write!(f, "English"),
+            Self::Italian => write!(f, "Italiano"),
+        }
+    }
+}
+
+type Catalog = HashMap<&'static str, String>;
+
+static CATALOGS: LazyLock<Vec<Catalog>> = LazyLock::new(|| {
+    Language::ALL
+        .iter()
+        .map(|language| parse_catalog(language.catalog_source()))
+        .collect()
+});
+
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+pub fn set_language(language: Language) {
+    CURRENT_LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+#[must_use]
+pub fn language() -> Language {
+    Language::ALL[usize::from(CURRENT_LANGUAGE.load(Ordering::Relaxed))]
+}
+
+fn parse_catalog(source: &'static str) -> Catalog {
+    let mut catalog = Catalog::new();
+    let mut current_id = None;
+
+    for line in source.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            // Continuation of the previous message.
+            if let Some(value) = current_id.and_then(|id| catalog.get_mut(id)) {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        if let Some((id, value)) = line.split_once('=') {
+            let id = id.trim();
+            catalog.insert(id, value.trim().to_string());
+            current_id = Some(id);
+        }
+    }
+
+    catalog
+}
+
+fn lookup(id: &str) -> Option<&'static str> {
+    let catalogs = &*CATALOGS;
+
+    catalogs[language() as usize]
+        .get(id)
+        .or_else(|| catalogs[Language::English as usize].get(id))
+        .map(String::as_str)
+}
+
+/// Text of the message `id` in the current language, the id itself if there is no such message.
+#[must_use]
+pub fn tr(id: &'static str) -> &'static str {
+    lookup(id).unwrap_or(id)
+}
+
+/// Like [`tr`], replacing the `{ $name }` arguments of the message.
+#[must_use]
+pub fn tr_args(id: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(tr(id).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{ ${name} }}"), &value.to_string())
+    })
+}
+
+/// Title of a tool window, its message is `tool-` followed by the name in kebab case.
+#[must_use]
+pub fn tool_title(name: &'static str) -> &'static str {
+    let id = format!("tool-{}", name.to_lowercase().replace(' ', "-"));
+
+    lookup(&id).unwrap_or(name)
+}
@@ -0,0 +1,203 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use emu::{
+    debugger::instruction_stats::{InstructionCount, InstructionStats as CpuInstructionStats},
+    gba::Gba,
+};
+
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// The report is rebuilt at most this often, like the profiler one.
+const REPORT_REFRESH: Duration = Duration::from_millis(500);
+
+/// Instructions a game executed by kind and the ones it never did, to attach to bug
+/// reports and to see which parts of the core matter the most.
+pub struct InstructionStats {
+    gba: Arc<Mutex<Gba>>,
+    total: u64,
+    executed: Vec<InstructionCount>,
+    never_executed: Vec<(bool, &'static str)>,
+    last_refresh: Option<Instant>,
+}
+
+impl InstructionStats {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            total: 0,
+            executed: Vec::new(),
+            never_executed: Vec::new(),
+            last_refresh: None,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.gba.lock().unwrap().cpu.instruction_stats.is_some()
+    }
+
+    fn set_recording(&mut self, record: bool) {
+        self.gba.lock().unwrap().cpu.instruction_stats = record.then(CpuInstructionStats::default);
+        self.last_refresh = None;
+    }
+
+    fn reset(&mut self) {
+        if let Some(stats) = &mut self.gba.lock().unwrap().cpu.instruction_stats {
+            stats.clear();
+        }
+        self.last_refresh = None;
+    }
+
+    fn refresh(&mut self) {
+        if self
+            .last_refresh
+            .is_some_and(|last| last.elapsed() < REPORT_REFRESH)
+        {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+
+        let gba = self.gba.lock().unwrap();
+        let Some(stats) = &gba.cpu.instruction_stats else {
+            return;
+        };
+
+        self.total = stats.total();
+        self.executed = stats.executed();
+        self.never_executed = stats.never_executed();
+        drop(gba);
+    }
+
+    fn copy_report(&self, ui: &egui::Ui) {
+        let report = self
+            .gba
+            .lock()
+            .unwrap()
+            .cpu
+            .instruction_stats
+            .as_ref()
+            .map(CpuInstructionStats::report);
+
+        if let Some(report) = report {
+            ui.output_mut(|output| output.copied_text = report);
+        }
+    }
+
+    fn report_ui(&self, ui: &mut egui::Ui) {
+        #[allow(clippy::cast_precision_loss)]
+        let percent =
+            |count: u64| format!("{:.2}%", count as f64 * 100.0 / self.total.max(1) as f64);
+        let set = |is_thumb| if is_thumb { "Thumb" } else { "ARM" };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("Instruction stats")
+                .num_columns(4)
+                .spacing([16.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(tr("instruction-set"));
+                    ui.strong(tr("instruction"));
+                    ui.strong(tr("executed"));
+                    ui.label("");
+                    ui.end_row();
+
+                    for count in &self.executed {
+                        ui.label(set(count.is_thumb));
+                        ui.monospace(count.kind);
+                        ui.label(count.count.to_string());
+                        ui.label(percent(count.count));
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            egui::CollapsingHeader::new(format!(
+                "{} ({})",
+                tr("never-executed"),
+                self.never_executed.len()
+            ))
+            .show(ui, |ui| {
+                for (is_thumb, kind) in &self.never_executed {
+                    ui.monospace(format!("{:<5} {kind}", set(*is_thumb)));
+                }
+            });
+        });
+    }
+}
+
+impl UiTool for InstructionStats {
+    fn name(&self) -> &'static str {
+        "Instruction Stats"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(360.0)
+            .default_height(320.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut is_recording = self.is_recording();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut is_recording, tr("record-instruction-stats"))
+                .on_hover_text(tr("record-instruction-stats-hint"))
+                .changed()
+            {
+                self.set_recording(is_recording);
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("reset")))
+                .clicked()
+            {
+                self.reset();
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("copy-report")))
+                .on_hover_text(tr("copy-report-hint"))
+                .clicked()
+            {
+                self.copy_report(ui);
+            }
+        });
+
+        if !is_recording && self.executed.is_empty() {
+            ui.label(tr("instruction-stats-not-recording"));
+            return;
+        }
+
+        if is_recording {
+            self.refresh();
+        }
+
+        ui.label(format!("{}: {}", tr("executed"), self.total));
+        ui.separator();
+        self.report_ui(ui);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("record-instruction-stats")),
+            Command::new(tr("reset-instruction-stats")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        match index {
+            0 => self.set_recording(!self.is_recording()),
+            _ => self.reset(),
+        }
+
+        true
+    }
+}
@@ -1,10 +1,62 @@
 mod about;
+mod achievements;
 pub mod app;
+mod audio;
+#[cfg(feature = "audio")]
+mod audio_output;
+mod backup_memory;
+mod battery_save;
+mod border;
+mod call_stack;
+mod cheats;
+mod command_palette;
+pub mod config;
+#[cfg(unix)]
+mod control_socket;
+mod coverage;
 mod cpu_handler;
 mod cpu_registers;
+pub mod crash;
+mod debug_output;
+mod dialog;
 #[cfg(feature = "disassembler")]
 mod disassembler;
+mod discord_presence;
+mod frame_pacing;
+#[cfg(feature = "gamepad")]
+mod gamepad_input;
 mod gba_color;
 mod gba_display;
+mod hot_reload;
+pub mod i18n;
+mod instruction_stats;
+mod library;
+mod memory;
+pub mod movie_render;
+mod netplay;
+mod network_rom;
+mod osd;
+mod palette_viewer;
+pub mod paths;
+mod pause_menu;
+mod play_time;
+mod preset;
+mod profiler;
+mod renderer;
+#[cfg(feature = "achievements")]
+mod retro_achievements;
+mod rom_info;
+mod rtc_battery;
+mod rumble;
+#[cfg(feature = "rumble")]
+mod rumble_output;
 mod savegame;
+mod screen_filter;
+mod second_core;
+mod source;
+pub mod test_runner;
+mod theme;
+mod timeline;
 mod ui_traits;
+mod watch;
+mod watchdog;
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use eframe::epaint::textures::TextureOptions;
+use egui::load::SizedTexture;
+use native_dialog::FileDialog;
+use serde::{Deserialize, Serialize};
+
+use emu::cartridge::header::{Header, HEADER_SIZE};
+use logger::log;
+
+use crate::i18n::tr;
+use crate::paths;
+use crate::play_time::{format_last_played, format_play_time, PlayLog, PlayRecord};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+const LIBRARY_FILE_NAME: &str = "library.json";
+
+/// Extensions of the games the emulator opens, see `app::load_gba`.
+pub const ROM_EXTENSIONS: [&str; 2] = ["gba", "mb"];
+
+/// Box art is looked for by file name of the ROM first, by game code after
+/// (eg. `Pokemon Emerald.png` or `BPEE.jpg`).
+const BOX_ART_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+const BOX_ART_SIZE: egui::Vec2 = egui::vec2(128.0, 128.0);
+
+/// Folders below the ROM directories scanned, the games are usually one or two levels down.
+const MAX_SCAN_DEPTH: usize = 4;
+
+/// What the library keeps between sessions, in `library.json` of the data directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct LibraryFile {
+    rom_dirs: Vec<PathBuf>,
+    box_art_dir: Option<PathBuf>,
+}
+
+impl LibraryFile {
+    fn path() -> Option<PathBuf> {
+        paths::data_dir().map(|dir| dir.join(LIBRARY_FILE_NAME))
+    }
+
+    fn load() -> Self {
+        let Some(path) = Self::path().filter(|path| path.is_file()) else {
+            return Self::default();
+        };
+
+        fs::read_to_string(&path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|data| Ok(serde_json::from_str(&data)?))
+            .unwrap_or_else(|e| {
+                log(format!("can't read library {}: {e}", path.display()));
+                Self::default()
+            })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path().ok_or("No data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// A game found in the ROM directories.
+struct Game {
+    path: PathBuf,
+    /// From the cartridge header, the file name if the header has none.
+    title: String,
+    code: String,
+}
+
+impl Game {
+    /// Reads only the header, scanning a big collection stays fast.
+    fn read(path: PathBuf) -> Option<Self> {
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        fs::File::open(&path)
+            .ok()?
+            .take(HEADER_SIZE as u64)
+            .read_to_end(&mut header)
+            .ok()?;
+        let header = Header::new(&header).ok()?;
+
+        let title = if header.game_title.trim().is_empty() {
+            path.file_stem()?.to_string_lossy().into_owned()
+        } else {
+            header.game_title.trim().to_owned()
+        };
+
+        Some(Self {
+            path,
+            title,
+            code: header.game_code.trim().to_owned(),
+        })
+    }
+}
+
+/// The games of the ROM directories with their box art and how much they were played.
+/// Opening one starts a new emulator with it and closes this one.
+pub struct Library {
+    file: LibraryFile,
+    games: Vec<Game>,
+    /// The directories are scanned the first time the window is shown.
+    is_scanned: bool,
+    /// Textures of the box art by game, `None` if it has none.
+    box_art: HashMap<PathBuf, Option<egui::TextureHandle>>,
+    /// How much the games were played, counted by [`crate::play_time::PlayTime`].
+    play_log: Arc<Mutex<PlayLog>>,
+    /// Last error, eg. a game that can't be started.
+    message: Option<String>,
+}
+
+impl Library {
+    pub fn new(play_log: Arc<Mutex<PlayLog>>) -> Self {
+        Self {
+            file: LibraryFile::load(),
+            games: Vec::new(),
+            is_scanned: false,
+            box_art: HashMap::new(),
+            play_log,
+            message: None,
+        }
+    }
+
+    fn scan(&mut self) {
+        let mut paths = Vec::new();
+        for dir in &self.file.rom_dirs {
+            find_roms(dir, MAX_SCAN_DEPTH, &mut paths);
+        }
+        paths.sort();
+        paths.dedup();
+
+        self.games = paths.into_iter().filter_map(Game::read).collect();
+        self.games
+            .sort_by_cached_key(|game| game.title.to_lowercase());
+        self.box_art.clear();
+        self.is_scanned = true;
+    }
+
+    fn launch(ctx: &egui::Context, path: &Path) -> Result<(), Box<dyn Error>> {
+        // The play time of this game is saved when the window closes.
+        // Same executable and working directory, the BIOS is looked for there.
+        std::process::Command::new(std::env::current_exe()?)
+            .arg(path)
+            .spawn()?;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+
+        Ok(())
+    }
+
+    fn add_rom_dir(&mut self) -> Result<(), Box<dyn Error>> {
+        let dir = FileDialog::new()
+            .set_location("~")
+            .show_open_single_dir()?
+            .ok_or_else(|| tr("no-file-selected"))?;
+
+        if !self.file.rom_dirs.contains(&dir) {
+            self.file.rom_dirs.push(dir);
+        }
+        self.file.save()?;
+        self.scan();
+
+        Ok(())
+    }
+
+    fn set_box_art_dir(&mut self) -> Result<(), Box<dyn Error>> {
+        let dir = FileDialog::new()
+            .set_location("~")
+            .show_open_single_dir()?
+            .ok_or_else(|| tr("no-file-selected"))?;
+
+        self.file.box_art_dir = Some(dir);
+        self.file.save()?;
+        self.box_art.clear();
+
+        Ok(())
+    }
+
+    fn box_art(&mut self, ctx: &egui::Context, game: &Game) -> Option<egui::TextureHandle> {
+        let dir = self.file.box_art_dir.as_ref()?;
+
+        self.box_art
+            .entry(game.path.clone())
+            .or_insert_with(|| {
+                let stem = game.path.file_stem()?.to_string_lossy().into_owned();
+                let image = [stem, game.code.clone()]
+                    .iter()
+                    .flat_map(|name| {
+                        BOX_ART_EXTENSIONS.map(|extension| dir.join(format!("{name}.{extension}")))
+                    })
+                    .find(|path| path.is_file())
+                    .and_then(|path| {
+                        load_image(&path)
+                            .map_err(|e| log(format!("can't load {}: {e}", path.display())))
+                            .ok()
+                    })?;
+
+                Some(ctx.load_texture(
+                    format!("box art {}", game.path.display()),
+                    image,
+                    TextureOptions::LINEAR,
+                ))
+            })
+            .clone()
+    }
+
+    fn dirs_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(tr("add-rom-folder")).clicked() {
+                if let Err(e) = self.add_rom_dir() {
+                    self.message = Some(e.to_string());
+                }
+            }
+            if ui.button(tr("box-art-folder")).clicked() {
+                if let Err(e) = self.set_box_art_dir() {
+                    self.message = Some(e.to_string());
+                }
+            }
+            if ui.button(tr("rescan")).clicked() {
+                self.scan();
+            }
+        });
+
+        let mut removed = None;
+        for (index, dir) in self.file.rom_dirs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").clicked() {
+                    removed = Some(index);
+                }
+                ui.monospace(dir.display().to_string());
+            });
+        }
+        if let Some(index) = removed {
+            self.file.rom_dirs.remove(index);
+            if let Err(e) = self.file.save() {
+                log(format!("can't save library: {e}"));
+            }
+            self.scan();
+        }
+    }
+
+    fn grid_ui(&mut self, ui: &mut egui::Ui) {
+        let games = std::mem::take(&mut self.games);
+        let mut launched = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for game in &games {
+                    let box_art = self.box_art(ui.ctx(), game);
+                    let record = self.play_log.lock().unwrap().by_path(&game.path).cloned();
+
+                    let response = ui
+                        .push_id(&game.path, |ui| {
+                            ui.set_width(BOX_ART_SIZE.x);
+                            ui.vertical(|ui| card_ui(ui, game, box_art.as_ref(), record.as_ref()))
+                                .response
+                        })
+                        .inner
+                        .interact(egui::Sense::click())
+                        .on_hover_text(game.path.display().to_string());
+
+                    if response.double_clicked() {
+                        launched = Some(game.path.clone());
+                    }
+                }
+            });
+        });
+
+        self.games = games;
+
+        if let Some(path) = launched {
+            if let Err(e) = Self::launch(ui.ctx(), &path) {
+                self.message = Some(e.to_string());
+            }
+        }
+    }
+}
+
+fn card_ui(
+    ui: &mut egui::Ui,
+    game: &Game,
+    box_art: Option<&egui::TextureHandle>,
+    record: Option<&PlayRecord>,
+) {
+    if let Some(texture) = box_art {
+        ui.image(egui::ImageSource::Texture(SizedTexture {
+            id: texture.id(),
+            size: BOX_ART_SIZE,
+        }));
+    } else {
+        let (rect, _) = ui.allocate_exact_size(BOX_ART_SIZE, egui::Sense::hover());
+        ui.painter()
+            .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+        ui.painter().text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            &game.code,
+            egui::FontId::monospace(16.0),
+            ui.visuals().weak_text_color(),
+        );
+    }
+
+    ui.add(egui::Label::new(egui::RichText::new(&game.title).strong()).truncate());
+
+    let Some(record) = record else {
+        ui.small(tr("never-played"));
+        return;
+    };
+
+    ui.small(format_last_played(record.last_played));
+    ui.small(format_play_time(record.play_time));
+}
+
+/// Adds the games in `dir` and in its subfolders up to `depth` levels down.
+fn find_roms(dir: &Path, depth: usize, roms: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth > 0 {
+                find_roms(&path, depth - 1, roms);
+            }
+        } else if path.extension().is_some_and(|extension| {
+            ROM_EXTENSIONS
+                .iter()
+                .any(|rom_extension| extension.eq_ignore_ascii_case(rom_extension))
+        }) {
+            // The same path as the one of the game being played, to match their records.
+            roms.push(fs::canonicalize(&path).unwrap_or(path));
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn load_image(path: &Path) -> Result<egui::ColorImage, Box<dyn Error>> {
+    let image = image::open(path)?
+        .thumbnail(BOX_ART_SIZE.x as u32, BOX_ART_SIZE.y as u32)
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_flat_samples().as_slice(),
+    ))
+}
+
+impl UiTool for Library {
+    fn name(&self) -> &'static str {
+        "Library"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        if *open && !self.is_scanned {
+            self.scan();
+        }
+
+        tool_window(ctx, self.name())
+            .default_width(640.0)
+            .default_height(480.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.dirs_ui(ui);
+
+        if let Some(message) = &self.message {
+            ui.colored_label(egui::Color32::YELLOW, message);
+        }
+        ui.separator();
+
+        if self.games.is_empty() {
+            ui.label(tr("no-games-found"));
+        } else {
+            ui.small(tr("double-click-to-play"));
+            self.grid_ui(ui);
+        }
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("add-rom-folder")),
+            Command::new(tr("rescan-library")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        match index {
+            0 => {
+                if let Err(e) = self.add_rom_dir() {
+                    self.message = Some(e.to_string());
+                }
+            }
+            _ => self.scan(),
+        }
+
+        true
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+use emu::gba::Gba;
+use native_dialog::FileDialog;
+
+use crate::dialog::show_error;
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Memory regions of the bus with their start and size.
+const REGIONS: [(&str, u32, u32); 7] = [
+    ("BIOS", 0x0000_0000, 0x4000),
+    ("EWRAM", 0x0200_0000, 0x4_0000),
+    ("IWRAM", 0x0300_0000, 0x8000),
+    ("I/O", 0x0400_0000, 0x400),
+    ("Palette", 0x0500_0000, 0x400),
+    ("VRAM", 0x0600_0000, 0x1_8000),
+    ("OAM", 0x0700_0000, 0x400),
+];
+
+/// Dumps a range of memory to a file and loads files back into memory.
+pub struct Memory {
+    gba: Arc<Mutex<Gba>>,
+    address: String,
+    length: String,
+}
+
+impl Memory {
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            address: "02000000".to_string(),
+            length: "40000".to_string(),
+        }
+    }
+
+    /// Address and length typed by the user, the address can also be a symbol name.
+    fn parse_range(&self, address: &str, length: &str) -> Option<(u32, u32)> {
+        let address = self
+            .gba
+            .lock()
+            .unwrap()
+            .cpu
+            .symbols
+            .parse_address(address)?;
+        let length = length.trim();
+        let hex = length
+            .strip_prefix("0x")
+            .or_else(|| length.strip_prefix("0X"))
+            .unwrap_or(length);
+
+        Some((address, u32::from_str_radix(hex, 16).ok()?))
+    }
+
+    fn dump(&self, address: u32, length: u32) -> Result<(), Box<dyn Error>> {
+        let data = self.gba.lock().unwrap().dump_memory(address, length)?;
+
+        let path = FileDialog::new()
+            .set_location("~")
+            .set_filename(&format!("{address:08X}.bin"))
+            .add_filter(tr("binary-file"), &["bin"])
+            .show_save_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        std::fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    /// Loads a whole file at `address`.
+    fn load(&self, address: u32) -> Result<(), Box<dyn Error>> {
+        let path = FileDialog::new()
+            .set_location("~")
+            .add_filter(tr("binary-file"), &["bin"])
+            .show_open_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        let data = std::fs::read(path)?;
+        self.gba.lock().unwrap().load_memory(address, &data)?;
+
+        Ok(())
+    }
+
+    fn buttons_ui(&self, ui: &mut egui::Ui) {
+        let range = self.parse_range(&self.address, &self.length);
+        // Files are loaded whole, the length is not needed.
+        let address = self
+            .parse_range(&self.address, "0")
+            .map(|(address, _)| address);
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(range.is_some(), egui::Button::new(tr("dump-memory")))
+                .on_hover_text(tr("dump-memory-hint"))
+                .clicked()
+            {
+                if let Some(Err(err)) = range.map(|(address, length)| self.dump(address, length)) {
+                    show_error(err.as_ref());
+                }
+            }
+
+            if ui
+                .add_enabled(address.is_some(), egui::Button::new(tr("load-memory")))
+                .on_hover_text(tr("load-memory-hint"))
+                .clicked()
+            {
+                if let Some(Err(err)) = address.map(|address| self.load(address)) {
+                    show_error(err.as_ref());
+                }
+            }
+        });
+    }
+}
+
+/// Splits the `ADDRESS LENGTH` argument of the dump command.
+fn split_argument(argument: &str) -> (&str, &str) {
+    let mut parts = argument.split_whitespace();
+
+    (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    )
+}
+
+impl UiTool for Memory {
+    fn name(&self) -> &'static str {
+        "Memory"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(300.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            for (name, start, size) in REGIONS {
+                if ui
+                    .button(name)
+                    .on_hover_text(format!("0x{start:08X}-0x{:08X}", start + size - 1))
+                    .clicked()
+                {
+                    self.address = format!("{start:08X}");
+                    self.length = format!("{size:X}");
+                }
+            }
+        });
+        ui.separator();
+
+        egui::Grid::new("Memory range")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr("address"))
+                    .on_hover_text(tr("breakpoint-address-hint"));
+                ui.text_edit_singleline(&mut self.address);
+                ui.end_row();
+
+                ui.label(tr("length-hex"));
+                ui.text_edit_singleline(&mut self.length);
+                ui.end_row();
+            });
+
+        self.buttons_ui(ui);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::with_argument(tr("dump-memory"), tr("dump-memory-argument-hint")),
+            Command::with_argument(tr("load-memory"), tr("breakpoint-address-hint")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, argument: &str) -> bool {
+        let result = if index == 0 {
+            let (address, length) = split_argument(argument);
+            let Some((address, length)) = self.parse_range(address, length) else {
+                return false;
+            };
+
+            self.dump(address, length)
+        } else {
+            let Some((address, _)) = self.parse_range(argument, "0") else {
+                return false;
+            };
+
+            self.load(address)
+        };
+
+        if let Err(err) = result {
+            show_error(err.as_ref());
+        }
+
+        true
+    }
+}
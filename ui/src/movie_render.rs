@@ -0,0 +1,154 @@
+//! Renders the run of a movie to a video file, eg. to publish a TAS or attach to a bug.
+//!
+//! There's no window, the frames are made as fast as the host can emulate them. The
+//! encoding is done by `ffmpeg`, it must be in the `PATH`.
+
+use std::error::Error;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use emu::{
+    gba::Gba,
+    movie::Movie,
+    render::{LCD_HEIGHT, LCD_WIDTH},
+};
+
+use crate::gba_display::screen_rgb;
+
+/// Frames per second of the LCD, 16.78 MHz over 280896 cycles.
+const FRAME_RATE: &str = "16777216/280896";
+
+/// Plays `movie` from power on and encodes the frames and the sound to `output`, the
+/// format follows the extension (eg. `.mp4`, `.mkv`).
+///
+/// # Errors
+/// It returns an error if `ffmpeg` can't be started or fails.
+pub fn render(gba: &mut Gba, movie: &Movie, output: &Path) -> Result<(), Box<dyn Error>> {
+    // The sound is only complete at the end, the video is encoded while the game runs
+    // and both are put together after.
+    let extension = output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("mkv");
+    let video = temporary_path(output, extension)?;
+    let audio = temporary_path(output, "wav")?;
+
+    let result = render_video(gba, movie, &video)
+        .and_then(|samples| write_wav(&audio, &samples, gba.audio_settings().sample_rate))
+        .and_then(|()| mux(&video, &audio, output));
+
+    // The temporary files may be missing if the render stopped before them.
+    let _ = fs::remove_file(&video);
+    let _ = fs::remove_file(&audio);
+
+    result
+}
+
+/// A hidden file next to `output`, the video has its extension so the codec fits the
+/// container.
+fn temporary_path(output: &Path, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let name = output
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("{} is not a file name", output.display()))?;
+
+    Ok(output.with_file_name(format!(".{name}.render.{extension}")))
+}
+
+/// Encodes the frames of the run to `path`, it returns the audio samples.
+fn render_video(
+    gba: &mut Gba,
+    movie: &Movie,
+    path: &Path,
+) -> Result<Vec<[i16; 2]>, Box<dyn Error>> {
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pixel_format",
+            "rgb24",
+        ])
+        .args(["-video_size", &format!("{LCD_WIDTH}x{LCD_HEIGHT}")])
+        .args(["-framerate", FRAME_RATE, "-i", "-"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("can't start ffmpeg: {e}"))?;
+
+    let mut samples = Vec::new();
+    let written = {
+        let mut frames = BufWriter::new(ffmpeg.stdin.take().ok_or("ffmpeg has no input")?);
+        movie
+            .inputs()
+            .iter()
+            .try_for_each(|&keys| {
+                gba.set_key_input(keys);
+                gba.run_frame();
+                samples.extend(gba.take_audio_samples());
+                frames.write_all(&screen_rgb(&gba.cpu.bus.lcd.buffer))
+            })
+            .and_then(|()| frames.flush())
+    };
+
+    // The input is closed, ffmpeg finishes the file.
+    let status = ffmpeg.wait()?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed to encode the video ({status})").into());
+    }
+    written?;
+
+    Ok(samples)
+}
+
+/// 16 bits stereo PCM.
+fn write_wav(path: &Path, samples: &[[i16; 2]], sample_rate: u32) -> Result<(), Box<dyn Error>> {
+    let data_size = u32::try_from(samples.len() * 4).map_err(|_| "The run is too long")?;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 4);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    // PCM, 2 channels
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 4).to_le_bytes());
+    // 4 bytes a sample, 16 bits a channel
+    wav.extend_from_slice(&4_u16.to_le_bytes());
+    wav.extend_from_slice(&16_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for [left, right] in samples {
+        wav.extend_from_slice(&left.to_le_bytes());
+        wav.extend_from_slice(&right.to_le_bytes());
+    }
+
+    fs::write(path, wav)?;
+
+    Ok(())
+}
+
+/// Puts the video and the sound in `output`, the video is not encoded again.
+fn mux(video: &Path, audio: &Path, output: &Path) -> Result<(), Box<dyn Error>> {
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(video)
+        .arg("-i")
+        .arg(audio)
+        .args(["-map", "0:v", "-map", "1:a", "-c:v", "copy"])
+        .arg(output)
+        .status()
+        .map_err(|e| format!("can't start ffmpeg: {e}"))?;
+
+    if !status.success() {
+        return Err(format!("ffmpeg failed to add the sound ({status})").into());
+    }
+
+    Ok(())
+}
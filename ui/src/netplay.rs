@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use emu::cartridge::hash::RomHash;
+use emu::cpu::hardware::keypad::{Key, NO_KEYS_PRESSED};
+use emu::gba::Gba;
+use emu::netplay::{Message, Session, SyncMode};
+
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, UiTool};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7845";
+
+/// Rollbacks deserialize the whole machine, it doesn't fit in the default thread stack.
+const SESSION_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+type SessionResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+#[derive(Clone)]
+enum Status {
+    Idle,
+    Waiting(String),
+    Connected {
+        mode: SyncMode,
+        frame: u32,
+        rollback_frames: u64,
+    },
+    Error(String),
+}
+
+pub struct Netplay {
+    gba: Arc<Mutex<Gba>>,
+    address: String,
+    rollback: bool,
+    frames: u32,
+    /// Local keys, read by the session thread every frame.
+    keys: Arc<AtomicU16>,
+    status: Arc<Mutex<Status>>,
+    running: Arc<AtomicBool>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Netplay {
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            address: DEFAULT_ADDRESS.to_string(),
+            rollback: false,
+            frames: 2,
+            keys: Arc::new(AtomicU16::new(NO_KEYS_PRESSED)),
+            status: Arc::new(Mutex::new(Status::Idle)),
+            running: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+        }
+    }
+
+    const fn mode(&self) -> SyncMode {
+        if self.rollback {
+            SyncMode::Rollback {
+                max_frames: self.frames,
+            }
+        } else {
+            SyncMode::InputDelay {
+                frames: self.frames,
+            }
+        }
+    }
+
+    fn start(&mut self, is_host: bool) {
+        self.running.store(true, Ordering::Relaxed);
+
+        let address = self.address.clone();
+        let mode = self.mode();
+        let gba = Arc::clone(&self.gba);
+        let keys = Arc::clone(&self.keys);
+        let status = Arc::clone(&self.status);
+        let running = Arc::clone(&self.running);
+
+        let handle = thread::Builder::new()
+            .stack_size(SESSION_STACK_SIZE)
+            .spawn(move || {
+                let result = run_session(&address, is_host, mode, &gba, &keys, &status, &running);
+
+                *status.lock().unwrap() = match result {
+                    Ok(()) => Status::Idle,
+                    Err(err) => Status::Error(err.to_string()),
+                };
+                running.store(false, Ordering::Relaxed);
+            });
+
+        match handle {
+            Ok(handle) => self.thread_handle = Some(handle),
+            Err(err) => {
+                *self.status.lock().unwrap() = Status::Error(err.to_string());
+                self.running.store(false, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        if let Some(handle) = self.thread_handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Default key bindings: arrows, X (A), Z (B), Enter (Start), Backspace (Select), A (L), S (R).
+pub fn read_keys(input: &egui::InputState) -> u16 {
+    let mut keys = NO_KEYS_PRESSED;
+
+    for key in Key::ALL {
+        let binding = match key {
+            Key::A => egui::Key::X,
+            Key::B => egui::Key::Z,
+            Key::Select => egui::Key::Backspace,
+            Key::Start => egui::Key::Enter,
+            Key::Right => egui::Key::ArrowRight,
+            Key::Left => egui::Key::ArrowLeft,
+            Key::Up => egui::Key::ArrowUp,
+            Key::Down => egui::Key::ArrowDown,
+            Key::R => egui::Key::S,
+            Key::L => egui::Key::A,
+        };
+
+        if input.key_down(binding) {
+            keys &= !(1 << key.bit());
+        }
+    }
+
+    keys
+}
+
+fn connect(
+    address: &str,
+    is_host: bool,
+    status: &Mutex<Status>,
+    running: &AtomicBool,
+) -> Result<TcpStream, Box<dyn Error + Send + Sync>> {
+    if !is_host {
+        *status.lock().unwrap() =
+            Status::Waiting(tr_args("connecting-to", &[("address", &address)]));
+        return Ok(TcpStream::connect(address)?);
+    }
+
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    *status.lock().unwrap() =
+        Status::Waiting(tr_args("waiting-for-player", &[("address", &address)]));
+
+    while running.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Err(tr("cancelled").into())
+}
+
+fn run_session(
+    address: &str,
+    is_host: bool,
+    mode: SyncMode,
+    gba: &Mutex<Gba>,
+    keys: &AtomicU16,
+    status: &Mutex<Status>,
+    running: &AtomicBool,
+) -> SessionResult {
+    let mut stream = connect(address, is_host, status, running)?;
+    stream.set_nodelay(true)?;
+
+    let rom_crc32 = RomHash::new(&gba.lock().unwrap().cpu.bus.internal_memory.rom).crc32;
+    Message::Hello { rom_crc32, mode }.write_to(&mut stream)?;
+
+    let Message::Hello {
+        rom_crc32: remote_crc32,
+        mode: remote_mode,
+    } = Message::read_from(&mut stream)?
+    else {
+        return Err(tr("unexpected-message").into());
+    };
+
+    if remote_crc32 != rom_crc32 {
+        return Err(tr("different-rom").into());
+    }
+
+    // The client plays with the settings of the host.
+    let mode = if is_host { mode } else { remote_mode };
+    let mut session = Session::new(mode, usize::from(!is_host));
+
+    // Both cores have to start from the same state.
+    gba.lock().unwrap().reset()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut reader = stream.try_clone()?;
+    thread::spawn(move || loop {
+        let message = Message::read_from(&mut reader);
+        let is_err = message.is_err();
+
+        if sender.send(message).is_err() || is_err {
+            return;
+        }
+    });
+
+    while running.load(Ordering::Relaxed) {
+        if let Some(message) = session.poll_local_input(keys.load(Ordering::Relaxed)) {
+            message.write_to(&mut stream)?;
+        }
+
+        for message in receiver.try_iter() {
+            if let Message::Input { frame, keys } = message? {
+                session.add_remote_input(frame, keys);
+            }
+        }
+
+        let advanced = session.advance(&mut *gba.lock().unwrap())?;
+        if !advanced {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        *status.lock().unwrap() = Status::Connected {
+            mode,
+            frame: session.frame(),
+            rollback_frames: session.rollback_frames,
+        };
+    }
+
+    stream.shutdown(std::net::Shutdown::Both)?;
+
+    Ok(())
+}
+
+impl UiTool for Netplay {
+    fn name(&self) -> &'static str {
+        "Netplay"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(320.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.keys.store(ui.input(read_keys), Ordering::Relaxed);
+
+        let is_running = self.running.load(Ordering::Relaxed);
+
+        ui.add_enabled_ui(!is_running, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("address"));
+                ui.text_edit_singleline(&mut self.address);
+            });
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.rollback, false, tr("input-delay"));
+                ui.radio_value(&mut self.rollback, true, tr("rollback"));
+            });
+
+            let frames_label = if self.rollback {
+                tr("max-rollback-frames")
+            } else {
+                tr("delay-frames")
+            };
+            ui.add(egui::Slider::new(&mut self.frames, 1..=10).text(frames_label));
+        });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new(tr("host")))
+                .clicked()
+            {
+                self.start(true);
+            }
+
+            if ui
+                .add_enabled(!is_running, egui::Button::new(tr("connect")))
+                .clicked()
+            {
+                self.start(false);
+            }
+
+            if ui
+                .add_enabled(is_running, egui::Button::new(tr("disconnect")))
+                .clicked()
+            {
+                self.stop();
+            }
+        });
+
+        ui.add_space(8.0);
+        let status = self.status.lock().unwrap().clone();
+        match status {
+            Status::Idle => {
+                ui.label(tr("not-connected"));
+            }
+            Status::Waiting(text) => {
+                ui.label(text);
+            }
+            Status::Connected {
+                mode,
+                frame,
+                rollback_frames,
+            } => {
+                ui.label(tr_args("connected", &[("mode", &mode)]));
+                ui.label(tr_args(
+                    "netplay-frame",
+                    &[("frame", &frame), ("rollback", &rollback_frames)],
+                ));
+            }
+            Status::Error(text) => {
+                ui.colored_label(egui::Color32::RED, text);
+            }
+        }
+
+        ui.small(tr("netplay-hint"));
+    }
+}
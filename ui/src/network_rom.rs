@@ -0,0 +1,115 @@
+//! ROMs downloaded over HTTP(S) in place of a file, eg. `clementine https://host/game.gba`
+//! for test runners. A hash of the ROM can be checked by adding it to the URL as
+//! `#sha1=<digest>` (or `#crc32=`, `#md5=`), the fragment is not sent to the server.
+
+use std::error::Error;
+use std::path::Path;
+
+#[cfg(feature = "network")]
+use std::io::Read;
+#[cfg(feature = "network")]
+use std::time::Duration;
+
+use emu::cartridge::hash::RomHash;
+
+/// The biggest cartridge ROM, 32 `MBytes`. A bigger download is stopped.
+#[cfg(feature = "network")]
+const MAX_ROM_SIZE: u64 = 0x0200_0000;
+
+#[cfg(feature = "network")]
+const TIMEOUT: Duration = Duration::from_mins(2);
+
+/// Whether the cartridge is a URL to download, not a file.
+pub fn is_url(cartridge_name: &str) -> bool {
+    cartridge_name.starts_with("http://") || cartridge_name.starts_with("https://")
+}
+
+/// The name of the files kept for the cartridge (eg. the `.sav`), for a URL the name of the
+/// file downloaded in the working directory.
+pub fn local_name(cartridge_name: &str) -> &str {
+    if !is_url(cartridge_name) {
+        return cartridge_name;
+    }
+
+    let path = cartridge_name
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(cartridge_name);
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("download.gba")
+}
+
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+fn split_fragment(url: &str) -> (&str, Option<&str>) {
+    url.split_once('#')
+        .map_or((url, None), |(url, fragment)| (url, Some(fragment)))
+}
+
+/// Compares the ROM with the hash in the fragment of the URL (eg. `sha1=<digest>`).
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+fn check_hash(rom: &[u8], fragment: &str) -> Result<(), Box<dyn Error>> {
+    let (kind, expected) = fragment
+        .split_once('=')
+        .ok_or_else(|| format!("The hash `{fragment}` must be `sha1=`, `crc32=` or `md5=`"))?;
+    let hash = RomHash::new(rom);
+    let actual = match kind {
+        "sha1" => hash.sha1,
+        "md5" => hash.md5,
+        "crc32" => format!("{:08x}", hash.crc32),
+        _ => return Err(format!("Unknown hash `{kind}`, use sha1, crc32 or md5").into()),
+    };
+
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!("The {kind} of the ROM is {actual}, expected {expected}").into());
+    }
+
+    Ok(())
+}
+
+/// Downloads the ROM at `url` in memory and checks its hash if the URL has one.
+///
+/// # Errors
+/// It returns an error if the download fails, it's bigger than a cartridge or the hash
+/// doesn't match.
+#[cfg(feature = "network")]
+pub fn download(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (url, fragment) = split_fragment(url);
+    let response = ureq::AgentBuilder::new()
+        .user_agent(concat!("clementine/", env!("CARGO_PKG_VERSION")))
+        .timeout(TIMEOUT)
+        .build()
+        .get(url)
+        .call()?;
+
+    let length = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<u64>().ok());
+    if length.is_some_and(|length| length > MAX_ROM_SIZE) {
+        return Err(format!("The ROM at {url} is bigger than a cartridge").into());
+    }
+
+    // The length is not always sent, the limit is checked on the data too.
+    let mut rom = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_ROM_SIZE + 1)
+        .read_to_end(&mut rom)?;
+    if rom.len() as u64 > MAX_ROM_SIZE {
+        return Err(format!("The ROM at {url} is bigger than a cartridge").into());
+    }
+
+    if let Some(fragment) = fragment {
+        check_hash(&rom, fragment)?;
+    }
+
+    Ok(rom)
+}
+
+/// # Errors
+/// It always returns an error, the network code is not built.
+#[cfg(not(feature = "network"))]
+pub fn download(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err(format!("Can't download {url}, Clementine was built without the network feature").into())
+}
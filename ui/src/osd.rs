@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+/// How long a message stays on screen.
+const MESSAGE_DURATION: Duration = Duration::from_secs(2);
+
+/// On-screen display, short messages shown on top of everything (eg. "Muted").
+#[derive(Default)]
+pub struct Osd {
+    message: Option<(String, Instant)>,
+}
+
+impl Osd {
+    /// Replaces the current message, if any.
+    pub fn show_message(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let Some((message, shown_at)) = &self.message else {
+            return;
+        };
+
+        if shown_at.elapsed() > MESSAGE_DURATION {
+            self.message = None;
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("OSD"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 16.0])
+            .interactable(false)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.heading(message.as_str());
+                });
+            });
+    }
+}
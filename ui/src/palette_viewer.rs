@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+
+use egui::Color32;
+use emu::{
+    gba::Gba,
+    render::color::{Color, PaletteType},
+};
+
+use crate::gba_color::GbaColor;
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, UiTool};
+
+/// Side of a color swatch, in points.
+const SWATCH_SIZE: f32 = 14.0;
+
+/// BG and OBJ palettes, a color can be picked and edited while the game runs.
+pub struct PaletteViewer {
+    gba: Arc<Mutex<Gba>>,
+    selected: Option<(PaletteType, usize)>,
+}
+
+impl PaletteViewer {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            selected: None,
+        }
+    }
+
+    fn palette_ui(&mut self, ui: &mut egui::Ui, palette: &PaletteType, colors: &[Color]) {
+        egui::Grid::new(ui.id().with(palette == &PaletteType::BG))
+            .spacing([1.0, 1.0])
+            .show(ui, |ui| {
+                for (index, color) in colors.iter().enumerate() {
+                    let is_selected = self.selected == Some((palette.clone(), index));
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(SWATCH_SIZE, SWATCH_SIZE),
+                        egui::Sense::click(),
+                    );
+
+                    ui.painter()
+                        .rect_filled(rect, 0.0, Color32::from(GbaColor(*color)));
+                    if is_selected {
+                        ui.painter().rect_stroke(
+                            rect,
+                            0.0,
+                            egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                        );
+                    }
+
+                    if response
+                        .on_hover_text(format!("{index} (0x{:04X})", color.0))
+                        .clicked()
+                    {
+                        self.selected = Some((palette.clone(), index));
+                    }
+
+                    if index % 16 == 15 {
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    /// Editor of the selected color, every change is written at once.
+    fn editor_ui(&self, ui: &mut egui::Ui, palette: &PaletteType, index: usize) {
+        let mut gba = self.gba.lock().unwrap();
+        let color = gba.palette_color(palette, index);
+
+        let palette_name = match palette {
+            PaletteType::BG => "BG",
+            PaletteType::OBJ => "OBJ",
+        };
+        let title = tr_args(
+            "palette-entry",
+            &[("palette", &palette_name), ("index", &index)],
+        );
+        if *palette == PaletteType::BG && index == 0 {
+            ui.strong(format!("{title} ({})", tr("backdrop")));
+        } else {
+            ui.strong(title);
+        }
+
+        let mut channels = [color.red(), color.green(), color.blue()];
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            let mut picked = Color32::from(GbaColor(color));
+            changed |= ui.color_edit_button_srgba(&mut picked).changed();
+            if changed {
+                let GbaColor(picked) = GbaColor::from(picked);
+                channels = [picked.red(), picked.green(), picked.blue()];
+            }
+
+            ui.monospace(format!("0x{:04X}", color.0));
+        });
+
+        for (channel, name) in channels.iter_mut().zip(["R", "G", "B"]) {
+            changed |= ui
+                .add(egui::Slider::new(channel, 0..=31).text(name))
+                .changed();
+        }
+
+        if changed {
+            let [red, green, blue] = channels;
+            gba.set_palette_color(palette, index, Color::from_rgb(red, green, blue));
+        }
+    }
+}
+
+impl UiTool for PaletteViewer {
+    fn name(&self) -> &'static str {
+        "Palette Viewer"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .resizable(false)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let gba = self.gba.lock().unwrap();
+        let [bg, obj] = [PaletteType::BG, PaletteType::OBJ].map(|palette| {
+            (0..256)
+                .map(|index| gba.palette_color(&palette, index))
+                .collect::<Vec<_>>()
+        });
+        drop(gba);
+
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.label("BG");
+                self.palette_ui(ui, &PaletteType::BG, &bg);
+            });
+            ui.vertical(|ui| {
+                ui.label("OBJ");
+                self.palette_ui(ui, &PaletteType::OBJ, &obj);
+            });
+        });
+        ui.separator();
+
+        match self.selected.clone() {
+            Some((palette, index)) => self.editor_ui(ui, &palette, index),
+            None => {
+                ui.label(tr("palette-select-hint"));
+            }
+        }
+    }
+}
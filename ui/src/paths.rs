@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+
+use directories::{BaseDirs, ProjectDirs};
+use logger::log;
+
+/// A file with this name next to the executable turns the portable mode on, like `--portable`.
+const PORTABLE_MARKER: &str = "portable.txt";
+
+/// Directory next to the executable with the files of the portable mode.
+const PORTABLE_DIR_NAME: &str = "clementine-data";
+
+pub const BIOS_FILE_NAME: &str = "gba_bios.bin";
+
+/// Written in the data directory once the files of the older versions have been moved.
+const MIGRATION_MARKER: &str = ".migrated";
+
+/// Files and directories kept in the data directory, everything but the config.
+const DATA_FILES: [&str; 6] = [
+    "states",
+    "rtc",
+    "crashes",
+    "play_time.json",
+    "library.json",
+    "achievements.json",
+];
+
+static IS_PORTABLE: AtomicBool = AtomicBool::new(false);
+
+/// Resolved the first time they are needed, after the portable mode is set.
+static DIRS: LazyLock<Option<Dirs>> = LazyLock::new(|| {
+    if let Some(dir) = portable_dir() {
+        return Some(Dirs {
+            config: dir.clone(),
+            cache: dir.join("cache"),
+            data: dir,
+        });
+    }
+
+    ProjectDirs::from("io.github", "RIP-Comm", "clementine").map(|dirs| Dirs {
+        config: dirs.config_dir().to_path_buf(),
+        data: dirs.data_dir().to_path_buf(),
+        cache: dirs.cache_dir().to_path_buf(),
+    })
+});
+
+/// Where the files of the frontend are kept, the directories of the platform (XDG on
+/// Linux, `Application Support` on macOS, `AppData` on Windows).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    /// The config of the application.
+    pub config: PathBuf,
+    /// What the user would miss: states, clocks of the cartridges, play time, library...
+    pub data: PathBuf,
+    /// What can be made again, it can be deleted.
+    pub cache: PathBuf,
+}
+
+/// `None` if the platform has no home directory.
+pub fn dirs() -> Option<&'static Dirs> {
+    DIRS.as_ref()
+}
+
+/// Directory of the data, `None` if the platform has no home directory.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    dirs().map(|dirs| dirs.data.clone())
+}
+
+/// Keeps the files next to the executable instead of the directories of the platform,
+/// eg. to run it from a USB stick. It must be set before the directories are used.
+pub fn set_portable(is_portable: bool) {
+    IS_PORTABLE.store(is_portable, Ordering::Relaxed);
+}
+
+/// Where the files are kept in portable mode, `None` if it's off.
+pub(crate) fn portable_dir() -> Option<PathBuf> {
+    let executable = std::env::current_exe().ok()?;
+    let dir = executable.parent()?;
+
+    (IS_PORTABLE.load(Ordering::Relaxed) || dir.join(PORTABLE_MARKER).is_file())
+        .then(|| dir.join(PORTABLE_DIR_NAME))
+}
+
+/// Moves the files of the older versions to the directories of the platform, once.
+///
+/// They were all kept in `<config dir>/clementine`, and the BIOS of the working directory
+/// is copied to the data directory so it's found from anywhere. Nothing is overwritten.
+pub fn migrate() {
+    let Some(dirs) = dirs() else {
+        return;
+    };
+    if portable_dir().is_some() || dirs.data.join(MIGRATION_MARKER).exists() {
+        return;
+    }
+
+    let old_dir = BaseDirs::new().map(|base| base.config_dir().join("clementine"));
+    let result = std::env::current_dir()
+        .map_err(Into::into)
+        .and_then(|dir| migrate_to(dirs, old_dir.as_deref(), &dir.join(BIOS_FILE_NAME)));
+    if let Err(e) = result {
+        log(format!("can't move the files of the older versions: {e}"));
+    }
+}
+
+/// Moves the files of `old_dir` to `dirs` and copies the BIOS at `bios`.
+fn migrate_to(dirs: &Dirs, old_dir: Option<&Path>, bios: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(&dirs.data)?;
+
+    if let Some(old_dir) = old_dir {
+        move_file(old_dir, &dirs.config, "config.json")?;
+        for name in DATA_FILES {
+            move_file(old_dir, &dirs.data, name)?;
+        }
+    }
+
+    let target = dirs.data.join(BIOS_FILE_NAME);
+    if bios.is_file() && !target.exists() {
+        fs::copy(bios, target)?;
+    }
+
+    fs::write(dirs.data.join(MIGRATION_MARKER), "")?;
+
+    Ok(())
+}
+
+/// Moves `name` from `from` to `to` if it's only in `from`.
+fn move_file(from: &Path, to: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+    let (source, target) = (from.join(name), to.join(name));
+    if source == target || !source.exists() || target.exists() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(to)?;
+    fs::rename(&source, &target)?;
+    log(format!(
+        "moved {} to {}",
+        source.display(),
+        target.display()
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// An empty directory for the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clementine-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrate_moves_the_old_files() {
+        let root = test_dir("migrate");
+        let old_dir = root.join("old");
+        let dirs = Dirs {
+            config: root.join("config"),
+            data: root.join("data"),
+            cache: root.join("cache"),
+        };
+        fs::create_dir_all(old_dir.join("states")).unwrap();
+        fs::write(old_dir.join("states").join("game.state"), "state").unwrap();
+        fs::write(old_dir.join("config.json"), "old config").unwrap();
+        fs::write(old_dir.join("play_time.json"), "old play time").unwrap();
+        fs::write(root.join(BIOS_FILE_NAME), "bios").unwrap();
+        // Already there, it's kept.
+        fs::create_dir_all(&dirs.data).unwrap();
+        fs::write(dirs.data.join("play_time.json"), "play time").unwrap();
+
+        migrate_to(&dirs, Some(&old_dir), &root.join(BIOS_FILE_NAME)).unwrap();
+
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(read(dirs.config.join("config.json")), "old config");
+        assert_eq!(read(dirs.data.join("states").join("game.state")), "state");
+        assert_eq!(read(dirs.data.join("play_time.json")), "play time");
+        assert_eq!(read(old_dir.join("play_time.json")), "old play time");
+        assert_eq!(read(dirs.data.join(BIOS_FILE_NAME)), "bios");
+        assert!(root.join(BIOS_FILE_NAME).is_file());
+        assert!(!old_dir.join("config.json").exists());
+        assert!(dirs.data.join(MIGRATION_MARKER).is_file());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn move_file_into_itself() {
+        let dir = test_dir("move-file");
+        fs::write(dir.join("library.json"), "library").unwrap();
+
+        move_file(&dir, &dir, "library.json").unwrap();
+        move_file(&dir, &dir.join("data"), "missing.json").unwrap();
+
+        assert!(dir.join("library.json").is_file());
+        assert!(!dir.join("data").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use std::sync::Mutex;
+
+use emu::gba::Gba;
+
+use crate::i18n::{tr, tr_args};
+use crate::savegame::{self, SLOTS};
+
+/// An entry of the menu, from top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Resume,
+    Reset,
+    SaveSlot(usize),
+    LoadSlot(usize),
+    Quit,
+}
+
+impl Entry {
+    fn all() -> Vec<Self> {
+        let slots = (1..=SLOTS).flat_map(|slot| [Self::SaveSlot(slot), Self::LoadSlot(slot)]);
+
+        [Self::Resume, Self::Reset]
+            .into_iter()
+            .chain(slots)
+            .chain([Self::Quit])
+            .collect()
+    }
+
+    fn title(self) -> String {
+        match self {
+            Self::Resume => tr("resume").to_string(),
+            Self::Reset => tr("reset").to_string(),
+            Self::SaveSlot(slot) => tr_args("save-slot", &[("slot", &slot)]),
+            Self::LoadSlot(slot) => tr_args("load-slot", &[("slot", &slot)]),
+            Self::Quit => tr("quit").to_string(),
+        }
+    }
+}
+
+/// Keys pressed in the last frame that move around the menu, the gamepads press them
+/// too (see [`crate::gamepad_input`]).
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+struct MenuInput {
+    /// Esc on the keyboard, Start on a gamepad.
+    toggle: bool,
+    /// The up arrow or Shift+Tab.
+    up: bool,
+    /// The down arrow or Tab.
+    down: bool,
+    /// Enter or the A key (X) on the keyboard, the bottom face button on a gamepad.
+    confirm: bool,
+    /// The B key (Z) on the keyboard, the right face button on a gamepad.
+    back: bool,
+}
+
+impl MenuInput {
+    /// The keys are left to the focused widget (eg. the command palette).
+    fn from_keyboard(ctx: &egui::Context, is_open: bool) -> Self {
+        if ctx.wants_keyboard_input() {
+            return Self::default();
+        }
+
+        ctx.input_mut(|input| {
+            let toggle = input.consume_key(egui::Modifiers::NONE, egui::Key::Escape);
+            if !is_open {
+                return Self {
+                    toggle,
+                    ..Self::default()
+                };
+            }
+
+            let previous = input.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab);
+            let mut consume = |key| input.consume_key(egui::Modifiers::NONE, key);
+
+            Self {
+                toggle,
+                up: consume(egui::Key::ArrowUp) | previous,
+                down: consume(egui::Key::ArrowDown) | consume(egui::Key::Tab),
+                confirm: consume(egui::Key::Enter) | consume(egui::Key::X),
+                back: consume(egui::Key::Z),
+            }
+        })
+    }
+}
+
+/// What the owner of the emulation thread has to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    /// The menu was opened, the game stops.
+    Opened,
+    /// The menu was closed, the game goes on.
+    Closed,
+}
+
+/// In-game menu drawn over everything, with the actions needed to play from the couch.
+/// It's driven with the keys of the game, or a gamepad with the `gamepad` feature. The
+/// highlighted entry holds the keyboard focus, which can't leave the menu while it's open.
+pub struct PauseMenu {
+    is_open: bool,
+    /// Position of the highlighted entry in [`Entry::all`].
+    selected: usize,
+    /// Screens of the save slots, loaded when the menu opens, `None` for empty slots.
+    thumbnails: Vec<Option<egui::TextureHandle>>,
+    /// Last message, eg. a slot that can't be loaded.
+    message: Option<String>,
+}
+
+impl PauseMenu {
+    pub const fn new() -> Self {
+        Self {
+            is_open: false,
+            selected: 0,
+            thumbnails: Vec::new(),
+            message: None,
+        }
+    }
+
+    /// Shows the menu if it's open, it returns when it's opened or closed.
+    pub fn show(&mut self, ctx: &egui::Context, gba: &Mutex<Gba>) -> Option<MenuEvent> {
+        let input = MenuInput::from_keyboard(ctx, self.is_open);
+
+        if !self.is_open {
+            if !input.toggle {
+                return None;
+            }
+
+            self.is_open = true;
+            self.selected = 0;
+            self.message = None;
+            self.load_thumbnails(ctx, gba);
+            return Some(MenuEvent::Opened);
+        }
+
+        if input.toggle || input.back {
+            return Some(self.close());
+        }
+
+        let entries = Entry::all();
+        if input.down {
+            self.selected = (self.selected + 1) % entries.len();
+        }
+        if input.up {
+            self.selected = self.selected.checked_sub(1).unwrap_or(entries.len() - 1);
+        }
+
+        let chosen = self
+            .ui(ctx, &entries)
+            .or_else(|| input.confirm.then(|| entries[self.selected]));
+
+        chosen.and_then(|entry| self.run(ctx, gba, entry))
+    }
+
+    fn close(&mut self) -> MenuEvent {
+        self.is_open = false;
+        self.thumbnails.clear();
+
+        MenuEvent::Closed
+    }
+
+    fn run(&mut self, ctx: &egui::Context, gba: &Mutex<Gba>, entry: Entry) -> Option<MenuEvent> {
+        match entry {
+            Entry::Resume => return Some(self.close()),
+            Entry::Reset => {
+                let result = gba.lock().unwrap().hard_reset();
+                match result {
+                    Ok(()) => return Some(self.close()),
+                    Err(err) => self.message = Some(err),
+                }
+            }
+            Entry::SaveSlot(slot) => match savegame::save_slot(gba, slot) {
+                Ok(()) => {
+                    self.message = Some(tr_args("slot-saved", &[("slot", &slot)]));
+                    self.load_thumbnails(ctx, gba);
+                }
+                Err(err) => self.message = Some(err.to_string()),
+            },
+            Entry::LoadSlot(slot) => match savegame::load_slot(gba, slot) {
+                Ok(()) => return Some(self.close()),
+                Err(err) => self.message = Some(err.to_string()),
+            },
+            Entry::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+
+        None
+    }
+
+    fn load_thumbnails(&mut self, ctx: &egui::Context, gba: &Mutex<Gba>) {
+        self.thumbnails = savegame::slot_textures(ctx, gba);
+    }
+
+    /// Draws the menu over a dimmed application, it returns the entry clicked.
+    fn ui(&self, ctx: &egui::Context, entries: &[Entry]) -> Option<Entry> {
+        let mut clicked = None;
+        let screen = ctx.screen_rect();
+
+        egui::Area::new(egui::Id::new("Pause menu"))
+            .fixed_pos(screen.min)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                // Catches the clicks outside the menu, the windows below are not usable.
+                ui.allocate_rect(screen, egui::Sense::click());
+                ui.painter()
+                    .rect_filled(screen, 0.0, egui::Color32::from_black_alpha(180));
+
+                let menu = egui::Rect::from_center_size(screen.center(), egui::vec2(320.0, 0.0));
+                ui.allocate_ui_at_rect(menu, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.vertical_centered(|ui| ui.heading(tr("paused")));
+                        ui.separator();
+
+                        for (index, &entry) in entries.iter().enumerate() {
+                            if let Entry::SaveSlot(slot) = entry {
+                                self.thumbnail_ui(ui, slot);
+                            }
+
+                            let response = ui.selectable_label(
+                                index == self.selected,
+                                egui::RichText::new(entry.title()).size(18.0),
+                            );
+                            if index == self.selected {
+                                response.scroll_to_me(None);
+                                keep_focus(ui, &response);
+                            }
+                            if response.clicked() {
+                                clicked = Some(entry);
+                            }
+                        }
+
+                        if let Some(message) = &self.message {
+                            ui.separator();
+                            ui.label(message);
+                        }
+                    });
+                });
+            });
+
+        clicked
+    }
+
+    fn thumbnail_ui(&self, ui: &mut egui::Ui, slot: usize) {
+        ui.separator();
+        savegame::thumbnail_ui(ui, self.thumbnails.get(slot - 1).and_then(Option::as_ref));
+    }
+}
+
+/// Gives the keyboard focus to the highlighted entry, egui doesn't move it with Tab and
+/// the arrows, the menu does.
+fn keep_focus(ui: &egui::Ui, response: &egui::Response) {
+    if !response.has_focus() {
+        response.request_focus();
+    }
+
+    ui.memory_mut(|memory| {
+        memory.set_focus_lock_filter(
+            response.id,
+            egui::EventFilter {
+                tab: true,
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                escape: true,
+            },
+        );
+    });
+}
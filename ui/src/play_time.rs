@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use emu::{cartridge::hash::game_key, cpu::hardware::sound::CPU_FREQUENCY, gba::Gba};
+use logger::log;
+
+use crate::i18n::{tr, tr_args};
+use crate::paths;
+use crate::ui_traits::{tool_window, UiTool};
+
+const PLAY_TIME_FILE_NAME: &str = "play_time.json";
+
+/// The play time is saved this often, a crash loses at most this much.
+const SAVE_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Bigger jumps of the clock between two updates come from loading a state or
+/// resetting, not from playing.
+const MAX_CYCLES_PER_UPDATE: u128 = CPU_FREQUENCY as u128 * 10;
+
+/// How much a game was played.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayRecord {
+    /// From the cartridge header.
+    pub title: String,
+    /// Where the ROM was the last time it was played, to find it in the library.
+    pub path: Option<PathBuf>,
+    /// Seconds the game ran, pauses excluded.
+    pub play_time: u64,
+    /// Unix seconds of the start of the last session.
+    pub last_played: u64,
+    pub sessions: u32,
+}
+
+/// Play records of every game, by ROM hash so renamed and moved files keep theirs.
+/// Kept in `play_time.json` of the data directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayLog {
+    pub games: BTreeMap<String, PlayRecord>,
+}
+
+impl PlayLog {
+    fn path() -> Option<PathBuf> {
+        paths::data_dir().map(|dir| dir.join(PLAY_TIME_FILE_NAME))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path().filter(|path| path.is_file()) else {
+            return Self::default();
+        };
+
+        fs::read_to_string(&path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|data| Ok(serde_json::from_str(&data)?))
+            .unwrap_or_else(|e| {
+                log(format!("can't read play time {}: {e}", path.display()));
+                Self::default()
+            })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path().ok_or("No data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// The record of the ROM last played from `path`.
+    pub fn by_path(&self, path: &Path) -> Option<&PlayRecord> {
+        self.games
+            .values()
+            .find(|record| record.path.as_deref() == Some(path))
+    }
+}
+
+/// Counts how long the game runs and shows the statistics of every game played.
+pub struct PlayTime {
+    gba: Arc<Mutex<Gba>>,
+    play_log: Arc<Mutex<PlayLog>>,
+    /// Hash of the ROM being played, its key in the log.
+    game: String,
+    /// Clock of the emulator at the last update.
+    last_cycles: u128,
+    /// Cycles run and not yet added to the log.
+    unsaved_cycles: u128,
+    last_save: Instant,
+}
+
+impl PlayTime {
+    /// Starts a session of the game loaded in `gba`, played from `cartridge_name`.
+    pub fn new(gba: Arc<Mutex<Gba>>, play_log: Arc<Mutex<PlayLog>>, cartridge_name: &str) -> Self {
+        let locked_gba = gba.lock().unwrap();
+        let game = game_key(&locked_gba.cpu.bus.internal_memory.rom);
+        let title = locked_gba.cartridge_header.game_title.trim().to_owned();
+        let last_cycles = locked_gba.cycles();
+        drop(locked_gba);
+
+        let mut locked_log = play_log.lock().unwrap();
+        let record = locked_log.games.entry(game.clone()).or_default();
+        record.title = title;
+        record.path = fs::canonicalize(cartridge_name).ok();
+        record.last_played = unix_timestamp();
+        record.sessions += 1;
+        if let Err(e) = locked_log.save() {
+            log(format!("can't save play time: {e}"));
+        }
+        drop(locked_log);
+
+        Self {
+            gba,
+            play_log,
+            game,
+            last_cycles,
+            unsaved_cycles: 0,
+            last_save: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, is_closing: bool) {
+        let cycles = self.gba.lock().unwrap().cycles();
+        if cycles > self.last_cycles && cycles - self.last_cycles < MAX_CYCLES_PER_UPDATE {
+            self.unsaved_cycles += cycles - self.last_cycles;
+        }
+        self.last_cycles = cycles;
+
+        if is_closing || self.last_save.elapsed() > SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    fn save(&mut self) {
+        let seconds = self.unsaved_cycles / u128::from(CPU_FREQUENCY);
+        self.unsaved_cycles %= u128::from(CPU_FREQUENCY);
+        self.last_save = Instant::now();
+
+        let mut play_log = self.play_log.lock().unwrap();
+        let record = play_log.games.entry(self.game.clone()).or_default();
+        record.play_time += u64::try_from(seconds).unwrap_or(u64::MAX);
+
+        if let Err(e) = play_log.save() {
+            log(format!("can't save play time: {e}"));
+        }
+    }
+}
+
+/// eg. "3h 12m played".
+pub fn format_play_time(seconds: u64) -> String {
+    tr_args(
+        "play-time",
+        &[
+            ("hours", &(seconds / 3600)),
+            ("minutes", &(seconds / 60 % 60)),
+        ],
+    )
+}
+
+/// eg. "Played yesterday".
+pub fn format_last_played(timestamp: u64) -> String {
+    match unix_timestamp().saturating_sub(timestamp) / 86_400 {
+        0 => tr("played-today").to_string(),
+        1 => tr("played-yesterday").to_string(),
+        days => tr_args("played-days-ago", &[("days", &days)]),
+    }
+}
+
+/// Seconds since the Unix epoch on the host, 0 if the host clock is before it.
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+impl UiTool for PlayTime {
+    fn name(&self) -> &'static str {
+        "Play Time"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        self.update(ctx.input(|input| input.viewport().close_requested()));
+
+        tool_window(ctx, self.name())
+            .default_width(420.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let games = self.play_log.lock().unwrap().games.clone();
+        let mut games = games.iter().collect::<Vec<_>>();
+        games.sort_by_key(|(_, record)| std::cmp::Reverse(record.play_time));
+
+        let total = games.iter().map(|(_, record)| record.play_time).sum();
+        ui.label(tr_args(
+            "play-time-total",
+            &[("games", &games.len()), ("time", &format_play_time(total))],
+        ));
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("Play time")
+                .striped(true)
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.strong(tr("play-time-game"));
+                    ui.strong(tr("play-time-played"));
+                    ui.strong(tr("play-time-sessions"));
+                    ui.strong(tr("play-time-last-played"));
+                    ui.end_row();
+
+                    for (game, record) in games {
+                        let title = if record.title.is_empty() {
+                            game.as_str()
+                        } else {
+                            record.title.as_str()
+                        };
+                        let label = if *game == self.game {
+                            egui::RichText::new(format!("▶ {title}")).strong()
+                        } else {
+                            egui::RichText::new(title)
+                        };
+
+                        ui.label(label).on_hover_text(game);
+                        ui.label(format_play_time(record.play_time));
+                        ui.label(record.sessions.to_string());
+                        ui.label(format_last_played(record.last_played));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::border::BorderScaling;
+use crate::config::Config;
+use crate::frame_pacing::PresentMode;
+use crate::i18n::tr;
+
+/// Settings for the kind of device Clementine runs on, chosen when it's started the first
+/// time and from the settings later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// A window next to the tools, drawn at every refresh of the monitor.
+    Desktop,
+    /// A handheld PC like the Steam Deck: fullscreen, sharp pixels and fewer frames drawn
+    /// to save the battery. The menus are driven by the gamepad with the `gamepad` feature.
+    Handheld,
+}
+
+impl Preset {
+    pub const ALL: [Self; 2] = [Self::Desktop, Self::Handheld];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Desktop => tr("preset-desktop"),
+            Self::Handheld => tr("preset-handheld"),
+        }
+    }
+
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Desktop => tr("preset-desktop-description"),
+            Self::Handheld => tr("preset-handheld-description"),
+        }
+    }
+
+    /// Changes the settings of `config` handled by the preset, the others are kept.
+    pub fn apply(self, config: &mut Config) {
+        let is_handheld = self == Self::Handheld;
+
+        config.preset = Some(self);
+        config.fullscreen = is_handheld;
+        config.border.scaling = if is_handheld {
+            BorderScaling::Integer
+        } else {
+            BorderScaling::Fit
+        };
+        // The window is drawn only when the game completes a frame.
+        config.present_mode = if is_handheld {
+            PresentMode::Adaptive
+        } else {
+            PresentMode::Vsync
+        };
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use emu::{
+    debugger::profiler::{FunctionProfile, Profiler as CpuProfiler},
+    gba::Gba,
+};
+
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// The report is rebuilt at most this often, the table would be unreadable otherwise.
+const REPORT_REFRESH: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Function,
+    Calls,
+    SelfCycles,
+    TotalCycles,
+}
+
+/// Cycles spent in each emulated function, to find the slow parts of a game.
+pub struct Profiler {
+    gba: Arc<Mutex<Gba>>,
+    /// Functions with their names, sorted by `sort_by`.
+    report: Vec<(FunctionProfile, String)>,
+    cycles: u64,
+    sort_by: SortBy,
+    last_refresh: Option<Instant>,
+}
+
+impl Profiler {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            report: Vec::new(),
+            cycles: 0,
+            sort_by: SortBy::SelfCycles,
+            last_refresh: None,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.gba.lock().unwrap().cpu.profiler.is_some()
+    }
+
+    fn set_recording(&mut self, record: bool) {
+        self.gba.lock().unwrap().cpu.profiler = record.then(CpuProfiler::default);
+        self.last_refresh = None;
+    }
+
+    fn reset(&mut self) {
+        if let Some(profiler) = &mut self.gba.lock().unwrap().cpu.profiler {
+            profiler.clear();
+        }
+        self.last_refresh = None;
+    }
+
+    fn refresh(&mut self) {
+        if self
+            .last_refresh
+            .is_some_and(|last| last.elapsed() < REPORT_REFRESH)
+        {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+
+        let gba = self.gba.lock().unwrap();
+        let Some(profiler) = &gba.cpu.profiler else {
+            return;
+        };
+
+        self.cycles = profiler.cycles();
+        let symbols = &gba.cpu.symbols;
+        self.report = profiler
+            .report()
+            .into_iter()
+            .map(|profile| {
+                let name = profile.function.map_or_else(
+                    || tr("profiler-outside-calls").to_string(),
+                    |address| {
+                        symbols
+                            .describe(address)
+                            .unwrap_or_else(|| format!("0x{address:08X}"))
+                    },
+                );
+                (profile, name)
+            })
+            .collect();
+        drop(gba);
+
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        match self.sort_by {
+            SortBy::Function => self.report.sort_by(|(_, a), (_, b)| a.cmp(b)),
+            SortBy::Calls => self
+                .report
+                .sort_by_key(|(profile, _)| std::cmp::Reverse(profile.calls)),
+            SortBy::SelfCycles => self
+                .report
+                .sort_by_key(|(profile, _)| std::cmp::Reverse(profile.self_cycles)),
+            SortBy::TotalCycles => self
+                .report
+                .sort_by_key(|(profile, _)| std::cmp::Reverse(profile.total_cycles)),
+        }
+    }
+
+    fn header(&mut self, ui: &mut egui::Ui, sort_by: SortBy, title: &str) {
+        let title = egui::RichText::new(title).strong();
+
+        if ui
+            .selectable_label(self.sort_by == sort_by, title)
+            .on_hover_text(tr("sort-by-column"))
+            .clicked()
+        {
+            self.sort_by = sort_by;
+            self.sort();
+        }
+    }
+
+    fn report_ui(&mut self, ui: &mut egui::Ui) {
+        #[allow(clippy::cast_precision_loss)]
+        let percent = |cycles: u64, total: u64| {
+            format!("{:.2}%", cycles as f64 * 100.0 / total.max(1) as f64)
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("Profiler report")
+                .num_columns(6)
+                .spacing([16.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    self.header(ui, SortBy::Function, tr("function"));
+                    self.header(ui, SortBy::Calls, tr("calls"));
+                    self.header(ui, SortBy::SelfCycles, tr("self-cycles"));
+                    ui.label("");
+                    self.header(ui, SortBy::TotalCycles, tr("total-cycles"));
+                    ui.label("");
+                    ui.end_row();
+
+                    for (profile, name) in &self.report {
+                        ui.monospace(name);
+                        ui.label(profile.calls.to_string());
+                        ui.label(profile.self_cycles.to_string());
+                        ui.label(percent(profile.self_cycles, self.cycles));
+                        ui.label(profile.total_cycles.to_string());
+                        ui.label(percent(profile.total_cycles, self.cycles));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+impl UiTool for Profiler {
+    fn name(&self) -> &'static str {
+        "Profiler"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(480.0)
+            .default_height(320.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut is_recording = self.is_recording();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut is_recording, tr("record-profile"))
+                .on_hover_text(tr("record-profile-hint"))
+                .changed()
+            {
+                self.set_recording(is_recording);
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("reset")))
+                .clicked()
+            {
+                self.reset();
+            }
+        });
+
+        if !is_recording && self.report.is_empty() {
+            ui.label(tr("profiler-not-recording"));
+            return;
+        }
+
+        if is_recording {
+            self.refresh();
+        }
+
+        ui.label(format!("{}: {}", tr("cycles"), self.cycles));
+        ui.separator();
+        self.report_ui(ui);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::new(tr("record-profile")),
+            Command::new(tr("reset-profile")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        match index {
+            0 => self.set_recording(!self.is_recording()),
+            _ => self.reset(),
+        }
+
+        true
+    }
+}
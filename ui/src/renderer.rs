@@ -0,0 +1,419 @@
+//! Back ends drawing the frames of the LCD in the window. The software one converts the
+//! colors on the CPU and gives egui a texture. The OpenGL one uploads the 15 bit colors
+//! and converts them in a shader; it's experimental, a first step to draw the layers on
+//! the GPU at a higher resolution.
+//!
+//! The OpenGL back end stands in for a wgpu compute one: eframe is built with glow, so
+//! a fragment shader on its context needs no new dependency. Moving to wgpu means
+//! switching eframe to it and is still to be decided.
+
+use std::mem;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui_glow::{self, glow, ShaderVersion};
+use egui::{Color32, Pos2, Rect, Ui};
+use glow::HasContext;
+use serde::{Deserialize, Serialize};
+
+use emu::cpu::hardware::lcd::Color;
+use emu::render::color_correction::ColorCorrection;
+use emu::render::{LCD_HEIGHT, LCD_WIDTH};
+use logger::log;
+
+use crate::gba_display;
+use crate::i18n::tr;
+use crate::screen_filter;
+
+/// A whole frame of the LCD, row by row.
+pub type Frame = [[Color; LCD_WIDTH]; LCD_HEIGHT];
+
+/// The whole of a texture.
+const FULL_UV: Rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+
+/// Size of the texture of the frame.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const FRAME_SIZE: [i32; 2] = [LCD_WIDTH as i32, LCD_HEIGHT as i32];
+
+/// The color table is a texture of 256 colors by 128 rows, the row is the high byte of
+/// the color.
+const TABLE_SIZE: [i32; 2] = [256, 128];
+
+/// A triangle covering the viewport, `position` goes from (0, 0) at the top left to
+/// (1, 1) at the bottom right of it.
+const VERTEX_SHADER: &str = r"
+out vec2 position;
+
+void main() {
+    vec2 corner = vec2(float((gl_VertexID & 1) * 2), float(gl_VertexID & 2));
+    position = vec2(corner.x, 1.0 - corner.y);
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+/// The color of each pixel is the entry of the table for its 15 bits.
+const FRAGMENT_SHADER: &str = r"
+precision highp float;
+precision highp int;
+precision highp usampler2D;
+
+uniform usampler2D frame;
+uniform sampler2D colors;
+
+in vec2 position;
+out vec4 fragment_color;
+
+void main() {
+    ivec2 size = textureSize(frame, 0);
+    ivec2 pixel = clamp(ivec2(position * vec2(size)), ivec2(0), size - 1);
+    uint color = texelFetch(frame, pixel, 0).r & 0x7FFFu;
+
+    fragment_color = vec4(texelFetch(colors, ivec2(color & 0xFFu, color >> 8), 0).rgb, 1.0);
+}
+";
+
+/// Back end chosen in the settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RendererKind {
+    #[default]
+    Software,
+    OpenGl,
+}
+
+impl RendererKind {
+    pub const ALL: [Self; 2] = [Self::Software, Self::OpenGl];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Software => tr("renderer-software"),
+            Self::OpenGl => tr("renderer-opengl"),
+        }
+    }
+}
+
+/// Index in [`RendererKind::ALL`] of the back end drawing every screen.
+static CURRENT_RENDERER: AtomicU8 = AtomicU8::new(0);
+
+#[allow(clippy::cast_possible_truncation)]
+pub fn set_renderer(kind: RendererKind) {
+    let index = RendererKind::ALL
+        .iter()
+        .position(|option| *option == kind)
+        .unwrap_or_default();
+
+    CURRENT_RENDERER.store(index as u8, Ordering::Relaxed);
+}
+
+fn current_renderer() -> RendererKind {
+    RendererKind::ALL[usize::from(CURRENT_RENDERER.load(Ordering::Relaxed))]
+}
+
+/// Draws the frames of the LCD.
+pub trait Renderer {
+    /// Draws `frame` stretched over `rect`, it returns `false` if the frame may not be
+    /// drawn, when the back end can't run on this system or doesn't know yet.
+    fn paint(&mut self, ui: &Ui, rect: Rect, frame: &Frame) -> bool;
+}
+
+/// The colors are converted with the table of the color correction and loaded in a
+/// texture of egui, scaled by the filter chosen in the settings.
+pub struct SoftwareRenderer {
+    texture_name: &'static str,
+}
+
+impl Renderer for SoftwareRenderer {
+    fn paint(&mut self, ui: &Ui, rect: Rect, frame: &Frame) -> bool {
+        let texture = screen_filter::load_texture(
+            ui.ctx(),
+            self.texture_name,
+            gba_display::screen_image(frame),
+            rect.size(),
+        );
+        ui.painter()
+            .image(texture.id(), rect, FULL_UV, Color32::WHITE);
+
+        true
+    }
+}
+
+/// The frame is uploaded as it is and the shader looks up the colors in the table of the
+/// color correction. It needs GLSL 1.40 or GLSL ES 3.00, which is only known once the
+/// first paint callback runs: until then the frame is also drawn by another back end.
+#[derive(Default)]
+pub struct GlRenderer {
+    /// Created by the first paint callback, it's the only place with the OpenGL context.
+    state: Arc<Mutex<GlState>>,
+}
+
+#[derive(Default)]
+enum GlState {
+    #[default]
+    Uninitialized,
+    Ready(GlScreen),
+    Unsupported,
+}
+
+impl Renderer for GlRenderer {
+    fn paint(&mut self, ui: &Ui, rect: Rect, frame: &Frame) -> bool {
+        let is_ready = match *self.state.lock().unwrap() {
+            GlState::Uninitialized => false,
+            GlState::Ready(_) => true,
+            GlState::Unsupported => return false,
+        };
+
+        let pixels = frame
+            .as_flattened()
+            .iter()
+            .flat_map(|color| color.0.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let correction = gba_display::color_correction();
+        let state = Arc::clone(&self.state);
+
+        let callback = egui_glow::CallbackFn::new(move |_info, painter| {
+            let gl = painter.gl();
+            delete_released_screens(gl);
+            let mut state = state.lock().unwrap();
+
+            if matches!(*state, GlState::Uninitialized) {
+                *state = GlScreen::new(gl).map_or_else(
+                    |err| {
+                        log(format!("OpenGL renderer not available: {err}"));
+                        GlState::Unsupported
+                    },
+                    GlState::Ready,
+                );
+            }
+            if let GlState::Ready(screen) = &mut *state {
+                screen.paint(gl, &pixels, correction);
+            }
+        });
+
+        ui.painter().add(egui::PaintCallback {
+            rect,
+            callback: Arc::new(callback),
+        });
+
+        is_ready
+    }
+}
+
+impl Drop for GlRenderer {
+    fn drop(&mut self) {
+        // `Unsupported` so that a callback still queued doesn't create a new screen.
+        let state = mem::replace(&mut *self.state.lock().unwrap(), GlState::Unsupported);
+        if let GlState::Ready(screen) = state {
+            RELEASED_SCREENS.lock().unwrap().push(screen);
+        }
+    }
+}
+
+/// Screens of the renderers dropped since the last paint callback. Their objects can
+/// only be deleted with the context, by the next callback or when the app exits.
+static RELEASED_SCREENS: Mutex<Vec<GlScreen>> = Mutex::new(Vec::new());
+
+/// Deletes the objects of the screens of the dropped renderers, the app calls it on exit
+/// after dropping the screens.
+pub fn delete_released_screens(gl: &glow::Context) {
+    for screen in RELEASED_SCREENS.lock().unwrap().drain(..) {
+        screen.delete(gl);
+    }
+}
+
+/// The objects of OpenGL drawing a screen, see [`GlScreen::delete`].
+struct GlScreen {
+    program: glow::Program,
+    vertex_array: glow::VertexArray,
+    frame: glow::Texture,
+    colors: glow::Texture,
+    /// Correction whose table is in `colors`.
+    correction: Option<ColorCorrection>,
+}
+
+impl GlScreen {
+    fn new(gl: &glow::Context) -> Result<Self, String> {
+        let shader_version = ShaderVersion::get(gl);
+        if !shader_version.is_new_shader_interface() {
+            return Err(format!("{shader_version:?} shaders are too old"));
+        }
+
+        // SAFETY: the objects are created on the context of the paint callback.
+        unsafe {
+            let program = link_program(gl, shader_version.version_declaration())?;
+            let vertex_array = gl.create_vertex_array()?;
+            let frame = create_texture(gl)?;
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::R16UI.cast_signed(),
+                FRAME_SIZE[0],
+                FRAME_SIZE[1],
+                0,
+                glow::RED_INTEGER,
+                glow::UNSIGNED_SHORT,
+                None,
+            );
+            let colors = create_texture(gl)?;
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGB8.cast_signed(),
+                TABLE_SIZE[0],
+                TABLE_SIZE[1],
+                0,
+                glow::RGB,
+                glow::UNSIGNED_BYTE,
+                None,
+            );
+
+            Ok(Self {
+                program,
+                vertex_array,
+                frame,
+                colors,
+                correction: None,
+            })
+        }
+    }
+
+    /// Draws `pixels` (the colors of a frame in native endianness) over the viewport.
+    fn paint(&mut self, gl: &glow::Context, pixels: &[u8], correction: ColorCorrection) {
+        // SAFETY: the objects were created on this context, the sizes of the data match
+        // the ones of the textures.
+        unsafe {
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.colors));
+            if self.correction != Some(correction) {
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    TABLE_SIZE[0],
+                    TABLE_SIZE[1],
+                    glow::RGB,
+                    glow::UNSIGNED_BYTE,
+                    glow::PixelUnpackData::Slice(gba_display::color_table(correction).as_bytes()),
+                );
+                self.correction = Some(correction);
+            }
+
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.frame));
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                FRAME_SIZE[0],
+                FRAME_SIZE[1],
+                glow::RED_INTEGER,
+                glow::UNSIGNED_SHORT,
+                glow::PixelUnpackData::Slice(pixels),
+            );
+
+            gl.use_program(Some(self.program));
+            gl.uniform_1_i32(gl.get_uniform_location(self.program, "frame").as_ref(), 0);
+            gl.uniform_1_i32(gl.get_uniform_location(self.program, "colors").as_ref(), 1);
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.draw_arrays(glow::TRIANGLES, 0, 3);
+        }
+    }
+
+    /// Deletes the objects, on the context they were created on.
+    fn delete(self, gl: &glow::Context) {
+        // SAFETY: the objects were created on this context and nothing uses them anymore.
+        unsafe {
+            gl.delete_program(self.program);
+            gl.delete_vertex_array(self.vertex_array);
+            gl.delete_texture(self.frame);
+            gl.delete_texture(self.colors);
+        }
+    }
+}
+
+/// A texture sampled without filtering, bound to `TEXTURE_2D`.
+unsafe fn create_texture(gl: &glow::Context) -> Result<glow::Texture, String> {
+    let texture = gl.create_texture()?;
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::NEAREST.cast_signed());
+    }
+
+    Ok(texture)
+}
+
+unsafe fn link_program(gl: &glow::Context, version: &str) -> Result<glow::Program, String> {
+    let program = gl.create_program()?;
+    let mut shaders = Vec::new();
+    let mut result = Ok(());
+
+    for (kind, source) in [
+        (glow::VERTEX_SHADER, VERTEX_SHADER),
+        (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+    ] {
+        let shader = match gl.create_shader(kind) {
+            Ok(shader) => shader,
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        };
+        gl.shader_source(shader, &format!("{version}{source}"));
+        gl.compile_shader(shader);
+        gl.attach_shader(program, shader);
+        shaders.push(shader);
+        if !gl.get_shader_compile_status(shader) {
+            result = Err(gl.get_shader_info_log(shader));
+            break;
+        }
+    }
+
+    if result.is_ok() {
+        gl.link_program(program);
+        if !gl.get_program_link_status(program) {
+            result = Err(gl.get_program_info_log(program));
+        }
+    }
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+
+    // Nothing is left behind when it fails.
+    result
+        .map(|()| program)
+        .inspect_err(|_| gl.delete_program(program))
+}
+
+/// The screen of a core, drawn by the back end chosen in the settings. It falls back to
+/// the software one where OpenGL can't be used, and on the first frame, before it's known.
+pub struct Screen {
+    software: SoftwareRenderer,
+    gl: GlRenderer,
+}
+
+impl Screen {
+    /// Each core needs its own `texture_name`, or the screens would overwrite each other.
+    pub fn new(texture_name: &'static str) -> Self {
+        Self {
+            software: SoftwareRenderer { texture_name },
+            gl: GlRenderer::default(),
+        }
+    }
+
+    pub fn paint(&mut self, ui: &Ui, rect: Rect, frame: &Frame) {
+        let drawn = match current_renderer() {
+            RendererKind::Software => false,
+            RendererKind::OpenGl => self.gl.paint(ui, rect, frame),
+        };
+
+        // Painted over the paint callback, if there is one.
+        if !drawn {
+            self.software.paint(ui, rect, frame);
+        }
+    }
+}
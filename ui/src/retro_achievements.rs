@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use emu::achievements::Achievement;
+
+/// The API used by emulators, the same `rcheevos` calls.
+const API_URL: &str = "https://retroachievements.org/dorequest.php";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Flags of the achievements in the official set, the others are still being tested.
+const OFFICIAL_FLAGS: u32 = 3;
+
+/// A logged in user, the password is never kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub username: String,
+    pub token: String,
+}
+
+/// The achievement set of a game, with the ones already earned unlocked.
+pub struct GameSet {
+    pub title: String,
+    pub achievements: Vec<Achievement>,
+    /// Achievements with conditions that are not supported, they are left out.
+    pub unsupported: usize,
+}
+
+/// Every response has these, `error` is set when `success` is not.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Status {
+    success: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LoginResponse {
+    user: String,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct GameIdResponse {
+    #[serde(rename = "GameID")]
+    game_id: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PatchResponse {
+    patch_data: PatchData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PatchData {
+    title: String,
+    achievements: Vec<AchievementData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AchievementData {
+    #[serde(rename = "ID")]
+    id: u32,
+    mem_addr: String,
+    title: String,
+    description: String,
+    points: u32,
+    flags: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SessionResponse {
+    #[serde(default)]
+    unlocks: Vec<Unlock>,
+}
+
+#[derive(Deserialize)]
+struct Unlock {
+    #[serde(rename = "ID")]
+    id: u32,
+}
+
+fn request<T: DeserializeOwned>(form: &[(&str, &str)]) -> Result<T, Box<dyn Error>> {
+    let body = ureq::AgentBuilder::new()
+        .user_agent(concat!("clementine/", env!("CARGO_PKG_VERSION")))
+        .timeout(TIMEOUT)
+        .build()
+        .post(API_URL)
+        .send_form(form)?
+        .into_string()?;
+
+    let status: Status = serde_json::from_str(&body)?;
+    if !status.success {
+        return Err(status
+            .error
+            .unwrap_or_else(|| "Request refused".to_owned())
+            .into());
+    }
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Logs in with the password, the token returned is used for the other requests.
+pub fn login(username: &str, password: &str) -> Result<Session, Box<dyn Error>> {
+    let response: LoginResponse = request(&[("r", "login2"), ("u", username), ("p", password)])?;
+
+    Ok(Session {
+        username: response.user,
+        token: response.token,
+    })
+}
+
+/// Finds the game by the MD5 of its ROM and reads its achievements.
+pub fn load_game(session: &Session, hash: &str) -> Result<GameSet, Box<dyn Error>> {
+    let response: GameIdResponse = request(&[("r", "gameid"), ("m", hash)])?;
+    if response.game_id == 0 {
+        return Err("This ROM is not recognized by RetroAchievements".into());
+    }
+    let game_id = response.game_id.to_string();
+
+    let login = [
+        ("u", session.username.as_str()),
+        ("t", session.token.as_str()),
+    ];
+    let patch: PatchResponse = request(&[&login[..], &[("r", "patch"), ("g", &game_id)]].concat())?;
+    let started: SessionResponse = request(
+        &[
+            &login[..],
+            &[("r", "startsession"), ("g", &game_id), ("m", hash)],
+        ]
+        .concat(),
+    )?;
+
+    let mut unsupported = 0;
+    let achievements = patch
+        .patch_data
+        .achievements
+        .into_iter()
+        .filter(|data| data.flags == OFFICIAL_FLAGS)
+        .filter_map(|data| {
+            let achievement = Achievement::new(
+                data.id,
+                data.title,
+                data.description,
+                data.points,
+                &data.mem_addr,
+            );
+
+            achievement
+                .map_err(|e| {
+                    logger::log(format!("achievement {} not supported: {e}", data.id));
+                    unsupported += 1;
+                })
+                .ok()
+        })
+        .map(|mut achievement| {
+            if started
+                .unlocks
+                .iter()
+                .any(|unlock| unlock.id == achievement.id)
+            {
+                achievement.unlock();
+            }
+            achievement
+        })
+        .collect();
+
+    Ok(GameSet {
+        title: patch.patch_data.title,
+        achievements,
+        unsupported,
+    })
+}
+
+/// Tells the server an achievement was earned, never in hardcore mode.
+pub fn award(session: &Session, achievement_id: u32, hash: &str) -> Result<(), Box<dyn Error>> {
+    let id = achievement_id.to_string();
+    // The server checks the request was made by an emulator with this signature.
+    let validation = format!("{:x}", md5::compute(format!("{id}{}0", session.username)));
+
+    request::<Status>(&[
+        ("r", "awardachievement"),
+        ("u", &session.username),
+        ("t", &session.token),
+        ("a", &id),
+        ("h", "0"),
+        ("m", hash),
+        ("v", &validation),
+    ])?;
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use emu::{
+    cartridge::{
+        dat::{Dat, Verification},
+        hash::RomHash,
+        patch::PatchKind,
+    },
+    debugger::symbols,
+    gba::Gba,
+};
+use native_dialog::FileDialog;
+
+use crate::dialog::show_error;
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, UiTool};
+
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+pub struct RomInfo {
+    gba: Arc<Mutex<Gba>>,
+    /// Computed the first time the window is shown, hashing a big ROM takes a while.
+    hash: Option<RomHash>,
+    verification: Option<Verification>,
+}
+
+impl RomInfo {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            hash: None,
+            verification: None,
+        }
+    }
+
+    fn apply_patch(&mut self) -> Result<(), Box<dyn Error>> {
+        let path = FileDialog::new()
+            .set_location("~")
+            .add_filter(tr("rom-patch"), &PatchKind::EXTENSIONS)
+            .show_open_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        let patch_data = std::fs::read(path)?;
+
+        self.gba.lock().unwrap().apply_patch(&patch_data)?;
+        self.hash = None;
+        self.verification = None;
+
+        Ok(())
+    }
+
+    fn load_symbols(&self) -> Result<(), Box<dyn Error>> {
+        let path = FileDialog::new()
+            .set_location("~")
+            .add_filter(tr("symbol-file"), &symbols::EXTENSIONS)
+            .show_open_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        let data = std::fs::read(path)?;
+
+        self.gba.lock().unwrap().load_debug_info(&data)?;
+
+        Ok(())
+    }
+
+    fn verify_with_dat(&mut self) -> Result<(), Box<dyn Error>> {
+        let path = FileDialog::new()
+            .set_location("~")
+            .add_filter("No-Intro DAT", &["dat", "xml"])
+            .show_open_single_file()?;
+
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
+        let dat = Dat::parse(&std::fs::read_to_string(path)?)?;
+
+        let gba = self.gba.lock().unwrap();
+        let rom = &gba.cpu.bus.internal_memory.rom;
+        let hash = self.hash.get_or_insert_with(|| RomHash::new(rom));
+        self.verification = Some(dat.verify(rom, hash, &gba.cartridge_header.game_code));
+        drop(gba);
+
+        Ok(())
+    }
+}
+
+fn check_label(valid: bool) -> &'static str {
+    if valid {
+        tr("valid")
+    } else {
+        tr("invalid")
+    }
+}
+
+impl UiTool for RomInfo {
+    fn name(&self) -> &'static str {
+        "ROM Info"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(320.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let gba = self.gba.lock().unwrap();
+        let header = &gba.cartridge_header;
+        let hash = self
+            .hash
+            .get_or_insert_with(|| RomHash::new(&gba.cpu.bus.internal_memory.rom));
+
+        ui.heading(tr("header"));
+        ui.add_space(8.0);
+
+        egui::Grid::new("ROM Header")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(tr("title"));
+                ui.label(header.title());
+                ui.end_row();
+
+                ui.label(tr("game-code"));
+                ui.label(&header.game_code);
+                ui.end_row();
+
+                ui.label(tr("maker-code"));
+                ui.label(&header.maker_code);
+                ui.end_row();
+
+                ui.label(tr("version"));
+                ui.label(format!("{}", header.version()));
+                ui.end_row();
+
+                ui.label(tr("nintendo-logo"));
+                ui.label(check_label(header.is_logo_valid()));
+                ui.end_row();
+
+                ui.label(tr("complement-check"));
+                ui.label(format!(
+                    "0x{:02X} {}",
+                    header.complement_check,
+                    check_label(header.is_complement_check_valid())
+                ));
+                ui.end_row();
+
+                ui.label(tr("size"));
+                ui.label(tr_args("size-bytes", &[("size", &hash.size)]));
+                ui.end_row();
+
+                ui.label("CRC32");
+                ui.label(format!("{:08X}", hash.crc32));
+                ui.end_row();
+
+                ui.label("MD5");
+                ui.label(&hash.md5);
+                ui.end_row();
+
+                ui.label("SHA-1");
+                ui.label(&hash.sha1);
+                ui.end_row();
+            });
+
+        let warnings = header.warnings();
+        if !warnings.is_empty() {
+            ui.add_space(8.0);
+            ui.colored_label(egui::Color32::YELLOW, tr("bad-dump"));
+            for warning in warnings {
+                ui.label(format!("• {warning}"));
+            }
+        }
+
+        drop(gba);
+
+        if let Some(verification) = &self.verification {
+            ui.add_space(8.0);
+            let color = match verification {
+                Verification::Verified { .. } => egui::Color32::GREEN,
+                Verification::Unknown => egui::Color32::GRAY,
+                Verification::Overdump { .. } | Verification::BadDump { .. } => {
+                    egui::Color32::YELLOW
+                }
+            };
+            ui.colored_label(color, verification.to_string());
+        }
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("verify-with-dat"))
+                .on_hover_text(tr("verify-with-dat-hint"))
+                .clicked()
+            {
+                self.verify_with_dat()
+                    .unwrap_or_else(|err| show_error(err.as_ref()));
+            }
+
+            if ui
+                .button(tr("apply-patch"))
+                .on_hover_text(tr("apply-patch-hint"))
+                .clicked()
+            {
+                self.apply_patch()
+                    .unwrap_or_else(|err| show_error(err.as_ref()));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("load-symbols"))
+                .on_hover_text(tr("load-symbols-hint"))
+                .clicked()
+            {
+                self.load_symbols()
+                    .unwrap_or_else(|err| show_error(err.as_ref()));
+            }
+
+            let count = self.gba.lock().unwrap().cpu.symbols.len();
+            if count > 0 {
+                ui.label(tr_args("symbols-loaded", &[("count", &count)]));
+            }
+        });
+    }
+}
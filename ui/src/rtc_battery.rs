@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use emu::{cartridge::hash::game_key, gba::Gba};
+
+use crate::paths;
+
+/// What the battery of the cartridge keeps of its clock between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedClock {
+    /// Seconds the clock is ahead of the host (behind if negative), so it keeps
+    /// the time set by the game and counts the time the emulator was closed.
+    offset: i64,
+    /// Status register, with the 12/24-hour mode.
+    control: u8,
+}
+
+/// Clock files are kept in `rtc` of the data directory, one per game.
+fn path(gba: &Gba) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = paths::data_dir().ok_or("No data directory")?;
+    let game = game_key(&gba.cpu.bus.internal_memory.rom);
+
+    Ok(dir.join("rtc").join(format!("{game}.json")))
+}
+
+/// Sets the clock of the cartridge as it was left in the last session, if there was one.
+pub fn restore(gba: &mut Gba, host_timestamp: u64) -> Result<(), Box<dyn Error>> {
+    if gba.rtc_timestamp().is_none() {
+        return Ok(());
+    }
+    let path = path(gba)?;
+    if !path.is_file() {
+        return Ok(());
+    }
+
+    let clock: SavedClock = serde_json::from_str(&fs::read_to_string(path)?)?;
+    gba.set_rtc_timestamp(host_timestamp.saturating_add_signed(clock.offset));
+    gba.set_rtc_control(clock.control);
+
+    Ok(())
+}
+
+pub fn save(gba: &Gba, host_timestamp: u64) -> Result<(), Box<dyn Error>> {
+    let (Some(timestamp), Some(control)) = (gba.rtc_timestamp(), gba.rtc_control()) else {
+        return Ok(());
+    };
+
+    #[allow(clippy::cast_possible_wrap)]
+    let clock = SavedClock {
+        offset: timestamp.wrapping_sub(host_timestamp) as i64,
+        control,
+    };
+
+    let path = path(gba)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&clock)?)?;
+
+    Ok(())
+}
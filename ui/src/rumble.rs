@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use emu::gba::Gba;
+
+use crate::i18n::tr;
+#[cfg(feature = "rumble")]
+use crate::i18n::tr_args;
+#[cfg(feature = "rumble")]
+use crate::rumble_output::RumbleOutput;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Rumble of the cartridges with a motor on the host gamepads.
+pub struct Rumble {
+    gba: Arc<Mutex<Gba>>,
+    is_enabled: bool,
+    /// Percentage of the strength asked by the game.
+    intensity: u8,
+    /// How much the motor spun in the last frame, `None` if the cartridge has no rumble.
+    strength: Option<f32>,
+    #[cfg(feature = "rumble")]
+    output: Result<RumbleOutput, String>,
+}
+
+impl Rumble {
+    #[cfg_attr(not(feature = "rumble"), allow(clippy::missing_const_for_fn))]
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            is_enabled: true,
+            intensity: 100,
+            strength: None,
+            #[cfg(feature = "rumble")]
+            output: RumbleOutput::new().map_err(|err| err.to_string()),
+        }
+    }
+
+    /// Passes the rumble of the last frame to the gamepads.
+    fn update(&mut self) {
+        self.strength = self.gba.lock().unwrap().take_rumble();
+
+        #[cfg(feature = "rumble")]
+        if let Ok(output) = &mut self.output {
+            let strength = if self.is_enabled {
+                self.strength.unwrap_or_default() * f32::from(self.intensity) / 100.0
+            } else {
+                0.0
+            };
+
+            if let Err(err) = output.set_strength(strength) {
+                logger::log(format!("can't rumble: {err}"));
+            }
+        }
+    }
+
+    #[cfg(feature = "rumble")]
+    fn output_ui(&self, ui: &mut egui::Ui) {
+        match &self.output {
+            Ok(output) => {
+                let names = output.gamepad_names();
+                if names.is_empty() {
+                    ui.label(tr("no-rumble-gamepads"));
+                }
+                for name in names {
+                    ui.label(format!("🎮 {name}"));
+                }
+            }
+            Err(err) => {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    tr_args("no-rumble-output", &[("error", err)]),
+                );
+            }
+        }
+    }
+}
+
+impl UiTool for Rumble {
+    fn name(&self) -> &'static str {
+        "Rumble"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        self.update();
+
+        tool_window(ctx, self.name())
+            .default_width(240.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.is_enabled, tr("rumble-enabled"));
+        ui.add_enabled(
+            self.is_enabled,
+            egui::Slider::new(&mut self.intensity, 0..=100)
+                .text(tr("rumble-intensity"))
+                .suffix("%"),
+        );
+
+        ui.separator();
+        match self.strength {
+            Some(strength) => {
+                ui.add(egui::ProgressBar::new(strength).text(tr("rumble-motor")));
+            }
+            None => {
+                ui.label(tr("no-rumble-cartridge"));
+            }
+        }
+
+        #[cfg(feature = "rumble")]
+        self.output_ui(ui);
+        #[cfg(not(feature = "rumble"))]
+        ui.small(tr("rumble-not-built"));
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::new(tr(if self.is_enabled {
+            "disable-rumble"
+        } else {
+            "enable-rumble"
+        }))]
+    }
+
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        self.is_enabled = !self.is_enabled;
+
+        true
+    }
+}
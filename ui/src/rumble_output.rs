@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::{EventType, Gilrs};
+
+/// Strength changes smaller than this don't restart the effect.
+const STRENGTH_STEP: f32 = 0.05;
+
+/// Force feedback of the gamepads connected to the host.
+pub struct RumbleOutput {
+    gilrs: Gilrs,
+    effect: Option<Effect>,
+    strength: f32,
+}
+
+impl RumbleOutput {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            gilrs: Gilrs::new().map_err(|err| err.to_string())?,
+            effect: None,
+            strength: 0.0,
+        })
+    }
+
+    /// Names of the connected gamepads with force feedback.
+    pub fn gamepad_names(&self) -> Vec<String> {
+        self.gilrs
+            .gamepads()
+            .filter(|(_, gamepad)| gamepad.is_ff_supported())
+            .map(|(_, gamepad)| gamepad.name().to_string())
+            .collect()
+    }
+
+    /// Rumbles every gamepad from 0 (stopped) to 1 (strongest) until the next call.
+    pub fn set_strength(&mut self, strength: f32) -> Result<(), Box<dyn Error>> {
+        // The events have to be read for gilrs to see the gamepads being plugged.
+        let mut gamepads_changed = false;
+        while let Some(event) = self.gilrs.next_event() {
+            gamepads_changed |=
+                matches!(event.event, EventType::Connected | EventType::Disconnected);
+        }
+
+        if (strength - self.strength).abs() < STRENGTH_STEP && !gamepads_changed {
+            return Ok(());
+        }
+        self.strength = strength;
+        // Dropping the effect stops it.
+        self.effect = None;
+
+        let gamepads = self
+            .gilrs
+            .gamepads()
+            .filter(|(_, gamepad)| gamepad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if strength < STRENGTH_STEP || gamepads.is_empty() {
+            return Ok(());
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let magnitude = (strength.min(1.0) * f32::from(u16::MAX)) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude },
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(100),
+                    ..Replay::default()
+                },
+                ..BaseEffect::default()
+            })
+            .repeat(Repeat::Infinitely)
+            .gamepads(&gamepads)
+            .finish(&mut self.gilrs)?;
+        effect.play()?;
+        self.effect = Some(effect);
+
+        Ok(())
+    }
+}
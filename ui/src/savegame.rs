@@ -1,36 +1,60 @@
 use std::{
     error::Error,
     io::{Read, Write},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
-use emu::gba::Gba;
+use eframe::epaint::textures::TextureOptions;
+use egui::load::SizedTexture;
 
-use crate::ui_traits::UiTool;
-use emu::cpu::arm7tdmi::Arm7tdmi;
-use native_dialog::{FileDialog, MessageDialog};
+use emu::{
+    cartridge::hash::game_key,
+    gba::{Gba, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH},
+};
+
+use crate::dialog::show_error;
+use crate::gba_display::color_image;
+use crate::i18n::{tr, tr_args};
+use crate::paths;
+use crate::ui_traits::{tool_window, Command, UiTool};
+use native_dialog::FileDialog;
 use std::fs;
 
+/// Quick save slots reachable from the command palette.
+pub const SLOTS: usize = 4;
+
 pub struct SaveGame {
     gba: Arc<Mutex<Gba>>,
+    /// Thumbnails of the slots, `None` for empty ones. They are loaded when the window
+    /// is shown and after a slot is saved.
+    thumbnails: Option<Vec<Option<egui::TextureHandle>>>,
 }
 
 impl SaveGame {
     pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
-        Self { gba }
+        Self {
+            gba,
+            thumbnails: None,
+        }
     }
 
     fn save_state(&self) -> Result<(), Box<dyn Error>> {
+        // Save states are named after the game so they are easy to match with the ROM.
+        let filename = format!(
+            "{}.clm",
+            game_key(&self.gba.lock().unwrap().cpu.bus.internal_memory.rom)
+        );
+
         let path = FileDialog::new()
             .set_location("~")
-            .add_filter("Clementine save file", &["clm"])
+            .set_filename(&filename)
+            .add_filter(tr("clementine-save-file"), &["clm"])
             .show_save_single_file()?;
 
-        let path = path.ok_or("No file selected")?;
-
-        let cpu = &self.gba.lock().unwrap().cpu;
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
 
-        let encoded = bincode::serialize(cpu)?;
+        let encoded = self.gba.lock().unwrap().save_state_file()?;
         let mut file = fs::OpenOptions::new()
             .write(true)
             .truncate(true)
@@ -44,55 +68,195 @@ impl SaveGame {
     fn load_state(&self) -> Result<(), Box<dyn Error>> {
         let path = FileDialog::new()
             .set_location("~")
-            .add_filter("Clementine save file", &["clm"])
+            .add_filter(tr("clementine-save-file"), &["clm"])
             .show_open_single_file()?;
 
-        let path = path.ok_or("No file selected")?;
+        let path = path.ok_or_else(|| tr("no-file-selected"))?;
 
         let mut file = fs::OpenOptions::new().read(true).open(path)?;
         let mut encoded = Vec::new();
         file.read_to_end(&mut encoded)?;
 
-        let cpu = &mut self.gba.lock().unwrap().cpu;
-        let decoded: Arm7tdmi = bincode::deserialize(&encoded)?;
-        *cpu = decoded;
+        self.gba.lock().unwrap().load_state_file(&encoded)?;
 
         Ok(())
     }
 }
 
+/// Slot files are kept in `states` of the data directory, one set per game.
+fn slot_path(gba: &Mutex<Gba>, slot: usize) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = paths::data_dir().ok_or("No data directory")?;
+    let game = game_key(&gba.lock().unwrap().cpu.bus.internal_memory.rom);
+
+    Ok(dir.join("states").join(format!("{game}.{slot}.clm")))
+}
+
+pub fn save_slot(gba: &Mutex<Gba>, slot: usize) -> Result<(), Box<dyn Error>> {
+    let path = slot_path(gba, slot)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let encoded = gba.lock().unwrap().save_state_file()?;
+    fs::write(path, encoded)?;
+
+    Ok(())
+}
+
+pub fn load_slot(gba: &Mutex<Gba>, slot: usize) -> Result<(), Box<dyn Error>> {
+    let path = slot_path(gba, slot)?;
+    if !path.is_file() {
+        return Err(tr_args("empty-slot", &[("slot", &slot)]).into());
+    }
+
+    let encoded = fs::read(path)?;
+    gba.lock().unwrap().load_state_file(&encoded)?;
+
+    Ok(())
+}
+
+/// The screen when the slot was saved at half size, `None` if it's empty.
+pub fn slot_thumbnail(
+    gba: &Mutex<Gba>,
+    slot: usize,
+) -> Result<Option<egui::ColorImage>, Box<dyn Error>> {
+    let path = slot_path(gba, slot)?;
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let thumbnail = Gba::state_file_thumbnail(&fs::read(path)?)?;
+
+    Ok(Some(color_image(
+        [THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT],
+        &thumbnail,
+    )))
+}
+
+/// The thumbnails of all the slots as textures, `None` for the empty ones.
+pub fn slot_textures(ctx: &egui::Context, gba: &Mutex<Gba>) -> Vec<Option<egui::TextureHandle>> {
+    (1..=SLOTS)
+        .map(|slot| {
+            let image = slot_thumbnail(gba, slot)
+                .map_err(|err| logger::log(format!("can't read slot {slot}: {err}")))
+                .ok()
+                .flatten()?;
+
+            Some(ctx.load_texture(format!("slot {slot}"), image, TextureOptions::NEAREST))
+        })
+        .collect()
+}
+
+/// The thumbnail of a slot at its size, or a placeholder if it's empty.
+#[allow(clippy::cast_precision_loss)]
+pub fn thumbnail_ui(ui: &mut egui::Ui, texture: Option<&egui::TextureHandle>) {
+    let size = egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32);
+
+    match texture {
+        Some(texture) => {
+            ui.image(egui::ImageSource::Texture(SizedTexture {
+                id: texture.id(),
+                size,
+            }));
+        }
+        None => {
+            ui.add_sized(size, egui::Label::new(tr("empty-slot-thumbnail")));
+        }
+    }
+}
+
 impl UiTool for SaveGame {
     fn name(&self) -> &'static str {
         "Save Game"
     }
 
     fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
-        egui::Window::new(self.name())
+        tool_window(ctx, self.name())
             .default_width(50.0)
             .open(open)
             .show(ctx, |ui| self.ui(ui));
+
+        if !*open {
+            self.thumbnails = None;
+        }
     }
 
     fn ui(&mut self, ui: &mut egui::Ui) {
-        if ui.button("Save").clicked() {
-            self.save_state().unwrap_or_else(|err| {
-                // Looking at the code of `MessageDialog` it seems like `.show_alert()` can never return `Err`
-                MessageDialog::new()
-                    .set_title("Clementine")
-                    .set_text(err.to_string().as_str())
-                    .show_alert()
-                    .unwrap();
-            });
+        if ui.button(tr("save")).clicked() {
+            self.save_state()
+                .unwrap_or_else(|err| show_error(err.as_ref()));
+        }
+
+        if ui.button(tr("load")).clicked() {
+            self.load_state()
+                .unwrap_or_else(|err| show_error(err.as_ref()));
         }
 
-        if ui.button("Load").clicked() {
-            self.load_state().unwrap_or_else(|err| {
-                MessageDialog::new()
-                    .set_title("Clementine")
-                    .set_text(err.to_string().as_str())
-                    .show_alert()
-                    .unwrap();
-            });
+        ui.separator();
+
+        let thumbnails = self
+            .thumbnails
+            .get_or_insert_with(|| slot_textures(ui.ctx(), &self.gba))
+            .clone();
+        let mut saved = false;
+
+        egui::Grid::new("Save slots").show(ui, |ui| {
+            for (slot, thumbnail) in (1..=SLOTS).zip(&thumbnails) {
+                if ui
+                    .button(tr_args("save-slot", &[("slot", &slot)]))
+                    .clicked()
+                {
+                    save_slot(&self.gba, slot).unwrap_or_else(|err| show_error(err.as_ref()));
+                    saved = true;
+                }
+
+                // The preview tells what's in the slot before it's loaded over the game.
+                let load = ui
+                    .add_enabled(
+                        thumbnail.is_some(),
+                        egui::Button::new(tr_args("load-slot", &[("slot", &slot)])),
+                    )
+                    .on_hover_ui(|ui| thumbnail_ui(ui, thumbnail.as_ref()))
+                    .on_disabled_hover_ui(|ui| thumbnail_ui(ui, None));
+                if load.clicked() {
+                    load_slot(&self.gba, slot).unwrap_or_else(|err| show_error(err.as_ref()));
+                }
+                ui.end_row();
+            }
+        });
+
+        if saved {
+            self.thumbnails = None;
         }
     }
+
+    fn commands(&self) -> Vec<Command> {
+        let mut commands = vec![
+            Command::new(tr("save-state")),
+            Command::new(tr("load-state")),
+        ];
+
+        commands
+            .extend((1..=SLOTS).map(|slot| Command::new(tr_args("save-slot", &[("slot", &slot)]))));
+        commands
+            .extend((1..=SLOTS).map(|slot| Command::new(tr_args("load-slot", &[("slot", &slot)]))));
+
+        commands
+    }
+
+    fn run_command(&mut self, index: usize, _argument: &str) -> bool {
+        let result = match index {
+            0 => self.save_state(),
+            1 => self.load_state(),
+            index if index < 2 + SLOTS => {
+                self.thumbnails = None;
+                save_slot(&self.gba, index - 1)
+            }
+            index => load_slot(&self.gba, index - 1 - SLOTS),
+        };
+
+        result.unwrap_or_else(|err| show_error(err.as_ref()));
+
+        true
+    }
 }
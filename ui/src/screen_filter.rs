@@ -0,0 +1,300 @@
+//! How the frames are scaled to the size of the window by the software renderer, the
+//! upscalers run on the CPU before the texture is given to egui.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use eframe::epaint::textures::TextureOptions;
+use egui::{Color32, ColorImage, TextureHandle, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Largest integer scale of the sharp bilinear filter, past it the difference can't be seen.
+const MAX_SHARP_SCALE: usize = 8;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScreenFilter {
+    /// Square pixels, uneven in size when the window isn't a multiple of the LCD.
+    #[default]
+    Nearest,
+    /// Scaled by the largest integer factor that fits and then filtered, the pixels stay
+    /// sharp and have the same size.
+    SharpBilinear,
+    /// `Scale2x` (EPX), it rounds the diagonals of the pixel art.
+    Scale2x,
+    /// `Scale3x` (`AdvMAME3x`), like `Scale2x` with a bigger factor.
+    Scale3x,
+}
+
+impl ScreenFilter {
+    pub const ALL: [Self; 4] = [
+        Self::Nearest,
+        Self::SharpBilinear,
+        Self::Scale2x,
+        Self::Scale3x,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nearest => tr("screen-filter-nearest"),
+            Self::SharpBilinear => tr("screen-filter-sharp-bilinear"),
+            Self::Scale2x => tr("screen-filter-scale2x"),
+            Self::Scale3x => tr("screen-filter-scale3x"),
+        }
+    }
+}
+
+/// Index in [`ScreenFilter::ALL`] of the filter of every screen.
+static CURRENT_FILTER: AtomicU8 = AtomicU8::new(0);
+
+#[allow(clippy::cast_possible_truncation)]
+pub fn set_screen_filter(filter: ScreenFilter) {
+    let index = ScreenFilter::ALL
+        .iter()
+        .position(|option| *option == filter)
+        .unwrap_or_default();
+
+    CURRENT_FILTER.store(index as u8, Ordering::Relaxed);
+}
+
+fn current_filter() -> ScreenFilter {
+    ScreenFilter::ALL[usize::from(CURRENT_FILTER.load(Ordering::Relaxed))]
+}
+
+/// Loads `image` in the texture `texture_name` with the filter chosen in the settings,
+/// for a screen of `display_size` points.
+pub fn load_texture(
+    ctx: &egui::Context,
+    texture_name: &str,
+    image: ColorImage,
+    display_size: Vec2,
+) -> TextureHandle {
+    let (image, options) = match current_filter() {
+        ScreenFilter::Nearest => (image, TextureOptions::NEAREST),
+        ScreenFilter::SharpBilinear => {
+            let scale = sharp_scale(&image, display_size * ctx.pixels_per_point());
+            (scale_nearest(&image, scale), TextureOptions::LINEAR)
+        }
+        ScreenFilter::Scale2x => (scale2x(&image), TextureOptions::NEAREST),
+        ScreenFilter::Scale3x => (scale3x(&image), TextureOptions::NEAREST),
+    };
+
+    ctx.load_texture(texture_name, image, options)
+}
+
+/// The largest integer scale of `image` that fits in `display_pixels`.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn sharp_scale(image: &ColorImage, display_pixels: Vec2) -> usize {
+    let [width, height] = image.size.map(|side| side.max(1) as f32);
+    let scale = (display_pixels.x / width)
+        .min(display_pixels.y / height)
+        .floor();
+
+    (scale.max(1.0) as usize).min(MAX_SHARP_SCALE)
+}
+
+fn scale_nearest(image: &ColorImage, scale: usize) -> ColorImage {
+    let [width, height] = image.size;
+    let pixels = (0..height * scale)
+        .flat_map(|y| (0..width * scale).map(move |x| (x / scale, y / scale)))
+        .map(|(x, y)| image.pixels[y * width + x])
+        .collect();
+
+    ColorImage {
+        size: [width * scale, height * scale],
+        pixels,
+    }
+}
+
+/// The pixel at `x`, `y` moved by `dx`, `dy`, the edges are repeated past the borders.
+fn neighbour(image: &ColorImage, x: usize, y: usize, dx: isize, dy: isize) -> Color32 {
+    let [width, height] = image.size;
+    let x = x.saturating_add_signed(dx).min(width - 1);
+    let y = y.saturating_add_signed(dy).min(height - 1);
+
+    image.pixels[y * width + x]
+}
+
+/// Every pixel becomes a block of `scale * scale` pixels made by `block` from the 3x3
+/// pixels around it, row by row.
+fn scale_by_blocks<const N: usize>(
+    image: &ColorImage,
+    scale: usize,
+    block: fn(&[[Color32; 3]; 3]) -> [Color32; N],
+) -> ColorImage {
+    let [width, height] = image.size;
+    let mut pixels = vec![Color32::BLACK; width * height * scale * scale];
+
+    for y in 0..height {
+        for x in 0..width {
+            let around: [[Color32; 3]; 3] = std::array::from_fn(|row| {
+                std::array::from_fn(|column| {
+                    neighbour(image, x, y, column.cast_signed() - 1, row.cast_signed() - 1)
+                })
+            });
+
+            for (index, color) in block(&around).into_iter().enumerate() {
+                let (block_x, block_y) = (index % scale, index / scale);
+                pixels[(y * scale + block_y) * width * scale + x * scale + block_x] = color;
+            }
+        }
+    }
+
+    ColorImage {
+        size: [width * scale, height * scale],
+        pixels,
+    }
+}
+
+#[allow(clippy::many_single_char_names)]
+fn scale2x(image: &ColorImage) -> ColorImage {
+    scale_by_blocks(image, 2, |around| {
+        let [[_, b, _], [d, e, f], [_, h, _]] = *around;
+
+        if b != h && d != f {
+            [
+                if d == b { d } else { e },
+                if b == f { f } else { e },
+                if d == h { d } else { e },
+                if h == f { f } else { e },
+            ]
+        } else {
+            [e; 4]
+        }
+    })
+}
+
+#[allow(clippy::many_single_char_names)]
+fn scale3x(image: &ColorImage) -> ColorImage {
+    scale_by_blocks(image, 3, |around| {
+        let [[a, b, c], [d, e, f], [g, h, i]] = *around;
+
+        if b != h && d != f {
+            [
+                if d == b { d } else { e },
+                if (d == b && e != c) || (b == f && e != a) {
+                    b
+                } else {
+                    e
+                },
+                if b == f { f } else { e },
+                if (d == b && e != g) || (d == h && e != a) {
+                    d
+                } else {
+                    e
+                },
+                e,
+                if (b == f && e != i) || (h == f && e != c) {
+                    f
+                } else {
+                    e
+                },
+                if d == h { d } else { e },
+                if (d == h && e != i) || (h == f && e != g) {
+                    h
+                } else {
+                    e
+                },
+                if h == f { f } else { e },
+            ]
+        } else {
+            [e; 9]
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// An image drawn with `#` for white and `.` for black pixels.
+    fn image(rows: &[&str]) -> ColorImage {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|pixel| {
+                if pixel == '#' {
+                    Color32::WHITE
+                } else {
+                    Color32::BLACK
+                }
+            })
+            .collect();
+
+        ColorImage {
+            size: [rows[0].len(), rows.len()],
+            pixels,
+        }
+    }
+
+    fn rows(image: &ColorImage) -> Vec<String> {
+        image
+            .pixels
+            .chunks(image.size[0])
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| if pixel == Color32::WHITE { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sharp_scale() {
+        let image = ColorImage::new([240, 160], Color32::BLACK);
+
+        assert_eq!(super::sharp_scale(&image, Vec2::new(500.0, 400.0)), 2);
+        assert_eq!(super::sharp_scale(&image, Vec2::new(100.0, 100.0)), 1);
+        assert_eq!(
+            super::sharp_scale(&image, Vec2::new(4000.0, 4000.0)),
+            MAX_SHARP_SCALE
+        );
+    }
+
+    #[test]
+    fn scale_nearest() {
+        let scaled = super::scale_nearest(&image(&["#.", ".."]), 2);
+
+        assert_eq!(rows(&scaled), ["##..", "##..", "....", "...."]);
+    }
+
+    #[test]
+    fn scale2x_rounds_the_diagonals() {
+        // The borders are repeated, the ends of the line are corners.
+        let diagonal = image(&["#..", ".#.", "..#"]);
+
+        assert_eq!(
+            rows(&scale2x(&diagonal)),
+            ["##....", "#.#...", ".###..", "..###.", "...#.#", "....##"]
+        );
+        // Nothing changes without edges.
+        assert_eq!(rows(&scale2x(&image(&["##", "##"]))), ["####"; 4]);
+    }
+
+    #[test]
+    fn scale3x_rounds_the_diagonals() {
+        let diagonal = image(&["#..", ".#.", "..#"]);
+
+        assert_eq!(
+            rows(&scale3x(&diagonal)),
+            [
+                "###......",
+                "##.#.....",
+                "#..#.....",
+                ".#####...",
+                "...###...",
+                "...#####.",
+                ".....#..#",
+                ".....#.##",
+                "......###",
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use emu::cpu::hardware::keypad::NO_KEYS_PRESSED;
+use emu::gba::Gba;
+use emu::render::{LCD_HEIGHT, LCD_WIDTH};
+
+use crate::border::BorderScaling;
+use crate::gba_display::show_screen;
+use crate::i18n::tr;
+use crate::netplay::read_keys;
+use crate::renderer::Screen;
+use crate::ui_traits::{tool_window, UiTool};
+
+/// Makes a new core running the cartridge of the main one, with the same settings.
+pub type CoreFactory = Box<dyn Fn() -> Gba>;
+
+/// A second emulator next to the main one, eg. to compare two runs or as the other end
+/// of a link cable. It has its own inputs: the keys go to it while the pointer is on its screen.
+pub struct SecondCore {
+    main_gba: Arc<Mutex<Gba>>,
+    new_core: CoreFactory,
+    core: Option<RunningCore>,
+    error: Option<String>,
+    screen: Screen,
+}
+
+/// A core stepped frame by frame in its own thread until it's dropped.
+struct RunningCore {
+    gba: Arc<Mutex<Gba>>,
+    /// Keys pressed on this core, set before each frame.
+    keys: Arc<AtomicU16>,
+    running: Arc<AtomicBool>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+}
+
+impl RunningCore {
+    fn start(gba: Gba) -> Self {
+        let gba = Arc::new(Mutex::new(gba));
+        let keys = Arc::new(AtomicU16::new(NO_KEYS_PRESSED));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let gba_clone = Arc::clone(&gba);
+        let keys_clone = Arc::clone(&keys);
+        let running_clone = Arc::clone(&running);
+        let thread_handle = thread::spawn(move || {
+            while running_clone.load(Ordering::Relaxed) {
+                let mut gba = gba_clone.lock().unwrap();
+                gba.set_key_input(keys_clone.load(Ordering::Relaxed));
+                gba.run_frame();
+            }
+        });
+
+        Self {
+            gba,
+            keys,
+            running,
+            thread_handle: Some(thread_handle),
+        }
+    }
+}
+
+impl Drop for RunningCore {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        if let Some(handle) = self.thread_handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+impl SecondCore {
+    pub fn new(main_gba: Arc<Mutex<Gba>>, new_core: CoreFactory) -> Self {
+        Self {
+            main_gba,
+            new_core,
+            core: None,
+            error: None,
+            screen: Screen::new("second_core_display"),
+        }
+    }
+
+    /// Puts the second core in the same state of the main one, to compare what follows.
+    fn copy_main_state(&mut self) {
+        let Some(core) = &self.core else {
+            return;
+        };
+
+        let state = self.main_gba.lock().unwrap().save_state();
+        self.error = state
+            .and_then(|state| core.gba.lock().unwrap().load_state(&state))
+            .err();
+    }
+}
+
+impl UiTool for SecondCore {
+    fn name(&self) -> &'static str {
+        "Second Core"
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .open(open)
+            .default_width(LCD_WIDTH as f32)
+            .default_height(LCD_HEIGHT as f32)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+
+        // Closing the window stops the core.
+        if !*open {
+            self.core = None;
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.core.is_none() {
+                if ui.button(tr("start-second-core")).clicked() {
+                    self.core = Some(RunningCore::start((self.new_core)()));
+                    self.error = None;
+                }
+            } else if ui.button(tr("stop-second-core")).clicked() {
+                self.core = None;
+            }
+
+            if ui
+                .add_enabled(
+                    self.core.is_some(),
+                    egui::Button::new(tr("copy-main-state")),
+                )
+                .on_hover_text(tr("copy-main-state-hint"))
+                .clicked()
+            {
+                self.copy_main_state();
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        let Some(core) = &self.core else {
+            ui.small(tr("second-core-hint"));
+            return;
+        };
+
+        let response = show_screen(
+            ui,
+            &core.gba.lock().unwrap(),
+            &mut self.screen,
+            BorderScaling::Fit,
+        );
+        let keys = if response.hovered() {
+            ui.input(read_keys)
+        } else {
+            NO_KEYS_PRESSED
+        };
+        core.keys.store(keys, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use emu::debugger::line_info::Location;
+use emu::gba::Gba;
+
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Steps run for each lock of the emulator while stepping or running to a line,
+/// the other tools can lock it in between.
+const STEP_CHUNK: u64 = 100_000;
+
+/// Source listing of homebrew built with debug info, with line stepping.
+pub struct Source {
+    gba: Arc<Mutex<Gba>>,
+    /// Lines of the files read so far by path, `None` if a file can't be read.
+    files: HashMap<String, Option<Vec<String>>>,
+    /// File in the listing, it follows the current line.
+    shown_file: Option<usize>,
+    /// Line chosen for "Run to line".
+    selected: Option<Location>,
+    /// Current line on the last frame, the listing scrolls to it when it changes.
+    last_location: Option<Location>,
+    running: Arc<AtomicBool>,
+}
+
+impl Source {
+    pub fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            files: HashMap::new(),
+            shown_file: None,
+            selected: None,
+            last_location: None,
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Runs `task` in chunks in a thread until it returns `true` or it's stopped.
+    fn run_in_background(&self, mut task: impl FnMut(&mut Gba) -> bool + Send + 'static) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let gba = Arc::clone(&self.gba);
+        let running = Arc::clone(&self.running);
+
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                if task(&mut gba.lock().unwrap()) {
+                    break;
+                }
+            }
+
+            running.store(false, Ordering::Relaxed);
+        });
+    }
+
+    fn step_line(&self) {
+        let from = self.gba.lock().unwrap().source_location();
+
+        self.run_in_background(move |gba| gba.step_line(from, STEP_CHUNK));
+    }
+
+    fn run_to_line(&self, location: Location) {
+        let addresses = self.gba.lock().unwrap().cpu.line_info.addresses(location);
+
+        self.run_in_background(move |gba| gba.run_to(&addresses, STEP_CHUNK));
+    }
+
+    fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    fn controls_ui(&self, ui: &mut egui::Ui) {
+        let is_running = self.running.load(Ordering::Relaxed);
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new(tr("step-line")))
+                .clicked()
+            {
+                self.step_line();
+            }
+
+            if ui
+                .add_enabled(
+                    !is_running && self.selected.is_some(),
+                    egui::Button::new(tr("run-to-line")),
+                )
+                .on_hover_text(tr("run-to-line-hint"))
+                .clicked()
+            {
+                if let Some(location) = self.selected {
+                    self.run_to_line(location);
+                }
+            }
+
+            if ui
+                .add_enabled(is_running, egui::Button::new(tr("stop")))
+                .clicked()
+            {
+                self.stop();
+            }
+        });
+    }
+
+    fn listing_ui(&mut self, ui: &mut egui::Ui, file: usize, path: &str, scroll_to_current: bool) {
+        let current = self.last_location.filter(|location| location.file == file);
+
+        let lines = self.files.entry(path.to_string()).or_insert_with(|| {
+            std::fs::read_to_string(path)
+                .ok()
+                .map(|text| text.lines().map(String::from).collect())
+        });
+
+        let Some(lines) = lines else {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                tr_args("source-not-found", &[("file", &path)]),
+            );
+            return;
+        };
+
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (index, text) in lines.iter().enumerate() {
+                    let location = Location {
+                        file,
+                        line: u32::try_from(index + 1).unwrap_or(u32::MAX),
+                    };
+                    let is_current = current == Some(location);
+                    let marker = if is_current { "▶" } else { " " };
+
+                    let mut label =
+                        egui::RichText::new(format!("{marker}{:>5}  {text}", location.line))
+                            .monospace();
+                    if is_current {
+                        label = label.color(egui::Color32::YELLOW);
+                    }
+
+                    let response = ui.selectable_label(self.selected == Some(location), label);
+                    if response.clicked() {
+                        self.selected = Some(location);
+                    }
+                    if is_current && scroll_to_current {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                    }
+                }
+            });
+    }
+}
+
+impl UiTool for Source {
+    fn name(&self) -> &'static str {
+        "Source"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(480.0)
+            .default_height(400.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let gba = self.gba.lock().unwrap();
+        let files = gba.cpu.line_info.files().to_vec();
+        let location = gba.source_location();
+        let address = gba.cpu.next_instruction_address();
+        drop(gba);
+
+        if files.is_empty() {
+            ui.label(tr("no-debug-info"));
+            return;
+        }
+
+        self.controls_ui(ui);
+
+        let has_moved = location != self.last_location;
+        self.last_location = location;
+
+        match location {
+            Some(location) => {
+                ui.label(format!("{}:{}", files[location.file], location.line));
+                if has_moved {
+                    self.shown_file = Some(location.file);
+                }
+            }
+            None => {
+                ui.label(tr_args(
+                    "no-source-location",
+                    &[("address", &format!("0x{address:08X}"))],
+                ));
+            }
+        }
+
+        // Another ELF may have been loaded in the meantime.
+        let shown_file = self
+            .shown_file
+            .filter(|&file| file < files.len())
+            .unwrap_or_default();
+        egui::ComboBox::from_id_source("Source file")
+            .selected_text(&files[shown_file])
+            .width(ui.available_width())
+            .show_ui(ui, |ui| {
+                for (index, path) in files.iter().enumerate() {
+                    ui.selectable_value(&mut self.shown_file, Some(index), path);
+                }
+            });
+        ui.separator();
+
+        self.listing_ui(ui, shown_file, &files[shown_file], has_moved);
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::new(tr("step-line"))]
+    }
+
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        self.step_line();
+
+        true
+    }
+}
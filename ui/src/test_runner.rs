@@ -0,0 +1,497 @@
+//! Runs every ROM of a directory without a window, eg. test suites in CI, and reports the
+//! results as `JUnit` XML. What makes a ROM pass is in `tests.json` of the directory:
+//!
+//! ```json
+//! {
+//!   "frames": 600,
+//!   "roms": {
+//!     "arm.gba": { "video": "1a2b3c4d" },
+//!     "timer.gba": { "frames": 120, "memory": [{ "address": 50331648, "value": 0 }] },
+//!     "suite.gba": { "output": "All tests passed" }
+//!   }
+//! }
+//! ```
+//!
+//! Without criteria a ROM passes if it runs all the frames without crashing and without
+//! a fatal message on the debug output.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use emu::{
+    boot_patch::PatchWidth,
+    cpu::hardware::{debug::DebugLevel, keypad::NO_KEYS_PRESSED},
+    gba::Gba,
+    run_hash::RunHasher,
+};
+
+use crate::app::try_load_gba;
+use crate::library::ROM_EXTENSIONS;
+
+const MANIFEST_FILE_NAME: &str = "tests.json";
+
+const DEFAULT_FRAMES: u64 = 600;
+
+/// A worker holds a whole machine, it doesn't fit in the default thread stack.
+const WORKER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    /// Frames run by the ROMs that don't say.
+    frames: u64,
+    roms: BTreeMap<String, Criteria>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            frames: DEFAULT_FRAMES,
+            roms: BTreeMap::new(),
+        }
+    }
+}
+
+/// What a ROM must do to pass, all of them are checked after the last frame.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+struct Criteria {
+    frames: Option<u64>,
+    /// CRC32 of the frames, as printed by `--hash-frames`.
+    video: Option<String>,
+    /// CRC32 of the audio samples, as printed by `--hash-frames`.
+    audio: Option<String>,
+    memory: Vec<MemoryCheck>,
+    /// Text printed on the debug output (mGBA, `AGBPrint` or no$gba).
+    output: Option<String>,
+}
+
+/// A value expected in memory, eg. where a test suite writes the number of failures.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct MemoryCheck {
+    address: u32,
+    value: u32,
+    #[serde(default)]
+    width: PatchWidth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The ROM ran but didn't meet its criteria.
+    Failed(String),
+    /// The ROM couldn't run, eg. it's missing or the emulator crashed.
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// File name of the ROM.
+    pub name: String,
+    pub outcome: Outcome,
+    pub duration: Duration,
+    /// Everything printed on the debug output.
+    pub output: String,
+}
+
+/// Runs the ROMs of `dir` on `threads` threads, the results are sorted by name. Each
+/// result is printed on stderr as soon as it's known.
+///
+/// # Errors
+/// It returns an error if the directory or the manifest can't be read.
+///
+/// # Panics
+/// It panics if a worker panics outside of the emulator.
+pub fn run(dir: &Path, threads: usize) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let manifest: Manifest = if manifest_path.is_file() {
+        serde_json::from_str(&fs::read_to_string(&manifest_path)?)
+            .map_err(|e| format!("{}: {e}", manifest_path.display()))?
+    } else {
+        Manifest::default()
+    };
+
+    let mut roms = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    ROM_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+                })
+        })
+        .collect::<Vec<PathBuf>>();
+    roms.sort();
+
+    // A ROM in the manifest that is not there is an error, not a test less.
+    let mut results = manifest
+        .roms
+        .keys()
+        .filter(|name| !dir.join(name).is_file())
+        .map(|name| TestResult {
+            name: name.clone(),
+            outcome: Outcome::Error("The ROM is missing".to_string()),
+            duration: Duration::ZERO,
+            output: String::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let next = AtomicUsize::new(0);
+    let finished = Mutex::new(Vec::with_capacity(roms.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let worker = thread::Builder::new()
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    while let Some(rom) = roms.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let result = run_rom(rom, &manifest);
+                        eprintln!("{}", summary(&result));
+                        finished.lock().unwrap().push(result);
+                    }
+                });
+            if let Err(e) = worker {
+                eprintln!("can't start a test worker: {e}");
+            }
+        }
+    });
+
+    results.extend(finished.into_inner().unwrap());
+    results.sort_by(|first, second| first.name.cmp(&second.name));
+
+    Ok(results)
+}
+
+fn run_rom(rom: &Path, manifest: &Manifest) -> TestResult {
+    let name = rom
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let criteria = manifest.roms.get(&name).cloned().unwrap_or_default();
+    let frames = criteria.frames.unwrap_or(manifest.frames);
+    let start = Instant::now();
+    let mut output = String::new();
+
+    let outcome = match try_load_gba(&rom.to_string_lossy()) {
+        Ok(mut gba) => {
+            let run = panic::catch_unwind(AssertUnwindSafe(|| {
+                check(&mut gba, frames, &criteria, &mut output)
+            }));
+            match run {
+                Ok(Ok(())) => Outcome::Passed,
+                Ok(Err(failure)) => Outcome::Failed(failure),
+                Err(payload) => Outcome::Error(panic_message(payload.as_ref())),
+            }
+        }
+        Err(e) => Outcome::Error(format!("Can't load the ROM: {e}")),
+    };
+
+    TestResult {
+        name,
+        outcome,
+        duration: start.elapsed(),
+        output,
+    }
+}
+
+/// Runs `frames` frames and checks the criteria, the debug output is added to `output`.
+fn check(
+    gba: &mut Gba,
+    frames: u64,
+    criteria: &Criteria,
+    output: &mut String,
+) -> Result<(), String> {
+    gba.set_key_input(NO_KEYS_PRESSED);
+    let mut hasher = RunHasher::default();
+
+    for frame in 0..frames {
+        gba.run_frame();
+        hasher.add_frame(&gba.cpu.bus.lcd.buffer);
+        hasher.add_samples(&gba.take_audio_samples());
+
+        let messages = &mut gba.cpu.bus.debug_output.messages;
+        let fatal = messages
+            .iter()
+            .find(|message| message.level == DebugLevel::Fatal)
+            .map(|message| message.text.clone());
+        for message in messages.iter() {
+            let _ = writeln!(output, "{message}");
+        }
+        messages.clear();
+
+        if let Some(fatal) = fatal {
+            return Err(format!("Fatal message at frame {frame}: {fatal}"));
+        }
+    }
+
+    let hash = hasher.hash();
+    let mut failures = Vec::new();
+
+    for (kind, expected, actual) in [
+        ("video", &criteria.video, hash.video),
+        ("audio", &criteria.audio, hash.audio),
+    ] {
+        let actual = format!("{actual:08x}");
+        if let Some(expected) = expected
+            .as_ref()
+            .filter(|e| !e.eq_ignore_ascii_case(&actual))
+        {
+            failures.push(format!("The {kind} hash is {actual}, expected {expected}"));
+        }
+    }
+
+    for memory in &criteria.memory {
+        let expected = memory.width.le_bytes(memory.value);
+        let length = u32::try_from(expected.len()).unwrap_or_default();
+        match gba.dump_memory(memory.address, length) {
+            Ok(actual) if actual == expected => {}
+            Ok(actual) => failures.push(format!(
+                "{:08X} is {actual:02X?}, expected {expected:02X?}",
+                memory.address
+            )),
+            Err(e) => failures.push(e),
+        }
+    }
+
+    if let Some(text) = criteria
+        .output
+        .as_ref()
+        .filter(|text| !output.contains(*text))
+    {
+        failures.push(format!("`{text}` was not printed"));
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+
+    format!("The emulator crashed: {message}")
+}
+
+/// A line for the console, eg. `FAIL arm.gba (1.20 s): The video hash is ...`.
+#[must_use]
+pub fn summary(result: &TestResult) -> String {
+    let seconds = result.duration.as_secs_f64();
+    match &result.outcome {
+        Outcome::Passed => format!("PASS  {} ({seconds:.2} s)", result.name),
+        Outcome::Failed(failure) => format!("FAIL  {} ({seconds:.2} s): {failure}", result.name),
+        Outcome::Error(error) => format!("ERROR {} ({seconds:.2} s): {error}", result.name),
+    }
+}
+
+/// The results as a `JUnit` XML report, a test suite named `suite` with a test case for
+/// each ROM.
+#[must_use]
+pub fn junit_xml(suite: &str, results: &[TestResult]) -> String {
+    let count = |is_counted: fn(&Outcome) -> bool| {
+        results
+            .iter()
+            .filter(|result| is_counted(&result.outcome))
+            .count()
+    };
+    let failures = count(|outcome| matches!(outcome, Outcome::Failed(_)));
+    let errors = count(|outcome| matches!(outcome, Outcome::Error(_)));
+    let time = results
+        .iter()
+        .map(|result| result.duration.as_secs_f64())
+        .sum::<f64>();
+    let suite = escape_xml(suite);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+        results.len()
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{suite}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+        results.len()
+    );
+
+    for result in results {
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{suite}\" time=\"{:.3}\">",
+            escape_xml(&result.name),
+            result.duration.as_secs_f64()
+        );
+        match &result.outcome {
+            Outcome::Passed => {}
+            Outcome::Failed(failure) => {
+                let _ = write!(
+                    xml,
+                    "\n      <failure message=\"{}\"/>",
+                    escape_xml(failure)
+                );
+            }
+            Outcome::Error(error) => {
+                let _ = write!(xml, "\n      <error message=\"{}\"/>", escape_xml(error));
+            }
+        }
+        if !result.output.is_empty() {
+            let _ = write!(
+                xml,
+                "\n      <system-out>{}</system-out>",
+                escape_xml(&result.output)
+            );
+        }
+        if result.outcome != Outcome::Passed || !result.output.is_empty() {
+            xml.push_str("\n    ");
+        }
+        xml.push_str("</testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML 1.0, eg. garbage printed by a broken ROM.
+            '\t' | '\n' | '\r' => escaped.push(character),
+            character if character.is_control() => {}
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use emu::cartridge::header::Header;
+    use emu::cpu::hardware::debug::DebugSource;
+
+    /// A game looping on its first instruction (`B .`), booted without a BIOS.
+    fn gba() -> Gba {
+        let mut rom = vec![0; 0x200];
+        rom[..4].copy_from_slice(&0xEAFF_FFFE_u32.to_le_bytes());
+        let header = Header::new(&rom).unwrap();
+        Gba::new(header, [0; 0x0000_4000], rom)
+    }
+
+    #[test]
+    fn check_failures() {
+        let criteria = Criteria {
+            video: Some("FFFFFFFF".to_string()),
+            memory: vec![MemoryCheck {
+                address: 0x0300_0000,
+                value: 1,
+                width: PatchWidth::Byte,
+            }],
+            output: Some("All tests passed".to_string()),
+            ..Criteria::default()
+        };
+        let video = format!("{:08x}", RunHasher::default().hash().video);
+
+        assert_eq!(
+            check(&mut gba(), 0, &criteria, &mut String::new()),
+            Err(format!(
+                "The video hash is {video}, expected FFFFFFFF; 03000000 is [00], expected [01]; \
+                 `All tests passed` was not printed"
+            ))
+        );
+
+        // The hashes are compared without the case.
+        let criteria = Criteria {
+            video: Some(video.to_uppercase()),
+            ..Criteria::default()
+        };
+        assert_eq!(check(&mut gba(), 0, &criteria, &mut String::new()), Ok(()));
+    }
+
+    #[test]
+    fn check_stops_at_a_fatal_message() {
+        let mut gba = gba();
+        let debug_output = &mut gba.cpu.bus.debug_output;
+        debug_output.push(DebugLevel::Info, DebugSource::Mgba, "start".to_string());
+        debug_output.push(DebugLevel::Fatal, DebugSource::Mgba, "abort".to_string());
+        let mut output = String::new();
+
+        assert_eq!(
+            check(&mut gba, 10, &Criteria::default(), &mut output),
+            Err("Fatal message at frame 0: abort".to_string())
+        );
+        assert_eq!(output, "[mGBA] [INFO] start\n[mGBA] [FATAL] abort\n");
+    }
+
+    #[test]
+    fn escape_xml() {
+        assert_eq!(
+            super::escape_xml("<a href=\"x\">&'\u{1}\tb</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;\tb&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn junit_xml() {
+        let result = |name: &str, outcome, milliseconds, output: &str| TestResult {
+            name: name.to_string(),
+            outcome,
+            duration: Duration::from_millis(milliseconds),
+            output: output.to_string(),
+        };
+        let results = [
+            result("arm.gba", Outcome::Passed, 1500, ""),
+            result(
+                "a&b.gba",
+                Outcome::Failed("R0 <> 1".to_string()),
+                250,
+                "done",
+            ),
+            result(
+                "missing.gba",
+                Outcome::Error("The ROM is missing".to_string()),
+                0,
+                "",
+            ),
+        ];
+
+        assert_eq!(
+            super::junit_xml("roms", &results),
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites tests=\"3\" failures=\"1\" errors=\"1\" time=\"1.750\">\n",
+                "  <testsuite name=\"roms\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"1.750\">\n",
+                "    <testcase name=\"arm.gba\" classname=\"roms\" time=\"1.500\"></testcase>\n",
+                "    <testcase name=\"a&amp;b.gba\" classname=\"roms\" time=\"0.250\">\n",
+                "      <failure message=\"R0 &lt;&gt; 1\"/>\n",
+                "      <system-out>done</system-out>\n",
+                "    </testcase>\n",
+                "    <testcase name=\"missing.gba\" classname=\"roms\" time=\"0.000\">\n",
+                "      <error message=\"The ROM is missing\"/>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n",
+                "</testsuites>\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Zoom factors offered in the settings, 1 is the size chosen by the system (DPI).
+pub const UI_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
+/// Outline of the widget with the keyboard focus (or pressed), egui draws it as pressed.
+const FOCUS_STROKE_WIDTH: f32 = 2.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Self; 2] = [Self::Dark, Self::Light];
+
+    /// The focused widgets get the outline of the selection, to be found without a mouse.
+    #[must_use]
+    pub fn visuals(self) -> egui::Visuals {
+        let mut visuals = match self {
+            Self::Dark => egui::Visuals::dark(),
+            Self::Light => egui::Visuals::light(),
+        };
+        visuals.widgets.active.bg_stroke =
+            egui::Stroke::new(FOCUS_STROKE_WIDTH, visuals.selection.stroke.color);
+
+        visuals
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dark => tr("theme-dark"),
+            Self::Light => tr("theme-light"),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::{Arc, Mutex};
+
+use emu::{
+    debugger::timeline::{Timeline as TimelineLog, TimelineEntry, TimelineEvent},
+    gba::Gba,
+};
+
+use crate::i18n::{tr, tr_args};
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Entries kept by the core, timers clocking the sound fill it quickly.
+const CAPACITY: usize = 50_000;
+
+/// A frame is 228 lines of 308 dots, 4 cycles each.
+const LINES: u16 = 228;
+const DOTS: u32 = 308;
+const CYCLES_PER_FRAME: u128 = 280_896;
+
+/// Visible area, the rest of the frame is `HBlank` and `VBlank`.
+const VISIBLE_LINES: u16 = 160;
+const VISIBLE_DOTS: u32 = 240;
+
+/// Entries listed below the plot, from the most recent.
+const LISTED_ENTRIES: usize = 200;
+
+const DMA_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 160, 255);
+const TIMER_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 220, 80);
+const IRQ_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 90, 90);
+
+/// DMA transfers, timer overflows and interrupt requests of the last frame drawn
+/// where they happened on the screen, to debug raster effects and sound timing.
+pub struct Timeline {
+    gba: Arc<Mutex<Gba>>,
+    show_dma: bool,
+    show_timers: bool,
+    show_irqs: bool,
+}
+
+impl Timeline {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            show_dma: true,
+            show_timers: true,
+            show_irqs: true,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.gba.lock().unwrap().cpu.bus.timeline.is_some()
+    }
+
+    fn set_recording(&self, record: bool) {
+        self.gba.lock().unwrap().cpu.bus.timeline = record.then(|| TimelineLog::new(CAPACITY));
+    }
+
+    const fn is_shown(&self, event: TimelineEvent) -> bool {
+        match event {
+            TimelineEvent::Dma { .. } => self.show_dma,
+            TimelineEvent::TimerOverflow(_) => self.show_timers,
+            TimelineEvent::Irq { .. } => self.show_irqs,
+        }
+    }
+
+    /// The frame as a 308x228 grid, each entry a dot where the LCD was when it happened.
+    #[allow(clippy::cast_precision_loss)]
+    fn plot_ui(ui: &mut egui::Ui, entries: &[TimelineEntry]) {
+        let scale = (ui.available_width() / DOTS as f32).clamp(1.0, 3.0);
+        let size = egui::vec2(DOTS as f32, f32::from(LINES)) * scale;
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        let rect = response.rect;
+
+        let point =
+            |dot: u32, line: u16| rect.min + egui::vec2(dot as f32, f32::from(line)) * scale;
+
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+        painter.rect_filled(
+            egui::Rect::from_min_max(rect.min, point(VISIBLE_DOTS, VISIBLE_LINES)),
+            0.0,
+            egui::Color32::from_gray(50),
+        );
+
+        for entry in entries {
+            let color = match entry.event {
+                TimelineEvent::Dma { .. } => DMA_COLOR,
+                TimelineEvent::TimerOverflow(_) => TIMER_COLOR,
+                TimelineEvent::Irq { .. } => IRQ_COLOR,
+            };
+            let min = point(entry.dot, entry.line);
+
+            painter.rect_filled(
+                egui::Rect::from_min_size(min, egui::vec2(scale, scale)),
+                0.0,
+                color,
+            );
+        }
+
+        if let Some(position) = response.hover_pos() {
+            let offset = (position - rect.min) / scale;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let (dot, line) = (offset.x as u32, offset.y as u16);
+
+            response.on_hover_text(tr_args(
+                "timeline-position",
+                &[("line", &line), ("dot", &dot)],
+            ));
+        }
+    }
+}
+
+impl UiTool for Timeline {
+    fn name(&self) -> &'static str {
+        "Timeline"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(330.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut is_recording = self.is_recording();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut is_recording, tr("record-timeline"))
+                .changed()
+            {
+                self.set_recording(is_recording);
+            }
+
+            if ui
+                .add_enabled(is_recording, egui::Button::new(tr("clear")))
+                .clicked()
+            {
+                if let Some(timeline) = &mut self.gba.lock().unwrap().cpu.bus.timeline {
+                    timeline.clear();
+                }
+            }
+        });
+
+        if !is_recording {
+            ui.label(tr("timeline-not-recording"));
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_dma, "DMA")
+                .on_hover_text(tr("timeline-dma"));
+            ui.checkbox(&mut self.show_timers, tr("timers"))
+                .on_hover_text(tr("timeline-timers"));
+            ui.checkbox(&mut self.show_irqs, "IRQ")
+                .on_hover_text(tr("timeline-irqs"));
+        });
+
+        // Only the last frame is plotted, counting back from the latest entry.
+        let entries = {
+            let gba = self.gba.lock().unwrap();
+            let Some(timeline) = &gba.cpu.bus.timeline else {
+                return;
+            };
+            let last_cycle = timeline.entries().back().map_or(0, |entry| entry.cycle);
+
+            let entries = timeline
+                .entries()
+                .iter()
+                .filter(|entry| entry.cycle + CYCLES_PER_FRAME > last_cycle)
+                .filter(|entry| self.is_shown(entry.event))
+                .copied()
+                .collect::<Vec<_>>();
+            drop(gba);
+
+            entries
+        };
+
+        ui.separator();
+        Self::plot_ui(ui, &entries);
+        ui.colored_label(egui::Color32::GRAY, tr("timeline-legend"));
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_source("timeline entries")
+            .show(ui, |ui| {
+                for entry in entries.iter().rev().take(LISTED_ENTRIES) {
+                    ui.monospace(format!(
+                        "{:>3}:{:<3} {}",
+                        entry.line, entry.dot, entry.event
+                    ));
+                }
+            });
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![Command::new(tr("record-timeline"))]
+    }
+
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        self.set_recording(!self.is_recording());
+
+        true
+    }
+}
@@ -1,3 +1,5 @@
+use crate::i18n::tool_title;
+
 pub trait UiTool {
     /// `&'static` so we can also use it as a key to store open/close state.
     fn name(&self) -> &'static str;
@@ -6,4 +8,55 @@ pub trait UiTool {
     fn show(&mut self, ctx: &egui::Context, open: &mut bool);
 
     fn ui(&mut self, ui: &mut egui::Ui);
+
+    /// Actions of the tool listed in the command palette.
+    fn commands(&self) -> Vec<Command> {
+        Vec::new()
+    }
+
+    /// Runs the command at `index` of [`Self::commands`].
+    /// `argument` is the text typed by the user, empty for commands without one.
+    /// It returns `false` if the argument is not valid, the palette asks it again.
+    fn run_command(&mut self, _index: usize, _argument: &str) -> bool {
+        true
+    }
+}
+
+/// An action listed in the command palette.
+pub struct Command {
+    pub title: String,
+    /// Hint of the text asked before running the command (eg. an address), `None` if it takes none.
+    pub argument: Option<&'static str>,
+}
+
+impl Command {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            argument: None,
+        }
+    }
+
+    pub fn with_argument(title: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            title: title.into(),
+            argument: Some(hint),
+        }
+    }
+}
+
+/// Window of a tool, placed where it was left in the last session.
+/// The title is translated, the id stays the name so the state survives a change of language.
+pub fn tool_window(ctx: &egui::Context, name: &'static str) -> egui::Window<'static> {
+    let window = egui::Window::new(tool_title(name)).id(egui::Id::new(name));
+
+    match ctx.data(|data| data.get_temp::<egui::Pos2>(saved_position_id(name))) {
+        Some(position) => window.default_pos(position),
+        None => window,
+    }
+}
+
+/// Where the position of a tool window from the last session is kept in the egui data.
+pub fn saved_position_id(name: &str) -> egui::Id {
+    egui::Id::new(name).with("saved position")
 }
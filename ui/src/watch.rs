@@ -0,0 +1,155 @@
+use std::sync::{Arc, Mutex};
+
+use emu::{
+    debugger::watch::{Watch, WatchFormat},
+    gba::Gba,
+};
+
+use crate::i18n::tr;
+use crate::ui_traits::{tool_window, Command, UiTool};
+
+/// Registers and memory values evaluated on every frame of the interface,
+/// so they are live while the game runs and up to date when it's paused.
+pub struct Watches {
+    gba: Arc<Mutex<Gba>>,
+    expression: String,
+    error: Option<String>,
+    entries: Vec<(Watch, WatchFormat)>,
+}
+
+impl Watches {
+    pub const fn new(gba: Arc<Mutex<Gba>>) -> Self {
+        Self {
+            gba,
+            expression: String::new(),
+            error: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// It returns `false` if the expression is not valid, the error is shown.
+    fn add(&mut self, expression: &str) -> bool {
+        let parsed = Watch::parse(expression, &self.gba.lock().unwrap().cpu.symbols);
+
+        match parsed {
+            Ok(watch) => {
+                self.entries.push((watch, WatchFormat::default()));
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(err);
+                false
+            }
+        }
+    }
+}
+
+fn format_name(format: WatchFormat) -> &'static str {
+    match format {
+        WatchFormat::Hex => tr("watch-hex"),
+        WatchFormat::Unsigned => tr("watch-unsigned"),
+        WatchFormat::Signed => tr("watch-signed"),
+    }
+}
+
+impl UiTool for Watches {
+    fn name(&self) -> &'static str {
+        "Watch"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        tool_window(ctx, self.name())
+            .default_width(300.0)
+            .open(open)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.expression)
+                    .hint_text(tr("watch-expression-hint"))
+                    .desired_width(160.0),
+            );
+            let is_submitted =
+                response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+            if (ui.button(tr("add-watch")).clicked() || is_submitted)
+                && self.add(&self.expression.clone())
+            {
+                self.expression.clear();
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        if self.entries.is_empty() {
+            ui.small(tr("watch-syntax"));
+            return;
+        }
+
+        let values = {
+            let gba = self.gba.lock().unwrap();
+            let values = self
+                .entries
+                .iter()
+                .map(|(watch, _)| watch.evaluate(&gba))
+                .collect::<Vec<_>>();
+            drop(gba);
+
+            values
+        };
+
+        let mut removed = None;
+        egui::Grid::new("Watches")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, ((watch, format), value)) in
+                    self.entries.iter_mut().zip(values).enumerate()
+                {
+                    ui.monospace(&watch.text);
+                    ui.monospace(value.format(*format));
+
+                    egui::ComboBox::from_id_source(("watch format", index))
+                        .selected_text(format_name(*format))
+                        .show_ui(ui, |ui| {
+                            for option in WatchFormat::ALL {
+                                ui.selectable_value(format, option, format_name(option));
+                            }
+                        });
+
+                    if ui.button("X").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed {
+            self.entries.remove(index);
+        }
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        vec![
+            Command::with_argument(tr("add-watch"), tr("watch-expression-hint")),
+            Command::new(tr("clear-watches")),
+        ]
+    }
+
+    fn run_command(&mut self, index: usize, argument: &str) -> bool {
+        match index {
+            0 => return self.add(argument),
+            _ => self.entries.clear(),
+        }
+
+        true
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use emu::{events::CoreEvent, gba::Gba};
+
+use crate::i18n::{tr, tr_args};
+
+/// Time without a frame, while the emulation runs, after which it's considered stuck.
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogStatus {
+    Running,
+    /// The dialog is shown, the tools must not lock the emulator or the window freezes too.
+    Stuck,
+    /// The emulation stopped after the user asked to pause it, the debugger can look at it.
+    Paused,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Watching,
+    Stalled,
+    /// Waiting for the thread of the emulation to stop and release the emulator.
+    Pausing,
+}
+
+/// Notices when the core stops producing frames while it runs (eg. a decoder bug
+/// looping forever or threads waiting for each other) and asks the user whether to wait
+/// or to pause it and look at it with the debugger, instead of a frozen window.
+pub struct Watchdog {
+    gba: Arc<Mutex<Gba>>,
+    /// Counted by a callback of the core, it's read without locking the emulator.
+    frames: Arc<AtomicU64>,
+    /// Set while the emulation runs, cleared to pause it.
+    is_running: Arc<AtomicBool>,
+    last_frames: u64,
+    last_progress: Instant,
+    state: State,
+}
+
+impl Watchdog {
+    pub fn new(gba: Arc<Mutex<Gba>>, is_running: Arc<AtomicBool>) -> Self {
+        let frames = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&frames);
+        gba.lock()
+            .unwrap()
+            .on_event(CoreEvent::FrameComplete, move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+
+        Self {
+            gba,
+            frames,
+            is_running,
+            last_frames: 0,
+            last_progress: Instant::now(),
+            state: State::Watching,
+        }
+    }
+
+    /// Checks the progress of the emulation, with the dialog if it's stuck.
+    pub fn show(&mut self, ctx: &egui::Context) -> WatchdogStatus {
+        let frames = self.frames.load(Ordering::Relaxed);
+
+        match self.state {
+            State::Watching | State::Stalled => {
+                if frames != self.last_frames || !self.is_running.load(Ordering::Relaxed) {
+                    self.last_frames = frames;
+                    self.last_progress = Instant::now();
+                    self.state = State::Watching;
+                }
+                if self.last_progress.elapsed() < STALL_TIMEOUT {
+                    return WatchdogStatus::Running;
+                }
+                self.state = State::Stalled;
+            }
+            State::Pausing => {
+                if self.gba.try_lock().is_ok() {
+                    self.state = State::Watching;
+                    self.last_progress = Instant::now();
+                    return WatchdogStatus::Paused;
+                }
+            }
+        }
+
+        self.dialog(ctx);
+        WatchdogStatus::Stuck
+    }
+
+    fn dialog(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr("watchdog-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if self.state == State::Pausing {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr("watchdog-pausing"));
+                    });
+                    return;
+                }
+
+                ui.label(tr_args(
+                    "watchdog-stalled",
+                    &[("seconds", &self.last_progress.elapsed().as_secs())],
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("watchdog-wait")).clicked() {
+                        self.last_progress = Instant::now();
+                        self.state = State::Watching;
+                    }
+                    if ui.button(tr("watchdog-debug")).clicked() {
+                        self.is_running.store(false, Ordering::Relaxed);
+                        self.state = State::Pausing;
+                    }
+                });
+            });
+    }
+}
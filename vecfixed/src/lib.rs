@@ -69,6 +69,24 @@ impl<const N: usize, T: Default + ToString> VecFixed<N, T> {
     pub fn front(&self) -> Option<&T> {
         self.buffer.front()
     }
+
+    /// Iterate the elements from the oldest to the latest pushed.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.buffer.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.next_index = 0;
+        self.buffer.clear();
+    }
 }
 
 #[cfg(test)]
@@ -131,4 +149,24 @@ mod tests {
 
         assert_eq!(ring.join(" "), "hello world !!!");
     }
+
+    #[test]
+    fn iter_and_clear() {
+        let mut ring: VecFixed<3, u8> = VecFixed::new();
+        assert!(ring.is_empty());
+
+        ring.push(1);
+        ring.push(2);
+        ring.push(3);
+        ring.push(4);
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(ring.len(), 3);
+
+        ring.clear();
+        assert!(ring.is_empty());
+
+        ring.push(5);
+        assert_eq!(ring.next_index, 1);
+        assert_eq!(ring.buffer, [5]);
+    }
 }